@@ -0,0 +1,85 @@
+//! Compara politicas de rebalanceo sobre la misma serie de precios.
+//!
+//! ```text
+//! cargo run --example backtest_comparison
+//! ```
+//!
+//! Los precios son sinteticos (una accion que sube de a poco y paga dividendos trimestrales, y
+//! un bono que casi no se mueve), para que el ejemplo corra sin datos externos.
+
+use fintual_coding_challenge::backtest::{self, BacktestConfig, MarketData, RebalanceSchedule};
+use fintual_coding_challenge::costs::CostModel;
+use fintual_coding_challenge::{Date, Portfolio, PortfolioTarget, Stock};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+fn market() -> MarketData {
+    let mut market = MarketData::new();
+    for month in 0..24 {
+        let date = Date::new(2022 + month / 12, month as u32 % 12 + 1, 1).unwrap();
+        let months = Decimal::from(month);
+
+        // la accion sube 2% al mes con un bajon a mitad de la serie
+        let shock = if (10..14).contains(&month) {
+            dec!(0.8)
+        } else {
+            Decimal::ONE
+        };
+        let stock = dec!(100) * (Decimal::ONE + dec!(0.02) * months) * shock;
+        let bond = dec!(100) + dec!(0.3) * months;
+        market = market.with_prices(date, &[("ACME", stock.round_dp(2)), ("BOND", bond)]);
+
+        if month % 3 == 2 {
+            market = market.with_dividend(date, "ACME", dec!(4));
+        }
+    }
+    market
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let target = PortfolioTarget::try_from(vec![
+        (dec!(60), Stock::new("ACME", dec!(100))),
+        (dec!(40), Stock::new("BOND", dec!(100))),
+    ])?;
+    let portfolio = Portfolio::builder()
+        .with_cash(dec!(10000))
+        .with_target(target)
+        .build()?;
+
+    let fees = CostModel::free().with_per_trade(dec!(1));
+    let policies = [
+        (
+            "Comprar y mantener",
+            BacktestConfig::default().with_schedule(RebalanceSchedule::Never),
+        ),
+        ("Mensual", BacktestConfig::default()),
+        (
+            "Trimestral",
+            BacktestConfig::default().with_schedule(RebalanceSchedule::EveryMonths(3)),
+        ),
+        (
+            "Trimestral + DRIP",
+            BacktestConfig::default()
+                .with_schedule(RebalanceSchedule::EveryMonths(3))
+                .with_drip(true),
+        ),
+        (
+            "Mensual con comision",
+            BacktestConfig::default().with_costs(fees),
+        ),
+    ];
+    let configs: Vec<BacktestConfig> = policies.iter().map(|(_, c)| c.clone()).collect();
+
+    let results = backtest::compare(&portfolio, &market(), &configs);
+    for ((name, _), result) in policies.iter().zip(&results) {
+        let total = result.total_return().unwrap_or_default() * dec!(100);
+        println!(
+            "{name:<22} retorno {:>6}%  rebalanceos {:>2}  dividendos {}",
+            total.round_dp(2),
+            result.rebalances,
+            result.dividends.round_dp(2)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Rebalancea una cartera cripto con precios en vivo de CoinGecko.
+//!
+//! ```text
+//! cargo run --example live_prices --features crypto
+//! ```
+//!
+//! Si no hay red, la `SourceChain` cae a precios fijos para que el ejemplo siga funcionando.
+
+use fintual_coding_challenge::crypto::CryptoPortfolio;
+use fintual_coding_challenge::prices::coingecko::CoinGeckoSource;
+use fintual_coding_challenge::prices::{PriceSource, SourceChain, StaticPrices};
+use fintual_coding_challenge::{PortfolioTarget, Stock};
+use rust_decimal_macros::dec;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let target = PortfolioTarget::try_from(vec![
+        (dec!(60), Stock::new("BTC", dec!(60000))),
+        (dec!(40), Stock::new("ETH", dec!(3000))),
+    ])?;
+
+    let mut portfolio = CryptoPortfolio::new(target)
+        .with_holding(Stock::new("BTC", dec!(60000)), dec!(0.05))
+        .with_cash(dec!(2000));
+
+    let fallback = StaticPrices::new()
+        .with("BTC", dec!(60000))
+        .with("ETH", dec!(3000));
+    let live = CoinGeckoSource::new("usd");
+    let source: Box<dyn PriceSource> = match live.latest_prices(&["BTC"]) {
+        Ok(_) => Box::new(SourceChain::new().with(live).with(fallback)),
+        Err(error) => {
+            eprintln!("Sin precios en vivo ({error}); usando precios fijos");
+            Box::new(fallback)
+        }
+    };
+
+    let missing = portfolio.refresh_prices(source.as_ref())?;
+    if !missing.is_empty() {
+        eprintln!("Sin precio para: {}", missing.join(", "));
+    }
+
+    println!("Valor total: {}", portfolio.total_value().round_dp(2));
+    println!("{}", portfolio.rebalance());
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! Rebalancea una cartera cargada desde planillas CSV.
+//!
+//! ```text
+//! cargo run --example rebalance_csv -- [holdings.csv] [target.csv]
+//! ```
+//!
+//! Sin argumentos usa las planillas de `examples/data`. Los tickers del objetivo que no estan en
+//! la cartera (VOO en el ejemplo) se cotizan con una fuente estatica.
+
+use fintual_coding_challenge::import::{parse_csv, parse_weights_csv};
+use fintual_coding_challenge::prices::{PriceSource, SourceChain, StaticPrices};
+use fintual_coding_challenge::{Map, PortfolioTarget};
+use rust_decimal_macros::dec;
+use std::error::Error;
+
+fn read(path: Option<String>, default: &str) -> Result<String, Box<dyn Error>> {
+    match path {
+        Some(path) => Ok(std::fs::read_to_string(path)?),
+        None => Ok(default.to_string()),
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let holdings = read(args.next(), include_str!("data/holdings.csv"))?;
+    let weights = read(args.next(), include_str!("data/target.csv"))?;
+
+    let statement = parse_csv(&holdings)?;
+    let weights = parse_weights_csv(&weights)?;
+
+    // precios: los de la cartola primero, y una fuente estatica para lo que falta
+    let held = statement
+        .holdings
+        .iter()
+        .fold(StaticPrices::new(), |prices, h| {
+            prices.with(&h.ticker, h.price)
+        });
+    let prices = SourceChain::new()
+        .with(held)
+        .with(StaticPrices::new().with("VOO", dec!(470)));
+    let tickers: Vec<&str> = weights.keys().map(String::as_str).collect();
+    let quotes: Map<String, _> = prices.latest_prices(&tickers)?;
+
+    let target = PortfolioTarget::try_from((weights, &quotes))?;
+    let portfolio = statement.into_portfolio(target)?;
+
+    println!("Valor total: {}", portfolio.total_value());
+    for (ticker, units) in portfolio.iter_holdings() {
+        println!(
+            "  {ticker} x{units} ({}%)",
+            portfolio.weight_of(ticker).round_dp(2)
+        );
+    }
+
+    let suggestion = portfolio.rebalance_portfolio();
+    println!("\n{suggestion}");
+
+    let mut trades = portfolio.trades(&suggestion);
+    trades.sort_by(|a, b| b.cmp(a));
+    if let Some(largest) = trades.first() {
+        println!(
+            "\nOperacion mas grande: {} por {}",
+            largest.ticker,
+            largest.notional()
+        );
+    }
+
+    // el objetivo se puede ajustar sin armarlo de nuevo
+    let mut edited = portfolio.allocation().clone();
+    edited.set_weight("VOO", dec!(40))?;
+    println!("\nCon 40% en VOO:");
+    for (weight, stock) in edited.targets() {
+        println!("  {}: {}%", stock.name(), weight.round_dp(2));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! Rebalanceo consciente de impuestos.
+//!
+//! ```text
+//! cargo run --example tax_aware
+//! ```
+//!
+//! Marca las operaciones que caerian en la regla de ventas lavadas segun el diario de la cuenta,
+//! muestra la ganancia no realizada por lote y propone cosechar perdidas con un sustituto.
+
+use fintual_coding_challenge::journal::{Journal, Transaction, TransactionKind};
+use fintual_coding_challenge::tax::{Substitutes, WashSaleRule};
+use fintual_coding_challenge::{Date, Portfolio, PortfolioTarget, Stock};
+use rust_decimal_macros::dec;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let date = |y, m, d| Date::new(y, m, d).unwrap();
+    let today = date(2024, 6, 15);
+
+    let target = PortfolioTarget::try_from(vec![
+        (dec!(50), Stock::new("VTI", dec!(200))),
+        (dec!(50), Stock::new("VXUS", dec!(50))),
+    ])?;
+    let portfolio = Portfolio::builder()
+        .with_stock(
+            Stock::new("VTI", dec!(200)).with_basis(dec!(250), date(2024, 6, 1)),
+            10,
+        )
+        .with_stock(
+            Stock::new("VXUS", dec!(50)).with_basis(dec!(40), date(2021, 3, 1)),
+            10,
+        )
+        .with_target(target)
+        .build()?;
+
+    let journal: Journal = [
+        Transaction::trade(
+            date(2021, 3, 1),
+            TransactionKind::Buy,
+            "VXUS",
+            dec!(10),
+            dec!(40),
+        ),
+        Transaction::trade(
+            date(2024, 6, 1),
+            TransactionKind::Buy,
+            "VTI",
+            dec!(10),
+            dec!(250),
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    println!("{}\n", portfolio.unrealized_pnl_at(today));
+
+    let mut suggestion = portfolio.rebalance_portfolio();
+    portfolio.check_wash_sales(&mut suggestion, &journal, today, &WashSaleRule::default());
+    println!("Sugerencia:\n{suggestion}\n");
+
+    let substitutes = Substitutes::new().with("VTI", Stock::new("ITOT", dec!(100)));
+    let plan = portfolio.harvest_losses(dec!(100), &substitutes);
+    println!("Cosecha de perdidas:\n{plan}");
+
+    Ok(())
+}
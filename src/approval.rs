@@ -0,0 +1,357 @@
+//! Aprobacion de sugerencias.
+//!
+//! Cuando una persona revisa cada rebalanceo antes de mandarlo al broker, la sugerencia pasa por
+//! estados: se crea como borrador, alguien la aprueba (o la rechaza), se ejecuta y finalmente se
+//! concilia con lo que el broker realmente hizo. `ReviewedSuggestion` guarda las ordenes junto
+//! con cada paso (quien, cuando y una nota opcional) y no deja saltarse ninguno.
+//!
+//! ```text
+//! Borrador -> Aprobada -> Ejecutada -> Conciliada
+//!     \           \
+//!      `-----------`---> Rechazada
+//! ```
+
+use crate::clock::Clock;
+use crate::costs::CostModel;
+use crate::date::{Date, Timestamp};
+use crate::error::ApprovalError;
+use crate::execution::{Fill, Order};
+use crate::i18n::{Language, Localize, language};
+use crate::id::SuggestionId;
+use crate::reconcile::Reconciliation;
+use crate::{Portfolio, RebalanceSuggestion};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// En que paso del flujo esta una sugerencia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApprovalState {
+    Draft,
+    Approved,
+    Executed,
+    Reconciled,
+    Rejected,
+}
+
+impl ApprovalState {
+    /// Si desde este estado se puede pasar a `next`.
+    pub fn can_become(self, next: ApprovalState) -> bool {
+        use ApprovalState::*;
+
+        matches!(
+            (self, next),
+            (Draft, Approved)
+                | (Approved, Executed)
+                | (Executed, Reconciled)
+                | (Draft, Rejected)
+                | (Approved, Rejected)
+        )
+    }
+
+    /// Si ya no puede cambiar.
+    pub fn is_final(self) -> bool {
+        matches!(self, ApprovalState::Reconciled | ApprovalState::Rejected)
+    }
+}
+
+impl Localize for ApprovalState {
+    fn localize(&self, language: Language) -> String {
+        let (es, en) = match self {
+            ApprovalState::Draft => ("borrador", "draft"),
+            ApprovalState::Approved => ("aprobada", "approved"),
+            ApprovalState::Executed => ("ejecutada", "executed"),
+            ApprovalState::Reconciled => ("conciliada", "reconciled"),
+            ApprovalState::Rejected => ("rechazada", "rejected"),
+        };
+        match language {
+            Language::Es => es.into(),
+            Language::En => en.into(),
+        }
+    }
+}
+
+impl fmt::Display for ApprovalState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// Un paso del flujo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub state: ApprovalState,
+    pub at: Timestamp,
+
+    /// Quien dio el paso (un usuario, o el nombre del proceso automatico).
+    pub by: String,
+
+    /// Comentario, p. ej. el motivo de un rechazo.
+    pub note: Option<String>,
+
+    /// Cuantas ordenes quedaron con residuo al conciliar (ver `Reconciliation::residual`); cero
+    /// en los demas pasos. Se traduce al mostrar el historial.
+    pub residual: usize,
+}
+
+/// Una sugerencia con su flujo de aprobacion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewedSuggestion {
+    pub id: SuggestionId,
+    pub orders: Vec<Order>,
+
+    /// Cada paso, empezando por la creacion del borrador.
+    history: Vec<Transition>,
+}
+
+impl ReviewedSuggestion {
+    /// Un borrador con las ordenes de `suggestion`, creado por `author`.
+    pub fn draft(suggestion: &RebalanceSuggestion<'_>, author: &str, clock: impl Clock) -> Self {
+        Self::from_orders(suggestion.id, suggestion.orders(), author, clock.now())
+    }
+
+    /// Un borrador con ordenes armadas por otro lado.
+    pub fn from_orders(id: SuggestionId, orders: Vec<Order>, author: &str, at: Timestamp) -> Self {
+        Self {
+            id,
+            orders,
+            history: vec![Transition {
+                state: ApprovalState::Draft,
+                at,
+                by: author.into(),
+                note: None,
+                residual: 0,
+            }],
+        }
+    }
+
+    pub fn state(&self) -> ApprovalState {
+        self.history
+            .last()
+            .map_or(ApprovalState::Draft, |step| step.state)
+    }
+
+    pub fn history(&self) -> &[Transition] {
+        &self.history
+    }
+
+    /// El paso en que se llego a `state`, si se llego.
+    pub fn step(&self, state: ApprovalState) -> Option<&Transition> {
+        self.history.iter().find(|step| step.state == state)
+    }
+
+    /// Quien aprobo la sugerencia, si ya se aprobo.
+    pub fn approver(&self) -> Option<&str> {
+        Some(&self.step(ApprovalState::Approved)?.by)
+    }
+
+    fn advance(
+        &mut self,
+        state: ApprovalState,
+        by: &str,
+        note: Option<&str>,
+        residual: usize,
+        at: Timestamp,
+    ) -> Result<(), ApprovalError> {
+        let from = self.state();
+        if !from.can_become(state) {
+            return Err(ApprovalError::InvalidTransition { from, to: state });
+        }
+
+        self.history.push(Transition {
+            state,
+            at,
+            by: by.into(),
+            note: note.map(ToString::to_string),
+            residual,
+        });
+        Ok(())
+    }
+
+    pub fn approve(&mut self, approver: &str, clock: impl Clock) -> Result<(), ApprovalError> {
+        self.advance(ApprovalState::Approved, approver, None, 0, clock.now())
+    }
+
+    /// Rechaza un borrador o una sugerencia aprobada que todavia no se ejecuta.
+    pub fn reject(
+        &mut self,
+        by: &str,
+        reason: &str,
+        clock: impl Clock,
+    ) -> Result<(), ApprovalError> {
+        self.advance(ApprovalState::Rejected, by, Some(reason), 0, clock.now())
+    }
+
+    /// Marca la sugerencia como ejecutada (enviada al broker). Antes confirma que `portfolio`
+    /// sigue en el estado con que se calculo (ver `Portfolio::verify_suggestion`): una sugerencia
+    /// aprobada que quedo vieja no se manda.
+    pub fn execute(
+        &mut self,
+        portfolio: &Portfolio,
+        by: &str,
+        clock: impl Clock,
+    ) -> Result<(), ApprovalError> {
+        let from = self.state();
+        if !from.can_become(ApprovalState::Executed) {
+            return Err(ApprovalError::InvalidTransition {
+                from,
+                to: ApprovalState::Executed,
+            });
+        }
+        portfolio
+            .verify_suggestion(self.id)
+            .map_err(ApprovalError::Stale)?;
+
+        self.advance(ApprovalState::Executed, by, None, 0, clock.now())
+    }
+
+    /// Aplica lo que el broker realmente ejecuto (ver `Portfolio::reconcile`) y cierra el flujo.
+    /// El paso guarda cuantas ordenes quedaron con residuo (ver `Transition::residual`).
+    pub fn reconcile(
+        &mut self,
+        portfolio: &mut Portfolio,
+        fills: &[Fill],
+        date: Date,
+        costs: &CostModel,
+        by: &str,
+        clock: impl Clock,
+    ) -> Result<Reconciliation, ApprovalError> {
+        let from = self.state();
+        if !from.can_become(ApprovalState::Reconciled) {
+            return Err(ApprovalError::InvalidTransition {
+                from,
+                to: ApprovalState::Reconciled,
+            });
+        }
+
+        let reconciliation = portfolio
+            .reconcile(&self.orders, fills, date, costs)
+            .map_err(ApprovalError::Event)?;
+        let residual = reconciliation.residual.len();
+        self.advance(ApprovalState::Reconciled, by, None, residual, clock.now())?;
+        Ok(reconciliation)
+    }
+}
+
+impl Localize for ReviewedSuggestion {
+    fn localize(&self, language: Language) -> String {
+        let mut lines = vec![match language {
+            Language::Es => format!("Sugerencia {} ({} ordenes)", self.id, self.orders.len()),
+            Language::En => format!("Suggestion {} ({} orders)", self.id, self.orders.len()),
+        }];
+        for step in &self.history {
+            let residual = match (step.residual, language) {
+                (0, _) => None,
+                (1, Language::Es) => Some("1 orden con residuo".to_string()),
+                (n, Language::Es) => Some(format!("{n} ordenes con residuo")),
+                (1, Language::En) => Some("1 order with a residual".to_string()),
+                (n, Language::En) => Some(format!("{n} orders with a residual")),
+            };
+            let note = step
+                .note
+                .clone()
+                .or(residual)
+                .map_or(String::new(), |n| format!(": {n}"));
+            lines.push(format!(
+                "- {} {} {}{note}",
+                step.at,
+                step.state.localize(language),
+                step.by
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for ReviewedSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        Portfolio {
+            stocks: vec![],
+            allocation: PortfolioTarget::new(Stock::new("KO", dec!(10))),
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_draft_is_approved_executed_and_reconciled() {
+        let clock = TestClock::at_date(Date::new(2024, 3, 1).unwrap());
+        let mut portfolio = portfolio();
+        let mut suggestion =
+            ReviewedSuggestion::draft(&portfolio.rebalance_portfolio(), "rebalanceador", &clock);
+
+        assert_eq!(
+            suggestion.execute(&portfolio, "broker", &clock),
+            Err(ApprovalError::InvalidTransition {
+                from: ApprovalState::Draft,
+                to: ApprovalState::Executed,
+            })
+        );
+        clock.advance(3600);
+        suggestion.approve("ana", &clock).unwrap();
+        suggestion.execute(&portfolio, "broker", &clock).unwrap();
+
+        let fills = [Fill::buy("KO", 6, dec!(10))];
+        let date = clock.today();
+        let reconciliation = suggestion
+            .reconcile(
+                &mut portfolio,
+                &fills,
+                date,
+                &CostModel::free(),
+                "ops",
+                &clock,
+            )
+            .unwrap();
+        assert_eq!(reconciliation.residual[0].units, 4);
+
+        assert_eq!(suggestion.state(), ApprovalState::Reconciled);
+        assert!(suggestion.state().is_final());
+        assert_eq!(suggestion.approver(), Some("ana"));
+        let approved = suggestion.step(ApprovalState::Approved).unwrap();
+        assert_eq!(
+            approved.at.as_secs() - suggestion.history()[0].at.as_secs(),
+            3600
+        );
+        assert_eq!(suggestion.history()[3].residual, 1);
+        assert_eq!(suggestion.history()[3].note, None);
+        assert!(
+            suggestion
+                .localize(Language::En)
+                .ends_with("ops: 1 order with a residual")
+        );
+    }
+
+    #[test]
+    fn test_rejected_or_stale_suggestions_are_not_executed() {
+        let clock = TestClock::at_date(Date::new(2024, 3, 1).unwrap());
+        let mut portfolio = portfolio();
+        let mut suggestion =
+            ReviewedSuggestion::draft(&portfolio.rebalance_portfolio(), "rebalanceador", &clock);
+        suggestion.approve("ana", &clock).unwrap();
+
+        portfolio.update_price("KO", dec!(11));
+        assert!(matches!(
+            suggestion.execute(&portfolio, "broker", &clock),
+            Err(ApprovalError::Stale(_))
+        ));
+
+        suggestion.reject("ana", "precios viejos", &clock).unwrap();
+        assert!(suggestion.approve("beto", &clock).is_err());
+        assert_eq!(suggestion.history().len(), 3);
+    }
+}
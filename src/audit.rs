@@ -0,0 +1,384 @@
+//! Registro de auditoria de las sugerencias de rebalanceo.
+//!
+//! Cada sugerencia que se genera queda guardada junto con todo lo necesario para explicarla
+//! despues: los precios usados, el objetivo, la estrategia y las restricciones vigentes. El
+//! historial es de solo agregar; no hay forma de editar ni borrar un registro.
+
+use crate::clock::Clock;
+use crate::date::{Date, Timestamp};
+use crate::{Portfolio, RebalanceStrategy, RebalanceSuggestion};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Una sugerencia de rebalanceo tal como fue generada.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebalanceRecord {
+    pub timestamp: Timestamp,
+
+    /// Precio por unidad de cada stock involucrado (del portafolio y del objetivo).
+    pub prices: BTreeMap<String, Decimal>,
+
+    /// Proporcion objetivo (en %) de cada stock.
+    pub target: BTreeMap<String, Decimal>,
+
+    pub to_buy: BTreeMap<String, usize>,
+    pub to_sell: BTreeMap<String, usize>,
+    pub strategy: RebalanceStrategy,
+
+    /// Descripcion legible de las restricciones que aplicaban al momento de generar la sugerencia.
+    pub constraints: Vec<String>,
+}
+
+impl RebalanceRecord {
+    /// Captura el estado del portafolio y la sugerencia calculada a partir de el.
+    pub fn new(
+        timestamp: Timestamp,
+        portfolio: &Portfolio,
+        suggestion: &RebalanceSuggestion<'_>,
+        strategy: RebalanceStrategy,
+    ) -> Self {
+        let mut prices = BTreeMap::new();
+        for stock in portfolio.stocks() {
+            prices.insert(stock.name().to_string(), stock.current_price());
+        }
+
+        let mut target = BTreeMap::new();
+        for (weight, stock) in portfolio.allocation().targets() {
+            prices.insert(stock.name().to_string(), stock.current_price());
+            target.insert(stock.name().to_string(), *weight);
+        }
+
+        let owned = |map: &crate::Map<&str, usize>| {
+            map.iter()
+                .map(|(name, units)| (name.to_string(), *units))
+                .collect()
+        };
+
+        Self {
+            timestamp,
+            prices,
+            target,
+            to_buy: owned(&suggestion.to_buy),
+            to_sell: owned(&suggestion.to_sell),
+            strategy,
+            constraints: Vec::new(),
+        }
+    }
+
+    pub fn with_constraints(mut self, constraints: Vec<String>) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    /// Serializa el registro en una linea de texto (campos separados por tabs).
+    fn to_line(&self) -> String {
+        let map = |m: &BTreeMap<String, Decimal>| {
+            m.iter()
+                .map(|(k, v)| format!("{}={v}", escape(k)))
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        let units = |m: &BTreeMap<String, usize>| {
+            m.iter()
+                .map(|(k, v)| format!("{}={v}", escape(k)))
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+
+        [
+            self.timestamp.as_secs().to_string(),
+            self.strategy.name().to_string(),
+            map(&self.prices),
+            map(&self.target),
+            units(&self.to_buy),
+            units(&self.to_sell),
+            self.constraints
+                .iter()
+                .map(|c| escape(c))
+                .collect::<Vec<_>>()
+                .join(";"),
+        ]
+        .join("\t")
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [
+            timestamp,
+            strategy,
+            prices,
+            target,
+            to_buy,
+            to_sell,
+            constraints,
+        ] = fields[..]
+        else {
+            return None;
+        };
+
+        Some(Self {
+            timestamp: Timestamp::from_secs(timestamp.parse().ok()?),
+            strategy: RebalanceStrategy::from_name(strategy)?,
+            prices: parse_map(prices)?,
+            target: parse_map(target)?,
+            to_buy: parse_map(to_buy)?,
+            to_sell: parse_map(to_sell)?,
+            constraints: split_list(constraints).map(|c| unescape(&c)).collect(),
+        })
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace(';', "\\s")
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('s') => out.push(';'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+fn split_list(field: &str) -> impl Iterator<Item = String> + '_ {
+    field.split(';').filter(|s| !s.is_empty()).map(String::from)
+}
+
+fn parse_map<V: FromStr>(field: &str) -> Option<BTreeMap<String, V>> {
+    split_list(field)
+        .map(|entry| {
+            let (key, value) = entry.rsplit_once('=')?;
+            Some((unescape(key), value.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Almacenamiento de solo-agregar para registros de rebalanceo.
+pub trait HistoryStore {
+    fn append(&mut self, record: RebalanceRecord) -> io::Result<()>;
+
+    /// Todos los registros, en el orden en que fueron agregados.
+    fn records(&self) -> &[RebalanceRecord];
+
+    /// Registros cuya fecha (UTC) esta entre `from` y `to`, ambos inclusive.
+    fn between(&self, from: Date, to: Date) -> Vec<&RebalanceRecord> {
+        self.records()
+            .iter()
+            .filter(|r| (from..=to).contains(&r.timestamp.date()))
+            .collect()
+    }
+}
+
+/// Historial en memoria; util para tests o procesos cortos.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryHistory {
+    records: Vec<RebalanceRecord>,
+}
+
+impl InMemoryHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl HistoryStore for InMemoryHistory {
+    fn append(&mut self, record: RebalanceRecord) -> io::Result<()> {
+        self.records.push(record);
+        Ok(())
+    }
+
+    fn records(&self) -> &[RebalanceRecord] {
+        &self.records
+    }
+}
+
+/// Historial persistido en un archivo de texto, un registro por linea.
+///
+/// El archivo solo se abre en modo append, asi que los registros antiguos nunca se reescriben.
+#[derive(Debug)]
+pub struct FileHistory {
+    path: PathBuf,
+    records: Vec<RebalanceRecord>,
+}
+
+impl FileHistory {
+    /// Abre (o crea) el historial en `path`, leyendo los registros existentes.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut records = Vec::new();
+
+        if path.exists() {
+            for (number, line) in BufReader::new(File::open(&path)?).lines().enumerate() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+
+                let record = RebalanceRecord::from_line(&line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Registro invalido en la linea {}", number + 1),
+                    )
+                })?;
+                records.push(record);
+            }
+        }
+
+        Ok(Self { path, records })
+    }
+}
+
+impl HistoryStore for FileHistory {
+    fn append(&mut self, record: RebalanceRecord) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", record.to_line())?;
+
+        self.records.push(record);
+        Ok(())
+    }
+
+    fn records(&self) -> &[RebalanceRecord] {
+        &self.records
+    }
+}
+
+impl Portfolio {
+    /// Calcula la sugerencia de rebalanceo y la deja registrada en el historial.
+    pub fn rebalance_and_record(
+        &self,
+        store: &mut impl HistoryStore,
+        timestamp: Timestamp,
+    ) -> io::Result<RebalanceSuggestion<'_>> {
+        let suggestion = self.rebalance_portfolio();
+        let record = RebalanceRecord::new(
+            timestamp,
+            self,
+            &suggestion,
+            RebalanceStrategy::Conservative,
+        );
+        store.append(record)?;
+
+        Ok(suggestion)
+    }
+
+    /// `rebalance_and_record` con la hora que marca `clock`.
+    pub fn rebalance_and_record_now(
+        &self,
+        store: &mut impl HistoryStore,
+        clock: &impl Clock,
+    ) -> io::Result<RebalanceSuggestion<'_>> {
+        self.rebalance_and_record(store, clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("CASH", dec!(1)); 100],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(25))),
+        }
+    }
+
+    fn day(d: u32) -> Timestamp {
+        Timestamp::from_date(Date::new(2024, 1, d).unwrap())
+    }
+
+    #[test]
+    fn test_record_captures_inputs_and_output() {
+        let mut history = InMemoryHistory::new();
+        portfolio()
+            .rebalance_and_record(&mut history, day(1))
+            .unwrap();
+
+        let record = &history.records()[0];
+        assert_eq!(record.prices["META"], dec!(25));
+        assert_eq!(record.target["META"], dec!(100));
+        assert_eq!(record.to_buy["META"], 4);
+        assert_eq!(record.to_sell["CASH"], 100);
+    }
+
+    #[test]
+    fn test_query_by_date_range() {
+        let mut history = InMemoryHistory::new();
+        for d in [1, 5, 10] {
+            portfolio()
+                .rebalance_and_record(&mut history, day(d))
+                .unwrap();
+        }
+
+        let found = history.between(
+            Date::new(2024, 1, 2).unwrap(),
+            Date::new(2024, 1, 10).unwrap(),
+        );
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].timestamp, day(5));
+    }
+
+    #[test]
+    fn test_records_are_stamped_by_the_clock() {
+        let clock = crate::clock::TestClock::new(day(1));
+        let mut history = InMemoryHistory::new();
+        portfolio()
+            .rebalance_and_record_now(&mut history, &clock)
+            .unwrap();
+
+        let stamp = history.records()[0].timestamp;
+        assert_eq!(stamp, day(1));
+        clock.advance_days(2);
+        assert!(clock.is_stale(stamp, 86_400));
+    }
+
+    #[test]
+    fn test_file_history_roundtrip() {
+        let path = std::env::temp_dir().join(format!("audit-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let portfolio = portfolio();
+        let suggestion = portfolio.rebalance_portfolio();
+        let record = RebalanceRecord::new(
+            day(3),
+            &portfolio,
+            &suggestion,
+            RebalanceStrategy::Conservative,
+        )
+        .with_constraints(vec!["max 10 trades; sin cripto".into()]);
+
+        FileHistory::open(&path)
+            .unwrap()
+            .append(record.clone())
+            .unwrap();
+        let reopened = FileHistory::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.records(), &[record]);
+    }
+}
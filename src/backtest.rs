@@ -0,0 +1,1583 @@
+//! Backtests: como le habria ido a un portafolio con una politica de rebalanceo sobre precios
+//! historicos.
+//!
+//! En cada dia de la serie se aplican los precios, luego los dividendos de ese dia y, si toca,
+//! se rebalancea con `rebalance_portfolio` y se ejecuta con `Portfolio::apply`. Los dividendos
+//! pueden reinvertirse en el mismo ticker (DRIP) o quedar como caja; en ambos casos el retorno
+//! del backtest es retorno total, no solo de precio.
+//!
+//! Las ordenes se deciden con los precios de cierre, asi que las de fondos mutuos (ver `fund`)
+//! llegan despues del corte y se ejecutan al valor cuota del dia siguiente de la serie.
+
+use crate::Portfolio;
+use crate::costs::CostModel;
+use crate::date::Date;
+use crate::execution::{Order, Side};
+use crate::i18n::{Language, Localize, language};
+use crate::journal::{Journal, TransactionKind};
+use crate::metrics::std_dev;
+use crate::prices::{FillPrice, PriceHistory};
+use crate::progress::{Cancelled, Monitor};
+use crate::projection::percentile;
+use crate::reports::{RoundingLedger, TurnoverReport};
+use crate::rng::{Rng, SeededRng};
+use crate::rolling::{Rolling, max_drawdown};
+use crate::settlement::Settlement;
+use rust_decimal::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Dividendo pagado por unidad de un ticker en una fecha.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dividend {
+    pub date: Date,
+    pub ticker: String,
+    pub per_unit: Decimal,
+}
+
+/// Precios y dividendos historicos.
+#[derive(Debug, Clone, Default)]
+pub struct MarketData {
+    /// Dias ordenados por fecha, cada uno con los precios de cierre conocidos.
+    days: Vec<(Date, Vec<(String, Decimal)>)>,
+    dividends: Vec<Dividend>,
+
+    /// Barras completas, si los precios vienen de una `PriceHistory`; se usan para ejecutar a
+    /// un precio distinto del cierre (ver `BacktestConfig::fill_price`).
+    bars: PriceHistory,
+}
+
+impl MarketData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agrega los precios de un dia; si el dia ya existia, los precios se agregan a los que
+    /// tenia.
+    pub fn with_prices(mut self, date: Date, prices: &[(&str, Decimal)]) -> Self {
+        let prices = prices.iter().map(|(t, p)| (t.to_string(), *p));
+        match self.days.binary_search_by_key(&date, |(d, _)| *d) {
+            Ok(index) => self.days[index].1.extend(prices),
+            Err(index) => self.days.insert(index, (date, prices.collect())),
+        }
+        self
+    }
+
+    /// Precios de cierre de cada dia de `history`, guardando las barras para poder ejecutar a
+    /// otro precio.
+    pub fn from_history(history: &PriceHistory) -> Self {
+        let mut market = Self::new();
+        for date in history.dates() {
+            let closes: Vec<(&str, Decimal)> = history
+                .tickers()
+                .filter_map(|ticker| Some((ticker, history.bar(ticker, date)?.close)))
+                .collect();
+            market = market.with_prices(date, &closes);
+        }
+        market.bars = history.clone();
+        market
+    }
+
+    pub fn with_dividend(mut self, date: Date, ticker: &str, per_unit: Decimal) -> Self {
+        self.dividends.push(Dividend {
+            date,
+            ticker: ticker.into(),
+            per_unit,
+        });
+        self
+    }
+
+    pub fn dates(&self) -> impl Iterator<Item = Date> + '_ {
+        self.days.iter().map(|(date, _)| *date)
+    }
+
+    pub fn dividends_on(&self, date: Date) -> impl Iterator<Item = &Dividend> {
+        self.dividends.iter().filter(move |d| d.date == date)
+    }
+}
+
+/// Cada cuanto se rebalancea.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebalanceSchedule {
+    /// Solo el primer dia (comprar y mantener).
+    Never,
+
+    /// El primer dia y luego cada `n` meses calendario.
+    EveryMonths(u32),
+
+    /// El primer dia y luego cada dia en que, con los precios de ese dia, el peso de algun
+    /// ticker se aleja mas de esos puntos porcentuales de su objetivo (o de su banda propia, ver
+    /// `PortfolioTarget::with_band`).
+    Band(Decimal),
+}
+
+/// Aporte periodico de caja, como el de un ahorrante que deposita todos los meses. El primer
+/// aporte es `every_months` meses despues del primer dia del backtest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContributionSchedule {
+    pub amount: Decimal,
+    pub every_months: u32,
+}
+
+impl ContributionSchedule {
+    pub fn monthly(amount: Decimal) -> Self {
+        Self::every_months(1, amount)
+    }
+
+    pub fn every_months(every_months: u32, amount: Decimal) -> Self {
+        Self {
+            amount,
+            every_months: every_months.max(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacktestConfig {
+    pub schedule: RebalanceSchedule,
+
+    /// Reinvertir los dividendos en el mismo ticker; si no, quedan como caja.
+    pub drip: bool,
+
+    /// Comisiones que se cobran en cada orden.
+    pub costs: CostModel,
+
+    /// Precio de la barra al que se ejecutan los rebalanceos; sin barras se usa el cierre. La
+    /// curva de valor siempre usa el cierre.
+    pub fill_price: FillPrice,
+
+    /// Cuando se liquidan las ventas. Con T+N las compras que no alcanza a pagar la caja
+    /// liquidada se hacen el primer dia de precios despues de la liquidacion (a ese precio),
+    /// salvo que antes toque rebalancear de nuevo.
+    pub settlement: Settlement,
+
+    /// Aportes periodicos. El dia del aporte, si no toca rebalancear, la caja se invierte solo
+    /// comprando (ver `Portfolio::contribution_suggestion`).
+    pub contributions: Option<ContributionSchedule>,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            schedule: RebalanceSchedule::EveryMonths(1),
+            drip: false,
+            costs: CostModel::free(),
+            fill_price: FillPrice::Close,
+            settlement: Settlement::immediate(),
+            contributions: None,
+        }
+    }
+}
+
+impl BacktestConfig {
+    pub fn with_schedule(mut self, schedule: RebalanceSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub fn with_drip(mut self, drip: bool) -> Self {
+        self.drip = drip;
+        self
+    }
+
+    pub fn with_costs(mut self, costs: CostModel) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    pub fn with_fill_price(mut self, fill_price: FillPrice) -> Self {
+        self.fill_price = fill_price;
+        self
+    }
+
+    pub fn with_settlement(mut self, settlement: Settlement) -> Self {
+        self.settlement = settlement;
+        self
+    }
+
+    pub fn with_contributions(mut self, contributions: ContributionSchedule) -> Self {
+        self.contributions = Some(contributions);
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestResult {
+    /// Valor del portafolio (stocks + caja) al cierre de cada dia.
+    pub equity_curve: Vec<(Date, Decimal)>,
+
+    /// Total de dividendos recibidos.
+    pub dividends: Decimal,
+
+    /// Cuantas veces se rebalanceo (contando solo las que generaron ordenes).
+    pub rebalances: usize,
+
+    /// Dias en que se rebalanceo (los mismos que cuenta `rebalances`).
+    pub rebalance_dates: Vec<Date>,
+
+    /// Ordenes de rebalanceo ejecutadas (sin contar las compras de DRIP).
+    pub trades: usize,
+
+    /// Monto total comprado mas vendido en los rebalanceos, a precio de ejecucion.
+    pub traded: Decimal,
+
+    /// Peso (en %) de cada ticker en cartera al cierre de cada dia; la caja es lo que falta
+    /// para 100.
+    pub weights: Vec<(Date, Vec<(String, Decimal)>)>,
+
+    /// Lo que se dejo de invertir por redondear en cada rebalanceo (ver
+    /// `Portfolio::rounding_loss`).
+    pub rounding: RoundingLedger,
+
+    /// Aportes hechos, con su fecha. La curva de valor los incluye.
+    pub contributions: Vec<(Date, Decimal)>,
+
+    /// Compras, ventas y comisiones ejecutadas (rebalanceos y DRIP).
+    pub journal: Journal,
+
+    /// Estado final del portafolio.
+    pub portfolio: Portfolio,
+}
+
+impl BacktestResult {
+    /// Retorno total entre el primer y el ultimo dia; `None` si no hay dias o el valor inicial
+    /// es cero. Con aportes es el retorno ponderado por tiempo (ver `growth_curve`), asi que
+    /// los aportes no cuentan como ganancia.
+    pub fn total_return(&self) -> Option<Decimal> {
+        let growth = self.growth_curve();
+        let (_, first) = growth.first()?;
+        let (_, last) = growth.last()?;
+        if first.is_zero() {
+            return None;
+        }
+
+        Some(last / first - Decimal::ONE)
+    }
+
+    /// Total aportado durante el backtest.
+    pub fn contributed(&self) -> Decimal {
+        self.contributions.iter().map(|(_, amount)| *amount).sum()
+    }
+
+    /// Ganancia en plata: valor final menos valor inicial menos lo aportado.
+    pub fn gain(&self) -> Option<Decimal> {
+        let (_, first) = self.equity_curve.first()?;
+        let (_, last) = self.equity_curve.last()?;
+        Some(last - first - self.contributed())
+    }
+
+    /// La curva de valor sin el efecto de los aportes: parte en el mismo valor y cada dia crece
+    /// solo lo que rindio el portafolio (el aporte del dia se descuenta del valor de cierre).
+    /// Sin aportes es la misma `equity_curve`.
+    pub fn growth_curve(&self) -> Vec<(Date, Decimal)> {
+        if self.contributions.is_empty() {
+            return self.equity_curve.clone();
+        }
+
+        let mut curve = Vec::with_capacity(self.equity_curve.len());
+        let mut previous: Option<(Decimal, Decimal)> = None;
+        for (date, value) in &self.equity_curve {
+            let grown = match previous {
+                Some((previous_value, growth)) if !previous_value.is_zero() => {
+                    let contributed: Decimal = self
+                        .contributions
+                        .iter()
+                        .filter(|(d, _)| d == date)
+                        .map(|(_, amount)| *amount)
+                        .sum();
+                    growth * (value - contributed) / previous_value
+                }
+                _ => *value,
+            };
+            curve.push((*date, grown));
+            previous = Some((*value, grown));
+        }
+        curve
+    }
+
+    /// Volatilidad anualizada de los retornos de la curva de valor, infiriendo cuantas
+    /// observaciones hay por año a partir de las fechas; `None` con menos de tres dias.
+    pub fn volatility(&self) -> Option<f64> {
+        let periods_per_year = periods_per_year(&self.equity_curve)?;
+        let rolling = self
+            .rolling(self.equity_curve.len() - 1)
+            .with_periods_per_year(periods_per_year);
+        rolling.volatility().first().map(|(_, v)| *v)
+    }
+
+    /// Rotacion: monto transado en rebalanceos sobre el valor promedio del portafolio (1 = se
+    /// transo una vez el portafolio completo).
+    pub fn turnover(&self) -> Option<Decimal> {
+        if self.equity_curve.is_empty() {
+            return None;
+        }
+        let average = self.equity_curve.iter().map(|(_, v)| *v).sum::<Decimal>()
+            / Decimal::from(self.equity_curve.len());
+        (!average.is_zero()).then(|| self.traded / average)
+    }
+
+    /// Rotacion anualizada (lo menor entre compras y ventas, incluido el DRIP) y permanencia
+    /// promedio de cada ticker, sobre todo el backtest; `None` sin dias.
+    pub fn turnover_report(&self) -> Option<TurnoverReport> {
+        let (from, _) = self.equity_curve.first()?;
+        let (to, _) = self.equity_curve.last()?;
+        let average = self.equity_curve.iter().map(|(_, v)| *v).sum::<Decimal>()
+            / Decimal::from(self.equity_curve.len());
+        Some(TurnoverReport::new(&self.journal, *from, *to, average))
+    }
+
+    /// Estadisticas moviles de la curva de valor (sin aportes, ver `growth_curve`), con
+    /// ventanas de `window` dias.
+    pub fn rolling(&self, window: usize) -> Rolling {
+        Rolling::new(&self.growth_curve(), window)
+    }
+}
+
+/// Corre el mismo portafolio inicial con cada configuracion, para comparar politicas (con o sin
+/// rebalanceo, DRIP, comisiones) sobre los mismos precios. Los resultados van en el orden de
+/// `configs`.
+pub fn compare(
+    portfolio: &Portfolio,
+    market: &MarketData,
+    configs: &[BacktestConfig],
+) -> Vec<BacktestResult> {
+    configs
+        .iter()
+        .map(|config| run(portfolio.clone(), market, config))
+        .collect()
+}
+
+/// Resultado de una politica en `frequency_study`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleComparison {
+    pub schedule: RebalanceSchedule,
+    pub total_return: Option<Decimal>,
+    pub volatility: Option<f64>,
+    pub turnover: Option<Decimal>,
+    pub trades: usize,
+}
+
+/// Tabla comparativa de `frequency_study`, en el orden de las politicas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyStudy {
+    pub rows: Vec<ScheduleComparison>,
+}
+
+/// Las politicas que compara `frequency_study`: mensual, trimestral, anual y bandas de 5 puntos.
+pub const STUDY_SCHEDULES: [RebalanceSchedule; 4] = [
+    RebalanceSchedule::EveryMonths(1),
+    RebalanceSchedule::EveryMonths(3),
+    RebalanceSchedule::EveryMonths(12),
+    RebalanceSchedule::Band(Decimal::from_parts(5, 0, 0, false, 0)),
+];
+
+/// ¿Cada cuanto conviene rebalancear? Corre `config` con cada politica de `STUDY_SCHEDULES`
+/// sobre los mismos precios y arma la tabla de retorno, volatilidad, rotacion y ordenes.
+pub fn frequency_study(
+    portfolio: &Portfolio,
+    market: &MarketData,
+    config: &BacktestConfig,
+) -> FrequencyStudy {
+    frequency_study_with(portfolio, market, config, &STUDY_SCHEDULES)
+}
+
+/// Igual que `frequency_study` con otras politicas.
+pub fn frequency_study_with(
+    portfolio: &Portfolio,
+    market: &MarketData,
+    config: &BacktestConfig,
+    schedules: &[RebalanceSchedule],
+) -> FrequencyStudy {
+    let configs: Vec<BacktestConfig> = schedules
+        .iter()
+        .map(|schedule| config.clone().with_schedule(*schedule))
+        .collect();
+
+    let rows = compare(portfolio, market, &configs)
+        .into_iter()
+        .zip(schedules)
+        .map(|(result, schedule)| ScheduleComparison {
+            schedule: *schedule,
+            total_return: result.total_return(),
+            volatility: result.volatility(),
+            turnover: result.turnover(),
+            trades: result.trades,
+        })
+        .collect();
+
+    FrequencyStudy { rows }
+}
+
+impl Localize for RebalanceSchedule {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (RebalanceSchedule::Never, Language::Es) => "nunca".into(),
+            (RebalanceSchedule::Never, Language::En) => "never".into(),
+            (RebalanceSchedule::EveryMonths(1), Language::Es) => "mensual".into(),
+            (RebalanceSchedule::EveryMonths(1), Language::En) => "monthly".into(),
+            (RebalanceSchedule::EveryMonths(3), Language::Es) => "trimestral".into(),
+            (RebalanceSchedule::EveryMonths(3), Language::En) => "quarterly".into(),
+            (RebalanceSchedule::EveryMonths(12), Language::Es) => "anual".into(),
+            (RebalanceSchedule::EveryMonths(12), Language::En) => "annual".into(),
+            (RebalanceSchedule::EveryMonths(n), Language::Es) => format!("cada {n} meses"),
+            (RebalanceSchedule::EveryMonths(n), Language::En) => format!("every {n} months"),
+            (RebalanceSchedule::Band(band), Language::Es) => format!("bandas de {band}%"),
+            (RebalanceSchedule::Band(band), Language::En) => format!("{band}% bands"),
+        }
+    }
+}
+
+impl Localize for FrequencyStudy {
+    fn localize(&self, language: Language) -> String {
+        let header = match language {
+            Language::Es => "politica | retorno % | volatilidad % | rotacion | ordenes",
+            Language::En => "schedule | return % | volatility % | turnover | trades",
+        };
+        let percent = |value: Option<Decimal>| {
+            value.map_or("-".into(), |v| {
+                (v * Decimal::ONE_HUNDRED).round_dp(2).to_string()
+            })
+        };
+
+        let rows = self.rows.iter().map(|row| {
+            format!(
+                "{} | {} | {} | {} | {}",
+                row.schedule.localize(language),
+                percent(row.total_return),
+                percent(row.volatility.and_then(Decimal::from_f64)),
+                row.turnover
+                    .map_or("-".into(), |t| t.round_dp(2).to_string()),
+                row.trades
+            )
+        });
+
+        std::iter::once(header.to_string())
+            .chain(rows)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for FrequencyStudy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// Cuanto cuesta no llegar exactamente al objetivo: la caja que sobra y el drift que deja la
+/// estrategia conservadora (unidades enteras, sin pasarse) frente a un portafolio ideal.
+///
+/// El ideal parte con el mismo valor, se rebalancea los mismos dias pero con unidades
+/// fraccionarias, sin comisiones y dejando exactamente la caja del objetivo, y recibe los mismos
+/// dividendos.
+#[derive(Debug, Clone)]
+pub struct DriftCost {
+    /// El backtest real.
+    pub result: BacktestResult,
+
+    /// Valor del portafolio ideal al cierre de cada dia.
+    pub ideal_curve: Vec<(Date, Decimal)>,
+
+    /// Caja por sobre la del objetivo, en puntos porcentuales, promediada entre dias.
+    pub average_excess_cash: Decimal,
+
+    /// Drift promedio: la mitad de la suma de las diferencias absolutas con el objetivo (el
+    /// porcentaje del portafolio que esta "mal puesto"), promediada entre dias.
+    pub average_drift: Decimal,
+}
+
+impl DriftCost {
+    /// Valor final del ideal menos el real; positivo si no llegar al objetivo costo plata.
+    pub fn opportunity_cost(&self) -> Decimal {
+        let last = |curve: &[(Date, Decimal)]| curve.last().map(|(_, v)| *v).unwrap_or_default();
+        last(&self.ideal_curve) - last(&self.result.equity_curve)
+    }
+
+    /// Diferencia de retorno total, ideal menos real.
+    pub fn return_gap(&self) -> Option<Decimal> {
+        let (_, first) = self.ideal_curve.first()?;
+        let (_, last) = self.ideal_curve.last()?;
+        if first.is_zero() {
+            return None;
+        }
+        Some(last / first - Decimal::ONE - self.result.total_return()?)
+    }
+
+    /// Tracking error anualizado del real contra el ideal: desviacion estandar de la diferencia
+    /// de retornos de cada periodo.
+    pub fn tracking_error(&self) -> Option<f64> {
+        let returns = |curve: &[(Date, Decimal)]| -> Vec<f64> {
+            curve
+                .windows(2)
+                .map(|pair| {
+                    let (from, to) = (pair[0].1.to_f64()?, pair[1].1.to_f64()?);
+                    Some(if from == 0.0 { 0.0 } else { to / from - 1.0 })
+                })
+                .map(Option::unwrap_or_default)
+                .collect()
+        };
+        let differences: Vec<f64> = returns(&self.result.equity_curve)
+            .into_iter()
+            .zip(returns(&self.ideal_curve))
+            .map(|(actual, ideal)| actual - ideal)
+            .collect();
+
+        let periods_per_year = periods_per_year(&self.result.equity_curve)?;
+        Some(std_dev(&differences)? * periods_per_year.sqrt())
+    }
+}
+
+/// Corre el backtest y lo compara con el portafolio ideal (ver `DriftCost`). Para comparar
+/// politicas basta con llamarla con cada configuracion.
+pub fn drift_cost(
+    portfolio: &Portfolio,
+    market: &MarketData,
+    config: &BacktestConfig,
+) -> DriftCost {
+    let result = run(portfolio.clone(), market, config);
+    let target = portfolio.allocation();
+
+    let mut start = portfolio.clone();
+    if let Some((_, prices)) = market.days.first() {
+        for (ticker, price) in prices {
+            start.update_price(ticker, *price);
+        }
+    }
+    let mut prices: BTreeMap<&str, Decimal> = target
+        .targets()
+        .iter()
+        .map(|(_, stock)| (stock.name(), stock.current_price()))
+        .collect();
+    let mut units: BTreeMap<&str, Decimal> = BTreeMap::new();
+    let mut cash = start.total_value();
+
+    let mut ideal_curve = Vec::with_capacity(market.days.len());
+    for (index, (date, day_prices)) in market.days.iter().enumerate() {
+        for (ticker, price) in day_prices {
+            if let Some(known) = prices.get_mut(ticker.as_str()) {
+                *known = *price;
+            }
+        }
+        for dividend in market.dividends_on(*date) {
+            let held = units
+                .get(dividend.ticker.as_str())
+                .copied()
+                .unwrap_or_default();
+            cash += dividend.per_unit * held;
+        }
+        let contributed: Decimal = result
+            .contributions
+            .iter()
+            .filter(|(d, _)| d == date)
+            .map(|(_, amount)| *amount)
+            .sum();
+        cash += contributed;
+
+        let value = |units: &BTreeMap<&str, Decimal>, cash: Decimal| {
+            cash + units
+                .iter()
+                .map(|(ticker, units)| units * prices.get(ticker).copied().unwrap_or_default())
+                .sum::<Decimal>()
+        };
+        if index == 0 || !contributed.is_zero() || result.rebalance_dates.contains(date) {
+            let total = value(&units, cash);
+            units = target
+                .targets()
+                .iter()
+                .filter_map(|(weight, stock)| {
+                    let price = prices.get(stock.name()).filter(|p| !p.is_zero())?;
+                    Some((stock.name(), total * weight / Decimal::ONE_HUNDRED / price))
+                })
+                .collect();
+            cash = total - value(&units, Decimal::ZERO);
+        }
+        ideal_curve.push((*date, value(&units, cash)));
+    }
+
+    let days = Decimal::from(result.weights.len().max(1));
+    let target_weights: Vec<(&str, Decimal)> = target
+        .targets()
+        .iter()
+        .map(|(weight, stock)| (stock.name(), *weight))
+        .collect();
+    let (excess_cash, drift) = result.weights.iter().fold(
+        (Decimal::ZERO, Decimal::ZERO),
+        |(excess_cash, drift), (_, weights)| {
+            let invested: Decimal = weights.iter().map(|(_, w)| *w).sum();
+            let mut differences: Decimal = target_weights
+                .iter()
+                .map(|(ticker, target)| {
+                    let actual = weights
+                        .iter()
+                        .find(|(t, _)| t == ticker)
+                        .map_or(Decimal::ZERO, |(_, w)| *w);
+                    (actual - target).abs()
+                })
+                .sum();
+            differences += weights
+                .iter()
+                .filter(|(t, _)| !target.contains_key(t))
+                .map(|(_, w)| *w)
+                .sum::<Decimal>();
+            let cash_difference = Decimal::ONE_HUNDRED - invested - target.cash_weight();
+            differences += cash_difference.abs();
+
+            (
+                excess_cash + cash_difference,
+                drift + differences / Decimal::TWO,
+            )
+        },
+    );
+
+    DriftCost {
+        result,
+        ideal_curve,
+        average_excess_cash: excess_cash / days,
+        average_drift: drift / days,
+    }
+}
+
+impl Localize for DriftCost {
+    fn localize(&self, language: Language) -> String {
+        let percent = |value: Option<Decimal>| {
+            value.map_or("-".into(), |v| {
+                (v * Decimal::ONE_HUNDRED).round_dp(2).to_string()
+            })
+        };
+        let cost = self.opportunity_cost().round_dp(2);
+        let gap = percent(self.return_gap());
+        let tracking = percent(self.tracking_error().and_then(Decimal::from_f64));
+        let cash = self.average_excess_cash.round_dp(2);
+        let drift = self.average_drift.round_dp(2);
+
+        match language {
+            Language::Es => format!(
+                "Costo de oportunidad: {cost} ({gap}% de retorno), tracking error {tracking}%, \
+                 caja extra promedio {cash}%, drift promedio {drift}%"
+            ),
+            Language::En => format!(
+                "Opportunity cost: {cost} ({gap}% return), tracking error {tracking}%, \
+                 average excess cash {cash}%, average drift {drift}%"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for DriftCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// Cuanto costo tener caja sin invertir (la que deja la estrategia conservadora, los dividendos
+/// y los aportes que esperan el proximo rebalanceo) frente a haberla tenido invertida.
+///
+/// El contrafactual es el mismo portafolio con la caja de cada cierre repartida entre los
+/// holdings en las mismas proporciones: en cada periodo esa caja habria rendido lo que rindio la
+/// parte invertida (solo por precio; los dividendos que habria pagado no se cuentan).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CashDrag {
+    /// Caja en % del portafolio, promediada entre dias.
+    pub average_cash: Decimal,
+
+    /// Lo que habria ganado (o perdido, si es negativo) la caja de cada cierre al dia siguiente.
+    pub daily: Vec<(Date, Decimal)>,
+
+    /// Retorno total del backtest (ver `BacktestResult::total_return`) y el del contrafactual.
+    pub actual_return: Option<Decimal>,
+    pub invested_return: Option<Decimal>,
+}
+
+impl CashDrag {
+    /// Costo total en plata; positivo si la caja le resto al portafolio.
+    pub fn cost(&self) -> Decimal {
+        self.daily.iter().map(|(_, cost)| *cost).sum()
+    }
+
+    /// Retorno que se perdio por la caja: contrafactual menos real.
+    pub fn return_drag(&self) -> Option<Decimal> {
+        Some(self.invested_return? - self.actual_return?)
+    }
+}
+
+/// Pares ticker-valor de un dia (pesos o precios).
+type Quotes<'a> = &'a [(String, Decimal)];
+
+/// Calcula la `CashDrag` de un backtest con los precios de `market`, que deben ser los mismos
+/// con que se corrio.
+pub fn cash_drag(result: &BacktestResult, market: &MarketData) -> CashDrag {
+    let growth = result.growth_curve();
+    let mut daily = Vec::new();
+    let mut invested_growth = Decimal::ONE;
+    let mut cash_total = Decimal::ZERO;
+
+    let steps = result
+        .equity_curve
+        .iter()
+        .zip(&result.weights)
+        .zip(&market.days)
+        .zip(&growth);
+    // valor, pesos, precios y crecimiento del cierre anterior
+    let mut previous: Option<(Decimal, Quotes<'_>, Quotes<'_>, Decimal)> = None;
+    for ((((date, value), (_, weights)), (_, prices)), (_, grown)) in steps {
+        let invested: Decimal = weights.iter().map(|(_, w)| *w).sum();
+        cash_total += Decimal::ONE_HUNDRED - invested;
+
+        if let Some((previous_value, previous_weights, previous_prices, previous_grown)) = previous
+        {
+            let price = |prices: &[(String, Decimal)], ticker: &str| {
+                prices.iter().find(|(t, _)| t == ticker).map(|(_, p)| *p)
+            };
+            let previous_invested: Decimal = previous_weights.iter().map(|(_, w)| *w).sum();
+            // retorno de la parte invertida: promedio de los retornos ponderado por peso
+            let invested_return = if previous_invested.is_zero() {
+                Decimal::ZERO
+            } else {
+                previous_weights
+                    .iter()
+                    .filter_map(|(ticker, weight)| {
+                        let from = price(previous_prices, ticker).filter(|p| !p.is_zero())?;
+                        let to = price(prices, ticker).unwrap_or(from);
+                        Some(weight * (to / from - Decimal::ONE))
+                    })
+                    .sum::<Decimal>()
+                    / previous_invested
+            };
+            let cash_share = (Decimal::ONE_HUNDRED - previous_invested) / Decimal::ONE_HUNDRED;
+            daily.push((*date, previous_value * cash_share * invested_return));
+
+            let actual_return = if previous_grown.is_zero() {
+                Decimal::ZERO
+            } else {
+                grown / previous_grown - Decimal::ONE
+            };
+            invested_growth *= Decimal::ONE + actual_return + cash_share * invested_return;
+        }
+        previous = Some((*value, weights, prices, *grown));
+    }
+
+    let days = Decimal::from(result.weights.len().max(1));
+    let actual_return = result.total_return();
+    CashDrag {
+        average_cash: cash_total / days,
+        daily,
+        invested_return: actual_return.map(|_| invested_growth - Decimal::ONE),
+        actual_return,
+    }
+}
+
+impl Localize for CashDrag {
+    fn localize(&self, language: Language) -> String {
+        let drag = self.return_drag().map_or("-".into(), |v| {
+            (v * Decimal::ONE_HUNDRED).round_dp(2).to_string()
+        });
+        let cost = self.cost().round_dp(2);
+        let cash = self.average_cash.round_dp(2);
+
+        match language {
+            Language::Es => format!(
+                "Costo de la caja sin invertir: {cost} ({drag}% de retorno), caja promedio {cash}%"
+            ),
+            Language::En => {
+                format!("Cost of uninvested cash: {cost} ({drag}% return), average cash {cash}%")
+            }
+        }
+    }
+}
+
+impl fmt::Display for CashDrag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// Parametros del remuestreo de `compare_significance`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BootstrapConfig {
+    /// Cuantas historias alternativas se generan.
+    pub resamples: usize,
+
+    /// Largo (en dias) de cada bloque de retornos que se copia junto, para conservar algo de la
+    /// autocorrelacion de los precios; 1 es el bootstrap simple.
+    pub block: usize,
+
+    /// Nivel de confianza del intervalo (p. ej. `0.95`).
+    pub confidence: f64,
+
+    pub seed: u64,
+}
+
+impl Default for BootstrapConfig {
+    fn default() -> Self {
+        Self {
+            resamples: 500,
+            block: 5,
+            confidence: 0.95,
+            seed: 0,
+        }
+    }
+}
+
+impl MarketData {
+    /// Una historia alternativa con las mismas fechas y precios iniciales, armada con bloques de
+    /// `block` dias de retornos diarios sacados al azar (con reposicion) de esta historia. Los
+    /// retornos de todos los tickers de un dia se copian juntos, asi que se mantiene su
+    /// correlacion. Los dividendos quedan en sus fechas; las barras no se copian.
+    pub fn resample<R: Rng>(&self, rng: &mut R, block: usize) -> MarketData {
+        let block = block.max(1);
+        // retorno de cada ticker entre cada dia y el anterior
+        let returns: Vec<Vec<(&str, Decimal)>> = self
+            .days
+            .windows(2)
+            .map(|pair| {
+                let (_, from) = &pair[0];
+                let (_, to) = &pair[1];
+                to.iter()
+                    .filter_map(|(ticker, price)| {
+                        let (_, previous) = from.iter().find(|(t, _)| t == ticker)?;
+                        (!previous.is_zero()).then(|| (ticker.as_str(), price / previous))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let Some((first, prices)) = self.days.first() else {
+            return self.clone();
+        };
+        let mut days = vec![(*first, prices.clone())];
+        let mut start = 0;
+        for (index, (date, _)) in self.days.iter().enumerate().skip(1) {
+            if (index - 1) % block == 0 {
+                start = rng.next_below(returns.len());
+            }
+            let day = &returns[(start + (index - 1) % block) % returns.len()];
+            let (_, previous) = days.last().unwrap();
+            let prices = previous
+                .iter()
+                .map(|(ticker, price)| {
+                    let growth = day
+                        .iter()
+                        .find(|(t, _)| t == ticker)
+                        .map_or(Decimal::ONE, |(_, g)| *g);
+                    (ticker.clone(), (price * growth).round_dp(6))
+                })
+                .collect();
+            days.push((*date, prices));
+        }
+
+        MarketData {
+            days,
+            dividends: self.dividends.clone(),
+            bars: PriceHistory::default(),
+        }
+    }
+}
+
+/// Distribucion de la diferencia de una medida entre dos estrategias (A menos B) sobre las
+/// historias remuestreadas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// Diferencia en la historia real.
+    pub observed: f64,
+
+    /// Promedio sobre las historias remuestreadas.
+    pub mean: f64,
+
+    /// Intervalo de confianza por percentiles.
+    pub low: f64,
+    pub high: f64,
+
+    /// Fraccion de historias en que la diferencia tuvo el signo contrario al promedio, por dos
+    /// (un valor p bilateral aproximado); mientras mas chico, menos probable que sea suerte.
+    pub p_value: f64,
+}
+
+impl Difference {
+    fn new(observed: f64, samples: &[f64], confidence: f64) -> Self {
+        let tail = (1.0 - confidence) / 2.0;
+        let mean = samples.iter().sum::<f64>() / samples.len().max(1) as f64;
+        let opposite = samples
+            .iter()
+            .filter(|v| if mean >= 0.0 { **v <= 0.0 } else { **v >= 0.0 })
+            .count();
+
+        Self {
+            observed,
+            mean,
+            low: percentile(samples, tail).unwrap_or(observed),
+            high: percentile(samples, 1.0 - tail).unwrap_or(observed),
+            p_value: (2.0 * opposite as f64 / samples.len().max(1) as f64).min(1.0),
+        }
+    }
+
+    /// Si el intervalo de confianza no incluye el cero.
+    pub fn is_significant(&self) -> bool {
+        self.low > 0.0 || self.high < 0.0
+    }
+}
+
+/// Resultado de `compare_significance`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyComparison {
+    pub config: BootstrapConfig,
+
+    /// Retorno total de A menos el de B.
+    pub returns: Difference,
+
+    /// Maxima caida de A menos la de B (positivo si A cae mas).
+    pub drawdowns: Difference,
+}
+
+/// ¿La diferencia entre dos estrategias es real o suerte de esta historia en particular? Corre
+/// ambas sobre los precios de `market` y sobre `config.resamples` historias remuestreadas (ver
+/// `MarketData::resample`), y resume la diferencia de retorno total y de maxima caida con un
+/// intervalo de confianza en vez de un solo numero.
+pub fn compare_significance(
+    portfolio: &Portfolio,
+    market: &MarketData,
+    a: &BacktestConfig,
+    b: &BacktestConfig,
+    config: &BootstrapConfig,
+) -> StrategyComparison {
+    let differences = |market: &MarketData| {
+        let [a, b] = [a, b].map(|config| {
+            let result = run(portfolio.clone(), market, config);
+            let total_return = result
+                .total_return()
+                .and_then(|r| r.to_f64())
+                .unwrap_or(0.0);
+            let values: Vec<f64> = result
+                .growth_curve()
+                .iter()
+                .filter_map(|(_, v)| v.to_f64())
+                .collect();
+            (total_return, max_drawdown(&values))
+        });
+        (a.0 - b.0, a.1 - b.1)
+    };
+
+    let observed = differences(market);
+    let mut rng = SeededRng::from_seed(config.seed);
+    let (returns, drawdowns): (Vec<f64>, Vec<f64>) = (0..config.resamples)
+        .map(|_| differences(&market.resample(&mut rng, config.block)))
+        .unzip();
+
+    StrategyComparison {
+        config: *config,
+        returns: Difference::new(observed.0, &returns, config.confidence),
+        drawdowns: Difference::new(observed.1, &drawdowns, config.confidence),
+    }
+}
+
+impl Difference {
+    fn localize_as(&self, name: &str, language: Language) -> String {
+        let pct = |v: f64| format!("{:.2}%", v * 100.0);
+        let (observed, low, high) = (pct(self.observed), pct(self.low), pct(self.high));
+        let p = format!("{:.3}", self.p_value);
+
+        match (language, self.is_significant()) {
+            (Language::Es, significant) => format!(
+                "{name}: {observed} (intervalo {low} a {high}, p = {p}){}",
+                if significant {
+                    ", significativa"
+                } else {
+                    ", no significativa"
+                }
+            ),
+            (Language::En, significant) => format!(
+                "{name}: {observed} (interval {low} to {high}, p = {p}){}",
+                if significant {
+                    ", significant"
+                } else {
+                    ", not significant"
+                }
+            ),
+        }
+    }
+}
+
+impl Localize for StrategyComparison {
+    fn localize(&self, language: Language) -> String {
+        let confidence = format!("{:.0}%", self.config.confidence * 100.0);
+        let resamples = self.config.resamples;
+        let (title, returns, drawdowns) = match language {
+            Language::Es => (
+                format!("Diferencia A - B en {resamples} historias remuestreadas ({confidence})"),
+                "Retorno total",
+                "Maxima caida",
+            ),
+            Language::En => (
+                format!("Difference A - B over {resamples} resampled histories ({confidence})"),
+                "Total return",
+                "Max drawdown",
+            ),
+        };
+
+        format!(
+            "{title}\n{}\n{}",
+            self.returns.localize_as(returns, language),
+            self.drawdowns.localize_as(drawdowns, language)
+        )
+    }
+}
+
+impl fmt::Display for StrategyComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// Caja sin liquidar y compras que esperan liquidacion durante un backtest.
+#[derive(Debug, Default)]
+struct Settling {
+    /// Plata de ventas y el dia en que se liquida.
+    unsettled: Vec<(Date, Decimal)>,
+
+    /// Compras que esperan a que se liquiden las ventas, con el dia desde el que se pueden hacer.
+    deferred: Vec<(Date, Order)>,
+
+    /// Todo lo ejecutado en los rebalanceos.
+    journal: Journal,
+}
+
+impl Settling {
+    /// Como `execute`, pero dejando las ordenes de fondos para el dia siguiente, al proximo valor
+    /// cuota.
+    fn submit(
+        &mut self,
+        portfolio: &mut Portfolio,
+        orders: Vec<Order>,
+        date: Date,
+        config: &BacktestConfig,
+    ) -> (usize, Decimal) {
+        let (funds, orders): (Vec<Order>, Vec<Order>) = orders
+            .into_iter()
+            .partition(|order| portfolio.priced(&order.ticker).is_some_and(|s| s.is_fund()));
+        self.deferred
+            .extend(funds.into_iter().map(|order| (date.add_days(1), order)));
+        self.execute(portfolio, &orders, date, config)
+    }
+
+    /// Ejecuta las ordenes una a una (ignorando las que fallan) respetando la liquidacion, y
+    /// devuelve cuantas operaciones se hicieron y el monto transado.
+    fn execute(
+        &mut self,
+        portfolio: &mut Portfolio,
+        orders: &[Order],
+        date: Date,
+        config: &BacktestConfig,
+    ) -> (usize, Decimal) {
+        let (mut trades, mut traded) = (0, Decimal::ZERO);
+        for order in orders {
+            let pending = self.unsettled.iter().map(|(_, amount)| *amount).sum();
+            let Ok(result) = portfolio.apply_settled(
+                std::slice::from_ref(order),
+                date,
+                &config.costs,
+                &config.settlement,
+                pending,
+            ) else {
+                continue;
+            };
+
+            result.execution.record_in(&mut self.journal);
+            for transaction in &result.execution.transactions {
+                if matches!(
+                    transaction.kind,
+                    TransactionKind::Buy | TransactionKind::Sell
+                ) {
+                    trades += 1;
+                    traded += transaction.units * transaction.price;
+                }
+            }
+            if !result.unsettled.is_zero() {
+                self.unsettled.push((result.settles_on, result.unsettled));
+            }
+            self.deferred.extend(
+                result
+                    .deferred
+                    .into_iter()
+                    .map(|order| (result.settles_on, order)),
+            );
+        }
+        (trades, traded)
+    }
+}
+
+/// Observaciones por año de una curva, a partir de sus fechas; `None` con menos de tres dias.
+fn periods_per_year(curve: &[(Date, Decimal)]) -> Option<f64> {
+    let (first, _) = curve.first()?;
+    let (last, _) = curve.last()?;
+    let years = first.years_until(*last);
+    (curve.len() >= 3 && years > 0.0).then(|| (curve.len() - 1) as f64 / years)
+}
+
+/// Corre el backtest.
+///
+/// Una orden que falla (p. ej. un precio que no llego ese dia para un ticker nuevo) se ignora:
+/// en un backtest preferimos seguir adelante y que el resultado lo refleje.
+pub fn run(portfolio: Portfolio, market: &MarketData, config: &BacktestConfig) -> BacktestResult {
+    match run_with(portfolio, market, config, &mut Monitor::default()) {
+        Ok(result) => result,
+        Err(_) => unreachable!("un monitor sin token nunca cancela"),
+    }
+}
+
+/// Igual que `run`, reportando el avance dia a dia a `monitor` y deteniendose si se cancela.
+pub fn run_with(
+    mut portfolio: Portfolio,
+    market: &MarketData,
+    config: &BacktestConfig,
+    monitor: &mut Monitor,
+) -> Result<BacktestResult, Cancelled> {
+    let mut equity_curve = Vec::new();
+    let mut dividends = Decimal::ZERO;
+    let mut rebalances = 0;
+    let mut rebalance_dates = Vec::new();
+    let mut weights = Vec::new();
+    let mut trades = 0;
+    let mut traded = Decimal::ZERO;
+    let mut settling = Settling::default();
+    let mut last_rebalance: Option<Date> = None;
+    let mut contributions = Vec::new();
+    let mut rounding = RoundingLedger::default();
+    let mut last_contribution: Option<Date> = None;
+    let total = market.days.len();
+
+    for (index, (date, prices)) in market.days.iter().enumerate() {
+        monitor.check(index, total)?;
+
+        for (ticker, price) in prices {
+            portfolio.update_price(ticker, *price);
+        }
+
+        for dividend in market.dividends_on(*date) {
+            let held = portfolio
+                .stocks()
+                .iter()
+                .filter(|s| s.name() == dividend.ticker)
+                .count();
+            let amount = dividend.per_unit * Decimal::from(held);
+            dividends += amount;
+            portfolio.cash += amount;
+
+            if config.drip {
+                let units = portfolio
+                    .priced(&dividend.ticker)
+                    .map(|s| s.current_price())
+                    .filter(|price| !price.is_zero())
+                    .and_then(|price| (amount / price).trunc().to_usize())
+                    .unwrap_or(0);
+                if units > 0
+                    && let Ok(execution) = portfolio.apply(
+                        &[Order::buy(&dividend.ticker, units)],
+                        *date,
+                        &config.costs,
+                    )
+                {
+                    execution.record_in(&mut settling.journal);
+                }
+            }
+        }
+
+        let mut contributed = false;
+        if let Some(schedule) = config.contributions {
+            match last_contribution {
+                None => last_contribution = Some(*date),
+                Some(last) if last.months_until(*date) >= schedule.every_months => {
+                    last_contribution = Some(*date);
+                    portfolio.cash += schedule.amount;
+                    contributions.push((*date, schedule.amount));
+                    contributed = true;
+                }
+                Some(_) => {}
+            }
+        }
+
+        let due = match (config.schedule, last_rebalance) {
+            (_, None) => true,
+            (RebalanceSchedule::Never, Some(_)) => false,
+            (RebalanceSchedule::EveryMonths(n), Some(last)) => last.months_until(*date) >= n,
+            (RebalanceSchedule::Band(band), Some(_)) => portfolio.needs_rebalance(band),
+        };
+        settling
+            .unsettled
+            .retain(|(settles_on, _)| settles_on > date);
+        if !due {
+            let mut ready: Vec<Order> = settling
+                .deferred
+                .extract_if(.., |(settles_on, _)| *settles_on <= *date)
+                .map(|(_, order)| order)
+                .collect();
+            // las ventas de fondos que quedaron pendientes financian las compras
+            ready.sort_by_key(|order| order.side != Side::Sell);
+            let (count, amount) = settling.execute(&mut portfolio, &ready, *date, config);
+            trades += count;
+            traded += amount;
+
+            if contributed {
+                let orders = portfolio.contribution_suggestion().orders();
+                let (count, amount) = settling.submit(&mut portfolio, orders, *date, config);
+                trades += count;
+                traded += amount;
+            }
+        }
+        if due {
+            last_rebalance = Some(*date);
+            let fills: Vec<(&str, Decimal)> = market
+                .bars
+                .tickers()
+                .filter_map(|t| Some((t, market.bars.bar(t, *date)?.price(config.fill_price))))
+                .collect();
+            for (ticker, price) in &fills {
+                portfolio.update_price(ticker, *price);
+            }
+
+            let orders = portfolio.rebalance_portfolio().orders();
+            if !orders.is_empty() {
+                rounding.record(*date, portfolio.rounding_loss());
+                rebalances += 1;
+                rebalance_dates.push(*date);
+                // lo que quedaba pendiente del rebalanceo anterior lo reemplaza este
+                settling.deferred.clear();
+                let (count, amount) = settling.submit(&mut portfolio, orders, *date, config);
+                trades += count;
+                traded += amount;
+            }
+
+            for (ticker, price) in prices {
+                portfolio.update_price(ticker, *price);
+            }
+        }
+
+        let value = portfolio.total_value();
+        equity_curve.push((*date, value));
+        let day_weights = portfolio
+            .weights()
+            .into_iter()
+            .map(|(ticker, weight)| (ticker.to_string(), weight))
+            .collect();
+        weights.push((*date, day_weights));
+        monitor.report(index + 1, total);
+    }
+
+    Ok(BacktestResult {
+        equity_curve,
+        dividends,
+        rebalances,
+        rebalance_dates,
+        trades,
+        traded,
+        weights,
+        rounding,
+        contributions,
+        journal: settling.journal,
+        portfolio,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn d(month: u32, day: u32) -> Date {
+        Date::new(2024, month, day).unwrap()
+    }
+
+    fn portfolio() -> Portfolio {
+        Portfolio {
+            cash: dec!(1000),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: PortfolioTarget::new(Stock::new("KO", dec!(10))),
+        }
+    }
+
+    fn market() -> MarketData {
+        MarketData::new()
+            .with_prices(d(1, 2), &[("KO", dec!(10))])
+            .with_prices(d(3, 1), &[("KO", dec!(10))])
+            .with_dividend(d(3, 1), "KO", dec!(0.5))
+            .with_prices(d(6, 3), &[("KO", dec!(12))])
+    }
+
+    #[test]
+    fn test_drip_reinvests_dividends() {
+        let config = BacktestConfig::default().with_schedule(RebalanceSchedule::Never);
+
+        let results = compare(
+            &portfolio(),
+            &market(),
+            &[config.clone(), config.with_drip(true)],
+        );
+        let (cash, drip) = (&results[0], &results[1]);
+
+        // 100 KO pagan 50; con DRIP se compran 5 KO mas
+        assert_eq!(cash.dividends, dec!(50));
+        assert_eq!(cash.portfolio.stocks().len(), 100);
+        assert_eq!(drip.portfolio.stocks().len(), 105);
+
+        assert_eq!(cash.equity_curve.last().unwrap().1, dec!(1250));
+        assert_eq!(drip.equity_curve.last().unwrap().1, dec!(1260));
+        assert!(drip.total_return() > cash.total_return());
+    }
+
+    #[test]
+    fn test_monthly_contributions_buy_without_rebalancing() {
+        let market = MarketData::new()
+            .with_prices(d(1, 2), &[("KO", dec!(10))])
+            .with_prices(d(2, 2), &[("KO", dec!(10))])
+            .with_prices(d(3, 4), &[("KO", dec!(12))]);
+        let config = BacktestConfig::default()
+            .with_schedule(RebalanceSchedule::Never)
+            .with_contributions(ContributionSchedule::monthly(dec!(100)));
+
+        // 100 KO el primer dia, 10 con el aporte de febrero y 8 (a 12) con el de marzo
+        let result = run(portfolio(), &market, &config);
+        assert_eq!(result.contributed(), dec!(200));
+        assert_eq!(result.portfolio.stocks().len(), 118);
+        assert_eq!(result.rebalances, 1);
+        assert_eq!(result.rounding.total_unspent(), dec!(0));
+        assert_eq!(result.trades, 3);
+        assert_eq!(result.equity_curve.last().unwrap().1, dec!(1420));
+        assert_eq!(result.gain(), Some(dec!(220)));
+        // el precio subio 20% y eso es lo que rindio, no lo aportado
+        assert_eq!(result.total_return(), Some(dec!(0.2)));
+    }
+
+    #[test]
+    fn test_cancelled_backtest_stops_early() {
+        use crate::progress::CancellationToken;
+
+        let token = CancellationToken::new();
+        let mut days = Vec::new();
+        let mut monitor = Monitor::new()
+            .with_cancellation(token.clone())
+            .with_callback(|p| {
+                days.push(p.done);
+                if p.done == 2 {
+                    token.cancel();
+                }
+            });
+
+        let error = run_with(
+            portfolio(),
+            &market(),
+            &BacktestConfig::default(),
+            &mut monitor,
+        )
+        .unwrap_err();
+        drop(monitor);
+
+        assert_eq!(days, vec![1, 2]);
+        assert_eq!(error.progress.done, 2);
+    }
+
+    #[test]
+    fn test_fills_at_open_from_bars() {
+        use crate::prices::Bar;
+
+        let history = PriceHistory::new()
+            .with_bar(
+                "KO",
+                d(1, 2),
+                Bar::new(dec!(8), dec!(10), dec!(8), dec!(10)),
+            )
+            .with_bar("KO", d(1, 3), Bar::flat(dec!(10)));
+        let market = MarketData::from_history(&history);
+        let config = BacktestConfig::default()
+            .with_schedule(RebalanceSchedule::Never)
+            .with_fill_price(FillPrice::Open);
+
+        // se compra a 8 (125 KO) pero se valoriza al cierre de 10
+        let result = run(portfolio(), &market, &config);
+        assert_eq!(result.portfolio.stocks().len(), 125);
+        assert_eq!(result.equity_curve[0].1, dec!(1250));
+    }
+
+    #[test]
+    fn test_frequency_study_with_bands() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("A", dec!(10))),
+            (dec!(50), Stock::new("B", dec!(10))),
+        ])
+        .unwrap();
+        let portfolio = Portfolio {
+            allocation: target,
+            ..portfolio()
+        };
+        let market = MarketData::new()
+            .with_prices(d(1, 2), &[("A", dec!(10)), ("B", dec!(10))])
+            .with_prices(d(2, 2), &[("A", dec!(11)), ("B", dec!(10))])
+            .with_prices(d(3, 4), &[("A", dec!(12.1)), ("B", dec!(10))])
+            .with_prices(d(4, 2), &[("A", dec!(14.6)), ("B", dec!(10))]);
+
+        let study = frequency_study(&portfolio, &market, &BacktestConfig::default());
+        let trades: Vec<usize> = study.rows.iter().map(|r| r.trades).collect();
+        // con bandas de 5 puntos solo se rebalancea en abril, cuando A llega a pesar 59%
+        assert_eq!(trades, vec![6, 4, 2, 4]);
+        assert!(study.rows[2].turnover < study.rows[0].turnover);
+        assert!(study.rows[0].volatility.is_some());
+        assert!(
+            study
+                .localize(Language::En)
+                .starts_with("schedule | return % | volatility % | turnover | trades\nmonthly |")
+        );
+    }
+
+    #[test]
+    fn test_drift_cost_against_ideal() {
+        // con 1000 y KO a 30 sobran 10 de caja que el ideal si invierte
+        let portfolio = Portfolio {
+            allocation: PortfolioTarget::new(Stock::new("KO", dec!(30))),
+            ..portfolio()
+        };
+        let market = MarketData::new()
+            .with_prices(d(1, 2), &[("KO", dec!(30))])
+            .with_prices(d(2, 2), &[("KO", dec!(33))]);
+        let config = BacktestConfig::default().with_schedule(RebalanceSchedule::Never);
+
+        let cost = drift_cost(&portfolio, &market, &config);
+        assert_eq!(cost.ideal_curve[1].1, dec!(1100));
+        assert_eq!(cost.result.equity_curve[1].1, dec!(1099));
+        assert_eq!(cost.opportunity_cost(), dec!(1));
+        assert_eq!(cost.average_excess_cash.round_dp(4), dec!(0.9550));
+        assert_eq!(cost.average_drift, cost.average_excess_cash);
+        assert!(cost.return_gap().unwrap() > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_settlement_delays_buys() {
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            stocks: vec![Stock::new("GOOG", dec!(10)); 100],
+            ..portfolio()
+        };
+        // jueves 2 de mayo, y las ventas se liquidan el lunes 6
+        let market = MarketData::new()
+            .with_prices(
+                Date::new(2024, 5, 2).unwrap(),
+                &[("KO", dec!(10)), ("GOOG", dec!(10))],
+            )
+            .with_prices(
+                Date::new(2024, 5, 3).unwrap(),
+                &[("KO", dec!(11)), ("GOOG", dec!(10))],
+            )
+            .with_prices(
+                Date::new(2024, 5, 6).unwrap(),
+                &[("KO", dec!(12)), ("GOOG", dec!(10))],
+            );
+        let config = BacktestConfig::default()
+            .with_schedule(RebalanceSchedule::Never)
+            .with_settlement(Settlement::t_plus(2));
+
+        let result = run(portfolio, &market, &config);
+        // el viernes todo sigue en caja
+        assert!(result.weights[1].1.is_empty());
+        assert_eq!(result.trades, 2);
+        // la compra se hace el lunes a 12: 83 KO
+        assert_eq!(result.portfolio.stocks().len(), 83);
+        assert_eq!(result.equity_curve[2].1, dec!(1000));
+    }
+
+    #[test]
+    fn test_fund_orders_fill_at_next_nav() {
+        let portfolio = Portfolio {
+            allocation: PortfolioTarget::new(Stock::fund("RISKY", dec!(10), 18 * 60)),
+            ..portfolio()
+        };
+        let market = MarketData::new()
+            .with_prices(d(1, 2), &[("RISKY", dec!(10))])
+            .with_prices(d(1, 3), &[("RISKY", dec!(12))])
+            .with_prices(d(1, 4), &[("RISKY", dec!(12))]);
+        let config = BacktestConfig::default().with_schedule(RebalanceSchedule::Never);
+
+        let result = run(portfolio, &market, &config);
+        // se piden 100 cuotas a 10, pero el valor cuota del dia siguiente es 12: 83
+        assert!(result.weights[0].1.is_empty());
+        assert_eq!(result.portfolio.stocks().len(), 83);
+        assert_eq!(result.trades, 1);
+        assert_eq!(result.equity_curve[2].1, dec!(1000));
+    }
+
+    #[test]
+    fn test_turnover_report_from_executed_trades() {
+        let mut portfolio = portfolio();
+        portfolio.allocation = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("KO", dec!(10))),
+            (dec!(50), Stock::new("PEP", dec!(10))),
+        ])
+        .unwrap();
+        let market = MarketData::new()
+            .with_prices(d(1, 2), &[("KO", dec!(10)), ("PEP", dec!(10))])
+            .with_prices(d(2, 2), &[("KO", dec!(15)), ("PEP", dec!(10))]);
+
+        let result = run(portfolio, &market, &BacktestConfig::default());
+        let report = result.turnover_report().unwrap();
+
+        // en febrero se venden 9 KO (comprados 31 dias antes) y se compran 12 PEP
+        let ko = &report.holdings[0];
+        assert_eq!((ko.sold_units, ko.sold_days), (dec!(9), Some(dec!(31))));
+        assert_eq!(report.holdings[1].held_units, dec!(62));
+        assert_eq!(report.sales, dec!(135));
+        // lo menor entre compras (1.120) y ventas sobre el valor promedio
+        assert_eq!(report.turnover(), Some(dec!(0.12)));
+    }
+
+    #[test]
+    fn test_cash_drag_of_uninvested_dividends() {
+        let config = BacktestConfig::default().with_schedule(RebalanceSchedule::Never);
+        let result = run(portfolio(), &market(), &config);
+        let drag = cash_drag(&result, &market());
+
+        // los 50 del dividendo de marzo quedan en caja mientras KO sube 20%
+        assert_eq!(drag.cost().round_dp(6), dec!(10));
+        assert_eq!(drag.actual_return, Some(dec!(0.25)));
+        // invertidos habrian ganado 10 sobre los 1.000 iniciales
+        assert_eq!(drag.return_drag().unwrap().round_dp(6), dec!(0.01));
+    }
+
+    #[test]
+    fn test_significance_of_identical_and_different_strategies() {
+        let market = (1..=28).fold(MarketData::new(), |market, day| {
+            // sube todos los dias, a veces mas y a veces menos
+            let price = dec!(10) + Decimal::from(2 * day + day % 2);
+            market.with_prices(d(1, day), &[("KO", price)])
+        });
+        let resampled = market.resample(&mut SeededRng::from_seed(1), 2);
+        assert_eq!(resampled.dates().count(), 28);
+        assert_eq!(resampled.days[0], market.days[0]);
+
+        let config = BootstrapConfig {
+            resamples: 50,
+            block: 2,
+            ..Default::default()
+        };
+        let free = BacktestConfig::default().with_schedule(RebalanceSchedule::Never);
+        let same = compare_significance(&portfolio(), &market, &free, &free, &config);
+        assert_eq!(same.returns.mean, 0.0);
+        assert!(!same.returns.is_significant());
+
+        // B paga la comision de la compra inicial el primer dia: parte valiendo menos con las
+        // mismas acciones, asi que en toda historia al alza rinde mas (sobre menos plata)
+        let costly = free
+            .clone()
+            .with_costs(CostModel::free().with_per_trade(dec!(10)));
+        let comparison = compare_significance(&portfolio(), &market, &free, &costly, &config);
+        assert!(comparison.returns.high < 0.0);
+        assert!(comparison.returns.is_significant());
+        assert_eq!(comparison.returns.p_value, 0.0);
+    }
+
+    #[test]
+    fn test_monthly_schedule_reinvests_cash_dividends() {
+        let result = run(portfolio(), &market(), &BacktestConfig::default());
+
+        // se rebalancea el primer dia y el 1 de marzo (la caja del dividendo compra 5 KO)
+        assert_eq!(result.rebalances, 2);
+        assert_eq!(result.portfolio.stocks().len(), 105);
+        assert_eq!(result.total_return(), Some(dec!(0.26)));
+    }
+}
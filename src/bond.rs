@@ -0,0 +1,145 @@
+//! Bonos: valor nominal, cupon y vencimiento.
+//!
+//! Un bono se cotiza "limpio", como % de su valor nominal (p. ej. 98.5), pero lo que se paga al
+//! comprarlo es el precio "sucio": el limpio mas el interes devengado desde el ultimo cupon. Para
+//! valorizar un portafolio lo que importa es el sucio, que es lo que devuelve
+//! `Stock::current_price` para un bono.
+
+use crate::date::Date;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Cuantos cupones paga el bono al año.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CouponFrequency {
+    Annual,
+    #[default]
+    SemiAnnual,
+    Quarterly,
+    Monthly,
+}
+
+impl CouponFrequency {
+    pub fn per_year(&self) -> u32 {
+        match self {
+            CouponFrequency::Annual => 1,
+            CouponFrequency::SemiAnnual => 2,
+            CouponFrequency::Quarterly => 4,
+            CouponFrequency::Monthly => 12,
+        }
+    }
+
+    fn months(&self) -> i32 {
+        12 / self.per_year() as i32
+    }
+}
+
+/// Condiciones de un bono con cupon fijo.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bond {
+    /// Valor nominal de una unidad.
+    pub face_value: Decimal,
+
+    /// Tasa cupon anual, como fraccion (`0.05` para un 5%).
+    pub coupon_rate: Decimal,
+
+    pub maturity: Date,
+
+    pub frequency: CouponFrequency,
+}
+
+impl Bond {
+    /// Bono con cupon semestral.
+    pub fn new(face_value: Decimal, coupon_rate: Decimal, maturity: Date) -> Self {
+        Self {
+            face_value,
+            coupon_rate,
+            maturity,
+            frequency: CouponFrequency::default(),
+        }
+    }
+
+    pub fn with_frequency(mut self, frequency: CouponFrequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Monto de cada cupon por unidad.
+    pub fn coupon_amount(&self) -> Decimal {
+        self.face_value * self.coupon_rate / Decimal::from(self.frequency.per_year())
+    }
+
+    /// Fechas de cupon anterior (o igual) y siguiente a `date`. El calendario se calcula hacia
+    /// atras desde el vencimiento, siempre a partir de este (y no del cupon anterior) para que
+    /// un vencimiento el 31 no vaya derivando a 30 y 28.
+    fn coupon_period(&self, date: Date) -> (Date, Date) {
+        let step = self.frequency.months();
+        let mut k = 1;
+        while self.maturity.add_months(-k * step) > date {
+            k += 1;
+        }
+
+        (
+            self.maturity.add_months(-k * step),
+            self.maturity.add_months(-(k - 1) * step),
+        )
+    }
+
+    /// Interes devengado por unidad a la fecha `date`, con la convencion Actual/Actual (la
+    /// fraccion del periodo de cupon transcurrida). Cero despues del vencimiento.
+    pub fn accrued_interest(&self, date: Date) -> Decimal {
+        if date >= self.maturity {
+            return Decimal::ZERO;
+        }
+
+        let (previous, next) = self.coupon_period(date);
+        let elapsed = Decimal::from(previous.days_until(date));
+        let length = Decimal::from(previous.days_until(next));
+
+        self.coupon_amount() * elapsed / length
+    }
+
+    /// Precio sucio por unidad a partir de la cotizacion limpia (en % del nominal).
+    pub fn dirty_price(&self, clean_quote: Decimal, date: Date) -> Decimal {
+        self.face_value * clean_quote / dec!(100) + self.accrued_interest(date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(year: i32, month: u32, day: u32) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_accrued_interest_halfway_through_period() {
+        let bond = Bond::new(dec!(1000), dec!(0.06), d(2030, 12, 31));
+
+        // periodo 2024-06-30 → 2024-12-31 (184 dias), cupon 30
+        assert_eq!(
+            bond.coupon_period(d(2024, 9, 30)),
+            (d(2024, 6, 30), d(2024, 12, 31))
+        );
+        assert_eq!(
+            bond.accrued_interest(d(2024, 9, 30)).round_dp(6),
+            (dec!(30) * dec!(92) / dec!(184)).round_dp(6)
+        );
+        assert_eq!(bond.accrued_interest(d(2024, 12, 31)), Decimal::ZERO);
+        assert_eq!(bond.accrued_interest(d(2031, 1, 1)), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_dirty_price_adds_accrued_interest() {
+        let bond = Bond::new(dec!(100), dec!(0.12), d(2026, 1, 1))
+            .with_frequency(CouponFrequency::Monthly);
+
+        // un mes de 31 dias, 10 dias devengados de un cupon de 1
+        let dirty = bond.dirty_price(dec!(98.5), d(2025, 3, 11));
+        assert_eq!(
+            dirty.round_dp(6),
+            (dec!(98.5) + dec!(10) / dec!(31)).round_dp(6)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Construccion de portafolios.
+
+use crate::error::PortfolioError;
+use crate::money::Currency;
+use crate::{Portfolio, PortfolioTarget, Stock};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use rust_decimal::Decimal;
+
+/// Arma un `Portfolio` paso a paso:
+///
+/// ```
+/// use fintual_coding_challenge::{Portfolio, PortfolioTarget, Stock};
+/// use rust_decimal_macros::dec;
+///
+/// let portfolio = Portfolio::builder()
+///     .with_cash(dec!(1000))
+///     .with_holding("META", 4, dec!(10))
+///     .with_target(PortfolioTarget::new(Stock::new("META", dec!(10))))
+///     .build()
+///     .unwrap();
+/// assert_eq!(portfolio.stocks().len(), 4);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PortfolioBuilder {
+    cash: Decimal,
+    foreign_cash: BTreeMap<Currency, Decimal>,
+    stocks: Vec<Stock>,
+    target: Option<PortfolioTarget>,
+}
+
+impl PortfolioBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Caja en la moneda base; se suma si se llama mas de una vez.
+    pub fn with_cash(mut self, cash: Decimal) -> Self {
+        self.cash += cash;
+        self
+    }
+
+    /// Saldo en otra moneda (ver `Portfolio::with_foreign_cash`).
+    pub fn with_foreign_cash(mut self, currency: Currency, amount: Decimal) -> Self {
+        *self.foreign_cash.entry(currency).or_default() += amount;
+        self
+    }
+
+    /// `units` unidades de una accion a `price` cada una.
+    pub fn with_holding(self, ticker: &str, units: usize, price: Decimal) -> Self {
+        self.with_stock(Stock::new(ticker, price), units)
+    }
+
+    /// `units` copias de `stock`, para holdings que no son acciones simples (bonos, otra moneda,
+    /// con costo de compra, etc.).
+    pub fn with_stock(mut self, stock: Stock, units: usize) -> Self {
+        self.stocks.extend(core::iter::repeat_n(stock, units));
+        self
+    }
+
+    pub fn with_target(mut self, target: PortfolioTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Exige un objetivo, caja no negativa y precios positivos.
+    pub fn build(self) -> Result<Portfolio, PortfolioError> {
+        let allocation = self.target.ok_or(PortfolioError::MissingTarget)?;
+        if self.cash < Decimal::ZERO {
+            return Err(PortfolioError::NegativeCash(self.cash));
+        }
+        if let Some((_, amount)) = self.foreign_cash.iter().find(|(_, a)| **a < Decimal::ZERO) {
+            return Err(PortfolioError::NegativeCash(*amount));
+        }
+        if let Some(stock) = self
+            .stocks
+            .iter()
+            .find(|s| s.current_price() <= Decimal::ZERO)
+        {
+            return Err(PortfolioError::NonPositivePrice(stock.name().into()));
+        }
+
+        Ok(Portfolio {
+            cash: self.cash,
+            foreign_cash: self.foreign_cash,
+            stocks: self.stocks,
+            allocation,
+        })
+    }
+}
+
+impl Portfolio {
+    pub fn builder() -> PortfolioBuilder {
+        PortfolioBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_build_validates() {
+        let target = PortfolioTarget::new(Stock::new("META", dec!(10)));
+
+        let portfolio = Portfolio::builder()
+            .with_cash(dec!(5))
+            .with_holding("META", 2, dec!(10))
+            .with_holding("AAPL", 1, dec!(20))
+            .with_foreign_cash(Currency::Eur, dec!(3))
+            .with_target(target.clone())
+            .build()
+            .unwrap();
+        assert_eq!(portfolio.total_value(), dec!(45));
+        assert_eq!(portfolio.foreign_cash()[&Currency::Eur], dec!(3));
+
+        assert_eq!(
+            Portfolio::builder().build().unwrap_err(),
+            PortfolioError::MissingTarget
+        );
+        assert_eq!(
+            Portfolio::builder()
+                .with_cash(dec!(-1))
+                .with_target(target.clone())
+                .build()
+                .unwrap_err(),
+            PortfolioError::NegativeCash(dec!(-1))
+        );
+        assert_eq!(
+            Portfolio::builder()
+                .with_holding("META", 1, Decimal::ZERO)
+                .with_target(target)
+                .build()
+                .unwrap_err(),
+            PortfolioError::NonPositivePrice("META".into())
+        );
+    }
+}
@@ -0,0 +1,469 @@
+//! Topes de peso por posicion.
+//!
+//! Una politica tipica es "ninguna accion sobre 10%". `WeightCaps` guarda un tope por defecto y
+//! topes propios de algunos tickers, y los hace cumplir en dos lugares:
+//!
+//! - al validar un objetivo (`WeightCaps::apply`): cada stock sobre su tope queda en el tope y lo
+//!   que sobra se reparte entre los demas stocks en proporcion a sus pesos, repitiendo hasta que
+//!   ninguno se pase;
+//! - al rebalancear, como etapa del pipeline: lo mismo con las unidades objetivo a los precios
+//!   actuales, para objetivos que no se validaron con estos topes.
+//!
+//! El peso del efectivo no se toca. Si todos los stocks quedan topados y todavia sobra peso, el
+//! objetivo no es factible.
+//!
+//! `PositionLimits` es el caso contrario: evita posiciones demasiado chicas (un 0,3% que solo
+//! agrega comisiones) y demasiadas posiciones. Las que no cumplen salen del objetivo y su peso
+//! se consolida en las que quedan, tambien en proporcion; el `PositionReport` dice cuales salieron
+//! y por que.
+
+use crate::error::TargetError;
+use crate::execution::Order;
+use crate::i18n::{Language, Localize, language};
+use crate::pipeline::{Plan, RebalanceStage};
+use crate::{Portfolio, PortfolioTarget};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Topes de peso, en % del portafolio.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WeightCaps {
+    default: Option<Decimal>,
+    tickers: BTreeMap<String, Decimal>,
+}
+
+impl WeightCaps {
+    /// Sin topes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// El mismo tope para todos los tickers sin tope propio.
+    pub fn uniform(cap: Decimal) -> Self {
+        Self {
+            default: Some(cap),
+            tickers: BTreeMap::new(),
+        }
+    }
+
+    /// Tope propio de `ticker`, que reemplaza al tope por defecto.
+    pub fn with_cap(mut self, ticker: &str, cap: Decimal) -> Self {
+        self.tickers.insert(ticker.into(), cap);
+        self
+    }
+
+    /// El tope que aplica a `ticker`, si hay.
+    pub fn cap(&self, ticker: &str) -> Option<Decimal> {
+        self.tickers.get(ticker).copied().or(self.default)
+    }
+
+    /// `target` con los topes aplicados y el exceso repartido. Falla si los topes no dejan
+    /// lugar para todo el peso de los stocks.
+    pub fn apply(&self, target: &PortfolioTarget) -> Result<PortfolioTarget, TargetError> {
+        let mut weights: Vec<(&str, Decimal)> = target
+            .targets()
+            .iter()
+            .map(|(weight, stock)| (stock.name(), *weight))
+            .collect();
+        let excess = redistribute(&mut weights, |ticker| self.cap(ticker));
+        if excess > Decimal::ZERO {
+            return Err(TargetError::InfeasibleCaps(excess));
+        }
+
+        Ok(reweighted(target, &weights, |ticker, weight| {
+            self.cap(ticker).is_none_or(|cap| weight < cap)
+        }))
+    }
+
+    /// Falla si algun stock de `target` esta sobre su tope, sin corregir nada.
+    pub fn check(&self, target: &PortfolioTarget) -> Result<(), TargetError> {
+        let excess: Decimal = target
+            .targets()
+            .iter()
+            .filter_map(|(weight, stock)| Some(*weight - self.cap(stock.name())?))
+            .filter(|over| *over > Decimal::ZERO)
+            .sum();
+        if excess > Decimal::ZERO {
+            return Err(TargetError::InfeasibleCaps(excess));
+        }
+        Ok(())
+    }
+}
+
+/// `target` con los pesos de `weights` (los stocks que no aparecen salen), redondeados a 6
+/// decimales; lo que falta para el total original va al stock mas grande que `can_grow` acepte.
+fn reweighted(
+    target: &PortfolioTarget,
+    weights: &[(&str, Decimal)],
+    can_grow: impl Fn(&str, Decimal) -> bool,
+) -> PortfolioTarget {
+    let total: Decimal = target.targets().iter().map(|(w, _)| *w).sum();
+    let mut rounded: Vec<(&str, Decimal)> =
+        weights.iter().map(|(t, w)| (*t, w.round_dp(6))).collect();
+    let residual = total - rounded.iter().map(|(_, w)| *w).sum::<Decimal>();
+    if let Some((_, weight)) = rounded
+        .iter_mut()
+        .filter(|(ticker, weight)| can_grow(ticker, *weight))
+        .max_by_key(|(_, weight)| *weight)
+    {
+        *weight += residual;
+    }
+
+    let mut adjusted = target.clone();
+    adjusted
+        .targets
+        .retain(|(_, stock)| rounded.iter().any(|(t, _)| *t == stock.name()));
+    for (weight, stock) in adjusted.targets.iter_mut() {
+        if let Some((_, new)) = rounded.iter().find(|(t, _)| *t == stock.name()) {
+            *weight = *new;
+        }
+    }
+    adjusted
+}
+
+/// Deja cada monto en su tope y reparte lo que sobra entre los que no estan topados, en
+/// proporcion a sus montos, hasta que ninguno se pase. Devuelve lo que no se pudo repartir.
+fn redistribute(amounts: &mut [(&str, Decimal)], cap: impl Fn(&str) -> Option<Decimal>) -> Decimal {
+    let mut capped = alloc::vec![false; amounts.len()];
+    loop {
+        let mut excess = Decimal::ZERO;
+        for (index, (ticker, amount)) in amounts.iter_mut().enumerate() {
+            if let Some(cap) = cap(ticker).filter(|cap| *amount > *cap) {
+                excess += *amount - cap;
+                *amount = cap;
+                capped[index] = true;
+            }
+        }
+        if excess.is_zero() {
+            return Decimal::ZERO;
+        }
+
+        let free: Decimal = amounts
+            .iter()
+            .zip(&capped)
+            .filter(|(_, capped)| !**capped)
+            .map(|((_, amount), _)| *amount)
+            .sum();
+        if free <= Decimal::ZERO {
+            return excess;
+        }
+        for ((_, amount), _) in amounts.iter_mut().zip(&capped).filter(|(_, c)| !**c) {
+            *amount += excess * *amount / free;
+        }
+    }
+}
+
+/// Durante el rebalanceo, aplica los topes a las unidades objetivo con los precios actuales.
+/// Va antes de redondear.
+impl RebalanceStage for WeightCaps {
+    fn name(&self) -> &str {
+        "caps"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        if plan.total.is_zero() {
+            return;
+        }
+
+        let price = |ticker: &str| portfolio.priced(ticker).map(|s| s.current_price());
+        let mut values: Vec<(&'a str, Decimal)> = plan
+            .targets
+            .iter()
+            .filter_map(|(ticker, units)| Some((*ticker, *units * price(ticker)?)))
+            .collect();
+        // lo que no se puede repartir queda en caja
+        redistribute(&mut values, |ticker| {
+            Some(plan.total * self.cap(ticker)? / Decimal::ONE_HUNDRED)
+        });
+
+        for (ticker, value) in values {
+            if let Some(price) = price(ticker).filter(|p| !p.is_zero()) {
+                plan.targets.insert(ticker, value / price);
+            }
+        }
+    }
+}
+
+/// Por que salio una posicion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// Pesaba menos que el minimo.
+    BelowMinimum,
+
+    /// Habia mas posiciones que el maximo y era de las mas chicas.
+    TooManyHoldings,
+}
+
+/// Una posicion que salio del objetivo, con el peso (en %) que tenia.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedPosition {
+    pub ticker: String,
+    pub weight: Decimal,
+    pub reason: DropReason,
+}
+
+/// Lo que cambio `PositionLimits`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PositionReport {
+    pub dropped: Vec<DroppedPosition>,
+}
+
+impl PositionReport {
+    pub fn is_empty(&self) -> bool {
+        self.dropped.is_empty()
+    }
+
+    /// Peso total consolidado en las posiciones que quedaron.
+    pub fn consolidated(&self) -> Decimal {
+        self.dropped.iter().map(|d| d.weight).sum()
+    }
+}
+
+/// Peso minimo por posicion y cantidad maxima de posiciones. La posicion mas grande nunca sale.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PositionLimits {
+    /// En % del portafolio.
+    pub min_weight: Option<Decimal>,
+    pub max_holdings: Option<usize>,
+}
+
+impl PositionLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_min_weight(mut self, min_weight: Decimal) -> Self {
+        self.min_weight = Some(min_weight);
+        self
+    }
+
+    pub fn with_max_holdings(mut self, max_holdings: usize) -> Self {
+        self.max_holdings = Some(max_holdings.max(1));
+        self
+    }
+
+    /// Saca de `amounts` (pesos o montos que suman `total`) lo que no cumple, de menor a mayor,
+    /// y reparte su peso entre lo que queda en proporcion.
+    fn limit(&self, amounts: &mut Vec<(&str, Decimal)>, total: Decimal) -> Vec<DroppedPosition> {
+        amounts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let mut dropped = Vec::new();
+        let mut drop = |amounts: &mut Vec<(&str, Decimal)>, reason| {
+            let (ticker, amount) = amounts.pop().unwrap();
+            dropped.push(DroppedPosition {
+                ticker: ticker.to_string(),
+                weight: amount * Decimal::ONE_HUNDRED / total,
+                reason,
+            });
+            let rest: Decimal = amounts.iter().map(|(_, a)| *a).sum();
+            for (_, other) in amounts.iter_mut() {
+                *other += amount * *other / rest;
+            }
+        };
+
+        if let Some(min) = self.min_weight {
+            while amounts.len() > 1
+                && amounts[amounts.len() - 1].1 * Decimal::ONE_HUNDRED / total < min
+            {
+                drop(amounts, DropReason::BelowMinimum);
+            }
+        }
+        if let Some(max) = self.max_holdings {
+            while amounts.len() > max {
+                drop(amounts, DropReason::TooManyHoldings);
+            }
+        }
+        dropped
+    }
+
+    /// `target` sin las posiciones que no cumplen, con su peso consolidado en las demas.
+    pub fn apply(&self, target: &PortfolioTarget) -> (PortfolioTarget, PositionReport) {
+        let mut weights: Vec<(&str, Decimal)> = target
+            .targets()
+            .iter()
+            .map(|(weight, stock)| (stock.name(), *weight))
+            .collect();
+        let dropped = self.limit(&mut weights, Decimal::ONE_HUNDRED);
+
+        let adjusted = reweighted(target, &weights, |_, _| true);
+        (adjusted, PositionReport { dropped })
+    }
+}
+
+/// Durante el rebalanceo, saca las posiciones que quedarian chicas o de sobra a los precios
+/// actuales y reparte su monto entre las demas. Va antes de redondear.
+impl RebalanceStage for PositionLimits {
+    fn name(&self) -> &str {
+        "position_limits"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        if plan.total.is_zero() {
+            return;
+        }
+
+        let price = |ticker: &str| portfolio.priced(ticker).map(|s| s.current_price());
+        let mut values: Vec<(&'a str, Decimal)> = plan
+            .targets
+            .iter()
+            .filter(|(_, units)| !units.is_zero())
+            .filter_map(|(ticker, units)| Some((*ticker, *units * price(ticker)?)))
+            .collect();
+        for dropped in self.limit(&mut values, plan.total) {
+            if let Some(units) = plan.targets.get_mut(dropped.ticker.as_str()) {
+                *units = Decimal::ZERO;
+            }
+        }
+        for (ticker, value) in values {
+            if let Some(price) = price(ticker).filter(|p| !p.is_zero()) {
+                plan.targets.insert(ticker, value / price);
+            }
+        }
+    }
+}
+
+impl Portfolio {
+    /// Rebalancea con la estrategia conservadora contra el objetivo ajustado por `limits` (ver
+    /// `PositionLimits::apply`), y dice que posiciones salieron.
+    pub fn rebalance_with_limits(&self, limits: &PositionLimits) -> (Vec<Order>, PositionReport) {
+        let (allocation, report) = limits.apply(&self.allocation);
+        let portfolio = Portfolio {
+            allocation,
+            ..self.clone()
+        };
+        (portfolio.rebalance_portfolio().orders(), report)
+    }
+}
+
+impl Localize for PositionReport {
+    fn localize(&self, language: Language) -> String {
+        if self.dropped.is_empty() {
+            return match language {
+                Language::Es => "Todas las posiciones cumplen los limites".into(),
+                Language::En => "All positions are within limits".into(),
+            };
+        }
+
+        let dropped: Vec<String> = self
+            .dropped
+            .iter()
+            .map(|d| {
+                let reason = match (d.reason, language) {
+                    (DropReason::BelowMinimum, Language::Es) => "bajo el minimo",
+                    (DropReason::BelowMinimum, Language::En) => "below minimum",
+                    (DropReason::TooManyHoldings, Language::Es) => "sobran posiciones",
+                    (DropReason::TooManyHoldings, Language::En) => "too many holdings",
+                };
+                format!("{} {}% ({reason})", d.ticker, d.weight.round_dp(2))
+            })
+            .collect();
+        let total = self.consolidated().round_dp(2);
+        match language {
+            Language::Es => format!("Salen {}; se consolidan {total}%", dropped.join(", ")),
+            Language::En => format!("Dropped {}; {total}% consolidated", dropped.join(", ")),
+        }
+    }
+}
+
+impl fmt::Display for PositionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stock;
+    use crate::pipeline::Pipeline;
+    use rust_decimal_macros::dec;
+
+    fn target() -> PortfolioTarget {
+        PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("AAPL", dec!(10))),
+            (dec!(30), Stock::new("MSFT", dec!(10))),
+            (dec!(20), Stock::new("KO", dec!(10))),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_excess_is_redistributed_proportionally() {
+        let caps = WeightCaps::uniform(dec!(40));
+        assert_eq!(
+            caps.check(&target()),
+            Err(TargetError::InfeasibleCaps(dec!(10)))
+        );
+
+        // los 10 que sobran de AAPL van 6 a MSFT y 4 a KO
+        let capped = caps.apply(&target()).unwrap();
+        let weights: Vec<Decimal> = capped.targets().iter().map(|(w, _)| *w).collect();
+        assert_eq!(weights, [dec!(40), dec!(36), dec!(24)]);
+        assert!(caps.check(&capped).is_ok());
+
+        assert_eq!(
+            WeightCaps::uniform(dec!(30)).apply(&target()),
+            Err(TargetError::InfeasibleCaps(dec!(10)))
+        );
+    }
+
+    #[test]
+    fn test_small_and_extra_positions_are_consolidated() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(60), Stock::new("AAPL", dec!(10))),
+            (dec!(30), Stock::new("MSFT", dec!(10))),
+            (dec!(9.7), Stock::new("KO", dec!(10))),
+            (dec!(0.3), Stock::new("PEP", dec!(10))),
+        ])
+        .unwrap();
+        let limits = PositionLimits::new()
+            .with_min_weight(dec!(1))
+            .with_max_holdings(2);
+
+        let (adjusted, report) = limits.apply(&target);
+        let weights: Vec<Decimal> = adjusted.targets().iter().map(|(w, _)| *w).collect();
+        assert_eq!(weights, [dec!(66.666667), dec!(33.333333)]);
+        assert_eq!(report.dropped[0].reason, DropReason::BelowMinimum);
+        assert_eq!(report.dropped[1].ticker, "KO");
+        assert_eq!(report.dropped[1].reason, DropReason::TooManyHoldings);
+
+        let portfolio = Portfolio {
+            stocks: vec![Stock::new("PEP", dec!(10)); 3],
+            allocation: target,
+            cash: dec!(970),
+            foreign_cash: Default::default(),
+        };
+        let (orders, _) = portfolio.rebalance_with_limits(&limits);
+        assert_eq!(orders[0], Order::sell("PEP", 3));
+        assert_eq!(orders.len(), 3);
+
+        // como etapa, sin reporte
+        let suggestion = Pipeline::conservative()
+            .insert_before("round", limits)
+            .run(&portfolio);
+        assert_eq!(suggestion.to_buy["AAPL"], 66);
+        assert!(!suggestion.to_buy.contains_key("KO"));
+    }
+
+    #[test]
+    fn test_rebalance_respects_caps() {
+        let portfolio = Portfolio {
+            stocks: vec![],
+            allocation: target(),
+            cash: dec!(1000),
+            foreign_cash: Default::default(),
+        };
+        let caps = WeightCaps::new().with_cap("AAPL", dec!(25));
+        let suggestion = Pipeline::conservative()
+            .insert_before("round", caps)
+            .run(&portfolio);
+
+        // AAPL queda en 25 y MSFT y KO suben a 45 y 30
+        assert_eq!(suggestion.to_buy["AAPL"], 25);
+        assert_eq!(suggestion.to_buy["MSFT"], 45);
+        assert_eq!(suggestion.to_buy["KO"], 30);
+    }
+}
@@ -0,0 +1,159 @@
+//! Series listas para graficar.
+//!
+//! Cada serie es un nombre y una lista de `(Date, f64)`, que es lo que cualquier libreria de
+//! graficos sabe dibujar sin adaptadores. Salen de un `BacktestResult`: la curva de valor, el
+//! drift de cada ticker del objetivo y la composicion del portafolio dia a dia. Con la feature
+//! `png` se pueden dibujar directamente con `png::render`.
+
+#[cfg(feature = "png")]
+pub mod png;
+
+use crate::backtest::BacktestResult;
+use crate::date::Date;
+use rust_decimal::prelude::*;
+
+/// Una serie con nombre.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(Date, f64)>,
+}
+
+impl Series {
+    pub fn new(name: &str, points: Vec<(Date, f64)>) -> Self {
+        Self {
+            name: name.into(),
+            points,
+        }
+    }
+
+    /// Minimo y maximo de los valores; `None` si no hay puntos.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        self.points
+            .iter()
+            .fold(None, |range, (_, value)| match range {
+                None => Some((*value, *value)),
+                Some((min, max)) => Some((min.min(*value), max.max(*value))),
+            })
+    }
+}
+
+/// Nombre de la serie de la caja en `allocation_stack`.
+pub const CASH: &str = "Cash";
+
+/// Valor del portafolio al cierre de cada dia.
+pub fn equity_curve(result: &BacktestResult) -> Series {
+    Series::new(
+        "Equity",
+        result
+            .equity_curve
+            .iter()
+            .map(|(date, value)| (*date, value.to_f64().unwrap_or(0.0)))
+            .collect(),
+    )
+}
+
+/// Drift (peso actual menos objetivo, en puntos porcentuales) de cada ticker del objetivo, dia a
+/// dia. Un ticker que no se tiene cuenta con peso cero.
+pub fn drift(result: &BacktestResult) -> Vec<Series> {
+    result
+        .portfolio
+        .allocation()
+        .targets()
+        .iter()
+        .map(|(target, stock)| {
+            let points = result
+                .weights
+                .iter()
+                .map(|(date, weights)| {
+                    let actual = weight_in(weights, stock.name());
+                    (*date, (actual - target).to_f64().unwrap_or(0.0))
+                })
+                .collect();
+            Series::new(stock.name(), points)
+        })
+        .collect()
+}
+
+/// Composicion del portafolio dia a dia, apilada: cada serie es el borde superior de su banda
+/// (en %), asi que la ultima, la caja, siempre vale 100. Los tickers van ordenados y aparecen
+/// todos los que se tuvieron en algun momento.
+pub fn allocation_stack(result: &BacktestResult) -> Vec<Series> {
+    let mut tickers: Vec<&str> = result
+        .weights
+        .iter()
+        .flat_map(|(_, weights)| weights.iter().map(|(ticker, _)| ticker.as_str()))
+        .collect();
+    tickers.sort_unstable();
+    tickers.dedup();
+
+    let mut series: Vec<Series> = tickers
+        .iter()
+        .chain([&CASH])
+        .map(|ticker| Series::new(ticker, Vec::with_capacity(result.weights.len())))
+        .collect();
+    for (date, weights) in &result.weights {
+        let mut top = Decimal::ZERO;
+        for (ticker, serie) in tickers.iter().zip(series.iter_mut()) {
+            top += weight_in(weights, ticker);
+            serie.points.push((*date, top.to_f64().unwrap_or(0.0)));
+        }
+        if let Some(cash) = series.last_mut() {
+            cash.points.push((*date, 100.0));
+        }
+    }
+
+    series
+}
+
+fn weight_in(weights: &[(String, Decimal)], ticker: &str) -> Decimal {
+    weights
+        .iter()
+        .find(|(t, _)| t == ticker)
+        .map(|(_, weight)| *weight)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{self, BacktestConfig, MarketData, RebalanceSchedule};
+    use crate::{Portfolio, PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_series_from_backtest() {
+        let d = |day| Date::new(2024, 1, day).unwrap();
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("A", dec!(10))),
+            (dec!(50), Stock::new("B", dec!(10))),
+        ])
+        .unwrap();
+        let portfolio = Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: Vec::new(),
+            allocation: target,
+        };
+        let market = MarketData::new()
+            .with_prices(d(1), &[("A", dec!(10)), ("B", dec!(10))])
+            .with_prices(d(2), &[("A", dec!(30)), ("B", dec!(10))]);
+        let config = BacktestConfig::default().with_schedule(RebalanceSchedule::Never);
+        let result = backtest::run(portfolio, &market, &config);
+
+        assert_eq!(
+            equity_curve(&result).points,
+            vec![(d(1), 100.0), (d(2), 200.0)]
+        );
+
+        let drift = drift(&result);
+        assert_eq!(drift[0].name, "A");
+        assert_eq!(drift[0].points, vec![(d(1), 0.0), (d(2), 25.0)]);
+        assert_eq!(drift[1].range(), Some((-25.0, 0.0)));
+
+        let stack = allocation_stack(&result);
+        let names: Vec<&str> = stack.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["A", "B", CASH]);
+        assert_eq!(stack[1].points[1], (d(2), 100.0));
+    }
+}
@@ -0,0 +1,220 @@
+//! Graficos de lineas en PNG, sin dependencias.
+//!
+//! Son graficos para mirar rapido un resultado, no para publicar: lineas sobre fondo blanco,
+//! ejes en gris y sin texto (no hay fuentes). El color de cada serie sigue `PALETTE` en orden,
+//! asi que la leyenda se arma afuera con los nombres de las series. El PNG va sin compresion
+//! (bloques "stored" de deflate), que para imagenes chicas da archivos de pocos cientos de KB.
+
+use super::Series;
+use crate::export::crc32;
+use std::io;
+use std::path::Path;
+
+/// Colores de las series, en orden; si hay mas series que colores se repiten.
+pub const PALETTE: [[u8; 3]; 8] = [
+    [31, 119, 180],
+    [255, 127, 14],
+    [44, 160, 44],
+    [214, 39, 40],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [127, 127, 127],
+];
+
+const MARGIN: usize = 10;
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const AXIS: [u8; 3] = [160, 160, 160];
+
+/// Imagen RGB en memoria.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: BACKGROUND.repeat(width * height),
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let index = (y as usize * self.width + x as usize) * 3;
+        self.pixels[index..index + 3].copy_from_slice(&color);
+    }
+
+    /// Linea de Bresenham de 2 pixeles de grosor.
+    fn line(&mut self, (x0, y0): (i64, i64), (x1, y1): (i64, i64), color: [u8; 3]) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+        loop {
+            self.set(x, y, color);
+            self.set(x, y + 1, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += sx;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Filas con el byte de filtro (0, sin filtro) que exige PNG al comienzo de cada una.
+    fn scanlines(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width * 3) {
+            data.push(0);
+            data.extend_from_slice(row);
+        }
+        data
+    }
+}
+
+/// Dibuja las series en un grafico de lineas de `width` x `height` pixeles y devuelve el PNG.
+/// El eje horizontal son las fechas (a escala, no por indice) y el vertical va del minimo al
+/// maximo de todas las series juntas.
+pub fn render(series: &[Series], width: usize, height: usize) -> Vec<u8> {
+    let (width, height) = (width.max(3 * MARGIN), height.max(3 * MARGIN));
+    let mut canvas = Canvas::new(width, height);
+
+    let (left, right) = (MARGIN as i64, (width - MARGIN) as i64);
+    let (top, bottom) = (MARGIN as i64, (height - MARGIN) as i64);
+    canvas.line((left, top), (left, bottom), AXIS);
+    canvas.line((left, bottom), (right, bottom), AXIS);
+
+    let days = series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|(date, _)| date.days_since_epoch()));
+    let (first, last) = days.fold((i64::MAX, i64::MIN), |(a, b), d| (a.min(d), b.max(d)));
+    let (min, max) = series
+        .iter()
+        .filter_map(Series::range)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), (min, max)| {
+            (a.min(min), b.max(max))
+        });
+    if first > last {
+        return encode(&canvas);
+    }
+
+    let x_of = |days: i64| {
+        let span = (last - first).max(1) as f64;
+        left + ((days - first) as f64 / span * (right - left) as f64).round() as i64
+    };
+    let y_of = |value: f64| {
+        let span = if max > min { max - min } else { 1.0 };
+        bottom - ((value - min) / span * (bottom - top) as f64).round() as i64
+    };
+
+    for (index, serie) in series.iter().enumerate() {
+        let color = PALETTE[index % PALETTE.len()];
+        let points: Vec<(i64, i64)> = serie
+            .points
+            .iter()
+            .map(|(date, value)| (x_of(date.days_since_epoch()), y_of(*value)))
+            .collect();
+        match points.as_slice() {
+            [single] => canvas.line(*single, *single, color),
+            _ => points
+                .windows(2)
+                .for_each(|pair| canvas.line(pair[0], pair[1], color)),
+        }
+    }
+
+    encode(&canvas)
+}
+
+/// Igual que `render`, escribiendo el PNG en `path`.
+pub fn write_to(
+    series: &[Series],
+    width: usize,
+    height: usize,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    std::fs::write(path, render(series, width, height))
+}
+
+fn encode(canvas: &Canvas) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(canvas.width as u32).to_be_bytes());
+    header.extend_from_slice(&(canvas.height as u32).to_be_bytes());
+    // 8 bits por canal, RGB, deflate, filtros estandar, sin entrelazado
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &zlib_stored(&canvas.scanlines()));
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Stream zlib con bloques deflate sin comprimir (de hasta 65535 bytes cada uno).
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(u8::from(blocks.peek().is_none()));
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + u32::from(*byte)) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Date;
+
+    #[test]
+    fn test_render_png() {
+        let d = |day| Date::new(2024, 1, day).unwrap();
+        let series = [Series::new(
+            "A",
+            vec![(d(1), 1.0), (d(3), 2.0), (d(2), 0.5)],
+        )];
+        let png = render(&series, 40, 30);
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 40);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
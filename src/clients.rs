@@ -0,0 +1,446 @@
+//! Clientes de un asesor.
+//!
+//! Un asesor maneja muchos clientes, cada uno con sus cuentas, sus restricciones (un `RuleSet`,
+//! p. ej. tickers que no quiere tener) y su tolerancia. En vez de armar el objetivo de cada uno a
+//! mano, la mayoria sigue alguno de los modelos del asesor: mientras un cliente este asignado a
+//! un modelo, sus cuentas se rebalancean contra ese modelo y no contra su objetivo propio.
+//!
+//! `Advisor` junta los modelos y los clientes, y hace las operaciones en bloque: rebalancear a
+//! todos los clientes de un modelo y listar a los que estan fuera de tolerancia. Cuando cambia un
+//! modelo, `Advisor::update_model` propaga el cambio a todas las cuentas suscritas en una sola
+//! llamada.
+
+use crate::error::ClientError;
+use crate::execution::Order;
+use crate::household::Account;
+use crate::i18n::{Language, Localize, language};
+use crate::pipeline::Pipeline;
+use crate::reports::verification::Drift;
+use crate::rules::{RuleSet, Violation};
+use crate::{Portfolio, PortfolioTarget};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Un cliente con sus cuentas.
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub id: String,
+    pub name: String,
+    accounts: Vec<Account>,
+    model: Option<String>,
+    rules: RuleSet,
+
+    /// Reglas propias de algunas cuentas, que reemplazan a las del cliente (ver
+    /// `with_account_rules`).
+    account_rules: BTreeMap<String, RuleSet>,
+
+    /// Desviacion aceptada, en puntos porcentuales, para los tickers sin banda propia.
+    tolerance: Decimal,
+}
+
+impl Client {
+    /// Un cliente sin cuentas, sin modelo, sin restricciones y con 5 puntos de tolerancia.
+    pub fn new(id: &str, name: &str) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            accounts: Vec::new(),
+            model: None,
+            rules: RuleSet::default(),
+            account_rules: BTreeMap::new(),
+            tolerance: dec!(5),
+        }
+    }
+
+    pub fn with_account(mut self, name: &str, portfolio: Portfolio) -> Self {
+        self.accounts.push(Account::new(name, portfolio));
+        self
+    }
+
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Reglas para una sola cuenta en vez de las del cliente, p. ej. una cuenta de ahorro
+    /// previsional con limites distintos a los de la cuenta de libre disposicion.
+    pub fn with_account_rules(mut self, account: &str, rules: RuleSet) -> Self {
+        self.account_rules.insert(account.into(), rules);
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Decimal) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    pub fn account_mut(&mut self, name: &str) -> Option<&mut Portfolio> {
+        self.accounts
+            .iter_mut()
+            .find(|a| a.name == name)
+            .map(|a| &mut a.portfolio)
+    }
+
+    /// Modelo asignado, si tiene.
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// Las reglas que aplican a `account`: las propias de la cuenta o, si no tiene, las del
+    /// cliente.
+    pub fn rules_for(&self, account: &str) -> &RuleSet {
+        self.account_rules.get(account).unwrap_or(&self.rules)
+    }
+
+    pub fn tolerance(&self) -> Decimal {
+        self.tolerance
+    }
+
+    pub fn total_value(&self) -> Decimal {
+        self.accounts
+            .iter()
+            .map(|a| a.portfolio.total_value())
+            .sum()
+    }
+}
+
+/// Ordenes sugeridas para una cuenta de un cliente.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOrders {
+    pub client: String,
+    pub account: String,
+    pub orders: Vec<Order>,
+}
+
+/// Una cuenta fuera de tolerancia o que no cumple las reglas de su cliente.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutOfTolerance {
+    pub client: String,
+    pub account: String,
+
+    /// Tickers que se alejaron de su objetivo mas de lo aceptado.
+    pub drifts: Vec<Drift>,
+
+    pub violations: Vec<Violation>,
+}
+
+/// Resultado de `Advisor::out_of_tolerance`, en el orden de los clientes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToleranceReport {
+    pub accounts: Vec<OutOfTolerance>,
+}
+
+impl ToleranceReport {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Ids de los clientes con alguna cuenta en el reporte, sin repetir.
+    pub fn clients(&self) -> Vec<&str> {
+        let mut clients: Vec<&str> = Vec::new();
+        for row in &self.accounts {
+            if !clients.contains(&row.client.as_str()) {
+                clients.push(&row.client);
+            }
+        }
+        clients
+    }
+}
+
+/// Los modelos y clientes de un asesor.
+#[derive(Debug, Clone, Default)]
+pub struct Advisor {
+    models: BTreeMap<String, PortfolioTarget>,
+    clients: Vec<Client>,
+}
+
+impl Advisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agrega un modelo, o lo reemplaza si ya habia uno con ese nombre.
+    pub fn with_model(mut self, name: &str, target: PortfolioTarget) -> Self {
+        self.models.insert(name.into(), target);
+        self
+    }
+
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.clients.push(client);
+        self
+    }
+
+    pub fn model(&self, name: &str) -> Option<&PortfolioTarget> {
+        self.models.get(name)
+    }
+
+    pub fn clients(&self) -> &[Client] {
+        &self.clients
+    }
+
+    pub fn client(&self, id: &str) -> Option<&Client> {
+        self.clients.iter().find(|c| c.id == id)
+    }
+
+    pub fn client_mut(&mut self, id: &str) -> Option<&mut Client> {
+        self.clients.iter_mut().find(|c| c.id == id)
+    }
+
+    /// Clientes asignados a `model`.
+    pub fn clients_of<'a>(&'a self, model: &'a str) -> impl Iterator<Item = &'a Client> {
+        self.clients
+            .iter()
+            .filter(move |c| c.model.as_deref() == Some(model))
+    }
+
+    /// Asigna el cliente `id` a `model`; con `None` vuelve a usar el objetivo de cada cuenta.
+    pub fn assign(&mut self, id: &str, model: Option<&str>) -> Result<(), ClientError> {
+        if let Some(model) = model.filter(|m| !self.models.contains_key(*m)) {
+            return Err(ClientError::UnknownModel(model.into()));
+        }
+        let client = self
+            .client_mut(id)
+            .ok_or_else(|| ClientError::UnknownClient(id.into()))?;
+        client.model = model.map(Into::into);
+        Ok(())
+    }
+
+    /// La cuenta tal como se rebalancea: con el objetivo del modelo del cliente, si tiene.
+    fn effective(&self, client: &Client, account: &Account) -> Portfolio {
+        match client.model.as_ref().and_then(|m| self.models.get(m)) {
+            Some(model) => Portfolio {
+                allocation: model.clone(),
+                ..account.portfolio.clone()
+            },
+            None => account.portfolio.clone(),
+        }
+    }
+
+    /// Rebalancea cada cuenta del cliente `id` con la estrategia conservadora, respetando sus
+    /// reglas (ver `Client::rules_for`).
+    pub fn rebalance_client(&self, id: &str) -> Result<Vec<ClientOrders>, ClientError> {
+        let client = self
+            .client(id)
+            .ok_or_else(|| ClientError::UnknownClient(id.into()))?;
+
+        Ok(client
+            .accounts
+            .iter()
+            .map(|account| {
+                let rules = client.rules_for(&account.name).clone();
+                let pipeline = Pipeline::conservative().insert_before("round", rules);
+                let portfolio = self.effective(client, account);
+                ClientOrders {
+                    client: client.id.clone(),
+                    account: account.name.clone(),
+                    orders: pipeline.run(&portfolio).orders(),
+                }
+            })
+            .filter(|row| !row.orders.is_empty())
+            .collect())
+    }
+
+    /// Rebalancea todas las cuentas de los clientes asignados a `model`. Solo aparecen las
+    /// cuentas que tienen algo que hacer.
+    pub fn rebalance_model(&self, model: &str) -> Result<Vec<ClientOrders>, ClientError> {
+        if !self.models.contains_key(model) {
+            return Err(ClientError::UnknownModel(model.into()));
+        }
+
+        let mut orders = Vec::new();
+        for client in self.clients_of(model) {
+            orders.extend(self.rebalance_client(&client.id)?);
+        }
+        Ok(orders)
+    }
+
+    /// Cambia el modelo `name` (o lo crea) y devuelve de una vez lo que tiene que hacer cada
+    /// cuenta suscrita para seguir la nueva version, con las reglas de cada cuenta.
+    pub fn update_model(&mut self, name: &str, target: PortfolioTarget) -> Vec<ClientOrders> {
+        self.models.insert(name.into(), target);
+        self.rebalance_model(name).unwrap_or_default()
+    }
+
+    /// Cuentas que se alejaron de su objetivo (el del modelo, si el cliente tiene) mas que la
+    /// tolerancia del cliente, o que no cumplen sus reglas.
+    pub fn out_of_tolerance(&self) -> ToleranceReport {
+        let mut accounts = Vec::new();
+        for client in &self.clients {
+            for account in &client.accounts {
+                let portfolio = self.effective(client, account);
+                let drifts: Vec<Drift> = portfolio
+                    .verify_against_target(client.tolerance)
+                    .residual()
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                let violations = client
+                    .rules_for(&account.name)
+                    .check_portfolio(&portfolio)
+                    .err();
+
+                if !drifts.is_empty() || violations.is_some() {
+                    accounts.push(OutOfTolerance {
+                        client: client.id.clone(),
+                        account: account.name.clone(),
+                        drifts,
+                        violations: violations.unwrap_or_default(),
+                    });
+                }
+            }
+        }
+        ToleranceReport { accounts }
+    }
+}
+
+impl Localize for ToleranceReport {
+    fn localize(&self, language: Language) -> String {
+        if self.accounts.is_empty() {
+            return match language {
+                Language::Es => "Todos los clientes estan dentro de tolerancia".into(),
+                Language::En => "All clients are within tolerance".into(),
+            };
+        }
+
+        let mut lines = Vec::new();
+        lines.push(match language {
+            Language::Es => format!("{} cuentas fuera de tolerancia", self.accounts.len()),
+            Language::En => format!("{} accounts out of tolerance", self.accounts.len()),
+        });
+        for row in &self.accounts {
+            let mut problems: Vec<String> = row
+                .drifts
+                .iter()
+                .map(|d| format!("{} {:+}", d.ticker, d.difference().round_dp(2)))
+                .collect();
+            problems.extend(row.violations.iter().map(|v| v.localize(language)));
+            lines.push(format!(
+                "- {} / {}: {}",
+                row.client,
+                row.account,
+                problems.join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for ToleranceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stock;
+    use crate::execution::Side;
+    use crate::rules::Rule;
+    use crate::universe::Universe;
+
+    fn portfolio(ko: usize, pep: usize, cash: Decimal) -> Portfolio {
+        Portfolio {
+            stocks: [("KO", ko), ("PEP", pep)]
+                .into_iter()
+                .flat_map(|(t, n)| core::iter::repeat_n(Stock::new(t, dec!(10)), n))
+                .collect(),
+            allocation: PortfolioTarget::new(Stock::new("KO", dec!(10))),
+            cash,
+            foreign_cash: Default::default(),
+        }
+    }
+
+    fn advisor() -> Advisor {
+        let balanced = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("KO", dec!(10))),
+            (dec!(50), Stock::new("PEP", dec!(10))),
+        ])
+        .unwrap();
+
+        Advisor::new()
+            .with_model("balanceado", balanced)
+            .with_client(Client::new("ana", "Ana").with_account("apv", portfolio(10, 0, dec!(0))))
+            .with_client(
+                Client::new("beto", "Beto")
+                    .with_account("ahorro", portfolio(0, 0, dec!(100)))
+                    .with_rules(RuleSet::new(Universe::new()).with(Rule::Banned("PEP".into()))),
+            )
+            .with_client(Client::new("caro", "Caro").with_account("apv", portfolio(5, 5, dec!(0))))
+    }
+
+    #[test]
+    fn test_rebalance_every_client_of_a_model() {
+        let mut advisor = advisor();
+        assert_eq!(
+            advisor.assign("nadie", Some("balanceado")),
+            Err(ClientError::UnknownClient("nadie".into()))
+        );
+        for id in ["ana", "beto", "caro"] {
+            advisor.assign(id, Some("balanceado")).unwrap();
+        }
+
+        let orders = advisor.rebalance_model("balanceado").unwrap();
+        // caro ya esta en el modelo; beto no compra PEP por sus reglas
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].client, "ana");
+        assert_eq!(orders[0].orders.len(), 2);
+        assert_eq!(orders[1].orders.len(), 1);
+        assert_eq!(orders[1].orders[0].side, Side::Buy);
+        assert_eq!(orders[1].orders[0].ticker, "KO");
+    }
+
+    #[test]
+    fn test_model_update_propagates_with_account_overrides() {
+        let banned = |ticker: &str| RuleSet::new(Universe::new()).with(Rule::Banned(ticker.into()));
+        let mut advisor = advisor().with_client(
+            Client::new("dani", "Dani")
+                .with_account("apv", portfolio(0, 0, dec!(100)))
+                .with_account("libre", portfolio(0, 0, dec!(100)))
+                .with_account_rules("apv", banned("PEP")),
+        );
+        advisor.assign("caro", Some("balanceado")).unwrap();
+        advisor.assign("dani", Some("balanceado")).unwrap();
+
+        // el modelo pasa a 100% PEP
+        let orders = advisor.update_model(
+            "balanceado",
+            PortfolioTarget::new(Stock::new("PEP", dec!(10))),
+        );
+        let accounts: Vec<(&str, &str)> = orders
+            .iter()
+            .map(|row| (row.client.as_str(), row.account.as_str()))
+            .collect();
+        // la cuenta apv de dani no puede tener PEP, asi que no hace nada
+        assert_eq!(accounts, [("caro", "apv"), ("dani", "libre")]);
+        assert_eq!(orders[0].orders.len(), 2);
+        assert_eq!(orders[1].orders[0].units, 10);
+    }
+
+    #[test]
+    fn test_report_clients_out_of_tolerance() {
+        let mut advisor = advisor();
+        advisor.assign("caro", Some("balanceado")).unwrap();
+
+        // ana sigue su objetivo propio (100% KO); beto tiene todo en caja
+        let report = advisor.out_of_tolerance();
+        assert_eq!(report.clients(), ["beto"]);
+        assert_eq!(report.accounts[0].drifts[0].ticker, "KO");
+
+        advisor.assign("ana", Some("balanceado")).unwrap();
+        assert_eq!(advisor.out_of_tolerance().clients(), ["ana", "beto"]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Reloj inyectable.
+//!
+//! Lo que depende de la hora (a que valor cuota va una orden, ganancias de corto o largo plazo,
+//! la fecha de un registro de auditoria, si una sugerencia ya es muy vieja) recibe un `Clock` en
+//! vez de leer el reloj del sistema, para poder probarlo con un `TestClock` que marca la hora que
+//! uno quiera y avanza solo cuando se le pide.
+
+use crate::date::{Date, Timestamp};
+use core::sync::atomic::{AtomicI64, Ordering};
+
+/// Fuente de la hora actual.
+pub trait Clock {
+    fn now(&self) -> Timestamp;
+
+    /// Fecha actual en UTC.
+    fn today(&self) -> Date {
+        self.now().date()
+    }
+
+    /// Si paso mas de `max_age_secs` desde `stamp`.
+    fn is_stale(&self, stamp: Timestamp, max_age_secs: i64) -> bool {
+        self.now().as_secs() - stamp.as_secs() > max_age_secs
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Timestamp {
+        (**self).now()
+    }
+}
+
+/// El reloj del sistema.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+}
+
+/// Reloj detenido que solo se mueve con `set` o `advance`. Se puede compartir entre threads.
+#[derive(Debug, Default)]
+pub struct TestClock {
+    secs: AtomicI64,
+}
+
+impl TestClock {
+    pub fn new(now: Timestamp) -> Self {
+        Self {
+            secs: AtomicI64::new(now.as_secs()),
+        }
+    }
+
+    /// Medianoche UTC de `date`.
+    pub fn at_date(date: Date) -> Self {
+        Self::new(Timestamp::from_date(date))
+    }
+
+    pub fn set(&self, now: Timestamp) {
+        self.secs.store(now.as_secs(), Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, secs: i64) {
+        self.secs.fetch_add(secs, Ordering::SeqCst);
+    }
+
+    pub fn advance_days(&self, days: i64) {
+        self.advance(days * 86_400);
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::from_secs(self.secs.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_moves_only_when_told() {
+        let clock = TestClock::at_date(Date::new(2024, 1, 31).unwrap());
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+
+        clock.advance(3600);
+        assert!(!clock.is_stale(start, 3600));
+        clock.advance_days(1);
+        assert!(clock.is_stale(start, 3600));
+        assert_eq!(clock.today(), Date::new(2024, 2, 1).unwrap());
+    }
+}
@@ -0,0 +1,368 @@
+//! Reduccion gradual de una posicion concentrada.
+//!
+//! Quien recibe acciones de su empleador suele terminar con buena parte del portafolio en un
+//! solo ticker, y venderlo todo de una vez realiza demasiadas ganancias (o simplemente no esta
+//! permitido). `DivestmentPlanner` arma un calendario: cada periodo vende hasta un limite (de
+//! unidades, de monto o de ganancias realizadas) y con lo recaudado compra lo que falta del resto
+//! del objetivo, hasta que el ticker llega a su peso objetivo.
+//!
+//! La simulacion usa los precios actuales para todos los periodos: el calendario dice cuanto
+//! vender y en cuantos pasos, no predice precios. Las unidades se venden en el mismo orden que
+//! `Portfolio::apply_event` (las ultimas compradas primero) y no se venden unidades bloqueadas a la
+//! fecha de cada periodo. Las unidades sin costo conocido cuentan como ganancia completa.
+
+use crate::Portfolio;
+use crate::costs::CostModel;
+use crate::date::Date;
+use crate::error::EventError;
+use crate::execution::Order;
+use crate::i18n::{Language, Localize, language};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Cuanto se puede vender en cada periodo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SellLimit {
+    /// Unidades.
+    Units(usize),
+
+    /// Monto, a precio actual.
+    Value(Decimal),
+
+    /// Ganancias realizadas (precio menos costo), p. ej. lo que queda exento en el tramo de
+    /// impuestos.
+    Gains(Decimal),
+}
+
+/// Planificador de la venta gradual de `ticker`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DivestmentPlanner {
+    ticker: String,
+    limit: SellLimit,
+    every_months: i32,
+    max_periods: usize,
+    costs: CostModel,
+}
+
+impl DivestmentPlanner {
+    /// Vende una vez al mes, por hasta 10 años, sin comisiones.
+    pub fn new(ticker: &str, limit: SellLimit) -> Self {
+        Self {
+            ticker: ticker.into(),
+            limit,
+            every_months: 1,
+            max_periods: 120,
+            costs: CostModel::free(),
+        }
+    }
+
+    /// Meses entre un periodo y el siguiente.
+    pub fn with_frequency(mut self, months: i32) -> Self {
+        self.every_months = months.max(1);
+        self
+    }
+
+    pub fn with_max_periods(mut self, periods: usize) -> Self {
+        self.max_periods = periods;
+        self
+    }
+
+    pub fn with_costs(mut self, costs: CostModel) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Simula el calendario partiendo en `start`, sobre una copia del portafolio.
+    pub fn plan(
+        &self,
+        portfolio: &Portfolio,
+        start: Date,
+    ) -> Result<DivestmentSchedule, EventError> {
+        let mut simulated = portfolio.clone();
+        let mut periods = Vec::new();
+        let initial_weight = simulated.weight_of(&self.ticker);
+
+        for period in 0..self.max_periods {
+            let date = start.add_months(self.every_months * period as i32);
+            let excess = self.excess(&simulated);
+            if excess == 0 {
+                break;
+            }
+
+            let (units, gain) = self.sellable(&simulated, date, excess);
+            if units == 0 {
+                break;
+            }
+
+            let price = simulated
+                .priced(&self.ticker)
+                .map(|stock| stock.current_price())
+                .unwrap_or_default();
+            simulated.apply(&[Order::sell(&self.ticker, units)], date, &self.costs)?;
+
+            let buys = simulated.contribution_suggestion().orders();
+            simulated.apply(&buys, date, &self.costs)?;
+
+            periods.push(DivestmentPeriod {
+                date,
+                units,
+                proceeds: price * Decimal::from(units),
+                realized_gain: gain,
+                buys,
+                weight: simulated.weight_of(&self.ticker),
+            });
+        }
+
+        Ok(DivestmentSchedule {
+            ticker: self.ticker.clone(),
+            initial_weight,
+            target_weight: self.target_weight(portfolio),
+            remaining: self.excess(&simulated),
+            periods,
+        })
+    }
+
+    /// Peso objetivo del ticker, en %; cero si no esta en el objetivo.
+    fn target_weight(&self, portfolio: &Portfolio) -> Decimal {
+        portfolio
+            .allocation()
+            .targets()
+            .iter()
+            .find(|(_, stock)| stock.name() == self.ticker)
+            .map(|(weight, _)| *weight)
+            .unwrap_or_default()
+    }
+
+    /// Unidades sobre el peso objetivo, a precios actuales.
+    fn excess(&self, portfolio: &Portfolio) -> usize {
+        let held = portfolio
+            .iter_holdings()
+            .find(|(ticker, _)| *ticker == self.ticker)
+            .map(|(_, units)| units)
+            .unwrap_or(0);
+        let Some(price) = portfolio.priced(&self.ticker).map(|s| s.current_price()) else {
+            return 0;
+        };
+        if price <= Decimal::ZERO {
+            return 0;
+        }
+
+        let target = portfolio.total_value() * self.target_weight(portfolio) / dec!(100) / price;
+        let target = target.floor().to_usize().unwrap_or(0);
+        held.saturating_sub(target)
+    }
+
+    /// Cuantas de las `excess` unidades se pueden vender el dia `date` sin pasarse del limite, y
+    /// la ganancia que realizan.
+    fn sellable(&self, portfolio: &Portfolio, date: Date, excess: usize) -> (usize, Decimal) {
+        // mismo orden en que las saca `apply_event`: las ultimas primero, las no bloqueadas antes
+        let mut units: Vec<_> = portfolio
+            .stocks()
+            .iter()
+            .rev()
+            .filter(|stock| stock.name() == self.ticker)
+            .collect();
+        units.sort_by_key(|stock| stock.sale_order());
+
+        let mut sold = 0;
+        let mut value = Decimal::ZERO;
+        let mut gain = Decimal::ZERO;
+        for stock in units {
+            if sold == excess || stock.is_locked(date) {
+                break;
+            }
+
+            let price = stock.current_price();
+            let unit_gain = price - stock.basis().map(|basis| basis.cost).unwrap_or_default();
+            let within = match self.limit {
+                SellLimit::Units(limit) => sold < limit,
+                SellLimit::Value(limit) => value + price <= limit,
+                SellLimit::Gains(limit) => gain + unit_gain <= limit,
+            };
+            if !within {
+                break;
+            }
+
+            sold += 1;
+            value += price;
+            gain += unit_gain;
+        }
+
+        (sold, gain)
+    }
+}
+
+/// Lo que se hace en un periodo del calendario.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DivestmentPeriod {
+    pub date: Date,
+
+    /// Unidades vendidas del ticker concentrado.
+    pub units: usize,
+
+    /// Lo recaudado, antes de comisiones.
+    pub proceeds: Decimal,
+
+    pub realized_gain: Decimal,
+
+    /// Compras del resto del objetivo con lo recaudado.
+    pub buys: Vec<Order>,
+
+    /// Peso del ticker despues del periodo, en %.
+    pub weight: Decimal,
+}
+
+/// Calendario de venta de una posicion concentrada.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DivestmentSchedule {
+    pub ticker: String,
+    pub initial_weight: Decimal,
+    pub target_weight: Decimal,
+    pub periods: Vec<DivestmentPeriod>,
+
+    /// Unidades que siguen sobre el objetivo al terminar; cero si el calendario converge.
+    pub remaining: usize,
+}
+
+impl DivestmentSchedule {
+    /// Si al final del calendario el ticker llega a su peso objetivo.
+    pub fn converges(&self) -> bool {
+        self.remaining == 0
+    }
+
+    pub fn total_units(&self) -> usize {
+        self.periods.iter().map(|p| p.units).sum()
+    }
+
+    pub fn total_gain(&self) -> Decimal {
+        self.periods.iter().map(|p| p.realized_gain).sum()
+    }
+}
+
+impl Localize for DivestmentSchedule {
+    fn localize(&self, language: Language) -> String {
+        let mut out = match language {
+            Language::Es => format!(
+                "Venta gradual de {}: de {}% a {}% en {} periodos\n",
+                self.ticker,
+                self.initial_weight.round_dp(2),
+                self.target_weight.round_dp(2),
+                self.periods.len()
+            ),
+            Language::En => format!(
+                "Gradual sale of {}: from {}% to {}% over {} periods\n",
+                self.ticker,
+                self.initial_weight.round_dp(2),
+                self.target_weight.round_dp(2),
+                self.periods.len()
+            ),
+        };
+
+        for period in &self.periods {
+            out += &match language {
+                Language::Es => format!(
+                    "  {}: vender {} ({}), ganancia {}, queda en {}%\n",
+                    period.date,
+                    period.units,
+                    period.proceeds.round_dp(2),
+                    period.realized_gain.round_dp(2),
+                    period.weight.round_dp(2)
+                ),
+                Language::En => format!(
+                    "  {}: sell {} ({}), gain {}, ends at {}%\n",
+                    period.date,
+                    period.units,
+                    period.proceeds.round_dp(2),
+                    period.realized_gain.round_dp(2),
+                    period.weight.round_dp(2)
+                ),
+            };
+        }
+
+        if !self.converges() {
+            out += &match language {
+                Language::Es => format!("  Quedan {} unidades sobre el objetivo\n", self.remaining),
+                Language::En => format!("  {} units remain above target\n", self.remaining),
+            };
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for DivestmentSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use alloc::vec;
+
+    fn concentrated() -> Portfolio {
+        let bought = Date::new(2020, 1, 1).unwrap();
+        let mut stocks = Vec::new();
+        // 60 unidades de la empresa a 10 (costo 4) y 40 de un fondo a 10
+        for _ in 0..60 {
+            stocks.push(Stock::new("ACME", dec!(10)).with_basis(dec!(4), bought));
+        }
+        for _ in 0..40 {
+            stocks.push(Stock::new("FUND", dec!(10)).with_basis(dec!(10), bought));
+        }
+
+        Portfolio {
+            stocks,
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            allocation: PortfolioTarget::try_from_vec(vec![
+                (dec!(10), Stock::new("ACME", dec!(10))),
+                (dec!(90), Stock::new("FUND", dec!(10))),
+            ])
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_unit_limit_converges_to_target() {
+        let start = Date::new(2025, 1, 31).unwrap();
+        let schedule = DivestmentPlanner::new("ACME", SellLimit::Units(15))
+            .plan(&concentrated(), start)
+            .unwrap();
+
+        assert!(schedule.converges());
+        assert_eq!(schedule.initial_weight, dec!(60));
+        assert_eq!(
+            schedule.periods.iter().map(|p| p.units).collect::<Vec<_>>(),
+            vec![15, 15, 15, 5]
+        );
+        assert_eq!(schedule.periods[1].date, Date::new(2025, 2, 28).unwrap());
+        assert_eq!(schedule.periods[0].buys, vec![Order::buy("FUND", 15)]);
+        assert_eq!(schedule.periods.last().unwrap().weight, dec!(10));
+        assert_eq!(schedule.total_gain(), dec!(300));
+    }
+
+    #[test]
+    fn test_gains_budget_limits_each_period() {
+        let start = Date::new(2025, 1, 1).unwrap();
+        let schedule = DivestmentPlanner::new("ACME", SellLimit::Gains(dec!(100)))
+            .with_frequency(12)
+            .with_max_periods(2)
+            .plan(&concentrated(), start)
+            .unwrap();
+
+        // cada unidad gana 6, asi que caben 16 por año
+        assert_eq!(schedule.periods.len(), 2);
+        assert!(schedule.periods.iter().all(|p| p.units == 16));
+        assert!(schedule.periods.iter().all(|p| p.realized_gain == dec!(96)));
+        assert_eq!(schedule.periods[1].date, Date::new(2026, 1, 1).unwrap());
+        assert!(!schedule.converges());
+        assert_eq!(schedule.remaining, 18);
+    }
+}
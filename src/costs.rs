@@ -0,0 +1,80 @@
+//! Costos de transaccion.
+
+use rust_decimal::Decimal;
+
+/// Comisiones que cobra el broker por una orden.
+///
+/// La comision de una orden es `per_trade + per_unit * unidades + rate * monto`, con un minimo de
+/// `minimum`. Por defecto todo es cero (sin comisiones).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostModel {
+    /// Cargo fijo por orden.
+    pub per_trade: Decimal,
+
+    /// Cargo por unidad transada.
+    pub per_unit: Decimal,
+
+    /// Fraccion del monto transado (`0.001` para 10 puntos base).
+    pub rate: Decimal,
+
+    /// Comision minima por orden.
+    pub minimum: Decimal,
+}
+
+impl CostModel {
+    /// Sin comisiones.
+    pub fn free() -> Self {
+        Self::default()
+    }
+
+    pub fn with_per_trade(mut self, per_trade: Decimal) -> Self {
+        self.per_trade = per_trade;
+        self
+    }
+
+    pub fn with_per_unit(mut self, per_unit: Decimal) -> Self {
+        self.per_unit = per_unit;
+        self
+    }
+
+    pub fn with_rate(mut self, rate: Decimal) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_minimum(mut self, minimum: Decimal) -> Self {
+        self.minimum = minimum;
+        self
+    }
+
+    /// Comision de una orden de `units` unidades a `price`. Una orden vacia no cobra.
+    pub fn commission(&self, units: usize, price: Decimal) -> Decimal {
+        if units == 0 {
+            return Decimal::ZERO;
+        }
+
+        let units = Decimal::from(units);
+        let commission = self.per_trade + self.per_unit * units + self.rate * units * price;
+        commission.max(self.minimum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_commission_components_and_minimum() {
+        let costs = CostModel::free()
+            .with_per_trade(dec!(1))
+            .with_per_unit(dec!(0.01))
+            .with_rate(dec!(0.001))
+            .with_minimum(dec!(2));
+
+        assert_eq!(costs.commission(0, dec!(100)), Decimal::ZERO);
+        assert_eq!(costs.commission(1, dec!(10)), dec!(2));
+        assert_eq!(costs.commission(100, dec!(100)), dec!(12));
+        assert_eq!(CostModel::free().commission(100, dec!(100)), Decimal::ZERO);
+    }
+}
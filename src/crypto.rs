@@ -0,0 +1,345 @@
+//! Portafolios con cantidades fraccionales, pensados para cripto.
+//!
+//! `Portfolio` modela un stock como unidades enteras (un `Stock` por unidad), lo que no sirve
+//! para activos que se transan en fracciones de hasta 8 decimales o mas. Aca cada holding es un
+//! `Stock` con su cantidad en `Decimal`, y cada activo puede tener sus propias reglas de
+//! cantidad: precision, orden minima y umbral de "polvo" (saldos tan chicos que no se pueden
+//! vender).
+
+use crate::Map;
+use crate::i18n::{Language, Localize, language};
+use crate::{PortfolioTarget, Stock};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Reglas de cantidad de un activo, tipicas de un exchange.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantityRules {
+    /// Decimales permitidos en la cantidad (8 para BTC, p. ej.).
+    pub decimals: u32,
+
+    /// Cantidad minima de una orden, en unidades del activo.
+    pub min_order_units: Decimal,
+
+    /// Valor minimo de una orden, en la moneda de cotizacion (el "min notional" de los exchanges).
+    pub min_order_value: Decimal,
+
+    /// Bajo esta cantidad un saldo se considera polvo: no se intenta vender y, si una venta lo
+    /// dejaria bajo este umbral, se vende todo.
+    pub dust: Decimal,
+}
+
+impl Default for QuantityRules {
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+impl QuantityRules {
+    /// Reglas con `decimals` decimales y sin minimos.
+    pub fn new(decimals: u32) -> Self {
+        Self {
+            decimals,
+            min_order_units: Decimal::ZERO,
+            min_order_value: Decimal::ZERO,
+            dust: Decimal::ZERO,
+        }
+    }
+
+    pub fn with_min_order_units(mut self, units: Decimal) -> Self {
+        self.min_order_units = units;
+        self
+    }
+
+    pub fn with_min_order_value(mut self, value: Decimal) -> Self {
+        self.min_order_value = value;
+        self
+    }
+
+    pub fn with_dust(mut self, dust: Decimal) -> Self {
+        self.dust = dust;
+        self
+    }
+
+    /// Redondea hacia abajo a la precision del activo (estrategia conservadora, igual que en
+    /// `Portfolio::rebalance_portfolio`).
+    pub fn round_down(&self, quantity: Decimal) -> Decimal {
+        quantity.round_dp_with_strategy(self.decimals, RoundingStrategy::ToZero)
+    }
+
+    /// Si una orden de `quantity` a `price` cumple los minimos del exchange.
+    pub fn accepts(&self, quantity: Decimal, price: Decimal) -> bool {
+        !quantity.is_zero()
+            && quantity >= self.min_order_units
+            && quantity * price >= self.min_order_value
+    }
+}
+
+/// Portafolio con holdings fraccionales.
+#[derive(Debug, Clone)]
+pub struct CryptoPortfolio {
+    holdings: Vec<(Stock, Decimal)>,
+    allocation: PortfolioTarget,
+    cash: Decimal,
+    rules: Map<String, QuantityRules>,
+}
+
+impl CryptoPortfolio {
+    pub fn new(allocation: PortfolioTarget) -> Self {
+        Self {
+            holdings: Vec::new(),
+            allocation,
+            cash: Decimal::ZERO,
+            rules: Map::new(),
+        }
+    }
+
+    /// Agrega `quantity` del activo, sumandola si ya habia.
+    pub fn with_holding(mut self, stock: Stock, quantity: Decimal) -> Self {
+        match self
+            .holdings
+            .iter_mut()
+            .find(|(s, _)| s.name() == stock.name())
+        {
+            Some((_, held)) => *held += quantity,
+            None => self.holdings.push((stock, quantity)),
+        }
+        self
+    }
+
+    pub fn with_cash(mut self, cash: Decimal) -> Self {
+        self.cash = cash;
+        self
+    }
+
+    /// Reglas de cantidad de un ticker; los que no tengan usan `QuantityRules::default()`.
+    pub fn with_rules(mut self, ticker: &str, rules: QuantityRules) -> Self {
+        self.rules.insert(ticker.into(), rules);
+        self
+    }
+
+    pub fn holdings(&self) -> &[(Stock, Decimal)] {
+        &self.holdings
+    }
+
+    pub fn cash(&self) -> Decimal {
+        self.cash
+    }
+
+    pub fn allocation(&self) -> &PortfolioTarget {
+        &self.allocation
+    }
+
+    pub fn rules(&self, ticker: &str) -> QuantityRules {
+        self.rules.get(ticker).cloned().unwrap_or_default()
+    }
+
+    pub fn quantity_of(&self, ticker: &str) -> Decimal {
+        self.holdings
+            .iter()
+            .find(|(s, _)| s.name() == ticker)
+            .map_or(Decimal::ZERO, |(_, q)| *q)
+    }
+
+    pub fn total_value(&self) -> Decimal {
+        self.holdings
+            .iter()
+            .map(|(stock, quantity)| stock.current_price() * quantity)
+            .sum::<Decimal>()
+            + self.cash
+    }
+
+    /// Actualiza el precio de un ticker en los holdings y en el objetivo; ver
+    /// `Portfolio::update_price`.
+    pub fn update_price(&mut self, ticker: &str, price: Decimal) -> bool {
+        let mut found = false;
+
+        let held = self.holdings.iter_mut().map(|(stock, _)| stock);
+        let targeted = self.allocation.targets.iter_mut().map(|(_, stock)| stock);
+        for stock in held.chain(targeted).filter(|s| s.name() == ticker) {
+            stock.current_price = price;
+            found = true;
+        }
+
+        found
+    }
+
+    /// Holdings que son polvo segun sus reglas.
+    pub fn dust(&self) -> Vec<(&str, Decimal)> {
+        self.holdings
+            .iter()
+            .filter(|(stock, quantity)| {
+                !quantity.is_zero() && *quantity < self.rules(stock.name()).dust
+            })
+            .map(|(stock, quantity)| (stock.name(), *quantity))
+            .collect()
+    }
+
+    /// Sugerencia de rebalanceo con cantidades fraccionales.
+    ///
+    /// Igual que en `Portfolio`, se apunta a la proporcion objetivo sin pasarse, pero truncando a
+    /// la precision de cada activo en vez de a unidades enteras. Ademas:
+    /// - una orden que no cumple el minimo del exchange se descarta (el holding queda un poco
+    ///   desbalanceado, que es mejor que una orden rechazada);
+    /// - si una venta dejaria un saldo bajo el umbral de polvo, se vende todo;
+    /// - el polvo de activos que ya no estan en el objetivo no se intenta vender.
+    pub fn rebalance(&self) -> FractionalSuggestion {
+        let mut suggestion = FractionalSuggestion::default();
+        let total = self.total_value();
+        if total.is_zero() {
+            return suggestion;
+        }
+
+        for (stock, held) in &self.holdings {
+            let rules = self.rules(stock.name());
+            if !self.allocation.contains_key(stock.name())
+                && *held >= rules.dust
+                && rules.accepts(*held, stock.current_price())
+            {
+                suggestion.to_sell.insert(stock.name().into(), *held);
+            }
+        }
+
+        for (ratio, target_stock) in self.allocation.targets() {
+            let name = target_stock.name();
+            let price = target_stock.current_price();
+            if price.is_zero() {
+                continue;
+            }
+
+            let rules = self.rules(name);
+            let held = self.quantity_of(name);
+            let target = rules.round_down(total * (ratio / dec!(100)) / price);
+
+            if target > held {
+                let quantity = rules.round_down(target - held);
+                if rules.accepts(quantity, price) {
+                    suggestion.to_buy.insert(name.into(), quantity);
+                }
+            } else if target < held {
+                let mut quantity = rules.round_down(held - target);
+                if held - quantity < rules.dust {
+                    quantity = held;
+                }
+                if rules.accepts(quantity, price) {
+                    suggestion.to_sell.insert(name.into(), quantity);
+                }
+            }
+        }
+
+        suggestion
+    }
+}
+
+/// Sugerencia de rebalanceo con cantidades fraccionales.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FractionalSuggestion {
+    pub to_buy: BTreeMap<String, Decimal>,
+    pub to_sell: BTreeMap<String, Decimal>,
+}
+
+impl Localize for FractionalSuggestion {
+    fn localize(&self, language: Language) -> String {
+        let (sell, buy, nothing) = match language {
+            Language::Es => ("Vender", "Comprar", "No hay operaciones sugeridas."),
+            Language::En => ("Sell", "Buy", "No trades suggested."),
+        };
+
+        let lines: Vec<String> = self
+            .to_sell
+            .iter()
+            .map(|(name, q)| format!("{sell} {} {name}", q.normalize()))
+            .chain(
+                self.to_buy
+                    .iter()
+                    .map(|(name, q)| format!("{buy} {} {name}", q.normalize())),
+            )
+            .collect();
+
+        if lines.is_empty() {
+            nothing.into()
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+impl fmt::Display for FractionalSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target() -> PortfolioTarget {
+        PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("BTC", dec!(60000))),
+            (dec!(50), Stock::new("ETH", dec!(3000))),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rebalance_to_eight_decimals() {
+        let portfolio = CryptoPortfolio::new(target()).with_cash(dec!(1000));
+        let suggestion = portfolio.rebalance();
+
+        assert_eq!(suggestion.to_buy["BTC"], dec!(0.00833333));
+        assert_eq!(suggestion.to_buy["ETH"], dec!(0.16666666));
+        assert_eq!(
+            suggestion.localize(Language::En),
+            "Buy 0.00833333 BTC\nBuy 0.16666666 ETH"
+        );
+    }
+
+    #[test]
+    fn test_orders_below_minimum_are_skipped() {
+        let portfolio = CryptoPortfolio::new(target())
+            .with_holding(Stock::new("BTC", dec!(60000)), dec!(0.01))
+            .with_holding(Stock::new("ETH", dec!(3000)), dec!(0.19))
+            .with_rules("ETH", QuantityRules::new(4).with_min_order_value(dec!(10)));
+
+        // total = 600 + 570 = 1170; ETH objetivo 585 => 0.195, faltan 0.005 ETH = 15 USD
+        // BTC objetivo 585 => 0.00975, sobran 0.00025 BTC = 15 USD
+        let suggestion = portfolio.rebalance();
+        assert_eq!(suggestion.to_buy["ETH"], dec!(0.005));
+        assert_eq!(suggestion.to_sell["BTC"], dec!(0.00025));
+
+        let strict = portfolio.with_rules(
+            "BTC",
+            QuantityRules::default().with_min_order_value(dec!(20)),
+        );
+        assert!(!strict.rebalance().to_sell.contains_key("BTC"));
+    }
+
+    #[test]
+    fn test_dust_is_left_alone_or_swept() {
+        let portfolio = CryptoPortfolio::new(PortfolioTarget::new(Stock::new("BTC", dec!(60000))))
+            .with_holding(Stock::new("DOGE", dec!(0.1)), dec!(0.5))
+            .with_holding(Stock::new("BTC", dec!(60000)), dec!(1))
+            .with_rules("DOGE", QuantityRules::new(0).with_dust(dec!(1)));
+
+        assert_eq!(portfolio.dust(), vec![("DOGE", dec!(0.5))]);
+        assert!(portfolio.rebalance().to_sell.is_empty());
+
+        // una venta que dejaria menos que el polvo vende todo
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(1), Stock::new("BTC", dec!(60000))),
+            (dec!(99), Stock::new("ETH", dec!(3000))),
+        ])
+        .unwrap();
+        let portfolio = CryptoPortfolio::new(target)
+            .with_holding(Stock::new("BTC", dec!(60000)), dec!(0.01))
+            .with_rules("BTC", QuantityRules::default().with_dust(dec!(0.001)));
+
+        assert_eq!(portfolio.rebalance().to_sell["BTC"], dec!(0.01));
+    }
+}
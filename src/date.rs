@@ -0,0 +1,266 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
+/// Fecha de calendario (gregoriano, sin zona horaria).
+///
+/// No necesito horas ni zonas horarias para planificar ni para series de precios diarias, asi que
+/// prefiero un tipo chico y propio antes que sumar una dependencia como `chrono`. El orden de los
+/// campos importa: el `Ord` derivado compara año, mes y dia en ese orden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    /// Construye una fecha, o `None` si no existe (p. ej. 30 de febrero).
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        Some(Self { year, month, day })
+    }
+
+    /// Fecha actual en UTC segun el reloj del sistema.
+    #[cfg(feature = "std")]
+    pub fn today() -> Self {
+        Timestamp::now().date()
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// Dias desde el 1970-01-01 (algoritmo "days from civil" de Howard Hinnant).
+    pub fn days_since_epoch(&self) -> i64 {
+        let y = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let m = i64::from(self.month);
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        era * 146_097 + doe - 719_468
+    }
+
+    /// Inversa de `days_since_epoch`.
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+
+        Self { year, month, day }
+    }
+
+    pub fn add_days(&self, days: i64) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() + days)
+    }
+
+    /// Dia de la semana, de 0 (lunes) a 6 (domingo).
+    pub fn weekday(&self) -> u32 {
+        // el 1970-01-01 fue jueves
+        (self.days_since_epoch() + 3).rem_euclid(7) as u32
+    }
+
+    pub fn is_weekend(&self) -> bool {
+        self.weekday() >= 5
+    }
+
+    /// Suma dias habiles (lunes a viernes, sin feriados); desde un fin de semana el primer dia
+    /// habil es el lunes.
+    pub fn add_business_days(&self, days: u32) -> Self {
+        let mut date = *self;
+        let mut left = days;
+        while left > 0 {
+            date = date.add_days(1);
+            if !date.is_weekend() {
+                left -= 1;
+            }
+        }
+        date
+    }
+
+    /// Suma meses calendario; si el dia no existe en el mes de llegada se usa el ultimo dia
+    /// (31 de enero + 1 mes = 28/29 de febrero).
+    pub fn add_months(&self, months: i32) -> Self {
+        let index = self.year * 12 + self.month as i32 - 1 + months;
+        let year = index.div_euclid(12);
+        let month = (index.rem_euclid(12) + 1) as u32;
+        let day = self.day.min(days_in_month(year, month));
+
+        Self { year, month, day }
+    }
+
+    /// Dias entre `self` y `other` (negativo si `other` es anterior).
+    pub fn days_until(&self, other: Date) -> i64 {
+        other.days_since_epoch() - self.days_since_epoch()
+    }
+
+    /// Meses completos entre `self` y `other`; 0 si `other` no es posterior.
+    pub fn months_until(&self, other: Date) -> u32 {
+        let mut months = (other.year - self.year) * 12 + other.month as i32 - self.month as i32;
+        if other.day < self.day {
+            months -= 1;
+        }
+
+        months.max(0) as u32
+    }
+
+    /// Años (fraccionales) entre dos fechas usando la convencion Actual/365.
+    pub fn years_until(&self, other: Date) -> f64 {
+        self.days_until(other) as f64 / 365.0
+    }
+}
+
+/// Instante en el tiempo, en segundos desde 1970-01-01T00:00:00Z.
+///
+/// Para auditoria basta con resolucion de segundos; mas precision solo haria los registros mas
+/// dificiles de leer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn from_secs(secs: i64) -> Self {
+        Self(secs)
+    }
+
+    /// Medianoche UTC de una fecha.
+    pub fn from_date(date: Date) -> Self {
+        Self(date.days_since_epoch() * 86_400)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn now() -> Self {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        Self(secs)
+    }
+
+    pub fn as_secs(&self) -> i64 {
+        self.0
+    }
+
+    /// Fecha (UTC) a la que pertenece este instante.
+    pub fn date(&self) -> Date {
+        Date::from_days_since_epoch(self.0.div_euclid(86_400))
+    }
+}
+
+/// Formato ISO 8601 en UTC (`2024-03-15T10:30:00Z`).
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.rem_euclid(86_400);
+        write!(
+            f,
+            "{}T{:02}:{:02}:{:02}Z",
+            self.date(),
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        )
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Formato ISO 8601 (`2024-03-15`).
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    /// Lee fechas en formato ISO 8601 (`2024-03-15`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = || parts.next().and_then(|p| p.parse::<i64>().ok());
+
+        match (next(), next(), next()) {
+            (Some(y), Some(m), Some(d)) => Date::new(y as i32, m as u32, d as u32)
+                .ok_or_else(|| format!("Fecha invalida: {s}")),
+            _ => Err(format!("Fecha invalida: {s}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch_roundtrip() {
+        let date = Date::new(2024, 2, 29).unwrap();
+        assert_eq!(Date::from_days_since_epoch(date.days_since_epoch()), date);
+        assert_eq!(Date::new(1970, 1, 1).unwrap().days_since_epoch(), 0);
+        assert!(Date::new(2023, 2, 29).is_none());
+    }
+
+    #[test]
+    fn test_month_arithmetic() {
+        let jan31 = Date::new(2024, 1, 31).unwrap();
+        assert_eq!(jan31.add_months(1), Date::new(2024, 2, 29).unwrap());
+        assert_eq!(jan31.add_months(-2), Date::new(2023, 11, 30).unwrap());
+        assert_eq!(jan31.months_until(Date::new(2025, 1, 30).unwrap()), 11);
+        assert_eq!(jan31.months_until(Date::new(2025, 1, 31).unwrap()), 12);
+    }
+
+    #[test]
+    fn test_business_days() {
+        let friday = Date::new(2024, 5, 3).unwrap();
+        assert_eq!(friday.weekday(), 4);
+        assert_eq!(friday.add_business_days(2), Date::new(2024, 5, 7).unwrap());
+        assert!(friday.add_days(1).is_weekend());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let ts = Timestamp::from_secs(86_400 + 3_661);
+        assert_eq!(ts.date(), Date::new(1970, 1, 2).unwrap());
+        assert_eq!(ts.to_string(), "1970-01-02T01:01:01Z");
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let date: Date = "2024-03-05".parse().unwrap();
+        assert_eq!(date.to_string(), "2024-03-05");
+        assert!("2024-13-01".parse::<Date>().is_err());
+    }
+}
@@ -0,0 +1,74 @@
+//! Modo de prueba (dry-run).
+//!
+//! Para enchufar la libreria a un proceso productivo de a poco conviene poder correrlo todo sin
+//! que nada salga del proceso: con `set_run_mode(RunMode::DryRun)`, `rebalance_and_publish`,
+//! `apply_and_publish` y `reconcile_and_publish` (ver `publish`) calculan sobre una copia y dejan
+//! el portafolio como estaba, los publicadores de eventos reciben `EventPublisher::dry_run` en vez
+//! de `publish` (`LogPublisher` lo escribe con el prefijo `[dry-run]`) y los webhooks anotan el
+//! cuerpo sin mandar nada.
+//!
+//! El modo solo se revisa en esos bordes. `Portfolio::apply` y `Portfolio::reconcile` operan
+//! siempre, porque las simulaciones (backtest, liquidacion, planes de venta) las usan sobre sus
+//! propias copias y tienen que dar lo mismo en los dos modos.
+//!
+//! Como el idioma (ver `i18n`), es un ajuste de todo el proceso; los webhooks aceptan ademas un
+//! modo propio (`Webhook::with_run_mode`) y `publish::publish_in` recibe el modo explicito, que es
+//! lo que conviene usar en tests.
+//!
+//! ```
+//! use fintual_coding_challenge::costs::CostModel;
+//! use fintual_coding_challenge::dry_run::{RunMode, run_mode, set_run_mode};
+//! use fintual_coding_challenge::execution::Order;
+//! use fintual_coding_challenge::{Date, Portfolio, PortfolioTarget, Stock};
+//! use rust_decimal_macros::dec;
+//!
+//! set_run_mode(RunMode::DryRun);
+//! assert!(run_mode().is_dry_run());
+//!
+//! // las simulaciones siguen operando
+//! let mut portfolio = Portfolio::builder()
+//!     .with_cash(dec!(100))
+//!     .with_holding("META", 1, dec!(10))
+//!     .with_target(PortfolioTarget::new(Stock::new("META", dec!(10))))
+//!     .build()
+//!     .unwrap();
+//! let date = Date::new(2024, 1, 2).unwrap();
+//! portfolio.apply(&[Order::buy("META", 2)], date, &CostModel::free()).unwrap();
+//! assert_eq!(portfolio.cash(), dec!(80));
+//!
+//! set_run_mode(RunMode::Live);
+//! ```
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Si las operaciones que cambian estado o salen del proceso se hacen de verdad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RunMode {
+    #[default]
+    Live,
+
+    /// Se calcula todo, pero no se modifica nada ni se manda nada.
+    DryRun,
+}
+
+impl RunMode {
+    pub fn is_dry_run(self) -> bool {
+        self == RunMode::DryRun
+    }
+}
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Cambia el modo de todo el proceso.
+pub fn set_run_mode(mode: RunMode) {
+    DRY_RUN.store(mode.is_dry_run(), Ordering::Relaxed);
+}
+
+/// Modo actual; por defecto `Live`.
+pub fn run_mode() -> RunMode {
+    if DRY_RUN.load(Ordering::Relaxed) {
+        RunMode::DryRun
+    } else {
+        RunMode::Live
+    }
+}
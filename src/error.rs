@@ -0,0 +1,467 @@
+use crate::approval::ApprovalState;
+use crate::guard::PriceAnomaly;
+use crate::i18n::{Language, Localize, language};
+use crate::id::SuggestionId;
+use crate::models::Role;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::Decimal;
+
+/// Errores al construir o modificar un `PortfolioTarget`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    /// Las proporciones no suman exactamente 100%.
+    InvalidTotal(Decimal),
+
+    /// Algun stock tiene una proporcion de 0% o negativa.
+    NonPositiveWeight(String),
+
+    /// El ticker no pertenece al `Universe` contra el que se valido el objetivo.
+    UnknownTicker {
+        ticker: String,
+        did_you_mean: Option<String>,
+    },
+
+    /// Un portafolio modelo necesita un instrumento para un rol que no fue asignado.
+    MissingRole(Role),
+
+    /// Se quiso editar un ticker que no esta en el objetivo.
+    NotInTarget(String),
+
+    /// No hay precio para construir el stock de un ticker.
+    MissingPrice(String),
+
+    /// Los topes de peso (ver `caps`) no alcanzan para repartir el peso de los stocks: quedan
+    /// esos puntos porcentuales sin lugar.
+    InfeasibleCaps(Decimal),
+}
+
+impl Localize for TargetError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            TargetError::InvalidTotal(total) => match language {
+                Language::Es => format!("Los stocks objetivos no suman un 100% (suman {total}%)"),
+                Language::En => {
+                    format!("Target stocks do not add up to 100% (they add up to {total}%)")
+                }
+            },
+            TargetError::NonPositiveWeight(name) => match language {
+                Language::Es => format!("El stock {name} tiene valor 0 o negativo."),
+                Language::En => format!("Stock {name} has a zero or negative weight."),
+            },
+            TargetError::UnknownTicker {
+                ticker,
+                did_you_mean,
+            } => match (language, did_you_mean) {
+                (Language::Es, Some(other)) => {
+                    format!("El ticker {ticker} no existe. ¿Quisiste decir {other}?")
+                }
+                (Language::Es, None) => {
+                    format!("El ticker {ticker} no existe en el universo de inversion.")
+                }
+                (Language::En, Some(other)) => {
+                    format!("Unknown ticker {ticker}. Did you mean {other}?")
+                }
+                (Language::En, None) => {
+                    format!("Ticker {ticker} is not part of the investment universe.")
+                }
+            },
+            TargetError::MissingRole(role) => match language {
+                Language::Es => format!("El modelo requiere un instrumento para el rol {role}."),
+                Language::En => format!("The model requires an instrument for role {role}."),
+            },
+            TargetError::NotInTarget(ticker) => match language {
+                Language::Es => format!("El ticker {ticker} no esta en el objetivo."),
+                Language::En => format!("Ticker {ticker} is not part of the target."),
+            },
+            TargetError::MissingPrice(ticker) => match language {
+                Language::Es => format!("No hay precio para {ticker}."),
+                Language::En => format!("No price for {ticker}."),
+            },
+            TargetError::InfeasibleCaps(excess) => match language {
+                Language::Es => {
+                    format!("Los topes de peso no alcanzan: sobran {excess} puntos porcentuales.")
+                }
+                Language::En => format!("Weight caps are too tight: {excess} points left over."),
+            },
+        }
+    }
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for TargetError {}
+
+/// Errores al construir un `Portfolio` (ver `PortfolioBuilder`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortfolioError {
+    /// No se dio un objetivo.
+    MissingTarget,
+
+    /// Algun saldo es negativo.
+    NegativeCash(Decimal),
+
+    /// Algun holding tiene precio 0 o negativo.
+    NonPositivePrice(String),
+}
+
+impl Localize for PortfolioError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            PortfolioError::MissingTarget => match language {
+                Language::Es => "El portafolio necesita un objetivo.".into(),
+                Language::En => "The portfolio needs a target.".into(),
+            },
+            PortfolioError::NegativeCash(cash) => match language {
+                Language::Es => format!("La caja no puede ser negativa ({cash})."),
+                Language::En => format!("Cash cannot be negative ({cash})."),
+            },
+            PortfolioError::NonPositivePrice(ticker) => match language {
+                Language::Es => format!("El stock {ticker} tiene precio 0 o negativo."),
+                Language::En => format!("Stock {ticker} has a zero or negative price."),
+            },
+        }
+    }
+}
+
+impl fmt::Display for PortfolioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for PortfolioError {}
+
+/// Errores al validar una sugerencia antes de ejecutarla.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SuggestionError {
+    /// El portafolio cambio desde que se calculo la sugerencia.
+    Stale {
+        expected: SuggestionId,
+        actual: SuggestionId,
+    },
+
+    /// Paso mas tiempo del permitido desde que se calculo (ver `expiry`).
+    Expired { age_secs: i64, max_age_secs: i64 },
+
+    /// Un precio se movio mas de lo permitido desde que se calculo (ver `expiry`); `max_move`
+    /// en %.
+    PriceMoved {
+        ticker: String,
+        from: Decimal,
+        to: Decimal,
+        max_move: Decimal,
+    },
+}
+
+impl Localize for SuggestionError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            SuggestionError::Stale { expected, actual } => match language {
+                Language::Es => format!(
+                    "La sugerencia {expected} ya no corresponde al portafolio (estado actual {actual}); hay que recalcularla."
+                ),
+                Language::En => format!(
+                    "Suggestion {expected} no longer matches the portfolio (current state {actual}); it must be regenerated."
+                ),
+            },
+            SuggestionError::Expired {
+                age_secs,
+                max_age_secs,
+            } => match language {
+                Language::Es => format!(
+                    "La sugerencia tiene {age_secs} segundos y vence a los {max_age_secs}; hay que recalcularla."
+                ),
+                Language::En => format!(
+                    "The suggestion is {age_secs} seconds old and expires after {max_age_secs}; it must be regenerated."
+                ),
+            },
+            SuggestionError::PriceMoved {
+                ticker,
+                from,
+                to,
+                max_move,
+            } => match language {
+                Language::Es => format!(
+                    "{ticker} paso de {from} a {to}, mas del {max_move}% permitido; hay que recalcular la sugerencia."
+                ),
+                Language::En => format!(
+                    "{ticker} moved from {from} to {to}, more than the allowed {max_move}%; the suggestion must be regenerated."
+                ),
+            },
+        }
+    }
+}
+
+impl fmt::Display for SuggestionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for SuggestionError {}
+
+/// Errores al registrar un evento en un portafolio event-sourced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventError {
+    /// Se intento vender mas unidades de las que hay.
+    InsufficientUnits {
+        ticker: String,
+        held: usize,
+        requested: usize,
+    },
+
+    /// Se intento comprar un ticker que no esta ni en el objetivo ni en los holdings, asi que no
+    /// hay precio al que comprarlo.
+    UnknownTicker(String),
+
+    /// El monto de la operacion o la caja resultante no cabe en un `Decimal`.
+    Overflow(ArithmeticOverflow),
+}
+
+impl Localize for EventError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            EventError::InsufficientUnits {
+                ticker,
+                held,
+                requested,
+            } => match language {
+                Language::Es => {
+                    format!("No se pueden vender {requested} {ticker}: solo hay {held}.")
+                }
+                Language::En => format!("Cannot sell {requested} {ticker}: only {held} held."),
+            },
+            EventError::UnknownTicker(ticker) => match language {
+                Language::Es => format!("No hay precio para {ticker}."),
+                Language::En => format!("No price for {ticker}."),
+            },
+            EventError::Overflow(overflow) => overflow.localize(language),
+        }
+    }
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for EventError {}
+
+/// Un calculo de valorizacion o de rebalanceo que no cabe en un `Decimal` (unos 7.9e28), o que
+/// dividiria por un precio cero. Lo devuelven las variantes `checked_*` y `try_*` en vez de entrar
+/// en panico.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArithmeticOverflow {
+    /// El valor total del portafolio, o el de un ticker si se indica.
+    Valuation(Option<String>),
+
+    /// Las unidades objetivo de un ticker.
+    TargetUnits(String),
+
+    /// El monto de una operacion con un ticker, o su efecto en la caja.
+    Notional(String),
+
+    /// Hay que calcular unidades de un ticker cuyo precio es cero.
+    ZeroPrice(String),
+}
+
+impl Localize for ArithmeticOverflow {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (ArithmeticOverflow::Valuation(None), Language::Es) => {
+                "El valor total del portafolio es demasiado grande.".into()
+            }
+            (ArithmeticOverflow::Valuation(None), Language::En) => {
+                "The portfolio's total value is too large.".into()
+            }
+            (ArithmeticOverflow::Valuation(Some(ticker)), Language::Es) => {
+                format!("El valor de {ticker} es demasiado grande.")
+            }
+            (ArithmeticOverflow::Valuation(Some(ticker)), Language::En) => {
+                format!("The value of {ticker} is too large.")
+            }
+            (ArithmeticOverflow::TargetUnits(ticker), Language::Es) => {
+                format!("Las unidades objetivo de {ticker} son demasiado grandes.")
+            }
+            (ArithmeticOverflow::TargetUnits(ticker), Language::En) => {
+                format!("The target units of {ticker} are too large.")
+            }
+            (ArithmeticOverflow::Notional(ticker), Language::Es) => {
+                format!("El monto de la operacion con {ticker} es demasiado grande.")
+            }
+            (ArithmeticOverflow::Notional(ticker), Language::En) => {
+                format!("The amount of the trade in {ticker} is too large.")
+            }
+            (ArithmeticOverflow::ZeroPrice(ticker), Language::Es) => {
+                format!("El precio de {ticker} es cero.")
+            }
+            (ArithmeticOverflow::ZeroPrice(ticker), Language::En) => {
+                format!("The price of {ticker} is zero.")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ArithmeticOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for ArithmeticOverflow {}
+
+/// Errores de `PriceGuard`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PriceGuardError {
+    /// Hay cotizaciones retenidas sin confirmar ni descartar.
+    Unconfirmed(Vec<PriceAnomaly>),
+
+    /// No hay ninguna cotizacion retenida para ese ticker.
+    NothingPending(String),
+}
+
+impl Localize for PriceGuardError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            PriceGuardError::Unconfirmed(anomalies) => {
+                let moves: Vec<String> = anomalies
+                    .iter()
+                    .map(|a| {
+                        format!(
+                            "{} {} -> {} ({:+}%)",
+                            a.ticker,
+                            a.previous,
+                            a.quoted,
+                            a.change().round_dp(1)
+                        )
+                    })
+                    .collect();
+                match language {
+                    Language::Es => format!(
+                        "Hay cotizaciones sospechosas sin confirmar: {}.",
+                        moves.join(", ")
+                    ),
+                    Language::En => {
+                        format!(
+                            "Suspicious quotes are pending confirmation: {}.",
+                            moves.join(", ")
+                        )
+                    }
+                }
+            }
+            PriceGuardError::NothingPending(ticker) => match language {
+                Language::Es => format!("No hay una cotizacion retenida de {ticker}."),
+                Language::En => format!("No held quote for {ticker}."),
+            },
+        }
+    }
+}
+
+impl fmt::Display for PriceGuardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for PriceGuardError {}
+
+/// Errores al operar sobre los clientes de un `Advisor` (ver `clients`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientError {
+    /// No hay un cliente con ese id.
+    UnknownClient(String),
+
+    /// No hay un modelo con ese nombre.
+    UnknownModel(String),
+}
+
+impl Localize for ClientError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            ClientError::UnknownClient(id) => match language {
+                Language::Es => format!("No existe el cliente {id}."),
+                Language::En => format!("Unknown client {id}."),
+            },
+            ClientError::UnknownModel(name) => match language {
+                Language::Es => format!("No existe el modelo {name}."),
+                Language::En => format!("Unknown model {name}."),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for ClientError {}
+
+/// Errores del flujo de aprobacion de sugerencias (ver `approval`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalError {
+    /// El paso no se puede dar desde el estado actual (p. ej. ejecutar algo sin aprobar).
+    InvalidTransition {
+        from: ApprovalState,
+        to: ApprovalState,
+    },
+
+    /// El portafolio cambio desde que se calculo la sugerencia y no se puede ejecutar.
+    Stale(SuggestionError),
+
+    /// Fallo la conciliacion de lo ejecutado.
+    Event(EventError),
+}
+
+impl Localize for ApprovalError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            ApprovalError::InvalidTransition { from, to } => {
+                let (from, to) = (from.localize(language), to.localize(language));
+                match language {
+                    Language::Es => format!("Una sugerencia {from} no puede pasar a {to}."),
+                    Language::En => format!("A suggestion that is {from} cannot become {to}."),
+                }
+            }
+            ApprovalError::Stale(error) => error.localize(language),
+            ApprovalError::Event(error) => error.localize(language),
+        }
+    }
+}
+
+impl fmt::Display for ApprovalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for ApprovalError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_error_messages_in_both_languages() {
+        let error = TargetError::InvalidTotal(dec!(90));
+
+        assert_eq!(
+            error.localize(Language::Es),
+            "Los stocks objetivos no suman un 100% (suman 90%)"
+        );
+        assert_eq!(
+            error.localize(Language::En),
+            "Target stocks do not add up to 100% (they add up to 90%)"
+        );
+    }
+}
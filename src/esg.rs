@@ -0,0 +1,226 @@
+//! Filtros y puntajes ESG.
+//!
+//! Cada stock puede llevar un puntaje ESG (`Stock::with_esg_score`). Una `EsgPolicy` excluye o
+//! limita los que estan bajo un minimo, como etapa del rebalanceo (ver `pipeline`), y
+//! `Portfolio::esg_report` compara el puntaje ponderado del portafolio con el del objetivo.
+
+use crate::Portfolio;
+use crate::i18n::{Language, Localize, language};
+use crate::pipeline::{Plan, RebalanceStage};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Que hacer con los stocks de puntaje bajo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EsgPolicy {
+    /// Puntaje minimo; los stocks sin puntaje no se tocan.
+    pub min_score: Decimal,
+
+    /// Peso maximo (en %) de cada stock bajo el minimo; 0 los excluye.
+    pub max_weight: Decimal,
+}
+
+impl EsgPolicy {
+    pub fn exclude_below(min_score: Decimal) -> Self {
+        Self {
+            min_score,
+            max_weight: Decimal::ZERO,
+        }
+    }
+
+    pub fn cap_below(min_score: Decimal, max_weight: Decimal) -> Self {
+        Self {
+            min_score,
+            max_weight,
+        }
+    }
+
+    fn is_below(&self, score: Option<Decimal>) -> bool {
+        score.is_some_and(|score| score < self.min_score)
+    }
+}
+
+impl RebalanceStage for EsgPolicy {
+    fn name(&self) -> &str {
+        "esg"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        for (ticker, units) in plan.targets.iter_mut() {
+            let Some(stock) = portfolio.priced(ticker) else {
+                continue;
+            };
+            if self.is_below(stock.esg_score()) {
+                let max = plan.total * self.max_weight / Decimal::ONE_HUNDRED;
+                *units = (*units).min(max / stock.current_price());
+            }
+        }
+    }
+}
+
+/// Puntaje ESG del portafolio contra el del objetivo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EsgReport {
+    /// Promedio ponderado por peso de los stocks con puntaje; `None` si ninguno tiene.
+    pub portfolio_score: Option<Decimal>,
+    pub target_score: Option<Decimal>,
+
+    /// % del valor invertido que tiene puntaje.
+    pub coverage: Decimal,
+
+    /// Tickers del portafolio o del objetivo bajo el minimo de la politica.
+    pub below_minimum: Vec<String>,
+}
+
+/// Promedio ponderado y cobertura de pesos en %.
+fn weighted<'a>(
+    portfolio: &Portfolio,
+    weights: impl Iterator<Item = (&'a str, Decimal)>,
+) -> (Option<Decimal>, Decimal) {
+    let (mut total, mut scored, mut sum) = (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+    for (ticker, weight) in weights {
+        total += weight;
+        if let Some(score) = portfolio.priced(ticker).and_then(|s| s.esg_score()) {
+            scored += weight;
+            sum += score * weight;
+        }
+    }
+
+    let score = (!scored.is_zero()).then(|| sum / scored);
+    let coverage = if total.is_zero() {
+        Decimal::ZERO
+    } else {
+        scored / total * Decimal::ONE_HUNDRED
+    };
+    (score, coverage)
+}
+
+impl Portfolio {
+    /// Puntajes ESG del portafolio y del objetivo. Los puntajes salen del stock del objetivo si
+    /// esta ahi (como los precios), si no del holding.
+    pub fn esg_report(&self, policy: &EsgPolicy) -> EsgReport {
+        let weights = self.weights();
+        let (portfolio_score, coverage) = weighted(self, weights.iter().copied());
+        let targets = self.allocation().targets();
+        let (target_score, _) = weighted(self, targets.iter().map(|(w, s)| (s.name(), *w)));
+
+        let mut below_minimum: Vec<String> = weights
+            .iter()
+            .map(|(ticker, _)| *ticker)
+            .chain(targets.iter().map(|(_, s)| s.name()))
+            .filter(|ticker| {
+                self.priced(ticker)
+                    .is_some_and(|s| policy.is_below(s.esg_score()))
+            })
+            .map(String::from)
+            .collect();
+        below_minimum.sort();
+        below_minimum.dedup();
+
+        EsgReport {
+            portfolio_score,
+            target_score,
+            coverage,
+            below_minimum,
+        }
+    }
+}
+
+impl Localize for EsgReport {
+    fn localize(&self, language: Language) -> String {
+        let score = |s: Option<Decimal>| s.map_or("-".into(), |s| s.round_dp(2).to_string());
+        let (portfolio, target) = (score(self.portfolio_score), score(self.target_score));
+        let coverage = self.coverage.round_dp(2);
+
+        let mut lines = vec![match language {
+            Language::Es => {
+                format!("ESG: portafolio {portfolio}, objetivo {target} (cobertura {coverage}%)")
+            }
+            Language::En => {
+                format!("ESG: portfolio {portfolio}, target {target} (coverage {coverage}%)")
+            }
+        }];
+        if !self.below_minimum.is_empty() {
+            let tickers = self.below_minimum.join(", ");
+            lines.push(match language {
+                Language::Es => format!("Bajo el minimo: {tickers}"),
+                Language::En => format!("Below minimum: {tickers}"),
+            });
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for EsgReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::Pipeline;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (
+                dec!(50),
+                Stock::new("MSFT", dec!(10)).with_esg_score(dec!(80)),
+            ),
+            (
+                dec!(50),
+                Stock::new("XOM", dec!(10)).with_esg_score(dec!(20)),
+            ),
+        ])
+        .unwrap();
+
+        Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: target,
+        }
+    }
+
+    #[test]
+    fn test_low_scores_are_excluded_or_capped() {
+        let portfolio = portfolio();
+
+        let excluded = Pipeline::conservative()
+            .insert_before("round", EsgPolicy::exclude_below(dec!(50)))
+            .run(&portfolio);
+        assert_eq!(excluded.to_buy["MSFT"], 5);
+        assert!(!excluded.to_buy.contains_key("XOM"));
+
+        let capped = Pipeline::conservative()
+            .insert_before("round", EsgPolicy::cap_below(dec!(50), dec!(20)))
+            .run(&portfolio);
+        assert_eq!(capped.to_buy["XOM"], 2);
+    }
+
+    #[test]
+    fn test_weighted_score_against_target() {
+        let mut portfolio = portfolio();
+        portfolio.stocks = [
+            vec![Stock::new("MSFT", dec!(10)); 3],
+            vec![Stock::new("XOM", dec!(10)); 1],
+            vec![Stock::new("CASH_ETF", dec!(10)); 1],
+        ]
+        .concat();
+
+        let report = portfolio.esg_report(&EsgPolicy::exclude_below(dec!(50)));
+        // la caja no cuenta; CASH_ETF no tiene puntaje
+        assert_eq!(report.portfolio_score.unwrap().round_dp(2), dec!(65));
+        assert_eq!(report.target_score, Some(dec!(50)));
+        assert_eq!(report.coverage.round_dp(2), dec!(80));
+        assert_eq!(report.below_minimum, vec!["XOM".to_string()]);
+    }
+}
@@ -0,0 +1,255 @@
+//! Portafolio event-sourced.
+//!
+//! En vez de guardar solo el estado actual, se guarda la lista de cambios (eventos) que llevaron
+//! a el. Cualquier estado pasado se reconstruye re-aplicando los eventos desde el principio, lo
+//! que sirve para reportes ("¿como estaba el portafolio cuando se genero esta sugerencia?") y
+//! para depurar.
+
+use crate::error::EventError;
+use crate::{Portfolio, PortfolioTarget, Stock};
+use alloc::string::String;
+use alloc::vec::Vec;
+use rust_decimal::Decimal;
+
+/// Un cambio en el portafolio.
+#[derive(Debug, Clone)]
+pub enum PortfolioEvent {
+    /// Se compraron `units` unidades a `price` cada una.
+    Bought {
+        ticker: String,
+        units: usize,
+        price: Decimal,
+    },
+
+    /// Se vendieron `units` unidades.
+    Sold { ticker: String, units: usize },
+
+    /// Llego un nuevo precio para un ticker.
+    PriceUpdated { ticker: String, price: Decimal },
+
+    /// Se reemplazo el objetivo del portafolio.
+    TargetChanged(PortfolioTarget),
+}
+
+impl Portfolio {
+    /// Aplica un evento sobre el estado actual.
+    ///
+    /// Una venta de mas unidades de las que hay es un error; el portafolio queda intacto.
+    pub fn apply_event(&mut self, event: &PortfolioEvent) -> Result<(), EventError> {
+        match event {
+            PortfolioEvent::Bought {
+                ticker,
+                units,
+                price,
+            } => {
+                let stock = Stock::new(ticker, *price);
+                self.stocks.extend(core::iter::repeat_n(stock, *units));
+            }
+            PortfolioEvent::Sold { ticker, units } => {
+                let held = self.stocks.iter().filter(|s| s.name() == ticker).count();
+                if held < *units {
+                    return Err(EventError::InsufficientUnits {
+                        ticker: ticker.clone(),
+                        held,
+                        requested: *units,
+                    });
+                }
+
+                // se quitan las ultimas unidades compradas, partiendo por las que no tienen
+                // bloqueo (ver `lockup`) y luego las que se desbloquean antes; con el mismo
+                // bloqueo, las sin etiquetas antes que las etiquetadas (ver `metadata`)
+                let mut candidates: Vec<_> = self
+                    .stocks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, s)| s.name() == ticker)
+                    .map(|(index, s)| (s.sale_order(), core::cmp::Reverse(index)))
+                    .collect();
+                candidates.sort_unstable();
+
+                let mut sold = alloc::vec![false; self.stocks.len()];
+                for (_, core::cmp::Reverse(index)) in candidates.into_iter().take(*units) {
+                    sold[index] = true;
+                }
+                let mut sold = sold.into_iter();
+                self.stocks.retain(|_| !sold.next().unwrap());
+            }
+            PortfolioEvent::PriceUpdated { ticker, price } => {
+                self.update_price(ticker, *price);
+            }
+            PortfolioEvent::TargetChanged(target) => {
+                self.allocation = target.clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reconstruye un portafolio vacio con el objetivo inicial dado, aplicando los eventos en
+    /// orden.
+    pub fn replay(
+        initial_target: PortfolioTarget,
+        events: &[PortfolioEvent],
+    ) -> Result<Self, EventError> {
+        let mut portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: Vec::new(),
+            allocation: initial_target,
+        };
+
+        for event in events {
+            portfolio.apply_event(event)?;
+        }
+
+        Ok(portfolio)
+    }
+}
+
+/// Portafolio cuyo historial completo de cambios queda guardado.
+///
+/// La version `n` es el estado despues de aplicar los primeros `n` eventos; la version 0 es el
+/// portafolio vacio con el objetivo inicial.
+#[derive(Debug, Clone)]
+pub struct EventSourcedPortfolio {
+    initial_target: PortfolioTarget,
+    events: Vec<PortfolioEvent>,
+    current: Portfolio,
+}
+
+impl EventSourcedPortfolio {
+    pub fn new(initial_target: PortfolioTarget) -> Self {
+        let current = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: Vec::new(),
+            allocation: initial_target.clone(),
+        };
+
+        Self {
+            initial_target,
+            events: Vec::new(),
+            current,
+        }
+    }
+
+    /// Reconstruye a partir de un log de eventos existente.
+    pub fn from_events(
+        initial_target: PortfolioTarget,
+        events: Vec<PortfolioEvent>,
+    ) -> Result<Self, EventError> {
+        let current = Portfolio::replay(initial_target.clone(), &events)?;
+
+        Ok(Self {
+            initial_target,
+            events,
+            current,
+        })
+    }
+
+    /// Agrega un evento al log; si no se puede aplicar, no se guarda.
+    pub fn record(&mut self, event: PortfolioEvent) -> Result<(), EventError> {
+        self.current.apply_event(&event)?;
+        self.events.push(event);
+        Ok(())
+    }
+
+    pub fn events(&self) -> &[PortfolioEvent] {
+        &self.events
+    }
+
+    pub fn version(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Estado actual (siempre igual a `at_version(version())`, pero sin re-aplicar nada).
+    pub fn current(&self) -> &Portfolio {
+        &self.current
+    }
+
+    /// Estado del portafolio en una version pasada; `None` si la version no existe todavia.
+    pub fn at_version(&self, version: usize) -> Option<Portfolio> {
+        let events = self.events.get(..version)?;
+
+        // los eventos ya fueron validados al registrarlos, asi que re-aplicarlos no puede fallar
+        Portfolio::replay(self.initial_target.clone(), events).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn bought(ticker: &str, units: usize, price: Decimal) -> PortfolioEvent {
+        PortfolioEvent::Bought {
+            ticker: ticker.into(),
+            units,
+            price,
+        }
+    }
+
+    #[test]
+    fn test_time_travel() {
+        let mut portfolio =
+            EventSourcedPortfolio::new(PortfolioTarget::new(Stock::new("META", dec!(10))));
+
+        portfolio.record(bought("META", 3, dec!(10))).unwrap();
+        portfolio
+            .record(PortfolioEvent::PriceUpdated {
+                ticker: "META".into(),
+                price: dec!(20),
+            })
+            .unwrap();
+        portfolio
+            .record(PortfolioEvent::Sold {
+                ticker: "META".into(),
+                units: 1,
+            })
+            .unwrap();
+
+        assert_eq!(portfolio.version(), 3);
+        assert_eq!(portfolio.current().stocks().len(), 2);
+
+        let v1 = portfolio.at_version(1).unwrap();
+        assert_eq!(v1.stocks().len(), 3);
+        assert_eq!(v1.stocks()[0].current_price(), dec!(10));
+
+        assert!(portfolio.at_version(0).unwrap().stocks().is_empty());
+        assert!(portfolio.at_version(4).is_none());
+    }
+
+    #[test]
+    fn test_invalid_sale_is_not_recorded() {
+        let mut portfolio =
+            EventSourcedPortfolio::new(PortfolioTarget::new(Stock::new("META", dec!(10))));
+        portfolio.record(bought("META", 1, dec!(10))).unwrap();
+
+        let result = portfolio.record(PortfolioEvent::Sold {
+            ticker: "META".into(),
+            units: 2,
+        });
+
+        assert_eq!(
+            result,
+            Err(EventError::InsufficientUnits {
+                ticker: "META".into(),
+                held: 1,
+                requested: 2,
+            })
+        );
+        assert_eq!(portfolio.version(), 1);
+    }
+
+    #[test]
+    fn test_replay_matches_current_state() {
+        let target = PortfolioTarget::new(Stock::new("META", dec!(10)));
+        let events = vec![bought("META", 2, dec!(10)), bought("AAPL", 1, dec!(15))];
+
+        let rebuilt = EventSourcedPortfolio::from_events(target, events).unwrap();
+
+        assert_eq!(rebuilt.version(), 2);
+        assert_eq!(rebuilt.current().stocks().len(), 3);
+        assert_eq!(rebuilt.at_version(2).unwrap().stocks().len(), 3);
+    }
+}
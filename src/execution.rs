@@ -0,0 +1,403 @@
+//! Ejecucion de sugerencias.
+//!
+//! Una `RebalanceSuggestion` toma prestados los nombres del portafolio, asi que no se puede
+//! aplicar sobre el mismo portafolio mientras existe. `RebalanceSuggestion::orders` la convierte
+//! en una lista de `Order` que no presta nada y que `Portfolio::apply` ejecuta a los precios
+//! actuales, moviendo la caja y cobrando las comisiones de un `CostModel`.
+
+use crate::costs::CostModel;
+use crate::date::Date;
+use crate::error::{ArithmeticOverflow, EventError};
+use crate::events::PortfolioEvent;
+use crate::journal::{Journal, Transaction, TransactionKind};
+use crate::math::math_config;
+use crate::{Portfolio, RebalanceSuggestion, Stock};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use rust_decimal::Decimal;
+
+/// Lado de una orden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Side {
+    // las ventas van primero porque financian las compras
+    Sell,
+    Buy,
+}
+
+/// Una orden de compra o venta de unidades enteras.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Order {
+    pub ticker: String,
+    pub side: Side,
+    pub units: usize,
+}
+
+impl Order {
+    pub fn buy(ticker: &str, units: usize) -> Self {
+        Self {
+            ticker: ticker.into(),
+            side: Side::Buy,
+            units,
+        }
+    }
+
+    pub fn sell(ticker: &str, units: usize) -> Self {
+        Self {
+            ticker: ticker.into(),
+            side: Side::Sell,
+            units,
+        }
+    }
+}
+
+/// Una operacion sugerida con el precio al que se planifico.
+///
+/// Se ordena por monto (`notional`), y en empate por ticker, lado, unidades y precio, para poder
+/// listar las operaciones de mayor a menor de forma deterministica.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Trade {
+    pub ticker: String,
+    pub side: Side,
+    pub units: usize,
+    pub price: Decimal,
+}
+
+impl Trade {
+    pub fn notional(&self) -> Decimal {
+        self.price * Decimal::from(self.units)
+    }
+}
+
+impl Ord for Trade {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.notional()
+            .cmp(&other.notional())
+            .then_with(|| self.ticker.cmp(&other.ticker))
+            .then(self.side.cmp(&other.side))
+            .then(self.units.cmp(&other.units))
+            .then(self.price.cmp(&other.price))
+    }
+}
+
+impl PartialOrd for Trade {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Una orden ejecutada (quizas solo en parte) al precio que dio el mercado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub ticker: String,
+    pub side: Side,
+    pub units: usize,
+    pub price: Decimal,
+}
+
+impl Fill {
+    pub fn buy(ticker: &str, units: usize, price: Decimal) -> Self {
+        Self {
+            ticker: ticker.into(),
+            side: Side::Buy,
+            units,
+            price,
+        }
+    }
+
+    pub fn sell(ticker: &str, units: usize, price: Decimal) -> Self {
+        Self {
+            ticker: ticker.into(),
+            side: Side::Sell,
+            units,
+            price,
+        }
+    }
+}
+
+/// Lo que paso al ejecutar ordenes: cada operacion y cada comision como `Transaction`, listas
+/// para agregarse al diario de la cuenta.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Execution {
+    pub transactions: Vec<Transaction>,
+}
+
+impl Execution {
+    /// Comisiones cobradas en total.
+    pub fn commissions(&self) -> Decimal {
+        self.fees().map(|t| -t.cash_amount).sum()
+    }
+
+    /// Comisiones cobradas por las ordenes de un ticker.
+    pub fn commissions_for(&self, ticker: &str) -> Decimal {
+        self.fees()
+            .filter(|t| t.ticker.as_deref() == Some(ticker))
+            .map(|t| -t.cash_amount)
+            .sum()
+    }
+
+    fn fees(&self) -> impl Iterator<Item = &Transaction> {
+        self.transactions
+            .iter()
+            .filter(|t| t.kind == TransactionKind::Fee)
+    }
+
+    /// Agrega las transacciones al diario.
+    pub fn record_in(&self, journal: &mut Journal) {
+        for transaction in &self.transactions {
+            journal.push(transaction.clone());
+        }
+    }
+}
+
+impl RebalanceSuggestion<'_> {
+    /// Ordenes de la sugerencia: primero las ventas, cada lado ordenado por ticker.
+    pub fn orders(&self) -> Vec<Order> {
+        let mut orders: Vec<Order> = self
+            .to_sell
+            .iter()
+            .map(|(ticker, units)| Order::sell(ticker, *units))
+            .chain(
+                self.to_buy
+                    .iter()
+                    .map(|(ticker, units)| Order::buy(ticker, *units)),
+            )
+            .collect();
+        orders.sort_by(|a, b| a.side.cmp(&b.side).then(a.ticker.cmp(&b.ticker)));
+        orders
+    }
+}
+
+impl Portfolio {
+    /// Operaciones de una sugerencia con los precios actuales (a la punta que corresponde si hay,
+    /// ver `quote`), en el orden de `orders`.
+    pub fn trades(&self, suggestion: &RebalanceSuggestion<'_>) -> Vec<Trade> {
+        suggestion
+            .orders()
+            .into_iter()
+            .filter_map(|order| {
+                let price = self.priced(&order.ticker)?.price_for(order.side);
+                Some(Trade {
+                    ticker: order.ticker,
+                    side: order.side,
+                    units: order.units,
+                    price,
+                })
+            })
+            .collect()
+    }
+
+    /// Stock al que se compraria `ticker`: el del objetivo si esta ahi (que es el que recibe las
+    /// cotizaciones), si no el de los holdings.
+    pub(crate) fn priced(&self, ticker: &str) -> Option<&Stock> {
+        self.allocation
+            .targets()
+            .iter()
+            .map(|(_, stock)| stock)
+            .chain(self.stocks.iter())
+            .find(|stock| stock.name() == ticker)
+    }
+
+    /// Ejecuta las ordenes en orden a los precios actuales (al bid o al ask si hay puntas, ver
+    /// `quote`) el dia `date`: una venta agrega su valor a la caja y una compra lo descuenta, y
+    /// cada orden descuenta ademas su comision segun `costs` (la caja puede quedar negativa si
+    /// las ordenes no cuadran). Asi la caja despues de ejecutar es la que mostraria la cuenta del
+    /// broker.
+    ///
+    /// Las unidades compradas quedan con su costo y fecha (ver `lots`); las ventas sacan primero
+    /// las unidades sin bloqueo y sin etiquetas, y entre iguales las compradas mas recientemente
+    /// (ver `PortfolioEvent::Sold`). Si una orden falla, las anteriores ya quedaron aplicadas.
+    ///
+    /// No mira el modo dry-run: lo usan tambien las simulaciones (backtest, liquidacion), que
+    /// tienen que operar igual. El modo se revisa en los bordes (ver `dry_run`).
+    pub fn apply(
+        &mut self,
+        orders: &[Order],
+        date: Date,
+        costs: &CostModel,
+    ) -> Result<Execution, EventError> {
+        let mut execution = Execution::default();
+
+        for order in orders {
+            let price = self
+                .priced(&order.ticker)
+                .ok_or_else(|| EventError::UnknownTicker(order.ticker.clone()))?
+                .price_for(order.side);
+            let fill = Fill {
+                ticker: order.ticker.clone(),
+                side: order.side,
+                units: order.units,
+                price,
+            };
+            self.execute(&fill, date, costs, &mut execution)?;
+        }
+
+        Ok(execution)
+    }
+
+    /// Aplica una ejecucion al precio de `fill` y la anota en `execution`.
+    pub(crate) fn execute(
+        &mut self,
+        fill: &Fill,
+        date: Date,
+        costs: &CostModel,
+        execution: &mut Execution,
+    ) -> Result<(), EventError> {
+        let stock = self
+            .priced(&fill.ticker)
+            .ok_or_else(|| EventError::UnknownTicker(fill.ticker.clone()))?
+            .clone();
+        let overflow = || EventError::Overflow(ArithmeticOverflow::Notional(fill.ticker.clone()));
+        let amount = fill
+            .price
+            .checked_mul(Decimal::from(fill.units))
+            .map(|amount| math_config().notional(amount))
+            .ok_or_else(overflow)?;
+        // se revisa antes de tocar nada, para no dejar la venta a medio aplicar
+        let cash = match fill.side {
+            Side::Sell => self.cash.checked_add(amount),
+            Side::Buy => self.cash.checked_sub(amount),
+        }
+        .ok_or_else(overflow)?;
+
+        let kind = match fill.side {
+            Side::Sell => {
+                self.apply_event(&PortfolioEvent::Sold {
+                    ticker: fill.ticker.clone(),
+                    units: fill.units,
+                })?;
+                self.cash = cash;
+                TransactionKind::Sell
+            }
+            Side::Buy => {
+                // se clona el stock (y no se usa `PortfolioEvent::Bought`) para no perder los
+                // datos de un bono o la moneda
+                // la unidad nueva no hereda el bloqueo ni las etiquetas de la que se clono
+                let mut unit = stock.with_basis(fill.price, date);
+                unit.locked_until = None;
+                unit.metadata = None;
+                self.stocks.extend(core::iter::repeat_n(unit, fill.units));
+                self.cash = cash;
+                TransactionKind::Buy
+            }
+        };
+        execution.transactions.push(Transaction::trade(
+            date,
+            kind,
+            &fill.ticker,
+            Decimal::from(fill.units),
+            fill.price,
+        ));
+
+        let commission = math_config().notional(costs.commission(fill.units, fill.price));
+        if !commission.is_zero() {
+            self.cash = self.cash.checked_sub(commission).ok_or_else(overflow)?;
+            execution.transactions.push(Transaction {
+                ticker: Some(fill.ticker.clone()),
+                ..Transaction::cash(date, TransactionKind::Fee, -commission)
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortfolioTarget;
+    use rust_decimal_macros::dec;
+
+    fn today() -> Date {
+        Date::new(2024, 5, 2).unwrap()
+    }
+
+    #[test]
+    fn test_apply_suggestion_moves_cash() {
+        let mut portfolio = Portfolio {
+            cash: dec!(5),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("GOOG", dec!(50)); 2],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(30))),
+        };
+
+        let orders = portfolio.rebalance_portfolio().orders();
+        assert_eq!(orders, vec![Order::sell("GOOG", 2), Order::buy("META", 3)]);
+
+        let costs = CostModel::free().with_per_trade(dec!(1));
+        let execution = portfolio.apply(&orders, today(), &costs).unwrap();
+        assert_eq!(portfolio.stocks().len(), 3);
+        assert_eq!(portfolio.cash(), dec!(13));
+
+        assert_eq!(execution.commissions(), dec!(2));
+        assert_eq!(execution.commissions_for("META"), dec!(1));
+        let mut journal = Journal::new();
+        execution.record_in(&mut journal);
+        assert_eq!(journal.len(), 4);
+        assert_eq!(journal.cash_balance(), dec!(8));
+        assert_eq!(portfolio.stocks()[0].basis().unwrap().acquired, today());
+        assert!(portfolio.rebalance_portfolio().orders().is_empty());
+    }
+
+    #[test]
+    fn test_trades_sort_by_notional() {
+        let portfolio = Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("GOOG", dec!(5)); 2],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(10))),
+        };
+        let suggestion = portfolio.rebalance_portfolio();
+        assert_eq!(suggestion, portfolio.rebalance_portfolio());
+
+        let mut trades = portfolio.trades(&suggestion);
+        trades.sort_by(|a, b| b.cmp(a));
+        assert_eq!(trades[0].ticker, "META");
+        assert_eq!(trades[0].notional(), dec!(110));
+        assert_eq!(trades[1].notional(), dec!(10));
+
+        let unique: std::collections::HashSet<Stock> = portfolio.stocks().iter().cloned().collect();
+        assert_eq!(unique.len(), 1);
+    }
+
+    #[test]
+    fn test_apply_rejects_unknown_or_oversold() {
+        let mut portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(30))),
+        };
+
+        assert_eq!(
+            portfolio
+                .apply(&[Order::buy("XYZ", 1)], today(), &CostModel::free())
+                .unwrap_err(),
+            EventError::UnknownTicker("XYZ".into())
+        );
+        assert!(
+            portfolio
+                .apply(&[Order::sell("META", 1)], today(), &CostModel::free())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_apply_reports_cash_overflow() {
+        let mut portfolio = Portfolio {
+            cash: Decimal::MAX,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("META", dec!(30))],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(30))),
+        };
+
+        assert_eq!(
+            portfolio
+                .apply(&[Order::sell("META", 1)], today(), &CostModel::free())
+                .unwrap_err(),
+            EventError::Overflow(ArithmeticOverflow::Notional("META".into()))
+        );
+        assert_eq!(portfolio.stocks().len(), 1);
+        assert_eq!(portfolio.cash(), Decimal::MAX);
+    }
+}
@@ -0,0 +1,216 @@
+//! Vencimiento de sugerencias.
+//!
+//! `Portfolio::verify_suggestion` rechaza una sugerencia apenas cambia cualquier precio, lo que
+//! con cotizaciones en vivo obliga a recalcular todo el tiempo. Una `ExpiringSuggestion` guarda
+//! los precios con que se calculo y una `ExpiryPolicy`: sigue valiendo mientras los holdings, la
+//! caja y el objetivo sean los mismos, ningun precio se haya movido mas del porcentaje permitido
+//! y no haya pasado el plazo. Si no, hay que recalcularla.
+
+use crate::clock::Clock;
+use crate::date::Timestamp;
+use crate::error::SuggestionError;
+use crate::execution::Order;
+use crate::id::SuggestionId;
+use crate::{Portfolio, RebalanceStrategy, RebalanceSuggestion};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use rust_decimal::prelude::*;
+
+/// Cuando deja de valer una sugerencia. Sin limites, vale hasta que cambien los holdings, la caja
+/// o el objetivo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExpiryPolicy {
+    /// Movimiento maximo de cualquier precio, en %.
+    pub max_price_move: Option<Decimal>,
+
+    /// Antiguedad maxima, en segundos.
+    pub max_age_secs: Option<i64>,
+}
+
+impl ExpiryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_price_move(mut self, percent: Decimal) -> Self {
+        self.max_price_move = Some(percent.abs());
+        self
+    }
+
+    pub fn with_max_age(mut self, secs: i64) -> Self {
+        self.max_age_secs = Some(secs);
+        self
+    }
+}
+
+/// Una sugerencia con los precios y la hora en que se calculo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiringSuggestion {
+    pub id: SuggestionId,
+    pub orders: Vec<Order>,
+    pub created: Timestamp,
+    pub policy: ExpiryPolicy,
+
+    /// Holdings, caja y objetivo, sin precios (ver `Portfolio::structure_id`).
+    structure: SuggestionId,
+
+    /// Precio de cada ticker del portafolio o del objetivo al calcularla.
+    prices: BTreeMap<String, Decimal>,
+}
+
+impl ExpiringSuggestion {
+    /// Guarda `suggestion`, calculada ahora sobre `portfolio`.
+    pub fn new(
+        portfolio: &Portfolio,
+        suggestion: &RebalanceSuggestion<'_>,
+        policy: ExpiryPolicy,
+        clock: impl Clock,
+    ) -> Self {
+        Self {
+            id: suggestion.id,
+            orders: suggestion.orders(),
+            created: clock.now(),
+            policy,
+            structure: portfolio.structure_id(RebalanceStrategy::Conservative),
+            prices: prices(portfolio),
+        }
+    }
+
+    /// Precio de `ticker` al calcularla.
+    pub fn price(&self, ticker: &str) -> Option<Decimal> {
+        self.prices.get(ticker).copied()
+    }
+}
+
+fn prices(portfolio: &Portfolio) -> BTreeMap<String, Decimal> {
+    let held = portfolio.stocks().iter();
+    let targeted = portfolio.allocation().targets().iter().map(|(_, s)| s);
+    held.chain(targeted)
+        .filter_map(|stock| {
+            let price = portfolio.priced(stock.name())?.current_price();
+            Some((stock.name().to_string(), price))
+        })
+        .collect()
+}
+
+impl Portfolio {
+    /// Confirma que `suggestion` todavia se puede ejecutar segun su `ExpiryPolicy`: que no haya
+    /// vencido, que los holdings, la caja y el objetivo sigan iguales y que ningun precio se
+    /// haya movido mas de lo permitido. A diferencia de `verify_suggestion`, un movimiento chico
+    /// de precios no la invalida.
+    pub fn verify_expiring(
+        &self,
+        suggestion: &ExpiringSuggestion,
+        clock: impl Clock,
+    ) -> Result<(), SuggestionError> {
+        let policy = suggestion.policy;
+        if let Some(max_age_secs) = policy.max_age_secs {
+            let age_secs = clock.now().as_secs() - suggestion.created.as_secs();
+            if age_secs > max_age_secs {
+                return Err(SuggestionError::Expired {
+                    age_secs,
+                    max_age_secs,
+                });
+            }
+        }
+
+        let structure = self.structure_id(RebalanceStrategy::Conservative);
+        if structure != suggestion.structure {
+            return Err(SuggestionError::Stale {
+                expected: suggestion.structure,
+                actual: structure,
+            });
+        }
+
+        let Some(max_move) = policy.max_price_move else {
+            return Ok(());
+        };
+        for (ticker, to) in prices(self) {
+            let Some(from) = suggestion.price(&ticker).filter(|p| !p.is_zero()) else {
+                continue;
+            };
+            let moved = ((to - from) / from).abs() * Decimal::ONE_HUNDRED;
+            if moved > max_move {
+                return Err(SuggestionError::PriceMoved {
+                    ticker,
+                    from,
+                    to,
+                    max_move,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+    use crate::date::Date;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_small_moves_keep_the_suggestion_alive() {
+        let clock = TestClock::at_date(Date::new(2024, 3, 1).unwrap());
+        let mut portfolio = Portfolio {
+            stocks: vec![Stock::new("KO", dec!(10)); 5],
+            allocation: PortfolioTarget::new(Stock::new("PEP", dec!(20))),
+            cash: dec!(0),
+            foreign_cash: Default::default(),
+        };
+        let policy = ExpiryPolicy::new()
+            .with_max_price_move(dec!(2))
+            .with_max_age(15 * 60);
+        let suggestion =
+            ExpiringSuggestion::new(&portfolio, &portfolio.rebalance_portfolio(), policy, &clock);
+
+        portfolio.update_price("KO", dec!(10.15));
+        assert!(portfolio.verify_suggestion(suggestion.id).is_err());
+        assert_eq!(portfolio.verify_expiring(&suggestion, &clock), Ok(()));
+
+        portfolio.update_price("PEP", dec!(20.5));
+        assert_eq!(
+            portfolio.verify_expiring(&suggestion, &clock),
+            Err(SuggestionError::PriceMoved {
+                ticker: "PEP".into(),
+                from: dec!(20),
+                to: dec!(20.5),
+                max_move: dec!(2),
+            })
+        );
+
+        portfolio.update_price("PEP", dec!(20));
+        clock.advance(16 * 60);
+        assert!(matches!(
+            portfolio.verify_expiring(&suggestion, &clock),
+            Err(SuggestionError::Expired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_changed_holdings_invalidate_the_suggestion() {
+        let clock = TestClock::new(Timestamp::from_secs(0));
+        let mut portfolio = Portfolio {
+            stocks: vec![Stock::new("KO", dec!(10)); 5],
+            allocation: PortfolioTarget::new(Stock::new("KO", dec!(10))),
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+        };
+        let suggestion = ExpiringSuggestion::new(
+            &portfolio,
+            &portfolio.rebalance_portfolio(),
+            ExpiryPolicy::new(),
+            &clock,
+        );
+
+        portfolio.cash = dec!(50);
+        assert!(matches!(
+            portfolio.verify_expiring(&suggestion, &clock),
+            Err(SuggestionError::Stale { .. })
+        ));
+    }
+}
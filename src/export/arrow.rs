@@ -0,0 +1,232 @@
+//! Exportacion a Arrow y Parquet (feature `arrow`).
+//!
+//! La idea es poder analizar los resultados desde Python o DuckDB sin tener que parsear nada:
+//! `duckdb -c "select * from 'holdings.parquet'"`.
+//!
+//! Los montos se exportan como `Decimal128(38, 10)` para no perder precision en el camino; las
+//! fechas como `Date32` (dias desde 1970-01-01).
+
+use crate::Portfolio;
+use crate::date::Date;
+use crate::events::PortfolioEvent;
+use arrow_array::{ArrayRef, Date32Array, Decimal128Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+use rust_decimal::Decimal;
+use std::io::Write;
+use std::sync::Arc;
+
+const PRECISION: u8 = 38;
+const SCALE: u32 = 10;
+
+fn decimal_type() -> DataType {
+    DataType::Decimal128(PRECISION, SCALE as i8)
+}
+
+/// Convierte a un entero con `SCALE` decimales implicitos.
+fn to_i128(value: Decimal) -> i128 {
+    let mut value = value.round_dp(SCALE);
+    value.rescale(SCALE);
+    value.mantissa()
+}
+
+fn decimals(values: impl IntoIterator<Item = Option<Decimal>>) -> Result<ArrayRef, ArrowError> {
+    let array: Decimal128Array = values.into_iter().map(|v| v.map(to_i128)).collect();
+    Ok(Arc::new(
+        array.with_precision_and_scale(PRECISION, SCALE as i8)?,
+    ))
+}
+
+/// Una fila por ticker: unidades, precio, valor y % del portafolio.
+pub fn holdings_batch(portfolio: &Portfolio) -> Result<RecordBatch, ArrowError> {
+    let weights = portfolio.weights();
+
+    let mut tickers = Vec::new();
+    let mut units = Vec::new();
+    let mut prices = Vec::new();
+    let mut values = Vec::new();
+    for (ticker, _) in &weights {
+        let held: Vec<_> = portfolio
+            .stocks()
+            .iter()
+            .filter(|s| s.name() == *ticker)
+            .collect();
+        let value: Decimal = held.iter().map(|s| s.current_price()).sum();
+
+        tickers.push(*ticker);
+        units.push(held.len() as u64);
+        prices.push(Some(value / Decimal::from(held.len())));
+        values.push(Some(value));
+    }
+
+    let schema = Schema::new(vec![
+        Field::new("ticker", DataType::Utf8, false),
+        Field::new("units", DataType::UInt64, false),
+        Field::new("price", decimal_type(), false),
+        Field::new("value", decimal_type(), false),
+        Field::new("weight_pct", decimal_type(), false),
+    ]);
+
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(StringArray::from(tickers)),
+            Arc::new(UInt64Array::from(units)),
+            decimals(prices)?,
+            decimals(values)?,
+            decimals(weights.iter().map(|(_, w)| Some(*w)))?,
+        ],
+    )
+}
+
+/// Una fila por evento del log de transacciones, en orden. Las columnas que no aplican a un
+/// tipo de evento quedan en null.
+pub fn transactions_batch(events: &[PortfolioEvent]) -> Result<RecordBatch, ArrowError> {
+    let mut kinds = Vec::new();
+    let mut tickers = Vec::new();
+    let mut units = Vec::new();
+    let mut prices = Vec::new();
+
+    for event in events {
+        let (kind, ticker, quantity, price) = match event {
+            PortfolioEvent::Bought {
+                ticker,
+                units,
+                price,
+            } => (
+                "buy",
+                Some(ticker.as_str()),
+                Some(*units as u64),
+                Some(*price),
+            ),
+            PortfolioEvent::Sold { ticker, units } => {
+                ("sell", Some(ticker.as_str()), Some(*units as u64), None)
+            }
+            PortfolioEvent::PriceUpdated { ticker, price } => {
+                ("price", Some(ticker.as_str()), None, Some(*price))
+            }
+            PortfolioEvent::TargetChanged(_) => ("target", None, None, None),
+        };
+
+        kinds.push(kind);
+        tickers.push(ticker);
+        units.push(quantity);
+        prices.push(price);
+    }
+
+    let schema = Schema::new(vec![
+        Field::new("sequence", DataType::UInt64, false),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("ticker", DataType::Utf8, true),
+        Field::new("units", DataType::UInt64, true),
+        Field::new("price", decimal_type(), true),
+    ]);
+
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(UInt64Array::from_iter_values(0..events.len() as u64)),
+            Arc::new(StringArray::from(kinds)),
+            Arc::new(StringArray::from(tickers)),
+            Arc::new(UInt64Array::from(units)),
+            decimals(prices)?,
+        ],
+    )
+}
+
+/// Serie de valor del portafolio en el tiempo (curva de equity de un backtest o de
+/// valorizaciones diarias).
+pub fn equity_curve_batch(points: &[(Date, Decimal)]) -> Result<RecordBatch, ArrowError> {
+    let schema = Schema::new(vec![
+        Field::new("date", DataType::Date32, false),
+        Field::new("value", decimal_type(), false),
+    ]);
+
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(Date32Array::from_iter_values(
+                points.iter().map(|(d, _)| d.days_since_epoch() as i32),
+            )),
+            decimals(points.iter().map(|(_, v)| Some(*v)))?,
+        ],
+    )
+}
+
+/// Escribe un batch como archivo Parquet.
+pub fn write_parquet<W: Write + Send>(batch: &RecordBatch, writer: W) -> Result<(), ParquetError> {
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use arrow_array::Array;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_holdings_batch() {
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![
+                Stock::new("META", dec!(10)),
+                Stock::new("META", dec!(10)),
+                Stock::new("AAPL", dec!(30)),
+            ],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(10))),
+        };
+
+        let batch = holdings_batch(&portfolio).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let values = batch
+            .column_by_name("value")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert_eq!(values.value_as_string(0), "30.0000000000");
+    }
+
+    #[test]
+    fn test_transactions_and_parquet_output() {
+        let events = vec![
+            PortfolioEvent::Bought {
+                ticker: "META".into(),
+                units: 2,
+                price: dec!(10.5),
+            },
+            PortfolioEvent::Sold {
+                ticker: "META".into(),
+                units: 1,
+            },
+        ];
+
+        let batch = transactions_batch(&events).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert!(batch.column_by_name("price").unwrap().is_null(1));
+
+        let mut bytes = Vec::new();
+        write_parquet(&batch, &mut bytes).unwrap();
+        assert_eq!(&bytes[..4], b"PAR1");
+    }
+
+    #[test]
+    fn test_equity_curve_batch() {
+        let day = Date::new(1970, 1, 11).unwrap();
+        let batch = equity_curve_batch(&[(day, dec!(100))]).unwrap();
+
+        let dates = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Date32Array>()
+            .unwrap();
+        assert_eq!(dates.value(0), 10);
+    }
+}
@@ -0,0 +1,266 @@
+//! Ordenes en FIX 4.4 (`NewOrderSingle`, `35=D`).
+//!
+//! Los brokers institucionales reciben ordenes por FIX, no por planilla. `FixSession` guarda los
+//! datos de la sesion (comp IDs, cuenta, numero de secuencia) y escribe cada operacion de una
+//! sugerencia como un mensaje completo, con `BodyLength` y `CheckSum` calculados, listo para
+//! mandarlo por el socket. No manejamos la sesion en si (logon, heartbeats, reenvios): eso es
+//! del motor FIX de quien lo use.
+
+use crate::date::Timestamp;
+use crate::execution::{Side, Trade};
+use crate::i18n::{Language, Localize, language};
+use crate::{Portfolio, RebalanceSuggestion};
+use std::fmt::{self, Write};
+
+/// Separador de campos de FIX.
+pub const SOH: char = '\u{1}';
+
+/// Errores al armar un mensaje FIX.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixError {
+    /// El valor de un campo (su tag) trae un `SOH`, que cortaria el mensaje en ese punto.
+    SohInField(u32),
+}
+
+impl Localize for FixError {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (FixError::SohInField(tag), Language::Es) => {
+                format!("El campo FIX {tag} contiene el separador SOH.")
+            }
+            (FixError::SohInField(tag), Language::En) => {
+                format!("FIX field {tag} contains the SOH separator.")
+            }
+        }
+    }
+}
+
+impl fmt::Display for FixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for FixError {}
+
+/// Tipo de orden (tag 40).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrdType {
+    /// A mercado (`40=1`).
+    #[default]
+    Market,
+
+    /// Limite al precio de la sugerencia (`40=2`, con `44=<precio>`).
+    Limit,
+}
+
+/// Datos de la sesion FIX con los que se arman los mensajes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixSession {
+    sender_comp_id: String,
+    target_comp_id: String,
+    account: Option<String>,
+    ord_type: OrdType,
+    next_seq_num: u64,
+}
+
+impl FixSession {
+    pub fn new(sender_comp_id: &str, target_comp_id: &str) -> Self {
+        Self {
+            sender_comp_id: sender_comp_id.into(),
+            target_comp_id: target_comp_id.into(),
+            account: None,
+            ord_type: OrdType::default(),
+            next_seq_num: 1,
+        }
+    }
+
+    /// Cuenta del cliente en el broker (tag 1).
+    pub fn with_account(mut self, account: &str) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    pub fn with_ord_type(mut self, ord_type: OrdType) -> Self {
+        self.ord_type = ord_type;
+        self
+    }
+
+    /// Numero de secuencia (tag 34) del proximo mensaje, para seguir una sesion ya abierta.
+    pub fn with_seq_num(mut self, seq_num: u64) -> Self {
+        self.next_seq_num = seq_num;
+        self
+    }
+
+    pub fn next_seq_num(&self) -> u64 {
+        self.next_seq_num
+    }
+
+    /// `NewOrderSingle` de una operacion. Usa y avanza el numero de secuencia; `cl_ord_id` es el
+    /// identificador que el broker devuelve en los reportes de ejecucion. Si algun valor trae un
+    /// `SOH` devuelve el error sin gastar el numero de secuencia.
+    pub fn new_order_single(
+        &mut self,
+        trade: &Trade,
+        cl_ord_id: &str,
+        at: Timestamp,
+    ) -> Result<String, FixError> {
+        let time = utc_timestamp(at);
+        let mut body = String::new();
+        field(&mut body, 35, "D")?;
+        field(&mut body, 49, &self.sender_comp_id)?;
+        field(&mut body, 56, &self.target_comp_id)?;
+        field(&mut body, 34, &self.next_seq_num.to_string())?;
+        field(&mut body, 52, &time)?;
+        field(&mut body, 11, cl_ord_id)?;
+        if let Some(account) = &self.account {
+            field(&mut body, 1, account)?;
+        }
+        field(&mut body, 21, "1")?;
+        field(&mut body, 55, &trade.ticker)?;
+        let side = match trade.side {
+            Side::Buy => "1",
+            Side::Sell => "2",
+        };
+        field(&mut body, 54, side)?;
+        field(&mut body, 60, &time)?;
+        field(&mut body, 38, &trade.units.to_string())?;
+        match self.ord_type {
+            OrdType::Market => field(&mut body, 40, "1")?,
+            OrdType::Limit => {
+                field(&mut body, 40, "2")?;
+                field(&mut body, 44, &trade.price.normalize().to_string())?;
+            }
+        }
+
+        let mut message = String::new();
+        field(&mut message, 8, "FIX.4.4")?;
+        field(&mut message, 9, &body.len().to_string())?;
+        message.push_str(&body);
+        let checksum = message
+            .bytes()
+            .fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        field(&mut message, 10, &format!("{checksum:03}"))?;
+        self.next_seq_num += 1;
+        Ok(message)
+    }
+
+    /// Un mensaje por operacion de la sugerencia, en el orden de `orders` (ventas primero). Los
+    /// `ClOrdID` son `<prefijo>-<n>` con `n` desde 1. Si un mensaje falla no se gasta ningun
+    /// numero de secuencia.
+    pub fn suggestion_messages(
+        &mut self,
+        portfolio: &Portfolio,
+        suggestion: &RebalanceSuggestion<'_>,
+        cl_ord_id_prefix: &str,
+        at: Timestamp,
+    ) -> Result<Vec<String>, FixError> {
+        let first = self.next_seq_num;
+        let messages: Result<Vec<String>, FixError> = portfolio
+            .trades(suggestion)
+            .iter()
+            .enumerate()
+            .map(|(i, trade)| {
+                self.new_order_single(trade, &format!("{cl_ord_id_prefix}-{}", i + 1), at)
+            })
+            .collect();
+        if messages.is_err() {
+            self.next_seq_num = first;
+        }
+        messages
+    }
+}
+
+fn field(out: &mut String, tag: u32, value: &str) -> Result<(), FixError> {
+    if value.contains(SOH) {
+        return Err(FixError::SohInField(tag));
+    }
+    let _ = write!(out, "{tag}={value}{SOH}");
+    Ok(())
+}
+
+/// `UTCTimestamp` de FIX: `YYYYMMDD-HH:MM:SS`.
+fn utc_timestamp(at: Timestamp) -> String {
+    let date = at.date();
+    let secs = at.as_secs().rem_euclid(86_400);
+    format!(
+        "{:04}{:02}{:02}-{:02}:{:02}:{:02}",
+        date.year(),
+        date.month(),
+        date.day(),
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Date;
+    use rust_decimal_macros::dec;
+
+    fn trade() -> Trade {
+        Trade {
+            ticker: "META".into(),
+            side: Side::Buy,
+            units: 10,
+            price: dec!(250.50),
+        }
+    }
+
+    fn at() -> Timestamp {
+        Timestamp::from_secs(
+            Timestamp::from_date(Date::new(2024, 3, 15).unwrap()).as_secs() + 37_800,
+        )
+    }
+
+    #[test]
+    fn test_writes_header_body_and_valid_checksum() {
+        let mut session = FixSession::new("FINTUAL", "BROKER").with_account("ACC-1");
+        let message = session.new_order_single(&trade(), "ORD-1", at()).unwrap();
+        let fields: Vec<&str> = message.trim_end_matches(SOH).split(SOH).collect();
+
+        assert_eq!(fields[0], "8=FIX.4.4");
+        assert!(fields.contains(&"35=D"));
+        assert!(fields.contains(&"49=FINTUAL"));
+        assert!(fields.contains(&"56=BROKER"));
+        assert!(fields.contains(&"34=1"));
+        assert!(fields.contains(&"52=20240315-10:30:00"));
+        assert!(fields.contains(&"1=ACC-1"));
+        assert!(fields.contains(&"55=META"));
+        assert!(fields.contains(&"54=1"));
+        assert!(fields.contains(&"38=10"));
+        assert!(fields.contains(&"40=1"));
+        assert_eq!(session.next_seq_num(), 2);
+
+        let (before, checksum) = message.split_at(message.len() - 7);
+        let body_start = message.find("35=").unwrap();
+        let body_length: usize = fields[1].trim_start_matches("9=").parse().unwrap();
+        assert_eq!(body_length, before.len() - body_start);
+        let sum = before.bytes().map(u32::from).sum::<u32>() % 256;
+        assert_eq!(checksum, format!("10={sum:03}{SOH}"));
+    }
+
+    #[test]
+    fn test_limit_orders_carry_the_price() {
+        let mut session = FixSession::new("A", "B")
+            .with_ord_type(OrdType::Limit)
+            .with_seq_num(7);
+        let message = session.new_order_single(&trade(), "X", at()).unwrap();
+
+        assert!(message.contains(&format!("{SOH}34=7{SOH}")));
+        assert!(message.contains(&format!("{SOH}40=2{SOH}44=250.5{SOH}")));
+        assert!(!message.contains(&format!("{SOH}1=")));
+    }
+
+    #[test]
+    fn test_values_with_soh_are_rejected() {
+        let mut session = FixSession::new("A", "B").with_account("ACC\u{1}1=OTRA");
+        assert_eq!(
+            session.new_order_single(&trade(), "X", at()),
+            Err(FixError::SohInField(1))
+        );
+        assert_eq!(session.next_seq_num(), 1);
+    }
+}
@@ -0,0 +1,334 @@
+//! Contabilidad en texto plano: Beancount y ledger-cli.
+//!
+//! Quien lleva sus finanzas personales en Beancount o ledger quiere que lo que hace el motor
+//! (compras, ventas, dividendos) quede en sus libros sin transcribirlo a mano. `PlainTextLedger`
+//! escribe el `Journal` como transacciones balanceadas y los holdings de un `Portfolio` como
+//! precios y aserciones de saldo, para que el propio Beancount/ledger verifique que los libros
+//! cuadran con el portafolio.
+
+use crate::Portfolio;
+use crate::date::Date;
+use crate::instrument::Instrument;
+use crate::journal::{Journal, Transaction, TransactionKind};
+use crate::money::Currency;
+use rust_decimal::Decimal;
+use std::fmt::Write;
+
+/// Formato de salida.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Beancount,
+    Ledger,
+}
+
+/// Nombres de las cuentas contables que se usan. Las posiciones van en una subcuenta por ticker
+/// de `holdings` (p. ej. `Assets:Broker:META`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountNames {
+    pub cash: String,
+    pub holdings: String,
+    pub dividends: String,
+    pub interest: String,
+    pub fees: String,
+    pub capital_gains: String,
+
+    /// Contrapartida de depositos y retiros (la cuenta bancaria de origen).
+    pub transfers: String,
+}
+
+impl Default for AccountNames {
+    fn default() -> Self {
+        Self {
+            cash: "Assets:Broker:Cash".into(),
+            holdings: "Assets:Broker".into(),
+            dividends: "Income:Dividends".into(),
+            interest: "Income:Interest".into(),
+            fees: "Expenses:Fees".into(),
+            capital_gains: "Income:CapitalGains".into(),
+            transfers: "Assets:Bank".into(),
+        }
+    }
+}
+
+/// Escritor de libros en texto plano.
+#[derive(Debug, Clone)]
+pub struct PlainTextLedger {
+    dialect: Dialect,
+    accounts: AccountNames,
+
+    /// Moneda de la caja del portafolio.
+    currency: Currency,
+}
+
+impl PlainTextLedger {
+    pub fn new(dialect: Dialect, currency: Currency) -> Self {
+        Self {
+            dialect,
+            accounts: AccountNames::default(),
+            currency,
+        }
+    }
+
+    pub fn with_accounts(mut self, accounts: AccountNames) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    /// Precio de cada ticker y asercion de las unidades y la caja al dia `date`.
+    pub fn holdings(&self, portfolio: &Portfolio, date: Date) -> String {
+        let mut out = String::new();
+        let mut seen = Vec::new();
+        for stock in portfolio.stocks() {
+            if seen.contains(&stock.name()) {
+                continue;
+            }
+            seen.push(stock.name());
+            let price = amount(stock.current_price(), stock.currency().code());
+            match self.dialect {
+                Dialect::Beancount => {
+                    let _ = writeln!(out, "{date} price {} {price}", stock.name());
+                }
+                Dialect::Ledger => {
+                    let _ = writeln!(out, "P {} {} {price}", self.date(date), stock.name());
+                }
+            }
+        }
+
+        let cash = (
+            self.accounts.cash.clone(),
+            amount(portfolio.cash(), self.currency.code()),
+        );
+        let balances = portfolio
+            .iter_holdings()
+            .map(|(ticker, units)| (self.position(ticker), amount(Decimal::from(units), ticker)))
+            .chain(std::iter::once(cash));
+
+        match self.dialect {
+            Dialect::Beancount => {
+                for (account, balance) in balances {
+                    let _ = writeln!(out, "{date} balance {account} {balance}");
+                }
+            }
+            Dialect::Ledger => {
+                // ledger no tiene una directiva de saldo: se usa una transaccion vacia con
+                // aserciones en cada posting
+                let _ = writeln!(out, "{} * Saldos", self.date(date));
+                for (account, balance) in balances {
+                    let commodity = balance.split(' ').next_back().unwrap_or_default();
+                    let _ = writeln!(out, "    {account}  0 {commodity} = {balance}");
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Todas las transacciones del diario, en orden.
+    pub fn transactions(&self, journal: &Journal) -> String {
+        journal
+            .transactions()
+            .iter()
+            .map(|t| self.transaction(t))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn transaction(&self, t: &Transaction) -> String {
+        let code = self.currency.code();
+        let ticker = t.ticker.as_deref().unwrap_or_default();
+        let cash = amount(t.cash_amount, code);
+        let minus = |value: Decimal| amount(-value, code);
+
+        let (narration, postings): (String, Vec<(String, String)>) = match t.kind {
+            TransactionKind::Buy | TransactionKind::Sell => {
+                let buy = t.kind == TransactionKind::Buy;
+                let units = if buy { t.units } else { -t.units };
+                let position = match (self.dialect, buy) {
+                    (Dialect::Beancount, true) => {
+                        format!("{} {{{}}}", amount(units, ticker), amount(t.price, code))
+                    }
+                    (Dialect::Beancount, false) => {
+                        format!("{} {{}} @ {}", amount(units, ticker), amount(t.price, code))
+                    }
+                    (Dialect::Ledger, _) => {
+                        format!("{} @ {}", amount(units, ticker), amount(t.price, code))
+                    }
+                };
+
+                let mut postings = vec![
+                    (self.position(ticker), position),
+                    (self.accounts.cash.clone(), cash),
+                ];
+                // lo que el efecto en caja no explica por unidades * precio es comision
+                let fee = -(t.cash_amount + units * t.price);
+                if !fee.is_zero() {
+                    postings.push((self.accounts.fees.clone(), amount(fee, code)));
+                }
+                if !buy && self.dialect == Dialect::Beancount {
+                    postings.push((self.accounts.capital_gains.clone(), String::new()));
+                }
+
+                let verb = if buy { "Compra" } else { "Venta" };
+                (format!("{verb} {ticker}"), postings)
+            }
+            TransactionKind::Dividend => (
+                format!("Dividendo {ticker}"),
+                vec![
+                    (self.accounts.cash.clone(), cash),
+                    (
+                        format!("{}:{ticker}", self.accounts.dividends),
+                        minus(t.cash_amount),
+                    ),
+                ],
+            ),
+            TransactionKind::Interest | TransactionKind::Fee => {
+                let (narration, account) = if t.kind == TransactionKind::Fee {
+                    ("Comision", &self.accounts.fees)
+                } else {
+                    ("Intereses", &self.accounts.interest)
+                };
+                let narration = match &t.ticker {
+                    Some(ticker) => format!("{narration} {ticker}"),
+                    None => narration.to_string(),
+                };
+                (
+                    narration,
+                    vec![
+                        (self.accounts.cash.clone(), cash),
+                        (account.clone(), minus(t.cash_amount)),
+                    ],
+                )
+            }
+            TransactionKind::Deposit | TransactionKind::Withdrawal => {
+                let narration = if t.kind == TransactionKind::Deposit {
+                    "Deposito"
+                } else {
+                    "Retiro"
+                };
+                (
+                    narration.to_string(),
+                    vec![
+                        (self.accounts.cash.clone(), cash),
+                        (self.accounts.transfers.clone(), minus(t.cash_amount)),
+                    ],
+                )
+            }
+        };
+
+        let mut out = match self.dialect {
+            Dialect::Beancount => format!("{} * \"{narration}\"\n", t.date),
+            Dialect::Ledger => format!("{} * {narration}\n", self.date(t.date)),
+        };
+        for (account, value) in postings {
+            let _ = if value.is_empty() {
+                writeln!(out, "  {account}")
+            } else {
+                writeln!(out, "  {account}  {value}")
+            };
+        }
+        out
+    }
+
+    fn position(&self, ticker: &str) -> String {
+        format!("{}:{ticker}", self.accounts.holdings)
+    }
+
+    fn date(&self, date: Date) -> String {
+        match self.dialect {
+            Dialect::Beancount => date.to_string(),
+            Dialect::Ledger => date.to_string().replace('-', "/"),
+        }
+    }
+}
+
+fn amount(value: Decimal, commodity: &str) -> String {
+    format!("{} {commodity}", value.normalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn d(day: u32) -> Date {
+        Date::new(2024, 1, day).unwrap()
+    }
+
+    #[test]
+    fn test_beancount_transactions() {
+        let journal: Journal = vec![
+            Transaction::cash(d(1), TransactionKind::Deposit, dec!(100)),
+            Transaction {
+                cash_amount: dec!(-21),
+                ..Transaction::trade(d(2), TransactionKind::Buy, "META", dec!(2), dec!(10))
+            },
+            Transaction {
+                ticker: Some("META".into()),
+                ..Transaction::cash(d(3), TransactionKind::Dividend, dec!(0.5))
+            },
+            Transaction::trade(d(4), TransactionKind::Sell, "META", dec!(1), dec!(12)),
+        ]
+        .into_iter()
+        .collect();
+
+        let text = PlainTextLedger::new(Dialect::Beancount, Currency::Usd).transactions(&journal);
+        assert_eq!(
+            text,
+            "\
+2024-01-01 * \"Deposito\"
+  Assets:Broker:Cash  100 USD
+  Assets:Bank  -100 USD
+
+2024-01-02 * \"Compra META\"
+  Assets:Broker:META  2 META {10 USD}
+  Assets:Broker:Cash  -21 USD
+  Expenses:Fees  1 USD
+
+2024-01-03 * \"Dividendo META\"
+  Assets:Broker:Cash  0.5 USD
+  Income:Dividends:META  -0.5 USD
+
+2024-01-04 * \"Venta META\"
+  Assets:Broker:META  -1 META {} @ 12 USD
+  Assets:Broker:Cash  12 USD
+  Income:CapitalGains
+"
+        );
+    }
+
+    #[test]
+    fn test_ledger_holdings_and_trade() {
+        let portfolio = Portfolio {
+            cash: dec!(5),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("META", dec!(10)); 2],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(10))),
+        };
+        let ledger = PlainTextLedger::new(Dialect::Ledger, Currency::Usd);
+
+        assert_eq!(
+            ledger.holdings(&portfolio, d(31)),
+            "\
+P 2024/01/31 META 10 USD
+2024/01/31 * Saldos
+    Assets:Broker:META  0 META = 2 META
+    Assets:Broker:Cash  0 USD = 5 USD
+"
+        );
+
+        let journal: Journal = vec![Transaction::trade(
+            d(2),
+            TransactionKind::Buy,
+            "META",
+            dec!(2),
+            dec!(10),
+        )]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            ledger.transactions(&journal),
+            "2024/01/02 * Compra META\n  Assets:Broker:META  2 META @ 10 USD\n  Assets:Broker:Cash  -20 USD\n"
+        );
+    }
+}
@@ -0,0 +1,29 @@
+//! Exportadores a formatos externos.
+//!
+//! Cada formato vive en su propio submodulo y, si necesita dependencias pesadas, detras de su
+//! propia feature para que quien no lo use no tenga que compilarlas.
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "fix")]
+pub mod fix;
+pub mod ledger;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+
+/// CRC-32 (IEEE), el que usan zip y PNG.
+#[cfg(any(feature = "xlsx", feature = "png"))]
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
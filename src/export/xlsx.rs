@@ -0,0 +1,384 @@
+//! Exportacion a planillas Excel (`.xlsx`).
+//!
+//! Mucha gente termina queriendo una planilla para mirar y anotar. Un `.xlsx` es un zip con
+//! varios XML; como solo necesitamos texto y numeros, lo escribo a mano (zip sin compresion,
+//! strings inline, sin estilos) en vez de traer una dependencia. `portfolio_workbook` arma el
+//! libro estandar con holdings, drift, sugerencia y desempeño.
+
+use crate::Portfolio;
+use crate::date::Date;
+use crate::execution::Side;
+use crate::export::crc32;
+use crate::i18n::Language;
+use rust_decimal::Decimal;
+use std::io;
+use std::path::Path;
+
+/// Valor de una celda.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cell {
+    Text(String),
+    Number(Decimal),
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::Text(text.into())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::Text(text)
+    }
+}
+
+impl From<Decimal> for Cell {
+    fn from(number: Decimal) -> Self {
+        Cell::Number(number)
+    }
+}
+
+impl From<usize> for Cell {
+    fn from(number: usize) -> Self {
+        Cell::Number(Decimal::from(number))
+    }
+}
+
+/// Una hoja: nombre y filas de celdas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sheet {
+    pub name: String,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Sheet {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn with_row<C: Into<Cell>>(mut self, row: impl IntoIterator<Item = C>) -> Self {
+        self.rows.push(row.into_iter().map(Into::into).collect());
+        self
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+        );
+        for (r, row) in self.rows.iter().enumerate() {
+            xml.push_str(&format!(r#"<row r="{}">"#, r + 1));
+            for (c, cell) in row.iter().enumerate() {
+                let reference = format!("{}{}", column_name(c), r + 1);
+                match cell {
+                    Cell::Text(text) => xml.push_str(&format!(
+                        r#"<c r="{reference}" t="inlineStr"><is><t>{}</t></is></c>"#,
+                        escape(text)
+                    )),
+                    Cell::Number(number) => xml.push_str(&format!(
+                        r#"<c r="{reference}"><v>{}</v></c>"#,
+                        number.normalize()
+                    )),
+                }
+            }
+            xml.push_str("</row>");
+        }
+        xml.push_str("</sheetData></worksheet>");
+        xml
+    }
+}
+
+/// Libro con varias hojas.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Workbook {
+    sheets: Vec<Sheet>,
+}
+
+impl Workbook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_sheet(mut self, sheet: Sheet) -> Self {
+        self.sheets.push(sheet);
+        self
+    }
+
+    pub fn sheets(&self) -> &[Sheet] {
+        &self.sheets
+    }
+
+    /// Bytes del archivo `.xlsx`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut sheet_types = String::new();
+        let mut sheet_entries = String::new();
+        let mut sheet_rels = String::new();
+        for i in 1..=self.sheets.len() {
+            sheet_types.push_str(&format!(
+                r#"<Override PartName="/xl/worksheets/sheet{i}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
+            ));
+            sheet_entries.push_str(&format!(
+                r#"<sheet name="{}" sheetId="{i}" r:id="rId{i}"/>"#,
+                escape(&self.sheets[i - 1].name)
+            ));
+            sheet_rels.push_str(&format!(
+                r#"<Relationship Id="rId{i}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{i}.xml"/>"#
+            ));
+        }
+
+        let mut zip = ZipWriter::default();
+        zip.add(
+            "[Content_Types].xml",
+            &format!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>{sheet_types}</Types>"#
+            ),
+        );
+        zip.add(
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+        );
+        zip.add(
+            "xl/workbook.xml",
+            &format!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{sheet_entries}</sheets></workbook>"#
+            ),
+        );
+        zip.add(
+            "xl/_rels/workbook.xml.rels",
+            &format!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{sheet_rels}</Relationships>"#
+            ),
+        );
+        for (i, sheet) in self.sheets.iter().enumerate() {
+            zip.add(
+                &format!("xl/worksheets/sheet{}.xml", i + 1),
+                &sheet.to_xml(),
+            );
+        }
+
+        zip.finish()
+    }
+
+    pub fn write_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+}
+
+/// Libro estandar de un portafolio: holdings, drift contra el objetivo, sugerencia de rebalanceo
+/// y, si se entrega, la curva de valor (p. ej. la de un backtest).
+pub fn portfolio_workbook(
+    portfolio: &Portfolio,
+    equity_curve: &[(Date, Decimal)],
+    language: Language,
+) -> Workbook {
+    let es = language == Language::Es;
+    let t = |es_text: &'static str, en_text: &'static str| if es { es_text } else { en_text };
+
+    let mut holdings = Sheet::new(t("Posiciones", "Holdings")).with_row([
+        "Ticker",
+        t("Unidades", "Units"),
+        t("Precio", "Price"),
+        t("Valor", "Value"),
+        t("Peso %", "Weight %"),
+    ]);
+    for (ticker, units) in portfolio.iter_holdings() {
+        let value = portfolio.value_of(ticker);
+        holdings = holdings.with_row([
+            Cell::from(ticker),
+            Cell::from(units),
+            Cell::from(value / Decimal::from(units)),
+            Cell::from(value),
+            Cell::from(portfolio.weight_of(ticker).round_dp(4)),
+        ]);
+    }
+    holdings = holdings.with_row([
+        Cell::from(t("Caja", "Cash")),
+        Cell::from(""),
+        Cell::from(""),
+        Cell::from(portfolio.cash()),
+    ]);
+
+    let report = portfolio.verify_against_target(Decimal::ZERO);
+    let mut drift = Sheet::new("Drift").with_row([
+        "Ticker",
+        t("Objetivo %", "Target %"),
+        t("Actual %", "Actual %"),
+        t("Diferencia", "Difference"),
+    ]);
+    for d in &report.drifts {
+        drift = drift.with_row([
+            Cell::from(d.ticker.as_str()),
+            Cell::from(d.target),
+            Cell::from(d.actual.round_dp(4)),
+            Cell::from(d.difference().round_dp(4)),
+        ]);
+    }
+
+    let suggestion = portfolio.rebalance_portfolio();
+    let mut trades = Sheet::new(t("Sugerencia", "Suggestion")).with_row([
+        t("Operacion", "Side"),
+        "Ticker",
+        t("Unidades", "Units"),
+        t("Precio", "Price"),
+        t("Monto", "Amount"),
+    ]);
+    for trade in portfolio.trades(&suggestion) {
+        let side = match trade.side {
+            Side::Buy => t("Compra", "Buy"),
+            Side::Sell => t("Venta", "Sell"),
+        };
+        trades = trades.with_row([
+            Cell::from(side),
+            Cell::from(trade.ticker.as_str()),
+            Cell::from(trade.units),
+            Cell::from(trade.price),
+            Cell::from(trade.notional()),
+        ]);
+    }
+
+    let mut performance = Sheet::new(t("Desempeño", "Performance"))
+        .with_row([t("Fecha", "Date"), t("Valor", "Value")]);
+    for (date, value) in equity_curve {
+        performance = performance.with_row([Cell::from(date.to_string()), Cell::from(*value)]);
+    }
+
+    Workbook::new()
+        .with_sheet(holdings)
+        .with_sheet(drift)
+        .with_sheet(trades)
+        .with_sheet(performance)
+}
+
+/// Nombre de columna estilo Excel: 0 -> A, 25 -> Z, 26 -> AA.
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Zip minimo: entradas sin compresion (metodo 0), que es todo lo que Excel necesita.
+#[derive(Default)]
+struct ZipWriter {
+    data: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, content: &str) {
+        let (name, content) = (name.as_bytes(), content.as_bytes());
+        let crc = crc32(content);
+        let offset = self.data.len() as u32;
+        let size = content.len() as u32;
+
+        // version, flags, metodo, hora y fecha (1980-01-01)
+        let common = |out: &mut Vec<u8>| {
+            out.extend_from_slice(&20u16.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(&0x21u16.to_le_bytes());
+            out.extend_from_slice(&crc.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&size.to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+        };
+
+        self.data.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        common(&mut self.data);
+        self.data.extend_from_slice(name);
+        self.data.extend_from_slice(content);
+
+        self.central
+            .extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes());
+        common(&mut self.central);
+        // comentario, disco, atributos internos y externos
+        self.central.extend_from_slice(&[0; 10]);
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name);
+
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let central_offset = self.data.len() as u32;
+        let central_size = self.central.len() as u32;
+        self.data.append(&mut self.central);
+
+        self.data.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        self.data.extend_from_slice(&[0; 4]);
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&central_size.to_le_bytes());
+        self.data.extend_from_slice(&central_offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_zip_helpers() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(escape("P&G <x>"), "P&amp;G &lt;x&gt;");
+    }
+
+    #[test]
+    fn test_portfolio_workbook() {
+        let portfolio = Portfolio {
+            cash: dec!(20),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("META", dec!(10)); 2],
+            allocation: PortfolioTarget::new(Stock::new("AAPL", dec!(20))),
+        };
+        let date = Date::new(2024, 1, 31).unwrap();
+        let book = portfolio_workbook(&portfolio, &[(date, dec!(40))], Language::Es);
+
+        let names: Vec<_> = book.sheets().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Posiciones", "Drift", "Sugerencia", "Desempeño"]);
+        assert_eq!(
+            book.sheets()[2].rows[1],
+            vec![
+                Cell::from("Venta"),
+                Cell::from("META"),
+                Cell::from(2usize),
+                Cell::from(dec!(10)),
+                Cell::from(dec!(20)),
+            ]
+        );
+
+        let bytes = book.to_bytes();
+        assert!(bytes.starts_with(b"PK\x03\x04"));
+        // 4 partes fijas + 4 hojas
+        assert_eq!(
+            &bytes[bytes.len() - 12..bytes.len() - 10],
+            &8u16.to_le_bytes()
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Fondos mutuos con valor cuota diario.
+//!
+//! Un fondo no tiene precio durante el dia: las ordenes se acumulan y se ejecutan al valor cuota
+//! (NAV) que se calcula al cierre. Las que llegan despues de la hora de corte (o un fin de
+//! semana) van al valor cuota del dia habil siguiente, asi que el precio con el que se calcula un
+//! rebalanceo (el ultimo valor cuota conocido) es solo una estimacion. `Stock::execution_date`
+//! dice a que valor cuota va una orden, y el backtest ejecuta las ordenes de fondos al precio del
+//! dia siguiente al que se decidieron.
+
+use crate::clock::Clock;
+use crate::date::{Date, Timestamp};
+use crate::execution::Order;
+use crate::{InstrumentKind, Portfolio, Stock};
+use alloc::vec::Vec;
+use rust_decimal::Decimal;
+
+impl Stock {
+    /// Un fondo con valor cuota `nav` y hora de corte `cut_off` en minutos desde medianoche UTC
+    /// (p. ej. `18 * 60` para las 18:00).
+    pub fn fund(name: &str, nav: Decimal, cut_off: u32) -> Self {
+        Self {
+            kind: InstrumentKind::Fund { cut_off },
+            ..Stock::new(name, nav)
+        }
+    }
+
+    pub fn is_fund(&self) -> bool {
+        matches!(self.kind, InstrumentKind::Fund { .. })
+    }
+
+    /// Dia cuyo precio paga una orden puesta en `placed`: el mismo dia para una accion o un bono;
+    /// para un fondo, el mismo dia si es habil y antes del corte, si no el dia habil siguiente.
+    pub fn execution_date(&self, placed: Timestamp) -> Date {
+        let date = placed.date();
+        let InstrumentKind::Fund { cut_off } = self.kind else {
+            return date;
+        };
+
+        let minutes = placed.as_secs().rem_euclid(86_400) / 60;
+        if date.is_weekend() || minutes >= i64::from(cut_off) {
+            date.add_business_days(1)
+        } else {
+            date
+        }
+    }
+}
+
+impl Portfolio {
+    /// Dia cuyo precio paga cada orden si se pone en `placed` (ver `Stock::execution_date`). Los
+    /// tickers que no se conocen se ejecutan ese mismo dia.
+    pub fn execution_dates<'a>(
+        &self,
+        orders: &'a [Order],
+        placed: Timestamp,
+    ) -> Vec<(&'a Order, Date)> {
+        orders
+            .iter()
+            .map(|order| {
+                let date = self
+                    .priced(&order.ticker)
+                    .map_or(placed.date(), |stock| stock.execution_date(placed));
+                (order, date)
+            })
+            .collect()
+    }
+
+    /// `execution_dates` para ordenes puestas ahora segun `clock`.
+    pub fn execution_dates_now<'a>(
+        &self,
+        orders: &'a [Order],
+        clock: &impl Clock,
+    ) -> Vec<(&'a Order, Date)> {
+        self.execution_dates(orders, clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_orders_after_cut_off_go_to_next_nav() {
+        let fund = Stock::fund("RISKY", dec!(1500), 18 * 60);
+        // viernes 3 de mayo de 2024
+        let friday = Timestamp::from_date(Date::new(2024, 5, 3).unwrap());
+        let at = |hour: i64| Timestamp::from_secs(friday.as_secs() + hour * 3600);
+
+        assert_eq!(fund.execution_date(at(10)), Date::new(2024, 5, 3).unwrap());
+        assert_eq!(fund.execution_date(at(19)), Date::new(2024, 5, 6).unwrap());
+        assert_eq!(
+            fund.execution_date(at(24 + 10)),
+            Date::new(2024, 5, 6).unwrap()
+        );
+        assert_eq!(fund.current_price(), dec!(1500));
+
+        let stock = Stock::new("SPY", dec!(500));
+        assert_eq!(stock.execution_date(at(19)), Date::new(2024, 5, 3).unwrap());
+    }
+}
@@ -0,0 +1,186 @@
+//! Sugerencias de aporte o retiro en vez de ventas.
+//!
+//! Hay usuarios que prefieren llegar al objetivo depositando en lugar de vender (por impuestos o
+//! simplemente por costumbre). `Portfolio::funding_suggestion` calcula el portafolio mas chico
+//! que respeta el objetivo sin vender nada y dice cuanta caja falta (o sobra) para llegar a el, y
+//! que comprar con ella.
+
+use crate::Portfolio;
+use crate::i18n::{Language, Localize, language};
+use crate::instrument::Instrument;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Cuanto depositar (o cuanto se puede retirar) y que comprar para llegar al objetivo sin vender.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FundingSuggestion {
+    /// Positivo: hay que depositar. Negativo: se puede retirar. Ya descuenta la caja actual.
+    pub amount: Decimal,
+
+    /// Unidades a comprar de cada ticker con la caja resultante.
+    pub to_buy: BTreeMap<String, usize>,
+
+    /// Tickers que se tienen pero no estan en el objetivo: no se llega al objetivo sin venderlos,
+    /// asi que la sugerencia los deja como estan.
+    pub unsold: Vec<String>,
+}
+
+impl FundingSuggestion {
+    /// Monto a depositar; cero si no hace falta.
+    pub fn deposit(&self) -> Decimal {
+        self.amount.max(Decimal::ZERO)
+    }
+
+    /// Monto que se puede retirar; cero si hay que depositar.
+    pub fn withdrawal(&self) -> Decimal {
+        (-self.amount).max(Decimal::ZERO)
+    }
+}
+
+impl Localize for FundingSuggestion {
+    fn localize(&self, language: Language) -> String {
+        let (deposit, withdrawal) = (self.deposit().round_dp(2), self.withdrawal().round_dp(2));
+        let header = match (language, self.amount < Decimal::ZERO) {
+            (Language::Es, false) => format!("Depositar {deposit}"),
+            (Language::Es, true) => format!("Se puede retirar {withdrawal}"),
+            (Language::En, false) => format!("Deposit {deposit}"),
+            (Language::En, true) => format!("Can withdraw {withdrawal}"),
+        };
+
+        let buys = self.to_buy.iter().map(|(ticker, units)| match language {
+            Language::Es => format!("Comprar {ticker} x{units}"),
+            Language::En => format!("Buy {ticker} x{units}"),
+        });
+        let unsold = self.unsold.iter().map(|ticker| match language {
+            Language::Es => format!("{ticker} no esta en el objetivo y no se vende"),
+            Language::En => format!("{ticker} is not in the target and is not sold"),
+        });
+
+        core::iter::once(header)
+            .chain(buys)
+            .chain(unsold)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for FundingSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl Portfolio {
+    /// Aporte necesario para llegar al objetivo solo comprando.
+    ///
+    /// El valor total mas chico que no deja ningun ticker sobreponderado es el maximo, entre los
+    /// tickers del objetivo, de `valor que se tiene / peso objetivo`. A ese total se compra lo
+    /// que falta de cada ticker (redondeando unidades hacia abajo, como la estrategia
+    /// conservadora) y se deja la reserva de caja del objetivo; la diferencia con la caja actual
+    /// es el aporte. Si la caja actual alcanza de sobra, el monto es negativo y es lo que se
+    /// puede retirar.
+    pub fn funding_suggestion(&self) -> FundingSuggestion {
+        let held: BTreeMap<&str, usize> = self.iter_holdings().collect();
+        let unsold = held
+            .keys()
+            .filter(|ticker| !self.allocation.contains_key(ticker))
+            .map(|ticker| ticker.to_string())
+            .collect();
+
+        let total = self
+            .allocation
+            .targets()
+            .iter()
+            .filter(|(weight, _)| *weight > Decimal::ZERO)
+            .map(|(weight, stock)| self.value_of(stock.name()) / (weight / Decimal::ONE_HUNDRED))
+            .max()
+            .unwrap_or(Decimal::ZERO);
+
+        let mut to_buy = BTreeMap::new();
+        let mut cost = Decimal::ZERO;
+        for (weight, stock) in self.allocation.targets() {
+            let price = stock.current_price();
+            if price <= Decimal::ZERO {
+                continue;
+            }
+
+            let owned = Decimal::from(held.get(stock.name()).copied().unwrap_or(0));
+            let missing = total * (weight / Decimal::ONE_HUNDRED) / price - owned;
+            let units = stock
+                .round_to_increment(missing.max(Decimal::ZERO).trunc())
+                .to_usize()
+                .unwrap_or(0);
+            if units > 0 {
+                cost += price * Decimal::from(units);
+                to_buy.insert(stock.name().to_string(), units);
+            }
+        }
+
+        let reserve = total * self.allocation.cash_weight() / Decimal::ONE_HUNDRED;
+
+        FundingSuggestion {
+            amount: cost + reserve - self.cash,
+            to_buy,
+            unsold,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn portfolio(cash: Decimal, stocks: Vec<Stock>) -> Portfolio {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(40), Stock::new("META", dec!(10))),
+            (dec!(60), Stock::new("AAPL", dec!(20))),
+        ])
+        .unwrap();
+
+        Portfolio {
+            cash,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: target,
+        }
+    }
+
+    #[test]
+    fn test_deposit_to_reach_target_without_selling() {
+        // 8 META (80) tendria que ser el 40%: el total minimo es 200, con 120 en AAPL
+        let suggestion =
+            portfolio(dec!(20), vec![Stock::new("META", dec!(10)); 8]).funding_suggestion();
+
+        assert_eq!(suggestion.to_buy, BTreeMap::from([("AAPL".to_string(), 6)]));
+        assert_eq!(suggestion.deposit(), dec!(100));
+        assert_eq!(
+            suggestion.localize(Language::Es),
+            "Depositar 100\nComprar AAPL x6"
+        );
+    }
+
+    #[test]
+    fn test_excess_cash_can_be_withdrawn() {
+        let suggestion = portfolio(
+            dec!(500),
+            vec![
+                Stock::new("META", dec!(10)),
+                Stock::new("AAPL", dec!(20)),
+                Stock::new("TSLA", dec!(5)),
+            ],
+        )
+        .funding_suggestion();
+
+        // AAPL manda (20 / 60% = 33.33 de total): a META le falta menos de una unidad, asi que no
+        // hay nada que comprar y toda la caja sobra
+        assert!(suggestion.to_buy.is_empty());
+        assert_eq!(suggestion.withdrawal(), dec!(500));
+        assert_eq!(suggestion.unsold, vec!["TSLA".to_string()]);
+    }
+}
@@ -0,0 +1,490 @@
+//! Manejo de caja en varias monedas.
+//!
+//! Un portafolio puede tener saldos en monedas distintas a la base (ver
+//! `Portfolio::with_foreign_cash`) y stocks que cotizan en otras monedas. Para rebalancear se
+//! valoriza todo en la moneda base y, si una compra tiene que pagarse con caja de otra moneda, la
+//! sugerencia incluye la conversion necesaria, descontando la comision de cambio.
+
+use crate::Map;
+use crate::error::ArithmeticOverflow;
+use crate::i18n::{Language, Localize, language};
+use crate::lots::Lot;
+use crate::money::Currency;
+use crate::{InstrumentKind, Portfolio, RebalanceSuggestion, Stock};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Tipos de cambio contra una moneda base.
+#[derive(Debug, Clone)]
+pub struct FxRates {
+    base: Currency,
+
+    /// Cuanto vale una unidad de cada moneda en la base.
+    to_base: Map<Currency, Decimal>,
+}
+
+impl FxRates {
+    pub fn new(base: Currency) -> Self {
+        Self {
+            base,
+            to_base: Map::new(),
+        }
+    }
+
+    /// Fija cuantas unidades de la base vale una unidad de `currency` (p. ej. con base CLP,
+    /// `with_rate(Usd, 950)`).
+    pub fn with_rate(mut self, currency: Currency, rate: Decimal) -> Self {
+        self.to_base.insert(currency, rate);
+        self
+    }
+
+    pub fn base(&self) -> Currency {
+        self.base
+    }
+
+    /// Cuantas unidades de `to` se obtienen por una de `from`, sin comisiones.
+    pub fn rate(&self, from: Currency, to: Currency) -> Result<Decimal, FxError> {
+        let to_base = |currency| {
+            if currency == self.base {
+                Ok(Decimal::ONE)
+            } else {
+                self.to_base
+                    .get(&currency)
+                    .copied()
+                    .filter(|r| !r.is_zero())
+                    .ok_or(FxError::MissingRate(currency))
+            }
+        };
+
+        Ok(to_base(from)? / to_base(to)?)
+    }
+
+    pub fn convert(
+        &self,
+        amount: Decimal,
+        from: Currency,
+        to: Currency,
+    ) -> Result<Decimal, FxError> {
+        Ok(amount * self.rate(from, to)?)
+    }
+}
+
+/// Errores de manejo de monedas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FxError {
+    /// No hay tipo de cambio para la moneda.
+    MissingRate(Currency),
+
+    /// La comision de cambio no esta en `[0, 1)`.
+    InvalidFee(Decimal),
+
+    /// El rebalanceo en moneda base no cabe en un `Decimal`.
+    Overflow(ArithmeticOverflow),
+}
+
+impl Localize for FxError {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (FxError::MissingRate(currency), Language::Es) => {
+                format!("Falta el tipo de cambio de {currency}")
+            }
+            (FxError::MissingRate(currency), Language::En) => {
+                format!("Missing exchange rate for {currency}")
+            }
+            (FxError::InvalidFee(fee), Language::Es) => {
+                format!("La comision de cambio debe estar entre 0 y 1 (sin incluir 1): {fee}")
+            }
+            (FxError::InvalidFee(fee), Language::En) => {
+                format!("The exchange fee must be at least 0 and below 1: {fee}")
+            }
+            (FxError::Overflow(overflow), language) => overflow.localize(language),
+        }
+    }
+}
+
+impl fmt::Display for FxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for FxError {}
+
+/// Ganancia en moneda base de unidades compradas en otra moneda, separada en lo que vino del
+/// activo y lo que vino del tipo de cambio (como lo piden varias jurisdicciones al tributar).
+///
+/// Con costo `c` y precio `p` en la moneda del stock, y tipos de cambio `r0` al comprar y `r1`
+/// ahora (o al vender): la ganancia total es `p r1 - c r0`, la del activo `(p - c) r1` y la de
+/// moneda `c (r1 - r0)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FxGainSplit {
+    pub asset_gain: Decimal,
+    pub currency_gain: Decimal,
+}
+
+impl FxGainSplit {
+    fn new(units: usize, cost: Decimal, price: Decimal, rate_then: Decimal, now: Decimal) -> Self {
+        let units = Decimal::from(units);
+        Self {
+            asset_gain: (price - cost) * now * units,
+            currency_gain: cost * (now - rate_then) * units,
+        }
+    }
+
+    pub fn total(&self) -> Decimal {
+        self.asset_gain + self.currency_gain
+    }
+}
+
+impl Lot {
+    /// Ganancia no realizada del lote en la moneda base de `rates`. Si no se sabe el tipo de
+    /// cambio de compra (`CostBasis::fx_rate`) se asume el de hoy, o sea toda la ganancia queda
+    /// como del activo.
+    pub fn fx_split(&self, rates: &FxRates) -> Result<FxGainSplit, FxError> {
+        let now = rates.rate(self.currency, rates.base)?;
+        Ok(self.realized_fx_split(self.units, self.price, now))
+    }
+
+    /// Ganancia realizada al vender `units` unidades del lote a `price` (en la moneda del stock)
+    /// con un tipo de cambio `fx_rate` hacia la base.
+    pub fn realized_fx_split(&self, units: usize, price: Decimal, fx_rate: Decimal) -> FxGainSplit {
+        let rate_then = self.basis.fx_rate.unwrap_or(fx_rate);
+        FxGainSplit::new(units, self.basis.cost, price, rate_then, fx_rate)
+    }
+}
+
+impl Portfolio {
+    /// Ganancia no realizada de cada lote separada en activo y moneda, en la base de `rates`.
+    pub fn fx_gains(&self, rates: &FxRates) -> Result<Vec<(Lot, FxGainSplit)>, FxError> {
+        self.lots()
+            .into_iter()
+            .map(|lot| {
+                let split = lot.fx_split(rates)?;
+                Ok((lot, split))
+            })
+            .collect()
+    }
+}
+
+/// Una conversion de caja entre dos monedas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FxConversion {
+    pub from: Currency,
+    pub to: Currency,
+
+    /// Monto que sale de `from`, comision incluida.
+    pub sold: Decimal,
+
+    /// Monto que llega a `to`.
+    pub bought: Decimal,
+
+    /// Comision cobrada, en `from`.
+    pub fee: Decimal,
+}
+
+/// Sugerencia de rebalanceo con las conversiones de moneda que implica.
+#[derive(Debug)]
+pub struct FxSuggestion<'a> {
+    pub trades: RebalanceSuggestion<'a>,
+
+    /// Conversiones a hacer antes de las compras.
+    pub conversions: Vec<FxConversion>,
+
+    /// Montos que faltan en cada moneda despues de las conversiones; solo puede pasar cuando las
+    /// comisiones se comen el margen que dejaba la estrategia conservadora.
+    pub shortfall: BTreeMap<Currency, Decimal>,
+}
+
+impl Portfolio {
+    /// Como `rebalance_portfolio`, pero valorizando stocks y saldos en la moneda base de `rates`
+    /// (que se asume es la de `cash()`), y agregando las conversiones necesarias para pagar las
+    /// compras con la caja disponible. `fee` es la comision de cambio como fraccion del monto
+    /// convertido (`0.005` para 0,5%); fuera de `[0, 1)` es un error.
+    ///
+    /// Las compras se financian primero con caja en su misma moneda (incluyendo lo que dejan las
+    /// ventas); lo que falta se convierte desde la moneda base y luego desde las demas, en orden.
+    pub fn rebalance_with_fx(
+        &self,
+        rates: &FxRates,
+        fee: Decimal,
+    ) -> Result<FxSuggestion<'_>, FxError> {
+        if fee < Decimal::ZERO || fee >= Decimal::ONE {
+            return Err(FxError::InvalidFee(fee));
+        }
+        let base = rates.base();
+
+        let in_base = |stock: &Stock| -> Result<Stock, FxError> {
+            Ok(Stock {
+                current_price: rates.convert(stock.current_price(), stock.currency, base)?,
+                kind: InstrumentKind::Equity,
+                currency: base,
+                ..stock.clone()
+            })
+        };
+
+        let mut converted = self.clone();
+        for stock in converted.stocks.iter_mut() {
+            *stock = in_base(stock)?;
+        }
+        for (_, stock) in converted.allocation.targets.iter_mut() {
+            *stock = in_base(stock)?;
+        }
+        for (currency, amount) in core::mem::take(&mut converted.foreign_cash) {
+            converted.cash += rates.convert(amount, currency, base)?;
+        }
+
+        let plan = converted
+            .try_rebalance_portfolio()
+            .map_err(FxError::Overflow)?;
+
+        // la sugerencia del portafolio convertido toma prestados sus nombres; los traducimos a
+        // los de `self` para poder devolverla
+        let held = self.stocks.iter();
+        let targeted = self.allocation.targets.iter().map(|(_, stock)| stock);
+        let stocks: Vec<&Stock> = held.chain(targeted).collect();
+        let find = |name: &str| *stocks.iter().find(|s| s.name() == name).unwrap();
+
+        let mut trades = RebalanceSuggestion {
+            id: self.state_id(Default::default()),
+            priorities: plan.priorities.clone(),
+            ..Default::default()
+        };
+        let mut balances: BTreeMap<Currency, Decimal> = self.foreign_cash.clone();
+        *balances.entry(base).or_default() += self.cash;
+
+        // lo denominado en UF se paga y se cobra en pesos, sin conversion ni comision
+        let settle = |stock: &Stock, units: usize| -> Result<(Currency, Decimal), FxError> {
+            let currency = stock.currency.settlement();
+            let amount = stock.current_price() * Decimal::from(units);
+            Ok((currency, rates.convert(amount, stock.currency, currency)?))
+        };
+        for (name, units) in plan.to_sell {
+            let stock = find(name);
+            trades.to_sell.insert(stock.name(), units);
+            let (currency, amount) = settle(stock, units)?;
+            *balances.entry(currency).or_default() += amount;
+        }
+        for (name, units) in plan.to_buy {
+            let stock = self
+                .allocation
+                .targets
+                .iter()
+                .map(|(_, s)| s)
+                .find(|s| s.name() == name)
+                .unwrap();
+            trades.to_buy.insert(stock.name(), units);
+            let (currency, amount) = settle(stock, units)?;
+            *balances.entry(currency).or_default() -= amount;
+        }
+
+        let (conversions, shortfall) = fund(balances, rates, fee)?;
+
+        Ok(FxSuggestion {
+            trades,
+            conversions,
+            shortfall,
+        })
+    }
+}
+
+/// Cubre los saldos negativos con los positivos, primero desde la moneda base.
+fn fund(
+    mut balances: BTreeMap<Currency, Decimal>,
+    rates: &FxRates,
+    fee: Decimal,
+) -> Result<(Vec<FxConversion>, BTreeMap<Currency, Decimal>), FxError> {
+    let base = rates.base();
+    let mut sources: Vec<Currency> = balances.keys().copied().collect();
+    sources.sort_by_key(|c| *c != base);
+
+    let mut conversions = Vec::new();
+    let deficits: Vec<Currency> = balances
+        .iter()
+        .filter(|(_, amount)| **amount < Decimal::ZERO)
+        .map(|(currency, _)| *currency)
+        .collect();
+
+    for to in deficits {
+        for &from in &sources {
+            let needed = -balances[&to];
+            let available = balances[&from];
+            if needed <= Decimal::ZERO {
+                break;
+            }
+            if from == to || available <= Decimal::ZERO {
+                continue;
+            }
+
+            // cada unidad de `from` entrega `effective` unidades de `to` despues de la comision
+            let effective = rates.rate(from, to)? * (Decimal::ONE - fee);
+            let sold = (needed / effective).min(available);
+            let bought = sold * effective;
+
+            *balances.get_mut(&from).unwrap() -= sold;
+            *balances.get_mut(&to).unwrap() += bought;
+            conversions.push(FxConversion {
+                from,
+                to,
+                sold,
+                bought,
+                fee: sold * fee,
+            });
+        }
+    }
+
+    let shortfall = balances
+        .into_iter()
+        .filter(|(_, amount)| *amount < Decimal::ZERO)
+        .map(|(currency, amount)| (currency, -amount))
+        .collect();
+
+    Ok((conversions, shortfall))
+}
+
+impl Localize for FxConversion {
+    fn localize(&self, language: Language) -> String {
+        let sold = self.sold.round_dp(self.from.decimals());
+        let bought = self.bought.round_dp(self.to.decimals());
+        let fee = self.fee.round_dp(self.from.decimals());
+
+        match language {
+            Language::Es => format!(
+                "Convertir {sold} {} a {bought} {} (comision {fee} {})",
+                self.from, self.to, self.from
+            ),
+            Language::En => format!(
+                "Convert {sold} {} to {bought} {} (fee {fee} {})",
+                self.from, self.to, self.from
+            ),
+        }
+    }
+}
+
+/// Primero las conversiones, despues las operaciones.
+impl Localize for FxSuggestion<'_> {
+    fn localize(&self, language: Language) -> String {
+        self.conversions
+            .iter()
+            .map(|c| c.localize(language))
+            .chain(core::iter::once(self.trades.localize(language)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for FxSuggestion<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Date, PortfolioTarget};
+    use rust_decimal_macros::dec;
+
+    fn portfolio(cash_clp: Decimal) -> Portfolio {
+        let target = PortfolioTarget::new(Stock::new("META", dec!(500)));
+
+        Portfolio {
+            cash: cash_clp,
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: target,
+        }
+    }
+
+    #[test]
+    fn test_fx_gain_split() {
+        let acquired = Date::new(2024, 1, 10).unwrap();
+        // compradas a 100 USD con el dolar a 900; hoy valen 110 USD y el dolar esta a 1.000
+        let stock = Stock::new("META", dec!(110))
+            .with_fx_basis(dec!(100), acquired, dec!(900))
+            .with_currency(Currency::Usd);
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![stock; 2],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(110))),
+        };
+        let rates = FxRates::new(Currency::Clp).with_rate(Currency::Usd, dec!(1000));
+
+        let gains = portfolio.fx_gains(&rates).unwrap();
+        let (_, split) = &gains[0];
+        assert_eq!(split.asset_gain, dec!(20_000));
+        assert_eq!(split.currency_gain, dec!(20_000));
+        // 2 x (110 x 1.000 - 100 x 900)
+        assert_eq!(split.total(), dec!(40_000));
+
+        // vendiendo una a 90 USD con el dolar a 1.100: pierde en el activo y gana en la moneda
+        let sold = gains[0].0.realized_fx_split(1, dec!(90), dec!(1100));
+        assert_eq!(sold.asset_gain, dec!(-11_000));
+        assert_eq!(sold.currency_gain, dec!(20_000));
+
+        let usd = FxRates::new(Currency::Eur);
+        assert!(portfolio.fx_gains(&usd).is_err());
+    }
+
+    #[test]
+    fn test_buy_in_usd_funded_from_clp() {
+        let rates = FxRates::new(Currency::Clp).with_rate(Currency::Usd, dec!(1000));
+        let portfolio = portfolio(dec!(1_100_000));
+        let suggestion = portfolio.rebalance_with_fx(&rates, dec!(0.01)).unwrap();
+
+        // 1.100.000 CLP = 1.100 USD, alcanza para 2 META = 1.000 USD
+        assert_eq!(suggestion.trades.to_buy["META"], 2);
+        assert_eq!(suggestion.conversions.len(), 1);
+
+        let conversion = &suggestion.conversions[0];
+        assert_eq!(
+            (conversion.from, conversion.to),
+            (Currency::Clp, Currency::Usd)
+        );
+        assert_eq!(conversion.bought, dec!(1000));
+        assert_eq!(conversion.sold.round_dp(0), dec!(1010101));
+        assert!(suggestion.shortfall.is_empty());
+        assert_eq!(
+            conversion.localize(Language::En),
+            "Convert 1010101 CLP to 1000.00 USD (fee 10101 CLP)"
+        );
+    }
+
+    #[test]
+    fn test_same_currency_cash_needs_no_conversion() {
+        let rates = FxRates::new(Currency::Clp).with_rate(Currency::Usd, dec!(1000));
+        let portfolio = portfolio(Decimal::ZERO).with_foreign_cash(Currency::Usd, dec!(1200));
+
+        let suggestion = portfolio.rebalance_with_fx(&rates, dec!(0.01)).unwrap();
+        assert_eq!(suggestion.trades.to_buy["META"], 2);
+        assert!(suggestion.conversions.is_empty());
+    }
+
+    #[test]
+    fn test_fees_can_leave_a_shortfall() {
+        let rates = FxRates::new(Currency::Clp).with_rate(Currency::Usd, dec!(1000));
+        let portfolio = portfolio(dec!(1_000_000));
+        let suggestion = portfolio.rebalance_with_fx(&rates, dec!(0.01)).unwrap();
+
+        assert_eq!(suggestion.trades.to_buy["META"], 2);
+        assert_eq!(suggestion.shortfall[&Currency::Usd], dec!(10));
+
+        let missing = FxRates::new(Currency::Clp);
+        assert_eq!(
+            portfolio.rebalance_with_fx(&missing, dec!(0)).unwrap_err(),
+            FxError::MissingRate(Currency::Usd)
+        );
+        assert_eq!(
+            portfolio.rebalance_with_fx(&rates, dec!(1)).unwrap_err(),
+            FxError::InvalidFee(dec!(1))
+        );
+        assert!(matches!(
+            self::portfolio(Decimal::MAX).rebalance_with_fx(&rates, dec!(0.01)),
+            Err(FxError::Overflow(_))
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::date::Date;
+use crate::projection::{MonteCarloParams, simulate_final_values};
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Una meta de ahorro concreta: juntar cierto monto para cierta fecha.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Goal {
+    pub target_amount: Decimal,
+    pub target_date: Date,
+}
+
+impl Goal {
+    pub fn new(target_amount: Decimal, target_date: Date) -> Self {
+        Self {
+            target_amount,
+            target_date,
+        }
+    }
+
+    /// Aporte mensual necesario para llegar a la meta partiendo de `current_value` hoy, si el
+    /// portafolio rinde exactamente `expected_return` anual (p. ej. `dec!(0.07)`).
+    ///
+    /// Es la formula de anualidad: `FV = PV (1+r)^n + PMT ((1+r)^n - 1) / r`, despejando `PMT`,
+    /// con `r` la tasa mensual equivalente. Si la meta ya se alcanza sin aportes, devuelve cero;
+    /// si la fecha ya paso, devuelve todo lo que falta.
+    pub fn required_monthly_contribution(
+        &self,
+        current_value: Decimal,
+        today: Date,
+        expected_return: Decimal,
+    ) -> Decimal {
+        let months = today.months_until(self.target_date);
+        if months == 0 {
+            return (self.target_amount - current_value).max(Decimal::ZERO);
+        }
+
+        let n = Decimal::from(months);
+        let monthly_rate =
+            (Decimal::ONE + expected_return).powd(Decimal::ONE / dec!(12)) - Decimal::ONE;
+
+        let required = if monthly_rate.is_zero() {
+            (self.target_amount - current_value) / n
+        } else {
+            let growth = (Decimal::ONE + monthly_rate).powd(n);
+            (self.target_amount - current_value * growth) * monthly_rate / (growth - Decimal::ONE)
+        };
+
+        required.max(Decimal::ZERO)
+    }
+
+    /// Probabilidad (entre 0 y 1) de alcanzar la meta aportando `monthly_contribution` cada mes,
+    /// segun una simulacion de Monte Carlo.
+    pub fn probability_of_success(
+        &self,
+        current_value: Decimal,
+        monthly_contribution: Decimal,
+        today: Date,
+        params: &MonteCarloParams,
+    ) -> f64 {
+        if params.paths == 0 {
+            return 0.0;
+        }
+
+        let target = self.target_amount.to_f64().unwrap_or(f64::MAX);
+        let finals = simulate_final_values(
+            current_value.to_f64().unwrap_or(0.0),
+            monthly_contribution.to_f64().unwrap_or(0.0),
+            today.months_until(self.target_date),
+            params,
+        );
+
+        finals.iter().filter(|value| **value >= target).count() as f64 / finals.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn today() -> Date {
+        Date::new(2024, 1, 1).unwrap()
+    }
+
+    #[test]
+    fn test_contribution_without_returns_is_linear() {
+        let goal = Goal::new(dec!(1200), Date::new(2025, 1, 1).unwrap());
+        let monthly = goal.required_monthly_contribution(Decimal::ZERO, today(), Decimal::ZERO);
+
+        assert_eq!(monthly, dec!(100));
+    }
+
+    #[test]
+    fn test_contribution_with_returns_is_smaller() {
+        let goal = Goal::new(dec!(10000), Date::new(2034, 1, 1).unwrap());
+        let monthly = goal.required_monthly_contribution(dec!(1000), today(), dec!(0.05));
+
+        // sin retorno serian (10000 - 1000) / 120 = 75
+        assert!(monthly < dec!(75));
+        assert!(monthly > dec!(50));
+
+        let already_there = goal.required_monthly_contribution(dec!(20000), today(), dec!(0.05));
+        assert_eq!(already_there, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_probability_of_success() {
+        let goal = Goal::new(dec!(1200), Date::new(2025, 1, 1).unwrap());
+        let params = MonteCarloParams::new(0.0, 0.0).with_paths(100);
+
+        assert_eq!(
+            goal.probability_of_success(dec!(0), dec!(100), today(), &params),
+            1.0
+        );
+        assert_eq!(
+            goal.probability_of_success(dec!(0), dec!(99), today(), &params),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,745 @@
+//! Servicio gRPC con los RPC `Rebalance`, `Value` y `Drift`.
+//!
+//! Para desplegar el motor como microservicio JSON queda muy suelto (cualquier campo puede
+//! faltar o venir con otro tipo); con gRPC el contrato esta en `proto/rebalance.proto`.
+//!
+//! El modulo no trae transporte: los mensajes se codifican a mano en el formato binario de
+//! protobuf y `RebalanceService::call` atiende un RPC a partir de su path
+//! (`/fintual.rebalance.v1.Rebalancer/Rebalance`) y el cuerpo. Servirlo es envolver esa funcion
+//! en el servidor HTTP/2 que se use; `frame` y `unframe` hacen el prefijo de largo de los
+//! mensajes gRPC.
+//!
+//! Cada unidad de un holding es un `Stock` en memoria (unos 200 bytes), asi que un request no
+//! puede traer mas de `MAX_UNITS` unidades en total; si no, un mensaje de pocos bytes bastaria
+//! para botar el servidor. Los calculos usan las variantes con chequeo (`try_*`, `checked_*`):
+//! un request que no cabe en un `Decimal` se responde con INVALID_ARGUMENT, no con un panico.
+
+use crate::error::{ArithmeticOverflow, PortfolioError, TargetError};
+use crate::execution::{Order, Side};
+use crate::i18n::{Language, Localize, language};
+use crate::reports::verification::Drift;
+use crate::{Allocation, Portfolio, PortfolioTarget, Stock};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefijo de los paths de los RPC.
+pub const SERVICE: &str = "/fintual.rebalance.v1.Rebalancer/";
+
+/// Maximo de unidades, sumando todos los holdings, que acepta un request.
+pub const MAX_UNITS: u64 = 100_000;
+
+/// Errores al atender un RPC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrpcError {
+    /// El cuerpo no es un mensaje protobuf valido (o le falta un campo obligatorio).
+    Decode(String),
+
+    /// El path no corresponde a ningun RPC del servicio.
+    UnknownMethod(String),
+
+    /// El objetivo del request no es valido.
+    Target(TargetError),
+
+    /// El portafolio del request no es valido.
+    Portfolio(PortfolioError),
+
+    /// El precio objetivo de un ticker es cero o negativo.
+    InvalidPrice(String),
+
+    /// El portafolio del request no se puede valorizar o rebalancear en un `Decimal`.
+    Overflow(ArithmeticOverflow),
+}
+
+impl GrpcError {
+    /// Codigo de estado gRPC (`grpc-status`) con el que responder.
+    pub fn status_code(&self) -> u32 {
+        match self {
+            // UNIMPLEMENTED
+            GrpcError::UnknownMethod(_) => 12,
+            // INVALID_ARGUMENT
+            _ => 3,
+        }
+    }
+}
+
+impl Localize for GrpcError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            GrpcError::Decode(reason) => match language {
+                Language::Es => format!("Mensaje protobuf invalido: {reason}"),
+                Language::En => format!("Invalid protobuf message: {reason}"),
+            },
+            GrpcError::UnknownMethod(path) => match language {
+                Language::Es => format!("El servicio no tiene el metodo {path}."),
+                Language::En => format!("The service has no method {path}."),
+            },
+            GrpcError::Target(error) => error.localize(language),
+            GrpcError::Portfolio(error) => error.localize(language),
+            GrpcError::InvalidPrice(ticker) => match language {
+                Language::Es => format!("El precio objetivo de {ticker} debe ser positivo."),
+                Language::En => format!("The target price of {ticker} must be positive."),
+            },
+            GrpcError::Overflow(error) => error.localize(language),
+        }
+    }
+}
+
+impl fmt::Display for GrpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for GrpcError {}
+
+/// Un mensaje protobuf.
+pub trait Message: Sized {
+    fn encode_to(&self, out: &mut Vec<u8>);
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError>;
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_to(&mut out);
+        out
+    }
+}
+
+/// Holding del request: `units` unidades a `price`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Holding {
+    pub ticker: String,
+    pub units: u64,
+    pub price: Decimal,
+}
+
+/// Entrada del objetivo: peso en % y precio actual del stock.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TargetWeight {
+    pub ticker: String,
+    pub weight: Decimal,
+    pub price: Decimal,
+}
+
+/// Portafolio sobre el que opera un RPC.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PortfolioRequest {
+    pub cash: Decimal,
+    pub holdings: Vec<Holding>,
+    pub targets: Vec<TargetWeight>,
+    pub target_cash: Decimal,
+}
+
+impl PortfolioRequest {
+    /// El mismo portafolio que el request, validado como en `Portfolio::builder`.
+    pub fn to_portfolio(&self) -> Result<Portfolio, GrpcError> {
+        if let Some(target) = self.targets.iter().find(|t| t.price <= Decimal::ZERO) {
+            return Err(GrpcError::InvalidPrice(target.ticker.clone()));
+        }
+        let mut allocations: Vec<Allocation> = self
+            .targets
+            .iter()
+            .map(|t| Allocation::Stock(t.weight, Stock::new(&t.ticker, t.price)))
+            .collect();
+        if !self.target_cash.is_zero() {
+            allocations.push(Allocation::Cash(self.target_cash));
+        }
+        let target =
+            PortfolioTarget::try_from_allocations(allocations).map_err(GrpcError::Target)?;
+
+        let units = self
+            .holdings
+            .iter()
+            .try_fold(0u64, |total, h| total.checked_add(h.units));
+        if units.is_none_or(|units| units > MAX_UNITS) {
+            return Err(GrpcError::Decode(format!(
+                "mas de {MAX_UNITS} unidades en los holdings"
+            )));
+        }
+
+        self.holdings
+            .iter()
+            .fold(Portfolio::builder().with_cash(self.cash), |builder, h| {
+                builder.with_holding(&h.ticker, h.units as usize, h.price)
+            })
+            .with_target(target)
+            .build()
+            .map_err(GrpcError::Portfolio)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RebalanceReply {
+    pub suggestion_id: u64,
+    pub orders: Vec<Order>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValueReply {
+    pub total: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DriftRequest {
+    pub portfolio: PortfolioRequest,
+    pub tolerance: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DriftReply {
+    pub drifts: Vec<Drift>,
+    pub within_tolerance: bool,
+}
+
+/// Implementacion del servicio. No guarda estado: cada request trae el portafolio completo.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RebalanceService;
+
+impl RebalanceService {
+    pub fn rebalance(&self, request: &PortfolioRequest) -> Result<RebalanceReply, GrpcError> {
+        let portfolio = request.to_portfolio()?;
+        let suggestion = portfolio
+            .try_rebalance_portfolio()
+            .map_err(GrpcError::Overflow)?;
+        Ok(RebalanceReply {
+            suggestion_id: suggestion.id.as_u64(),
+            orders: suggestion.orders(),
+        })
+    }
+
+    pub fn value(&self, request: &PortfolioRequest) -> Result<ValueReply, GrpcError> {
+        Ok(ValueReply {
+            total: request
+                .to_portfolio()?
+                .checked_total_value()
+                .map_err(GrpcError::Overflow)?,
+        })
+    }
+
+    pub fn drift(&self, request: &DriftRequest) -> Result<DriftReply, GrpcError> {
+        let report = request
+            .portfolio
+            .to_portfolio()?
+            .checked_verify_against_target(request.tolerance)
+            .map_err(GrpcError::Overflow)?;
+        Ok(DriftReply {
+            within_tolerance: report.is_within_tolerance(),
+            drifts: report.drifts,
+        })
+    }
+
+    /// Atiende un RPC: `path` es el `:path` de HTTP/2 y `body` el mensaje ya sin el prefijo de
+    /// largo. Devuelve la respuesta codificada.
+    pub fn call(&self, path: &str, body: &[u8]) -> Result<Vec<u8>, GrpcError> {
+        match path.strip_prefix(SERVICE) {
+            Some("Rebalance") => Ok(self.rebalance(&PortfolioRequest::decode(body)?)?.encode()),
+            Some("Value") => Ok(self.value(&PortfolioRequest::decode(body)?)?.encode()),
+            Some("Drift") => Ok(self.drift(&DriftRequest::decode(body)?)?.encode()),
+            _ => Err(GrpcError::UnknownMethod(path.into())),
+        }
+    }
+}
+
+/// Agrega el prefijo de un mensaje gRPC: sin compresion (un byte en 0) y el largo en 4 bytes
+/// big-endian.
+pub fn frame(message: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(message.len() + 5);
+    out.push(0);
+    out.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    out.extend_from_slice(message);
+    out
+}
+
+/// Saca el prefijo de un mensaje gRPC. No soportamos mensajes comprimidos.
+pub fn unframe(bytes: &[u8]) -> Result<&[u8], GrpcError> {
+    let [compressed, a, b, c, d, rest @ ..] = bytes else {
+        return Err(GrpcError::Decode("falta el prefijo de largo".into()));
+    };
+    if *compressed != 0 {
+        return Err(GrpcError::Decode("mensaje comprimido".into()));
+    }
+    let len = u32::from_be_bytes([*a, *b, *c, *d]) as usize;
+    rest.get(..len)
+        .ok_or_else(|| GrpcError::Decode("mensaje truncado".into()))
+}
+
+// Formato binario de protobuf: cada campo es una clave (`numero << 3 | tipo`) en varint seguida
+// del valor; solo usamos varints (tipo 0), fixed64 (tipo 1) y largo + bytes (tipo 2).
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LEN: u8 = 2;
+const FIXED32: u8 = 5;
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_key(out: &mut Vec<u8>, field: u32, wire_type: u8) {
+    put_varint(out, u64::from(field) << 3 | u64::from(wire_type));
+}
+
+fn put_bytes(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    put_key(out, field, LEN);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+// en proto3 los valores por defecto no se escriben
+
+fn put_str(out: &mut Vec<u8>, field: u32, value: &str) {
+    if !value.is_empty() {
+        put_bytes(out, field, value.as_bytes());
+    }
+}
+
+fn put_decimal(out: &mut Vec<u8>, field: u32, value: Decimal) {
+    if !value.is_zero() {
+        put_str(out, field, &value.normalize().to_string());
+    }
+}
+
+fn put_u64(out: &mut Vec<u8>, field: u32, value: u64) {
+    if value != 0 {
+        put_key(out, field, VARINT);
+        put_varint(out, value);
+    }
+}
+
+fn put_message(out: &mut Vec<u8>, field: u32, message: &impl Message) {
+    put_bytes(out, field, &message.encode());
+}
+
+/// Valor de un campo leido.
+enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> Value<'a> {
+    fn u64(&self) -> Result<u64, GrpcError> {
+        match self {
+            Value::Varint(value) | Value::Fixed64(value) => Ok(*value),
+            Value::Bytes(_) => Err(GrpcError::Decode("se esperaba un numero".into())),
+        }
+    }
+
+    fn bytes(&self) -> Result<&'a [u8], GrpcError> {
+        match self {
+            Value::Bytes(bytes) => Ok(bytes),
+            _ => Err(GrpcError::Decode("se esperaban bytes".into())),
+        }
+    }
+
+    fn str(&self) -> Result<String, GrpcError> {
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| GrpcError::Decode("string no es UTF-8".into()))
+    }
+
+    fn decimal(&self) -> Result<Decimal, GrpcError> {
+        let text = self.str()?;
+        Decimal::from_str(&text).map_err(|_| GrpcError::Decode(format!("decimal invalido: {text}")))
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], GrpcError> {
+        if self.bytes.len() < n {
+            return Err(GrpcError::Decode("mensaje truncado".into()));
+        }
+
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn varint(&mut self) -> Result<u64, GrpcError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(GrpcError::Decode("varint demasiado largo".into()))
+    }
+
+    /// Siguiente campo conocido; los de tipo fixed32 (que ningun mensaje usa) se saltan.
+    fn field(&mut self) -> Result<Option<(u32, Value<'a>)>, GrpcError> {
+        loop {
+            if self.bytes.is_empty() {
+                return Ok(None);
+            }
+
+            let key = self.varint()?;
+            let field = (key >> 3) as u32;
+            let value = match key as u8 & 0x7 {
+                VARINT => Value::Varint(self.varint()?),
+                FIXED64 => Value::Fixed64(u64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+                LEN => {
+                    let len = self.varint()? as usize;
+                    Value::Bytes(self.take(len)?)
+                }
+                FIXED32 => {
+                    self.take(4)?;
+                    continue;
+                }
+                other => return Err(GrpcError::Decode(format!("tipo de campo {other}"))),
+            };
+            return Ok(Some((field, value)));
+        }
+    }
+}
+
+/// Recorre los campos de `bytes`; los numeros de campo que `read` no conoce se ignoran, como
+/// pide protobuf para poder agregar campos sin romper a los clientes antiguos.
+fn decode_fields<T: Default>(
+    bytes: &[u8],
+    mut read: impl FnMut(&mut T, u32, Value<'_>) -> Result<(), GrpcError>,
+) -> Result<T, GrpcError> {
+    let mut message = T::default();
+    let mut reader = Reader { bytes };
+    while let Some((field, value)) = reader.field()? {
+        read(&mut message, field, value)?;
+    }
+    Ok(message)
+}
+
+impl Message for Holding {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        put_str(out, 1, &self.ticker);
+        put_u64(out, 2, self.units);
+        put_decimal(out, 3, self.price);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        decode_fields(bytes, |m: &mut Self, field, value| {
+            match field {
+                1 => m.ticker = value.str()?,
+                2 => m.units = value.u64()?,
+                3 => m.price = value.decimal()?,
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Message for TargetWeight {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        put_str(out, 1, &self.ticker);
+        put_decimal(out, 2, self.weight);
+        put_decimal(out, 3, self.price);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        decode_fields(bytes, |m: &mut Self, field, value| {
+            match field {
+                1 => m.ticker = value.str()?,
+                2 => m.weight = value.decimal()?,
+                3 => m.price = value.decimal()?,
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Message for PortfolioRequest {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        put_decimal(out, 1, self.cash);
+        for holding in &self.holdings {
+            put_message(out, 2, holding);
+        }
+        for target in &self.targets {
+            put_message(out, 3, target);
+        }
+        put_decimal(out, 4, self.target_cash);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        decode_fields(bytes, |m: &mut Self, field, value| {
+            match field {
+                1 => m.cash = value.decimal()?,
+                2 => m.holdings.push(Holding::decode(value.bytes()?)?),
+                3 => m.targets.push(TargetWeight::decode(value.bytes()?)?),
+                4 => m.target_cash = value.decimal()?,
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Message for Order {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        put_str(out, 1, &self.ticker);
+        let side = match self.side {
+            Side::Sell => 1,
+            Side::Buy => 2,
+        };
+        put_u64(out, 2, side);
+        put_u64(out, 3, self.units as u64);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        let (mut ticker, mut side, mut units) = (String::new(), None, 0);
+        let mut reader = Reader { bytes };
+        while let Some((field, value)) = reader.field()? {
+            match field {
+                1 => ticker = value.str()?,
+                2 => side = Some(value.u64()?),
+                3 => units = value.u64()? as usize,
+                _ => {}
+            }
+        }
+        let side = match side {
+            Some(1) => Side::Sell,
+            Some(2) => Side::Buy,
+            _ => return Err(GrpcError::Decode("orden sin lado".into())),
+        };
+        Ok(Order {
+            ticker,
+            side,
+            units,
+        })
+    }
+}
+
+impl Message for RebalanceReply {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        if self.suggestion_id != 0 {
+            put_key(out, 1, FIXED64);
+            out.extend_from_slice(&self.suggestion_id.to_le_bytes());
+        }
+        for order in &self.orders {
+            put_message(out, 2, order);
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        decode_fields(bytes, |m: &mut Self, field, value| {
+            match field {
+                1 => m.suggestion_id = value.u64()?,
+                2 => m.orders.push(Order::decode(value.bytes()?)?),
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Message for ValueReply {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        put_decimal(out, 1, self.total);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        decode_fields(bytes, |m: &mut Self, field, value| {
+            if field == 1 {
+                m.total = value.decimal()?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Message for DriftRequest {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        put_message(out, 1, &self.portfolio);
+        put_decimal(out, 2, self.tolerance);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        decode_fields(bytes, |m: &mut Self, field, value| {
+            match field {
+                1 => m.portfolio = PortfolioRequest::decode(value.bytes()?)?,
+                2 => m.tolerance = value.decimal()?,
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Message for Drift {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        put_str(out, 1, &self.ticker);
+        put_decimal(out, 2, self.target);
+        put_decimal(out, 3, self.actual);
+        put_decimal(out, 4, self.tolerance);
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        let mut drift = Drift {
+            ticker: String::new(),
+            target: Decimal::ZERO,
+            actual: Decimal::ZERO,
+            tolerance: Decimal::ZERO,
+        };
+        let mut reader = Reader { bytes };
+        while let Some((field, value)) = reader.field()? {
+            match field {
+                1 => drift.ticker = value.str()?,
+                2 => drift.target = value.decimal()?,
+                3 => drift.actual = value.decimal()?,
+                4 => drift.tolerance = value.decimal()?,
+                _ => {}
+            }
+        }
+        Ok(drift)
+    }
+}
+
+impl Message for DriftReply {
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        for drift in &self.drifts {
+            put_message(out, 1, drift);
+        }
+        put_u64(out, 2, u64::from(self.within_tolerance));
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, GrpcError> {
+        decode_fields(bytes, |m: &mut Self, field, value| {
+            match field {
+                1 => m.drifts.push(Drift::decode(value.bytes()?)?),
+                2 => m.within_tolerance = value.u64()? != 0,
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn request() -> PortfolioRequest {
+        PortfolioRequest {
+            cash: dec!(100),
+            holdings: vec![Holding {
+                ticker: "META".into(),
+                units: 10,
+                price: dec!(10),
+            }],
+            targets: vec![
+                TargetWeight {
+                    ticker: "META".into(),
+                    weight: dec!(50),
+                    price: dec!(10),
+                },
+                TargetWeight {
+                    ticker: "AAPL".into(),
+                    weight: dec!(50),
+                    price: dec!(20),
+                },
+            ],
+            target_cash: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_messages_round_trip_through_the_wire_format() {
+        let request = DriftRequest {
+            portfolio: request(),
+            tolerance: dec!(0.5),
+        };
+        assert_eq!(DriftRequest::decode(&request.encode()).unwrap(), request);
+
+        let framed = frame(&request.encode());
+        assert_eq!(unframe(&framed).unwrap(), request.encode().as_slice());
+        assert!(unframe(&framed[..framed.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_call_dispatches_by_path() {
+        let service = RebalanceService;
+        let body = request().encode();
+
+        let reply = service
+            .call("/fintual.rebalance.v1.Rebalancer/Rebalance", &body)
+            .unwrap();
+        let reply = RebalanceReply::decode(&reply).unwrap();
+        assert_eq!(reply.orders, vec![Order::buy("AAPL", 5)]);
+
+        let value = service
+            .call("/fintual.rebalance.v1.Rebalancer/Value", &body)
+            .unwrap();
+        assert_eq!(ValueReply::decode(&value).unwrap().total, dec!(200));
+
+        let error = service
+            .call("/fintual.rebalance.v1.Rebalancer/Nope", &body)
+            .unwrap_err();
+        assert_eq!(error.status_code(), 12);
+    }
+
+    #[test]
+    fn test_invalid_target_is_invalid_argument() {
+        let mut request = request();
+        request.targets.pop();
+        let error = RebalanceService.value(&request).unwrap_err();
+
+        assert!(matches!(error, GrpcError::Target(_)));
+        assert_eq!(error.status_code(), 3);
+    }
+
+    #[test]
+    fn test_huge_unit_counts_are_rejected_before_building() {
+        let mut request = request();
+        request.holdings[0].units = 1 << 60;
+        let body = request.encode();
+
+        let error = RebalanceService
+            .call("/fintual.rebalance.v1.Rebalancer/Value", &body)
+            .unwrap_err();
+        assert!(matches!(error, GrpcError::Decode(_)));
+        assert_eq!(error.status_code(), 3);
+
+        request.holdings[0].units = u64::MAX;
+        request.holdings.push(request.holdings[0].clone());
+        assert!(matches!(
+            RebalanceService.value(&request),
+            Err(GrpcError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn test_zero_prices_and_overflows_are_invalid_argument() {
+        let mut zero = request();
+        zero.targets[1].price = Decimal::ZERO;
+        let error = RebalanceService.rebalance(&zero).unwrap_err();
+        assert_eq!(error, GrpcError::InvalidPrice("AAPL".into()));
+        assert_eq!(error.status_code(), 3);
+
+        let mut huge = request();
+        huge.cash = Decimal::MAX;
+        let drift = DriftRequest {
+            portfolio: huge.clone(),
+            tolerance: dec!(1),
+        };
+        assert!(matches!(
+            RebalanceService.value(&huge),
+            Err(GrpcError::Overflow(_))
+        ));
+        assert!(matches!(
+            RebalanceService.drift(&drift),
+            Err(GrpcError::Overflow(_))
+        ));
+        assert!(matches!(
+            RebalanceService.rebalance(&huge),
+            Err(GrpcError::Overflow(_))
+        ));
+    }
+}
@@ -0,0 +1,190 @@
+//! Proteccion contra cotizaciones implausibles.
+//!
+//! Un feed malo o un dedo gordo ("500" en vez de "50.0") haria que el rebalanceo venda o compre
+//! de mas con total confianza. `PriceGuard` se pone entre la fuente de precios y el portafolio:
+//! una cotizacion que se mueve mas de `max_move`% respecto al precio anterior no se aplica, queda
+//! retenida como `PriceAnomaly`, y `rebalance` se niega a sugerir nada hasta que alguien la
+//! confirme (se aplica) o la descarte (se ignora).
+
+use crate::error::PriceGuardError;
+use crate::{Portfolio, RebalanceSuggestion};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use rust_decimal::Decimal;
+
+/// Una cotizacion retenida por moverse demasiado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceAnomaly {
+    pub ticker: String,
+    pub previous: Decimal,
+    pub quoted: Decimal,
+}
+
+impl PriceAnomaly {
+    /// Variacion respecto al precio anterior, en %.
+    pub fn change(&self) -> Decimal {
+        (self.quoted - self.previous) / self.previous * Decimal::ONE_HUNDRED
+    }
+}
+
+/// Que paso con una cotizacion que paso por el filtro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuoteStatus {
+    /// Se aplico al portafolio.
+    Applied,
+
+    /// Se retuvo; el portafolio sigue con el precio anterior.
+    Held(PriceAnomaly),
+
+    /// El ticker no esta en el portafolio ni en el objetivo.
+    Unknown,
+}
+
+/// Filtro de cotizaciones con sus anomalias pendientes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceGuard {
+    /// Variacion maxima aceptada sin confirmar, en %.
+    max_move: Decimal,
+    pending: BTreeMap<String, PriceAnomaly>,
+}
+
+impl PriceGuard {
+    pub fn new(max_move: Decimal) -> Self {
+        Self {
+            max_move,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Aplica `price` a `ticker` si no se aleja mas de `max_move`% del precio actual; si no, la
+    /// retiene. Una cotizacion nueva del mismo ticker reemplaza a la retenida (y se compara
+    /// contra el precio que tiene el portafolio, no contra la retenida).
+    pub fn update_price(
+        &mut self,
+        portfolio: &mut Portfolio,
+        ticker: &str,
+        price: Decimal,
+    ) -> QuoteStatus {
+        let Some(previous) = portfolio.priced(ticker).map(|s| s.current_price()) else {
+            return QuoteStatus::Unknown;
+        };
+
+        let anomaly = PriceAnomaly {
+            ticker: ticker.to_string(),
+            previous,
+            quoted: price,
+        };
+        if previous > Decimal::ZERO && anomaly.change().abs() > self.max_move {
+            self.pending.insert(ticker.to_string(), anomaly.clone());
+            return QuoteStatus::Held(anomaly);
+        }
+
+        self.pending.remove(ticker);
+        portfolio.update_price(ticker, price);
+        QuoteStatus::Applied
+    }
+
+    /// Las cotizaciones retenidas, ordenadas por ticker.
+    pub fn pending(&self) -> Vec<&PriceAnomaly> {
+        self.pending.values().collect()
+    }
+
+    /// Da por buena la cotizacion retenida de `ticker` y la aplica.
+    pub fn confirm(
+        &mut self,
+        portfolio: &mut Portfolio,
+        ticker: &str,
+    ) -> Result<(), PriceGuardError> {
+        let anomaly = self
+            .pending
+            .remove(ticker)
+            .ok_or_else(|| PriceGuardError::NothingPending(ticker.to_string()))?;
+        portfolio.update_price(ticker, anomaly.quoted);
+        Ok(())
+    }
+
+    /// Descarta la cotizacion retenida de `ticker`; el portafolio queda con el precio anterior.
+    pub fn dismiss(&mut self, ticker: &str) -> Result<PriceAnomaly, PriceGuardError> {
+        self.pending
+            .remove(ticker)
+            .ok_or_else(|| PriceGuardError::NothingPending(ticker.to_string()))
+    }
+
+    /// Error si queda alguna cotizacion sin resolver.
+    pub fn check(&self) -> Result<(), PriceGuardError> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(PriceGuardError::Unconfirmed(
+                self.pending.values().cloned().collect(),
+            ))
+        }
+    }
+
+    /// `Portfolio::rebalance_portfolio`, salvo que haya cotizaciones sin resolver. Para
+    /// rebalancear igual (override) basta llamar directamente a `rebalance_portfolio`, que usa
+    /// los precios anteriores de los tickers retenidos.
+    pub fn rebalance<'a>(
+        &self,
+        portfolio: &'a Portfolio,
+    ) -> Result<RebalanceSuggestion<'a>, PriceGuardError> {
+        self.check()?;
+        Ok(portfolio.rebalance_portfolio())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("META", dec!(50)); 2],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(50))),
+        }
+    }
+
+    #[test]
+    fn test_large_move_is_held_and_blocks_rebalancing() {
+        let mut portfolio = portfolio();
+        let mut guard = PriceGuard::new(dec!(20));
+
+        assert_eq!(
+            guard.update_price(&mut portfolio, "META", dec!(55)),
+            QuoteStatus::Applied
+        );
+        let status = guard.update_price(&mut portfolio, "META", dec!(550));
+        let QuoteStatus::Held(anomaly) = status else {
+            panic!("deberia retenerse: {status:?}");
+        };
+        assert_eq!(anomaly.change(), dec!(900));
+        assert_eq!(portfolio.value_of("META"), dec!(110));
+        assert!(matches!(
+            guard.rebalance(&portfolio),
+            Err(PriceGuardError::Unconfirmed(pending)) if pending.len() == 1
+        ));
+
+        guard.dismiss("META").unwrap();
+        assert!(guard.rebalance(&portfolio).is_ok());
+    }
+
+    #[test]
+    fn test_confirm_applies_the_held_quote() {
+        let mut portfolio = portfolio();
+        let mut guard = PriceGuard::new(dec!(20));
+        guard.update_price(&mut portfolio, "META", dec!(25));
+
+        guard.confirm(&mut portfolio, "META").unwrap();
+        assert_eq!(portfolio.value_of("META"), dec!(50));
+        assert!(guard.check().is_ok());
+        assert_eq!(
+            guard.confirm(&mut portfolio, "META"),
+            Err(PriceGuardError::NothingPending("META".into()))
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Planes de un hogar con varias cuentas.
+//!
+//! Un hogar (p. ej. la cuenta de cada integrante mas una conjunta) se rebalancea contra un solo
+//! objetivo comun, mirando todas las cuentas como un portafolio. La sugerencia no es un mapa plano
+//! de tickers sino las ordenes de cada cuenta, y si alguna cuenta no tiene caja para lo que le
+//! toca comprar, transferencias de caja desde las que si tienen.
+
+use crate::execution::{Order, Side};
+use crate::i18n::{Language, Localize, language};
+use crate::netting::{NettedOrders, net};
+use crate::{Portfolio, PortfolioTarget};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Una cuenta del hogar. El objetivo propio de `portfolio` no se usa: manda el del hogar.
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub name: String,
+    pub portfolio: Portfolio,
+}
+
+impl Account {
+    pub fn new(name: &str, portfolio: Portfolio) -> Self {
+        Self {
+            name: name.into(),
+            portfolio,
+        }
+    }
+}
+
+/// Caja que conviene mover de una cuenta a otra.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CashTransfer {
+    pub from: String,
+    pub to: String,
+    pub amount: Decimal,
+}
+
+/// Sugerencia de rebalanceo de un hogar: ordenes por cuenta y transferencias entre cuentas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HouseholdSuggestion {
+    /// Ordenes de cada cuenta (ventas primero). Las cuentas sin ordenes no aparecen.
+    pub accounts: BTreeMap<String, Vec<Order>>,
+    pub transfers: Vec<CashTransfer>,
+}
+
+impl HouseholdSuggestion {
+    pub fn orders_for(&self, account: &str) -> &[Order] {
+        self.accounts.get(account).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.transfers.is_empty()
+    }
+
+    /// Netea las ordenes entre cuentas (ver `netting::net`): lo que una cuenta vende y otra compra
+    /// pasa como traspaso y al broker solo va la diferencia. La caja de esos traspasos se cobra
+    /// entre las cuentas aparte.
+    pub fn netted(&self) -> NettedOrders {
+        net(&self.accounts)
+    }
+}
+
+impl Localize for HouseholdSuggestion {
+    fn localize(&self, language: Language) -> String {
+        if self.is_empty() {
+            return match language {
+                Language::Es => "No hay nada que rebalancear.".into(),
+                Language::En => "Nothing to rebalance.".into(),
+            };
+        }
+
+        let mut lines = Vec::new();
+        for (account, orders) in &self.accounts {
+            lines.push(format!("{account}:"));
+            lines.extend(orders.iter().map(|o| match (o.side, language) {
+                (Side::Buy, Language::Es) => format!("  comprar {} x{}", o.ticker, o.units),
+                (Side::Buy, Language::En) => format!("  buy {} x{}", o.ticker, o.units),
+                (Side::Sell, Language::Es) => format!("  vender {} x{}", o.ticker, o.units),
+                (Side::Sell, Language::En) => format!("  sell {} x{}", o.ticker, o.units),
+            }));
+        }
+        lines.extend(self.transfers.iter().map(|t| {
+            let amount = t.amount.round_dp(2);
+            match language {
+                Language::Es => format!("Transferir {amount} de {} a {}", t.from, t.to),
+                Language::En => format!("Transfer {amount} from {} to {}", t.from, t.to),
+            }
+        }));
+
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for HouseholdSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// Varias cuentas con un objetivo comun.
+#[derive(Debug, Clone)]
+pub struct Household {
+    accounts: Vec<Account>,
+    target: PortfolioTarget,
+}
+
+impl Household {
+    pub fn new(target: PortfolioTarget) -> Self {
+        Self {
+            accounts: Vec::new(),
+            target,
+        }
+    }
+
+    pub fn with_account(mut self, name: &str, portfolio: Portfolio) -> Self {
+        self.accounts.push(Account::new(name, portfolio));
+        self
+    }
+
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    pub fn target(&self) -> &PortfolioTarget {
+        &self.target
+    }
+
+    pub fn total_value(&self) -> Decimal {
+        self.accounts
+            .iter()
+            .map(|a| a.portfolio.total_value())
+            .sum()
+    }
+
+    /// Todas las cuentas como un solo portafolio con el objetivo del hogar.
+    pub fn combined(&self) -> Portfolio {
+        Portfolio {
+            stocks: self
+                .accounts
+                .iter()
+                .flat_map(|a| a.portfolio.stocks.iter().cloned())
+                .collect(),
+            allocation: self.target.clone(),
+            cash: self.accounts.iter().map(|a| a.portfolio.cash).sum(),
+            foreign_cash: Default::default(),
+        }
+    }
+
+    /// Rebalancea el hogar completo con la estrategia conservadora y reparte las ordenes:
+    ///
+    /// - cada venta sale de las cuentas que mas unidades tienen del ticker;
+    /// - cada compra se hace en las cuentas con mas caja (contando lo que liberan sus ventas),
+    ///   hasta donde les alcance; lo que ninguna alcanza a pagar completo queda en la que mas
+    ///   caja tiene;
+    /// - si una cuenta queda con caja negativa, se sugiere transferirle desde las que sobran.
+    pub fn rebalance(&self) -> HouseholdSuggestion {
+        let combined = self.combined();
+        let orders = combined.rebalance_portfolio().orders();
+
+        let mut cash: Vec<Decimal> = self.accounts.iter().map(|a| a.portfolio.cash).collect();
+        let mut assigned: BTreeMap<usize, Vec<Order>> = BTreeMap::new();
+
+        for order in orders {
+            let Some(price) = combined.priced(&order.ticker).map(|s| s.current_price()) else {
+                continue;
+            };
+
+            match order.side {
+                Side::Sell => {
+                    let mut holders: Vec<(usize, usize)> = self
+                        .accounts
+                        .iter()
+                        .enumerate()
+                        .map(|(i, a)| {
+                            let held = a
+                                .portfolio
+                                .stocks
+                                .iter()
+                                .filter(|s| s.name() == order.ticker);
+                            (i, held.count())
+                        })
+                        .filter(|(_, held)| *held > 0)
+                        .collect();
+                    holders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+                    let mut remaining = order.units;
+                    for (index, held) in holders {
+                        let units = held.min(remaining);
+                        if units == 0 {
+                            break;
+                        }
+                        remaining -= units;
+                        cash[index] += price * Decimal::from(units);
+                        assigned
+                            .entry(index)
+                            .or_default()
+                            .push(Order::sell(&order.ticker, units));
+                    }
+                }
+                Side::Buy => {
+                    let mut remaining = order.units;
+                    for index in by_cash(&cash) {
+                        let affordable = (cash[index] / price).floor().to_usize().unwrap_or(0);
+                        let units = affordable.min(remaining);
+                        if units == 0 {
+                            continue;
+                        }
+                        remaining -= units;
+                        cash[index] -= price * Decimal::from(units);
+                        push_buy(&mut assigned, index, &order.ticker, units);
+                    }
+
+                    if remaining > 0
+                        && let Some(&index) = by_cash(&cash).first()
+                    {
+                        cash[index] -= price * Decimal::from(remaining);
+                        push_buy(&mut assigned, index, &order.ticker, remaining);
+                    }
+                }
+            }
+        }
+
+        HouseholdSuggestion {
+            accounts: assigned
+                .into_iter()
+                .map(|(index, orders)| (self.accounts[index].name.clone(), orders))
+                .collect(),
+            transfers: self.transfers(&mut cash),
+        }
+    }
+
+    /// Cubre la caja negativa de cada cuenta con la de las cuentas que tienen de sobra, partiendo
+    /// por las que mas tienen.
+    fn transfers(&self, cash: &mut [Decimal]) -> Vec<CashTransfer> {
+        let mut transfers = Vec::new();
+        for to in 0..cash.len() {
+            while cash[to] < Decimal::ZERO {
+                let Some(&from) = by_cash(cash).first().filter(|&&i| cash[i] > Decimal::ZERO)
+                else {
+                    break;
+                };
+                let amount = (-cash[to]).min(cash[from]);
+                cash[from] -= amount;
+                cash[to] += amount;
+                transfers.push(CashTransfer {
+                    from: self.accounts[from].name.clone(),
+                    to: self.accounts[to].name.clone(),
+                    amount,
+                });
+            }
+        }
+
+        transfers
+    }
+}
+
+/// Indices de las cuentas de mayor a menor caja (en empate, en el orden en que se agregaron).
+fn by_cash(cash: &[Decimal]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..cash.len()).collect();
+    indices.sort_by(|a, b| cash[*b].cmp(&cash[*a]).then(a.cmp(b)));
+    indices
+}
+
+/// Agrega una compra a la cuenta, sumandola a una compra previa del mismo ticker si ya habia.
+fn push_buy(assigned: &mut BTreeMap<usize, Vec<Order>>, index: usize, ticker: &str, units: usize) {
+    let orders = assigned.entry(index).or_default();
+    match orders
+        .iter_mut()
+        .find(|o| o.side == Side::Buy && o.ticker == ticker)
+    {
+        Some(order) => order.units += units,
+        None => orders.push(Order::buy(ticker, units)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stock;
+    use rust_decimal_macros::dec;
+
+    fn account(cash: Decimal, stocks: Vec<Stock>) -> Portfolio {
+        Portfolio {
+            cash,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: PortfolioTarget::new(Stock::new("CASH", Decimal::ONE)),
+        }
+    }
+
+    #[test]
+    fn test_orders_are_assigned_per_account() {
+        let aapl = Stock::new("AAPL", dec!(10));
+        let msft = Stock::new("MSFT", dec!(10));
+        let target =
+            PortfolioTarget::try_from_vec(vec![(dec!(50), aapl.clone()), (dec!(50), msft)])
+                .unwrap();
+        let household = Household::new(target)
+            .with_account("Ana", account(Decimal::ZERO, vec![aapl.clone(); 15]))
+            .with_account("Conjunta", account(dec!(50), vec![aapl; 5]));
+
+        // 250 en total: 12 AAPL y 12 MSFT; Ana vende 8 AAPL y con eso compra 8 MSFT, la
+        // conjunta compra 4 MSFT con su caja
+        let suggestion = household.rebalance();
+        assert_eq!(
+            suggestion.orders_for("Ana"),
+            [Order::sell("AAPL", 8), Order::buy("MSFT", 8)]
+        );
+        assert_eq!(suggestion.orders_for("Conjunta"), [Order::buy("MSFT", 4)]);
+        assert!(suggestion.transfers.is_empty());
+    }
+
+    #[test]
+    fn test_transfer_when_an_account_lacks_cash() {
+        let target = PortfolioTarget::new(Stock::new("MSFT", dec!(10)));
+        let household = Household::new(target)
+            .with_account("Ana", account(dec!(15), vec![]))
+            .with_account("Beto", account(dec!(15), vec![]));
+
+        // cada una alcanza a comprar 1; la tercera unidad la compra Ana con 5 que le pasa Beto
+        let suggestion = household.rebalance();
+        assert_eq!(suggestion.orders_for("Ana"), [Order::buy("MSFT", 2)]);
+        assert_eq!(suggestion.orders_for("Beto"), [Order::buy("MSFT", 1)]);
+        assert_eq!(
+            suggestion.transfers,
+            vec![CashTransfer {
+                from: "Beto".into(),
+                to: "Ana".into(),
+                amount: dec!(5),
+            }]
+        );
+        assert_eq!(
+            suggestion.localize(Language::Es).lines().last(),
+            Some("Transferir 5 de Beto a Ana")
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use crate::money::Locale;
+use alloc::string::String;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// Idiomas en los que la libreria puede producir mensajes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    /// El idioma original del proyecto.
+    #[default]
+    Es,
+    En,
+}
+
+impl From<Locale> for Language {
+    fn from(locale: Locale) -> Self {
+        match locale {
+            Locale::EsCl => Language::Es,
+            Locale::EnUs => Language::En,
+        }
+    }
+}
+
+/// Locale con el que los reportes muestran montos y porcentajes en cada idioma.
+impl From<Language> for Locale {
+    fn from(language: Language) -> Self {
+        match language {
+            Language::Es => Locale::EsCl,
+            Language::En => Locale::EnUs,
+        }
+    }
+}
+
+// Se guarda como u8 para poder usar un atomico y no necesitar un Mutex global.
+static CURRENT_LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// Cambia el idioma que usan los `Display` de errores y reportes en todo el proceso.
+pub fn set_language(language: Language) {
+    let raw = match language {
+        Language::Es => 0,
+        Language::En => 1,
+    };
+    CURRENT_LANGUAGE.store(raw, Ordering::Relaxed);
+}
+
+/// Idioma actualmente seleccionado; por defecto español.
+pub fn language() -> Language {
+    match CURRENT_LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::En,
+        _ => Language::Es,
+    }
+}
+
+/// Algo que sabe mostrarse en mas de un idioma.
+///
+/// Los tipos que implementan este trait deberian implementar `Display` delegando a
+/// `localize(language())`, para que el idioma global aplique sin que el usuario tenga que
+/// pasarlo en todos lados.
+pub trait Localize {
+    fn localize(&self, language: Language) -> String;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_from_locale() {
+        assert_eq!(Language::from(Locale::EsCl), Language::Es);
+        assert_eq!(Language::from(Locale::EnUs), Language::En);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{Portfolio, RebalanceStrategy};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Identificador deterministico de una sugerencia de rebalanceo.
+///
+/// Es un hash del estado que la produjo (holdings con sus precios, objetivo y estrategia): dos
+/// sugerencias calculadas sobre el mismo estado tienen el mismo id, asi que un sistema de
+/// ejecucion puede usarlo para no mandar dos veces la misma orden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct SuggestionId(u64);
+
+impl SuggestionId {
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for SuggestionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// FNV-1a de 64 bits.
+///
+/// No uso `DefaultHasher` porque Rust no garantiza que produzca lo mismo entre versiones, y un id
+/// que cambia al actualizar el compilador no sirve para deduplicar.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// Escribe un campo seguido de un separador, para que ("AB", "C") y ("A", "BC") no choquen.
+    fn field(&mut self, value: &str) {
+        self.write(value.as_bytes());
+        self.write(&[0]);
+    }
+}
+
+impl Portfolio {
+    /// Id que tendria una sugerencia calculada ahora mismo con esta estrategia.
+    ///
+    /// Los decimales se normalizan (`10.0` y `10` son el mismo precio) y todo se ordena antes de
+    /// hashear, asi que el orden en que se agregaron los stocks no afecta el resultado.
+    pub fn state_id(&self, strategy: RebalanceStrategy) -> SuggestionId {
+        self.hash_state(strategy, true)
+    }
+
+    /// Como `state_id` pero sin los precios: solo cambia si cambian los holdings, la caja o el
+    /// objetivo. Sirve para tolerar movimientos chicos de precio (ver `expiry`).
+    pub fn structure_id(&self, strategy: RebalanceStrategy) -> SuggestionId {
+        self.hash_state(strategy, false)
+    }
+
+    fn hash_state(&self, strategy: RebalanceStrategy, prices: bool) -> SuggestionId {
+        let price = |stock: &crate::Stock| {
+            if prices {
+                stock.current_price().normalize().to_string()
+            } else {
+                String::new()
+            }
+        };
+        let mut holdings: Vec<(&str, String)> =
+            self.stocks().iter().map(|s| (s.name(), price(s))).collect();
+        holdings.sort();
+
+        let mut targets: Vec<(&str, String, String)> = self
+            .allocation()
+            .targets()
+            .iter()
+            .map(|(weight, s)| (s.name(), weight.normalize().to_string(), price(s)))
+            .collect();
+        targets.sort();
+
+        let mut hasher = Fnv1a::new();
+        hasher.field(strategy.name());
+
+        hasher.field("cash");
+        hasher.field(&self.cash().normalize().to_string());
+        for (currency, amount) in self.foreign_cash() {
+            hasher.field(currency.code());
+            hasher.field(&amount.normalize().to_string());
+        }
+
+        hasher.field("holdings");
+        for (name, price) in &holdings {
+            hasher.field(name);
+            hasher.field(price);
+        }
+
+        hasher.field("target");
+        for (name, weight, price) in &targets {
+            hasher.field(name);
+            hasher.field(weight);
+            hasher.field(price);
+        }
+        hasher.field(&self.allocation().cash_weight().normalize().to_string());
+
+        SuggestionId(hasher.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Portfolio, PortfolioTarget, Stock};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn portfolio(stocks: Vec<Stock>) -> Portfolio {
+        Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(25))),
+        }
+    }
+
+    #[test]
+    fn test_id_is_stable_and_order_independent() {
+        let a = portfolio(vec![Stock::new("A", dec!(1)), Stock::new("B", dec!(2.0))]);
+        let b = portfolio(vec![Stock::new("B", dec!(2)), Stock::new("A", dec!(1))]);
+
+        assert_eq!(a.rebalance_portfolio().id, b.rebalance_portfolio().id);
+    }
+
+    #[test]
+    fn test_verify_suggestion_detects_state_changes() {
+        let before = portfolio(vec![Stock::new("A", dec!(100))]);
+        let id = before.rebalance_portfolio().id;
+        assert!(before.verify_suggestion(id).is_ok());
+
+        let after = portfolio(vec![Stock::new("A", dec!(101))]);
+        assert!(after.verify_suggestion(id).is_err());
+    }
+}
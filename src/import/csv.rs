@@ -0,0 +1,141 @@
+//! Lector de planillas CSV.
+//!
+//! Es el formato mas simple para cargar una cartera a mano o desde una planilla: una fila por
+//! posicion con encabezado `ticker,units,price`. Una fila con ticker `CASH` suma `units * price`
+//! a la caja (p. ej. `CASH,1000,1`). Los objetivos van en otro archivo con encabezado
+//! `ticker,weight`, con pesos en %.
+
+use super::{Holding, ImportError, Statement};
+use crate::Map;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Filas de datos (sin encabezado ni lineas vacias) con su numero de linea, validando que el
+/// encabezado sea `expected`.
+fn rows<'a>(
+    input: &'a str,
+    expected: &[&str],
+) -> Result<impl Iterator<Item = (usize, Vec<&'a str>)>, ImportError> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let (number, header) = lines.next().ok_or_else(|| ImportError::Malformed {
+        line: 1,
+        reason: "archivo vacio".into(),
+    })?;
+    let columns: Vec<String> = header
+        .split(',')
+        .map(|c| c.trim().to_ascii_lowercase())
+        .collect();
+    if columns != expected {
+        return Err(ImportError::Malformed {
+            line: number,
+            reason: format!("se esperaba el encabezado {}", expected.join(",")),
+        });
+    }
+
+    let width = expected.len();
+    Ok(lines.map(move |(number, line)| {
+        let mut fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        fields.resize(width, "");
+        (number, fields)
+    }))
+}
+
+fn decimal(line: usize, field: &str, name: &str) -> Result<Decimal, ImportError> {
+    Decimal::from_str(field).map_err(|_| ImportError::Malformed {
+        line,
+        reason: format!("{name} invalido: {field:?}"),
+    })
+}
+
+fn out_of_range(line: usize) -> ImportError {
+    ImportError::Malformed {
+        line,
+        reason: "monto fuera de rango".into(),
+    }
+}
+
+/// Lee una planilla de posiciones.
+pub fn parse_csv(input: &str) -> Result<Statement, ImportError> {
+    let mut statement = Statement::default();
+
+    for (line, fields) in rows(input, &["ticker", "units", "price"])? {
+        let ticker = fields[0];
+        let units = decimal(line, fields[1], "units")?;
+        let price = decimal(line, fields[2], "price")?;
+
+        if ticker.eq_ignore_ascii_case("cash") {
+            statement.cash = units
+                .checked_mul(price)
+                .and_then(|amount| statement.cash.checked_add(amount))
+                .ok_or_else(|| out_of_range(line))?;
+        } else {
+            statement.holdings.push(Holding {
+                ticker: ticker.into(),
+                units,
+                price,
+            });
+        }
+    }
+
+    Ok(statement)
+}
+
+/// Lee una planilla de pesos objetivo. El resultado se convierte en `PortfolioTarget` junto con
+/// los precios (ver `TryFrom<(Map<String, Decimal>, &Map<String, Decimal>)>`).
+pub fn parse_weights_csv(input: &str) -> Result<Map<String, Decimal>, ImportError> {
+    let mut weights = Map::new();
+    for (line, fields) in rows(input, &["ticker", "weight"])? {
+        let weight = decimal(line, fields[1], "weight")?;
+        let total: &mut Decimal = weights.entry(fields[0].to_string()).or_default();
+        *total = total
+            .checked_add(weight)
+            .ok_or_else(|| out_of_range(line))?;
+    }
+
+    Ok(weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parse_holdings_and_weights() {
+        let statement =
+            parse_csv("Ticker,Units,Price\nMETA,4,500\n\nCASH,1000,1\nAAPL,2,190.5\n").unwrap();
+        assert_eq!(statement.cash, dec!(1000));
+        assert_eq!(statement.holdings.len(), 2);
+        assert_eq!(statement.holdings[1].price, dec!(190.5));
+
+        let weights = parse_weights_csv("ticker,weight\nMETA,60\nAAPL,40").unwrap();
+        assert_eq!(weights["META"], dec!(60));
+    }
+
+    #[test]
+    fn test_malformed_rows_report_line() {
+        assert_eq!(
+            parse_csv("ticker,units,price\nMETA,cuatro,500").unwrap_err(),
+            ImportError::Malformed {
+                line: 2,
+                reason: "units invalido: \"cuatro\"".into(),
+            }
+        );
+        assert!(parse_csv("ticker,price\nMETA,500").is_err());
+
+        let huge = "79228162514264337593543950335";
+        assert!(matches!(
+            parse_csv(&format!("ticker,units,price\nCASH,{huge},2")).unwrap_err(),
+            ImportError::Malformed { line: 2, .. }
+        ));
+        assert!(matches!(
+            parse_weights_csv(&format!("ticker,weight\nMETA,{huge}\nMETA,1")).unwrap_err(),
+            ImportError::Malformed { line: 3, .. }
+        ));
+    }
+}
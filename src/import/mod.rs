@@ -0,0 +1,135 @@
+//! Importadores de cartolas de brokers tradicionales y planillas CSV, y de objetivos definidos
+//! en plantillas YAML (ver `yaml`).
+//!
+//! Todos producen un `Statement` con las posiciones, el efectivo y el diario de transacciones;
+//! de ahi se arma un `Portfolio` con `Statement::into_portfolio`.
+
+pub mod csv;
+pub mod ofx;
+pub mod qif;
+pub mod yaml;
+
+use crate::i18n::{Language, Localize, language};
+use crate::journal::Journal;
+use crate::{Portfolio, PortfolioTarget, Stock};
+use rust_decimal::prelude::*;
+use std::fmt;
+
+pub use csv::{parse_csv, parse_weights_csv};
+pub use ofx::parse_ofx;
+pub use qif::parse_qif;
+pub use yaml::{TargetTemplates, TemplateError};
+
+/// Posicion reportada por la cartola.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holding {
+    pub ticker: String,
+    pub units: Decimal,
+    pub price: Decimal,
+}
+
+/// Contenido de una cartola importada.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Statement {
+    pub holdings: Vec<Holding>,
+    pub cash: Decimal,
+    pub transactions: Journal,
+}
+
+impl Statement {
+    /// Arma un portafolio con las posiciones y el efectivo de la cartola.
+    ///
+    /// `Portfolio` solo maneja unidades enteras, asi que una posicion fraccional es un error en
+    /// vez de truncarse en silencio. Tambien se rechazan posiciones cortas y precios no positivos.
+    pub fn into_portfolio(self, allocation: PortfolioTarget) -> Result<Portfolio, ImportError> {
+        let mut stocks = Vec::new();
+        for holding in &self.holdings {
+            if holding.units.is_sign_negative() {
+                return Err(ImportError::NegativeUnits {
+                    ticker: holding.ticker.clone(),
+                    units: holding.units,
+                });
+            }
+            if holding.price <= Decimal::ZERO {
+                return Err(ImportError::NonPositivePrice {
+                    ticker: holding.ticker.clone(),
+                    price: holding.price,
+                });
+            }
+
+            let units = holding
+                .units
+                .to_usize()
+                .filter(|_| holding.units.fract().is_zero())
+                .ok_or_else(|| ImportError::FractionalUnits {
+                    ticker: holding.ticker.clone(),
+                    units: holding.units,
+                })?;
+
+            stocks.extend(std::iter::repeat_n(
+                Stock::new(&holding.ticker, holding.price),
+                units,
+            ));
+        }
+
+        Ok(Portfolio {
+            stocks,
+            allocation,
+            cash: self.cash,
+            foreign_cash: Default::default(),
+        })
+    }
+}
+
+/// Errores al importar una cartola.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// El archivo no tiene el formato esperado.
+    Malformed { line: usize, reason: String },
+
+    /// Una posicion tiene unidades fraccionales, que `Portfolio` no puede representar.
+    FractionalUnits { ticker: String, units: Decimal },
+
+    /// Una posicion tiene unidades negativas (se vendio mas de lo comprado).
+    NegativeUnits { ticker: String, units: Decimal },
+
+    /// Una posicion tiene precio cero o negativo.
+    NonPositivePrice { ticker: String, price: Decimal },
+}
+
+impl Localize for ImportError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            ImportError::Malformed { line, reason } => match language {
+                Language::Es => format!("Archivo invalido en la linea {line}: {reason}"),
+                Language::En => format!("Malformed file at line {line}: {reason}"),
+            },
+            ImportError::FractionalUnits { ticker, units } => match language {
+                Language::Es => {
+                    format!("La posicion de {ticker} tiene unidades fraccionales ({units})")
+                }
+                Language::En => format!("Position {ticker} has fractional units ({units})"),
+            },
+            ImportError::NegativeUnits { ticker, units } => match language {
+                Language::Es => {
+                    format!("La posicion de {ticker} tiene unidades negativas ({units})")
+                }
+                Language::En => format!("Position {ticker} has negative units ({units})"),
+            },
+            ImportError::NonPositivePrice { ticker, price } => match language {
+                Language::Es => {
+                    format!("La posicion de {ticker} tiene un precio invalido ({price})")
+                }
+                Language::En => format!("Position {ticker} has a non-positive price ({price})"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for ImportError {}
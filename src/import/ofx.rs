@@ -0,0 +1,358 @@
+//! Lector de archivos OFX (versiones 1.x SGML y 2.x XML) de cuentas de inversion.
+//!
+//! No es un parser completo de OFX; solo entiende lo necesario para reconstruir una cuenta:
+//! `SECLIST` (para traducir identificadores a tickers), `INVPOSLIST`, `INVBAL` e `INVTRANLIST`.
+
+use super::{Holding, ImportError, Statement};
+use crate::date::Date;
+use crate::journal::{Transaction, TransactionKind};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Un elemento OFX: los agregados tienen hijos, las hojas tienen valor.
+#[derive(Debug, Default)]
+struct Node {
+    tag: String,
+    value: Option<String>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn child(&self, tag: &str) -> Option<&Node> {
+        self.children.iter().find(|c| c.tag == tag)
+    }
+
+    /// Valor de un descendiente, siguiendo un camino de tags.
+    fn value_at(&self, path: &[&str]) -> Option<&str> {
+        let mut node = self;
+        for tag in path {
+            node = node.child(tag)?;
+        }
+        node.value.as_deref()
+    }
+
+    /// Todos los descendientes (a cualquier profundidad) con alguno de estos tags.
+    fn find_all<'a>(&'a self, tags: &[&str], out: &mut Vec<&'a Node>) {
+        for child in &self.children {
+            if tags.contains(&child.tag.as_str()) {
+                out.push(child);
+            }
+            child.find_all(tags, out);
+        }
+    }
+}
+
+/// Arma el arbol de elementos.
+///
+/// En OFX 1.x las hojas no se cierran (`<UNITS>10`), asi que una hoja es cualquier tag seguido de
+/// texto; los agregados si se cierran explicitamente. En 2.x (XML) las hojas tambien se cierran,
+/// y esos cierres simplemente se ignoran. Cualquier otro cierre (uno que no corresponde a nada
+/// abierto, o `</>`) es un error. Los nombres de los tags no distinguen mayusculas.
+fn parse_tree(input: &str) -> Result<Node, ImportError> {
+    let start = input
+        .find('<')
+        .ok_or_else(|| malformed(input, input.len(), "no hay tags"))?;
+    let mut stack = vec![Node::default()];
+    let mut rest = &input[start..];
+    // la ultima hoja leida, cuyo cierre (XML) se acepta sin hacer nada
+    let mut last_leaf: Option<String> = None;
+
+    while let Some(open) = rest.find('<') {
+        let after = &rest[open + 1..];
+        let close = after
+            .find('>')
+            .ok_or_else(|| malformed(input, input.len() - after.len(), "tag sin cerrar"))?;
+        let tag = after[..close].trim();
+        let text_end = after[close + 1..]
+            .find('<')
+            .unwrap_or(after.len() - close - 1);
+        let text = after[close + 1..close + 1 + text_end].trim();
+        rest = &after[close + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim().to_uppercase();
+            if last_leaf.take().is_some_and(|leaf| leaf == name) {
+                // cierre de la hoja recien leida (XML): no hay nada que hacer
+                continue;
+            }
+            // cierre de un agregado abierto; la raiz (sin tag) no se puede cerrar
+            let Some(depth) = stack.iter().skip(1).rposition(|n| n.tag == name) else {
+                let offset = input.len() - after.len();
+                return Err(malformed(
+                    input,
+                    offset,
+                    &format!("cierre sin abrir </{name}>"),
+                ));
+            };
+            while stack.len() > depth + 1 {
+                let node = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(node);
+            }
+        } else if text.is_empty() {
+            last_leaf = None;
+            stack.push(Node {
+                tag: tag.to_uppercase(),
+                ..Default::default()
+            });
+        } else {
+            last_leaf = Some(tag.to_uppercase());
+            stack.last_mut().unwrap().children.push(Node {
+                tag: tag.to_uppercase(),
+                value: Some(unescape(text)),
+                children: Vec::new(),
+            });
+        }
+    }
+
+    // agregados que quedaron abiertos (archivo cortado): los cerramos igual
+    while stack.len() > 1 {
+        let node = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(node);
+    }
+
+    Ok(stack.pop().unwrap())
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn malformed(input: &str, offset: usize, reason: &str) -> ImportError {
+    ImportError::Malformed {
+        line: input[..offset.min(input.len())].lines().count().max(1),
+        reason: reason.into(),
+    }
+}
+
+fn invalid(reason: String) -> ImportError {
+    ImportError::Malformed { line: 0, reason }
+}
+
+/// Fechas OFX: `YYYYMMDD` seguido opcionalmente de hora y zona horaria, que ignoramos.
+fn parse_date(value: &str) -> Result<Date, ImportError> {
+    let digits = value.get(..8).unwrap_or_default();
+    let number = |range: std::ops::Range<usize>| digits.get(range)?.parse().ok();
+
+    match (number(0..4), number(4..6), number(6..8)) {
+        (Some(y), Some(m), Some(d)) => Date::new(y as i32, m, d),
+        _ => None,
+    }
+    .ok_or_else(|| invalid(format!("fecha invalida: {value}")))
+}
+
+fn parse_decimal(value: Option<&str>, field: &str) -> Result<Decimal, ImportError> {
+    let value = value.ok_or_else(|| invalid(format!("falta {field}")))?;
+    Decimal::from_str(value.trim()).map_err(|_| invalid(format!("{field} invalido: {value}")))
+}
+
+/// Lee una cartola OFX de inversiones.
+pub fn parse_ofx(input: &str) -> Result<Statement, ImportError> {
+    let root = parse_tree(input)?;
+
+    let mut securities = Vec::new();
+    root.find_all(&["SECINFO"], &mut securities);
+    let tickers: HashMap<&str, &str> = securities
+        .iter()
+        .filter_map(|s| {
+            Some((
+                s.value_at(&["SECID", "UNIQUEID"])?,
+                s.value_at(&["TICKER"])?,
+            ))
+        })
+        .collect();
+    let ticker_of = |node: &Node| -> Result<String, ImportError> {
+        let id = node
+            .value_at(&["SECID", "UNIQUEID"])
+            .ok_or_else(|| invalid("falta SECID".into()))?;
+        Ok(tickers.get(id).copied().unwrap_or(id).to_string())
+    };
+
+    let mut statement = Statement::default();
+
+    let mut positions = Vec::new();
+    root.find_all(&["INVPOS"], &mut positions);
+    for position in positions {
+        statement.holdings.push(Holding {
+            ticker: ticker_of(position)?,
+            units: parse_decimal(position.value_at(&["UNITS"]), "UNITS")?,
+            price: parse_decimal(position.value_at(&["UNITPRICE"]), "UNITPRICE")?,
+        });
+    }
+
+    let mut balances = Vec::new();
+    root.find_all(&["INVBAL"], &mut balances);
+    if let Some(balance) = balances.first() {
+        statement.cash = parse_decimal(balance.value_at(&["AVAILCASH"]), "AVAILCASH")?;
+    }
+
+    let mut trades = Vec::new();
+    root.find_all(&["INVBUY", "INVSELL", "INCOME"], &mut trades);
+    for trade in trades {
+        let date = parse_date(
+            trade
+                .value_at(&["INVTRAN", "DTTRADE"])
+                .ok_or_else(|| invalid("falta DTTRADE".into()))?,
+        )?;
+        let total = parse_decimal(trade.value_at(&["TOTAL"]), "TOTAL")?;
+
+        let transaction = match trade.tag.as_str() {
+            "INCOME" => Transaction {
+                ticker: Some(ticker_of(trade)?),
+                ..Transaction::cash(date, income_kind(trade), total)
+            },
+            tag => {
+                let kind = if tag == "INVBUY" {
+                    TransactionKind::Buy
+                } else {
+                    TransactionKind::Sell
+                };
+                Transaction {
+                    // en OFX las ventas vienen con unidades negativas
+                    units: parse_decimal(trade.value_at(&["UNITS"]), "UNITS")?.abs(),
+                    price: parse_decimal(trade.value_at(&["UNITPRICE"]), "UNITPRICE")?,
+                    ticker: Some(ticker_of(trade)?),
+                    cash_amount: total,
+                    ..Transaction::cash(date, kind, total)
+                }
+            }
+        };
+        statement.transactions.push(transaction);
+    }
+
+    let mut bank = Vec::new();
+    root.find_all(&["STMTTRN"], &mut bank);
+    for entry in bank {
+        let amount = parse_decimal(entry.value_at(&["TRNAMT"]), "TRNAMT")?;
+        let date = parse_date(
+            entry
+                .value_at(&["DTPOSTED"])
+                .ok_or_else(|| invalid("falta DTPOSTED".into()))?,
+        )?;
+        let kind = match entry.value_at(&["TRNTYPE"]) {
+            Some("FEE") | Some("SRVCHG") => TransactionKind::Fee,
+            Some("INT") => TransactionKind::Interest,
+            Some("DIV") => TransactionKind::Dividend,
+            _ if amount < Decimal::ZERO => TransactionKind::Withdrawal,
+            _ => TransactionKind::Deposit,
+        };
+        statement
+            .transactions
+            .push(Transaction::cash(date, kind, amount));
+    }
+
+    Ok(statement)
+}
+
+fn income_kind(node: &Node) -> TransactionKind {
+    match node.value_at(&["INCOMETYPE"]) {
+        Some("INTEREST") => TransactionKind::Interest,
+        _ => TransactionKind::Dividend,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const SGML: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+
+<OFX>
+<INVSTMTMSGSRSV1><INVSTMTTRNRS><INVSTMTRS>
+<INVTRANLIST>
+<BUYSTOCK><INVBUY>
+<INVTRAN><FITID>1<DTTRADE>20240115120000[-5:EST]</INVTRAN>
+<SECID><UNIQUEID>30303M102<UNIQUEIDTYPE>CUSIP</SECID>
+<UNITS>10<UNITPRICE>350.00<COMMISSION>1.00<TOTAL>-3501.00
+</INVBUY><BUYTYPE>BUY</BUYSTOCK>
+<INCOME><INVTRAN><FITID>2<DTTRADE>20240301</INVTRAN>
+<SECID><UNIQUEID>30303M102<UNIQUEIDTYPE>CUSIP</SECID>
+<INCOMETYPE>DIV<TOTAL>5.00</INCOME>
+<INVBANKTRAN><STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20240110<TRNAMT>5000.00<FITID>3</STMTTRN></INVBANKTRAN>
+</INVTRANLIST>
+<INVPOSLIST>
+<POSSTOCK><INVPOS>
+<SECID><UNIQUEID>30303M102<UNIQUEIDTYPE>CUSIP</SECID>
+<HELDINAREA>CASH<POSTYPE>LONG<UNITS>10<UNITPRICE>400.00<MKTVAL>4000.00<DTPRICEASOF>20240301
+</INVPOS></POSSTOCK>
+</INVPOSLIST>
+<INVBAL><AVAILCASH>1504.00<MARGINBALANCE>0<SHORTBALANCE>0</INVBAL>
+</INVSTMTRS></INVSTMTTRNRS></INVSTMTMSGSRSV1>
+<SECLISTMSGSRSV1><SECLIST>
+<STOCKINFO><SECINFO><SECID><UNIQUEID>30303M102<UNIQUEIDTYPE>CUSIP</SECID>
+<SECNAME>Meta Platforms<TICKER>META</SECINFO></STOCKINFO>
+</SECLIST></SECLISTMSGSRSV1>
+</OFX>";
+
+    #[test]
+    fn test_sgml_statement() {
+        let statement = parse_ofx(SGML).unwrap();
+
+        assert_eq!(
+            statement.holdings,
+            vec![Holding {
+                ticker: "META".into(),
+                units: dec!(10),
+                price: dec!(400),
+            }]
+        );
+        assert_eq!(statement.cash, dec!(1504));
+
+        let kinds: Vec<_> = statement
+            .transactions
+            .transactions()
+            .iter()
+            .map(|t| (t.kind, t.cash_amount))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (TransactionKind::Deposit, dec!(5000)),
+                (TransactionKind::Buy, dec!(-3501)),
+                (TransactionKind::Dividend, dec!(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xml_statement() {
+        let xml = r#"<?xml version="1.0"?><?OFX OFXHEADER="200"?>
+<OFX><INVSTMTMSGSRSV1><INVSTMTTRNRS><INVSTMTRS><INVPOSLIST><POSMF><INVPOS>
+<SECID><UNIQUEID>VTI</UNIQUEID><UNIQUEIDTYPE>TICKER</UNIQUEIDTYPE></SECID>
+<UNITS>3</UNITS><UNITPRICE>250.5</UNITPRICE>
+</INVPOS></POSMF></INVPOSLIST></INVSTMTRS></INVSTMTTRNRS></INVSTMTMSGSRSV1></OFX>"#;
+
+        let statement = parse_ofx(xml).unwrap();
+        assert_eq!(statement.holdings[0].ticker, "VTI");
+        assert_eq!(statement.holdings[0].price, dec!(250.5));
+    }
+
+    #[test]
+    fn test_invalid_number() {
+        let broken = SGML.replace("<UNITPRICE>400.00", "<UNITPRICE>cuatrocientos");
+        assert!(parse_ofx(&broken).is_err());
+
+        for stray in [
+            "<OFX></>",
+            "<OFX></SECLIST>",
+            "<OFX><UNITS>1</UNITS></UNITS>",
+        ] {
+            assert!(
+                matches!(parse_ofx(stray), Err(ImportError::Malformed { .. })),
+                "{stray}"
+            );
+        }
+        // los cierres no distinguen mayusculas, igual que las aperturas
+        let lower = SGML.replace("</INVPOSLIST>", "</invposlist>");
+        assert_eq!(parse_ofx(&lower).unwrap().holdings.len(), 1);
+    }
+}
@@ -0,0 +1,362 @@
+//! Lector de archivos QIF (Quicken Interchange Format).
+//!
+//! Entiende las secciones `!Type:Invst` (operaciones de inversion) y `!Type:Bank` /
+//! `!Type:Cash` (movimientos de caja). QIF no trae posiciones, asi que las reconstruimos sumando
+//! las compras y ventas, con el precio de la ultima operacion de cada ticker.
+
+use super::{Holding, ImportError, Statement};
+use crate::date::Date;
+use crate::journal::{Transaction, TransactionKind};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Investment,
+    Bank,
+    /// Secciones que no nos interesan (categorias, cuentas, etc.).
+    Other,
+}
+
+/// Campos de un registro QIF, antes de interpretarlos.
+#[derive(Debug, Default)]
+struct Record {
+    line: usize,
+    date: Option<String>,
+    action: Option<String>,
+    security: Option<String>,
+    price: Option<String>,
+    quantity: Option<String>,
+    amount: Option<String>,
+    commission: Option<String>,
+}
+
+/// Lee un archivo QIF.
+pub fn parse_qif(input: &str) -> Result<Statement, ImportError> {
+    let mut section = Section::Other;
+    let mut record = Record::default();
+    let mut statement = Statement::default();
+
+    // algunos exportadores parten el archivo con un BOM
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    for (index, raw) in input.lines().enumerate() {
+        let line = raw.trim_end();
+        let number = index + 1;
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('!') {
+            section = match header.to_ascii_lowercase().as_str() {
+                "type:invst" => Section::Investment,
+                "type:bank" | "type:cash" => Section::Bank,
+                _ => Section::Other,
+            };
+            continue;
+        }
+
+        if record.line == 0 {
+            record.line = number;
+        }
+
+        let mut chars = line.chars();
+        let code = chars.next().unwrap_or_default();
+        let value = Some(chars.as_str().trim().to_string());
+        match code {
+            '^' => {
+                let finished = std::mem::take(&mut record);
+                match section {
+                    Section::Investment => push_trade(&mut statement, finished)?,
+                    Section::Bank => push_bank(&mut statement, finished)?,
+                    Section::Other => {}
+                }
+            }
+            'D' => record.date = value,
+            'N' => record.action = value,
+            'Y' => record.security = value,
+            'I' => record.price = value,
+            'Q' => record.quantity = value,
+            'T' | 'U' => record.amount = value,
+            'O' => record.commission = value,
+            _ => {}
+        }
+    }
+
+    statement.cash = statement.transactions.cash_balance();
+    statement.holdings = holdings(&statement);
+
+    Ok(statement)
+}
+
+fn push_trade(statement: &mut Statement, record: Record) -> Result<(), ImportError> {
+    let line = record.line;
+    let date = parse_date(record.date.as_deref(), line)?;
+    let action = record.action.as_deref().unwrap_or_default();
+    let amount = parse_amount(record.amount.as_deref(), line)?;
+    let commission = parse_amount(record.commission.as_deref(), line)?;
+
+    let kind = match action.to_ascii_lowercase().as_str() {
+        "buy" | "buyx" | "shrsin" => TransactionKind::Buy,
+        "sell" | "sellx" | "shrsout" => TransactionKind::Sell,
+        "div" | "divx" | "cgshort" | "cglong" => TransactionKind::Dividend,
+        "intinc" | "intincx" => TransactionKind::Interest,
+        "miscexp" | "miscexpx" => TransactionKind::Fee,
+        "cash" | "xin" | "contrib" => TransactionKind::Deposit,
+        "xout" | "withdrwx" => TransactionKind::Withdrawal,
+        other => {
+            return Err(ImportError::Malformed {
+                line,
+                reason: format!("accion desconocida: {other}"),
+            });
+        }
+    };
+
+    let transaction = match kind {
+        TransactionKind::Buy | TransactionKind::Sell => {
+            let ticker = record.security.ok_or_else(|| ImportError::Malformed {
+                line,
+                reason: "operacion sin instrumento".into(),
+            })?;
+            let units = parse_amount(record.quantity.as_deref(), line)?;
+            let price = parse_amount(record.price.as_deref(), line)?;
+
+            // `T` ya incluye la comision; si no viene, la calculamos: en una compra se suma al
+            // costo y en una venta se descuenta del producto
+            let overflow = || ImportError::Malformed {
+                line,
+                reason: "monto fuera de rango".into(),
+            };
+            let net = if amount.is_zero() {
+                let gross = units.checked_mul(price).ok_or_else(overflow)?;
+                match kind {
+                    TransactionKind::Buy => gross.checked_add(commission),
+                    _ => gross.checked_sub(commission),
+                }
+                .ok_or_else(overflow)?
+            } else {
+                amount
+            };
+            let cash_amount = match kind {
+                TransactionKind::Buy => -net,
+                _ => net,
+            };
+
+            Transaction {
+                cash_amount,
+                ..Transaction::trade(date, kind, &ticker, units, price)
+            }
+        }
+        TransactionKind::Fee | TransactionKind::Withdrawal => Transaction {
+            ticker: record.security,
+            ..Transaction::cash(date, kind, -amount.abs())
+        },
+        _ => Transaction {
+            ticker: record.security,
+            ..Transaction::cash(date, kind, amount)
+        },
+    };
+
+    statement.transactions.push(transaction);
+    Ok(())
+}
+
+fn push_bank(statement: &mut Statement, record: Record) -> Result<(), ImportError> {
+    let date = parse_date(record.date.as_deref(), record.line)?;
+    let amount = parse_amount(record.amount.as_deref(), record.line)?;
+    let kind = if amount < Decimal::ZERO {
+        TransactionKind::Withdrawal
+    } else {
+        TransactionKind::Deposit
+    };
+
+    statement
+        .transactions
+        .push(Transaction::cash(date, kind, amount));
+    Ok(())
+}
+
+/// Posiciones que resultan de las compras y ventas del diario.
+fn holdings(statement: &Statement) -> Vec<Holding> {
+    let mut holdings: Vec<Holding> = Vec::new();
+
+    for transaction in statement.transactions.transactions() {
+        let Some(ticker) = &transaction.ticker else {
+            continue;
+        };
+        let units = match transaction.kind {
+            TransactionKind::Buy => transaction.units,
+            TransactionKind::Sell => -transaction.units,
+            _ => continue,
+        };
+
+        match holdings.iter_mut().find(|h| &h.ticker == ticker) {
+            Some(holding) => {
+                holding.units += units;
+                holding.price = transaction.price;
+            }
+            None => holdings.push(Holding {
+                ticker: ticker.clone(),
+                units,
+                price: transaction.price,
+            }),
+        }
+    }
+
+    holdings.retain(|h| !h.units.is_zero());
+    holdings
+}
+
+/// Montos QIF: pueden traer separador de miles (`1,234.50`). Un campo ausente vale cero.
+fn parse_amount(value: Option<&str>, line: usize) -> Result<Decimal, ImportError> {
+    let Some(value) = value.filter(|v| !v.is_empty()) else {
+        return Ok(Decimal::ZERO);
+    };
+
+    Decimal::from_str(&value.replace(',', "")).map_err(|_| ImportError::Malformed {
+        line,
+        reason: format!("monto invalido: {value}"),
+    })
+}
+
+/// Fechas QIF: `M/D/YYYY`, `M/D/YY` o `M/D'YY` (Quicken usa el apostrofe desde el 2000). Los
+/// años de dos digitos se interpretan entre 1970 y 2069.
+fn parse_date(value: Option<&str>, line: usize) -> Result<Date, ImportError> {
+    let invalid = || ImportError::Malformed {
+        line,
+        reason: format!("fecha invalida: {}", value.unwrap_or_default()),
+    };
+
+    let normalized = value.ok_or_else(invalid)?.replace('\'', "/");
+    let parts: Vec<u32> = normalized
+        .split(['/', '-'])
+        .map(|p| p.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+
+    let [month, day, year] = parts[..] else {
+        return Err(invalid());
+    };
+    let year = match year {
+        0..=69 => 2000 + year,
+        70..=99 => 1900 + year,
+        _ => year,
+    };
+
+    Date::new(year as i32, month, day).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    const QIF: &str = "!Type:Bank
+D1/02'24
+T5,000.00
+PDeposito
+^
+!Type:Invst
+D1/15'24
+NBuy
+YMETA
+I350.00
+Q10
+O1.00
+T3,501.00
+^
+D2/10/2024
+NSell
+YMETA
+I400.00
+Q4
+T1,600.00
+^
+D3/01'24
+NDiv
+YMETA
+T5.00
+^
+";
+
+    #[test]
+    fn test_holdings_and_cash_from_trades() {
+        let statement = parse_qif(QIF).unwrap();
+
+        assert_eq!(
+            statement.holdings,
+            vec![Holding {
+                ticker: "META".into(),
+                units: dec!(6),
+                price: dec!(400),
+            }]
+        );
+        assert_eq!(statement.cash, dec!(3104));
+        assert_eq!(statement.transactions.len(), 4);
+
+        let portfolio = statement
+            .into_portfolio(PortfolioTarget::new(Stock::new("META", dec!(400))))
+            .unwrap();
+        assert_eq!(portfolio.stocks().len(), 6);
+        assert_eq!(portfolio.cash(), dec!(3104));
+    }
+
+    #[test]
+    fn test_fractional_units_are_rejected() {
+        let statement = parse_qif("!Type:Invst\nD1/15/2024\nNBuy\nYVTI\nI100\nQ0.5\n^\n").unwrap();
+
+        assert_eq!(
+            statement
+                .into_portfolio(PortfolioTarget::new(Stock::new("VTI", dec!(100))))
+                .unwrap_err(),
+            ImportError::FractionalUnits {
+                ticker: "VTI".into(),
+                units: dec!(0.5),
+            }
+        );
+    }
+
+    #[test]
+    fn test_short_positions_and_zero_prices_are_rejected() {
+        let target = || PortfolioTarget::new(Stock::new("VTI", dec!(100)));
+        let short = parse_qif("!Type:Invst\nD1/15/2024\nNSell\nYVTI\nI100\nQ2\n^\n").unwrap();
+        assert_eq!(
+            short.into_portfolio(target()).unwrap_err(),
+            ImportError::NegativeUnits {
+                ticker: "VTI".into(),
+                units: dec!(-2),
+            }
+        );
+
+        let free = parse_qif("!Type:Invst\nD1/15/2024\nNBuy\nYVTI\nI0\nQ2\n^\n").unwrap();
+        assert!(matches!(
+            free.into_portfolio(target()).unwrap_err(),
+            ImportError::NonPositivePrice { .. }
+        ));
+    }
+
+    #[test]
+    fn test_sell_without_total_subtracts_commission() {
+        let statement =
+            parse_qif("!Type:Invst\nD1/15/2024\nNSell\nYVTI\nI100\nQ2\nO1.50\n^\n").unwrap();
+        assert_eq!(statement.cash, dec!(198.50));
+
+        let err = parse_qif(
+            "!Type:Invst\nD1/15/2024\nNBuy\nYVTI\nI79228162514264337593543950335\nQ2\n^\n",
+        )
+        .unwrap_err();
+        assert!(matches!(err, ImportError::Malformed { line: 2, .. }));
+    }
+
+    #[test]
+    fn test_bom_and_non_ascii_codes_do_not_panic() {
+        let input = format!("\u{feff}{QIF}ñ desconocido\n");
+        assert_eq!(parse_qif(&input).unwrap(), parse_qif(QIF).unwrap());
+    }
+
+    #[test]
+    fn test_bad_date_reports_line() {
+        let err = parse_qif("!Type:Bank\nD13/45/2024\nT10\n^\n").unwrap_err();
+        assert!(matches!(err, ImportError::Malformed { line: 2, .. }));
+    }
+}
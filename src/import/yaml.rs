@@ -0,0 +1,1000 @@
+//! Objetivos definidos en plantillas YAML.
+//!
+//! Pensado para asesores que manejan un modelo base y variantes por cliente: cada archivo define
+//! modelos con nombre, un modelo puede heredar de otro (`extends`) y un archivo puede incluir
+//! otros (`include`), p. ej. uno comun con los modelos de la casa.
+//!
+//! ```yaml
+//! include: base.yaml
+//!
+//! models:
+//!   conservador:
+//!     extends: base
+//!     cash: 10
+//!     stocks:
+//!       BND: { weight: 50, band: 2 }
+//!       VTI: 40
+//!     groups:
+//!       internacional:
+//!         weight: 0
+//!     rules:
+//!       max_issuer_weight: 60
+//!       banned: [XOM]
+//! ```
+//!
+//! Un grupo reparte su `weight` entre sus `stocks` (en % del grupo). Al heredar, cada ticker,
+//! grupo y regla del hijo reemplaza al del padre, y un peso 0 lo saca. Cada modelo se valida al
+//! leerlo y los errores indican archivo, linea y columna.
+//!
+//! No hay crate de YAML entre las dependencias, asi que se lee un subconjunto: mapas por
+//! indentacion, listas con `-`, mapas y listas en linea (`{ .. }`, `[ .. ]`), comentarios y
+//! escalares simples (sin anclas, multilinea ni documentos multiples).
+
+use crate::error::TargetError;
+use crate::i18n::{Language, Localize, language};
+use crate::rules::{Rule, RuleSet};
+use crate::universe::Universe;
+use crate::{Allocation, Map, PortfolioTarget, Stock};
+use rust_decimal::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Donde esta algo en las plantillas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// Que salio mal en una plantilla.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateErrorKind {
+    /// El texto no es YAML valido (dentro del subconjunto que se lee).
+    Syntax(String),
+
+    /// Un valor no tiene la forma esperada para esa clave.
+    Invalid(String),
+
+    /// `extends` apunta a un modelo que no existe.
+    UnknownModel(String),
+
+    /// No se pudo leer un archivo incluido.
+    Include(String),
+
+    /// Modelos o archivos que se heredan o incluyen a si mismos.
+    Cycle(String),
+
+    /// El modelo resultante no es un objetivo valido.
+    Target(TargetError),
+}
+
+/// Error al leer o resolver una plantilla, con su ubicacion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub location: Location,
+    pub kind: TemplateErrorKind,
+}
+
+impl Localize for TemplateError {
+    fn localize(&self, language: Language) -> String {
+        use TemplateErrorKind::*;
+
+        let message = match (&self.kind, language) {
+            (Syntax(reason), Language::Es) => format!("YAML invalido: {reason}"),
+            (Syntax(reason), Language::En) => format!("invalid YAML: {reason}"),
+            (Invalid(reason), _) => reason.clone(),
+            (UnknownModel(name), Language::Es) => format!("no existe el modelo {name}"),
+            (UnknownModel(name), Language::En) => format!("unknown model {name}"),
+            (Include(file), Language::Es) => format!("no se pudo leer {file}"),
+            (Include(file), Language::En) => format!("could not read {file}"),
+            (Cycle(name), Language::Es) => format!("{name} se hereda o incluye a si mismo"),
+            (Cycle(name), Language::En) => format!("{name} extends or includes itself"),
+            (Target(error), language) => error.localize(language),
+        };
+        format!("{}: {message}", self.location)
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Un valor YAML con la posicion donde empieza.
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    value: Value,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Scalar(String),
+    List(Vec<Node>),
+    /// Pares clave-valor en orden, con la posicion de cada clave.
+    Map(Vec<(String, Node)>),
+}
+
+impl Node {
+    /// Un bloque debajo de una clave (o de un `-`) se ubica en la clave, que es lo que se ve al
+    /// buscar el error en el archivo.
+    fn at_key(mut self, line: usize, column: usize) -> Self {
+        if self.line != line {
+            self.line = line;
+            self.column = column;
+        }
+        self
+    }
+}
+
+struct Parser<'a> {
+    file: &'a str,
+    /// Lineas con contenido: numero, indentacion y texto sin comentario.
+    lines: Vec<(usize, usize, &'a str)>,
+    next: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(file: &'a str, source: &'a str) -> Result<Self, TemplateError> {
+        let mut lines = Vec::new();
+        for (index, raw) in source.lines().enumerate() {
+            let content = strip_comment(raw).trim_end();
+            let indent = content.len() - content.trim_start_matches(' ').len();
+            if content[indent..].starts_with('\t') {
+                return Err(syntax(file, index + 1, indent + 1, "indentacion con tabs"));
+            }
+            if !content.trim().is_empty() {
+                lines.push((index + 1, indent, content.trim()));
+            }
+        }
+        Ok(Self {
+            file,
+            lines,
+            next: 0,
+        })
+    }
+
+    fn document(mut self) -> Result<Node, TemplateError> {
+        let Some(&(_, indent, _)) = self.lines.first() else {
+            return Ok(Node {
+                value: Value::Map(Vec::new()),
+                line: 1,
+                column: 1,
+            });
+        };
+        let node = self.block(indent)?;
+        if let Some(&(line, indent, _)) = self.lines.get(self.next) {
+            return Err(syntax(
+                self.file,
+                line,
+                indent + 1,
+                "indentacion inesperada",
+            ));
+        }
+        Ok(node)
+    }
+
+    /// Un mapa o lista cuyas lineas tienen indentacion `indent`.
+    fn block(&mut self, indent: usize) -> Result<Node, TemplateError> {
+        let (line, _, text) = self.lines[self.next];
+        let is_list = text == "-" || text.starts_with("- ");
+        let mut items = Vec::new();
+        let mut entries = Vec::new();
+
+        while let Some(&(number, current, text)) = self.lines.get(self.next) {
+            if current < indent {
+                break;
+            }
+            if current > indent {
+                return Err(syntax(
+                    self.file,
+                    number,
+                    current + 1,
+                    "indentacion inesperada",
+                ));
+            }
+            self.next += 1;
+
+            if is_list {
+                let Some(rest) = text.strip_prefix('-') else {
+                    return Err(syntax(self.file, number, current + 1, "se esperaba `- `"));
+                };
+                let column = current + 1 + (text.len() - rest.trim_start().len());
+                let value = self.value(rest.trim_start(), number, column, indent)?;
+                items.push(value.at_key(number, current + 1));
+            } else {
+                let Some((key, rest)) = split_key(text) else {
+                    return Err(syntax(
+                        self.file,
+                        number,
+                        current + 1,
+                        "se esperaba `clave:`",
+                    ));
+                };
+                if entries.iter().any(|(k, _)| k == key) {
+                    let reason = format!("clave repetida {key}");
+                    return Err(syntax(self.file, number, current + 1, &reason));
+                }
+                let column = current + 1 + (text.len() - rest.len());
+                let value = self.value(rest, number, column, indent)?;
+                entries.push((unquote(key).to_string(), value.at_key(number, current + 1)));
+            }
+        }
+
+        Ok(Node {
+            value: if is_list {
+                Value::List(items)
+            } else {
+                Value::Map(entries)
+            },
+            line,
+            column: indent + 1,
+        })
+    }
+
+    /// El valor de una clave o item: en la misma linea, o un bloque mas indentado debajo.
+    fn value(
+        &mut self,
+        text: &str,
+        line: usize,
+        column: usize,
+        indent: usize,
+    ) -> Result<Node, TemplateError> {
+        if !text.is_empty() {
+            let mut inline = Inline {
+                file: self.file,
+                text,
+                position: 0,
+                line,
+                column,
+                depth: 0,
+            };
+            let node = inline.node()?;
+            inline.skip_spaces();
+            if inline.position < text.len() {
+                return Err(inline.error("texto sobrante"));
+            }
+            return Ok(node);
+        }
+
+        match self.lines.get(self.next) {
+            Some(&(_, child, _)) if child > indent => self.block(child),
+            _ => Ok(Node {
+                value: Value::Scalar(String::new()),
+                line,
+                column,
+            }),
+        }
+    }
+}
+
+/// Anidacion maxima de `[..]` y `{..}` en una linea; mas alla se rechaza en vez de agotar la pila.
+const MAX_DEPTH: usize = 32;
+
+/// Lector de valores en linea: escalares, `[..]` y `{..}`.
+struct Inline<'a> {
+    file: &'a str,
+    text: &'a str,
+    position: usize,
+    line: usize,
+    column: usize,
+    depth: usize,
+}
+
+impl Inline<'_> {
+    fn error(&self, reason: &str) -> TemplateError {
+        syntax(self.file, self.line, self.column + self.position, reason)
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn node(&mut self) -> Result<Node, TemplateError> {
+        self.skip_spaces();
+        let (line, column) = (self.line, self.column + self.position);
+        if matches!(self.peek(), Some('[' | '{')) {
+            if self.depth == MAX_DEPTH {
+                return Err(self.error("anidacion demasiado profunda"));
+            }
+            self.depth += 1;
+        }
+        let value = match self.peek() {
+            Some('[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.separated(']', |inline| {
+                    items.push(inline.node()?);
+                    Ok(())
+                })?;
+                self.depth -= 1;
+                Value::List(items)
+            }
+            Some('{') => {
+                self.position += 1;
+                let mut entries: Vec<(String, Node)> = Vec::new();
+                self.separated('}', |inline| {
+                    let key = inline.scalar(":")?;
+                    if inline.peek() != Some(':') {
+                        return Err(inline.error("se esperaba `:`"));
+                    }
+                    inline.position += 1;
+                    entries.push((key, inline.node()?));
+                    Ok(())
+                })?;
+                self.depth -= 1;
+                Value::Map(entries)
+            }
+            Some(_) => Value::Scalar(self.scalar("")?),
+            None => return Err(self.error("falta un valor")),
+        };
+        Ok(Node {
+            value,
+            line,
+            column,
+        })
+    }
+
+    /// Items separados por comas hasta `close`.
+    fn separated(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<(), TemplateError>,
+    ) -> Result<(), TemplateError> {
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some(c) if c == close => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                None => return Err(self.error(&format!("falta `{close}`"))),
+                _ => {}
+            }
+            item(self)?;
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(c) if c == close => {}
+                _ => return Err(self.error(&format!("se esperaba `,` o `{close}`"))),
+            }
+        }
+    }
+
+    /// Un escalar, entre comillas o hasta una coma, cierre o alguno de `stop`.
+    fn scalar(&mut self, stop: &str) -> Result<String, TemplateError> {
+        self.skip_spaces();
+        let rest = &self.text[self.position..];
+        if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            let Some(end) = rest[1..].find(quote) else {
+                return Err(self.error("comillas sin cerrar"));
+            };
+            self.position += end + 2;
+            return Ok(rest[1..end + 1].to_string());
+        }
+
+        let end = rest
+            .find(|c: char| c == ',' || c == ']' || c == '}' || stop.contains(c))
+            .unwrap_or(rest.len());
+        self.position += end;
+        Ok(rest[..end].trim().to_string())
+    }
+}
+
+fn syntax(file: &str, line: usize, column: usize, reason: &str) -> TemplateError {
+    TemplateError {
+        location: Location {
+            file: file.into(),
+            line,
+            column,
+        },
+        kind: TemplateErrorKind::Syntax(reason.into()),
+    }
+}
+
+/// La linea sin su comentario: un `#` al comienzo o despues de un espacio, fuera de comillas.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return &line[..index],
+            None => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+/// Separa `clave: valor` (o `clave:` sola) en clave y resto.
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    let index = text
+        .find(": ")
+        .or_else(|| text.ends_with(':').then(|| text.len() - 1))?;
+    let key = text[..index].trim();
+    (!key.is_empty() && !key.starts_with(['[', '{', '-'])).then(|| (key, text[index + 1..].trim()))
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .or_else(|| text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')))
+        .unwrap_or(text)
+}
+
+/// Peso de un ticker en un modelo, con su banda si tiene.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    weight: Decimal,
+    band: Option<Decimal>,
+    location: Location,
+}
+
+/// Un grupo: su peso en el portafolio y como se reparte entre sus tickers (en % del grupo).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Group {
+    weight: Decimal,
+    stocks: BTreeMap<String, Entry>,
+    location: Location,
+}
+
+/// Un modelo tal como esta escrito, antes de aplicar la herencia.
+#[derive(Debug, Clone)]
+struct Definition {
+    extends: Option<(String, Location)>,
+    cash: Option<Decimal>,
+    stocks: BTreeMap<String, Entry>,
+    groups: BTreeMap<String, Group>,
+    /// Reglas por clave: `banned` guarda la lista entera, asi un hijo la reemplaza completa.
+    rules: BTreeMap<String, Vec<Rule>>,
+    location: Location,
+}
+
+/// Un modelo con la herencia aplicada y los grupos repartidos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Model {
+    cash: Decimal,
+    stocks: BTreeMap<String, Entry>,
+    rules: Vec<Rule>,
+    location: Location,
+}
+
+impl Model {
+    /// Peso final de cada ticker, en %.
+    pub fn weights(&self) -> impl Iterator<Item = (&str, Decimal)> {
+        self.stocks
+            .iter()
+            .map(|(t, entry)| (t.as_str(), entry.weight))
+    }
+
+    pub fn cash_weight(&self) -> Decimal {
+        self.cash
+    }
+
+    /// Las reglas del modelo, para revisarlas con la metadata de `universe`.
+    pub fn rules(&self, universe: Universe) -> RuleSet {
+        self.rules
+            .iter()
+            .cloned()
+            .fold(RuleSet::new(universe), RuleSet::with)
+    }
+
+    /// El objetivo del modelo, con los precios de cada ticker.
+    pub fn target(&self, prices: &Map<String, Decimal>) -> Result<PortfolioTarget, TemplateError> {
+        self.build(|ticker| prices.get(ticker).copied())
+    }
+
+    fn build(
+        &self,
+        price: impl Fn(&str) -> Option<Decimal>,
+    ) -> Result<PortfolioTarget, TemplateError> {
+        let error = |location: &Location, error| TemplateError {
+            location: location.clone(),
+            kind: TemplateErrorKind::Target(error),
+        };
+
+        let mut allocations = Vec::new();
+        for (ticker, entry) in &self.stocks {
+            let price = price(ticker)
+                .ok_or_else(|| error(&entry.location, TargetError::MissingPrice(ticker.clone())))?;
+            if entry.weight <= Decimal::ZERO {
+                return Err(error(
+                    &entry.location,
+                    TargetError::NonPositiveWeight(ticker.clone()),
+                ));
+            }
+            allocations.push(Allocation::Stock(entry.weight, Stock::new(ticker, price)));
+        }
+        if !self.cash.is_zero() {
+            allocations.push(Allocation::Cash(self.cash));
+        }
+
+        let target = PortfolioTarget::try_from_allocations(allocations)
+            .map_err(|e| error(&self.location, e))?;
+        Ok(self
+            .stocks
+            .iter()
+            .filter_map(|(ticker, entry)| Some((ticker, entry.band?)))
+            .fold(target, |target, (ticker, band)| {
+                target.with_band(ticker, band)
+            }))
+    }
+}
+
+/// Los modelos de un archivo de plantillas y de los que incluye, ya resueltos y validados.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetTemplates {
+    models: BTreeMap<String, Model>,
+}
+
+impl TargetTemplates {
+    /// Lee una plantilla sin includes (un `include` es un error).
+    pub fn parse(file: &str, source: &str) -> Result<Self, TemplateError> {
+        Self::parse_with(file, source, |_| None)
+    }
+
+    /// Lee una plantilla pidiendole a `read` el contenido de cada archivo incluido.
+    pub fn parse_with(
+        file: &str,
+        source: &str,
+        read: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, TemplateError> {
+        let mut definitions = BTreeMap::new();
+        collect(file, source, &read, &mut Vec::new(), &mut definitions)?;
+
+        let mut models = BTreeMap::new();
+        for name in definitions.keys() {
+            resolve(name, &definitions, &mut models, &mut Vec::new())?;
+        }
+        // se valida con precios de mentira: que los pesos sumen 100 y sean positivos
+        for model in models.values() {
+            model.build(|_| Some(Decimal::ONE))?;
+        }
+
+        Ok(Self { models })
+    }
+
+    /// Lee el archivo en `path`; los includes son rutas relativas a su carpeta.
+    pub fn load(path: &Path) -> Result<Self, TemplateError> {
+        let file = path.display().to_string();
+        let source = std::fs::read_to_string(path).map_err(|_| TemplateError {
+            location: Location {
+                file: file.clone(),
+                line: 1,
+                column: 1,
+            },
+            kind: TemplateErrorKind::Include(file.clone()),
+        })?;
+        let folder = path.parent().unwrap_or(Path::new(""));
+        Self::parse_with(&file, &source, |include| {
+            std::fs::read_to_string(folder.join(include)).ok()
+        })
+    }
+
+    pub fn model(&self, name: &str) -> Option<&Model> {
+        self.models.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.models.keys().map(String::as_str)
+    }
+
+    /// El objetivo del modelo `name`, con los precios de cada ticker; `None` si no existe.
+    pub fn target(
+        &self,
+        name: &str,
+        prices: &Map<String, Decimal>,
+    ) -> Option<Result<PortfolioTarget, TemplateError>> {
+        Some(self.model(name)?.target(prices))
+    }
+}
+
+/// Lee las definiciones de `file` y de lo que incluye. Lo definido en `file` reemplaza a lo
+/// incluido.
+fn collect(
+    file: &str,
+    source: &str,
+    read: &impl Fn(&str) -> Option<String>,
+    stack: &mut Vec<String>,
+    definitions: &mut BTreeMap<String, Definition>,
+) -> Result<(), TemplateError> {
+    stack.push(file.into());
+    let root = Parser::new(file, source)?.document()?;
+    let at = |node: &Node| Location {
+        file: file.into(),
+        line: node.line,
+        column: node.column,
+    };
+    let entries = map(&root, file)?;
+
+    // primero los includes, para que los modelos locales siempre les ganen
+    for (_, node) in entries.iter().filter(|(key, _)| key == "include") {
+        let includes = match &node.value {
+            Value::List(items) => items.iter().collect(),
+            _ => vec![node],
+        };
+        for include in includes {
+            let name = scalar(include, file)?;
+            let error = |kind| TemplateError {
+                location: at(include),
+                kind,
+            };
+            if stack.iter().any(|f| f == name) {
+                return Err(error(TemplateErrorKind::Cycle(name.into())));
+            }
+            let source =
+                read(name).ok_or_else(|| error(TemplateErrorKind::Include(name.into())))?;
+            collect(name, &source, read, stack, definitions)?;
+        }
+    }
+
+    for (key, node) in entries {
+        match key.as_str() {
+            "include" => {}
+            "models" => {
+                for (name, model) in map(node, file)? {
+                    definitions.insert(name.clone(), definition(model, file)?);
+                }
+            }
+            _ => return Err(invalid(node, file, &format!("clave desconocida {key}"))),
+        }
+    }
+
+    stack.pop();
+    Ok(())
+}
+
+fn definition(node: &Node, file: &str) -> Result<Definition, TemplateError> {
+    let location = |node: &Node| Location {
+        file: file.into(),
+        line: node.line,
+        column: node.column,
+    };
+    let mut definition = Definition {
+        extends: None,
+        cash: None,
+        stocks: BTreeMap::new(),
+        groups: BTreeMap::new(),
+        rules: BTreeMap::new(),
+        location: location(node),
+    };
+
+    for (key, value) in map(node, file)? {
+        match key.as_str() {
+            "extends" => definition.extends = Some((scalar(value, file)?.into(), location(value))),
+            "cash" => definition.cash = Some(decimal(value, file)?),
+            "stocks" => definition.stocks = stocks(value, file)?,
+            "groups" => {
+                for (name, group) in map(value, file)? {
+                    let mut weight = None;
+                    let mut members = BTreeMap::new();
+                    for (key, value) in map(group, file)? {
+                        match key.as_str() {
+                            "weight" => weight = Some(decimal(value, file)?),
+                            "stocks" => members = stocks(value, file)?,
+                            _ => {
+                                return Err(invalid(
+                                    value,
+                                    file,
+                                    &format!("clave desconocida {key}"),
+                                ));
+                            }
+                        }
+                    }
+                    let weight =
+                        weight.ok_or_else(|| invalid(group, file, "al grupo le falta weight"))?;
+                    let group = Group {
+                        weight,
+                        stocks: members,
+                        location: location(group),
+                    };
+                    definition.groups.insert(name.clone(), group);
+                }
+            }
+            "rules" => {
+                for (name, value) in map(value, file)? {
+                    let rule = match name.as_str() {
+                        "max_issuer_weight" => Rule::MaxIssuerWeight(decimal(value, file)?),
+                        "max_sector_weight" => Rule::MaxSectorWeight(decimal(value, file)?),
+                        "min_holdings" => Rule::MinHoldings(
+                            scalar(value, file)?
+                                .parse()
+                                .map_err(|_| invalid(value, file, "se esperaba un entero"))?,
+                        ),
+                        "banned" => {
+                            let Value::List(items) = &value.value else {
+                                return Err(invalid(value, file, "se esperaba una lista"));
+                            };
+                            // una regla por ticker, guardadas juntas bajo la misma clave
+                            let banned = items
+                                .iter()
+                                .map(|item| Ok(Rule::Banned(scalar(item, file)?.into())))
+                                .collect::<Result<_, TemplateError>>()?;
+                            definition.rules.insert(name.clone(), banned);
+                            continue;
+                        }
+                        _ => {
+                            return Err(invalid(value, file, &format!("regla desconocida {name}")));
+                        }
+                    };
+                    definition.rules.insert(name.clone(), vec![rule]);
+                }
+            }
+            _ => return Err(invalid(value, file, &format!("clave desconocida {key}"))),
+        }
+    }
+
+    Ok(definition)
+}
+
+/// Tickers con su peso: `VTI: 60` o `VTI: { weight: 60, band: 2 }`.
+fn stocks(node: &Node, file: &str) -> Result<BTreeMap<String, Entry>, TemplateError> {
+    let mut stocks = BTreeMap::new();
+    for (ticker, value) in map(node, file)? {
+        let location = Location {
+            file: file.into(),
+            line: value.line,
+            column: value.column,
+        };
+        let entry = match &value.value {
+            Value::Scalar(_) => Entry {
+                weight: decimal(value, file)?,
+                band: None,
+                location,
+            },
+            _ => {
+                let mut weight = None;
+                let mut band = None;
+                for (key, value) in map(value, file)? {
+                    match key.as_str() {
+                        "weight" => weight = Some(decimal(value, file)?),
+                        "band" => band = Some(decimal(value, file)?),
+                        _ => return Err(invalid(value, file, &format!("clave desconocida {key}"))),
+                    }
+                }
+                Entry {
+                    weight: weight.ok_or_else(|| invalid(value, file, "falta weight"))?,
+                    band,
+                    location,
+                }
+            }
+        };
+        stocks.insert(ticker.clone(), entry);
+    }
+    Ok(stocks)
+}
+
+/// Aplica la herencia de `name` (y de sus ancestros) y reparte sus grupos.
+fn resolve(
+    name: &str,
+    definitions: &BTreeMap<String, Definition>,
+    models: &mut BTreeMap<String, Model>,
+    stack: &mut Vec<String>,
+) -> Result<Definition, TemplateError> {
+    let definition = &definitions[name];
+    let mut merged = match &definition.extends {
+        None => definition.clone(),
+        Some((parent, location)) => {
+            let error = |kind| TemplateError {
+                location: location.clone(),
+                kind,
+            };
+            if !definitions.contains_key(parent) {
+                return Err(error(TemplateErrorKind::UnknownModel(parent.clone())));
+            }
+            if stack.iter().any(|n| n == parent) || parent == name {
+                return Err(error(TemplateErrorKind::Cycle(parent.clone())));
+            }
+            stack.push(name.into());
+            let mut merged = resolve(parent, definitions, models, stack)?;
+            stack.pop();
+
+            merged.cash = definition.cash.or(merged.cash);
+            merged.stocks.extend(definition.stocks.clone());
+            merged.groups.extend(definition.groups.clone());
+            merged.rules.extend(definition.rules.clone());
+            merged.location = definition.location.clone();
+            merged
+        }
+    };
+
+    // peso 0 saca al ticker o grupo heredado
+    merged.stocks.retain(|_, entry| !entry.weight.is_zero());
+    merged.groups.retain(|_, group| !group.weight.is_zero());
+
+    let mut stocks = merged.stocks.clone();
+    for group in merged.groups.values() {
+        for (ticker, entry) in &group.stocks {
+            let weight = group.weight * entry.weight / Decimal::ONE_HUNDRED;
+            stocks
+                .entry(ticker.clone())
+                .and_modify(|existing| existing.weight += weight)
+                .or_insert(Entry {
+                    weight,
+                    ..entry.clone()
+                });
+        }
+    }
+
+    models.insert(
+        name.into(),
+        Model {
+            cash: merged.cash.unwrap_or_default(),
+            stocks,
+            rules: merged.rules.values().flatten().cloned().collect(),
+            location: merged.location.clone(),
+        },
+    );
+    Ok(merged)
+}
+
+fn invalid(node: &Node, file: &str, reason: &str) -> TemplateError {
+    TemplateError {
+        location: Location {
+            file: file.into(),
+            line: node.line,
+            column: node.column,
+        },
+        kind: TemplateErrorKind::Invalid(reason.into()),
+    }
+}
+
+fn map<'n>(node: &'n Node, file: &str) -> Result<&'n [(String, Node)], TemplateError> {
+    match &node.value {
+        Value::Map(entries) => Ok(entries),
+        _ => Err(invalid(node, file, "se esperaba un mapa")),
+    }
+}
+
+fn scalar<'n>(node: &'n Node, file: &str) -> Result<&'n str, TemplateError> {
+    match &node.value {
+        Value::Scalar(text) if !text.is_empty() => Ok(text),
+        _ => Err(invalid(node, file, "se esperaba un valor")),
+    }
+}
+
+fn decimal(node: &Node, file: &str) -> Result<Decimal, TemplateError> {
+    let text = scalar(node, file)?;
+    Decimal::from_str(text).map_err(|_| invalid(node, file, &format!("numero invalido {text:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const BASE: &str = "\
+models:
+  base:
+    cash: 5
+    stocks:
+      VTI: 55
+      BND: { weight: 40, band: 2 }
+    rules:
+      max_issuer_weight: 60
+";
+
+    #[test]
+    fn test_client_model_extends_included_base() {
+        let client = "\
+include: base.yaml  # modelo de la casa
+
+models:
+  cliente:
+    extends: base
+    stocks:
+      VTI: 0
+    groups:
+      acciones:
+        weight: 55
+        stocks:
+          VTI: 80
+          VEA: 20
+    rules:
+      banned: [XOM]
+";
+        let read = |file: &str| (file == "base.yaml").then(|| BASE.to_string());
+        let templates = TargetTemplates::parse_with("cliente.yaml", client, read).unwrap();
+        assert_eq!(templates.names().collect::<Vec<_>>(), ["base", "cliente"]);
+
+        let model = templates.model("cliente").unwrap();
+        let weights: Vec<_> = model.weights().collect();
+        assert_eq!(
+            weights,
+            [("BND", dec!(40)), ("VEA", dec!(11)), ("VTI", dec!(44))]
+        );
+        assert_eq!(model.rules(Universe::new()).rules().len(), 2);
+
+        let prices = Map::from([
+            ("VTI".to_string(), dec!(250)),
+            ("VEA".to_string(), dec!(50)),
+            ("BND".to_string(), dec!(72)),
+        ]);
+        let target = templates.target("cliente", &prices).unwrap().unwrap();
+        assert_eq!(target.cash_weight(), dec!(5));
+        assert_eq!(target.band("BND"), Some(dec!(2)));
+    }
+
+    #[test]
+    fn test_errors_point_at_file_line_and_column() {
+        let error = TargetTemplates::parse("a.yaml", "models:\n  m:\n    stocks:\n      VTI: 90\n")
+            .unwrap_err();
+        assert_eq!((error.location.line, error.location.column), (2, 3));
+        assert_eq!(
+            error.kind,
+            TemplateErrorKind::Target(TargetError::InvalidTotal(dec!(90)))
+        );
+
+        let error =
+            TargetTemplates::parse("a.yaml", "models:\n  m:\n    cash: diez\n").unwrap_err();
+        assert_eq!(
+            error.to_string().split(':').take(3).collect::<Vec<_>>(),
+            ["a.yaml", "3", "11"]
+        );
+
+        let error = TargetTemplates::parse("a.yaml", "models:\n  m:\n    stocks: { VTI: 100\n")
+            .unwrap_err();
+        assert_eq!(error.location.line, 3);
+        assert!(matches!(error.kind, TemplateErrorKind::Syntax(_)));
+
+        let looped = "models:\n  a:\n    extends: b\n  b:\n    extends: a\n";
+        let error = TargetTemplates::parse("a.yaml", looped).unwrap_err();
+        assert!(matches!(error.kind, TemplateErrorKind::Cycle(_)));
+
+        let deep = format!("models: {}", "[".repeat(10_000));
+        let error = TargetTemplates::parse("a.yaml", &deep).unwrap_err();
+        assert!(matches!(error.kind, TemplateErrorKind::Syntax(_)));
+    }
+
+    #[test]
+    fn test_local_models_and_banned_lists_override_inherited_ones() {
+        let shared = "\
+models:
+  base:
+    stocks:
+      VTI: 100
+    rules:
+      banned: [XOM, CVX]
+  cliente:
+    stocks:
+      BND: 100
+";
+        // el include va al final, pero el modelo local igual le gana
+        let client = "\
+models:
+  cliente:
+    extends: base
+    rules:
+      banned: [TSLA]
+include: shared.yaml
+";
+        let read = |file: &str| (file == "shared.yaml").then(|| shared.to_string());
+        let templates = TargetTemplates::parse_with("cliente.yaml", client, read).unwrap();
+        let model = templates.model("cliente").unwrap();
+        assert_eq!(model.weights().collect::<Vec<_>>(), [("VTI", dec!(100))]);
+        assert_eq!(
+            model.rules(Universe::new()).rules(),
+            [Rule::Banned("TSLA".into())]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::date::Date;
+use rust_decimal::prelude::*;
+use std::collections::BTreeMap;
+
+/// Serie de inflacion usada para pasar montos nominales a montos reales.
+///
+/// Puede ser una tasa anual constante (util para proyecciones) o una serie de niveles de un
+/// indice de precios, como el IPC, para medir retornos historicos.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InflationSeries {
+    kind: SeriesKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SeriesKind {
+    Constant(Decimal),
+    Index(BTreeMap<Date, Decimal>),
+}
+
+impl InflationSeries {
+    /// Inflacion anual constante, p. ej. `dec!(0.03)`.
+    pub fn constant(annual_rate: Decimal) -> Self {
+        Self {
+            kind: SeriesKind::Constant(annual_rate),
+        }
+    }
+
+    /// Serie de niveles de un indice de precios. Los niveles no positivos se descartan porque no
+    /// tienen sentido y harian dividir por cero.
+    pub fn from_index(points: impl IntoIterator<Item = (Date, Decimal)>) -> Self {
+        let index = points
+            .into_iter()
+            .filter(|(_, level)| *level > Decimal::ZERO)
+            .collect();
+
+        Self {
+            kind: SeriesKind::Index(index),
+        }
+    }
+
+    /// Nivel del indice vigente en una fecha: el ultimo publicado en o antes de ella.
+    fn level_at(index: &BTreeMap<Date, Decimal>, date: Date) -> Option<Decimal> {
+        index.range(..=date).next_back().map(|(_, level)| *level)
+    }
+
+    /// Cuanto subieron los precios entre dos fechas, como factor (`1.05` = 5% de inflacion).
+    ///
+    /// Devuelve `None` si la serie no cubre alguna de las fechas, si el nivel inicial es cero o
+    /// si el factor no cabe en un `Decimal`.
+    pub fn factor(&self, from: Date, to: Date) -> Option<Decimal> {
+        match &self.kind {
+            SeriesKind::Constant(rate) => {
+                let years = Decimal::from_f64(from.years_until(to))?;
+                (Decimal::ONE + rate).checked_powd(years)
+            }
+            SeriesKind::Index(index) => {
+                Self::level_at(index, to)?.checked_div(Self::level_at(index, from)?)
+            }
+        }
+    }
+
+    /// Expresa un monto nominal de la fecha `to` en pesos (o dolares) de la fecha `from`.
+    pub fn deflate(&self, amount: Decimal, from: Date, to: Date) -> Option<Decimal> {
+        amount.checked_div(self.factor(from, to)?)
+    }
+}
+
+/// Tasa real a partir de la nominal, con la ecuacion de Fisher exacta: `(1+n)/(1+i) - 1`.
+pub fn real_rate(nominal: Decimal, inflation: Decimal) -> Decimal {
+    (Decimal::ONE + nominal) / (Decimal::ONE + inflation) - Decimal::ONE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn date(y: i32, m: u32, d: u32) -> Date {
+        Date::new(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_index_factor_uses_last_known_level() {
+        let series = InflationSeries::from_index(vec![
+            (date(2023, 1, 1), dec!(100)),
+            (date(2024, 1, 1), dec!(104)),
+        ]);
+
+        assert_eq!(
+            series.factor(date(2023, 6, 1), date(2024, 3, 1)),
+            Some(dec!(1.04))
+        );
+        assert_eq!(series.factor(date(2022, 1, 1), date(2024, 1, 1)), None);
+
+        let zero = InflationSeries::from_index(vec![(date(2023, 1, 1), Decimal::ZERO)]);
+        assert_eq!(zero.factor(date(2023, 1, 1), date(2024, 1, 1)), None);
+        assert_eq!(
+            zero.deflate(dec!(100), date(2023, 1, 1), date(2024, 1, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_real_rate() {
+        assert_eq!(real_rate(dec!(0.05), dec!(0.05)), Decimal::ZERO);
+        assert!(real_rate(dec!(0.07), dec!(0.03)) < dec!(0.04));
+    }
+}
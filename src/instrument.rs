@@ -0,0 +1,141 @@
+//! Interfaz comun de todo lo que se puede tener en un portafolio.
+//!
+//! `Stock` empezo siendo el unico tipo de activo; ahora acciones, ETFs, bonos, cripto y caja
+//! comparten el trait `Instrument`, y el resto del crate solo necesita lo que el trait expone:
+//! un identificador, un precio por unidad, una moneda y de a cuanto se puede transar.
+
+use crate::money::Currency;
+use crate::{InstrumentKind, Stock};
+use rust_decimal::prelude::*;
+
+/// Algo que se puede comprar, vender y valorizar.
+pub trait Instrument {
+    /// Identificador unico (el ticker).
+    fn id(&self) -> &str;
+
+    /// Valor de una unidad en `currency()`.
+    fn price(&self) -> Decimal;
+
+    fn currency(&self) -> Currency;
+
+    /// Minima cantidad transable: 1 para una accion, 100 si se transa en lotes de 100,
+    /// `0.00000001` para BTC, un centavo para dolares en caja.
+    fn tradable_increment(&self) -> Decimal;
+
+    /// Redondea hacia abajo una cantidad a un multiplo del incremento transable.
+    fn round_to_increment(&self, quantity: Decimal) -> Decimal {
+        let increment = self.tradable_increment();
+        if increment <= Decimal::ZERO {
+            return quantity;
+        }
+
+        (quantity / increment).trunc() * increment
+    }
+
+    /// Como `round_to_increment`, pero `None` si la cantidad en incrementos no cabe en un
+    /// `Decimal`.
+    fn checked_round_to_increment(&self, quantity: Decimal) -> Option<Decimal> {
+        let increment = self.tradable_increment();
+        if increment <= Decimal::ZERO {
+            return Some(quantity);
+        }
+
+        Some(quantity.checked_div(increment)?.trunc() * increment)
+    }
+}
+
+impl Instrument for Stock {
+    fn id(&self) -> &str {
+        self.name()
+    }
+
+    fn price(&self) -> Decimal {
+        self.current_price()
+    }
+
+    fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    fn tradable_increment(&self) -> Decimal {
+        self.increment
+    }
+}
+
+/// Caja en una moneda, como instrumento: vale 1 y se transa de a la unidad minima de la moneda.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cash(pub Currency);
+
+impl Instrument for Cash {
+    fn id(&self) -> &str {
+        self.0.code()
+    }
+
+    fn price(&self) -> Decimal {
+        Decimal::ONE
+    }
+
+    fn currency(&self) -> Currency {
+        self.0
+    }
+
+    fn tradable_increment(&self) -> Decimal {
+        Decimal::new(1, self.0.decimals())
+    }
+}
+
+impl Stock {
+    /// Copia un instrumento cualquiera como `Stock`, para usarlo en un `Portfolio` o un
+    /// `PortfolioTarget`. El precio queda fijo al del momento de la copia.
+    pub fn from_instrument(instrument: &dyn Instrument) -> Self {
+        Self {
+            name: instrument.id().into(),
+            current_price: instrument.price(),
+            kind: InstrumentKind::Equity,
+            currency: instrument.currency(),
+            increment: instrument.tradable_increment(),
+            basis: None,
+            esg: None,
+            locked_until: None,
+            metadata: None,
+            bid_ask: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Portfolio, PortfolioTarget};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_cash_increment_follows_currency() {
+        assert_eq!(Cash(Currency::Usd).tradable_increment(), dec!(0.01));
+        assert_eq!(Cash(Currency::Clp).tradable_increment(), dec!(1));
+        assert_eq!(
+            Cash(Currency::Usd).round_to_increment(dec!(10.019)),
+            dec!(10.01)
+        );
+    }
+
+    #[test]
+    fn test_rebalance_respects_lot_size() {
+        let lot = Stock::new("7203.T", dec!(10)).with_increment(dec!(100));
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), lot),
+            (dec!(50), Stock::from_instrument(&Cash(Currency::Usd))),
+        ])
+        .unwrap();
+
+        let portfolio = Portfolio {
+            stocks: vec![],
+            allocation: target,
+            cash: dec!(5000),
+            foreign_cash: Default::default(),
+        };
+
+        // 2500 alcanzan para 250 acciones, pero se transan de a 100
+        assert_eq!(portfolio.rebalance_portfolio().to_buy["7203.T"], 200);
+    }
+}
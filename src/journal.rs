@@ -0,0 +1,165 @@
+//! Diario de transacciones de una cuenta.
+//!
+//! A diferencia de los `PortfolioEvent`, que describen cambios al modelo interno, una
+//! `Transaction` describe lo que paso en la cuenta real del broker: con fecha, cantidad, precio y
+//! el efecto en caja.
+
+use crate::date::Date;
+use alloc::string::String;
+use alloc::vec::Vec;
+use rust_decimal::Decimal;
+
+/// Tipo de movimiento.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionKind {
+    Buy,
+    Sell,
+    Dividend,
+    Interest,
+    Deposit,
+    Withdrawal,
+    Fee,
+}
+
+/// Un movimiento de la cuenta.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub date: Date,
+    pub kind: TransactionKind,
+
+    /// Ticker involucrado; `None` para movimientos puros de caja.
+    pub ticker: Option<String>,
+
+    /// Unidades compradas o vendidas (siempre positivas); cero si no aplica.
+    pub units: Decimal,
+
+    /// Precio por unidad; cero si no aplica.
+    pub price: Decimal,
+
+    /// Efecto en la caja: positivo si entra dinero, negativo si sale. Incluye comisiones.
+    pub cash_amount: Decimal,
+}
+
+impl Transaction {
+    /// Movimiento de caja sin ticker (deposito, retiro, comision, intereses).
+    pub fn cash(date: Date, kind: TransactionKind, cash_amount: Decimal) -> Self {
+        Self {
+            date,
+            kind,
+            ticker: None,
+            units: Decimal::ZERO,
+            price: Decimal::ZERO,
+            cash_amount,
+        }
+    }
+
+    /// Compra o venta de `units` a `price`; el efecto en caja se calcula sin comisiones.
+    pub fn trade(
+        date: Date,
+        kind: TransactionKind,
+        ticker: &str,
+        units: Decimal,
+        price: Decimal,
+    ) -> Self {
+        let gross = units * price;
+        let cash_amount = match kind {
+            TransactionKind::Sell => gross,
+            _ => -gross,
+        };
+
+        Self {
+            date,
+            kind,
+            ticker: Some(ticker.into()),
+            units,
+            price,
+            cash_amount,
+        }
+    }
+}
+
+/// Lista de transacciones, ordenada por fecha.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Journal {
+    transactions: Vec<Transaction>,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agrega una transaccion manteniendo el orden por fecha; las de la misma fecha quedan en el
+    /// orden en que se agregaron.
+    pub fn push(&mut self, transaction: Transaction) {
+        let index = self
+            .transactions
+            .partition_point(|t| t.date <= transaction.date);
+        self.transactions.insert(index, transaction);
+    }
+
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Transacciones entre dos fechas, ambas inclusive.
+    pub fn between(&self, from: Date, to: Date) -> impl Iterator<Item = &Transaction> {
+        self.transactions
+            .iter()
+            .filter(move |t| (from..=to).contains(&t.date))
+    }
+
+    /// Transacciones de un ticker.
+    pub fn for_ticker<'a>(&'a self, ticker: &'a str) -> impl Iterator<Item = &'a Transaction> {
+        self.transactions
+            .iter()
+            .filter(move |t| t.ticker.as_deref() == Some(ticker))
+    }
+
+    /// Saldo de caja que resulta de todas las transacciones.
+    pub fn cash_balance(&self) -> Decimal {
+        self.transactions.iter().map(|t| t.cash_amount).sum()
+    }
+}
+
+impl FromIterator<Transaction> for Journal {
+    fn from_iter<I: IntoIterator<Item = Transaction>>(iter: I) -> Self {
+        let mut journal = Journal::new();
+        for transaction in iter {
+            journal.push(transaction);
+        }
+        journal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_journal_keeps_date_order_and_balance() {
+        let d = |day| Date::new(2024, 1, day).unwrap();
+
+        let journal: Journal = vec![
+            Transaction::trade(d(5), TransactionKind::Buy, "META", dec!(2), dec!(10)),
+            Transaction::cash(d(1), TransactionKind::Deposit, dec!(100)),
+            Transaction::trade(d(9), TransactionKind::Sell, "META", dec!(1), dec!(12)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(journal.transactions()[0].kind, TransactionKind::Deposit);
+        assert_eq!(journal.cash_balance(), dec!(92));
+        assert_eq!(journal.for_ticker("META").count(), 2);
+        assert_eq!(journal.between(d(2), d(8)).count(), 1);
+    }
+}
@@ -1,6 +1,133 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
-use std::collections::HashMap;
+
+pub mod approval;
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod backtest;
+pub mod bond;
+pub mod builder;
+pub mod caps;
+#[cfg(feature = "std")]
+pub mod chart;
+pub mod clients;
+pub mod clock;
+pub mod concentration;
+pub mod costs;
+pub mod crypto;
+pub mod date;
+pub mod dry_run;
+pub mod error;
+pub mod esg;
+pub mod events;
+pub mod execution;
+pub mod expiry;
+#[cfg(feature = "std")]
+pub mod export;
+pub mod fund;
+pub mod funding;
+pub mod fx;
+#[cfg(feature = "std")]
+pub mod goals;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod guard;
+pub mod household;
+pub mod i18n;
+pub mod id;
+#[cfg(feature = "std")]
+pub mod import;
+#[cfg(feature = "std")]
+pub mod inflation;
+pub mod instrument;
+pub mod journal;
+pub mod lockup;
+pub mod lots;
+pub mod math;
+pub mod merge;
+pub mod metadata;
+#[cfg(feature = "std")]
+pub mod metrics;
+pub mod models;
+pub mod money;
+pub mod netting;
+pub mod numeric;
+#[cfg(feature = "std")]
+pub mod optimize;
+#[cfg(feature = "std")]
+pub mod performance;
+pub mod pipeline;
+pub mod premortem;
+#[cfg(feature = "std")]
+pub mod prices;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "std")]
+pub mod projection;
+#[cfg(feature = "std")]
+pub mod publish;
+pub mod quote;
+pub mod reconcile;
+pub mod reports;
+#[cfg(feature = "std")]
+pub mod rng;
+#[cfg(feature = "std")]
+pub mod rolling;
+pub mod rules;
+pub mod settlement;
+#[cfg(feature = "std")]
+pub mod shared;
+pub mod sleeves;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+pub mod targets;
+pub mod tax;
+pub mod uf;
+pub mod universe;
+#[cfg(feature = "std")]
+pub mod valuation;
+pub mod view;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+pub use bond::Bond;
+pub use builder::PortfolioBuilder;
+pub use date::{Date, Timestamp};
+pub use error::{ArithmeticOverflow, EventError, PortfolioError, SuggestionError, TargetError};
+pub use events::{EventSourcedPortfolio, PortfolioEvent};
+#[cfg(feature = "std")]
+pub use goals::Goal;
+pub use i18n::{Language, Localize};
+pub use id::SuggestionId;
+pub use instrument::Instrument;
+pub use lots::{CostBasis, Lot};
+pub use math::MathConfig;
+pub use metadata::Metadata;
+pub use money::{Currency, Locale, Money};
+pub use numeric::Numeric;
+pub use pipeline::{Pipeline, TradePriority};
+pub use quote::BidAsk;
+#[cfg(feature = "std")]
+pub use shared::SharedPortfolio;
+pub use targets::{TargetBuilder, TargetHistory};
+pub use tax::WashSaleConflict;
+pub use universe::Universe;
+pub use view::PortfolioView;
+
+/// Mapa de la API publica (p. ej. `RebalanceSuggestion::to_buy`). Es `BTreeMap` con y sin la
+/// feature `std` (`alloc` no trae tablas de hash), asi el tipo no cambia segun las features y
+/// el orden de iteracion es siempre por clave.
+pub type Map<K, V> = BTreeMap<K, V>;
 
 /// Problema original:
 ///
@@ -13,10 +140,17 @@ use std::collections::HashMap;
 ///
 /// Add documentation/comments to understand your thinking process and solution
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Portfolio {
     stocks: Vec<Stock>,
     allocation: PortfolioTarget,
+
+    /// Saldo en efectivo disponible para comprar, en la moneda base del portafolio.
+    cash: Decimal,
+
+    /// Saldos en otras monedas. No cuentan en `weights` ni en `rebalance_portfolio`, que no
+    /// saben de tipos de cambio; ver `Portfolio::rebalance_with_fx`.
+    foreign_cash: BTreeMap<Currency, Decimal>,
 }
 
 impl Portfolio {
@@ -24,74 +158,279 @@ impl Portfolio {
         &self.stocks
     }
 
+    pub fn cash(&self) -> Decimal {
+        self.cash
+    }
+
+    /// Saldos en monedas distintas a la base.
+    pub fn foreign_cash(&self) -> &BTreeMap<Currency, Decimal> {
+        &self.foreign_cash
+    }
+
+    /// Fija el saldo en una moneda distinta a la base.
+    pub fn with_foreign_cash(mut self, currency: Currency, amount: Decimal) -> Self {
+        self.foreign_cash.insert(currency, amount);
+        self
+    }
+
+    pub fn allocation(&self) -> &PortfolioTarget {
+        &self.allocation
+    }
+
+    /// Valor de los stocks mas la caja en la moneda base (sin los saldos en otras monedas).
+    ///
+    /// Entra en panico si el valor no cabe en un `Decimal`; ver `checked_total_value`.
+    pub fn total_value(&self) -> Decimal {
+        self.stocks
+            .iter()
+            .map(|s| s.current_price())
+            .sum::<Decimal>()
+            + self.cash
+    }
+
+    /// Como `total_value`, pero con un error si el valor no cabe en un `Decimal`.
+    pub fn checked_total_value(&self) -> Result<Decimal, ArithmeticOverflow> {
+        self.stocks
+            .iter()
+            .try_fold(self.cash, |total, s| total.checked_add(s.current_price()))
+            .ok_or(ArithmeticOverflow::Valuation(None))
+    }
+
+    /// Unidades de cada ticker, ordenadas por ticker.
+    pub fn iter_holdings(&self) -> impl Iterator<Item = (&str, usize)> {
+        let mut units: BTreeMap<&str, usize> = BTreeMap::new();
+        for stock in &self.stocks {
+            *units.entry(stock.name()).or_default() += 1;
+        }
+        units.into_iter()
+    }
+
+    /// Tickers que se tienen, ordenados y sin repetir.
+    pub fn tickers(&self) -> Vec<&str> {
+        self.iter_holdings().map(|(ticker, _)| ticker).collect()
+    }
+
+    /// Valor de las unidades de `ticker` a su precio actual; cero si no se tiene.
+    pub fn value_of(&self, ticker: &str) -> Decimal {
+        self.stocks
+            .iter()
+            .filter(|s| s.name() == ticker)
+            .map(|s| s.current_price())
+            .sum()
+    }
+
+    /// Como `value_of`, pero con un error si el valor no cabe en un `Decimal`.
+    pub fn checked_value_of(&self, ticker: &str) -> Result<Decimal, ArithmeticOverflow> {
+        self.stocks
+            .iter()
+            .filter(|s| s.name() == ticker)
+            .try_fold(Decimal::ZERO, |value, s| {
+                value.checked_add(s.current_price())
+            })
+            .ok_or_else(|| ArithmeticOverflow::Valuation(Some(ticker.into())))
+    }
+
+    /// % del valor total en `ticker` (ver `weights`); cero si el portafolio no vale nada.
+    pub fn weight_of(&self, ticker: &str) -> Decimal {
+        let total = self.total_value();
+        if total.is_zero() {
+            return Decimal::ZERO;
+        }
+        math::math_config().weight(self.value_of(ticker) / total * dec!(100))
+    }
+
+    /// Proporcion (en %, igual que en `PortfolioTarget`) que representa cada stock del valor
+    /// total del portafolio, ordenada de mayor a menor. Vacia si el portafolio no vale nada.
+    ///
+    /// El efectivo cuenta en el total pero no aparece como entrada, asi que si hay saldo los pesos
+    /// suman menos de 100%.
+    pub fn weights(&self) -> Vec<(&str, Decimal)> {
+        self.checked_weights()
+            .unwrap_or_else(|overflow| panic!("{overflow}"))
+    }
+
+    /// Como `weights`, pero con un error si la valorizacion no cabe en un `Decimal`.
+    pub fn checked_weights(&self) -> Result<Vec<(&str, Decimal)>, ArithmeticOverflow> {
+        let total = self.checked_total_value()?;
+        if total.is_zero() {
+            return Ok(Vec::new());
+        }
+
+        let mut values: Vec<(&str, Decimal)> = Vec::new();
+        for stock in self.stocks() {
+            match values.iter_mut().find(|(name, _)| *name == stock.name()) {
+                Some((name, value)) => {
+                    *value = value
+                        .checked_add(stock.current_price())
+                        .ok_or_else(|| ArithmeticOverflow::Valuation(Some(name.to_string())))?;
+                }
+                None => values.push((stock.name(), stock.current_price())),
+            }
+        }
+
+        for (_, value) in values.iter_mut() {
+            *value = math::math_config().weight(*value / total * dec!(100));
+        }
+
+        values.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        Ok(values)
+    }
+
     /// Muestra una sugerencia de rebalancio a partir de un portafolio.
     ///
     /// La forma de rebalanceo que voy a aplicar es la siguiente:
     /// 1. Se suman los stocks del portafolio segun su precio actual para tener una idea de cuanto
     ///    dinero requerimos.
     /// 2. Se hacen proporciones objetivo para cada stock segun lo asignado; esto nos dice cuanto
-    ///     de ese stock vender, cuando comprar.
+    ///    de ese stock vender, cuando comprar.
     /// 3. Debido a que estamos trabajando con stocks que no necesariamente van a cuadrar
     ///    perfectamente en proporciones de 40% o similares, utilizare una estrategia conservadora:
     ///    venderemos o compraremos la mayor cantidad de stock posible hasta llegar a la proporcion
     ///    objetivo sin pasarnos. Esto seguramente resulta en un saldo excedente dentro de la
     ///    cartera del usuario/cliente.
+    ///
+    /// Cada paso es una etapa de `pipeline::Pipeline::conservative()`; para agregar restricciones
+    /// o filtros propios se arma un `Pipeline` y se usa `Pipeline::run`.
     pub fn rebalance_portfolio<'a>(&'a self) -> RebalanceSuggestion<'a> {
-        let mut suggestion = RebalanceSuggestion::default();
-
-        let mut current_units: HashMap<&str, usize> = HashMap::new();
-        for stock in self.stocks() {
-            *current_units.entry(stock.name()).or_insert(0) += 1;
-        }
+        Pipeline::conservative().run(self)
+    }
 
-        let total_balance: Decimal = self.stocks().iter().map(|s| s.current_price()).sum();
+    /// Como `rebalance_portfolio`, pero con un error en vez de un panico si la valorizacion o
+    /// las unidades objetivo no caben en un `Decimal`.
+    pub fn try_rebalance_portfolio<'a>(
+        &'a self,
+    ) -> Result<RebalanceSuggestion<'a>, ArithmeticOverflow> {
+        Pipeline::conservative().try_run(self)
+    }
 
-        // no tenemos nada en el portafolio.
-        if total_balance.is_zero() {
-            return suggestion;
-        }
+    /// Sugerencia para invertir la caja sin vender nada, p. ej. despues de un aporte; ver
+    /// `pipeline::BuyOnly`.
+    pub fn contribution_suggestion<'a>(&'a self) -> RebalanceSuggestion<'a> {
+        Pipeline::contribution_only().run(self)
+    }
 
-        // cualquier stock que no existe en nuestra asignacion se sugiere eliminar completamente
-        for (name, &units) in &current_units {
-            if !self.allocation.contains_key(name) {
-                suggestion.to_sell.insert(name, units);
-            }
-        }
+    /// Igual que `rebalance_portfolio`, pero solo mueve los tickers que estan fuera de su banda
+    /// (`tolerance` o la de `PortfolioTarget::with_band`) y solo hasta el borde de la banda. Ver
+    /// `pipeline::BandEdge`.
+    pub fn rebalance_to_band_edge<'a>(&'a self, tolerance: Decimal) -> RebalanceSuggestion<'a> {
+        Pipeline::hysteresis(tolerance).run(self)
+    }
 
-        for (ratio, target_stock) in self.allocation.targets().iter() {
-            let name = target_stock.name();
-            let price_per_unit = target_stock.current_price();
+    /// Actualiza el precio de un ticker, tanto en los holdings como en el objetivo. Devuelve
+    /// `false` si el ticker no aparece en ninguno de los dos.
+    pub fn update_price(&mut self, ticker: &str, price: Decimal) -> bool {
+        let mut found = false;
 
-            // nuestro maximo dinero objetivo
-            let target_money = total_balance * (ratio / dec!(100.0));
+        let held = self.stocks.iter_mut();
+        let targeted = self.allocation.targets.iter_mut().map(|(_, stock)| stock);
+        for stock in held.chain(targeted).filter(|s| s.name == ticker) {
+            stock.current_price = price;
+            // un ultimo precio suelto deja obsoletas las puntas anteriores
+            stock.bid_ask = None;
+            found = true;
+        }
 
-            // esta es la cantidad maxima que podriamos tener (segun nuestra estrategia conservadora)
-            let target_units = (target_money / price_per_unit)
-                .trunc()
-                .to_usize() // Esto no deberia fallar pq estamos truncando un numero mayor a cero
-                .unwrap_or(0);
+        found
+    }
 
-            // esta es la cantidad que tenemos
-            let held_units = *current_units.get(name).unwrap_or(&0);
+    /// Mueve la fecha de valorizacion de todos los bonos (holdings y objetivo), para que el
+    /// interes devengado corresponda a `date`.
+    pub fn revalue_at(&mut self, date: Date) {
+        let held = self.stocks.iter_mut();
+        let targeted = self.allocation.targets.iter_mut().map(|(_, stock)| stock);
+        for stock in held.chain(targeted) {
+            stock.revalue_at(date);
+        }
+    }
 
-            if target_units > held_units {
-                // sugerimos comprar la diferencia
-                suggestion.to_buy.insert(name, target_units - held_units);
-            } else if target_units < held_units {
-                // sugerimos vender la diferencia
-                suggestion.to_sell.insert(name, held_units - target_units);
-            }
+    /// Confirma que una sugerencia (identificada por su id) todavia corresponde al estado actual
+    /// del portafolio; si cambiaron los holdings, los precios o el objetivo, hay que recalcularla
+    /// antes de ejecutarla. Para tolerar movimientos chicos de precio o ponerle plazo, ver
+    /// `expiry`.
+    pub fn verify_suggestion(&self, id: SuggestionId) -> Result<(), SuggestionError> {
+        let current = self.state_id(RebalanceStrategy::Conservative);
+        if current != id {
+            return Err(SuggestionError::Stale {
+                expected: id,
+                actual: current,
+            });
         }
 
-        suggestion
+        Ok(())
     }
 }
 
 /// Clase que representa un stock.
+///
+/// A pesar del nombre, tambien puede ser un bono (ver `Stock::bond`); asi un objetivo o un
+/// portafolio pueden mezclar ambos sin que el rebalanceo tenga que distinguirlos.
 #[derive(Debug, Clone)]
 pub struct Stock {
     name: String, // E.J: META, APPL, ETC.
+
+    /// Ultima cotizacion; para un bono es el precio limpio en % del nominal.
     current_price: Decimal,
+
+    kind: InstrumentKind,
+
+    currency: Currency,
+
+    /// Minima cantidad transable (ver `Instrument::tradable_increment`).
+    increment: Decimal,
+
+    /// Costo y fecha de compra de esta unidad, si se conocen (ver `lots`); en una caja para que
+    /// el stock no crezca.
+    basis: Option<alloc::boxed::Box<CostBasis>>,
+
+    /// Puntaje ESG, si se conoce (ver `esg`).
+    esg: Option<Decimal>,
+
+    /// Hasta cuando no se puede vender esta unidad (ver `lockup`).
+    locked_until: Option<Date>,
+
+    /// Etiquetas y campos libres (ver `metadata`); en una caja porque casi nunca hay, y asi el
+    /// stock no crece.
+    metadata: Option<alloc::boxed::Box<Metadata>>,
+
+    /// Puntas de compra y venta de la ultima cotizacion, si la fuente las da (ver `quote`).
+    bid_ask: Option<alloc::boxed::Box<BidAsk>>,
+}
+
+/// Dos stocks son iguales si tienen el mismo ticker y precio; el costo de compra, la moneda y el
+/// resto de los datos no cuentan, asi que dos unidades compradas en fechas distintas son iguales.
+impl PartialEq for Stock {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.current_price == other.current_price
+    }
+}
+
+impl Eq for Stock {}
+
+/// Consistente con `PartialEq` (`Decimal` hashea igual `10` y `10.0`).
+impl core::hash::Hash for Stock {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.current_price.hash(state);
+    }
+}
+
+/// Tipo de instrumento detras de un `Stock`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstrumentKind {
+    Equity,
+
+    /// Bono, valorizado a la fecha `valued_at`.
+    Bond {
+        terms: Bond,
+        valued_at: Date,
+    },
+
+    /// Fondo mutuo, valorizado por su valor cuota diario; las ordenes despues de la hora de
+    /// corte (`cut_off`, en minutos desde medianoche UTC) van al valor cuota del dia habil
+    /// siguiente (ver `fund`).
+    Fund {
+        cut_off: u32,
+    },
 }
 
 impl Stock {
@@ -102,26 +441,202 @@ impl Stock {
             // Por hoy, voy a confiar que el precio es correcto nomas, pero deberia haber un constructor capaz
             // de evitar enviar un precio con algun valor negativo por ejemplo.
             current_price: price,
+            kind: InstrumentKind::Equity,
+            currency: Currency::Usd,
+            increment: Decimal::ONE,
+            basis: None,
+            esg: None,
+            locked_until: None,
+            metadata: None,
+            bid_ask: None,
         }
     }
 
-    /// Getter simple.
+    /// Un bono cotizado a `clean_quote` (% del nominal) y valorizado a la fecha `valued_at`.
+    pub fn bond(name: &str, terms: Bond, clean_quote: Decimal, valued_at: Date) -> Self {
+        Self {
+            name: name.into(),
+            current_price: clean_quote,
+            kind: InstrumentKind::Bond { terms, valued_at },
+            currency: Currency::Usd,
+            increment: Decimal::ONE,
+            basis: None,
+            esg: None,
+            locked_until: None,
+            metadata: None,
+            bid_ask: None,
+        }
+    }
+
+    /// Por defecto un stock cotiza en dolares.
+    pub fn with_currency(mut self, currency: Currency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Lote minimo, p. ej. 100 en mercados que transan en lotes.
+    pub fn with_increment(mut self, increment: Decimal) -> Self {
+        self.increment = increment;
+        self
+    }
+
+    /// Unidad comprada a `cost` el dia `acquired`.
+    pub fn with_basis(mut self, cost: Decimal, acquired: Date) -> Self {
+        self.basis = Some(alloc::boxed::Box::new(CostBasis {
+            cost,
+            acquired,
+            fx_rate: None,
+        }));
+        self
+    }
+
+    /// Unidad en moneda extranjera comprada a `cost` (en su moneda) el dia `acquired`, cuando una
+    /// unidad de esa moneda valia `fx_rate` de la base; ver `fx::FxGainSplit`.
+    pub fn with_fx_basis(mut self, cost: Decimal, acquired: Date, fx_rate: Decimal) -> Self {
+        self.basis = Some(alloc::boxed::Box::new(CostBasis {
+            cost,
+            acquired,
+            fx_rate: Some(fx_rate),
+        }));
+        self
+    }
+
+    pub fn basis(&self) -> Option<&CostBasis> {
+        self.basis.as_deref()
+    }
+
+    /// Puntaje ESG del proveedor que se use; mientras mas alto, mejor.
+    pub fn with_esg_score(mut self, score: Decimal) -> Self {
+        self.esg = Some(score);
+        self
+    }
+
+    pub fn esg_score(&self) -> Option<Decimal> {
+        self.esg
+    }
+
+    /// Valor de una unidad. Para un bono es el precio sucio: nominal por cotizacion limpia mas
+    /// el interes devengado a la fecha de valorizacion.
     pub fn current_price(&self) -> Decimal {
+        match &self.kind {
+            InstrumentKind::Equity | InstrumentKind::Fund { .. } => self.current_price,
+            InstrumentKind::Bond { terms, valued_at } => {
+                terms.dirty_price(self.current_price, *valued_at)
+            }
+        }
+    }
+
+    /// Ultima cotizacion tal como llega del mercado (limpia, para un bono).
+    pub fn quote(&self) -> Decimal {
         self.current_price
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn kind(&self) -> &InstrumentKind {
+        &self.kind
+    }
+
+    /// Mueve la fecha de valorizacion de un bono; no hace nada con una accion.
+    pub fn revalue_at(&mut self, date: Date) {
+        if let InstrumentKind::Bond { valued_at, .. } = &mut self.kind {
+            *valued_at = date;
+        }
+    }
+}
+
+/// Estrategias con las que se puede calcular una sugerencia de rebalanceo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RebalanceStrategy {
+    /// Comprar o vender hasta la proporcion objetivo sin pasarse (ver `rebalance_portfolio`).
+    #[default]
+    Conservative,
+}
+
+impl RebalanceStrategy {
+    /// Nombre estable de la estrategia, usado en registros de auditoria.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RebalanceStrategy::Conservative => "conservative",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "conservative" => Some(RebalanceStrategy::Conservative),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct RebalanceSuggestion<'a> {
+    /// Hash del estado a partir del cual se calculo la sugerencia.
+    pub id: SuggestionId,
+
     /// Mappea un stock (idenficado por su nombre) a una cantidad a comprar.
-    pub to_buy: HashMap<&'a str, usize>,
+    pub to_buy: Map<&'a str, usize>,
 
     /// Mappea un stock (idenficado por su nombre) a una cantidad a vender.
-    pub to_sell: HashMap<&'a str, usize>,
+    pub to_sell: Map<&'a str, usize>,
+
+    /// Operaciones que generarian una venta lavada (ver `Portfolio::check_wash_sales`).
+    pub wash_sales: Vec<WashSaleConflict>,
+
+    /// Las operaciones de la sugerencia ordenadas por cuanto reducen el drift por monto transado;
+    /// si no se pueden hacer todas, conviene partir por las primeras.
+    pub priorities: Vec<TradePriority>,
+}
+
+/// Resumen legible de la sugerencia, una linea por operacion; primero las ventas porque son las
+/// que financian las compras.
+impl Localize for RebalanceSuggestion<'_> {
+    fn localize(&self, language: Language) -> String {
+        let (sell, buy, nothing) = match language {
+            Language::Es => ("Vender", "Comprar", "No hay operaciones sugeridas."),
+            Language::En => ("Sell", "Buy", "No trades suggested."),
+        };
+
+        let mut sells: Vec<_> = self.to_sell.iter().collect();
+        let mut buys: Vec<_> = self.to_buy.iter().collect();
+        sells.sort();
+        buys.sort();
+
+        let lines: Vec<String> = sells
+            .into_iter()
+            .map(|(name, units)| format!("{sell} {units} {name}"))
+            .chain(
+                buys.into_iter()
+                    .map(|(name, units)| format!("{buy} {units} {name}")),
+            )
+            .collect();
+
+        let conflicts = self
+            .wash_sales
+            .iter()
+            .map(|conflict| format!("! {}", conflict.localize(language)));
+
+        if lines.is_empty() {
+            core::iter::once(nothing.to_string())
+                .chain(conflicts)
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            lines
+                .into_iter()
+                .chain(conflicts)
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    }
+}
+
+impl core::fmt::Display for RebalanceSuggestion<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.localize(i18n::language()))
+    }
 }
 
 /// Representa los stocks que el cliente quiere obtener.
@@ -130,9 +645,36 @@ pub struct RebalanceSuggestion<'a> {
 /// hayan estados irrepresentables; por ejemplo, stocks de menos de 100%, o de mas de 100%;
 /// queremos evitar que los programadores que usen nuestra clase de portafolio puedan, por
 /// accidente, asignar algo sin sentido como (50% META, 75% APPL), o (-30% META), etc.
-#[derive(Debug)]
+///
+/// Parte del objetivo puede ser efectivo (ver `Allocation::Cash`): con 90% stocks / 10% caja el
+/// rebalanceo deja siempre al menos un 10% sin invertir.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PortfolioTarget {
     targets: Vec<(Decimal, Stock)>,
+
+    /// % del portafolio que se quiere mantener en efectivo.
+    cash: Decimal,
+
+    /// Tolerancia propia de algunos tickers, en puntos porcentuales (ver `with_band`).
+    bands: BTreeMap<String, Decimal>,
+}
+
+/// Una entrada de un objetivo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Allocation {
+    /// % del portafolio en un stock.
+    Stock(Decimal, Stock),
+
+    /// % del portafolio en efectivo.
+    Cash(Decimal),
+}
+
+impl Allocation {
+    pub fn weight(&self) -> Decimal {
+        match self {
+            Allocation::Stock(weight, _) | Allocation::Cash(weight) => *weight,
+        }
+    }
 }
 
 impl PortfolioTarget {
@@ -141,28 +683,91 @@ impl PortfolioTarget {
     pub fn new(stock: Stock) -> Self {
         Self {
             targets: vec![(dec!(100), stock)],
+            cash: Decimal::ZERO,
+            bands: BTreeMap::new(),
         }
     }
 
-    pub fn try_from_vec(stocks: Vec<(Decimal, Stock)>) -> Result<Self, String> {
-        if stocks.iter().map(|stock| stock.0).sum::<Decimal>() != dec!(100) {
-            return Err("Los stocks objetivos no suman un 100%".into());
+    pub fn try_from_vec(stocks: Vec<(Decimal, Stock)>) -> Result<Self, TargetError> {
+        Self::try_from_allocations(
+            stocks
+                .into_iter()
+                .map(|(weight, stock)| Allocation::Stock(weight, stock))
+                .collect(),
+        )
+    }
+
+    /// Igual que `try_from_vec` pero admitiendo entradas de efectivo. Varias entradas de
+    /// efectivo se suman.
+    pub fn try_from_allocations(allocations: Vec<Allocation>) -> Result<Self, TargetError> {
+        let total = allocations.iter().map(Allocation::weight).sum::<Decimal>();
+        if total != dec!(100) {
+            return Err(TargetError::InvalidTotal(total));
         }
 
-        if stocks.iter().any(|stock| stock.0 <= Decimal::ZERO) {
-            return Err("Al menos uno de los stocks provistos tiene valor 0 o negativo.".into());
+        let mut targets = Vec::new();
+        let mut cash = Decimal::ZERO;
+        for allocation in allocations {
+            match allocation {
+                Allocation::Stock(weight, stock) if weight <= Decimal::ZERO => {
+                    return Err(TargetError::NonPositiveWeight(stock.name().into()));
+                }
+                Allocation::Cash(weight) if weight <= Decimal::ZERO => {
+                    return Err(TargetError::NonPositiveWeight("cash".into()));
+                }
+                Allocation::Stock(weight, stock) => targets.push((weight, stock)),
+                Allocation::Cash(weight) => cash += weight,
+            }
         }
 
-        Ok(Self { targets: stocks })
+        Ok(Self {
+            targets,
+            cash,
+            bands: BTreeMap::new(),
+        })
+    }
+
+    /// Igual que `try_from_vec`, pero ademas exige que cada ticker pertenezca al universo dado.
+    pub fn try_from_vec_in(
+        stocks: Vec<(Decimal, Stock)>,
+        universe: &Universe,
+    ) -> Result<Self, TargetError> {
+        for (_, stock) in &stocks {
+            universe.check(stock.name())?;
+        }
+
+        Self::try_from_vec(stocks)
     }
 
     pub fn contains_key(&self, name: &str) -> bool {
         self.targets.iter().any(|stock| stock.1.name() == name)
     }
 
+    /// Entradas de stocks; el efectivo va aparte en `cash_weight`.
     pub fn targets(&self) -> &[(Decimal, Stock)] {
         &self.targets
     }
+
+    /// % del objetivo en efectivo.
+    pub fn cash_weight(&self) -> Decimal {
+        self.cash
+    }
+
+    /// Tolerancia propia de `ticker`, en puntos porcentuales: p. ej. ±5 para acciones y ±2 para
+    /// bonos. Los tickers sin banda usan la tolerancia global que se pase a
+    /// `verify_against_target` o `needs_rebalance`. Si `ticker` no esta en el objetivo no hace
+    /// nada.
+    pub fn with_band(mut self, ticker: &str, band: Decimal) -> Self {
+        if self.contains_key(ticker) {
+            self.bands.insert(ticker.into(), band.abs());
+        }
+        self
+    }
+
+    /// Tolerancia propia de `ticker`, si tiene.
+    pub fn band(&self, ticker: &str) -> Option<Decimal> {
+        self.bands.get(ticker).copied()
+    }
 }
 
 #[cfg(test)]
@@ -223,6 +828,8 @@ mod tests {
         }
 
         let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
             stocks,
             allocation: target,
         };
@@ -238,6 +845,8 @@ mod tests {
         // Resultado esperado: to_sell debe contener todas esas acciones.
         let target = PortfolioTarget::new(Stock::new("META", dec!(100.0)));
         let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
             stocks: vec![
                 Stock::new("GOOG", dec!(50.0)),
                 Stock::new("GOOG", dec!(50.0)),
@@ -261,6 +870,8 @@ mod tests {
         let target = PortfolioTarget::new(meta_target);
 
         let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
             stocks: vec![
                 Stock::new("CASH", dec!(1.0)); 100 // 100 unidades de 1€
             ],
@@ -296,6 +907,8 @@ mod tests {
         .unwrap();
 
         let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
             stocks: vec![Stock::new("OTHER", dec!(100.0))],
             allocation: target,
         };
@@ -306,6 +919,36 @@ mod tests {
         assert_eq!(*suggestion.to_buy.get("META").unwrap(), 1);
     }
 
+    #[test]
+    fn test_cash_allocation_leaves_reserve() {
+        let target = PortfolioTarget::try_from_allocations(vec![
+            Allocation::Stock(dec!(90), Stock::new("META", dec!(10))),
+            Allocation::Cash(dec!(10)),
+        ])
+        .unwrap();
+        assert_eq!(target.cash_weight(), dec!(10));
+
+        let portfolio = Portfolio {
+            cash: dec!(1000),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: target,
+        };
+
+        // compra 90 META y deja 100 en caja
+        let suggestion = portfolio.rebalance_portfolio();
+        assert_eq!(suggestion.to_buy["META"], 90);
+        assert!(!suggestion.to_buy.contains_key("cash"));
+
+        assert!(
+            PortfolioTarget::try_from_allocations(vec![
+                Allocation::Stock(dec!(110), Stock::new("META", dec!(10))),
+                Allocation::Cash(dec!(-10)),
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn test_rebalance_empty_portfolio() {
         // Escenario: El vector de stocks está vacío.
@@ -313,6 +956,8 @@ mod tests {
         // o manejar el total de 0.0.
         let target = PortfolioTarget::new(Stock::new("META", dec!(100.0)));
         let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
             stocks: vec![],
             allocation: target,
         };
@@ -321,4 +966,84 @@ mod tests {
         assert!(suggestion.to_buy.is_empty());
         assert!(suggestion.to_sell.is_empty());
     }
+
+    #[test]
+    fn test_rebalance_mixes_stocks_and_bonds() {
+        let terms = Bond::new(dec!(1000), dec!(0.06), Date::new(2030, 12, 31).unwrap());
+        let bond = |date| Stock::bond("BONO30", terms.clone(), dec!(95), date);
+
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("META", dec!(100))),
+            (dec!(50), bond(Date::new(2024, 12, 31).unwrap())),
+        ])
+        .unwrap();
+
+        let mut portfolio = Portfolio {
+            cash: dec!(2000),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: target,
+        };
+
+        // en fecha de cupon no hay devengado: 950 por bono, alcanza para 1
+        assert_eq!(portfolio.rebalance_portfolio().to_buy["BONO30"], 1);
+
+        // tres meses despues el bono vale 950 + 15, y con mas caja alcanza para 2
+        portfolio.cash = dec!(3900);
+        portfolio.revalue_at(Date::new(2025, 3, 31).unwrap());
+        assert_eq!(
+            portfolio.allocation().targets()[1]
+                .1
+                .current_price()
+                .round_dp(0),
+            dec!(965)
+        );
+        assert_eq!(portfolio.rebalance_portfolio().to_buy["BONO30"], 2);
+    }
+
+    #[test]
+    fn test_suggestion_summary_is_localized() {
+        let target = PortfolioTarget::new(Stock::new("META", dec!(25.0)));
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("CASH", dec!(1.0)); 50],
+            allocation: target,
+        };
+
+        let suggestion = portfolio.rebalance_portfolio();
+
+        assert_eq!(
+            suggestion.localize(Language::Es),
+            "Vender 50 CASH\nComprar 2 META"
+        );
+        assert_eq!(
+            suggestion.localize(Language::En),
+            "Sell 50 CASH\nBuy 2 META"
+        );
+    }
+
+    #[test]
+    fn test_holding_queries() {
+        let portfolio = Portfolio {
+            cash: dec!(20),
+            foreign_cash: Default::default(),
+            stocks: vec![
+                Stock::new("META", dec!(30)),
+                Stock::new("AAPL", dec!(10)),
+                Stock::new("META", dec!(30)),
+            ],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(30))),
+        };
+
+        assert_eq!(portfolio.total_value(), dec!(90));
+        assert_eq!(portfolio.tickers(), ["AAPL", "META"]);
+        assert_eq!(
+            portfolio.iter_holdings().collect::<Vec<_>>(),
+            [("AAPL", 1), ("META", 2)]
+        );
+        assert_eq!(portfolio.value_of("META"), dec!(60));
+        assert_eq!(portfolio.weight_of("AAPL").round_dp(2), dec!(11.11));
+        assert_eq!(portfolio.weight_of("TSLA"), Decimal::ZERO);
+    }
 }
@@ -0,0 +1,238 @@
+//! Periodos de permanencia (lock-ups).
+//!
+//! Algunas unidades no se pueden vender hasta cierta fecha: fondos con permanencia minima,
+//! acciones de la empresa que aun no se liberan, etc. Cada unidad guarda hasta cuando esta
+//! bloqueada (`Stock::with_lock_up`); la etapa `LockUp` evita que el rebalanceo sugiera venderlas
+//! y `Portfolio::lock_up_report` dice que ventas quedaron bloqueadas y desde cuando se podria
+//! hacer el rebalanceo completo.
+
+use crate::date::Date;
+use crate::i18n::{Language, Localize, language};
+use crate::pipeline::{Pipeline, Plan, RebalanceStage, fit_buys_to_cash};
+use crate::{Portfolio, RebalanceSuggestion, Stock};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+impl Stock {
+    /// Unidad que no se puede vender antes de `until`.
+    pub fn with_lock_up(mut self, until: Date) -> Self {
+        self.locked_until = Some(until);
+        self
+    }
+
+    pub fn locked_until(&self) -> Option<Date> {
+        self.locked_until
+    }
+
+    /// Si la unidad sigue bloqueada el dia `on`; el dia `until` ya se puede vender.
+    pub fn is_locked(&self, on: Date) -> bool {
+        self.locked_until.is_some_and(|until| on < until)
+    }
+}
+
+/// No sugiere vender unidades bloqueadas a la fecha: el objetivo de cada ticker no baja de las
+/// unidades bloqueadas que se tienen, y las compras se achican a lo que alcanza la caja.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockUp(pub Date);
+
+impl RebalanceStage for LockUp {
+    fn name(&self) -> &str {
+        "lock_up"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        for (ticker, units) in portfolio.locked_units(self.0) {
+            let target = plan.targets.entry(ticker).or_insert(Decimal::ZERO);
+            *target = (*target).max(Decimal::from(units));
+        }
+        fit_buys_to_cash(portfolio, plan);
+    }
+}
+
+/// Una venta que el rebalanceo completo pediria pero que los bloqueos no dejan hacer entera.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockedSell {
+    pub ticker: String,
+
+    /// Unidades que pide el rebalanceo sin bloqueos.
+    pub wanted: usize,
+
+    /// Unidades que se pueden vender hoy.
+    pub allowed: usize,
+
+    /// Desde cuando se liberan suficientes unidades para vender `wanted`.
+    pub unlocks_on: Date,
+}
+
+/// Ventas bloqueadas a una fecha.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockUpReport {
+    pub as_of: Date,
+    pub blocked: Vec<BlockedSell>,
+}
+
+impl LockUpReport {
+    /// Primer dia en que el rebalanceo completo (con los precios de hoy) se puede hacer; `None`
+    /// si ya se puede.
+    pub fn full_rebalance_on(&self) -> Option<Date> {
+        self.blocked.iter().map(|b| b.unlocks_on).max()
+    }
+}
+
+impl Portfolio {
+    /// Unidades bloqueadas el dia `on`, por ticker.
+    pub fn locked_units(&self, on: Date) -> BTreeMap<&str, usize> {
+        let mut locked = BTreeMap::new();
+        for stock in self.stocks().iter().filter(|s| s.is_locked(on)) {
+            *locked.entry(stock.name()).or_default() += 1;
+        }
+        locked
+    }
+
+    /// El rebalanceo conservador sin vender unidades bloqueadas el dia `as_of`.
+    pub fn rebalance_with_lock_ups(&self, as_of: Date) -> RebalanceSuggestion<'_> {
+        Pipeline::conservative()
+            .insert_before("round", LockUp(as_of))
+            .run(self)
+    }
+
+    /// Compara el rebalanceo completo con lo que dejan vender los bloqueos el dia `as_of`.
+    pub fn lock_up_report(&self, as_of: Date) -> LockUpReport {
+        let full = self.rebalance_portfolio();
+        let mut sells: Vec<(&str, usize)> = full.to_sell.into_iter().collect();
+        sells.sort();
+
+        let mut blocked = Vec::new();
+        for (ticker, wanted) in sells {
+            let units = self.stocks().iter().filter(|s| s.name() == ticker);
+            let free = units.clone().filter(|s| !s.is_locked(as_of)).count();
+            if wanted <= free {
+                continue;
+            }
+
+            let mut unlocks: Vec<Date> = units.filter_map(|s| s.locked_until).collect();
+            unlocks.sort();
+            unlocks.retain(|until| as_of < *until);
+            blocked.push(BlockedSell {
+                ticker: ticker.into(),
+                wanted,
+                allowed: free,
+                unlocks_on: unlocks[wanted - free - 1],
+            });
+        }
+
+        LockUpReport { as_of, blocked }
+    }
+}
+
+impl Localize for LockUpReport {
+    fn localize(&self, language: Language) -> String {
+        let Some(date) = self.full_rebalance_on() else {
+            return match language {
+                Language::Es => format!("Sin ventas bloqueadas al {}", self.as_of),
+                Language::En => format!("No blocked sells as of {}", self.as_of),
+            };
+        };
+
+        let mut lines = Vec::new();
+        lines.push(match language {
+            Language::Es => format!("Rebalanceo completo posible desde el {date}"),
+            Language::En => format!("Full rebalance possible from {date}"),
+        });
+        for b in &self.blocked {
+            lines.push(match language {
+                Language::Es => format!(
+                    "  {}: vender {} de {} (bloqueadas hasta el {})",
+                    b.ticker, b.allowed, b.wanted, b.unlocks_on
+                ),
+                Language::En => format!(
+                    "  {}: sell {} of {} (locked until {})",
+                    b.ticker, b.allowed, b.wanted, b.unlocks_on
+                ),
+            });
+        }
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for LockUpReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortfolioTarget;
+    use alloc::vec;
+    use rust_decimal_macros::dec;
+
+    fn d(month: u32) -> Date {
+        Date::new(2025, month, 1).unwrap()
+    }
+
+    fn portfolio() -> Portfolio {
+        let vested = Stock::new("ACME", dec!(10));
+        Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: [
+                vec![vested.clone(); 2],
+                vec![vested.clone().with_lock_up(d(3)); 3],
+                vec![vested.with_lock_up(d(6)); 5],
+            ]
+            .concat(),
+            allocation: PortfolioTarget::try_from_vec(vec![
+                (dec!(20), Stock::new("ACME", dec!(10))),
+                (dec!(80), Stock::new("SPY", dec!(10))),
+            ])
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_locked_units_are_not_sold() {
+        let portfolio = portfolio();
+        assert_eq!(portfolio.rebalance_portfolio().to_sell["ACME"], 8);
+
+        let suggestion = portfolio.rebalance_with_lock_ups(d(1));
+        assert_eq!(suggestion.to_sell["ACME"], 2);
+        assert_eq!(suggestion.to_buy["SPY"], 2);
+
+        // en marzo se liberan 3 mas
+        assert_eq!(portfolio.rebalance_with_lock_ups(d(3)).to_sell["ACME"], 5);
+    }
+
+    #[test]
+    fn test_report_says_when_full_rebalance_is_possible() {
+        let report = portfolio().lock_up_report(d(1));
+        assert_eq!(report.blocked.len(), 1);
+        assert_eq!(
+            (report.blocked[0].wanted, report.blocked[0].allowed),
+            (8, 2)
+        );
+        assert_eq!(report.full_rebalance_on(), Some(d(6)));
+
+        assert_eq!(portfolio().lock_up_report(d(6)).full_rebalance_on(), None);
+    }
+
+    #[test]
+    fn test_sales_take_unlocked_units_first() {
+        let mut portfolio = portfolio();
+        portfolio
+            .apply(
+                &[crate::execution::Order::sell("ACME", 3)],
+                d(1),
+                &Default::default(),
+            )
+            .unwrap();
+        // las 2 libres y la que se libera antes
+        assert_eq!(portfolio.locked_units(d(1))["ACME"], 7);
+        assert_eq!(portfolio.locked_units(d(3))["ACME"], 5);
+    }
+}
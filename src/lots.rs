@@ -0,0 +1,117 @@
+//! Lotes: unidades de un mismo ticker compradas al mismo costo el mismo dia.
+//!
+//! Como `Portfolio` guarda un `Stock` por unidad, el costo de compra va en cada unidad
+//! (`Stock::basis`) y los lotes se arman agrupando. Las unidades sin costo conocido (p. ej.
+//! importadas de una cartola sin historial) no forman parte de ningun lote.
+
+use crate::Portfolio;
+use crate::date::Date;
+use crate::money::Currency;
+use alloc::string::String;
+use alloc::vec::Vec;
+use rust_decimal::Decimal;
+
+/// Costo de compra de una unidad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CostBasis {
+    /// Costo por unidad, en la moneda del stock.
+    pub cost: Decimal,
+    pub acquired: Date,
+
+    /// Cuanto valia una unidad de la moneda del stock en la moneda base al comprar; `None` si no
+    /// se sabe (o si el stock cotiza en la base).
+    pub fx_rate: Option<Decimal>,
+}
+
+/// Unidades de un ticker con el mismo costo y fecha de compra.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lot {
+    pub ticker: String,
+    pub units: usize,
+    pub basis: CostBasis,
+
+    /// Precio actual por unidad.
+    pub price: Decimal,
+
+    /// Moneda en que estan el costo y el precio.
+    pub currency: Currency,
+}
+
+impl Lot {
+    pub fn cost(&self) -> Decimal {
+        self.basis.cost * Decimal::from(self.units)
+    }
+
+    pub fn market_value(&self) -> Decimal {
+        self.price * Decimal::from(self.units)
+    }
+
+    /// Ganancia (o perdida, si es negativa) no realizada.
+    pub fn unrealized(&self) -> Decimal {
+        self.market_value() - self.cost()
+    }
+}
+
+impl Portfolio {
+    /// Lotes del portafolio, ordenados por ticker y fecha de compra.
+    pub fn lots(&self) -> Vec<Lot> {
+        let mut lots: Vec<Lot> = Vec::new();
+
+        for stock in self.stocks() {
+            let Some(basis) = stock.basis() else {
+                continue;
+            };
+
+            match lots
+                .iter_mut()
+                .find(|lot| lot.ticker == stock.name() && lot.basis == *basis)
+            {
+                Some(lot) => lot.units += 1,
+                None => lots.push(Lot {
+                    ticker: stock.name().into(),
+                    units: 1,
+                    basis: *basis,
+                    price: stock.current_price(),
+                    currency: stock.currency,
+                }),
+            }
+        }
+
+        lots.sort_by(|a, b| {
+            a.ticker
+                .cmp(&b.ticker)
+                .then(a.basis.acquired.cmp(&b.basis.acquired))
+        });
+        lots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_units_are_grouped_in_lots() {
+        let d = |month| Date::new(2024, month, 1).unwrap();
+        let meta = Stock::new("META", dec!(50));
+
+        let mut stocks = vec![meta.clone().with_basis(dec!(40), d(3)); 2];
+        stocks.push(meta.clone().with_basis(dec!(60), d(1)));
+        stocks.push(meta.clone().with_basis(dec!(40), d(3)));
+        stocks.push(meta.clone());
+
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: PortfolioTarget::new(meta),
+        };
+
+        let lots = portfolio.lots();
+        assert_eq!(lots.len(), 2);
+        assert_eq!((lots[0].units, lots[0].unrealized()), (1, dec!(-10)));
+        assert_eq!((lots[1].units, lots[1].unrealized()), (3, dec!(30)));
+    }
+}
@@ -0,0 +1,211 @@
+//! Precision de los calculos en `Decimal`.
+//!
+//! Por defecto la libreria no redondea nada hasta el final: los pesos y las unidades objetivo
+//! usan los 28 digitos de `Decimal` y los montos quedan con los decimales que salgan de
+//! multiplicar precio por unidades. Un sistema contable suele tener sus propias convenciones, y
+//! para cuadrar al peso con el hay que calcular igual que el. `MathConfig` fija cuantos decimales
+//! se usan en:
+//!
+//! - los pesos intermedios, en % (`Portfolio::weights`, `weight_of` y el peso de cada ticker al
+//!   calcular las unidades objetivo), asi `weight_dp` significa lo mismo en todos lados;
+//! - los montos (lo que entra o sale de la caja por cada ejecucion y sus comisiones), con el
+//!   redondeo de `Rounding`;
+//! - las cantidades objetivo antes de que el pipeline las redondee al lote, siempre hacia cero
+//!   para no pasarse del objetivo.
+//!
+//! Como el idioma (ver `i18n`) y el modo de prueba (ver `dry_run`), es un ajuste de todo el
+//! proceso; los metodos de `MathConfig` sirven para calcular con una configuracion explicita.
+//!
+//! ```
+//! use fintual_coding_challenge::math::{MathConfig, Rounding, math_config, set_math_config};
+//! use rust_decimal_macros::dec;
+//!
+//! set_math_config(MathConfig::new().with_notional_dp(2, Rounding::HalfUp));
+//! assert_eq!(math_config().notional(dec!(10.125)), dec!(10.13));
+//! set_math_config(MathConfig::default());
+//! ```
+
+use core::sync::atomic::{AtomicU8, AtomicU32, Ordering};
+use rust_decimal::prelude::*;
+
+/// Como redondear un monto al ultimo decimal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Rounding {
+    /// La mitad al par mas cercano ("banker's rounding"); el de `Decimal::round_dp`.
+    #[default]
+    HalfEven,
+
+    /// La mitad lejos de cero, el que se espera ver en una cartola.
+    HalfUp,
+
+    /// Siempre hacia cero.
+    Down,
+}
+
+impl Rounding {
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            Rounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Rounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            Rounding::Down => RoundingStrategy::ToZero,
+        }
+    }
+}
+
+/// Decimales de cada tipo de calculo; `None` es sin redondear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MathConfig {
+    pub weight_dp: Option<u32>,
+    pub notional_dp: Option<u32>,
+    pub notional_rounding: Rounding,
+    pub quantity_dp: Option<u32>,
+}
+
+impl MathConfig {
+    /// Sin redondear nada.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_weight_dp(mut self, decimals: u32) -> Self {
+        self.weight_dp = Some(decimals);
+        self
+    }
+
+    pub fn with_notional_dp(mut self, decimals: u32, rounding: Rounding) -> Self {
+        self.notional_dp = Some(decimals);
+        self.notional_rounding = rounding;
+        self
+    }
+
+    pub fn with_quantity_dp(mut self, decimals: u32) -> Self {
+        self.quantity_dp = Some(decimals);
+        self
+    }
+
+    /// Un peso en %, redondeado a la mitad par.
+    pub fn weight(&self, weight: Decimal) -> Decimal {
+        match self.weight_dp {
+            Some(decimals) => weight.round_dp(decimals),
+            None => weight,
+        }
+    }
+
+    pub fn notional(&self, amount: Decimal) -> Decimal {
+        match self.notional_dp {
+            Some(decimals) => {
+                amount.round_dp_with_strategy(decimals, self.notional_rounding.strategy())
+            }
+            None => amount,
+        }
+    }
+
+    /// Una cantidad, truncada.
+    pub fn quantity(&self, units: Decimal) -> Decimal {
+        match self.quantity_dp {
+            Some(decimals) => units.round_dp_with_strategy(decimals, RoundingStrategy::ToZero),
+            None => units,
+        }
+    }
+
+    /// Como `numeric::target_units`, redondeando el peso (en %, como en `Portfolio::weights`) y
+    /// el resultado como cantidad.
+    pub fn target_units(&self, total: Decimal, weight: Decimal, price: Decimal) -> Decimal {
+        let fraction = self.weight(weight) / Decimal::ONE_HUNDRED;
+        self.quantity(total * fraction / price)
+    }
+
+    /// Como `target_units`, pero `None` si no cabe en un `Decimal`. El precio no puede ser cero.
+    pub fn checked_target_units(
+        &self,
+        total: Decimal,
+        weight: Decimal,
+        price: Decimal,
+    ) -> Option<Decimal> {
+        let fraction = self.weight(weight) / Decimal::ONE_HUNDRED;
+        let units = total.checked_mul(fraction)?.checked_div(price)?;
+        Some(self.quantity(units))
+    }
+}
+
+// Cada campo en su atomico, con `UNSET` para `None`, para no necesitar un Mutex global.
+const UNSET: u32 = u32::MAX;
+static WEIGHT_DP: AtomicU32 = AtomicU32::new(UNSET);
+static NOTIONAL_DP: AtomicU32 = AtomicU32::new(UNSET);
+static NOTIONAL_ROUNDING: AtomicU8 = AtomicU8::new(0);
+static QUANTITY_DP: AtomicU32 = AtomicU32::new(UNSET);
+
+/// Cambia la precision de todo el proceso.
+pub fn set_math_config(config: MathConfig) {
+    let store = |slot: &AtomicU32, decimals: Option<u32>| {
+        slot.store(decimals.unwrap_or(UNSET), Ordering::Relaxed);
+    };
+    store(&WEIGHT_DP, config.weight_dp);
+    store(&NOTIONAL_DP, config.notional_dp);
+    store(&QUANTITY_DP, config.quantity_dp);
+
+    let rounding = match config.notional_rounding {
+        Rounding::HalfEven => 0,
+        Rounding::HalfUp => 1,
+        Rounding::Down => 2,
+    };
+    NOTIONAL_ROUNDING.store(rounding, Ordering::Relaxed);
+}
+
+/// Precision actual; por defecto sin redondear.
+pub fn math_config() -> MathConfig {
+    let load = |slot: &AtomicU32| match slot.load(Ordering::Relaxed) {
+        UNSET => None,
+        decimals => Some(decimals),
+    };
+
+    MathConfig {
+        weight_dp: load(&WEIGHT_DP),
+        notional_dp: load(&NOTIONAL_DP),
+        notional_rounding: match NOTIONAL_ROUNDING.load(Ordering::Relaxed) {
+            1 => Rounding::HalfUp,
+            2 => Rounding::Down,
+            _ => Rounding::HalfEven,
+        },
+        quantity_dp: load(&QUANTITY_DP),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_default_config_does_not_round() {
+        let config = MathConfig::default();
+        assert_eq!(config.weight(dec!(0.123456789)), dec!(0.123456789));
+        assert_eq!(config.notional(dec!(10.125)), dec!(10.125));
+        assert_eq!(
+            config.target_units(dec!(1000), dec!(33.3), dec!(7)),
+            dec!(1000) * dec!(0.333) / dec!(7)
+        );
+    }
+
+    #[test]
+    fn test_config_rounds_each_kind_of_number() {
+        let config = MathConfig::new()
+            .with_weight_dp(2)
+            .with_notional_dp(2, Rounding::HalfUp)
+            .with_quantity_dp(3);
+
+        assert_eq!(config.weight(dec!(33.333)), dec!(33.33));
+        assert_eq!(config.notional(dec!(10.125)), dec!(10.13));
+        assert_eq!(
+            MathConfig::new()
+                .with_notional_dp(2, Rounding::HalfEven)
+                .notional(dec!(10.125)),
+            dec!(10.12)
+        );
+        // el peso se redondea en %, como en `Portfolio::weights`: 1000 * 0.3333 / 7 = 47.6142857...
+        assert_eq!(
+            config.target_units(dec!(1000), dec!(33.333), dec!(7)),
+            dec!(47.614)
+        );
+    }
+}
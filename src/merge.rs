@@ -0,0 +1,173 @@
+//! Consolidacion y division de portafolios.
+//!
+//! `Portfolio::merge` junta dos cuentas en una (p. ej. al traspasar una cuenta a otra) y
+//! `Portfolio::split` reparte una en varias por proporciones (p. ej. para separar sleeves).
+
+use crate::Portfolio;
+use crate::error::TargetError;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
+use rust_decimal::prelude::*;
+
+impl Portfolio {
+    /// Junta los holdings y saldos de `other` con los de este portafolio, manteniendo el objetivo
+    /// de `self`.
+    ///
+    /// Las unidades de un mismo ticker quedan juntas y con el precio que tenia `self` (que es el
+    /// que recibe las cotizaciones via su objetivo); cada unidad conserva su costo de compra.
+    pub fn merge(mut self, other: Portfolio) -> Portfolio {
+        self.cash += other.cash;
+        for (currency, amount) in other.foreign_cash {
+            *self.foreign_cash.entry(currency).or_default() += amount;
+        }
+
+        for mut stock in other.stocks {
+            if let Some(priced) = self.priced(stock.name()) {
+                stock.current_price = priced.current_price;
+            }
+            self.stocks.push(stock);
+        }
+        // sort estable: dentro de cada ticker se mantiene el orden de compra
+        self.stocks.sort_by(|a, b| a.name().cmp(b.name()));
+
+        self
+    }
+
+    /// Divide el portafolio en uno por cada peso (en %, deben sumar 100), todos con el mismo
+    /// objetivo.
+    ///
+    /// Las unidades de cada ticker se reparten proporcionalmente; como no se pueden partir, las
+    /// que sobran al truncar van a los portafolios con mayor parte fraccionaria (y, en empate, al
+    /// primero). La caja se reparte exacta y cualquier residuo de redondeo queda en el ultimo.
+    pub fn split(&self, weights: &[Decimal]) -> Result<Vec<Portfolio>, TargetError> {
+        if let Some(index) = weights.iter().position(|w| *w <= Decimal::ZERO) {
+            return Err(TargetError::NonPositiveWeight(format!("#{index}")));
+        }
+        let total: Decimal = weights.iter().sum();
+        if total != Decimal::ONE_HUNDRED {
+            return Err(TargetError::InvalidTotal(total));
+        }
+
+        let mut parts: Vec<Portfolio> = weights
+            .iter()
+            .map(|_| Portfolio {
+                cash: Decimal::ZERO,
+                foreign_cash: BTreeMap::new(),
+                stocks: Vec::new(),
+                allocation: self.allocation.clone(),
+            })
+            .collect();
+
+        let mut by_ticker: BTreeMap<&str, Vec<_>> = BTreeMap::new();
+        for stock in &self.stocks {
+            by_ticker.entry(stock.name()).or_default().push(stock);
+        }
+        for units in by_ticker.values() {
+            let counts = apportion(units.len(), weights);
+            let mut units = units.iter();
+            for (part, count) in parts.iter_mut().zip(counts) {
+                part.stocks
+                    .extend(units.by_ref().take(count).map(|s| (*s).clone()));
+            }
+        }
+
+        for (part, cash) in parts.iter_mut().zip(share(self.cash, weights)) {
+            part.cash = cash;
+        }
+        for (currency, amount) in &self.foreign_cash {
+            for (part, amount) in parts.iter_mut().zip(share(*amount, weights)) {
+                part.foreign_cash.insert(*currency, amount);
+            }
+        }
+
+        Ok(parts)
+    }
+}
+
+/// Reparte un monto segun `weights`; el residuo de redondeo queda en la ultima parte.
+fn share(amount: Decimal, weights: &[Decimal]) -> Vec<Decimal> {
+    let mut parts: Vec<Decimal> = weights
+        .iter()
+        .map(|w| amount * w / Decimal::ONE_HUNDRED)
+        .collect();
+    let assigned: Decimal = parts.iter().sum();
+    if let Some(last) = parts.last_mut() {
+        *last += amount - assigned;
+    }
+    parts
+}
+
+/// Reparte `units` enteras segun `weights` por el metodo del mayor resto.
+fn apportion(units: usize, weights: &[Decimal]) -> Vec<usize> {
+    let quotas: Vec<Decimal> = weights
+        .iter()
+        .map(|w| Decimal::from(units) * w / Decimal::ONE_HUNDRED)
+        .collect();
+    let mut counts: Vec<usize> = quotas
+        .iter()
+        .map(|q| q.trunc().to_usize().unwrap_or(0))
+        .collect();
+
+    let mut order: Vec<usize> = (0..weights.len()).collect();
+    order.sort_by(|a, b| quotas[*b].fract().cmp(&quotas[*a].fract()).then(a.cmp(b)));
+    let missing = units - counts.iter().sum::<usize>();
+    for index in order.into_iter().take(missing) {
+        counts[index] += 1;
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Date;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn portfolio(stocks: Vec<Stock>, cash: Decimal) -> Portfolio {
+        Portfolio {
+            cash,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(30))),
+        }
+    }
+
+    #[test]
+    fn test_merge_consolidates_holdings_and_cash() {
+        let acquired = Date::new(2023, 1, 10).unwrap();
+        let a = portfolio(
+            vec![Stock::new("META", dec!(30)), Stock::new("GOOG", dec!(50))],
+            dec!(10),
+        );
+        let b = portfolio(
+            vec![Stock::new("META", dec!(25)).with_basis(dec!(20), acquired)],
+            dec!(5),
+        );
+
+        let merged = a.merge(b);
+        assert_eq!(merged.cash(), dec!(15));
+        let names: Vec<&str> = merged.stocks().iter().map(|s| s.name()).collect();
+        assert_eq!(names, ["GOOG", "META", "META"]);
+        assert_eq!(merged.stocks()[2].current_price(), dec!(30));
+        assert_eq!(merged.stocks()[2].basis().unwrap().cost, dec!(20));
+    }
+
+    #[test]
+    fn test_split_by_weights() {
+        let whole = portfolio(vec![Stock::new("META", dec!(30)); 5], dec!(100));
+
+        let parts = whole.split(&[dec!(70), dec!(30)]).unwrap();
+        assert_eq!(parts[0].stocks().len(), 4);
+        assert_eq!(parts[1].stocks().len(), 1);
+        assert_eq!(parts[0].cash(), dec!(70));
+        assert_eq!(parts[1].cash(), dec!(30));
+
+        assert_eq!(
+            whole.split(&[dec!(50), dec!(30)]).unwrap_err(),
+            TargetError::InvalidTotal(dec!(80))
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Etiquetas y metadata libre por holding.
+//!
+//! Cada unidad puede llevar etiquetas (`"employer stock"`, `"gift"`) y campos clave-valor
+//! (`"locked_until" = "2026-06-30"`) sin que la libreria sepa lo que significan. Sirven para
+//! filtrar (`Portfolio::tagged`) y para politicas propias, como `KeepTagged`, que no deja vender
+//! las unidades con cierta etiqueta.
+
+use crate::date::Date;
+use crate::pipeline::{Plan, RebalanceStage, fit_buys_to_cash};
+use crate::{Portfolio, Stock};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use rust_decimal::prelude::*;
+
+/// Etiquetas y campos de un holding.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    tags: BTreeSet<String>,
+    fields: BTreeMap<String, String>,
+}
+
+/// La de los stocks sin metadata, que no guardan nada.
+static EMPTY: Metadata = Metadata::new();
+
+impl Metadata {
+    pub const fn new() -> Self {
+        Self {
+            tags: BTreeSet::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.insert(tag.into());
+        self
+    }
+
+    pub fn with_field(mut self, key: &str, value: &str) -> Self {
+        self.fields.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.fields.is_empty()
+    }
+}
+
+impl Stock {
+    pub fn with_tag(self, tag: &str) -> Self {
+        let metadata = self.metadata().clone().with_tag(tag);
+        self.with_metadata(metadata)
+    }
+
+    pub fn with_field(self, key: &str, value: &str) -> Self {
+        let metadata = self.metadata().clone().with_field(key, value);
+        self.with_metadata(metadata)
+    }
+
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = (!metadata.is_empty()).then(|| Box::new(metadata));
+        self
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        self.metadata.as_deref().unwrap_or(&EMPTY)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.metadata().has_tag(tag)
+    }
+
+    /// Prioridad para salir en una venta (la menor sale primero): las unidades sin bloqueo antes
+    /// que las bloqueadas (ver `lockup`) y, con el mismo bloqueo, las sin etiquetas antes que las
+    /// etiquetadas.
+    pub(crate) fn sale_order(&self) -> (Option<Date>, bool) {
+        (self.locked_until(), self.metadata().tags().next().is_some())
+    }
+}
+
+impl Portfolio {
+    /// Unidades que tienen la etiqueta `tag`.
+    pub fn tagged<'a>(&'a self, tag: &str) -> impl Iterator<Item = &'a Stock> {
+        self.stocks().iter().filter(move |s| s.has_tag(tag))
+    }
+
+    /// Valor de las unidades con la etiqueta `tag`.
+    pub fn tagged_value(&self, tag: &str) -> Decimal {
+        self.tagged(tag).map(|s| s.current_price()).sum()
+    }
+}
+
+/// No vende las unidades que tienen esta etiqueta (p. ej. acciones bloqueadas o de la empresa):
+/// el objetivo de cada ticker no baja de las unidades etiquetadas que se tienen, y las compras se
+/// achican a lo que alcanza a pagar la caja.
+///
+/// `Portfolio::apply` vende primero las unidades sin etiquetas, asi que las ventas que deja esta
+/// etapa salen de las otras unidades sin importar en que orden se compraron.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeepTagged(pub String);
+
+impl RebalanceStage for KeepTagged {
+    fn name(&self) -> &str {
+        "keep_tagged"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        let mut locked: BTreeMap<&str, usize> = BTreeMap::new();
+        for stock in portfolio.tagged(&self.0) {
+            *locked.entry(stock.name()).or_default() += 1;
+        }
+
+        for (ticker, units) in locked {
+            let target = plan.targets.entry(ticker).or_insert(Decimal::ZERO);
+            *target = (*target).max(Decimal::from(units));
+        }
+        fit_buys_to_cash(portfolio, plan);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pipeline, PortfolioTarget};
+    use alloc::vec;
+    use rust_decimal_macros::dec;
+
+    fn portfolio(tagged_first: bool) -> Portfolio {
+        let tagged = vec![Stock::new("ACME", dec!(10)).with_tag("employer stock"); 6];
+        let plain = vec![Stock::new("ACME", dec!(10)); 4];
+        let stocks = if tagged_first {
+            [tagged, plain].concat()
+        } else {
+            [plain, tagged].concat()
+        };
+
+        Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: PortfolioTarget::try_from_vec(vec![
+                (dec!(50), Stock::new("ACME", dec!(10))),
+                (dec!(50), Stock::new("SPY", dec!(10))),
+            ])
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_tagged_units_are_not_sold() {
+        let portfolio = portfolio(true);
+        assert_eq!(portfolio.tagged("employer stock").count(), 6);
+        assert_eq!(portfolio.tagged_value("employer stock"), dec!(60));
+        assert_eq!(portfolio.tagged("gift").count(), 0);
+
+        // sin la etapa se venden 5 ACME; con ella solo las 4 sin etiqueta
+        let plain = Pipeline::conservative().run(&portfolio);
+        assert_eq!(plain.to_sell["ACME"], 5);
+        let kept = Pipeline::conservative()
+            .insert_before("round", KeepTagged("employer stock".into()))
+            .run(&portfolio);
+        assert_eq!(kept.to_sell["ACME"], 4);
+        // y solo se compra lo que pagan esas ventas
+        assert_eq!(kept.to_buy["SPY"], 4);
+    }
+
+    #[test]
+    fn test_apply_sells_untagged_units_first() {
+        let date = Date::new(2024, 1, 2).unwrap();
+        for tagged_first in [true, false] {
+            let mut portfolio = portfolio(tagged_first);
+            let orders = Pipeline::conservative()
+                .insert_before("round", KeepTagged("employer stock".into()))
+                .run(&portfolio)
+                .orders();
+            portfolio
+                .apply(&orders, date, &crate::costs::CostModel::free())
+                .unwrap();
+
+            assert_eq!(portfolio.tagged("employer stock").count(), 6);
+            assert_eq!(
+                portfolio
+                    .stocks()
+                    .iter()
+                    .filter(|s| s.name() == "ACME")
+                    .count(),
+                6
+            );
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        let stock = Stock::new("ACME", dec!(10)).with_field("locked_until", "2026-06-30");
+        assert_eq!(stock.metadata().field("locked_until"), Some("2026-06-30"));
+        assert_eq!(stock.metadata().field("account"), None);
+        assert!(Stock::new("ACME", dec!(10)).metadata().is_empty());
+    }
+}
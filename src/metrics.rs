@@ -0,0 +1,478 @@
+//! Metricas de riesgo y estadisticas sobre series de retornos.
+//!
+//! Igual que en `projection`, todo esto es estadistica y no contabilidad, asi que trabaja con
+//! `f64`. Los retornos son fraccionales (`-0.02` = cayo un 2%) y las perdidas se reportan como
+//! numeros positivos (un VaR de `0.05` significa "se puede perder un 5%"). Las estadisticas
+//! basicas (`mean`, `std_dev`, `covariance`, `correlation`) son genericas sobre `Numeric` por si
+//! se quieren en `Decimal`.
+
+use crate::numeric::Numeric;
+use rust_decimal::prelude::ToPrimitive;
+
+/// Forma de estimar una metrica de cola.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TailMethod {
+    /// Usa directamente los retornos observados (o simulados), sin suponer una distribucion.
+    Historical,
+
+    /// Supone retornos normales con la media y desviacion de la muestra.
+    Parametric,
+}
+
+pub fn mean<N: Numeric>(values: &[N]) -> Option<N> {
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(values.iter().copied().sum::<N>() / N::from_usize(values.len()))
+}
+
+/// Desviacion estandar muestral (divide por `n - 1`).
+pub fn std_dev<N: Numeric>(values: &[N]) -> Option<N> {
+    if values.len() < 2 {
+        return None;
+    }
+
+    let mu = mean(values)?;
+    let var =
+        values.iter().map(|&x| (x - mu) * (x - mu)).sum::<N>() / N::from_usize(values.len() - 1);
+
+    var.sqrt()
+}
+
+/// Covarianza muestral entre dos series alineadas del mismo largo.
+pub fn covariance<N: Numeric>(a: &[N], b: &[N]) -> Option<N> {
+    if a.len() != b.len() || a.len() < 2 {
+        return None;
+    }
+
+    let (mean_a, mean_b) = (mean(a)?, mean(b)?);
+    let sum: N = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| (x - mean_a) * (y - mean_b))
+        .sum();
+
+    Some(sum / N::from_usize(a.len() - 1))
+}
+
+/// Correlacion de Pearson; `None` si alguna serie es constante.
+pub fn correlation<N: Numeric>(a: &[N], b: &[N]) -> Option<N> {
+    let denominator = std_dev(a)? * std_dev(b)?;
+    if denominator == N::ZERO {
+        return None;
+    }
+
+    Some(covariance(a, b)? / denominator)
+}
+
+/// Matriz de covarianzas entre los retornos de varios activos, indexada por ticker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CovarianceMatrix {
+    tickers: Vec<String>,
+    values: Vec<Vec<f64>>,
+}
+
+impl CovarianceMatrix {
+    pub fn tickers(&self) -> &[String] {
+        &self.tickers
+    }
+
+    /// Valores en el mismo orden que `tickers()`.
+    pub fn values(&self) -> &[Vec<f64>] {
+        &self.values
+    }
+
+    fn index_of(&self, ticker: &str) -> Option<usize> {
+        self.tickers.iter().position(|t| t == ticker)
+    }
+
+    pub fn covariance(&self, a: &str, b: &str) -> Option<f64> {
+        Some(self.values[self.index_of(a)?][self.index_of(b)?])
+    }
+
+    pub fn variance(&self, ticker: &str) -> Option<f64> {
+        self.covariance(ticker, ticker)
+    }
+
+    pub fn correlation(&self, a: &str, b: &str) -> Option<f64> {
+        let denominator = (self.variance(a)? * self.variance(b)?).sqrt();
+        if denominator == 0.0 {
+            return None;
+        }
+
+        Some(self.covariance(a, b)? / denominator)
+    }
+
+    /// Correlacion de cada par distinto de activos, en el orden de `tickers()`.
+    pub fn correlations(&self) -> Vec<(&str, &str, Option<f64>)> {
+        let mut pairs = Vec::new();
+        for (i, a) in self.tickers.iter().enumerate() {
+            for b in &self.tickers[i + 1..] {
+                pairs.push((a.as_str(), b.as_str(), self.correlation(a, b)));
+            }
+        }
+        pairs
+    }
+
+    /// Varianza de un portafolio con estos pesos (fraccionales, en el orden de `tickers()`):
+    /// `wᵀ Σ w`.
+    pub fn portfolio_variance(&self, weights: &[f64]) -> Option<f64> {
+        if weights.len() != self.tickers.len() {
+            return None;
+        }
+
+        let mut total = 0.0;
+        for (i, wi) in weights.iter().enumerate() {
+            for (j, wj) in weights.iter().enumerate() {
+                total += wi * wj * self.values[i][j];
+            }
+        }
+
+        Some(total)
+    }
+}
+
+/// Construye la matriz de covarianzas a partir de las series de retornos de cada activo.
+///
+/// Todas las series deben tener el mismo largo y al menos dos observaciones; alinearlas (por
+/// fechas) es responsabilidad de quien llama.
+pub fn covariance_matrix(histories: &[(&str, Vec<f64>)]) -> Option<CovarianceMatrix> {
+    let mut values = vec![vec![0.0; histories.len()]; histories.len()];
+
+    for (i, (_, a)) in histories.iter().enumerate() {
+        for (j, (_, b)) in histories.iter().enumerate().skip(i) {
+            let cov = covariance(a, b)?;
+            values[i][j] = cov;
+            values[j][i] = cov;
+        }
+    }
+
+    Some(CovarianceMatrix {
+        tickers: histories.iter().map(|(t, _)| t.to_string()).collect(),
+        values,
+    })
+}
+
+/// Resultado de una regresion lineal simple `y = alpha + beta * x`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Regression {
+    pub alpha: f64,
+    pub beta: f64,
+    /// Que fraccion de la varianza de `y` explica `x` (entre 0 y 1).
+    pub r_squared: f64,
+}
+
+/// Regresion por minimos cuadrados de `y` contra `x`; `None` si `x` es constante o las series no
+/// estan alineadas.
+pub fn regress(y: &[f64], x: &[f64]) -> Option<Regression> {
+    let var_x = std_dev(x)?.powi(2);
+    if var_x == 0.0 {
+        return None;
+    }
+
+    let beta = covariance(x, y)? / var_x;
+    let alpha = mean(y)? - beta * mean(x)?;
+    let r_squared = correlation(x, y).map_or(0.0, |r| r * r);
+
+    Some(Regression {
+        alpha,
+        beta,
+        r_squared,
+    })
+}
+
+/// Beta de una serie de retornos respecto a un benchmark.
+pub fn beta(returns: &[f64], benchmark: &[f64]) -> Option<f64> {
+    regress(returns, benchmark).map(|r| r.beta)
+}
+
+/// Exposicion al mercado de cada holding y del portafolio completo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BetaExposure {
+    pub holdings: Vec<(String, Regression)>,
+    pub portfolio: Regression,
+}
+
+/// Regresiones de cada activo y del portafolio (con los pesos dados, fraccionales) contra el
+/// benchmark. La beta del portafolio es el promedio ponderado de las betas individuales, pero la
+/// regresion completa tambien da el alpha y el R² del conjunto.
+pub fn beta_exposure(
+    weights: &[f64],
+    histories: &[(&str, Vec<f64>)],
+    benchmark: &[f64],
+) -> Option<BetaExposure> {
+    let holdings = histories
+        .iter()
+        .map(|(ticker, returns)| Some((ticker.to_string(), regress(returns, benchmark)?)))
+        .collect::<Option<Vec<_>>>()?;
+
+    let series: Vec<Vec<f64>> = histories.iter().map(|(_, r)| r.clone()).collect();
+    let portfolio = regress(&portfolio_returns(weights, &series)?, benchmark)?;
+
+    Some(BetaExposure {
+        holdings,
+        portfolio,
+    })
+}
+
+impl crate::Portfolio {
+    /// `beta_exposure` usando los pesos actuales del portafolio. Los holdings sin historia
+    /// cuentan con peso pero sin retorno, asi que conviene pasar la historia de todos.
+    pub fn beta_exposure(
+        &self,
+        histories: &[(&str, Vec<f64>)],
+        benchmark: &[f64],
+    ) -> Option<BetaExposure> {
+        let current = self.weights();
+        let weights: Vec<f64> = histories
+            .iter()
+            .map(|(ticker, _)| {
+                current
+                    .iter()
+                    .find(|(name, _)| name == ticker)
+                    .and_then(|(_, w)| w.to_f64())
+                    .map_or(0.0, |w| w / 100.0)
+            })
+            .collect();
+
+        beta_exposure(&weights, histories, benchmark)
+    }
+}
+
+/// Retornos del portafolio a partir de los retornos de cada activo y sus pesos (fraccionales,
+/// deberian sumar 1). Todas las series deben estar alineadas y tener el mismo largo.
+pub fn portfolio_returns(weights: &[f64], asset_returns: &[Vec<f64>]) -> Option<Vec<f64>> {
+    if weights.len() != asset_returns.len() {
+        return None;
+    }
+
+    let periods = asset_returns.first().map_or(0, Vec::len);
+    if asset_returns.iter().any(|series| series.len() != periods) {
+        return None;
+    }
+
+    Some(
+        (0..periods)
+            .map(|t| {
+                weights
+                    .iter()
+                    .zip(asset_returns)
+                    .map(|(w, series)| w * series[t])
+                    .sum()
+            })
+            .collect(),
+    )
+}
+
+/// Value at Risk: la perdida que no se supera con probabilidad `confidence` (p. ej. `0.95`).
+pub fn var(returns: &[f64], confidence: f64, method: TailMethod) -> Option<f64> {
+    if returns.is_empty() || !(0.0..1.0).contains(&confidence) {
+        return None;
+    }
+
+    match method {
+        TailMethod::Historical => {
+            let worst = tail(returns, confidence);
+            Some(-*worst.last()?)
+        }
+        TailMethod::Parametric => {
+            let z = inverse_normal_cdf(1.0 - confidence);
+            Some(-(mean(returns)? + z * std_dev(returns)?))
+        }
+    }
+}
+
+/// Expected shortfall (CVaR): la perdida promedio en los casos que superan el VaR.
+pub fn expected_shortfall(returns: &[f64], confidence: f64, method: TailMethod) -> Option<f64> {
+    if returns.is_empty() || !(0.0..1.0).contains(&confidence) {
+        return None;
+    }
+
+    match method {
+        TailMethod::Historical => Some(-mean(&tail(returns, confidence))?),
+        TailMethod::Parametric => {
+            let z = inverse_normal_cdf(1.0 - confidence);
+            Some(-mean(returns)? + std_dev(returns)? * normal_pdf(z) / (1.0 - confidence))
+        }
+    }
+}
+
+/// Peores `(1 - confidence)` retornos, de peor a mejor. Siempre incluye al menos uno.
+fn tail(returns: &[f64], confidence: f64) -> Vec<f64> {
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    // `1.0 - 0.95` no es exactamente 0.05: sin el margen, 5.000000000000004 subiria a 6
+    let exact = (1.0 - confidence) * sorted.len() as f64;
+    let count = (exact - 1e-9).ceil().max(1.0) as usize;
+    sorted.truncate(count);
+    sorted
+}
+
+fn normal_pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Inversa de la CDF normal estandar (aproximacion racional de Acklam, error relativo ~1e-9).
+pub fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const LOW: f64 = 0.02425;
+
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+
+    if p < LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -inverse_normal_cdf(1.0 - p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_historical_var_and_es() {
+        // 20 retornos: el peor 10% son los 2 peores
+        let mut returns = vec![0.01; 18];
+        returns.push(-0.10);
+        returns.push(-0.20);
+
+        let var = var(&returns, 0.9, TailMethod::Historical).unwrap();
+        let es = expected_shortfall(&returns, 0.9, TailMethod::Historical).unwrap();
+
+        assert!((var - 0.10).abs() < 1e-12);
+        assert!((es - 0.15).abs() < 1e-12);
+
+        // el 5% de 100 son exactamente 5, no 6
+        let mut returns = vec![0.01; 94];
+        returns.extend([-0.05, -0.04, -0.03, -0.02, -0.01, -0.5]);
+        let var = super::var(&returns, 0.95, TailMethod::Historical).unwrap();
+        assert!((var - 0.02).abs() < 1e-12, "{var}");
+    }
+
+    #[test]
+    fn test_parametric_var_matches_normal_quantile() {
+        // media 0
+        let returns = [-1.0, 1.0, -1.0, 1.0];
+        let sd = std_dev(&returns).unwrap();
+
+        let var = var(&returns, 0.95, TailMethod::Parametric).unwrap();
+        assert!((var - 1.644_853_6 * sd).abs() < 1e-6);
+
+        let es = expected_shortfall(&returns, 0.95, TailMethod::Parametric).unwrap();
+        assert!(es > var);
+    }
+
+    #[test]
+    fn test_portfolio_returns() {
+        let returns = portfolio_returns(&[0.5, 0.5], &[vec![0.1, -0.1], vec![0.0, 0.1]]).unwrap();
+        assert_eq!(returns, vec![0.05, 0.0]);
+
+        assert_eq!(portfolio_returns(&[1.0], &[vec![0.1], vec![0.2]]), None);
+    }
+
+    #[test]
+    fn test_covariance_matrix_and_correlations() {
+        let matrix = covariance_matrix(&[
+            ("A", vec![0.01, 0.02, 0.03]),
+            ("B", vec![0.02, 0.04, 0.06]),
+            ("C", vec![0.03, 0.02, 0.01]),
+        ])
+        .unwrap();
+
+        assert!((matrix.variance("A").unwrap() - 0.0001).abs() < 1e-12);
+        assert!((matrix.covariance("A", "B").unwrap() - 0.0002).abs() < 1e-12);
+        assert!((matrix.correlation("A", "B").unwrap() - 1.0).abs() < 1e-9);
+        assert!((matrix.correlation("A", "C").unwrap() + 1.0).abs() < 1e-9);
+        assert_eq!(matrix.correlations().len(), 3);
+
+        // A y C se cancelan perfectamente
+        let variance = matrix.portfolio_variance(&[0.5, 0.0, 0.5]).unwrap();
+        assert!(variance.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_covariance_rejects_misaligned_series() {
+        assert_eq!(
+            covariance_matrix(&[("A", vec![0.1, 0.2]), ("B", vec![0.1])]),
+            None
+        );
+        assert_eq!(correlation(&[0.1, 0.1], &[0.1, 0.2]), None);
+    }
+
+    #[test]
+    fn test_beta_regression() {
+        let benchmark = [0.01, -0.02, 0.03, 0.00];
+        let leveraged: Vec<f64> = benchmark.iter().map(|r| 0.001 + 2.0 * r).collect();
+
+        let regression = regress(&leveraged, &benchmark).unwrap();
+        assert!((regression.beta - 2.0).abs() < 1e-9);
+        assert!((regression.alpha - 0.001).abs() < 1e-9);
+        assert!((regression.r_squared - 1.0).abs() < 1e-9);
+
+        assert_eq!(beta(&benchmark, &[0.0; 4]), None);
+    }
+
+    #[test]
+    fn test_portfolio_beta_is_weighted_average() {
+        let benchmark = vec![0.01, -0.02, 0.03, 0.00];
+        let double: Vec<f64> = benchmark.iter().map(|r| 2.0 * r).collect();
+        let cash = vec![0.0; 4];
+
+        let exposure =
+            beta_exposure(&[0.5, 0.5], &[("LEV", double), ("CASH", cash)], &benchmark).unwrap();
+
+        assert!((exposure.holdings[0].1.beta - 2.0).abs() < 1e-9);
+        assert!(exposure.holdings[1].1.beta.abs() < 1e-9);
+        assert!((exposure.portfolio.beta - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        assert_eq!(var(&[], 0.95, TailMethod::Historical), None);
+        assert_eq!(var(&[0.1], 1.5, TailMethod::Historical), None);
+        assert_eq!(var(&[0.1], 0.95, TailMethod::Parametric), None);
+    }
+}
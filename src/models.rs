@@ -0,0 +1,198 @@
+//! Portafolios modelo.
+//!
+//! Armar un `PortfolioTarget` a mano es facil de equivocar, asi que aca dejo algunos presets
+//! conocidos. Los presets no saben de tickers: hablan de roles ("acciones nacionales", "bonos de
+//! largo plazo", etc) y es el usuario quien decide que instrumento cumple cada rol.
+
+use crate::Map;
+use crate::error::TargetError;
+use crate::{PortfolioTarget, Stock};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// Rol que cumple un instrumento dentro de un modelo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Role {
+    DomesticEquity,
+    InternationalEquity,
+    Bonds,
+    LongTermBonds,
+    IntermediateBonds,
+    Gold,
+    Commodities,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Role::DomesticEquity => "domestic-equity",
+            Role::InternationalEquity => "international-equity",
+            Role::Bonds => "bonds",
+            Role::LongTermBonds => "long-term-bonds",
+            Role::IntermediateBonds => "intermediate-bonds",
+            Role::Gold => "gold",
+            Role::Commodities => "commodities",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Asigna a cada rol el instrumento que lo representa.
+#[derive(Debug, Clone, Default)]
+pub struct ModelMapping {
+    roles: Map<Role, Stock>,
+}
+
+impl ModelMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, role: Role, stock: Stock) -> Self {
+        self.roles.insert(role, stock);
+        self
+    }
+
+    pub fn get(&self, role: Role) -> Option<&Stock> {
+        self.roles.get(&role)
+    }
+}
+
+/// Presets parametrizables de asignacion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Model {
+    /// Acciones y bonos; el clasico 60/40 es `equity = 60`.
+    StocksBonds { equity: Decimal },
+
+    /// Acciones nacionales, internacionales y bonos (el "three-fund portfolio").
+    ThreeFund {
+        domestic: Decimal,
+        international: Decimal,
+        bonds: Decimal,
+    },
+
+    /// Version simplificada del "all weather": 30% acciones, 40% bonos largos, 15% bonos
+    /// intermedios, 7.5% oro y 7.5% commodities.
+    AllWeather,
+}
+
+impl Model {
+    pub fn sixty_forty() -> Self {
+        Model::StocksBonds { equity: dec!(60) }
+    }
+
+    /// Variante comun del three-fund: 48% nacional, 32% internacional, 20% bonos.
+    pub fn three_fund() -> Self {
+        Model::ThreeFund {
+            domestic: dec!(48),
+            international: dec!(32),
+            bonds: dec!(20),
+        }
+    }
+
+    /// Proporcion objetivo de cada rol.
+    pub fn weights(&self) -> Vec<(Decimal, Role)> {
+        match *self {
+            Model::StocksBonds { equity } => vec![
+                (equity, Role::DomesticEquity),
+                (dec!(100) - equity, Role::Bonds),
+            ],
+            Model::ThreeFund {
+                domestic,
+                international,
+                bonds,
+            } => vec![
+                (domestic, Role::DomesticEquity),
+                (international, Role::InternationalEquity),
+                (bonds, Role::Bonds),
+            ],
+            Model::AllWeather => vec![
+                (dec!(30), Role::DomesticEquity),
+                (dec!(40), Role::LongTermBonds),
+                (dec!(15), Role::IntermediateBonds),
+                (dec!(7.5), Role::Gold),
+                (dec!(7.5), Role::Commodities),
+            ],
+        }
+    }
+
+    /// Construye el objetivo usando los instrumentos del mapping.
+    ///
+    /// Si dos roles apuntan al mismo ticker (p. ej. un solo ETF para bonos largos e
+    /// intermedios) sus proporciones se suman. Las proporciones de 0% se omiten, asi un 100/0
+    /// es valido. El resultado pasa por la misma validacion de `try_from_vec`.
+    pub fn build(&self, mapping: &ModelMapping) -> Result<PortfolioTarget, TargetError> {
+        let mut targets: Vec<(Decimal, Stock)> = Vec::new();
+
+        for (weight, role) in self.weights() {
+            if weight.is_zero() {
+                continue;
+            }
+
+            let stock = mapping.get(role).ok_or(TargetError::MissingRole(role))?;
+
+            match targets.iter_mut().find(|(_, s)| s.name() == stock.name()) {
+                Some((existing, _)) => *existing += weight,
+                None => targets.push((weight, stock.clone())),
+            }
+        }
+
+        PortfolioTarget::try_from_vec(targets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping() -> ModelMapping {
+        ModelMapping::new()
+            .with(Role::DomesticEquity, Stock::new("VTI", dec!(250)))
+            .with(Role::InternationalEquity, Stock::new("VXUS", dec!(60)))
+            .with(Role::Bonds, Stock::new("BND", dec!(72)))
+            .with(Role::LongTermBonds, Stock::new("BND", dec!(72)))
+            .with(Role::IntermediateBonds, Stock::new("BND", dec!(72)))
+            .with(Role::Gold, Stock::new("GLD", dec!(190)))
+            .with(Role::Commodities, Stock::new("DBC", dec!(22)))
+    }
+
+    #[test]
+    fn test_sixty_forty() {
+        let target = Model::sixty_forty().build(&mapping()).unwrap();
+        let weights: Vec<_> = target
+            .targets()
+            .iter()
+            .map(|(w, s)| (*w, s.name()))
+            .collect();
+
+        assert_eq!(weights, vec![(dec!(60), "VTI"), (dec!(40), "BND")]);
+    }
+
+    #[test]
+    fn test_all_weather_merges_roles_sharing_a_ticker() {
+        let target = Model::AllWeather.build(&mapping()).unwrap();
+        let bonds = target
+            .targets()
+            .iter()
+            .find(|(_, s)| s.name() == "BND")
+            .unwrap();
+
+        assert_eq!(bonds.0, dec!(55));
+        assert_eq!(target.targets().len(), 4);
+    }
+
+    #[test]
+    fn test_missing_role_and_invalid_parameters() {
+        let incomplete = ModelMapping::new().with(Role::DomesticEquity, Stock::new("VTI", dec!(1)));
+        assert_eq!(
+            Model::three_fund().build(&incomplete).unwrap_err(),
+            TargetError::MissingRole(Role::InternationalEquity)
+        );
+
+        let broken = Model::StocksBonds { equity: dec!(120) };
+        assert!(broken.build(&mapping()).is_err());
+    }
+}
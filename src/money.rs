@@ -0,0 +1,227 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Monedas soportadas por la libreria.
+///
+/// Cada moneda sabe cuantos decimales se usan al mostrarla; por ejemplo, el peso chileno no
+/// utiliza decimales, mientras que el dolar usa dos (centavos).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Currency {
+    Clp,
+    Usd,
+    Eur,
+
+    /// Unidad de Fomento: unidad de cuenta chilena reajustada por inflacion (ver `uf`). No se
+    /// tiene caja en UF; lo que cotiza en UF se paga en pesos.
+    Clf,
+}
+
+impl Currency {
+    /// Codigo ISO 4217 de la moneda.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Clp => "CLP",
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Clf => "CLF",
+        }
+    }
+
+    /// Inversa de `code`.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "CLP" => Some(Currency::Clp),
+            "USD" => Some(Currency::Usd),
+            "EUR" => Some(Currency::Eur),
+            "CLF" | "UF" => Some(Currency::Clf),
+            _ => None,
+        }
+    }
+
+    /// Cantidad de decimales con los que se muestra un monto en esta moneda.
+    pub fn decimals(&self) -> u32 {
+        match self {
+            Currency::Clp => 0,
+            Currency::Usd | Currency::Eur => 2,
+            Currency::Clf => 4,
+        }
+    }
+
+    /// Simbolo de la moneda segun el locale; el "$" es ambiguo entre CLP y USD, asi que la moneda
+    /// extranjera se desambigua con un prefijo.
+    pub fn symbol(&self, locale: Locale) -> &'static str {
+        match (self, locale) {
+            (Currency::Clp, Locale::EsCl) => "$",
+            (Currency::Clp, Locale::EnUs) => "CLP$",
+            (Currency::Usd, Locale::EsCl) => "US$",
+            (Currency::Usd, Locale::EnUs) => "$",
+            (Currency::Eur, _) => "€",
+            (Currency::Clf, _) => "UF ",
+        }
+    }
+
+    /// Moneda en que se paga lo que esta denominado en esta: pesos para la UF, la misma para las
+    /// demas.
+    pub fn settlement(&self) -> Currency {
+        match self {
+            Currency::Clf => Currency::Clp,
+            currency => *currency,
+        }
+    }
+
+    /// Locale con el que se muestra la moneda cuando no se especifica ninguno.
+    pub fn default_locale(&self) -> Locale {
+        match self {
+            Currency::Clp | Currency::Eur | Currency::Clf => Locale::EsCl,
+            Currency::Usd => Locale::EnUs,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Convenciones regionales para mostrar numeros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    /// Español de Chile: `$1.234.567,89`
+    #[default]
+    EsCl,
+    /// Ingles de EEUU: `$1,234,567.89`
+    EnUs,
+}
+
+impl Locale {
+    pub fn thousands_separator(&self) -> char {
+        match self {
+            Locale::EsCl => '.',
+            Locale::EnUs => ',',
+        }
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        match self {
+            Locale::EsCl => ',',
+            Locale::EnUs => '.',
+        }
+    }
+
+    /// Formatea un numero con los separadores del locale y una cantidad fija de decimales.
+    ///
+    /// El redondeo es "half away from zero", que es el que la gente espera ver en una cartola.
+    pub fn format_decimal(&self, value: Decimal, decimals: u32) -> String {
+        let rounded =
+            value.round_dp_with_strategy(decimals, RoundingStrategy::MidpointAwayFromZero);
+        let digits = rounded.abs().to_string();
+
+        let (int_part, frac_part) = match digits.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, frac_part),
+            None => (digits.as_str(), ""),
+        };
+
+        let mut out = String::new();
+        if rounded.is_sign_negative() && !rounded.is_zero() {
+            out.push('-');
+        }
+
+        for (i, c) in int_part.chars().enumerate() {
+            if i > 0 && (int_part.len() - i) % 3 == 0 {
+                out.push(self.thousands_separator());
+            }
+            out.push(c);
+        }
+
+        if decimals > 0 {
+            out.push(self.decimal_separator());
+            out.push_str(frac_part);
+            for _ in frac_part.len()..decimals as usize {
+                out.push('0');
+            }
+        }
+
+        out
+    }
+}
+
+/// Un monto de dinero asociado a una moneda.
+///
+/// No se permite operar directamente entre montos de distinta moneda; para eso se necesita una
+/// conversion explicita.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+    amount: Decimal,
+    currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(Decimal::ZERO, currency)
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Muestra el monto segun las convenciones del locale; el signo va antes del simbolo.
+    pub fn format(&self, locale: Locale) -> String {
+        let number = locale.format_decimal(self.amount, self.currency.decimals());
+        let symbol = self.currency.symbol(locale);
+
+        match number.strip_prefix('-') {
+            Some(abs) => format!("-{symbol}{abs}"),
+            None => format!("{symbol}{number}"),
+        }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(self.currency.default_locale()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_clp_has_no_decimals() {
+        let money = Money::new(dec!(1234567.6), Currency::Clp);
+        assert_eq!(money.to_string(), "$1.234.568");
+    }
+
+    #[test]
+    fn test_usd_has_two_decimals() {
+        let money = Money::new(dec!(1234567.5), Currency::Usd);
+        assert_eq!(money.to_string(), "$1,234,567.50");
+        assert_eq!(money.format(Locale::EsCl), "US$1.234.567,50");
+    }
+
+    #[test]
+    fn test_negative_and_small_amounts() {
+        assert_eq!(
+            Money::new(dec!(-5.005), Currency::Usd).to_string(),
+            "-$5.01"
+        );
+        assert_eq!(Money::new(dec!(999), Currency::Clp).to_string(), "$999");
+        assert_eq!(Money::zero(Currency::Usd).to_string(), "$0.00");
+        assert_eq!(
+            Money::new(dec!(1500.25), Currency::Clf).to_string(),
+            "UF 1.500,2500"
+        );
+    }
+}
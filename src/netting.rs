@@ -0,0 +1,155 @@
+//! Neteo de ordenes entre sleeves o cuentas.
+//!
+//! Cuando una parte vende un ticker y otra lo compra, mandar las dos ordenes al broker paga dos
+//! veces comisiones y spread por nada. `net` cruza las ordenes opuestas del mismo ticker, deja
+//! solo la diferencia como orden externa y anota cada cruce como un traspaso interno de unidades
+//! de una parte a otra.
+
+use crate::execution::{Order, Side};
+use crate::i18n::{Language, Localize, language};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Unidades que pasan de una parte a otra sin salir al mercado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InternalTransfer {
+    pub ticker: String,
+    pub from: String,
+    pub to: String,
+    pub units: usize,
+}
+
+/// Resultado del neteo.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NettedOrders {
+    /// Lo que hay que mandar al broker, ventas primero y por ticker.
+    pub external: Vec<Order>,
+    pub transfers: Vec<InternalTransfer>,
+}
+
+impl NettedOrders {
+    /// Unidades que no se transan gracias al neteo (cada traspaso ahorra una compra y una venta).
+    pub fn units_saved(&self) -> usize {
+        self.transfers.iter().map(|t| t.units * 2).sum()
+    }
+}
+
+/// Unidades de cada parte en un lado de un ticker.
+type Parts<'a> = Vec<(&'a str, usize)>;
+
+/// Netea las ordenes de cada parte (un sleeve, una cuenta), con las partes por nombre. Los
+/// cruces se hacen en orden de nombre, de vendedores a compradores.
+pub fn net(orders: &BTreeMap<String, Vec<Order>>) -> NettedOrders {
+    // por ticker: quien vende y quien compra, con sus unidades
+    let mut sides: BTreeMap<&str, (Parts, Parts)> = BTreeMap::new();
+    for (part, part_orders) in orders {
+        for order in part_orders {
+            let (sellers, buyers) = sides.entry(&order.ticker).or_default();
+            match order.side {
+                Side::Sell => sellers.push((part, order.units)),
+                Side::Buy => buyers.push((part, order.units)),
+            }
+        }
+    }
+
+    let mut netted = NettedOrders::default();
+    for (ticker, (mut sellers, mut buyers)) in sides {
+        let (mut s, mut b) = (0, 0);
+        while s < sellers.len() && b < buyers.len() {
+            let units = sellers[s].1.min(buyers[b].1);
+            if units > 0 && sellers[s].0 != buyers[b].0 {
+                netted.transfers.push(InternalTransfer {
+                    ticker: ticker.into(),
+                    from: sellers[s].0.into(),
+                    to: buyers[b].0.into(),
+                    units,
+                });
+            }
+            sellers[s].1 -= units;
+            buyers[b].1 -= units;
+            if sellers[s].1 == 0 {
+                s += 1;
+            }
+            if buyers[b].1 == 0 {
+                b += 1;
+            }
+        }
+
+        let sold: usize = sellers.iter().map(|(_, units)| units).sum();
+        let bought: usize = buyers.iter().map(|(_, units)| units).sum();
+        if sold > 0 {
+            netted.external.push(Order::sell(ticker, sold));
+        }
+        if bought > 0 {
+            netted.external.push(Order::buy(ticker, bought));
+        }
+    }
+
+    netted
+        .external
+        .sort_by(|a, b| a.side.cmp(&b.side).then(a.ticker.cmp(&b.ticker)));
+    netted
+}
+
+impl Localize for NettedOrders {
+    fn localize(&self, language: Language) -> String {
+        let orders = self.external.iter().map(|o| match (o.side, language) {
+            (Side::Buy, Language::Es) => format!("comprar {} x{}", o.ticker, o.units),
+            (Side::Buy, Language::En) => format!("buy {} x{}", o.ticker, o.units),
+            (Side::Sell, Language::Es) => format!("vender {} x{}", o.ticker, o.units),
+            (Side::Sell, Language::En) => format!("sell {} x{}", o.ticker, o.units),
+        });
+        let transfers = self.transfers.iter().map(|t| match language {
+            Language::Es => format!(
+                "traspasar {} x{} de {} a {}",
+                t.ticker, t.units, t.from, t.to
+            ),
+            Language::En => format!("move {} x{} from {} to {}", t.ticker, t.units, t.from, t.to),
+        });
+        orders.chain(transfers).collect::<Vec<_>>().join("\n")
+    }
+}
+
+impl fmt::Display for NettedOrders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_opposing_orders_become_transfers() {
+        let orders = BTreeMap::from([
+            (
+                "Ana".into(),
+                vec![Order::sell("VOO", 5), Order::buy("BND", 2)],
+            ),
+            ("Beto".into(), vec![Order::buy("VOO", 3)]),
+            ("Conjunta".into(), vec![Order::buy("VOO", 4)]),
+        ]);
+
+        let netted = net(&orders);
+        assert_eq!(
+            netted.external,
+            vec![Order::buy("BND", 2), Order::buy("VOO", 2)]
+        );
+        assert_eq!(netted.transfers.len(), 2);
+        assert_eq!(
+            netted.transfers[1],
+            InternalTransfer {
+                ticker: "VOO".into(),
+                from: "Ana".into(),
+                to: "Conjunta".into(),
+                units: 2,
+            }
+        );
+        assert_eq!(netted.units_saved(), 10);
+    }
+}
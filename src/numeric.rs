@@ -0,0 +1,183 @@
+//! Backend numerico de los algoritmos.
+//!
+//! La contabilidad se hace en `Decimal`, pero las simulaciones con millones de caminos no
+//! necesitan 28 digitos y si necesitan velocidad. `Numeric` abstrae lo que usan los algoritmos
+//! (aritmetica, raiz, exponencial, logaritmo) para que cada uno elija: `Decimal` para cuadrar al
+//! centavo, `f64` para simular rapido. Las funciones de conversion permiten pasar de un mundo al
+//! otro en los bordes (p. ej. valorizar en `Decimal`, simular en `f64`).
+//!
+//! `f64` solo implementa `Numeric` con la feature `std`, porque `exp`, `ln` y `sqrt` de `f64`
+//! vienen de la libreria estandar.
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::iter::Sum;
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+use rust_decimal::MathematicalOps;
+use rust_decimal::prelude::*;
+
+/// Numero con el que pueden trabajar los algoritmos genericos.
+pub trait Numeric:
+    Copy
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + Sum
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn from_usize(n: usize) -> Self;
+
+    /// `None` si el numero no cabe (p. ej. un `f64` infinito en `Decimal`).
+    fn from_f64(x: f64) -> Option<Self>;
+    fn from_decimal(x: Decimal) -> Option<Self>;
+
+    fn to_f64(self) -> f64;
+    fn to_decimal(self) -> Option<Decimal>;
+
+    /// `None` para numeros negativos.
+    fn sqrt(self) -> Option<Self>;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn floor(self) -> Self;
+
+    /// Convierte a otro backend pasando por el que preserve mas precision.
+    fn convert<M: Numeric>(self) -> Option<M> {
+        match self.to_decimal() {
+            Some(x) => M::from_decimal(x),
+            None => M::from_f64(self.to_f64()),
+        }
+    }
+}
+
+impl Numeric for Decimal {
+    const ZERO: Self = Decimal::ZERO;
+    const ONE: Self = Decimal::ONE;
+
+    fn from_usize(n: usize) -> Self {
+        Decimal::from(n)
+    }
+
+    fn from_f64(x: f64) -> Option<Self> {
+        <Decimal as FromPrimitive>::from_f64(x)
+    }
+
+    fn from_decimal(x: Decimal) -> Option<Self> {
+        Some(x)
+    }
+
+    fn to_f64(self) -> f64 {
+        ToPrimitive::to_f64(&self).unwrap_or(f64::NAN)
+    }
+
+    fn to_decimal(self) -> Option<Decimal> {
+        Some(self)
+    }
+
+    fn sqrt(self) -> Option<Self> {
+        MathematicalOps::sqrt(&self)
+    }
+
+    fn exp(self) -> Self {
+        MathematicalOps::exp(&self)
+    }
+
+    fn ln(self) -> Self {
+        MathematicalOps::ln(&self)
+    }
+
+    fn floor(self) -> Self {
+        Decimal::floor(&self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Numeric for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_usize(n: usize) -> Self {
+        n as f64
+    }
+
+    fn from_f64(x: f64) -> Option<Self> {
+        Some(x)
+    }
+
+    fn from_decimal(x: Decimal) -> Option<Self> {
+        ToPrimitive::to_f64(&x)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn to_decimal(self) -> Option<Decimal> {
+        <Decimal as FromPrimitive>::from_f64(self)
+    }
+
+    fn sqrt(self) -> Option<Self> {
+        (self >= 0.0).then(|| f64::sqrt(self))
+    }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        f64::ln(self)
+    }
+
+    fn floor(self) -> Self {
+        f64::floor(self)
+    }
+}
+
+/// Unidades (fraccionales) de un activo que corresponden a `weight` por ciento de `total` a
+/// precio `price`. Es el calculo base del rebalanceo.
+pub fn target_units<N: Numeric>(total: N, weight: N, price: N) -> N {
+    total * (weight / N::from_usize(100)) / price
+}
+
+/// Pasa una serie a `f64`; los valores que no se pueden representar quedan como `NaN`.
+pub fn to_f64_vec<N: Numeric>(values: &[N]) -> Vec<f64> {
+    values.iter().map(|x| x.to_f64()).collect()
+}
+
+/// Pasa una serie a `Decimal`; `None` si algun valor no cabe (infinito, `NaN`, demasiado grande).
+pub fn to_decimal_vec<N: Numeric>(values: &[N]) -> Option<Vec<Decimal>> {
+    values.iter().map(|x| x.to_decimal()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_same_algorithm_in_both_backends() {
+        assert_eq!(target_units(dec!(1000), dec!(40), dec!(8)), dec!(50));
+        assert_eq!(target_units(1000.0, 40.0, 8.0), 50.0);
+        assert_eq!(Numeric::sqrt(dec!(2.25)), Some(dec!(1.5)));
+        assert_eq!(Numeric::sqrt(-1.0), None);
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(to_f64_vec(&[dec!(0.5), dec!(-2)]), vec![0.5, -2.0]);
+        assert_eq!(
+            to_decimal_vec(&[0.25, 3.0]),
+            Some(vec![dec!(0.25), dec!(3)])
+        );
+        assert_eq!(to_decimal_vec(&[f64::INFINITY]), None);
+        assert_eq!(dec!(1.5).convert::<f64>(), Some(1.5));
+    }
+}
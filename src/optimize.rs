@@ -0,0 +1,1051 @@
+//! Generacion de objetivos a partir de estimaciones de riesgo y retorno.
+//!
+//! Hasta aca el crate solo consumia un `PortfolioTarget`; este modulo propone uno. A partir de
+//! retornos esperados y una matriz de covarianzas (ver `metrics::covariance_matrix`),
+//! `MeanVariance` busca un punto de la frontera eficiente: el de mayor Sharpe o el de mayor
+//! retorno para una volatilidad objetivo, respetando limites de peso por activo.
+//! `RiskBudgeting` en cambio reparte el riesgo, y solo necesita la covarianza. `Kelly` dimensiona
+//! cada posicion por separado segun el criterio de Kelly. `BlackLitterman` mezcla los retornos
+//! implicitos en los pesos de mercado con vistas propias y entrega retornos esperados para
+//! `MeanVariance`.
+//!
+//! Como `metrics`, todo se calcula en `f64` (pesos fraccionales, retornos anuales); solo el
+//! objetivo final se pasa a `Decimal`, en % con dos decimales.
+
+use crate::error::TargetError;
+use crate::i18n::{Language, Localize, language};
+use crate::metrics::CovarianceMatrix;
+use crate::{Allocation, PortfolioTarget, Stock};
+use rust_decimal::prelude::*;
+use std::fmt;
+
+/// Errores al optimizar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptimizeError {
+    /// Un ticker de la matriz de covarianzas no tiene retorno esperado (o al reves).
+    MissingAsset(String),
+
+    /// Los limites no permiten pesos que sumen 100% (p. ej. minimos que suman mas de 100%).
+    InfeasibleBounds,
+
+    /// Ni el portafolio de minima varianza llega tan abajo; trae la minima alcanzable.
+    VolatilityUnreachable(f64),
+
+    /// Las vistas son redundantes o contradictorias entre si (p. ej. la misma vista dos veces
+    /// con confianza total).
+    SingularViews,
+
+    /// No se pudo construir el objetivo con los pesos resultantes.
+    Target(TargetError),
+}
+
+impl Localize for OptimizeError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            OptimizeError::MissingAsset(ticker) => match language {
+                Language::Es => format!("Falta el retorno esperado o la covarianza de {ticker}."),
+                Language::En => format!("Missing expected return or covariance for {ticker}."),
+            },
+            OptimizeError::InfeasibleBounds => match language {
+                Language::Es => "Los limites de peso no permiten sumar 100%.".into(),
+                Language::En => "Weight bounds cannot add up to 100%.".into(),
+            },
+            OptimizeError::VolatilityUnreachable(minimum) => {
+                let minimum = (minimum * 100.0 * 100.0).round() / 100.0;
+                match language {
+                    Language::Es => {
+                        format!("La volatilidad minima alcanzable es {minimum}%.")
+                    }
+                    Language::En => format!("The lowest reachable volatility is {minimum}%."),
+                }
+            }
+            OptimizeError::SingularViews => match language {
+                Language::Es => "Las vistas son redundantes o contradictorias.".into(),
+                Language::En => "The views are redundant or contradictory.".into(),
+            },
+            OptimizeError::Target(error) => error.localize(language),
+        }
+    }
+}
+
+impl fmt::Display for OptimizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for OptimizeError {}
+
+impl From<TargetError> for OptimizeError {
+    fn from(error: TargetError) -> Self {
+        OptimizeError::Target(error)
+    }
+}
+
+/// Un punto de la frontera eficiente.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontierPoint {
+    pub target: PortfolioTarget,
+
+    /// Pesos fraccionales sin redondear, en el orden de la matriz de covarianzas.
+    pub weights: Vec<(String, f64)>,
+    pub expected_return: f64,
+    pub volatility: f64,
+}
+
+impl FrontierPoint {
+    /// Sharpe contra una tasa libre de riesgo; `None` si la volatilidad es cero.
+    pub fn sharpe(&self, risk_free: f64) -> Option<f64> {
+        (self.volatility > 0.0).then(|| (self.expected_return - risk_free) / self.volatility)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Asset {
+    stock: Stock,
+    expected_return: f64,
+    min: f64,
+    max: f64,
+}
+
+/// Optimizador media-varianza (Markowitz) con limites por activo.
+#[derive(Debug, Clone)]
+pub struct MeanVariance {
+    covariance: CovarianceMatrix,
+    assets: Vec<Asset>,
+    risk_free: f64,
+}
+
+impl MeanVariance {
+    pub fn new(covariance: CovarianceMatrix) -> Self {
+        Self {
+            covariance,
+            assets: Vec::new(),
+            risk_free: 0.0,
+        }
+    }
+
+    /// Agrega un activo con su retorno esperado anual (fraccional, `0.07` = 7%). Sin limites
+    /// puede ir de 0% a 100%.
+    pub fn with_asset(mut self, stock: Stock, expected_return: f64) -> Self {
+        self.assets.push(Asset {
+            stock,
+            expected_return,
+            min: 0.0,
+            max: 1.0,
+        });
+        self
+    }
+
+    /// Limites de peso de un ticker, en % como en `PortfolioTarget`.
+    pub fn with_bounds(mut self, ticker: &str, min: Decimal, max: Decimal) -> Self {
+        if let Some(asset) = self.assets.iter_mut().find(|a| a.stock.name() == ticker) {
+            asset.min = min.to_f64().unwrap_or(0.0) / 100.0;
+            asset.max = max.to_f64().unwrap_or(100.0) / 100.0;
+        }
+        self
+    }
+
+    /// Tasa libre de riesgo para el Sharpe (fraccional, anual).
+    pub fn with_risk_free(mut self, rate: f64) -> Self {
+        self.risk_free = rate;
+        self
+    }
+
+    /// El punto de la frontera con mayor Sharpe.
+    pub fn max_sharpe(&self) -> Result<FrontierPoint, OptimizeError> {
+        let problem = self.problem()?;
+        let best = aversions()
+            .map(|aversion| problem.solve(aversion))
+            .max_by(|a, b| {
+                let sharpe = |w: &[f64]| {
+                    let vol = problem.volatility(w);
+                    if vol > 0.0 {
+                        (problem.expected_return(w) - self.risk_free) / vol
+                    } else {
+                        f64::NEG_INFINITY
+                    }
+                };
+                sharpe(a).total_cmp(&sharpe(b))
+            })
+            .unwrap_or_default();
+
+        self.point(&problem, best)
+    }
+
+    /// El punto de la frontera con mayor retorno cuya volatilidad no pasa de `volatility`
+    /// (fraccional, anual).
+    pub fn target_volatility(&self, volatility: f64) -> Result<FrontierPoint, OptimizeError> {
+        let problem = self.problem()?;
+
+        // mientras mas aversion al riesgo, menos volatilidad: se busca la menor aversion que
+        // cumple con el objetivo
+        let (mut low, mut high) = (MIN_AVERSION, MAX_AVERSION);
+        let safest = problem.solve(high);
+        if problem.volatility(&safest) > volatility + TOLERANCE {
+            return Err(OptimizeError::VolatilityUnreachable(
+                problem.volatility(&safest),
+            ));
+        }
+        if problem.volatility(&problem.solve(low)) <= volatility {
+            return self.point(&problem, problem.solve(low));
+        }
+
+        let mut best = safest;
+        for _ in 0..60 {
+            let middle = (low * high).sqrt();
+            let weights = problem.solve(middle);
+            if problem.volatility(&weights) <= volatility {
+                high = middle;
+                best = weights;
+            } else {
+                low = middle;
+            }
+        }
+
+        self.point(&problem, best)
+    }
+
+    fn problem(&self) -> Result<Problem, OptimizeError> {
+        let tickers = self.covariance.tickers();
+        let mut assets = Vec::with_capacity(tickers.len());
+        for ticker in tickers {
+            let asset = self
+                .assets
+                .iter()
+                .find(|a| a.stock.name() == ticker)
+                .ok_or_else(|| OptimizeError::MissingAsset(ticker.clone()))?;
+            assets.push(asset.clone());
+        }
+        if let Some(extra) = self
+            .assets
+            .iter()
+            .find(|a| !tickers.contains(&a.stock.name().to_string()))
+        {
+            return Err(OptimizeError::MissingAsset(extra.stock.name().into()));
+        }
+
+        // un minimo sobre el maximo haria entrar en panico a `Problem::project`
+        if assets.iter().any(|a| a.min > a.max) {
+            return Err(OptimizeError::InfeasibleBounds);
+        }
+        let (min, max): (f64, f64) = assets
+            .iter()
+            .fold((0.0, 0.0), |(min, max), a| (min + a.min, max + a.max));
+        if assets.is_empty() || min > 1.0 + TOLERANCE || max < 1.0 - TOLERANCE {
+            return Err(OptimizeError::InfeasibleBounds);
+        }
+
+        Ok(Problem {
+            covariance: self.covariance.values().to_vec(),
+            assets,
+        })
+    }
+
+    fn point(&self, problem: &Problem, weights: Vec<f64>) -> Result<FrontierPoint, OptimizeError> {
+        let stocks: Vec<(Stock, f64)> = problem
+            .assets
+            .iter()
+            .zip(&weights)
+            .map(|(asset, w)| (asset.stock.clone(), *w))
+            .collect();
+
+        Ok(FrontierPoint {
+            target: to_target(&stocks)?,
+            expected_return: problem.expected_return(&weights),
+            volatility: problem.volatility(&weights),
+            weights: problem
+                .assets
+                .iter()
+                .map(|a| a.stock.name().to_string())
+                .zip(weights)
+                .collect(),
+        })
+    }
+}
+
+/// Aporte de un activo al riesgo del portafolio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskContribution {
+    pub ticker: String,
+
+    /// Peso fraccional sin redondear.
+    pub weight: f64,
+
+    /// Fraccion de la varianza total que aporta: `w_i (Σw)_i / wᵀΣw`. Suman 1.
+    pub contribution: f64,
+}
+
+/// Resultado de `RiskBudgeting::solve`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskAllocation {
+    pub target: PortfolioTarget,
+    pub contributions: Vec<RiskContribution>,
+    pub volatility: f64,
+}
+
+/// Asignacion por presupuesto de riesgo: pesos tales que cada activo aporta a la varianza del
+/// portafolio lo que se le asigno. Sin presupuestos explicitos todos aportan lo mismo (equal
+/// risk contribution, o "risk parity").
+///
+/// No necesita retornos esperados, que son la estimacion mas ruidosa; solo la matriz de
+/// covarianzas.
+#[derive(Debug, Clone)]
+pub struct RiskBudgeting {
+    covariance: CovarianceMatrix,
+    assets: Vec<(Stock, f64)>,
+}
+
+impl RiskBudgeting {
+    pub fn new(covariance: CovarianceMatrix) -> Self {
+        Self {
+            covariance,
+            assets: Vec::new(),
+        }
+    }
+
+    /// Agrega un activo con presupuesto 1 (relativo a los demas).
+    pub fn with_asset(mut self, stock: Stock) -> Self {
+        self.assets.push((stock, 1.0));
+        self
+    }
+
+    /// Presupuesto relativo de un ticker: con presupuestos 2 y 1, el primero aporta dos tercios
+    /// del riesgo.
+    pub fn with_budget(mut self, ticker: &str, budget: f64) -> Self {
+        if let Some(asset) = self.assets.iter_mut().find(|(s, _)| s.name() == ticker) {
+            asset.1 = budget;
+        }
+        self
+    }
+
+    /// Resuelve `min ½ yᵀΣy - Σ b_i ln y_i` por descenso coordenado (cada coordenada tiene
+    /// solucion cerrada) y normaliza `y` para que sume 1. En el optimo `y_i (Σy)_i = b_i`, o
+    /// sea, cada aporte al riesgo es proporcional a su presupuesto.
+    pub fn solve(&self) -> Result<RiskAllocation, OptimizeError> {
+        let tickers = self.covariance.tickers();
+        let mut assets = Vec::with_capacity(tickers.len());
+        for ticker in tickers {
+            let (stock, budget) = self
+                .assets
+                .iter()
+                .find(|(s, _)| s.name() == ticker)
+                .ok_or_else(|| OptimizeError::MissingAsset(ticker.clone()))?;
+            assets.push((stock.clone(), *budget));
+        }
+        if let Some((extra, _)) = self
+            .assets
+            .iter()
+            .find(|(s, _)| !tickers.iter().any(|t| t == s.name()))
+        {
+            return Err(OptimizeError::MissingAsset(extra.name().into()));
+        }
+        if assets.is_empty() || assets.iter().any(|(_, b)| *b <= 0.0) {
+            return Err(OptimizeError::InfeasibleBounds);
+        }
+
+        let sigma = self.covariance.values();
+        let total_budget: f64 = assets.iter().map(|(_, b)| b).sum();
+        let budgets: Vec<f64> = assets.iter().map(|(_, b)| b / total_budget).collect();
+        let n = assets.len();
+
+        let mut y: Vec<f64> = (0..n)
+            .map(|i| 1.0 / sigma[i][i].max(TOLERANCE).sqrt())
+            .collect();
+        for _ in 0..1_000 {
+            let mut change = 0.0f64;
+            for i in 0..n {
+                let others: f64 = (0..n).filter(|&j| j != i).map(|j| sigma[i][j] * y[j]).sum();
+                let diagonal = sigma[i][i].max(TOLERANCE);
+                let next = (-others + (others * others + 4.0 * diagonal * budgets[i]).sqrt())
+                    / (2.0 * diagonal);
+                change = change.max((next - y[i]).abs());
+                y[i] = next;
+            }
+            if change < 1e-12 {
+                break;
+            }
+        }
+
+        let sum: f64 = y.iter().sum();
+        let weights: Vec<f64> = y.iter().map(|v| v / sum).collect();
+        let sigma_w: Vec<f64> = sigma
+            .iter()
+            .map(|row| row.iter().zip(&weights).map(|(c, w)| c * w).sum())
+            .collect();
+        let variance: f64 = weights.iter().zip(&sigma_w).map(|(w, sw)| w * sw).sum();
+
+        let contributions = assets
+            .iter()
+            .zip(&weights)
+            .zip(&sigma_w)
+            .map(|(((stock, _), w), sw)| RiskContribution {
+                ticker: stock.name().into(),
+                weight: *w,
+                contribution: if variance > 0.0 {
+                    w * sw / variance
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+        let stocks: Vec<(Stock, f64)> = assets
+            .into_iter()
+            .map(|(stock, _)| stock)
+            .zip(weights)
+            .collect();
+
+        Ok(RiskAllocation {
+            target: to_target(&stocks)?,
+            contributions,
+            volatility: variance.max(0.0).sqrt(),
+        })
+    }
+}
+
+/// Resultado de `Kelly::size`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KellySizing {
+    /// Objetivo con los pesos ya escalados, topados y normalizados; lo que no se invierte queda
+    /// como caja.
+    pub target: PortfolioTarget,
+
+    /// Fraccion de Kelly completa de cada activo, antes de escalar, topar o normalizar (puede
+    /// ser negativa o mayor que 1).
+    pub full_kelly: Vec<(String, f64)>,
+}
+
+/// Tamaño de posiciones segun el criterio de Kelly, activo por activo: `f = (μ - r) / σ²`.
+///
+/// Kelly completo maximiza el crecimiento esperado pero con caidas brutales si las estimaciones
+/// estan infladas (y siempre lo estan), asi que lo usual es usar una fraccion (`with_fraction`)
+/// y un tope por activo (`with_cap`). Las posiciones negativas (cortas) quedan en cero y si la
+/// suma pasa de 100% se escala para no usar apalancamiento.
+#[derive(Debug, Clone)]
+pub struct Kelly {
+    assets: Vec<(Stock, f64, f64)>,
+    fraction: f64,
+    cap: Decimal,
+    risk_free: f64,
+}
+
+impl Default for Kelly {
+    fn default() -> Self {
+        Self {
+            assets: Vec::new(),
+            fraction: 1.0,
+            cap: Decimal::ONE_HUNDRED,
+            risk_free: 0.0,
+        }
+    }
+}
+
+impl Kelly {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agrega un activo con su retorno esperado y varianza (fraccionales, anuales).
+    pub fn with_asset(mut self, stock: Stock, expected_return: f64, variance: f64) -> Self {
+        self.assets.push((stock, expected_return, variance));
+        self
+    }
+
+    /// Fraccion de Kelly a usar, p. ej. `0.5` para "medio Kelly".
+    pub fn with_fraction(mut self, fraction: f64) -> Self {
+        self.fraction = fraction;
+        self
+    }
+
+    /// Peso maximo por activo, en %; uno negativo hace fallar a `size`.
+    pub fn with_cap(mut self, cap: Decimal) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_risk_free(mut self, rate: f64) -> Self {
+        self.risk_free = rate;
+        self
+    }
+
+    /// Pesos de Kelly topados; si suman mas de 100% se escalan para quedar invertidos por
+    /// completo, y lo que se pierde al redondear va al mayor (como en `to_target`). Si no, lo que
+    /// falta queda en caja.
+    pub fn size(&self) -> Result<KellySizing, OptimizeError> {
+        if self.cap.is_sign_negative() {
+            return Err(OptimizeError::InfeasibleBounds);
+        }
+        let cap = self.cap.to_f64().unwrap_or(100.0) / 100.0;
+        let full_kelly: Vec<(String, f64)> = self
+            .assets
+            .iter()
+            .map(|(stock, mu, variance)| {
+                let f = if *variance > 0.0 {
+                    (mu - self.risk_free) / variance
+                } else {
+                    0.0
+                };
+                (stock.name().to_string(), f)
+            })
+            .collect();
+
+        let mut weights: Vec<f64> = full_kelly
+            .iter()
+            .map(|(_, f)| (f * self.fraction).clamp(0.0, cap))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        let fully_invested = total > 1.0;
+        if fully_invested {
+            weights.iter_mut().for_each(|w| *w /= total);
+        }
+
+        let mut allocations: Vec<Allocation> = self
+            .assets
+            .iter()
+            .zip(&weights)
+            .filter_map(|((stock, _, _), w)| {
+                let percent = Decimal::from_f64(w * 100.0)?.round_dp(2);
+                (percent > Decimal::ZERO).then(|| Allocation::Stock(percent, stock.clone()))
+            })
+            .collect();
+        let invested: Decimal = allocations.iter().map(Allocation::weight).sum();
+        let residual = Decimal::ONE_HUNDRED - invested;
+        if fully_invested || residual.is_sign_negative() {
+            let largest = allocations.iter_mut().max_by_key(|a| a.weight());
+            if let Some(Allocation::Stock(weight, _)) = largest {
+                *weight += residual;
+            }
+        } else if !residual.is_zero() {
+            allocations.push(Allocation::Cash(residual));
+        }
+
+        Ok(KellySizing {
+            target: PortfolioTarget::try_from_allocations(allocations)?,
+            full_kelly,
+        })
+    }
+}
+
+/// Una vista sobre el retorno esperado: de un activo o de una combinacion (p. ej. "META le gana a
+/// AAPL por 2%").
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    weights: Vec<(String, f64)>,
+    expected: f64,
+    confidence: f64,
+}
+
+impl View {
+    /// El retorno esperado de `ticker` es `expected` (fraccional, anual).
+    pub fn absolute(ticker: &str, expected: f64) -> Self {
+        Self {
+            weights: vec![(ticker.into(), 1.0)],
+            expected,
+            confidence: 0.5,
+        }
+    }
+
+    /// `outperformer` rinde `spread` mas que `underperformer` (fraccional, anual).
+    pub fn relative(outperformer: &str, underperformer: &str, spread: f64) -> Self {
+        Self {
+            weights: vec![(outperformer.into(), 1.0), (underperformer.into(), -1.0)],
+            expected: spread,
+            confidence: 0.5,
+        }
+    }
+
+    /// Confianza entre 0 y 1. Con 1 el posterior cumple la vista exactamente; con 0.5 (el valor
+    /// por omision) la incertidumbre de la vista es la misma que la del prior (`τ pΣpᵀ`, como
+    /// en He y Litterman).
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence.clamp(f64::EPSILON, 1.0);
+        self
+    }
+}
+
+/// Retornos esperados a posteriori de `BlackLitterman`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posterior {
+    /// Retornos implicitos en los pesos de mercado (el prior), en el orden de la matriz de
+    /// covarianzas.
+    pub implied: Vec<(String, f64)>,
+
+    /// Retornos esperados despues de incorporar las vistas.
+    pub expected: Vec<(String, f64)>,
+    covariance: CovarianceMatrix,
+    stocks: Vec<Stock>,
+}
+
+impl Posterior {
+    /// Un `MeanVariance` con los retornos a posteriori y la misma matriz de covarianzas, listo
+    /// para agregarle limites y optimizar.
+    pub fn optimizer(&self) -> MeanVariance {
+        self.stocks.iter().zip(&self.expected).fold(
+            MeanVariance::new(self.covariance.clone()),
+            |optimizer, (stock, (_, expected))| optimizer.with_asset(stock.clone(), *expected),
+        )
+    }
+}
+
+/// Mezcla de pesos de mercado y vistas propias al estilo Black-Litterman.
+///
+/// Los pesos de mercado (p. ej. por capitalizacion) se asumen optimos, y de ahi se despejan los
+/// retornos que los justifican (`π = δΣw`). Las vistas mueven esos retornos segun su confianza,
+/// y como el punto de partida es el mercado, un activo sin vistas no termina con un peso
+/// absurdo como suele pasar al darle retornos estimados a mano a `MeanVariance`.
+#[derive(Debug, Clone)]
+pub struct BlackLitterman {
+    covariance: CovarianceMatrix,
+    assets: Vec<(Stock, f64)>,
+    views: Vec<View>,
+    risk_aversion: f64,
+    tau: f64,
+}
+
+impl BlackLitterman {
+    pub fn new(covariance: CovarianceMatrix) -> Self {
+        Self {
+            covariance,
+            assets: Vec::new(),
+            views: Vec::new(),
+            risk_aversion: 2.5,
+            tau: 0.05,
+        }
+    }
+
+    /// Agrega un activo con su peso de mercado; los pesos se normalizan, asi que pueden ser
+    /// capitalizaciones directamente.
+    pub fn with_asset(mut self, stock: Stock, market_weight: f64) -> Self {
+        self.assets.push((stock, market_weight));
+        self
+    }
+
+    pub fn with_view(mut self, view: View) -> Self {
+        self.views.push(view);
+        self
+    }
+
+    /// Aversion al riesgo `δ` del mercado (2.5 por omision).
+    pub fn with_risk_aversion(mut self, risk_aversion: f64) -> Self {
+        self.risk_aversion = risk_aversion;
+        self
+    }
+
+    /// Escala `τ` de la incertidumbre del prior (0.05 por omision).
+    pub fn with_tau(mut self, tau: f64) -> Self {
+        self.tau = tau;
+        self
+    }
+
+    /// `μ = π + τΣPᵀ (τPΣPᵀ + Ω)⁻¹ (Q - Pπ)`, con `Ω` diagonal segun la confianza de cada vista.
+    pub fn posterior(&self) -> Result<Posterior, OptimizeError> {
+        let tickers = self.covariance.tickers();
+        let mut stocks = Vec::with_capacity(tickers.len());
+        let mut weights = Vec::with_capacity(tickers.len());
+        for ticker in tickers {
+            let (stock, weight) = self
+                .assets
+                .iter()
+                .find(|(s, _)| s.name() == ticker)
+                .ok_or_else(|| OptimizeError::MissingAsset(ticker.clone()))?;
+            stocks.push(stock.clone());
+            weights.push(weight.max(0.0));
+        }
+        if let Some((extra, _)) = self
+            .assets
+            .iter()
+            .find(|(s, _)| !tickers.contains(&s.name().to_string()))
+        {
+            return Err(OptimizeError::MissingAsset(extra.name().into()));
+        }
+
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return Err(OptimizeError::InfeasibleBounds);
+        }
+        weights.iter_mut().for_each(|w| *w /= total);
+
+        let sigma = self.covariance.values();
+        let times_sigma = |v: &[f64]| -> Vec<f64> {
+            sigma
+                .iter()
+                .map(|row| row.iter().zip(v).map(|(c, x)| c * x).sum())
+                .collect()
+        };
+        let dot = |a: &[f64], b: &[f64]| -> f64 { a.iter().zip(b).map(|(x, y)| x * y).sum() };
+
+        let implied: Vec<f64> = times_sigma(&weights)
+            .into_iter()
+            .map(|x| self.risk_aversion * x)
+            .collect();
+
+        // cada vista como fila de P sobre los tickers de la matriz
+        let mut picks: Vec<Vec<f64>> = Vec::with_capacity(self.views.len());
+        for view in &self.views {
+            let mut row = vec![0.0; tickers.len()];
+            for (ticker, weight) in &view.weights {
+                let index = tickers
+                    .iter()
+                    .position(|t| t == ticker)
+                    .ok_or_else(|| OptimizeError::MissingAsset(ticker.clone()))?;
+                row[index] += weight;
+            }
+            picks.push(row);
+        }
+
+        let sigma_picks: Vec<Vec<f64>> = picks.iter().map(|p| times_sigma(p)).collect();
+        let system: Vec<Vec<f64>> = picks
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                sigma_picks
+                    .iter()
+                    .enumerate()
+                    .map(|(j, sp)| {
+                        let prior = self.tau * dot(p, sp);
+                        if i == j {
+                            let confidence = self.views[i].confidence;
+                            prior + prior * (1.0 - confidence) / confidence
+                        } else {
+                            prior
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let surprise: Vec<f64> = picks
+            .iter()
+            .zip(&self.views)
+            .map(|(p, view)| view.expected - dot(p, &implied))
+            .collect();
+        let solution = solve_linear(system, surprise).ok_or(OptimizeError::SingularViews)?;
+
+        let expected: Vec<f64> = implied
+            .iter()
+            .enumerate()
+            .map(|(i, pi)| {
+                let shift: f64 = sigma_picks
+                    .iter()
+                    .zip(&solution)
+                    .map(|(sp, x)| sp[i] * x)
+                    .sum();
+                pi + self.tau * shift
+            })
+            .collect();
+
+        Ok(Posterior {
+            implied: tickers.iter().cloned().zip(implied).collect(),
+            expected: tickers.iter().cloned().zip(expected).collect(),
+            covariance: self.covariance.clone(),
+            stocks,
+        })
+    }
+}
+
+/// Resuelve `Ax = b` por eliminacion gaussiana con pivoteo parcial; `None` si `A` es singular.
+fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            row.iter_mut()
+                .zip(pivot_row)
+                .skip(col)
+                .for_each(|(x, p)| *x -= factor * p);
+            b[col + 1 + offset] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let rest: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - rest) / a[row][row];
+    }
+    Some(x)
+}
+
+const MIN_AVERSION: f64 = 1e-3;
+const MAX_AVERSION: f64 = 1e4;
+const TOLERANCE: f64 = 1e-9;
+
+/// Aversiones al riesgo con que se recorre la frontera, en escala logaritmica.
+fn aversions() -> impl Iterator<Item = f64> {
+    let steps = 200;
+    let ratio = (MAX_AVERSION / MIN_AVERSION).ln();
+    (0..=steps).map(move |i| MIN_AVERSION * (ratio * i as f64 / steps as f64).exp())
+}
+
+struct Problem {
+    covariance: Vec<Vec<f64>>,
+    assets: Vec<Asset>,
+}
+
+impl Problem {
+    fn expected_return(&self, weights: &[f64]) -> f64 {
+        self.assets
+            .iter()
+            .zip(weights)
+            .map(|(a, w)| a.expected_return * w)
+            .sum()
+    }
+
+    fn volatility(&self, weights: &[f64]) -> f64 {
+        self.variance(weights).max(0.0).sqrt()
+    }
+
+    fn variance(&self, weights: &[f64]) -> f64 {
+        weights
+            .iter()
+            .zip(self.sigma_times(weights))
+            .map(|(w, sw)| w * sw)
+            .sum()
+    }
+
+    fn sigma_times(&self, weights: &[f64]) -> Vec<f64> {
+        self.covariance
+            .iter()
+            .map(|row| row.iter().zip(weights).map(|(c, w)| c * w).sum())
+            .collect()
+    }
+
+    /// Maximiza `retorno - aversion/2 * varianza` con gradiente proyectado sobre los pesos que
+    /// suman 1 y respetan los limites.
+    fn solve(&self, aversion: f64) -> Vec<f64> {
+        let n = self.assets.len();
+        // el paso se acota por la curvatura (traza de Σ como cota del mayor valor propio)
+        let trace: f64 = (0..n).map(|i| self.covariance[i][i]).sum();
+        let step = 1.0 / (aversion * trace.max(TOLERANCE));
+
+        let mut weights = self.project(&vec![1.0 / n as f64; n]);
+        for _ in 0..2_000 {
+            let sigma_w = self.sigma_times(&weights);
+            let moved: Vec<f64> = weights
+                .iter()
+                .zip(&self.assets)
+                .zip(sigma_w)
+                .map(|((w, a), sw)| w + step * (a.expected_return - aversion * sw))
+                .collect();
+            let next = self.project(&moved);
+            let change: f64 = next.iter().zip(&weights).map(|(a, b)| (a - b).abs()).sum();
+            weights = next;
+            if change < 1e-12 {
+                break;
+            }
+        }
+        weights
+    }
+
+    /// Proyeccion sobre `{Σw = 1, min ≤ w ≤ max}`: se busca por biseccion el corrimiento `τ`
+    /// tal que `Σ clamp(v - τ) = 1`.
+    fn project(&self, values: &[f64]) -> Vec<f64> {
+        let clamp = |tau: f64| -> Vec<f64> {
+            values
+                .iter()
+                .zip(&self.assets)
+                .map(|(v, a)| (v - tau).clamp(a.min, a.max))
+                .collect()
+        };
+
+        let spread = values.iter().fold(0.0f64, |acc, v| acc.max(v.abs())) + 1.0;
+        let (mut low, mut high) = (-spread, spread);
+        for _ in 0..100 {
+            let middle = (low + high) / 2.0;
+            if clamp(middle).iter().sum::<f64>() > 1.0 {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        clamp((low + high) / 2.0)
+    }
+}
+
+/// Pasa pesos fraccionales a un `PortfolioTarget` en % con dos decimales. Los pesos que quedan
+/// en cero se omiten y lo que se pierde al redondear va al mayor.
+pub(crate) fn to_target(weights: &[(Stock, f64)]) -> Result<PortfolioTarget, TargetError> {
+    let mut targets: Vec<(Decimal, Stock)> = weights
+        .iter()
+        .filter_map(|(stock, w)| {
+            let percent = Decimal::from_f64(w * 100.0)?.round_dp(2);
+            (percent > Decimal::ZERO).then(|| (percent, stock.clone()))
+        })
+        .collect();
+
+    let residual = Decimal::ONE_HUNDRED - targets.iter().map(|(w, _)| *w).sum::<Decimal>();
+    if let Some((weight, _)) = targets.iter_mut().max_by(|a, b| a.0.cmp(&b.0)) {
+        *weight += residual;
+    }
+
+    PortfolioTarget::try_from_vec(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::covariance_matrix;
+    use rust_decimal_macros::dec;
+
+    fn optimizer() -> MeanVariance {
+        // dos activos sin correlacion: A rinde 10% con 20% de volatilidad, B 5% con 10%
+        let a = vec![0.2, -0.2, 0.2, -0.2];
+        let b = vec![0.1, 0.1, -0.1, -0.1];
+        let covariance = covariance_matrix(&[("A", a), ("B", b)]).unwrap();
+
+        MeanVariance::new(covariance)
+            .with_asset(Stock::new("A", dec!(10)), 0.10)
+            .with_asset(Stock::new("B", dec!(10)), 0.05)
+    }
+
+    #[test]
+    fn test_max_sharpe_matches_closed_form() {
+        let point = optimizer().max_sharpe().unwrap();
+
+        // sin correlacion, los pesos de tangencia son proporcionales a μ/σ²
+        let a = point.weights[0].1;
+        let (var_a, var_b) = (0.2f64.powi(2) * 4.0 / 3.0, 0.1f64.powi(2) * 4.0 / 3.0);
+        let expected = (0.10 / var_a) / (0.10 / var_a + 0.05 / var_b);
+        assert!((a - expected).abs() < 0.01, "{a} vs {expected}");
+
+        let total: Decimal = point.target.targets().iter().map(|(w, _)| *w).sum();
+        assert_eq!(total, dec!(100));
+    }
+
+    #[test]
+    fn test_equal_risk_contribution() {
+        let a = vec![0.2, -0.2, 0.2, -0.2];
+        let b = vec![0.1, 0.1, -0.1, -0.1];
+        let covariance = covariance_matrix(&[("A", a), ("B", b)]).unwrap();
+        let allocation = RiskBudgeting::new(covariance)
+            .with_asset(Stock::new("A", dec!(10)))
+            .with_asset(Stock::new("B", dec!(10)))
+            .solve()
+            .unwrap();
+
+        // sin correlacion, B (la mitad de volatil) pesa el doble: 1/3 y 2/3
+        for c in &allocation.contributions {
+            assert!((c.contribution - 0.5).abs() < 1e-9);
+        }
+        assert!((allocation.contributions[0].weight - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(
+            allocation.target.targets()[0],
+            (dec!(33.33), Stock::new("A", dec!(10)))
+        );
+    }
+
+    #[test]
+    fn test_black_litterman_views() {
+        let covariance = covariance_matrix(&[
+            ("META", vec![0.02, -0.01, 0.03, 0.00]),
+            ("AAPL", vec![0.01, 0.00, 0.01, 0.02]),
+        ])
+        .unwrap();
+        let model = BlackLitterman::new(covariance)
+            .with_asset(Stock::new("META", dec!(10)), 600.0)
+            .with_asset(Stock::new("AAPL", dec!(10)), 400.0);
+
+        let prior = model.posterior().unwrap();
+        assert_eq!(prior.implied, prior.expected);
+
+        let certain = model
+            .clone()
+            .with_view(View::relative("META", "AAPL", 0.02).with_confidence(1.0))
+            .posterior()
+            .unwrap();
+        let spread = certain.expected[0].1 - certain.expected[1].1;
+        assert!((spread - 0.02).abs() < 1e-9);
+
+        let unsure = model
+            .with_view(View::relative("META", "AAPL", 0.02).with_confidence(0.1))
+            .posterior()
+            .unwrap();
+        let implied_spread = unsure.implied[0].1 - unsure.implied[1].1;
+        let unsure_spread = unsure.expected[0].1 - unsure.expected[1].1;
+        assert!(implied_spread < unsure_spread && unsure_spread < 0.02);
+
+        let point = certain.optimizer().max_sharpe().unwrap();
+        assert!(point.target.contains_key("META"));
+    }
+
+    #[test]
+    fn test_fractional_kelly_is_capped_and_leaves_cash() {
+        let sizing = Kelly::new()
+            // f = 0.08 / 0.04 = 2 (topado a 40%) y 0.02 / 0.16 = 0.125
+            .with_asset(Stock::new("A", dec!(10)), 0.08, 0.04)
+            .with_asset(Stock::new("B", dec!(10)), 0.02, 0.16)
+            .with_asset(Stock::new("C", dec!(10)), -0.01, 0.04)
+            .with_fraction(0.5)
+            .with_cap(dec!(40))
+            .size()
+            .unwrap();
+
+        assert_eq!(sizing.full_kelly[0], ("A".to_string(), 2.0));
+        assert_eq!(sizing.target.targets()[0].0, dec!(40));
+        assert_eq!(sizing.target.targets()[1].0, dec!(6.25));
+        assert!(!sizing.target.contains_key("C"));
+        assert_eq!(sizing.target.cash_weight(), dec!(53.75));
+    }
+
+    #[test]
+    fn test_kelly_rounding_goes_to_the_largest_weight() {
+        // seis activos iguales que se pasan del 100%: cada uno queda en 16.67
+        let sizing = ["A", "B", "C", "D", "E", "F"]
+            .into_iter()
+            .fold(Kelly::new(), |kelly, ticker| {
+                kelly.with_asset(Stock::new(ticker, dec!(10)), 0.08, 0.04)
+            })
+            .size()
+            .unwrap();
+        let weights: Vec<Decimal> = sizing.target.targets().iter().map(|(w, _)| *w).collect();
+        assert_eq!(weights.iter().sum::<Decimal>(), dec!(100));
+        assert_eq!(weights.iter().filter(|w| **w == dec!(16.67)).count(), 5);
+        assert_eq!(sizing.target.cash_weight(), Decimal::ZERO);
+
+        assert_eq!(
+            Kelly::new()
+                .with_asset(Stock::new("A", dec!(10)), 0.08, 0.04)
+                .with_cap(dec!(-10))
+                .size()
+                .unwrap_err(),
+            OptimizeError::InfeasibleBounds
+        );
+    }
+
+    #[test]
+    fn test_target_volatility_and_bounds() {
+        let optimizer = optimizer().with_bounds("A", dec!(0), dec!(30));
+
+        let point = optimizer.target_volatility(0.12).unwrap();
+        assert!(point.volatility <= 0.12 + 1e-6);
+        assert!(point.weights[0].1 <= 0.30 + 1e-9);
+
+        assert!(matches!(
+            optimizer.target_volatility(0.01),
+            Err(OptimizeError::VolatilityUnreachable(_))
+        ));
+        assert_eq!(
+            optimizer
+                .clone()
+                .with_bounds("B", dec!(80), dec!(100))
+                .with_bounds("A", dec!(30), dec!(40))
+                .max_sharpe(),
+            Err(OptimizeError::InfeasibleBounds)
+        );
+        // los totales alcanzan, pero el minimo de A esta sobre su maximo
+        assert_eq!(
+            optimizer
+                .clone()
+                .with_bounds("A", dec!(60), dec!(20))
+                .max_sharpe(),
+            Err(OptimizeError::InfeasibleBounds)
+        );
+    }
+}
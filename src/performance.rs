@@ -0,0 +1,120 @@
+//! Medidas simples de rendimiento entre dos valorizaciones, en version nominal y real.
+
+use crate::date::Date;
+use crate::inflation::InflationSeries;
+use rust_decimal::prelude::*;
+
+/// Ganancia (o perdida, si es negativa) nominal.
+pub fn profit(initial_value: Decimal, final_value: Decimal) -> Decimal {
+    final_value - initial_value
+}
+
+/// Retorno total como fraccion (`0.1` = 10%); `None` si no habia nada invertido o si no cabe
+/// en un `Decimal`.
+pub fn total_return(initial_value: Decimal, final_value: Decimal) -> Option<Decimal> {
+    final_value
+        .checked_div(initial_value)?
+        .checked_sub(Decimal::ONE)
+}
+
+/// Retorno anualizado (CAGR) entre dos fechas.
+///
+/// Para periodos de menos de un año anualizar exagera mucho los resultados, pero eso es lo que
+/// la formula dice; queda a criterio de quien lo muestre. Si el resultado no cabe en un
+/// `Decimal` (un periodo muy corto con mucho crecimiento), devuelve `None`.
+pub fn annualized_return(
+    initial_value: Decimal,
+    final_value: Decimal,
+    from: Date,
+    to: Date,
+) -> Option<Decimal> {
+    let growth = Decimal::ONE + total_return(initial_value, final_value)?;
+    let years = Decimal::from_f64(from.years_until(to))?;
+
+    if years <= Decimal::ZERO || growth < Decimal::ZERO {
+        return None;
+    }
+
+    growth
+        .checked_powd(Decimal::ONE / years)?
+        .checked_sub(Decimal::ONE)
+}
+
+/// Ganancia real: el valor final se expresa en moneda de la fecha inicial antes de restar.
+pub fn real_profit(
+    initial_value: Decimal,
+    final_value: Decimal,
+    from: Date,
+    to: Date,
+    inflation: &InflationSeries,
+) -> Option<Decimal> {
+    Some(inflation.deflate(final_value, from, to)? - initial_value)
+}
+
+/// Retorno total real como fraccion.
+pub fn real_total_return(
+    initial_value: Decimal,
+    final_value: Decimal,
+    from: Date,
+    to: Date,
+    inflation: &InflationSeries,
+) -> Option<Decimal> {
+    total_return(initial_value, inflation.deflate(final_value, from, to)?)
+}
+
+/// Retorno anualizado real.
+pub fn real_annualized_return(
+    initial_value: Decimal,
+    final_value: Decimal,
+    from: Date,
+    to: Date,
+    inflation: &InflationSeries,
+) -> Option<Decimal> {
+    annualized_return(
+        initial_value,
+        inflation.deflate(final_value, from, to)?,
+        from,
+        to,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn date(y: i32, m: u32, d: u32) -> Date {
+        Date::new(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_nominal_measures() {
+        assert_eq!(profit(dec!(100), dec!(121)), dec!(21));
+        assert_eq!(total_return(dec!(100), dec!(121)), Some(dec!(0.21)));
+        assert_eq!(total_return(Decimal::ZERO, dec!(121)), None);
+
+        // 730 dias son exactamente 2 años en Actual/365
+        let cagr = annualized_return(dec!(100), dec!(121), date(2021, 1, 1), date(2023, 1, 1));
+        assert_eq!(cagr.unwrap().round_dp(6), dec!(0.1));
+
+        // crecer 1000 veces en un dia no se puede anualizar en un Decimal
+        let day = annualized_return(dec!(1), dec!(1000), date(2023, 1, 1), date(2023, 1, 2));
+        assert_eq!(day, None);
+    }
+
+    #[test]
+    fn test_real_measures_remove_inflation() {
+        let from = date(2023, 1, 1);
+        let to = date(2024, 1, 1);
+        let inflation = InflationSeries::from_index(vec![(from, dec!(100)), (to, dec!(110))]);
+
+        assert_eq!(
+            real_profit(dec!(100), dec!(110), from, to, &inflation),
+            Some(Decimal::ZERO)
+        );
+        assert_eq!(
+            real_total_return(dec!(100), dec!(121), from, to, &inflation),
+            Some(dec!(0.1))
+        );
+    }
+}
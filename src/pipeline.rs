@@ -0,0 +1,818 @@
+//! Rebalanceo como una serie de etapas.
+//!
+//! `Portfolio::rebalance_portfolio` es el pipeline `Pipeline::conservative()`: sumar los
+//! holdings, calcular las unidades objetivo y redondearlas hacia abajo. Cada etapa es un
+//! `RebalanceStage`, asi que se pueden agregar restricciones, filtros de costo o reglas propias
+//! (p. ej. un filtro de compliance) sin copiar el algoritmo.
+//!
+//! Las etapas de este modulo, `prioritize` y la conversion final a unidades enteras calculan con
+//! aritmetica chequeada: el primer calculo que no cabe en un `Decimal` (o que dividiria por un
+//! precio cero) queda en `Plan::overflow` y `Pipeline::try_run` lo devuelve como error. Una etapa
+//! propia que quiera lo mismo tiene que anotar sus desbordes igual.
+
+use crate::error::ArithmeticOverflow;
+use crate::execution::Side;
+use crate::instrument::Instrument;
+use crate::math::math_config;
+use crate::{Portfolio, RebalanceStrategy, RebalanceSuggestion, Stock};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use rust_decimal::prelude::*;
+
+/// Estado que va pasando de una etapa a la siguiente.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan<'a> {
+    /// Unidades que se tienen de cada ticker.
+    pub held: BTreeMap<&'a str, usize>,
+
+    /// Valor del portafolio, caja incluida.
+    pub total: Decimal,
+
+    /// Unidades que se quieren tener de cada ticker; pueden ser fraccionarias hasta que alguna
+    /// etapa las redondee. Un ticker que no aparece aqui no se toca.
+    pub targets: BTreeMap<&'a str, Decimal>,
+
+    /// Primer calculo que no cupo en un `Decimal`; `Pipeline::try_run` lo devuelve como error.
+    pub overflow: Option<ArithmeticOverflow>,
+}
+
+/// Una etapa del rebalanceo.
+pub trait RebalanceStage {
+    /// Nombre de la etapa, para ubicarla con `Pipeline::insert_before`.
+    fn name(&self) -> &str;
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>);
+}
+
+/// Suma las unidades de cada ticker y el valor total.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AggregateHoldings;
+
+impl RebalanceStage for AggregateHoldings {
+    fn name(&self) -> &str {
+        "aggregate"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        for stock in portfolio.stocks() {
+            *plan.held.entry(stock.name()).or_default() += 1;
+        }
+
+        // el efectivo disponible tambien sirve para financiar compras
+        match portfolio.checked_total_value() {
+            Ok(total) => plan.total = total,
+            Err(overflow) => plan.overflow = Some(overflow),
+        }
+    }
+}
+
+/// Unidades objetivo segun los pesos del objetivo; lo que no esta en el objetivo va a cero. Si
+/// el portafolio no vale nada no hay objetivo.
+///
+/// Los pesos se calculan al precio del stock (el punto medio si hay puntas), pero las compras se
+/// pagan al ask y las ventas se cobran al bid; si con eso la caja no alcanza, las compras se
+/// achican como en `BuyOnly`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComputeTargets;
+
+impl RebalanceStage for ComputeTargets {
+    fn name(&self) -> &str {
+        "targets"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        if plan.total.is_zero() {
+            return;
+        }
+
+        for name in plan.held.keys() {
+            if !portfolio.allocation().contains_key(name) {
+                plan.targets.insert(name, Decimal::ZERO);
+            }
+        }
+
+        for (ratio, stock) in portfolio.allocation().targets() {
+            match checked_target_units(plan.total, *ratio, stock) {
+                Ok(units) => {
+                    plan.targets.insert(stock.name(), units);
+                }
+                Err(overflow) => {
+                    plan.overflow = Some(overflow);
+                    return;
+                }
+            }
+        }
+        fit_buys_to_cash(portfolio, plan);
+    }
+}
+
+/// Unidades de `stock` que corresponden a `weight`% de `total`, con la precision de
+/// `math::math_config`.
+fn checked_target_units(
+    total: Decimal,
+    weight: Decimal,
+    stock: &Stock,
+) -> Result<Decimal, ArithmeticOverflow> {
+    let price = stock.current_price();
+    if price.is_zero() {
+        return Err(ArithmeticOverflow::ZeroPrice(stock.name().into()));
+    }
+    math_config()
+        .checked_target_units(total, weight, price)
+        .ok_or_else(|| ArithmeticOverflow::TargetUnits(stock.name().into()))
+}
+
+/// Unidades enteras de un objetivo: truncadas, y cero si alguna etapa lo dejo negativo.
+fn whole_units(name: &str, units: Decimal) -> Result<usize, ArithmeticOverflow> {
+    units
+        .max(Decimal::ZERO)
+        .trunc()
+        .to_usize()
+        .ok_or_else(|| ArithmeticOverflow::TargetUnits(name.into()))
+}
+
+/// Limita el peso de cada ticker a `self.0`% del valor total.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxWeight(pub Decimal);
+
+impl RebalanceStage for MaxWeight {
+    fn name(&self) -> &str {
+        "max_weight"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        for (name, units) in plan.targets.iter_mut() {
+            let Some(stock) = portfolio.priced(name) else {
+                continue;
+            };
+            match checked_target_units(plan.total, self.0, stock) {
+                Ok(max) => *units = (*units).min(max),
+                Err(overflow) => {
+                    plan.overflow = Some(overflow);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Rebalanceo con histeresis: un ticker dentro de su banda no se toca, y uno fuera vuelve solo
+/// hasta el borde de la banda en vez de hasta el objetivo exacto. Rota bastante menos que
+/// rebalancear al centro, a cambio de vivir con algo de drift.
+///
+/// La banda de cada ticker es la de `PortfolioTarget::with_band` o, si no tiene, `self.0`
+/// puntos porcentuales. Va antes de redondear, asi que una compra puede quedar hasta una unidad
+/// antes del borde. Como no necesariamente vende lo mismo que compra, las compras se achican a
+/// lo que alcanza a pagar la caja (ver `BuyOnly`).
+#[derive(Debug, Clone, Copy)]
+pub struct BandEdge(pub Decimal);
+
+impl RebalanceStage for BandEdge {
+    fn name(&self) -> &str {
+        "band_edge"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        if plan.total.is_zero() {
+            return;
+        }
+
+        for (weight, stock) in portfolio.allocation().targets() {
+            let Some(units) = plan.targets.get_mut(stock.name()) else {
+                continue;
+            };
+            let price = stock.current_price();
+            if price.is_zero() {
+                continue;
+            }
+
+            let held = Decimal::from(plan.held.get(stock.name()).copied().unwrap_or(0));
+            let current = held
+                .checked_mul(price)
+                .and_then(|value| value.checked_div(plan.total))
+                .and_then(|fraction| fraction.checked_mul(Decimal::ONE_HUNDRED));
+            let Some(current) = current else {
+                plan.overflow = Some(ArithmeticOverflow::Valuation(Some(stock.name().into())));
+                return;
+            };
+            let band = portfolio.allocation().band(stock.name()).unwrap_or(self.0);
+            let edge = if current > weight + band {
+                weight + band
+            } else if current < weight - band {
+                weight - band
+            } else {
+                *units = held;
+                continue;
+            };
+            match checked_target_units(plan.total, edge, stock) {
+                Ok(edge_units) => *units = edge_units,
+                Err(overflow) => {
+                    plan.overflow = Some(overflow);
+                    return;
+                }
+            }
+        }
+        fit_buys_to_cash(portfolio, plan);
+    }
+}
+
+/// Solo compras: invierte la caja disponible (la que sobra sobre la caja del objetivo) en los
+/// tickers que estan bajo su objetivo, sin vender nada. Si la caja no alcanza para llevarlos a
+/// todos al objetivo, se reparte en proporcion a lo que le falta a cada uno.
+///
+/// Es lo que hace un ahorrante que aporta todos los meses: el aporte corrige el drift en vez de
+/// vender lo que subio.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuyOnly;
+
+impl RebalanceStage for BuyOnly {
+    fn name(&self) -> &str {
+        "buy_only"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        for (name, units) in plan.targets.iter_mut() {
+            let held = Decimal::from(plan.held.get(name).copied().unwrap_or(0));
+            *units = (*units).max(held);
+        }
+        fit_buys_to_cash(portfolio, plan);
+    }
+}
+
+/// Achica las compras del plan para que se paguen con la caja (sobre la del objetivo) mas lo
+/// que entra por las ventas del plan, en proporcion a lo que le falta a cada ticker. Para las
+/// etapas que suben el objetivo de algun ticker por sobre lo que le toca, que si no dejarian
+/// comprando mas de lo que hay.
+pub(crate) fn fit_buys_to_cash(portfolio: &Portfolio, plan: &mut Plan<'_>) {
+    // a la punta a la que se ejecutaria cada lado (ver `quote`)
+    let price = |name: &str, side: Side| {
+        portfolio
+            .priced(name)
+            .map_or(Decimal::ZERO, |s| s.price_for(side))
+    };
+    let Some(reserved) = plan
+        .total
+        .checked_mul(portfolio.allocation().cash_weight() / Decimal::ONE_HUNDRED)
+    else {
+        plan.overflow = Some(ArithmeticOverflow::Valuation(None));
+        return;
+    };
+    let mut available = portfolio.cash() - reserved;
+    let mut needed = Decimal::ZERO;
+    for (name, units) in &plan.targets {
+        let held = Decimal::from(plan.held.get(name).copied().unwrap_or(0));
+        let (total, side, units) = if *units < held {
+            (&mut available, Side::Sell, held - *units)
+        } else {
+            (&mut needed, Side::Buy, *units - held)
+        };
+        match units
+            .checked_mul(price(name, side))
+            .and_then(|amount| total.checked_add(amount))
+        {
+            Some(sum) => *total = sum,
+            None => {
+                plan.overflow = Some(ArithmeticOverflow::Notional(name.to_string()));
+                return;
+            }
+        }
+    }
+    if needed.is_zero() || available >= needed {
+        return;
+    }
+
+    let scale = (available.max(Decimal::ZERO) / needed).min(Decimal::ONE);
+    for (name, units) in plan.targets.iter_mut() {
+        let held = Decimal::from(plan.held.get(name).copied().unwrap_or(0));
+        if *units > held {
+            *units = held + (*units - held) * scale;
+        }
+    }
+}
+
+/// Estrategia conservadora: la mayor cantidad de unidades (en multiplos del lote) sin pasarse
+/// del objetivo.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundDown;
+
+impl RebalanceStage for RoundDown {
+    fn name(&self) -> &str {
+        "round"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        for (name, units) in plan.targets.iter_mut() {
+            let rounded = match portfolio.priced(name) {
+                Some(stock) => stock.checked_round_to_increment(units.trunc()),
+                None => Some(units.trunc()),
+            };
+            match rounded {
+                Some(rounded) => *units = rounded,
+                None => {
+                    plan.overflow = Some(ArithmeticOverflow::TargetUnits(name.to_string()));
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Descarta las operaciones que mueven menos de `self.0` en dinero, donde la comision se comeria
+/// el beneficio de rebalancear.
+#[derive(Debug, Clone, Copy)]
+pub struct MinTradeValue(pub Decimal);
+
+impl RebalanceStage for MinTradeValue {
+    fn name(&self) -> &str {
+        "min_trade_value"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        for (name, units) in plan.targets.iter_mut() {
+            let held = Decimal::from(plan.held.get(name).copied().unwrap_or(0));
+            let price = portfolio
+                .priced(name)
+                .map_or(Decimal::ZERO, |s| s.current_price());
+            // si el monto no cabe en un `Decimal`, seguro no es chico
+            if (*units - held)
+                .abs()
+                .checked_mul(price)
+                .is_some_and(|value| value < self.0)
+            {
+                *units = held;
+            }
+        }
+    }
+}
+
+/// Una operacion de la sugerencia y cuanto ayuda a acercarse al objetivo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradePriority {
+    pub ticker: String,
+    pub side: Side,
+    pub units: usize,
+
+    /// Monto de la operacion a precio actual.
+    pub notional: Decimal,
+
+    /// Cuantos puntos porcentuales baja el drift total (la suma de `|actual - objetivo|` de cada
+    /// ticker y de la caja) si se hace solo esta operacion; negativo si lo empeora.
+    pub drift_reduction: Decimal,
+}
+
+impl TradePriority {
+    /// Reduccion de drift por cada 1.000 transados, para comparar operaciones de distinto
+    /// tamaño; cero si la operacion no mueve dinero.
+    pub fn score(&self) -> Decimal {
+        if self.notional.is_zero() {
+            return Decimal::ZERO;
+        }
+        self.drift_reduction / self.notional * Decimal::ONE_THOUSAND
+    }
+}
+
+/// Candidatas del plan (las unidades objetivo, truncadas, que difieren de las que se tienen),
+/// de mayor a menor `score`.
+pub fn prioritize(
+    portfolio: &Portfolio,
+    plan: &Plan<'_>,
+) -> Result<Vec<TradePriority>, ArithmeticOverflow> {
+    if plan.total.is_zero() {
+        return Ok(Vec::new());
+    }
+    let weight = |value: Decimal| {
+        value
+            .checked_div(plan.total)?
+            .checked_mul(Decimal::ONE_HUNDRED)
+    };
+    let cash_gap = weight(portfolio.cash()).ok_or(ArithmeticOverflow::Valuation(None))?
+        - portfolio.allocation().cash_weight();
+
+    let mut priorities: Vec<TradePriority> = Vec::new();
+    for (name, units) in &plan.targets {
+        let target = whole_units(name, *units)?;
+        let held = plan.held.get(name).copied().unwrap_or(0);
+        let (side, units) = match target.cmp(&held) {
+            core::cmp::Ordering::Greater => (Side::Buy, target - held),
+            core::cmp::Ordering::Less => (Side::Sell, held - target),
+            core::cmp::Ordering::Equal => continue,
+        };
+        let price = portfolio
+            .priced(name)
+            .map_or(Decimal::ZERO, |s| s.current_price());
+        let goal = portfolio
+            .allocation()
+            .targets()
+            .iter()
+            .find(|(_, stock)| stock.name() == *name)
+            .map_or(Decimal::ZERO, |(weight, _)| *weight);
+
+        let sized = || {
+            let notional = price.checked_mul(Decimal::from(units))?;
+            let gap = weight(price.checked_mul(Decimal::from(held))?)? - goal;
+            let moved = match side {
+                Side::Buy => weight(notional)?,
+                Side::Sell => -weight(notional)?,
+            };
+            let drift_reduction = gap
+                .abs()
+                .checked_sub(gap.checked_add(moved)?.abs())?
+                .checked_add(cash_gap.abs())?
+                .checked_sub(cash_gap.checked_sub(moved)?.abs())?;
+            Some((notional, drift_reduction))
+        };
+        let (notional, drift_reduction) =
+            sized().ok_or_else(|| ArithmeticOverflow::Notional(name.to_string()))?;
+
+        priorities.push(TradePriority {
+            ticker: name.to_string(),
+            side,
+            units,
+            notional,
+            drift_reduction,
+        });
+    }
+
+    priorities.sort_by(|a, b| b.score().cmp(&a.score()).then(a.ticker.cmp(&b.ticker)));
+    Ok(priorities)
+}
+
+/// Deja solo las N (`self.0`) operaciones que mas reducen el drift por monto transado (ver
+/// `TradePriority::score`), para brokers que cobran por orden o cuando no hay tiempo de hacerlas
+/// todas. Una compra que la caja y las ventas ya elegidas no alcanzan a financiar se salta y se
+/// prueba con la siguiente.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxTrades(pub usize);
+
+impl RebalanceStage for MaxTrades {
+    fn name(&self) -> &str {
+        "max_trades"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        let candidates = match prioritize(portfolio, plan) {
+            Ok(candidates) => candidates,
+            Err(overflow) => {
+                plan.overflow = Some(overflow);
+                return;
+            }
+        };
+
+        let mut chosen: Vec<&str> = Vec::new();
+        let mut cash = portfolio.cash();
+        for candidate in &candidates {
+            if chosen.len() == self.0 {
+                break;
+            }
+            match candidate.side {
+                Side::Sell => match cash.checked_add(candidate.notional) {
+                    Some(total) => cash = total,
+                    None => {
+                        plan.overflow =
+                            Some(ArithmeticOverflow::Notional(candidate.ticker.clone()));
+                        return;
+                    }
+                },
+                Side::Buy if candidate.notional <= cash => cash -= candidate.notional,
+                Side::Buy => continue,
+            }
+            chosen.push(&candidate.ticker);
+        }
+
+        for (name, units) in plan.targets.iter_mut() {
+            if !chosen.contains(name) {
+                *units = Decimal::from(plan.held.get(name).copied().unwrap_or(0));
+            }
+        }
+    }
+}
+
+/// Lista ordenada de etapas.
+pub struct Pipeline {
+    stages: Vec<Box<dyn RebalanceStage>>,
+}
+
+impl Pipeline {
+    /// Un pipeline sin etapas; no sugiere nada.
+    pub fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// El algoritmo de `Portfolio::rebalance_portfolio`.
+    pub fn conservative() -> Self {
+        Self::new()
+            .then(AggregateHoldings)
+            .then(ComputeTargets)
+            .then(RoundDown)
+    }
+
+    /// Solo invierte la caja, sin vender (ver `BuyOnly`).
+    pub fn contribution_only() -> Self {
+        Self::conservative().insert_before("round", BuyOnly)
+    }
+
+    /// El algoritmo conservador, pero con histeresis (ver `BandEdge`).
+    pub fn hysteresis(tolerance: Decimal) -> Self {
+        Self::conservative().insert_before("round", BandEdge(tolerance))
+    }
+
+    /// Agrega una etapa al final.
+    pub fn then(mut self, stage: impl RebalanceStage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Agrega una etapa justo antes de la etapa llamada `name`, o al final si no hay ninguna.
+    pub fn insert_before(mut self, name: &str, stage: impl RebalanceStage + 'static) -> Self {
+        let index = self
+            .stages
+            .iter()
+            .position(|s| s.name() == name)
+            .unwrap_or(self.stages.len());
+        self.stages.insert(index, Box::new(stage));
+        self
+    }
+
+    pub fn stage_names(&self) -> Vec<&str> {
+        self.stages.iter().map(|s| s.name()).collect()
+    }
+
+    /// Corre las etapas en orden y sugiere comprar o vender la diferencia entre las unidades
+    /// objetivo (truncadas, por si ninguna etapa redondeo) y las que se tienen.
+    ///
+    /// Entra en panico si algun calculo no cabe en un `Decimal`; ver `try_run`.
+    pub fn run<'a>(&self, portfolio: &'a Portfolio) -> RebalanceSuggestion<'a> {
+        self.try_run(portfolio)
+            .unwrap_or_else(|overflow| panic!("{overflow}"))
+    }
+
+    /// Como `run`, pero con un error si alguna etapa anota un desborde en `Plan::overflow`; las
+    /// etapas siguientes ya no corren.
+    pub fn try_run<'a>(
+        &self,
+        portfolio: &'a Portfolio,
+    ) -> Result<RebalanceSuggestion<'a>, ArithmeticOverflow> {
+        let mut plan = Plan::default();
+        for stage in &self.stages {
+            stage.apply(portfolio, &mut plan);
+            if let Some(overflow) = plan.overflow.take() {
+                return Err(overflow);
+            }
+        }
+
+        let mut suggestion = RebalanceSuggestion {
+            id: portfolio.state_id(RebalanceStrategy::Conservative),
+            priorities: prioritize(portfolio, &plan)?,
+            ..Default::default()
+        };
+        for (name, units) in plan.targets {
+            let target = whole_units(name, units)?;
+            let held = plan.held.get(name).copied().unwrap_or(0);
+
+            if target > held {
+                suggestion.to_buy.insert(name, target - held);
+            } else if target < held {
+                suggestion.to_sell.insert(name, held - target);
+            }
+        }
+
+        Ok(suggestion)
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::conservative()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(60), Stock::new("META", dec!(10))),
+            (dec!(40), Stock::new("AAPL", dec!(10))),
+        ])
+        .unwrap();
+
+        Portfolio {
+            cash: dec!(75),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("AAPL", dec!(10)); 3],
+            allocation: target,
+        }
+    }
+
+    /// No deja comprar META, como haria un filtro de compliance.
+    struct Restricted;
+
+    impl RebalanceStage for Restricted {
+        fn name(&self) -> &str {
+            "restricted"
+        }
+
+        fn apply<'a>(&self, _: &'a Portfolio, plan: &mut Plan<'a>) {
+            plan.targets.remove("META");
+        }
+    }
+
+    #[test]
+    fn test_conservative_pipeline_matches_rebalance() {
+        let portfolio = portfolio();
+        let suggestion = Pipeline::conservative().run(&portfolio);
+
+        assert_eq!(suggestion.to_buy, portfolio.rebalance_portfolio().to_buy);
+        assert_eq!(suggestion.to_buy["META"], 6);
+        assert_eq!(suggestion.to_buy["AAPL"], 1);
+    }
+
+    #[test]
+    fn test_try_run_reports_overflow_instead_of_panicking() {
+        let mut huge = portfolio();
+        huge.stocks = vec![Stock::new("AAPL", Decimal::MAX); 2];
+        assert_eq!(
+            huge.try_rebalance_portfolio().unwrap_err(),
+            ArithmeticOverflow::Valuation(None)
+        );
+
+        let tiny = Portfolio {
+            cash: Decimal::MAX / dec!(2),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(0.0000001))),
+        };
+        assert_eq!(
+            Pipeline::conservative().try_run(&tiny).unwrap_err(),
+            ArithmeticOverflow::TargetUnits("META".into())
+        );
+
+        // mas unidades de las que caben en un usize: antes se leian como cero y se vendia todo
+        let rich = Portfolio {
+            cash: dec!(1e22),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("META", dec!(1)); 5],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(1))),
+        };
+        assert_eq!(
+            rich.try_rebalance_portfolio().unwrap_err(),
+            ArithmeticOverflow::TargetUnits("META".into())
+        );
+
+        let free = Portfolio {
+            allocation: PortfolioTarget::new(Stock::new("META", Decimal::ZERO)),
+            ..portfolio()
+        };
+        assert_eq!(
+            free.try_rebalance_portfolio().unwrap_err(),
+            ArithmeticOverflow::ZeroPrice("META".into())
+        );
+
+        assert!(portfolio().try_rebalance_portfolio().is_ok());
+    }
+
+    #[test]
+    fn test_hysteresis_trades_to_band_edge() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(40), Stock::new("META", dec!(10))),
+            (dec!(40), Stock::new("AAPL", dec!(10))),
+            (dec!(20), Stock::new("BOND", dec!(10))),
+        ])
+        .unwrap()
+        .with_band("BOND", dec!(2));
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: [
+                vec![Stock::new("META", dec!(10)); 50],
+                vec![Stock::new("AAPL", dec!(10)); 34],
+                vec![Stock::new("BOND", dec!(10)); 16],
+            ]
+            .concat(),
+            allocation: target,
+        };
+
+        // META 50% vuelve a 45%, AAPL 34% sube a 35% y BOND 16% (banda de 2) sube a 18%
+        let suggestion = Pipeline::hysteresis(dec!(5)).run(&portfolio);
+        assert_eq!(suggestion.to_sell["META"], 5);
+        assert_eq!(suggestion.to_buy["AAPL"], 1);
+        assert_eq!(suggestion.to_buy["BOND"], 2);
+
+        let full = portfolio.rebalance_portfolio();
+        assert_eq!(full.to_sell["META"], 10);
+    }
+
+    #[test]
+    fn test_hysteresis_does_not_buy_without_cash() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("A", dec!(1))),
+            (dec!(30), Stock::new("B", dec!(1))),
+            (dec!(20), Stock::new("C", dec!(1))),
+        ])
+        .unwrap();
+        let mut stocks = vec![Stock::new("A", dec!(1)); 54];
+        stocks.extend(vec![Stock::new("B", dec!(1)); 24]);
+        stocks.extend(vec![Stock::new("C", dec!(1)); 22]);
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: target,
+        };
+
+        // B esta un punto bajo su banda, pero A y C estan dentro y no se venden
+        let suggestion = Pipeline::hysteresis(dec!(5)).run(&portfolio);
+        assert!(suggestion.to_buy.is_empty());
+        assert!(suggestion.to_sell.is_empty());
+    }
+
+    #[test]
+    fn test_max_trades_keeps_best_scores() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("META", dec!(10))),
+            (dec!(30), Stock::new("AAPL", dec!(10))),
+            (dec!(20), Stock::new("BOND", dec!(10))),
+        ])
+        .unwrap();
+        let portfolio = Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: [
+                vec![Stock::new("META", dec!(10)); 60],
+                vec![Stock::new("AAPL", dec!(10)); 10],
+                vec![Stock::new("BOND", dec!(10)); 20],
+            ]
+            .concat(),
+            allocation: target,
+        };
+
+        // vender 10 META (-10 puntos, pero +10 de caja) no mueve el drift total; comprar 20 AAPL
+        // lo baja 20 puntos y la caja pasa de sobrar 10 a faltar 10
+        let full = portfolio.rebalance_portfolio();
+        assert_eq!(full.priorities[0].ticker, "AAPL");
+        assert_eq!(full.priorities[0].drift_reduction, dec!(20));
+        assert_eq!(full.priorities[0].score(), dec!(100));
+        assert_eq!(full.priorities[1].side, Side::Sell);
+
+        // con una sola orden, la compra no se financia con la caja y queda solo la venta
+        let one = Pipeline::conservative().then(MaxTrades(1)).run(&portfolio);
+        assert_eq!(one.to_sell["META"], 10);
+        assert!(one.to_buy.is_empty());
+    }
+
+    #[test]
+    fn test_contribution_only_never_sells() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("META", dec!(10))),
+            (dec!(30), Stock::new("AAPL", dec!(10))),
+            (dec!(20), Stock::new("BOND", dec!(10))),
+        ])
+        .unwrap();
+        let portfolio = Portfolio {
+            cash: dec!(60),
+            foreign_cash: Default::default(),
+            stocks: [
+                vec![Stock::new("META", dec!(10)); 70],
+                vec![Stock::new("AAPL", dec!(10)); 10],
+                vec![Stock::new("BOND", dec!(10)); 10],
+            ]
+            .concat(),
+            allocation: target,
+        };
+
+        // total 960: faltan 188 en AAPL y 92 en BOND, pero solo hay 60 (~40 y ~20)
+        let suggestion = Pipeline::contribution_only().run(&portfolio);
+        assert!(suggestion.to_sell.is_empty());
+        assert_eq!(suggestion.to_buy["AAPL"], 4);
+        assert_eq!(suggestion.to_buy["BOND"], 1);
+    }
+
+    #[test]
+    fn test_custom_stages() {
+        let portfolio = portfolio();
+        let pipeline = Pipeline::conservative()
+            .insert_before("round", MaxWeight(dec!(50)))
+            .then(MinTradeValue(dec!(20)))
+            .then(Restricted);
+        assert_eq!(
+            pipeline.stage_names(),
+            [
+                "aggregate",
+                "targets",
+                "max_weight",
+                "round",
+                "min_trade_value",
+                "restricted"
+            ]
+        );
+
+        let suggestion = pipeline.run(&portfolio);
+        assert!(suggestion.to_buy.is_empty());
+        assert!(suggestion.to_sell.is_empty());
+    }
+}
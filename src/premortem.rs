@@ -0,0 +1,380 @@
+//! Premortem de un rebalanceo: que tan sensible es la sugerencia a errores en los precios.
+//!
+//! Una sugerencia calculada con cotizaciones atrasadas puede cambiar bastante con precios
+//! correctos, o nada. `Portfolio::premortem` mueve las cotizaciones `shock`% hacia arriba y hacia
+//! abajo (cada ticker por separado y todos juntos), vuelve a correr el pipeline y compara cada
+//! orden con la original:
+//!
+//! - estable: sale igual en todos los escenarios;
+//! - cambia de tamaño: siempre va para el mismo lado, pero con otra cantidad;
+//! - al filo: en algun escenario desaparece, aparece o se da vuelta (compra en vez de venta).
+//!
+//! Las ordenes al filo son las que conviene revisar con precios frescos antes de ejecutar.
+//!
+//! Si la sugerencia de algun escenario no se puede calcular (ver `Pipeline::try_run`), el
+//! premortem devuelve el escenario que fallo en vez de entrar en panico.
+
+use crate::error::ArithmeticOverflow;
+use crate::execution::{Order, Side};
+use crate::i18n::{Language, Localize, language};
+use crate::pipeline::Pipeline;
+use crate::{Portfolio, Stock};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Que tanto cambia una orden entre escenarios.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stability {
+    Stable,
+    SizeChanges,
+    KnifeEdge,
+}
+
+impl Stability {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stability::Stable => "stable",
+            Stability::SizeChanges => "size_changes",
+            Stability::KnifeEdge => "knife_edge",
+        }
+    }
+}
+
+/// Sensibilidad de las ordenes de un ticker. Las unidades van con signo: positivas para compras,
+/// negativas para ventas y cero si no hay orden.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeSensitivity {
+    pub ticker: String,
+
+    /// Unidades en la sugerencia original.
+    pub base: i64,
+
+    /// Minimo y maximo en los escenarios (incluida la original).
+    pub low: i64,
+    pub high: i64,
+}
+
+impl TradeSensitivity {
+    pub fn stability(&self) -> Stability {
+        if self.low == self.high {
+            Stability::Stable
+        } else if self.low.signum() != self.high.signum() || self.low == 0 || self.high == 0 {
+            Stability::KnifeEdge
+        } else {
+            Stability::SizeChanges
+        }
+    }
+}
+
+/// Resultado de `Portfolio::premortem`, ordenado por ticker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Premortem {
+    /// Movimiento aplicado a las cotizaciones, en %.
+    pub shock: Decimal,
+
+    /// Cuantos escenarios se corrieron, sin contar el original.
+    pub scenarios: usize,
+
+    pub trades: Vec<TradeSensitivity>,
+}
+
+impl Premortem {
+    pub fn with_stability(&self, stability: Stability) -> impl Iterator<Item = &TradeSensitivity> {
+        self.trades
+            .iter()
+            .filter(move |trade| trade.stability() == stability)
+    }
+
+    /// Si ninguna orden queda al filo.
+    pub fn is_robust(&self) -> bool {
+        self.with_stability(Stability::KnifeEdge).next().is_none()
+    }
+}
+
+impl Localize for Premortem {
+    fn localize(&self, language: Language) -> String {
+        let mut out = match language {
+            Language::Es => format!(
+                "Premortem con precios +/-{}% ({} escenarios)\n",
+                self.shock, self.scenarios
+            ),
+            Language::En => format!(
+                "Premortem with prices +/-{}% ({} scenarios)\n",
+                self.shock, self.scenarios
+            ),
+        };
+
+        for trade in &self.trades {
+            let stability = match (language, trade.stability()) {
+                (Language::Es, Stability::Stable) => "estable",
+                (Language::Es, Stability::SizeChanges) => "cambia de tamaño",
+                (Language::Es, Stability::KnifeEdge) => "al filo",
+                (Language::En, Stability::Stable) => "stable",
+                (Language::En, Stability::SizeChanges) => "size changes",
+                (Language::En, Stability::KnifeEdge) => "knife-edge",
+            };
+            out += &format!(
+                "  {}: {} [{}, {}] {}\n",
+                trade.ticker, trade.base, trade.low, trade.high, stability
+            );
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for Premortem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// Errores de `Portfolio::premortem`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PremortemError {
+    /// El movimiento tiene que estar entre 0% y 100% (sin incluir el 100, que deja precios en
+    /// cero).
+    InvalidShock(Decimal),
+
+    /// No se pudo calcular la sugerencia con los precios actuales.
+    Base(ArithmeticOverflow),
+
+    /// No se pudo calcular un escenario: el ticker movido (o todos si es `None`) y el movimiento
+    /// en %, con signo.
+    Scenario {
+        ticker: Option<String>,
+        shift: Decimal,
+        cause: ArithmeticOverflow,
+    },
+}
+
+impl Localize for PremortemError {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (PremortemError::InvalidShock(shock), Language::Es) => {
+                format!("El movimiento de precios tiene que estar entre 0% y 100% (es {shock}%).")
+            }
+            (PremortemError::InvalidShock(shock), Language::En) => {
+                format!("The price shift must be between 0% and 100% (it is {shock}%).")
+            }
+            (PremortemError::Base(cause), language) => cause.localize(language),
+            (
+                PremortemError::Scenario {
+                    ticker,
+                    shift,
+                    cause,
+                },
+                language,
+            ) => {
+                let scope = match (ticker, language) {
+                    (Some(ticker), _) => ticker.clone(),
+                    (None, Language::Es) => "todos".into(),
+                    (None, Language::En) => "all".into(),
+                };
+                let cause = cause.localize(language);
+                match language {
+                    Language::Es => format!("Escenario {scope} {shift:+}%: {cause}"),
+                    Language::En => format!("Scenario {scope} {shift:+}%: {cause}"),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for PremortemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl core::error::Error for PremortemError {}
+
+impl Portfolio {
+    /// Corre `pipeline` con las cotizaciones movidas `shock`% (ver el modulo) y compara las
+    /// ordenes con las de los precios actuales. `shock` tiene que estar en `[0, 100)`.
+    pub fn premortem(
+        &self,
+        pipeline: &Pipeline,
+        shock: Decimal,
+    ) -> Result<Premortem, PremortemError> {
+        if shock.is_sign_negative() || shock >= dec!(100) {
+            return Err(PremortemError::InvalidShock(shock));
+        }
+        let mut tickers: Vec<String> = self.tickers().into_iter().map(String::from).collect();
+        for (_, stock) in self.allocation.targets() {
+            if !tickers.iter().any(|t| t == stock.name()) {
+                tickers.push(stock.name().to_string());
+            }
+        }
+
+        let mut trades: BTreeMap<String, (i64, i64, i64)> = BTreeMap::new();
+        let base = pipeline.try_run(self).map_err(PremortemError::Base)?;
+        let base = signed(&base.orders());
+        for ticker in &tickers {
+            let units = base.get(ticker).copied().unwrap_or(0);
+            trades.insert(ticker.clone(), (units, units, units));
+        }
+
+        let scopes = tickers.iter().map(Some).chain([None]);
+        let mut scenarios = 0;
+        for scope in scopes {
+            for shift in [shock, -shock] {
+                let ticker = scope.map(String::as_str);
+                let failed = |cause| PremortemError::Scenario {
+                    ticker: ticker.map(String::from),
+                    shift,
+                    cause,
+                };
+                let shocked = self
+                    .shocked(ticker, dec!(1) + shift / dec!(100))
+                    .map_err(failed)?;
+                let suggestion = pipeline.try_run(&shocked).map_err(failed)?;
+                let orders = signed(&suggestion.orders());
+                for (ticker, (_, low, high)) in trades.iter_mut() {
+                    let units = orders.get(ticker).copied().unwrap_or(0);
+                    *low = (*low).min(units);
+                    *high = (*high).max(units);
+                }
+                scenarios += 1;
+            }
+        }
+
+        Ok(Premortem {
+            shock,
+            scenarios,
+            trades: trades
+                .into_iter()
+                .filter(|(_, (base, low, high))| *base != 0 || low != high)
+                .map(|(ticker, (base, low, high))| TradeSensitivity {
+                    ticker,
+                    base,
+                    low,
+                    high,
+                })
+                .collect(),
+        })
+    }
+
+    /// Copia con las cotizaciones (y puntas) de `ticker`, o de todos si es `None`, multiplicadas
+    /// por `factor`.
+    fn shocked(
+        &self,
+        ticker: Option<&str>,
+        factor: Decimal,
+    ) -> Result<Portfolio, ArithmeticOverflow> {
+        let mut shocked = self.clone();
+        let scale = |stock: &mut Stock| {
+            if ticker.is_some_and(|ticker| stock.name != ticker) {
+                return Ok(());
+            }
+            let overflow = || ArithmeticOverflow::Valuation(Some(stock.name.clone()));
+            let price = stock
+                .current_price
+                .checked_mul(factor)
+                .ok_or_else(overflow)?;
+            if let Some(quote) = stock.bid_ask.as_mut() {
+                quote.bid = quote.bid.checked_mul(factor).ok_or_else(overflow)?;
+                quote.ask = quote.ask.checked_mul(factor).ok_or_else(overflow)?;
+            }
+            stock.current_price = price;
+            Ok(())
+        };
+        shocked.stocks.iter_mut().try_for_each(scale)?;
+        shocked
+            .allocation
+            .targets
+            .iter_mut()
+            .try_for_each(|(_, stock)| scale(stock))?;
+        Ok(shocked)
+    }
+}
+
+/// Unidades por ticker, positivas para compras y negativas para ventas.
+fn signed(orders: &[Order]) -> BTreeMap<String, i64> {
+    let mut units: BTreeMap<String, i64> = BTreeMap::new();
+    for order in orders {
+        let amount = order.units as i64;
+        *units.entry(order.ticker.clone()).or_default() += match order.side {
+            Side::Buy => amount,
+            Side::Sell => -amount,
+        };
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortfolioTarget;
+    use alloc::vec;
+
+    #[test]
+    fn test_premortem_separates_stable_and_knife_edge_trades() {
+        // AAA esta muy bajo su objetivo; BBB esta justo en el borde de una unidad
+        let mut stocks = vec![Stock::new("AAA", dec!(10)); 2];
+        stocks.extend(vec![Stock::new("BBB", dec!(10)); 8]);
+        let portfolio = Portfolio {
+            stocks,
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            allocation: PortfolioTarget::try_from_vec(vec![
+                (dec!(60), Stock::new("AAA", dec!(10))),
+                (dec!(40), Stock::new("BBB", dec!(10))),
+            ])
+            .unwrap(),
+        };
+
+        let report = portfolio
+            .premortem(&Pipeline::conservative(), dec!(5))
+            .unwrap();
+        assert_eq!(report.scenarios, 6);
+
+        let aaa = report.trades.iter().find(|t| t.ticker == "AAA").unwrap();
+        assert_eq!(aaa.base, 10);
+        assert_eq!(aaa.stability(), Stability::SizeChanges);
+
+        let bbb = report.trades.iter().find(|t| t.ticker == "BBB").unwrap();
+        assert_eq!(bbb.base, 0);
+        assert_eq!(bbb.stability(), Stability::KnifeEdge);
+        assert!(!report.is_robust());
+
+        let calm = portfolio
+            .premortem(&Pipeline::conservative(), Decimal::ZERO)
+            .unwrap();
+        assert!(calm.is_robust());
+        assert!(calm.with_stability(Stability::SizeChanges).next().is_none());
+
+        for shock in [dec!(-5), dec!(100), dec!(250)] {
+            assert_eq!(
+                portfolio.premortem(&Pipeline::conservative(), shock),
+                Err(PremortemError::InvalidShock(shock))
+            );
+        }
+    }
+
+    #[test]
+    fn test_failed_scenario_is_reported_instead_of_panicking() {
+        // con los precios actuales cabe, pero subiendo 50% la valorizacion no entra en un Decimal
+        let price = Decimal::MAX / dec!(12);
+        let portfolio = Portfolio {
+            stocks: vec![Stock::new("AAA", price); 10],
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            allocation: PortfolioTarget::try_from_vec(vec![(dec!(100), Stock::new("AAA", price))])
+                .unwrap(),
+        };
+
+        let error = portfolio
+            .premortem(&Pipeline::conservative(), dec!(50))
+            .unwrap_err();
+        assert!(
+            matches!(error, PremortemError::Scenario { .. }),
+            "{error:?}"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Precios de cripto desde la API publica de CoinGecko.
+//!
+//! CoinGecko identifica los activos por un id propio ("bitcoin") y no por el simbolo ("BTC"),
+//! asi que la fuente lleva un mapeo simbolo → id. Los tickers sin mapeo se ignoran, lo que permite
+//! ponerla en una `SourceChain` junto a una fuente de acciones.
+
+use super::{PriceError, PriceSource};
+use crate::Map;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const DEFAULT_URL: &str = "https://api.coingecko.com/api/v3/simple/price";
+
+/// Fuente de precios de CoinGecko.
+#[derive(Debug, Clone)]
+pub struct CoinGeckoSource {
+    base_url: String,
+
+    /// Moneda de cotizacion, en minusculas como la espera la API ("usd", "clp").
+    vs_currency: String,
+
+    /// Simbolo → id de CoinGecko.
+    ids: HashMap<String, String>,
+}
+
+impl Default for CoinGeckoSource {
+    fn default() -> Self {
+        Self::new("usd")
+    }
+}
+
+impl CoinGeckoSource {
+    /// Fuente que cotiza en `vs_currency`, con el mapeo de las monedas mas comunes.
+    pub fn new(vs_currency: &str) -> Self {
+        let ids = [
+            ("BTC", "bitcoin"),
+            ("ETH", "ethereum"),
+            ("SOL", "solana"),
+            ("ADA", "cardano"),
+            ("XRP", "ripple"),
+            ("DOGE", "dogecoin"),
+            ("USDT", "tether"),
+            ("USDC", "usd-coin"),
+        ]
+        .into_iter()
+        .map(|(symbol, id)| (symbol.to_string(), id.to_string()))
+        .collect();
+
+        Self {
+            base_url: DEFAULT_URL.into(),
+            vs_currency: vs_currency.to_lowercase(),
+            ids,
+        }
+    }
+
+    /// Agrega (o reemplaza) el id de CoinGecko de un simbolo.
+    pub fn with_symbol(mut self, symbol: &str, id: &str) -> Self {
+        self.ids.insert(symbol.into(), id.into());
+        self
+    }
+
+    /// Cambia la URL del endpoint, p. ej. para el plan pro o un proxy.
+    pub fn with_base_url(mut self, url: &str) -> Self {
+        self.base_url = url.into();
+        self
+    }
+
+    /// Interpreta la respuesta de `/simple/price`: `{"bitcoin": {"usd": 60000.5}, ...}`.
+    fn parse_response(
+        &self,
+        body: &str,
+        symbols: &[(&str, &str)],
+    ) -> Result<Map<String, Decimal>, PriceError> {
+        let json: serde_json::Value =
+            serde_json::from_str(body).map_err(|e| PriceError::InvalidResponse(e.to_string()))?;
+
+        let mut prices = Map::new();
+        for (symbol, id) in symbols {
+            let Some(number) = json.get(id).and_then(|v| v.get(&self.vs_currency)) else {
+                continue;
+            };
+
+            let text = number.to_string();
+            let price = Decimal::from_str(&text)
+                .or_else(|_| Decimal::from_scientific(&text))
+                .map_err(|_| {
+                    PriceError::InvalidResponse(format!("precio invalido para {id}: {text}"))
+                })?;
+            prices.insert(symbol.to_string(), price);
+        }
+
+        Ok(prices)
+    }
+}
+
+impl PriceSource for CoinGeckoSource {
+    fn latest_prices(&self, tickers: &[&str]) -> Result<Map<String, Decimal>, PriceError> {
+        let symbols: Vec<(&str, &str)> = tickers
+            .iter()
+            .filter_map(|t| Some((*t, self.ids.get(*t)?.as_str())))
+            .collect();
+        if symbols.is_empty() {
+            return Ok(Map::new());
+        }
+
+        let ids: Vec<&str> = symbols.iter().map(|(_, id)| *id).collect();
+        let body = ureq::get(&self.base_url)
+            .query("ids", &ids.join(","))
+            .query("vs_currencies", &self.vs_currency)
+            .call()
+            .map_err(|e| PriceError::Unavailable(e.to_string()))?
+            .into_string()
+            .map_err(|e| PriceError::Unavailable(e.to_string()))?;
+
+        self.parse_response(&body, &symbols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parse_response_keeps_precision() {
+        let source = CoinGeckoSource::new("USD").with_symbol("PEPE", "pepe");
+        let body = r#"{"bitcoin":{"usd":60123.45678901},"pepe":{"usd":1.234e-5}}"#;
+
+        let prices = source
+            .parse_response(
+                body,
+                &[("BTC", "bitcoin"), ("PEPE", "pepe"), ("ETH", "ethereum")],
+            )
+            .unwrap();
+
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices["BTC"], dec!(60123.45678901));
+        assert_eq!(prices["PEPE"], dec!(0.00001234));
+    }
+
+    #[test]
+    fn test_unknown_symbols_skip_the_request() {
+        let source = CoinGeckoSource::default().with_base_url("http://127.0.0.1:9");
+        assert!(source.latest_prices(&["META", "AAPL"]).unwrap().is_empty());
+    }
+}
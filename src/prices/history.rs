@@ -0,0 +1,507 @@
+//! Historia de precios en barras OHLCV.
+//!
+//! Un backtest que solo conoce el cierre asume que todo se ejecuta al cierre. Con barras
+//! completas (apertura, maximo, minimo, cierre y volumen) se puede elegir a que precio se
+//! ejecutan las ordenes (`FillPrice`) y agregar la historia diaria a semanas o meses para
+//! rebalanceos menos frecuentes.
+
+use crate::date::Date;
+use crate::i18n::{Language, Localize, language};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Una barra OHLCV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bar {
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+}
+
+impl Bar {
+    pub fn new(open: Decimal, high: Decimal, low: Decimal, close: Decimal) -> Self {
+        Self {
+            open,
+            high,
+            low,
+            close,
+            volume: Decimal::ZERO,
+        }
+    }
+
+    pub fn with_volume(mut self, volume: Decimal) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Barra de un solo precio (cuando solo se conoce el cierre).
+    pub fn flat(price: Decimal) -> Self {
+        Self::new(price, price, price, price)
+    }
+
+    pub fn price(&self, fill: FillPrice) -> Decimal {
+        match fill {
+            FillPrice::Open => self.open,
+            FillPrice::Close => self.close,
+            FillPrice::Midpoint => (self.high + self.low) / Decimal::TWO,
+            FillPrice::Typical => (self.high + self.low + self.close) / Decimal::from(3),
+        }
+    }
+
+    /// Junta dos barras consecutivas: apertura de la primera, cierre de la segunda.
+    fn merge(self, next: Bar) -> Bar {
+        Bar {
+            open: self.open,
+            high: self.high.max(next.high),
+            low: self.low.min(next.low),
+            close: next.close,
+            volume: self.volume + next.volume,
+        }
+    }
+}
+
+/// A que precio de la barra se ejecutan las ordenes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillPrice {
+    Open,
+    #[default]
+    Close,
+
+    /// Punto medio entre maximo y minimo.
+    Midpoint,
+
+    /// Precio tipico `(maximo + minimo + cierre) / 3`, una aproximacion al VWAP cuando no hay
+    /// datos intradiarios.
+    Typical,
+}
+
+/// Periodo al que se agregan barras.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Daily,
+
+    /// Semanas de lunes a domingo.
+    Weekly,
+    Monthly,
+}
+
+impl Period {
+    fn key(&self, date: Date) -> i64 {
+        match self {
+            Period::Daily => date.days_since_epoch(),
+            // el 1970-01-01 fue jueves: sumando 3 las semanas parten el lunes
+            Period::Weekly => (date.days_since_epoch() + 3).div_euclid(7),
+            Period::Monthly => i64::from(date.year()) * 12 + i64::from(date.month()),
+        }
+    }
+}
+
+/// Como se mide el retorno entre dos precios.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReturnKind {
+    /// `p1 / p0 - 1`.
+    Simple,
+
+    /// `ln(p1 / p0)`; se suman en el tiempo, lo que conviene para volatilidades.
+    Log,
+}
+
+/// Retornos de varios tickers alineados en las mismas fechas.
+///
+/// Igual que `metrics`, los retornos son `f64`: son la materia prima de volatilidades,
+/// correlaciones y optimizaciones, no contabilidad.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnSeries {
+    /// Fecha de cierre de cada periodo (la del precio final del retorno).
+    pub dates: Vec<Date>,
+    pub tickers: Vec<String>,
+
+    /// Un vector por ticker, en el orden de `tickers`, del mismo largo que `dates`.
+    pub returns: Vec<Vec<f64>>,
+}
+
+impl ReturnSeries {
+    pub fn get(&self, ticker: &str) -> Option<&[f64]> {
+        let index = self.tickers.iter().position(|t| t == ticker)?;
+        Some(&self.returns[index])
+    }
+
+    pub fn len(&self) -> usize {
+        self.dates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dates.is_empty()
+    }
+
+    /// En el formato de `metrics::covariance_matrix`.
+    pub fn histories(&self) -> Vec<(&str, Vec<f64>)> {
+        self.tickers
+            .iter()
+            .map(String::as_str)
+            .zip(self.returns.iter().cloned())
+            .collect()
+    }
+}
+
+/// Que hacer con las fechas en que a un ticker le falta precio (feriados de su mercado, un IPO
+/// posterior al inicio de la historia).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingData {
+    /// No completar nada: si las series no calzan, error.
+    Error,
+
+    /// Descartar la fecha para todos los tickers.
+    Drop,
+
+    /// Repetir el ultimo cierre conocido.
+    ForwardFill,
+
+    /// Interpolar linealmente (por dias) entre el cierre anterior y el siguiente.
+    Interpolate,
+}
+
+/// Errores al calcular sobre una historia de precios.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryError {
+    /// Un ticker no tiene precio en fechas que otros si tienen.
+    Misaligned {
+        ticker: String,
+        first_missing: Date,
+        missing: usize,
+    },
+}
+
+impl Localize for HistoryError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            HistoryError::Misaligned {
+                ticker,
+                first_missing,
+                missing,
+            } => match language {
+                Language::Es => format!(
+                    "A {ticker} le faltan precios en {missing} fechas (la primera {first_missing}); elige como completarlos."
+                ),
+                Language::En => format!(
+                    "{ticker} is missing prices on {missing} dates (first {first_missing}); choose how to fill them."
+                ),
+            },
+        }
+    }
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+/// Barras por ticker, ordenadas por fecha.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriceHistory {
+    bars: BTreeMap<String, BTreeMap<Date, Bar>>,
+}
+
+impl PriceHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agrega (o reemplaza) la barra de un ticker en una fecha.
+    pub fn with_bar(mut self, ticker: &str, date: Date, bar: Bar) -> Self {
+        self.insert(ticker, date, bar);
+        self
+    }
+
+    pub fn insert(&mut self, ticker: &str, date: Date, bar: Bar) {
+        self.bars
+            .entry(ticker.into())
+            .or_default()
+            .insert(date, bar);
+    }
+
+    pub fn tickers(&self) -> impl Iterator<Item = &str> {
+        self.bars.keys().map(String::as_str)
+    }
+
+    pub fn bar(&self, ticker: &str, date: Date) -> Option<&Bar> {
+        self.bars.get(ticker)?.get(&date)
+    }
+
+    /// Barras de un ticker en orden de fecha.
+    pub fn bars(&self, ticker: &str) -> impl Iterator<Item = (Date, &Bar)> {
+        self.bars
+            .get(ticker)
+            .into_iter()
+            .flat_map(|bars| bars.iter().map(|(date, bar)| (*date, bar)))
+    }
+
+    /// Todas las fechas con al menos una barra.
+    pub fn dates(&self) -> Vec<Date> {
+        let mut dates: Vec<Date> = self.bars.values().flat_map(|b| b.keys().copied()).collect();
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    /// Retornos por `period` de todos los tickers, alineados con `MissingData::Drop`: solo se
+    /// usan las fechas en que todos tienen precio.
+    pub fn returns(&self, period: Period, kind: ReturnKind) -> ReturnSeries {
+        match self.returns_with(period, kind, MissingData::Drop) {
+            Ok(series) => series,
+            Err(_) => unreachable!("con Drop todas las series quedan alineadas"),
+        }
+    }
+
+    /// Retornos por `period` de todos los tickers, completando primero los datos faltantes
+    /// segun `policy` (sobre las barras diarias, antes de agregar). Si despues de eso algun
+    /// ticker sigue sin precio en alguna fecha (p. ej. antes de su IPO con `ForwardFill`), se
+    /// devuelve `HistoryError::Misaligned` en vez de calcular retornos sobre tramos distintos.
+    pub fn returns_with(
+        &self,
+        period: Period,
+        kind: ReturnKind,
+        policy: MissingData,
+    ) -> Result<ReturnSeries, HistoryError> {
+        let filled = self.fill(policy);
+        filled.check_aligned()?;
+
+        let history = filled.aggregate(period);
+        let tickers: Vec<String> = history.bars.keys().cloned().collect();
+        let dates = history.dates();
+
+        let returns = tickers
+            .iter()
+            .map(|ticker| {
+                dates
+                    .windows(2)
+                    .map(|pair| {
+                        let close = |date| {
+                            history
+                                .bar(ticker, date)
+                                .and_then(|bar| bar.close.to_f64())
+                                .unwrap_or(f64::NAN)
+                        };
+                        let ratio = close(pair[1]) / close(pair[0]);
+                        match kind {
+                            ReturnKind::Simple => ratio - 1.0,
+                            ReturnKind::Log => ratio.ln(),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(ReturnSeries {
+            dates: dates.into_iter().skip(1).collect(),
+            tickers,
+            returns,
+        })
+    }
+
+    /// Completa (o descarta) las fechas en que a algun ticker le falta precio. Los huecos antes
+    /// del primer precio o despues del ultimo de un ticker solo se pueden descartar: ni
+    /// `ForwardFill` ni `Interpolate` inventan precios fuera de la historia del ticker.
+    pub fn fill(&self, policy: MissingData) -> PriceHistory {
+        let dates = self.dates();
+        let mut filled = self.clone();
+
+        match policy {
+            MissingData::Error => {}
+            MissingData::Drop => {
+                for bars in filled.bars.values_mut() {
+                    bars.retain(|date, _| self.bars.values().all(|other| other.contains_key(date)));
+                }
+            }
+            MissingData::ForwardFill => {
+                for (ticker, bars) in &self.bars {
+                    let mut last: Option<Decimal> = None;
+                    for date in &dates {
+                        match bars.get(date) {
+                            Some(bar) => last = Some(bar.close),
+                            None => {
+                                if let Some(close) = last {
+                                    filled.insert(ticker, *date, Bar::flat(close));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            MissingData::Interpolate => {
+                for (ticker, bars) in &self.bars {
+                    for date in &dates {
+                        if bars.contains_key(date) {
+                            continue;
+                        }
+                        let before = bars.range(..*date).next_back();
+                        let after = bars.range(*date..).next();
+                        if let (Some((d0, b0)), Some((d1, b1))) = (before, after) {
+                            let span = Decimal::from(d0.days_until(*d1));
+                            let elapsed = Decimal::from(d0.days_until(*date));
+                            let close = b0.close + (b1.close - b0.close) * elapsed / span;
+                            filled.insert(ticker, *date, Bar::flat(close));
+                        }
+                    }
+                }
+            }
+        }
+
+        filled
+    }
+
+    /// Error si algun ticker no tiene precio en todas las fechas de la historia.
+    pub fn check_aligned(&self) -> Result<(), HistoryError> {
+        let dates = self.dates();
+        for (ticker, bars) in &self.bars {
+            let missing: Vec<Date> = dates
+                .iter()
+                .copied()
+                .filter(|date| !bars.contains_key(date))
+                .collect();
+            if let Some(first) = missing.first() {
+                return Err(HistoryError::Misaligned {
+                    ticker: ticker.clone(),
+                    first_missing: *first,
+                    missing: missing.len(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Agrega las barras a semanas o meses. Cada barra agregada queda en la ultima fecha con
+    /// datos del periodo, que es cuando se conoce su cierre.
+    pub fn aggregate(&self, period: Period) -> PriceHistory {
+        let mut aggregated = PriceHistory::new();
+        for (ticker, bars) in &self.bars {
+            let mut current: Option<(i64, Date, Bar)> = None;
+            for (date, bar) in bars {
+                current = match current {
+                    Some((key, _, acc)) if key == period.key(*date) => {
+                        Some((key, *date, acc.merge(*bar)))
+                    }
+                    other => {
+                        if let Some((_, last, acc)) = other {
+                            aggregated.insert(ticker, last, acc);
+                        }
+                        Some((period.key(*date), *date, *bar))
+                    }
+                };
+            }
+            if let Some((_, last, acc)) = current {
+                aggregated.insert(ticker, last, acc);
+            }
+        }
+        aggregated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn d(month: u32, day: u32) -> Date {
+        Date::new(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_weekly_and_monthly_aggregation() {
+        // 2024-01-05 es viernes y 2024-01-08 lunes
+        let history = PriceHistory::new()
+            .with_bar(
+                "KO",
+                d(1, 4),
+                Bar::new(dec!(10), dec!(12), dec!(9), dec!(11)).with_volume(dec!(5)),
+            )
+            .with_bar(
+                "KO",
+                d(1, 5),
+                Bar::new(dec!(11), dec!(13), dec!(10), dec!(12)).with_volume(dec!(7)),
+            )
+            .with_bar("KO", d(1, 8), Bar::flat(dec!(14)))
+            .with_bar("KO", d(2, 1), Bar::flat(dec!(15)));
+
+        let weekly = history.aggregate(Period::Weekly);
+        assert_eq!(
+            weekly.bar("KO", d(1, 5)),
+            Some(&Bar::new(dec!(10), dec!(13), dec!(9), dec!(12)).with_volume(dec!(12)))
+        );
+        assert_eq!(weekly.bars("KO").count(), 3);
+
+        let monthly = history.aggregate(Period::Monthly);
+        assert_eq!(monthly.dates(), vec![d(1, 8), d(2, 1)]);
+        assert_eq!(monthly.bar("KO", d(1, 8)).unwrap().high, dec!(14));
+    }
+
+    #[test]
+    fn test_returns_are_aligned_across_tickers() {
+        // a BND le falta el 3 de enero: ese dia no se usa para ninguno
+        let history = PriceHistory::new()
+            .with_bar("KO", d(1, 2), Bar::flat(dec!(10)))
+            .with_bar("KO", d(1, 3), Bar::flat(dec!(11)))
+            .with_bar("KO", d(1, 4), Bar::flat(dec!(12)))
+            .with_bar("BND", d(1, 2), Bar::flat(dec!(100)))
+            .with_bar("BND", d(1, 4), Bar::flat(dec!(101)));
+
+        let simple = history.returns(Period::Daily, ReturnKind::Simple);
+        assert_eq!(simple.dates, vec![d(1, 4)]);
+        assert!((simple.get("KO").unwrap()[0] - 0.2).abs() < 1e-12);
+        assert!((simple.get("BND").unwrap()[0] - 0.01).abs() < 1e-12);
+
+        let log = history.returns(Period::Daily, ReturnKind::Log);
+        assert!((log.get("KO").unwrap()[0] - 1.2f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_missing_data_policies() {
+        // BND no cotiza el 3 de enero y KO recien aparece el 2
+        let history = PriceHistory::new()
+            .with_bar("BND", d(1, 1), Bar::flat(dec!(100)))
+            .with_bar("BND", d(1, 2), Bar::flat(dec!(100)))
+            .with_bar("BND", d(1, 4), Bar::flat(dec!(103)))
+            .with_bar("KO", d(1, 2), Bar::flat(dec!(10)))
+            .with_bar("KO", d(1, 3), Bar::flat(dec!(11)))
+            .with_bar("KO", d(1, 4), Bar::flat(dec!(12)));
+
+        let error = history
+            .returns_with(Period::Daily, ReturnKind::Simple, MissingData::Error)
+            .unwrap_err();
+        assert_eq!(
+            error.localize(Language::En),
+            "BND is missing prices on 1 dates (first 2024-01-03); choose how to fill them."
+        );
+
+        let filled = history.fill(MissingData::ForwardFill);
+        assert_eq!(filled.bar("BND", d(1, 3)).unwrap().close, dec!(100));
+        // el hueco antes del IPO no se inventa
+        assert!(filled.check_aligned().is_err());
+
+        let interpolated = history.fill(MissingData::Interpolate);
+        assert_eq!(interpolated.bar("BND", d(1, 3)).unwrap().close, dec!(101.5));
+
+        let dropped = history
+            .returns_with(Period::Daily, ReturnKind::Simple, MissingData::Drop)
+            .unwrap();
+        assert_eq!(dropped.dates, vec![d(1, 4)]);
+    }
+
+    #[test]
+    fn test_fill_prices() {
+        let bar = Bar::new(dec!(10), dec!(14), dec!(8), dec!(12));
+
+        assert_eq!(bar.price(FillPrice::Open), dec!(10));
+        assert_eq!(bar.price(FillPrice::Close), dec!(12));
+        assert_eq!(bar.price(FillPrice::Midpoint), dec!(11));
+        assert_eq!(bar.price(FillPrice::Typical), dec!(34) / dec!(3));
+    }
+}
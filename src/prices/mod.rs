@@ -0,0 +1,241 @@
+//! Fuentes de precios.
+//!
+//! Una `PriceSource` entrega el ultimo precio de un conjunto de tickers; `Portfolio` y
+//! `CryptoPortfolio` pueden refrescarse contra cualquiera. Para portafolios mixtos (stocks y
+//! cripto) se encadenan fuentes con `SourceChain`: cada ticker lo resuelve la primera fuente que
+//! lo conozca. Para APIs publicas conviene envolverlas en `RateLimitedSource` y `RetryingSource`.
+
+#[cfg(feature = "crypto")]
+pub mod coingecko;
+pub mod history;
+pub mod resilience;
+
+#[cfg(feature = "crypto")]
+pub use coingecko::CoinGeckoSource;
+pub use history::{
+    Bar, FillPrice, HistoryError, MissingData, Period, PriceHistory, ReturnKind, ReturnSeries,
+};
+pub use resilience::{RateLimitedSource, RetryingSource};
+
+use crate::crypto::CryptoPortfolio;
+use crate::i18n::{Language, Localize, language};
+use crate::{Map, Portfolio, PortfolioTarget};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// Algo que sabe cotizar tickers.
+pub trait PriceSource {
+    /// Ultimo precio de los tickers pedidos. Los tickers que la fuente no conoce simplemente no
+    /// aparecen en el resultado; el error queda para fallas de la fuente misma.
+    fn latest_prices(&self, tickers: &[&str]) -> Result<Map<String, Decimal>, PriceError>;
+}
+
+/// Errores al consultar una fuente de precios.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PriceError {
+    /// No se pudo contactar a la fuente.
+    Unavailable(String),
+
+    /// La fuente respondio algo que no entendemos.
+    InvalidResponse(String),
+}
+
+impl Localize for PriceError {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (PriceError::Unavailable(reason), Language::Es) => {
+                format!("Fuente de precios no disponible: {reason}")
+            }
+            (PriceError::Unavailable(reason), Language::En) => {
+                format!("Price source unavailable: {reason}")
+            }
+            (PriceError::InvalidResponse(reason), Language::Es) => {
+                format!("Respuesta invalida de la fuente de precios: {reason}")
+            }
+            (PriceError::InvalidResponse(reason), Language::En) => {
+                format!("Invalid price source response: {reason}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for PriceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for PriceError {}
+
+/// Precios fijos en memoria; util para tests o para cargar cotizaciones de un archivo.
+#[derive(Debug, Clone, Default)]
+pub struct StaticPrices {
+    prices: Map<String, Decimal>,
+}
+
+impl StaticPrices {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, ticker: &str, price: Decimal) -> Self {
+        self.prices.insert(ticker.into(), price);
+        self
+    }
+}
+
+impl PriceSource for StaticPrices {
+    fn latest_prices(&self, tickers: &[&str]) -> Result<Map<String, Decimal>, PriceError> {
+        Ok(tickers
+            .iter()
+            .filter_map(|t| Some((t.to_string(), *self.prices.get(*t)?)))
+            .collect())
+    }
+}
+
+/// Varias fuentes en orden de preferencia: a cada una solo se le piden los tickers que las
+/// anteriores no resolvieron.
+#[derive(Default)]
+pub struct SourceChain {
+    sources: Vec<Box<dyn PriceSource>>,
+}
+
+impl SourceChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, source: impl PriceSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+}
+
+impl PriceSource for SourceChain {
+    fn latest_prices(&self, tickers: &[&str]) -> Result<Map<String, Decimal>, PriceError> {
+        let mut prices = Map::new();
+
+        for source in &self.sources {
+            let pending: Vec<&str> = tickers
+                .iter()
+                .copied()
+                .filter(|t| !prices.contains_key(*t))
+                .collect();
+            if pending.is_empty() {
+                break;
+            }
+            prices.extend(source.latest_prices(&pending)?);
+        }
+
+        Ok(prices)
+    }
+}
+
+/// Tickers (sin repetir) que aparecen en un objetivo y en una lista de holdings.
+fn tickers<'a>(held: impl Iterator<Item = &'a str>, target: &'a PortfolioTarget) -> Vec<&'a str> {
+    let mut tickers: Vec<&str> = held
+        .chain(target.targets().iter().map(|(_, s)| s.name()))
+        .collect();
+    tickers.sort_unstable();
+    tickers.dedup();
+    tickers
+}
+
+impl Portfolio {
+    /// Actualiza todos los precios (holdings y objetivo) con una sola consulta a `source`.
+    /// Devuelve los tickers que la fuente no supo cotizar, que quedan con su precio anterior.
+    pub fn refresh_prices(&mut self, source: &dyn PriceSource) -> Result<Vec<String>, PriceError> {
+        let tickers: Vec<String> =
+            tickers(self.stocks().iter().map(|s| s.name()), self.allocation())
+                .into_iter()
+                .map(String::from)
+                .collect();
+
+        apply(&tickers, source, |ticker, price| {
+            self.update_price(ticker, price);
+        })
+    }
+}
+
+impl CryptoPortfolio {
+    /// Igual que `Portfolio::refresh_prices`.
+    pub fn refresh_prices(&mut self, source: &dyn PriceSource) -> Result<Vec<String>, PriceError> {
+        let tickers: Vec<String> = tickers(
+            self.holdings().iter().map(|(s, _)| s.name()),
+            self.allocation(),
+        )
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        apply(&tickers, source, |ticker, price| {
+            self.update_price(ticker, price);
+        })
+    }
+}
+
+fn apply(
+    tickers: &[String],
+    source: &dyn PriceSource,
+    mut update: impl FnMut(&str, Decimal),
+) -> Result<Vec<String>, PriceError> {
+    let requested: Vec<&str> = tickers.iter().map(String::as_str).collect();
+    let prices = source.latest_prices(&requested)?;
+
+    let mut missing = Vec::new();
+    for ticker in tickers {
+        match prices.get(ticker) {
+            Some(price) => update(ticker, *price),
+            None => missing.push(ticker.clone()),
+        }
+    }
+
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stock;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_chain_resolves_each_ticker_once() {
+        let stocks = StaticPrices::new().with("META", dec!(500));
+        let crypto = StaticPrices::new()
+            .with("BTC", dec!(60000))
+            .with("META", dec!(1));
+
+        let chain = SourceChain::new().with(stocks).with(crypto);
+        let prices = chain.latest_prices(&["META", "BTC", "XYZ"]).unwrap();
+
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices["META"], dec!(500));
+        assert_eq!(prices["BTC"], dec!(60000));
+    }
+
+    #[test]
+    fn test_refresh_mixed_portfolio() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("META", dec!(1))),
+            (dec!(50), Stock::new("BTC", dec!(1))),
+        ])
+        .unwrap();
+        let mut portfolio = CryptoPortfolio::new(target)
+            .with_holding(Stock::new("META", dec!(1)), dec!(2))
+            .with_holding(Stock::new("DOGE", dec!(1)), dec!(10));
+
+        let source = SourceChain::new()
+            .with(StaticPrices::new().with("META", dec!(500)))
+            .with(StaticPrices::new().with("BTC", dec!(60000)));
+
+        let missing = portfolio.refresh_prices(&source).unwrap();
+
+        assert_eq!(missing, vec!["DOGE".to_string()]);
+        assert_eq!(portfolio.total_value(), dec!(1010));
+        assert_eq!(
+            portfolio.allocation().targets()[1].1.current_price(),
+            dec!(60000)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Decoradores para fuentes de precios publicas.
+//!
+//! Las APIs gratuitas (Yahoo, CoinGecko) cortan a quien les pega muy seguido y fallan de vez en
+//! cuando sin que sea culpa de nadie. `RateLimitedSource` espera lo necesario entre consultas y
+//! `RetryingSource` reintenta las fallas transitorias con espera exponencial y jitter. Se
+//! combinan envolviendo una dentro de otra:
+//!
+//! ```text
+//! RetryingSource::new(RateLimitedSource::per_minute(CoinGeckoSource::default(), 30))
+//! ```
+
+use super::{PriceError, PriceSource};
+use crate::Map;
+use crate::rng::{Rng, SeededRng};
+use rust_decimal::Decimal;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Deja pasar como maximo una consulta cada `interval`; si llega antes, espera.
+#[derive(Debug)]
+pub struct RateLimitedSource<S> {
+    inner: S,
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+impl<S: PriceSource> RateLimitedSource<S> {
+    pub fn new(inner: S, interval: Duration) -> Self {
+        Self {
+            inner,
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// A lo mas `calls` consultas por minuto.
+    pub fn per_minute(inner: S, calls: u32) -> Self {
+        Self::new(inner, Duration::from_secs(60) / calls.max(1))
+    }
+}
+
+impl<S: PriceSource> PriceSource for RateLimitedSource<S> {
+    fn latest_prices(&self, tickers: &[&str]) -> Result<Map<String, Decimal>, PriceError> {
+        // el lock se mantiene durante la consulta para que dos threads no salgan juntos
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = *last {
+            let elapsed = previous.elapsed();
+            if elapsed < self.interval {
+                thread::sleep(self.interval - elapsed);
+            }
+        }
+        *last = Some(Instant::now());
+
+        self.inner.latest_prices(tickers)
+    }
+}
+
+/// Reintenta las consultas que fallan con `PriceError::Unavailable`. Una respuesta invalida no
+/// se reintenta: volver a preguntar daria lo mismo.
+///
+/// Antes del reintento `n` (desde 0) espera un tiempo al azar entre 0 y
+/// `min(base * 2^n, max_delay)` ("full jitter"), para que muchos clientes que fallaron a la vez
+/// no vuelvan todos juntos.
+#[derive(Debug)]
+pub struct RetryingSource<S> {
+    inner: S,
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    rng: Mutex<SeededRng>,
+}
+
+impl<S: PriceSource> RetryingSource<S> {
+    /// 4 intentos, partiendo en 500 ms y con espera maxima de 30 s.
+    pub fn new(inner: S) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self {
+            inner,
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            rng: Mutex::new(SeededRng::new(seed)),
+        }
+    }
+
+    /// Intentos en total, incluido el primero (al menos 1).
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn with_delays(mut self, base: Duration, max: Duration) -> Self {
+        self.base_delay = base;
+        self.max_delay = max;
+        self
+    }
+
+    /// Semilla del jitter, para que las esperas sean reproducibles.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(SeededRng::new(seed));
+        self
+    }
+
+    /// Espera maxima antes del reintento `retry` (desde 0), sin jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.base_delay
+            .checked_mul(2u32.saturating_pow(retry))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    fn jittered(&self, retry: u32) -> Duration {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        self.backoff(retry).mul_f64(rng.next_f64())
+    }
+}
+
+impl<S: PriceSource> PriceSource for RetryingSource<S> {
+    fn latest_prices(&self, tickers: &[&str]) -> Result<Map<String, Decimal>, PriceError> {
+        let mut retry = 0;
+        loop {
+            match self.inner.latest_prices(tickers) {
+                Err(PriceError::Unavailable(_)) if retry + 1 < self.max_attempts => {
+                    thread::sleep(self.jittered(retry));
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prices::StaticPrices;
+    use rust_decimal_macros::dec;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Falla con `error` las primeras `failures` veces.
+    struct Flaky {
+        failures: u32,
+        error: PriceError,
+        calls: AtomicU32,
+    }
+
+    impl PriceSource for &Flaky {
+        fn latest_prices(&self, tickers: &[&str]) -> Result<Map<String, Decimal>, PriceError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(self.error.clone());
+            }
+            StaticPrices::new()
+                .with("META", dec!(500))
+                .latest_prices(tickers)
+        }
+    }
+
+    fn flaky(failures: u32, error: PriceError) -> Flaky {
+        Flaky {
+            failures,
+            error,
+            calls: AtomicU32::new(0),
+        }
+    }
+
+    #[test]
+    fn test_retries_transient_failures_only() {
+        let source = flaky(2, PriceError::Unavailable("timeout".into()));
+        let retrying = RetryingSource::new(&source).with_delays(Duration::ZERO, Duration::ZERO);
+        assert_eq!(
+            retrying.latest_prices(&["META"]).unwrap()["META"],
+            dec!(500)
+        );
+        assert_eq!(source.calls.load(Ordering::SeqCst), 3);
+
+        let source = flaky(5, PriceError::Unavailable("timeout".into()));
+        let retrying = RetryingSource::new(&source)
+            .with_max_attempts(3)
+            .with_delays(Duration::ZERO, Duration::ZERO);
+        assert!(retrying.latest_prices(&["META"]).is_err());
+        assert_eq!(source.calls.load(Ordering::SeqCst), 3);
+
+        let source = flaky(1, PriceError::InvalidResponse("html".into()));
+        assert!(
+            RetryingSource::new(&source)
+                .latest_prices(&["META"])
+                .is_err()
+        );
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap_and_jitter_stays_below() {
+        let retrying = RetryingSource::new(StaticPrices::new())
+            .with_delays(Duration::from_millis(100), Duration::from_millis(500))
+            .with_seed(7);
+
+        let backoff: Vec<u128> = (0..5).map(|n| retrying.backoff(n).as_millis()).collect();
+        assert_eq!(backoff, [100, 200, 400, 500, 500]);
+        assert!((0..5).all(|n| retrying.jittered(n) <= retrying.backoff(n)));
+    }
+
+    #[test]
+    fn test_rate_limit_spaces_calls() {
+        let interval = Duration::from_millis(20);
+        let limited = RateLimitedSource::new(StaticPrices::new(), interval);
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            limited.latest_prices(&["META"]).unwrap();
+        }
+        assert!(start.elapsed() >= interval * 2);
+    }
+}
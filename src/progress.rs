@@ -0,0 +1,197 @@
+//! Progreso y cancelacion de calculos largos.
+//!
+//! Un backtest de varios años o un Monte Carlo con millones de caminos puede tardar minutos. Las
+//! variantes `*_with` de esos calculos reciben un `Monitor`, que avisa el avance (a un callback
+//! y/o a un `ProgressWatch` que otro thread puede consultar) y revisa en cada paso si alguien
+//! pidio cancelar con un `CancellationToken`.
+
+use crate::i18n::{Language, Localize, language};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Avance de un calculo: `done` de `total` pasos (dias de un backtest, caminos de un Monte
+/// Carlo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    /// Fraccion completada, entre 0 y 1. Un calculo sin pasos se considera terminado.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        self.done as f64 / self.total as f64
+    }
+}
+
+/// Token para pedir que un calculo se detenga. Los clones comparten el mismo estado, asi que se
+/// puede entregar uno al calculo y cancelar desde otro thread (p. ej. al cerrar la conexion).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Ultimo avance publicado por un `Monitor`, al estilo de un canal `watch`: no hay cola, solo
+/// se lee el valor mas reciente. Clonar es barato.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressWatch {
+    done: Arc<AtomicUsize>,
+    total: Arc<AtomicUsize>,
+}
+
+impl ProgressWatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self) -> Progress {
+        Progress {
+            done: self.done.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+        }
+    }
+
+    fn publish(&self, progress: Progress) {
+        self.total.store(progress.total, Ordering::Relaxed);
+        self.done.store(progress.done, Ordering::Relaxed);
+    }
+}
+
+/// El calculo se detuvo porque se cancelo su `CancellationToken`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled {
+    /// Hasta donde alcanzo a llegar.
+    pub progress: Progress,
+}
+
+impl Localize for Cancelled {
+    fn localize(&self, language: Language) -> String {
+        let Progress { done, total } = self.progress;
+        match language {
+            Language::Es => format!("Calculo cancelado despues de {done} de {total} pasos."),
+            Language::En => format!("Computation cancelled after {done} of {total} steps."),
+        }
+    }
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Lo que se le pasa a un calculo largo para seguirlo y poder cancelarlo. `Monitor::default()`
+/// no reporta nada y nunca cancela.
+#[derive(Default)]
+pub struct Monitor<'a> {
+    callback: Option<Box<dyn FnMut(Progress) + 'a>>,
+    watch: Option<ProgressWatch>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl<'a> Monitor<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Llama a `callback` despues de cada paso (p. ej. para mover una barra de progreso).
+    pub fn with_callback(mut self, callback: impl FnMut(Progress) + 'a) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Publica el avance en `watch` despues de cada paso.
+    pub fn with_watch(mut self, watch: ProgressWatch) -> Self {
+        self.watch = Some(watch);
+        self
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Revisa la cancelacion antes del paso `done` (contando desde cero); lo llaman los calculos.
+    pub(crate) fn check(&self, done: usize, total: usize) -> Result<(), Cancelled> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(Cancelled {
+                progress: Progress { done, total },
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Avisa que se completaron `done` de `total` pasos.
+    pub(crate) fn report(&mut self, done: usize, total: usize) {
+        let progress = Progress { done, total };
+        if let Some(watch) = &self.watch {
+            watch.publish(progress);
+        }
+        if let Some(callback) = &mut self.callback {
+            callback(progress);
+        }
+    }
+}
+
+impl fmt::Debug for Monitor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Monitor")
+            .field("callback", &self.callback.is_some())
+            .field("watch", &self.watch)
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monitor_reports_and_cancels() {
+        let token = CancellationToken::new();
+        let watch = ProgressWatch::new();
+        let mut seen = Vec::new();
+
+        {
+            let mut monitor = Monitor::new()
+                .with_callback(|p: Progress| seen.push(p.done))
+                .with_watch(watch.clone())
+                .with_cancellation(token.clone());
+
+            assert!(monitor.check(0, 4).is_ok());
+            monitor.report(1, 4);
+            monitor.report(2, 4);
+
+            token.cancel();
+            let error = monitor.check(2, 4).unwrap_err();
+            assert_eq!(error.progress.fraction(), 0.5);
+            assert_eq!(
+                error.localize(Language::En),
+                "Computation cancelled after 2 of 4 steps."
+            );
+        }
+
+        assert_eq!(seen, vec![1, 2]);
+        assert_eq!(watch.get(), Progress { done: 2, total: 4 });
+    }
+}
@@ -0,0 +1,385 @@
+//! Proyecciones de valor futuro por Monte Carlo.
+//!
+//! Las simulaciones se hacen en `f64` y no en `Decimal`: aca no estamos contando plata real sino
+//! estimando distribuciones con miles de caminos, y la velocidad importa mas que el ultimo
+//! decimal. Igual se puede simular en `Decimal` (ver `numeric::Numeric`) si se quiere comparar
+//! contra la contabilidad sin conversiones.
+
+use crate::numeric::Numeric;
+use crate::progress::{Cancelled, Monitor};
+use crate::rng::{Rng, SeededRng, SimulationConfig};
+
+/// Parametros de una simulacion de Monte Carlo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarloParams {
+    /// Retorno anual esperado, p. ej. `0.07` para un 7%.
+    pub expected_return: f64,
+
+    /// Volatilidad anual (desviacion estandar de los retornos), p. ej. `0.15`.
+    pub volatility: f64,
+
+    /// Cantidad de caminos a simular.
+    pub paths: usize,
+
+    /// Semilla del generador, para que dos corridas con los mismos parametros den lo mismo.
+    pub seed: u64,
+}
+
+impl MonteCarloParams {
+    pub fn new(expected_return: f64, volatility: f64) -> Self {
+        Self {
+            expected_return,
+            volatility,
+            paths: 10_000,
+            seed: 0,
+        }
+    }
+
+    pub fn with_paths(mut self, paths: usize) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Semilla y caminos de estos parametros, con un horizonte de `months` meses.
+    pub fn simulation(&self, months: u32) -> SimulationConfig {
+        SimulationConfig::new(self.seed, self.paths, months)
+    }
+
+    /// Misma simulacion pero en terminos reales: el retorno esperado se descuenta por la inflacion
+    /// anual (ecuacion de Fisher) y los resultados quedan en moneda de hoy. Esto asume que los
+    /// aportes mensuales se reajustan con la inflacion, que es lo razonable en planes largos.
+    pub fn real(mut self, annual_inflation: f64) -> Self {
+        self.expected_return = (1.0 + self.expected_return) / (1.0 + annual_inflation) - 1.0;
+        self
+    }
+}
+
+/// Simula el valor final de un portafolio despues de `months` meses, con `SeededRng` y la
+/// semilla y caminos de `params`. Ver `simulate`.
+pub fn simulate_final_values<N: Numeric>(
+    initial: N,
+    monthly_contribution: N,
+    months: u32,
+    params: &MonteCarloParams,
+) -> Vec<N> {
+    simulate::<SeededRng, N>(
+        initial,
+        monthly_contribution,
+        params,
+        &params.simulation(months),
+    )
+}
+
+/// Simula `config.paths` caminos de `config.horizon` meses con el generador `R`.
+///
+/// Cada mes el portafolio crece con un retorno log-normal y luego recibe el aporte mensual. La
+/// media del retorno logaritmico se ajusta (`- σ²/2`) para que el retorno anual esperado de la
+/// simulacion sea efectivamente `expected_return`. La semilla y caminos de `params` se ignoran:
+/// manda `config`.
+pub fn simulate<R: Rng, N: Numeric>(
+    initial: N,
+    monthly_contribution: N,
+    params: &MonteCarloParams,
+    config: &SimulationConfig,
+) -> Vec<N> {
+    match simulate_with::<R, N>(
+        initial,
+        monthly_contribution,
+        params,
+        config,
+        &mut Monitor::default(),
+    ) {
+        Ok(values) => values,
+        Err(_) => unreachable!("un monitor sin token nunca cancela"),
+    }
+}
+
+/// Igual que `simulate`, reportando el avance camino a camino a `monitor` y deteniendose si se
+/// cancela.
+pub fn simulate_with<R: Rng, N: Numeric>(
+    initial: N,
+    monthly_contribution: N,
+    params: &MonteCarloParams,
+    config: &SimulationConfig,
+    monitor: &mut Monitor,
+) -> Result<Vec<N>, Cancelled> {
+    let mut rng: R = config.rng();
+    let convert = |x: f64| N::from_f64(x).unwrap_or(N::ZERO);
+    let sigma = convert(params.volatility / 12f64.sqrt());
+    let mu = convert((1.0 + params.expected_return).ln() / 12.0) - sigma * sigma / convert(2.0);
+
+    let mut values = Vec::with_capacity(config.paths);
+    for path in 0..config.paths {
+        monitor.check(path, config.paths)?;
+
+        let mut value = initial;
+        for _ in 0..config.horizon {
+            value *= (mu + sigma * convert(rng.next_normal())).exp();
+            value += monthly_contribution;
+        }
+        values.push(value);
+        monitor.report(path + 1, config.paths);
+    }
+
+    Ok(values)
+}
+
+/// Bootstrapping historico: en vez de suponer retornos normales, cada mes de cada camino toma al
+/// azar (con reposicion) uno de los retornos mensuales observados en `history`. Vacio si no hay
+/// historia.
+pub fn bootstrap<R: Rng>(
+    initial: f64,
+    monthly_contribution: f64,
+    history: &[f64],
+    config: &SimulationConfig,
+) -> Vec<f64> {
+    if history.is_empty() {
+        return Vec::new();
+    }
+
+    let mut rng: R = config.rng();
+    (0..config.paths)
+        .map(|_| {
+            let mut value = initial;
+            for _ in 0..config.horizon {
+                value *= 1.0 + history[rng.next_below(history.len())];
+                value += monthly_contribution;
+            }
+            value
+        })
+        .collect()
+}
+
+/// Retiro mensual en la fase de desacumulacion (p. ej. en la jubilacion).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Withdrawal {
+    /// Monto fijo cada mes; para que se reajuste con la inflacion, simular en terminos reales
+    /// (`MonteCarloParams::real`).
+    Fixed(f64),
+
+    /// Porcentaje anual del valor que haya en ese momento (p. ej. `0.04` para la regla del 4%),
+    /// retirado en cuotas mensuales. Con esto el portafolio se achica pero nunca llega a cero.
+    Percentage(f64),
+}
+
+impl Withdrawal {
+    fn amount(&self, value: f64) -> f64 {
+        match self {
+            Self::Fixed(amount) => *amount,
+            Self::Percentage(rate) => value * rate / 12.0,
+        }
+    }
+}
+
+/// Resultado de `simulate_withdrawals`: como termino cada camino.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepletionAnalysis {
+    /// Valor al final del horizonte de cada camino (cero si se agoto).
+    pub final_values: Vec<f64>,
+
+    /// Mes (desde 1) en que se agoto cada camino, o `None` si llego al final con plata.
+    pub depleted_at: Vec<Option<u32>>,
+}
+
+impl DepletionAnalysis {
+    /// Probabilidad (entre 0 y 1) de que el portafolio se agote antes del horizonte.
+    pub fn depletion_probability(&self) -> f64 {
+        if self.depleted_at.is_empty() {
+            return 0.0;
+        }
+        self.depleted_at.iter().flatten().count() as f64 / self.depleted_at.len() as f64
+    }
+
+    /// Probabilidad de que el portafolio se haya agotado al terminar el año `year` (desde 1).
+    pub fn depleted_by_year(&self, year: u32) -> f64 {
+        if self.depleted_at.is_empty() {
+            return 0.0;
+        }
+        let depleted = self
+            .depleted_at
+            .iter()
+            .flatten()
+            .filter(|month| Self::year_of(**month) <= year)
+            .count();
+        depleted as f64 / self.depleted_at.len() as f64
+    }
+
+    /// Año (desde 1) en que se agota la mediana de los caminos; `None` si la mayoria llega al
+    /// final del horizonte con plata.
+    pub fn median_exhaustion_year(&self) -> Option<u32> {
+        let mut months: Vec<u32> = self.depleted_at.iter().flatten().copied().collect();
+        if months.len() * 2 <= self.depleted_at.len() {
+            return None;
+        }
+        months.sort_unstable();
+        // entre todos los caminos ordenados (los que no se agotan van al final), el del medio
+        Some(Self::year_of(months[(self.depleted_at.len() - 1) / 2]))
+    }
+
+    fn year_of(month: u32) -> u32 {
+        month.div_ceil(12)
+    }
+}
+
+/// Simula la fase de retiros: cada mes el portafolio crece con el mismo retorno log-normal que
+/// `simulate` y luego se retira `withdrawal`. Si el retiro no alcanza a cubrirse, el camino se
+/// agota ese mes y queda en cero.
+pub fn simulate_withdrawals<R: Rng>(
+    initial: f64,
+    withdrawal: Withdrawal,
+    params: &MonteCarloParams,
+    config: &SimulationConfig,
+) -> DepletionAnalysis {
+    let mut rng: R = config.rng();
+    let sigma = params.volatility / 12f64.sqrt();
+    let mu = (1.0 + params.expected_return).ln() / 12.0 - sigma * sigma / 2.0;
+
+    let mut final_values = Vec::with_capacity(config.paths);
+    let mut depleted_at = Vec::with_capacity(config.paths);
+    for _ in 0..config.paths {
+        let mut value = initial;
+        let mut depleted = None;
+        for month in 1..=config.horizon {
+            value *= (mu + sigma * rng.next_normal()).exp();
+            value -= withdrawal.amount(value);
+            if value <= 0.0 {
+                value = 0.0;
+                depleted = Some(month);
+                break;
+            }
+        }
+        final_values.push(value);
+        depleted_at.push(depleted);
+    }
+
+    DepletionAnalysis {
+        final_values,
+        depleted_at,
+    }
+}
+
+/// Percentil `p` (entre 0 y 1) de una muestra, interpolando linealmente.
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let rank = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let high = rank.ceil() as usize;
+
+    Some(sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_zero_volatility_is_deterministic_growth() {
+        let params = MonteCarloParams::new(0.10, 0.0).with_paths(3);
+        let values = simulate_final_values(100.0, 0.0, 12, &params);
+
+        for value in values {
+            assert!((value - 110.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_decimal_backend_matches_f64() {
+        let params = MonteCarloParams::new(0.10, 0.0).with_paths(1);
+        let values = simulate_final_values(dec!(100), dec!(0), 12, &params);
+
+        assert!((values[0] - dec!(110)).abs() < dec!(0.0001));
+    }
+
+    #[test]
+    fn test_same_seed_reproduces_results() {
+        let params = MonteCarloParams::new(0.07, 0.2).with_paths(50).with_seed(3);
+
+        assert_eq!(
+            simulate_final_values(1000.0, 10.0, 24, &params),
+            simulate_final_values(1000.0, 10.0, 24, &params)
+        );
+    }
+
+    #[test]
+    fn test_config_controls_rng_type_and_reproducibility() {
+        use crate::rng::Xoshiro256;
+
+        let params = MonteCarloParams::new(0.07, 0.2);
+        let config = SimulationConfig::new(9, 20, 12);
+        let xoshiro = simulate::<Xoshiro256, f64>(100.0, 0.0, &params, &config);
+
+        assert_eq!(xoshiro.len(), 20);
+        assert_eq!(
+            xoshiro,
+            simulate::<Xoshiro256, f64>(100.0, 0.0, &params, &config)
+        );
+        assert_ne!(
+            xoshiro,
+            simulate::<SeededRng, f64>(100.0, 0.0, &params, &config)
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_resamples_history() {
+        let config = SimulationConfig::new(1, 5, 3);
+        let values = bootstrap::<SeededRng>(100.0, 0.0, &[0.1, 0.1], &config);
+
+        // con un solo retorno posible el resultado es deterministico
+        for value in &values {
+            assert!((value - 133.1).abs() < 1e-9);
+        }
+        assert!(bootstrap::<SeededRng>(100.0, 0.0, &[], &config).is_empty());
+    }
+
+    #[test]
+    fn test_real_projection_discounts_inflation() {
+        let params = MonteCarloParams::new(0.05, 0.0).with_paths(1).real(0.05);
+        let values = simulate_final_values(100.0, 0.0, 120, &params);
+
+        assert!((values[0] - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fixed_withdrawals_deplete() {
+        let params = MonteCarloParams::new(0.0, 0.0);
+        let config = SimulationConfig::new(1, 10, 36);
+
+        // 1200 sin retorno alcanzan justo para 12 retiros de 100
+        let fixed =
+            simulate_withdrawals::<SeededRng>(1200.0, Withdrawal::Fixed(100.0), &params, &config);
+        assert_eq!(fixed.depletion_probability(), 1.0);
+        assert_eq!(fixed.depleted_at[0], Some(12));
+        assert_eq!(fixed.median_exhaustion_year(), Some(1));
+        assert_eq!(fixed.depleted_by_year(1), 1.0);
+
+        let percentage = simulate_withdrawals::<SeededRng>(
+            1200.0,
+            Withdrawal::Percentage(0.04),
+            &params,
+            &config,
+        );
+        assert_eq!(percentage.depletion_probability(), 0.0);
+        assert_eq!(percentage.median_exhaustion_year(), None);
+        assert!(percentage.final_values[0] < 1200.0);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(percentile(&values, 0.5), Some(3.0));
+        assert_eq!(percentile(&values, 0.25), Some(2.0));
+        assert_eq!(percentile(&[], 0.5), None);
+    }
+}
@@ -0,0 +1,550 @@
+//! Publicacion de eventos de rebalanceo.
+//!
+//! Otros sistemas (notificaciones, contabilidad, un dashboard) quieren enterarse cuando se genera
+//! o se aplica una sugerencia sin tener que consultar al motor. `EventPublisher` es el punto de
+//! extension: `rebalance_and_publish`, `apply_and_publish` y `reconcile_and_publish` hacen lo
+//! mismo que sus versiones normales y ademas publican el evento. Son el borde del proceso, asi
+//! que son las que respetan el modo dry-run (ver `dry_run`): calculan sobre una copia y le pasan
+//! el evento a `EventPublisher::dry_run`. `LogPublisher` deja por escrito, en el idioma actual, lo
+//! que se hizo o lo que se habria hecho.
+//!
+//! `LinePublisher` escribe cada evento en una linea como `clave<TAB>json`, con el id de la
+//! sugerencia como clave, para cualquier consumidor de lineas (p. ej. un productor de colas que
+//! lea su stdin). Cualquier otro destino solo tiene que implementar el trait.
+
+use crate::costs::CostModel;
+use crate::date::{Date, Timestamp};
+use crate::dry_run::{RunMode, run_mode};
+use crate::error::EventError;
+use crate::execution::{Execution, Fill, Order, Side};
+use crate::i18n::{Language, Localize, language};
+use crate::id::SuggestionId;
+use crate::reconcile::Reconciliation;
+use crate::{Portfolio, RebalanceSuggestion};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Algo que paso con una sugerencia.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RebalanceEvent {
+    /// Se calculo una sugerencia.
+    SuggestionGenerated {
+        at: Timestamp,
+        id: SuggestionId,
+        orders: Vec<Order>,
+    },
+
+    /// Se ejecutaron las ordenes de una sugerencia.
+    SuggestionApplied {
+        at: Timestamp,
+        id: SuggestionId,
+        orders: Vec<Order>,
+        commissions: Decimal,
+
+        /// Caja del portafolio despues de ejecutar.
+        cash: Decimal,
+    },
+}
+
+impl RebalanceEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RebalanceEvent::SuggestionGenerated { .. } => "suggestion_generated",
+            RebalanceEvent::SuggestionApplied { .. } => "suggestion_applied",
+        }
+    }
+
+    /// Clave con la que particionar: la sugerencia, para que sus eventos lleguen en orden.
+    pub fn key(&self) -> String {
+        match self {
+            RebalanceEvent::SuggestionGenerated { id, .. }
+            | RebalanceEvent::SuggestionApplied { id, .. } => id.to_string(),
+        }
+    }
+
+    fn orders(&self) -> &[Order] {
+        match self {
+            RebalanceEvent::SuggestionGenerated { orders, .. }
+            | RebalanceEvent::SuggestionApplied { orders, .. } => orders,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let (at, id, orders) = match self {
+            RebalanceEvent::SuggestionGenerated { at, id, orders }
+            | RebalanceEvent::SuggestionApplied { at, id, orders, .. } => (at, id, orders),
+        };
+        let orders: Vec<String> = orders
+            .iter()
+            .map(|o| {
+                let side = match o.side {
+                    Side::Sell => "sell",
+                    Side::Buy => "buy",
+                };
+                format!(
+                    r#"{{"ticker":{},"side":"{side}","units":{}}}"#,
+                    json_string(&o.ticker),
+                    o.units
+                )
+            })
+            .collect();
+
+        let mut json = format!(
+            r#"{{"event":"{}","at":"{at}","suggestion":"{id}","orders":[{}]"#,
+            self.name(),
+            orders.join(",")
+        );
+        if let RebalanceEvent::SuggestionApplied {
+            commissions, cash, ..
+        } = self
+        {
+            // como string para no perder precision en un double
+            json.push_str(&format!(
+                r#","commissions":"{}","cash":"{}""#,
+                commissions.normalize(),
+                cash.normalize()
+            ));
+        }
+        json.push('}');
+        json
+    }
+}
+
+impl Localize for RebalanceEvent {
+    fn localize(&self, language: Language) -> String {
+        let orders: Vec<String> = self
+            .orders()
+            .iter()
+            .map(|o| match (o.side, language) {
+                (Side::Sell, Language::Es) => format!("vender {} {}", o.units, o.ticker),
+                (Side::Buy, Language::Es) => format!("comprar {} {}", o.units, o.ticker),
+                (Side::Sell, Language::En) => format!("sell {} {}", o.units, o.ticker),
+                (Side::Buy, Language::En) => format!("buy {} {}", o.units, o.ticker),
+            })
+            .collect();
+        let orders = orders.join(", ");
+
+        match (self, language) {
+            (RebalanceEvent::SuggestionGenerated { at, id, .. }, Language::Es) => {
+                format!("{at} sugerencia {id}: {orders}")
+            }
+            (RebalanceEvent::SuggestionGenerated { at, id, .. }, Language::En) => {
+                format!("{at} suggestion {id}: {orders}")
+            }
+            (
+                RebalanceEvent::SuggestionApplied {
+                    at,
+                    id,
+                    commissions,
+                    cash,
+                    ..
+                },
+                Language::Es,
+            ) => format!(
+                "{at} ejecutada la sugerencia {id}: {orders}; comisiones {}, caja {}",
+                commissions.normalize(),
+                cash.normalize()
+            ),
+            (
+                RebalanceEvent::SuggestionApplied {
+                    at,
+                    id,
+                    commissions,
+                    cash,
+                    ..
+                },
+                Language::En,
+            ) => format!(
+                "{at} applied suggestion {id}: {orders}; commissions {}, cash {}",
+                commissions.normalize(),
+                cash.normalize()
+            ),
+        }
+    }
+}
+
+impl fmt::Display for RebalanceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// `value` como string JSON, con comillas; lo usan tambien los payloads de `webhook`.
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Destino de los eventos.
+pub trait EventPublisher {
+    fn publish(&mut self, event: &RebalanceEvent) -> io::Result<()>;
+
+    /// Lo que se llama en vez de `publish` en modo dry-run (ver `dry_run`): el evento que se
+    /// habria publicado, para anotarlo sin mandarlo. Por defecto no hace nada.
+    fn dry_run(&mut self, event: &RebalanceEvent) -> io::Result<()> {
+        let _ = event;
+        Ok(())
+    }
+}
+
+/// Publica `event` en `publisher`, o se lo pasa a `EventPublisher::dry_run` si `mode` es
+/// dry-run.
+pub fn publish_in(
+    mode: RunMode,
+    publisher: &mut impl EventPublisher,
+    event: &RebalanceEvent,
+) -> io::Result<()> {
+    match mode {
+        RunMode::Live => publisher.publish(event),
+        RunMode::DryRun => publisher.dry_run(event),
+    }
+}
+
+/// Guarda los eventos en memoria; util para tests o para despacharlos en lote.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryPublisher {
+    events: Vec<RebalanceEvent>,
+
+    /// Los que llegaron en modo dry-run.
+    dry_run: Vec<RebalanceEvent>,
+}
+
+impl InMemoryPublisher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> &[RebalanceEvent] {
+        &self.events
+    }
+
+    /// Eventos que se habrian publicado en modo dry-run.
+    pub fn dry_run_events(&self) -> &[RebalanceEvent] {
+        &self.dry_run
+    }
+}
+
+impl EventPublisher for InMemoryPublisher {
+    fn publish(&mut self, event: &RebalanceEvent) -> io::Result<()> {
+        self.events.push(event.clone());
+        Ok(())
+    }
+
+    fn dry_run(&mut self, event: &RebalanceEvent) -> io::Result<()> {
+        self.dry_run.push(event.clone());
+        Ok(())
+    }
+}
+
+/// Escribe un evento por linea como `clave<TAB>json`.
+#[derive(Debug)]
+pub struct LinePublisher<W: Write> {
+    out: W,
+}
+
+impl<W: Write> LinePublisher<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> EventPublisher for LinePublisher<W> {
+    fn publish(&mut self, event: &RebalanceEvent) -> io::Result<()> {
+        writeln!(self.out, "{}\t{}", event.key(), event.to_json())?;
+        self.out.flush()
+    }
+}
+
+/// Escribe cada evento como una linea legible (ver `Localize`); los de modo dry-run van con el
+/// prefijo `[dry-run]`, para saber que se habria hecho sin que pase nada.
+#[derive(Debug)]
+pub struct LogPublisher<W: Write> {
+    out: W,
+}
+
+impl<W: Write> LogPublisher<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> EventPublisher for LogPublisher<W> {
+    fn publish(&mut self, event: &RebalanceEvent) -> io::Result<()> {
+        writeln!(self.out, "{event}")?;
+        self.out.flush()
+    }
+
+    fn dry_run(&mut self, event: &RebalanceEvent) -> io::Result<()> {
+        writeln!(self.out, "[dry-run] {event}")?;
+        self.out.flush()
+    }
+}
+
+/// Errores de `apply_and_publish` y `reconcile_and_publish`.
+#[derive(Debug)]
+pub enum PublishError {
+    /// No se pudieron ejecutar las ordenes; no se publico nada.
+    Apply(EventError),
+
+    /// Las ordenes se ejecutaron pero el evento no se pudo publicar.
+    Publish(io::Error),
+}
+
+impl Localize for PublishError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            PublishError::Apply(error) => error.localize(language),
+            PublishError::Publish(error) => match language {
+                Language::Es => format!("Se ejecuto pero no se pudo publicar el evento: {error}"),
+                Language::En => format!("Executed but the event could not be published: {error}"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for PublishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for PublishError {}
+
+impl Portfolio {
+    /// Calcula la sugerencia de rebalanceo y publica `SuggestionGenerated`.
+    pub fn rebalance_and_publish(
+        &self,
+        publisher: &mut impl EventPublisher,
+        at: Timestamp,
+    ) -> io::Result<RebalanceSuggestion<'_>> {
+        let suggestion = self.rebalance_portfolio();
+        let event = RebalanceEvent::SuggestionGenerated {
+            at,
+            id: suggestion.id,
+            orders: suggestion.orders(),
+        };
+        publish_in(run_mode(), publisher, &event)?;
+
+        Ok(suggestion)
+    }
+
+    /// Ejecuta las ordenes de la sugerencia `id` (ver `apply`) y publica `SuggestionApplied`. En
+    /// modo dry-run no cambia el portafolio y el evento (con la caja que habria quedado) va a
+    /// `EventPublisher::dry_run`.
+    pub fn apply_and_publish(
+        &mut self,
+        id: SuggestionId,
+        orders: &[Order],
+        date: Date,
+        costs: &CostModel,
+        publisher: &mut impl EventPublisher,
+        at: Timestamp,
+    ) -> Result<Execution, PublishError> {
+        let mode = run_mode();
+        let mut preview = mode.is_dry_run().then(|| self.clone());
+        let portfolio = preview.as_mut().unwrap_or(self);
+        let execution = portfolio
+            .apply(orders, date, costs)
+            .map_err(PublishError::Apply)?;
+        let event = RebalanceEvent::SuggestionApplied {
+            at,
+            id,
+            orders: orders.to_vec(),
+            commissions: execution.commissions(),
+            cash: portfolio.cash,
+        };
+        publish_in(mode, publisher, &event).map_err(PublishError::Publish)?;
+
+        Ok(execution)
+    }
+
+    /// Concilia las ejecuciones `fills` de la sugerencia `id` contra `planned` (ver `reconcile`)
+    /// y publica `SuggestionApplied` con lo que de verdad se ejecuto. En modo dry-run no cambia el
+    /// portafolio y el evento va a `EventPublisher::dry_run`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn reconcile_and_publish(
+        &mut self,
+        id: SuggestionId,
+        planned: &[Order],
+        fills: &[Fill],
+        date: Date,
+        costs: &CostModel,
+        publisher: &mut impl EventPublisher,
+        at: Timestamp,
+    ) -> Result<Reconciliation, PublishError> {
+        let mode = run_mode();
+        let mut preview = mode.is_dry_run().then(|| self.clone());
+        let portfolio = preview.as_mut().unwrap_or(self);
+        let reconciliation = portfolio
+            .reconcile(planned, fills, date, costs)
+            .map_err(PublishError::Apply)?;
+        let event = RebalanceEvent::SuggestionApplied {
+            at,
+            id,
+            orders: fills
+                .iter()
+                .map(|fill| Order {
+                    ticker: fill.ticker.clone(),
+                    side: fill.side,
+                    units: fill.units,
+                })
+                .collect(),
+            commissions: reconciliation.execution.commissions(),
+            cash: portfolio.cash,
+        };
+        publish_in(mode, publisher, &event).map_err(PublishError::Publish)?;
+
+        Ok(reconciliation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("CASH", dec!(1)); 100],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(25))),
+        }
+    }
+
+    fn at() -> Timestamp {
+        Timestamp::from_date(Date::new(2024, 1, 2).unwrap())
+    }
+
+    #[test]
+    fn test_generated_then_applied() {
+        let mut portfolio = portfolio();
+        let mut publisher = InMemoryPublisher::new();
+        let (id, orders) = {
+            let suggestion = portfolio
+                .rebalance_and_publish(&mut publisher, at())
+                .unwrap();
+            (suggestion.id, suggestion.orders())
+        };
+        portfolio
+            .apply_and_publish(
+                id,
+                &orders,
+                at().date(),
+                &CostModel::default(),
+                &mut publisher,
+                at(),
+            )
+            .unwrap();
+
+        let names: Vec<_> = publisher.events().iter().map(|e| e.name()).collect();
+        assert_eq!(names, ["suggestion_generated", "suggestion_applied"]);
+        assert_eq!(publisher.events()[0].key(), publisher.events()[1].key());
+        assert!(matches!(
+            publisher.events()[1],
+            RebalanceEvent::SuggestionApplied { cash, .. } if cash == Decimal::ZERO
+        ));
+    }
+
+    #[test]
+    fn test_dry_run_events_are_kept_apart() {
+        let mut publisher = InMemoryPublisher::new();
+        let event = RebalanceEvent::SuggestionGenerated {
+            at: at(),
+            id: SuggestionId::default(),
+            orders: vec![],
+        };
+        publish_in(RunMode::DryRun, &mut publisher, &event).unwrap();
+
+        assert!(publisher.events().is_empty());
+        assert_eq!(publisher.dry_run_events(), [event]);
+    }
+
+    #[test]
+    fn test_reconcile_and_publish_reports_the_fills() {
+        let mut portfolio = portfolio();
+        let mut publisher = InMemoryPublisher::new();
+        let planned = [Order::buy("META", 8)];
+        let fills = [Fill::buy("META", 5, dec!(25))];
+
+        let reconciliation = portfolio
+            .reconcile_and_publish(
+                SuggestionId::default(),
+                &planned,
+                &fills,
+                at().date(),
+                &CostModel::free(),
+                &mut publisher,
+                at(),
+            )
+            .unwrap();
+
+        assert_eq!(reconciliation.residual, [Order::buy("META", 3)]);
+        assert!(matches!(
+            &publisher.events()[0],
+            RebalanceEvent::SuggestionApplied { orders, cash, .. }
+                if orders == &[Order::buy("META", 5)] && *cash == dec!(-25)
+        ));
+    }
+
+    #[test]
+    fn test_log_publisher_marks_dry_run_lines() {
+        let event = RebalanceEvent::SuggestionGenerated {
+            at: at(),
+            id: SuggestionId::default(),
+            orders: vec![Order::sell("CASH", 100), Order::buy("META", 8)],
+        };
+        let mut publisher = LogPublisher::new(Vec::new());
+        publisher.publish(&event).unwrap();
+        publish_in(RunMode::DryRun, &mut publisher, &event).unwrap();
+        let log = String::from_utf8(publisher.into_inner()).unwrap();
+
+        let line = event.localize(Language::Es);
+        assert!(line.ends_with("vender 100 CASH, comprar 8 META"));
+        let expected = format!("{}\n[dry-run] {}\n", event, event);
+        assert_eq!(log, expected);
+    }
+
+    #[test]
+    fn test_line_publisher_writes_key_and_json() {
+        let event = RebalanceEvent::SuggestionGenerated {
+            at: at(),
+            id: portfolio().state_id(crate::RebalanceStrategy::Conservative),
+            orders: vec![Order::buy("ME\"TA", 8)],
+        };
+        let mut publisher = LinePublisher::new(Vec::new());
+        publisher.publish(&event).unwrap();
+        let line = String::from_utf8(publisher.into_inner()).unwrap();
+
+        assert_eq!(
+            line,
+            format!(
+                "{}\t{{\"event\":\"suggestion_generated\",\"at\":\"2024-01-02T00:00:00Z\",\
+                 \"suggestion\":\"{}\",\"orders\":[{{\"ticker\":\"ME\\\"TA\",\"side\":\"buy\",\
+                 \"units\":8}}]}}\n",
+                event.key(),
+                event.key()
+            )
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Cotizaciones con puntas de compra y venta.
+//!
+//! Con solo el ultimo precio, una sugerencia cree que vende y compra al mismo precio. Si la
+//! fuente da puntas (`bid`, lo que pagan por vender; `ask`, lo que cobran por comprar), el stock
+//! se valoriza al punto medio y las operaciones se estiman (`Portfolio::trades`) y ejecutan
+//! (`Portfolio::apply`) a la punta que corresponde: las ventas al bid y las compras al ask. Al
+//! rebalancear, las compras se achican si lo que entra por las ventas al bid no alcanza para
+//! pagarlas al ask (ver `pipeline::ComputeTargets`).
+
+use crate::execution::Side;
+use crate::{InstrumentKind, Portfolio, Stock};
+use rust_decimal::Decimal;
+
+/// Puntas de una cotizacion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BidAsk {
+    pub bid: Decimal,
+    pub ask: Decimal,
+}
+
+impl BidAsk {
+    pub fn mid(&self) -> Decimal {
+        (self.bid + self.ask) / Decimal::TWO
+    }
+
+    /// Diferencia entre las puntas, en % del punto medio.
+    pub fn spread(&self) -> Decimal {
+        if self.mid().is_zero() {
+            return Decimal::ZERO;
+        }
+        (self.ask - self.bid) / self.mid() * Decimal::ONE_HUNDRED
+    }
+}
+
+impl Stock {
+    /// Cotizacion con puntas: el precio del stock pasa a ser el punto medio.
+    pub fn with_bid_ask(mut self, bid: Decimal, ask: Decimal) -> Self {
+        let quote = BidAsk { bid, ask };
+        self.current_price = quote.mid();
+        self.bid_ask = Some(alloc::boxed::Box::new(quote));
+        self
+    }
+
+    pub fn bid_ask(&self) -> Option<BidAsk> {
+        self.bid_ask.as_deref().copied()
+    }
+
+    /// Precio al que se estima que se hace una operacion: el bid para vender y el ask para
+    /// comprar, o `current_price` si no hay puntas. Para un bono las puntas son limpias y se
+    /// les suma el interes devengado igual que al precio.
+    pub fn price_for(&self, side: Side) -> Decimal {
+        let Some(quote) = self.bid_ask() else {
+            return self.current_price();
+        };
+
+        let clean = match side {
+            Side::Sell => quote.bid,
+            Side::Buy => quote.ask,
+        };
+        match &self.kind {
+            InstrumentKind::Bond { terms, valued_at } => terms.dirty_price(clean, *valued_at),
+            InstrumentKind::Equity | InstrumentKind::Fund { .. } => clean,
+        }
+    }
+}
+
+impl Portfolio {
+    /// Actualiza las puntas de un ticker (y su precio al punto medio) en los holdings y en el
+    /// objetivo. Devuelve `false` si el ticker no aparece en ninguno de los dos.
+    pub fn update_quote(&mut self, ticker: &str, bid: Decimal, ask: Decimal) -> bool {
+        let quote = BidAsk { bid, ask };
+        let found = self.update_price(ticker, quote.mid());
+
+        let held = self.stocks.iter_mut();
+        let targeted = self.allocation.targets.iter_mut().map(|(_, stock)| stock);
+        for stock in held.chain(targeted).filter(|s| s.name == ticker) {
+            stock.bid_ask = Some(alloc::boxed::Box::new(quote));
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortfolioTarget;
+    use crate::costs::CostModel;
+    use crate::date::Date;
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("META", dec!(10))),
+            (dec!(50), Stock::new("AAPL", dec!(10))),
+        ])
+        .unwrap();
+        let mut portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("META", dec!(10)); 10],
+            allocation: target,
+        };
+        portfolio.update_quote("META", dec!(9.9), dec!(10.1));
+        portfolio.update_quote("AAPL", dec!(9.8), dec!(10.2));
+        portfolio
+    }
+
+    #[test]
+    fn test_values_at_mid_and_estimates_at_each_side() {
+        let portfolio = portfolio();
+        assert_eq!(portfolio.total_value(), dec!(100));
+
+        let suggestion = portfolio.rebalance_portfolio();
+        let trades = portfolio.trades(&suggestion);
+        assert_eq!(trades[0].side, Side::Sell);
+        assert_eq!(trades[0].price, dec!(9.9));
+        assert_eq!(trades[1].side, Side::Buy);
+        assert_eq!(trades[1].price, dec!(10.2));
+        assert_eq!(
+            BidAsk {
+                bid: dec!(9.8),
+                ask: dec!(10.2)
+            }
+            .spread(),
+            dec!(4)
+        );
+    }
+
+    #[test]
+    fn test_buys_are_sized_at_the_ask() {
+        let mut portfolio = portfolio();
+        let orders = portfolio.rebalance_portfolio().orders();
+        // vender 5 META al bid da 49,5, que no alcanza para 5 AAPL al ask (51)
+        assert_eq!(
+            orders,
+            [
+                crate::execution::Order::sell("META", 5),
+                crate::execution::Order::buy("AAPL", 4)
+            ]
+        );
+
+        portfolio
+            .apply(&orders, Date::new(2024, 1, 2).unwrap(), &CostModel::free())
+            .unwrap();
+        assert!(portfolio.cash() >= Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_apply_fills_at_the_quote_and_last_price_clears_it() {
+        let mut portfolio = portfolio();
+        let orders = [crate::execution::Order::sell("META", 5)];
+        portfolio
+            .apply(
+                &orders,
+                Date::new(2024, 1, 2).unwrap(),
+                &CostModel::default(),
+            )
+            .unwrap();
+        assert_eq!(portfolio.cash(), dec!(49.5));
+
+        portfolio.update_price("META", dec!(11));
+        assert_eq!(portfolio.stocks()[0].bid_ask(), None);
+        assert_eq!(portfolio.stocks()[0].price_for(Side::Buy), dec!(11));
+    }
+}
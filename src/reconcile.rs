@@ -0,0 +1,225 @@
+//! Conciliacion de ejecuciones.
+//!
+//! Un broker real no siempre ejecuta lo que se le pide: una orden puede llenarse en parte, en
+//! varias ejecuciones o a un precio distinto al que se uso para planificar. `Portfolio::reconcile`
+//! aplica lo que realmente se ejecuto y dice que falta y cuanto costo la diferencia de precio.
+
+use crate::Portfolio;
+use crate::costs::CostModel;
+use crate::date::Date;
+use crate::error::EventError;
+use crate::execution::{Execution, Fill, Order, Side};
+use crate::i18n::{Language, Localize, language};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::Decimal;
+
+/// Diferencia entre el precio planificado y el precio promedio ejecutado de un ticker y lado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slippage {
+    pub ticker: String,
+    pub side: Side,
+
+    /// Unidades ejecutadas.
+    pub units: usize,
+    pub planned_price: Decimal,
+    pub average_price: Decimal,
+}
+
+impl Slippage {
+    /// Cuanto mas se pago (o menos se recibio) que lo planificado; negativo si el mercado
+    /// favorecio la ejecucion.
+    pub fn cost(&self) -> Decimal {
+        let difference = match self.side {
+            Side::Buy => self.average_price - self.planned_price,
+            Side::Sell => self.planned_price - self.average_price,
+        };
+        difference * Decimal::from(self.units)
+    }
+}
+
+/// Resultado de conciliar las ejecuciones contra las ordenes planificadas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Las ejecuciones aplicadas, como transacciones.
+    pub execution: Execution,
+
+    /// Lo que falta por ejecutar de cada orden planificada.
+    pub residual: Vec<Order>,
+
+    /// Deslizamiento por ticker y lado, en el orden de las ordenes.
+    pub slippage: Vec<Slippage>,
+}
+
+impl Reconciliation {
+    /// Si todo lo planificado se ejecuto.
+    pub fn is_complete(&self) -> bool {
+        self.residual.is_empty()
+    }
+
+    pub fn total_slippage(&self) -> Decimal {
+        self.slippage.iter().map(Slippage::cost).sum()
+    }
+}
+
+impl Portfolio {
+    /// Aplica las ejecuciones `fills` (en orden, cada una a su propio precio y con las comisiones
+    /// de `costs`) y las compara con las ordenes `planned`, que se asumen planificadas a los
+    /// precios actuales del portafolio.
+    ///
+    /// Lo ejecutado de mas o sin orden planificada se aplica igual, pero no deja residuo. Como en
+    /// `apply`, si una ejecucion falla las anteriores ya quedaron aplicadas.
+    pub fn reconcile(
+        &mut self,
+        planned: &[Order],
+        fills: &[Fill],
+        date: Date,
+        costs: &CostModel,
+    ) -> Result<Reconciliation, EventError> {
+        // precios con los que se planifico, antes de que las ejecuciones cambien algo
+        let planned_prices: BTreeMap<(Side, &str), Decimal> = planned
+            .iter()
+            .filter_map(|order| {
+                let price = self.priced(&order.ticker)?.current_price();
+                Some(((order.side, order.ticker.as_str()), price))
+            })
+            .collect();
+
+        let mut execution = Execution::default();
+        let mut filled: BTreeMap<(Side, &str), (usize, Decimal)> = BTreeMap::new();
+        for fill in fills {
+            self.execute(fill, date, costs, &mut execution)?;
+
+            let (units, amount) = filled.entry((fill.side, fill.ticker.as_str())).or_default();
+            *units += fill.units;
+            *amount += fill.price * Decimal::from(fill.units);
+        }
+
+        let mut residual = Vec::new();
+        let mut slippage = Vec::new();
+        for order in planned {
+            let key = (order.side, order.ticker.as_str());
+            let (units, amount) = filled.remove(&key).unwrap_or_default();
+
+            let missing = order.units.saturating_sub(units);
+            if missing > 0 {
+                residual.push(Order {
+                    units: missing,
+                    ..order.clone()
+                });
+            }
+
+            if let (Some(planned_price), true) = (planned_prices.get(&key), units > 0) {
+                slippage.push(Slippage {
+                    ticker: order.ticker.clone(),
+                    side: order.side,
+                    units,
+                    planned_price: *planned_price,
+                    average_price: amount / Decimal::from(units),
+                });
+            }
+        }
+
+        Ok(Reconciliation {
+            execution,
+            residual,
+            slippage,
+        })
+    }
+}
+
+impl Localize for Reconciliation {
+    fn localize(&self, language: Language) -> String {
+        let total = self.total_slippage().round_dp(2);
+        let header = match language {
+            Language::Es => format!("Deslizamiento total: {total}"),
+            Language::En => format!("Total slippage: {total}"),
+        };
+
+        let slippage = self.slippage.iter().map(|s| {
+            let side = match (s.side, language) {
+                (Side::Buy, Language::Es) => "compra",
+                (Side::Buy, Language::En) => "buy",
+                (Side::Sell, Language::Es) => "venta",
+                (Side::Sell, Language::En) => "sell",
+            };
+            format!(
+                "{side} {} x{}: {} -> {} ({})",
+                s.ticker,
+                s.units,
+                s.planned_price.round_dp(2),
+                s.average_price.round_dp(2),
+                s.cost().round_dp(2)
+            )
+        });
+        let residual = self.residual.iter().map(|o| match (o.side, language) {
+            (Side::Buy, Language::Es) => format!("Pendiente: comprar {} x{}", o.ticker, o.units),
+            (Side::Buy, Language::En) => format!("Pending: buy {} x{}", o.ticker, o.units),
+            (Side::Sell, Language::Es) => format!("Pendiente: vender {} x{}", o.ticker, o.units),
+            (Side::Sell, Language::En) => format!("Pending: sell {} x{}", o.ticker, o.units),
+        });
+
+        core::iter::once(header)
+            .chain(slippage)
+            .chain(residual)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for Reconciliation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn today() -> Date {
+        Date::new(2024, 5, 2).unwrap()
+    }
+
+    #[test]
+    fn test_partial_fills_leave_residual_and_slippage() {
+        let mut portfolio = Portfolio {
+            cash: dec!(5),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("GOOG", dec!(50)); 2],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(30))),
+        };
+        let planned = portfolio.rebalance_portfolio().orders();
+        assert_eq!(planned, vec![Order::sell("GOOG", 2), Order::buy("META", 3)]);
+
+        let fills = [
+            Fill::sell("GOOG", 2, dec!(49)),
+            Fill::buy("META", 1, dec!(30)),
+            Fill::buy("META", 1, dec!(31)),
+        ];
+        let result = portfolio
+            .reconcile(&planned, &fills, today(), &CostModel::free())
+            .unwrap();
+
+        assert!(!result.is_complete());
+        assert_eq!(result.residual, vec![Order::buy("META", 1)]);
+        assert_eq!(portfolio.cash(), dec!(42));
+
+        assert_eq!(result.slippage.len(), 2);
+        assert_eq!(result.slippage[0].cost(), dec!(2));
+        assert_eq!(result.slippage[1].average_price, dec!(30.5));
+        assert_eq!(result.total_slippage(), dec!(3));
+        assert_eq!(
+            result.localize(Language::En),
+            "Total slippage: 3.00\n\
+             sell GOOG x2: 50 -> 49 (2)\n\
+             buy META x2: 30 -> 30.50 (1.00)\n\
+             Pending: buy META x1"
+        );
+    }
+}
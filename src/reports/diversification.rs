@@ -0,0 +1,309 @@
+use crate::i18n::{Language, Localize, language};
+use crate::money::Locale;
+use crate::{Portfolio, PortfolioTarget};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Limites a partir de los cuales un portafolio se considera demasiado concentrado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConcentrationLimits {
+    /// Maximo % del portafolio en un solo stock.
+    pub max_single_weight: Decimal,
+
+    /// Cuantos stocks considerar en la medida "top N".
+    pub top_n: usize,
+
+    /// Maximo % del portafolio en los `top_n` stocks mas grandes.
+    pub max_top_n_weight: Decimal,
+}
+
+impl Default for ConcentrationLimits {
+    fn default() -> Self {
+        Self {
+            max_single_weight: dec!(25),
+            top_n: 3,
+            max_top_n_weight: dec!(60),
+        }
+    }
+}
+
+/// Medidas de concentracion de una distribucion de pesos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConcentrationMetrics {
+    /// Indice de Herfindahl-Hirschman con pesos fraccionales: 1 es todo en un stock, 1/N es
+    /// perfectamente repartido en N stocks.
+    pub herfindahl: Decimal,
+
+    /// `1 / herfindahl`: a cuantos stocks igualmente repartidos equivale esta distribucion.
+    pub effective_holdings: Decimal,
+
+    /// % del portafolio en los `top_n` stocks mas grandes.
+    pub top_n_weight: Decimal,
+
+    /// El stock mas grande y su %.
+    pub largest: Option<(String, Decimal)>,
+}
+
+impl ConcentrationMetrics {
+    /// Calcula las metricas a partir de pesos en %, ordenados de mayor a menor.
+    fn from_weights(weights: &[(&str, Decimal)], top_n: usize) -> Self {
+        let herfindahl: Decimal = weights
+            .iter()
+            .map(|(_, w)| (w / dec!(100)) * (w / dec!(100)))
+            .sum();
+
+        let effective_holdings = if herfindahl.is_zero() {
+            Decimal::ZERO
+        } else {
+            Decimal::ONE / herfindahl
+        };
+
+        Self {
+            herfindahl,
+            effective_holdings,
+            top_n_weight: weights.iter().take(top_n).map(|(_, w)| *w).sum(),
+            largest: weights.first().map(|(name, w)| (name.to_string(), *w)),
+        }
+    }
+}
+
+/// Alertas de sobreconcentracion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConcentrationFlag {
+    /// Un stock pesa mas que `max_single_weight`.
+    HoldingAboveLimit {
+        ticker: String,
+        weight: Decimal,
+        limit: Decimal,
+    },
+
+    /// Los `n` stocks mas grandes pesan mas que `max_top_n_weight`.
+    TopNAboveLimit {
+        n: usize,
+        weight: Decimal,
+        limit: Decimal,
+    },
+
+    /// El portafolio actual esta menos diversificado que su objetivo.
+    LessDiversifiedThanTarget { current: Decimal, target: Decimal },
+}
+
+/// Compara la concentracion del portafolio actual con la de su objetivo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiversificationReport {
+    pub current: ConcentrationMetrics,
+    pub target: ConcentrationMetrics,
+    pub flags: Vec<ConcentrationFlag>,
+}
+
+impl DiversificationReport {
+    pub fn is_over_concentrated(&self) -> bool {
+        !self.flags.is_empty()
+    }
+}
+
+fn target_weights(target: &PortfolioTarget) -> Vec<(&str, Decimal)> {
+    let mut weights: Vec<(&str, Decimal)> = target
+        .targets()
+        .iter()
+        .map(|(w, stock)| (stock.name(), *w))
+        .collect();
+    weights.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    weights
+}
+
+impl Portfolio {
+    /// Reporte de diversificacion del portafolio actual versus su objetivo.
+    pub fn diversification_report(&self, limits: &ConcentrationLimits) -> DiversificationReport {
+        let current_weights = self.weights();
+        let current = ConcentrationMetrics::from_weights(&current_weights, limits.top_n);
+        let target =
+            ConcentrationMetrics::from_weights(&target_weights(self.allocation()), limits.top_n);
+
+        let mut flags: Vec<ConcentrationFlag> = current_weights
+            .iter()
+            .filter(|(_, w)| *w > limits.max_single_weight)
+            .map(|(name, w)| ConcentrationFlag::HoldingAboveLimit {
+                ticker: name.to_string(),
+                weight: *w,
+                limit: limits.max_single_weight,
+            })
+            .collect();
+
+        if current.top_n_weight > limits.max_top_n_weight {
+            flags.push(ConcentrationFlag::TopNAboveLimit {
+                n: limits.top_n,
+                weight: current.top_n_weight,
+                limit: limits.max_top_n_weight,
+            });
+        }
+
+        if !current_weights.is_empty() && current.herfindahl > target.herfindahl {
+            flags.push(ConcentrationFlag::LessDiversifiedThanTarget {
+                current: current.effective_holdings,
+                target: target.effective_holdings,
+            });
+        }
+
+        DiversificationReport {
+            current,
+            target,
+            flags,
+        }
+    }
+}
+
+impl Localize for ConcentrationFlag {
+    fn localize(&self, language: Language) -> String {
+        let number = |value: &Decimal| Locale::from(language).format_decimal(*value, 2);
+        match self {
+            ConcentrationFlag::HoldingAboveLimit {
+                ticker,
+                weight,
+                limit,
+            } => {
+                let (weight, limit) = (number(weight), number(limit));
+                match language {
+                    Language::Es => format!("{ticker} pesa {weight}% (limite {limit}%)"),
+                    Language::En => format!("{ticker} weighs {weight}% (limit {limit}%)"),
+                }
+            }
+            ConcentrationFlag::TopNAboveLimit { n, weight, limit } => {
+                let (weight, limit) = (number(weight), number(limit));
+                match language {
+                    Language::Es => {
+                        format!("Los {n} mayores stocks pesan {weight}% (limite {limit}%)")
+                    }
+                    Language::En => format!("Top {n} holdings weigh {weight}% (limit {limit}%)"),
+                }
+            }
+            ConcentrationFlag::LessDiversifiedThanTarget { current, target } => {
+                let (current, target) = (number(current), number(target));
+                match language {
+                    Language::Es => format!("Equivale a {current} stocks, el objetivo a {target}"),
+                    Language::En => format!("Equivalent to {current} holdings, target is {target}"),
+                }
+            }
+        }
+    }
+}
+
+impl Localize for DiversificationReport {
+    fn localize(&self, language: Language) -> String {
+        let locale = Locale::from(language);
+        let (hhi, target_hhi) = (
+            locale.format_decimal(self.current.herfindahl, 4),
+            locale.format_decimal(self.target.herfindahl, 4),
+        );
+        let (effective, target_effective) = (
+            locale.format_decimal(self.current.effective_holdings, 2),
+            locale.format_decimal(self.target.effective_holdings, 2),
+        );
+        let header = match language {
+            Language::Es => format!(
+                "Herfindahl actual {hhi} (objetivo {target_hhi}), stocks efectivos {effective} \
+                 (objetivo {target_effective})"
+            ),
+            Language::En => format!(
+                "Current Herfindahl {hhi} (target {target_hhi}), effective holdings {effective} \
+                 (target {target_effective})"
+            ),
+        };
+
+        core::iter::once(header)
+            .chain(
+                self.flags
+                    .iter()
+                    .map(|flag| format!("- {}", flag.localize(language))),
+            )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for DiversificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stock;
+
+    #[test]
+    fn test_concentrated_portfolio_is_flagged() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(25), Stock::new("A", dec!(10))),
+            (dec!(25), Stock::new("B", dec!(10))),
+            (dec!(25), Stock::new("C", dec!(10))),
+            (dec!(25), Stock::new("D", dec!(10))),
+        ])
+        .unwrap();
+
+        let mut stocks = vec![Stock::new("A", dec!(10)); 8];
+        stocks.push(Stock::new("B", dec!(10)));
+        stocks.push(Stock::new("C", dec!(10)));
+
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: target,
+        };
+
+        let report = portfolio.diversification_report(&ConcentrationLimits::default());
+
+        assert_eq!(report.target.effective_holdings, dec!(4));
+        assert_eq!(report.current.herfindahl, dec!(0.66));
+        assert_eq!(report.current.largest, Some(("A".into(), dec!(80))));
+        assert_eq!(report.current.top_n_weight, dec!(100));
+        assert!(
+            report
+                .flags
+                .contains(&ConcentrationFlag::HoldingAboveLimit {
+                    ticker: "A".into(),
+                    weight: dec!(80),
+                    limit: dec!(25),
+                })
+        );
+        assert_eq!(report.flags.len(), 3);
+        assert_eq!(
+            report.flags[0].localize(Language::Es),
+            "A pesa 80,00% (limite 25,00%)"
+        );
+    }
+
+    #[test]
+    fn test_balanced_portfolio_has_no_flags() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("A", dec!(10))),
+            (dec!(50), Stock::new("B", dec!(10))),
+        ])
+        .unwrap();
+
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("A", dec!(10)), Stock::new("B", dec!(10))],
+            allocation: target,
+        };
+
+        let limits = ConcentrationLimits {
+            max_single_weight: dec!(50),
+            top_n: 2,
+            max_top_n_weight: dec!(100),
+        };
+
+        assert!(
+            !portfolio
+                .diversification_report(&limits)
+                .is_over_concentrated()
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use crate::Portfolio;
+use crate::fx::{FxError, FxRates};
+use crate::i18n::{Language, Localize, language};
+use crate::money::{Currency, Locale, Money};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Cuanto de la exposicion a cada moneda extranjera se quiere cubrir.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HedgePolicy {
+    /// % de la exposicion a cubrir para las monedas sin una tasa propia.
+    pub hedge_ratio: Decimal,
+
+    /// % a cubrir por moneda, cuando difiere de `hedge_ratio`.
+    pub overrides: BTreeMap<Currency, Decimal>,
+
+    /// Coberturas vigentes (nocional vendido a plazo), en la moneda cubierta.
+    pub existing: BTreeMap<Currency, Decimal>,
+}
+
+impl HedgePolicy {
+    pub fn new(hedge_ratio: Decimal) -> Self {
+        Self {
+            hedge_ratio,
+            overrides: BTreeMap::new(),
+            existing: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_ratio(mut self, currency: Currency, hedge_ratio: Decimal) -> Self {
+        self.overrides.insert(currency, hedge_ratio);
+        self
+    }
+
+    pub fn with_existing_hedge(mut self, currency: Currency, notional: Decimal) -> Self {
+        self.existing.insert(currency, notional);
+        self
+    }
+
+    pub fn ratio_for(&self, currency: Currency) -> Decimal {
+        self.overrides
+            .get(&currency)
+            .copied()
+            .unwrap_or(self.hedge_ratio)
+    }
+}
+
+/// Exposicion del portafolio a una moneda.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyExposure {
+    pub currency: Currency,
+
+    /// Valor de los stocks y la caja en esta moneda, en la moneda misma.
+    pub value: Decimal,
+
+    pub value_in_base: Decimal,
+
+    /// % del portafolio.
+    pub weight: Decimal,
+
+    /// % que se quiere cubrir (cero para la moneda base).
+    pub hedge_ratio: Decimal,
+
+    /// Nocional cubierto hoy, en la moneda.
+    pub current_hedge: Decimal,
+
+    /// Nocional a agregar (positivo) o deshacer (negativo) para llegar a `hedge_ratio`.
+    pub hedge_to_add: Decimal,
+}
+
+/// Exposicion por moneda versus la moneda base, con las coberturas sugeridas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HedgingReport {
+    pub base: Currency,
+    pub total_in_base: Decimal,
+
+    /// Una entrada por moneda, la base primero.
+    pub exposures: Vec<CurrencyExposure>,
+}
+
+impl HedgingReport {
+    /// % del portafolio expuesto a monedas extranjeras sin cubrir, con las coberturas vigentes.
+    /// Las monedas sin holdings (con una cobertura que sobra) no aportan exposicion.
+    pub fn unhedged_weight(&self) -> Decimal {
+        if self.total_in_base.is_zero() {
+            return Decimal::ZERO;
+        }
+
+        self.exposures
+            .iter()
+            .filter(|e| e.currency != self.base && !e.value.is_zero())
+            .map(|e| {
+                let unhedged = e.value - e.current_hedge;
+                unhedged * e.value_in_base / e.value
+            })
+            .sum::<Decimal>()
+            / self.total_in_base
+            * dec!(100)
+    }
+
+    /// Coberturas a ajustar, sin las que ya estan en su objetivo.
+    pub fn suggested_hedges(&self) -> impl Iterator<Item = &CurrencyExposure> {
+        self.exposures.iter().filter(|e| !e.hedge_to_add.is_zero())
+    }
+}
+
+impl Portfolio {
+    /// Reporte de exposicion cambiaria. La caja base (`cash()`) se asume en la moneda base de
+    /// `rates`.
+    pub fn hedging_report(
+        &self,
+        rates: &FxRates,
+        policy: &HedgePolicy,
+    ) -> Result<HedgingReport, FxError> {
+        let base = rates.base();
+
+        let mut values: BTreeMap<Currency, Decimal> = self.foreign_cash().clone();
+        *values.entry(base).or_default() += self.cash();
+        for stock in self.stocks() {
+            *values.entry(stock.currency).or_default() += stock.current_price();
+        }
+        for currency in policy.existing.keys() {
+            values.entry(*currency).or_default();
+        }
+
+        let mut exposures = Vec::new();
+        for (currency, value) in values {
+            let value_in_base = rates.convert(value, currency, base)?;
+            let (hedge_ratio, current_hedge) = if currency == base {
+                (Decimal::ZERO, Decimal::ZERO)
+            } else {
+                (
+                    policy.ratio_for(currency),
+                    policy.existing.get(&currency).copied().unwrap_or_default(),
+                )
+            };
+
+            exposures.push(CurrencyExposure {
+                currency,
+                value,
+                value_in_base,
+                weight: Decimal::ZERO,
+                hedge_ratio,
+                current_hedge,
+                hedge_to_add: value * hedge_ratio / dec!(100) - current_hedge,
+            });
+        }
+
+        let total_in_base: Decimal = exposures.iter().map(|e| e.value_in_base).sum();
+        if !total_in_base.is_zero() {
+            for exposure in exposures.iter_mut() {
+                exposure.weight = exposure.value_in_base / total_in_base * dec!(100);
+            }
+        }
+        exposures.sort_by_key(|e| e.currency != base);
+
+        Ok(HedgingReport {
+            base,
+            total_in_base,
+            exposures,
+        })
+    }
+}
+
+impl Localize for CurrencyExposure {
+    fn localize(&self, language: Language) -> String {
+        let locale = Locale::from(language);
+        let currency = self.currency;
+        let value = Money::new(self.value, currency).format(locale);
+        let weight = locale.format_decimal(self.weight, 2);
+        let hedge = Money::new(self.hedge_to_add.abs(), currency).format(locale);
+        let ratio = locale.format_decimal(self.hedge_ratio, 2);
+
+        let action = match (language, self.hedge_to_add.cmp(&Decimal::ZERO)) {
+            (_, core::cmp::Ordering::Equal) => String::new(),
+            (Language::Es, core::cmp::Ordering::Greater) => {
+                format!(", vender {hedge} a plazo")
+            }
+            (Language::Es, core::cmp::Ordering::Less) => {
+                format!(", deshacer cobertura de {hedge}")
+            }
+            (Language::En, core::cmp::Ordering::Greater) => format!(", sell {hedge} forward"),
+            (Language::En, core::cmp::Ordering::Less) => format!(", unwind {hedge} of hedges"),
+        };
+
+        match language {
+            Language::Es => {
+                format!("{currency}: {weight}% ({value}), cobertura objetivo {ratio}%{action}")
+            }
+            Language::En => {
+                format!("{currency}: {weight}% ({value}), target hedge {ratio}%{action}")
+            }
+        }
+    }
+}
+
+impl Localize for HedgingReport {
+    fn localize(&self, language: Language) -> String {
+        let locale = Locale::from(language);
+        let unhedged = locale.format_decimal(self.unhedged_weight(), 2);
+        let header = match language {
+            Language::Es => format!(
+                "Moneda base {}, {unhedged}% expuesto sin cobertura",
+                self.base
+            ),
+            Language::En => format!(
+                "Base currency {}, {unhedged}% unhedged foreign exposure",
+                self.base
+            ),
+        };
+
+        core::iter::once(header)
+            .chain(
+                self.exposures
+                    .iter()
+                    .filter(|e| e.currency != self.base)
+                    .map(|e| format!("- {}", e.localize(language))),
+            )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for HedgingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+
+    fn portfolio() -> Portfolio {
+        let meta = Stock::new("META", dec!(100));
+        let sap = Stock::new("SAP", dec!(200)).with_currency(Currency::Eur);
+
+        Portfolio {
+            cash: dec!(1_000_000),
+            foreign_cash: BTreeMap::from([(Currency::Usd, dec!(500))]),
+            stocks: vec![meta.clone(); 10]
+                .into_iter()
+                .chain(vec![sap; 5])
+                .collect(),
+            allocation: PortfolioTarget::new(meta),
+        }
+    }
+
+    #[test]
+    fn test_exposure_per_currency() {
+        let rates = FxRates::new(Currency::Clp)
+            .with_rate(Currency::Usd, dec!(1000))
+            .with_rate(Currency::Eur, dec!(1000));
+        let policy = HedgePolicy::new(dec!(50))
+            .with_ratio(Currency::Eur, dec!(100))
+            .with_existing_hedge(Currency::Usd, dec!(250));
+
+        let report = portfolio().hedging_report(&rates, &policy).unwrap();
+
+        // CLP 1.000.000, USD 1.500 (1.500.000 CLP), EUR 1.000 (1.000.000 CLP)
+        assert_eq!(report.total_in_base, dec!(3_500_000));
+        assert_eq!(report.exposures[0].currency, Currency::Clp);
+
+        let usd = report
+            .exposures
+            .iter()
+            .find(|e| e.currency == Currency::Usd)
+            .unwrap();
+        assert_eq!(usd.value, dec!(1500));
+        assert_eq!(usd.hedge_to_add, dec!(500));
+
+        let eur = report
+            .exposures
+            .iter()
+            .find(|e| e.currency == Currency::Eur)
+            .unwrap();
+        assert_eq!(eur.hedge_to_add, dec!(1000));
+        assert_eq!(report.suggested_hedges().count(), 2);
+
+        // sin cobertura: 1.250 USD + 1.000 EUR = 2.250.000 de 3.500.000
+        assert_eq!(report.unhedged_weight().round_dp(2), dec!(64.29));
+    }
+
+    #[test]
+    fn test_over_hedged_currency_is_unwound() {
+        let rates = FxRates::new(Currency::Usd).with_rate(Currency::Eur, dec!(1.1));
+        let policy = HedgePolicy::new(dec!(0)).with_existing_hedge(Currency::Eur, dec!(300));
+
+        let mut portfolio = portfolio();
+        portfolio.cash = Decimal::ZERO;
+        portfolio.foreign_cash.clear();
+
+        let report = portfolio.hedging_report(&rates, &policy).unwrap();
+        let eur = &report.exposures[1];
+
+        assert_eq!(eur.hedge_to_add, dec!(-300));
+
+        // una cobertura en una moneda que ya no se tiene no divide por cero
+        let policy = policy.with_existing_hedge(Currency::Clp, dec!(1000));
+        let rates = rates.with_rate(Currency::Clp, dec!(0.001));
+        let report = portfolio.hedging_report(&rates, &policy).unwrap();
+        // 700 EUR sin cubrir = 770 de 2.100 USD
+        assert_eq!(report.unhedged_weight().round_dp(2), dec!(36.67));
+        assert!(report.localize(Language::Es).contains("CLP: 0,00%"));
+        assert_eq!(
+            eur.localize(Language::En),
+            "EUR: 52.38% (€1,000.00), target hedge 0.00%, unwind €300.00 of hedges"
+        );
+    }
+}
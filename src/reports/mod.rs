@@ -0,0 +1,18 @@
+//! Reportes sobre el estado de un portafolio.
+//!
+//! Cada reporte es una estructura con datos (para que el usuario haga lo que quiera con ellos) e
+//! implementa `Localize` para producir un texto legible en español o ingles.
+
+pub mod diversification;
+pub mod hedging;
+pub mod pnl;
+pub mod rounding;
+pub mod turnover;
+pub mod verification;
+
+pub use diversification::{ConcentrationLimits, DiversificationReport};
+pub use hedging::{HedgePolicy, HedgingReport};
+pub use pnl::{HoldingPeriod, UnrealizedPnlReport};
+pub use rounding::{RoundingLedger, RoundingLoss};
+pub use turnover::{HoldingStats, TurnoverReport};
+pub use verification::VerificationReport;
@@ -0,0 +1,261 @@
+use crate::Portfolio;
+use crate::clock::Clock;
+use crate::date::Date;
+use crate::i18n::{Language, Localize, language};
+use crate::lots::Lot;
+use crate::money::{Currency, Locale, Money};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Clasificacion tributaria segun cuanto tiempo se ha mantenido un lote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HoldingPeriod {
+    /// Un año o menos.
+    ShortTerm,
+
+    /// Mas de un año.
+    LongTerm,
+}
+
+impl HoldingPeriod {
+    /// Mas de un año calendario entre la compra y `as_of` es largo plazo (comprado el
+    /// 2024-03-01, es largo plazo desde el 2025-03-02).
+    pub fn classify(acquired: Date, as_of: Date) -> Self {
+        if as_of > acquired.add_months(12) {
+            HoldingPeriod::LongTerm
+        } else {
+            HoldingPeriod::ShortTerm
+        }
+    }
+}
+
+/// Ganancia no realizada de un lote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LotPnl {
+    pub lot: Lot,
+    pub gain: Decimal,
+
+    /// Retorno en % sobre el costo.
+    pub return_pct: Decimal,
+    pub period: HoldingPeriod,
+}
+
+/// Ganancia no realizada de todos los lotes de un ticker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickerPnl {
+    pub ticker: String,
+    pub currency: Currency,
+    pub units: usize,
+    pub cost: Decimal,
+    pub market_value: Decimal,
+    pub gain: Decimal,
+    pub return_pct: Decimal,
+}
+
+/// Ganancias y perdidas no realizadas por lote y por ticker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnrealizedPnlReport {
+    pub as_of: Date,
+    pub lots: Vec<LotPnl>,
+    pub tickers: Vec<TickerPnl>,
+}
+
+impl UnrealizedPnlReport {
+    pub fn total_gain(&self) -> Decimal {
+        self.tickers.iter().map(|t| t.gain).sum()
+    }
+
+    /// Ganancia total de los lotes de un plazo.
+    pub fn gain_for(&self, period: HoldingPeriod) -> Decimal {
+        self.lots
+            .iter()
+            .filter(|l| l.period == period)
+            .map(|l| l.gain)
+            .sum()
+    }
+}
+
+fn return_pct(gain: Decimal, cost: Decimal) -> Decimal {
+    if cost.is_zero() {
+        Decimal::ZERO
+    } else {
+        gain / cost * dec!(100)
+    }
+}
+
+impl Portfolio {
+    /// Ganancias no realizadas a hoy; ver `unrealized_pnl_at`.
+    #[cfg(feature = "std")]
+    pub fn unrealized_pnl(&self) -> UnrealizedPnlReport {
+        self.unrealized_pnl_now(&crate::clock::SystemClock)
+    }
+
+    /// Ganancias no realizadas al dia que marca `clock`.
+    pub fn unrealized_pnl_now(&self, clock: &impl Clock) -> UnrealizedPnlReport {
+        self.unrealized_pnl_at(clock.today())
+    }
+
+    /// Ganancias no realizadas de las unidades con costo conocido, clasificadas segun el plazo a
+    /// la fecha `as_of`.
+    pub fn unrealized_pnl_at(&self, as_of: Date) -> UnrealizedPnlReport {
+        let lots: Vec<LotPnl> = self
+            .lots()
+            .into_iter()
+            .map(|lot| LotPnl {
+                gain: lot.unrealized(),
+                return_pct: return_pct(lot.unrealized(), lot.cost()),
+                period: HoldingPeriod::classify(lot.basis.acquired, as_of),
+                lot,
+            })
+            .collect();
+
+        let mut tickers: Vec<TickerPnl> = Vec::new();
+        for pnl in &lots {
+            let entry = match tickers.iter_mut().find(|t| t.ticker == pnl.lot.ticker) {
+                Some(entry) => entry,
+                None => {
+                    tickers.push(TickerPnl {
+                        ticker: pnl.lot.ticker.clone(),
+                        currency: pnl.lot.currency,
+                        units: 0,
+                        cost: Decimal::ZERO,
+                        market_value: Decimal::ZERO,
+                        gain: Decimal::ZERO,
+                        return_pct: Decimal::ZERO,
+                    });
+                    tickers.last_mut().unwrap()
+                }
+            };
+
+            entry.units += pnl.lot.units;
+            entry.cost += pnl.lot.cost();
+            entry.market_value += pnl.lot.market_value();
+            entry.gain += pnl.gain;
+            entry.return_pct = return_pct(entry.gain, entry.cost);
+        }
+
+        UnrealizedPnlReport {
+            as_of,
+            lots,
+            tickers,
+        }
+    }
+}
+
+impl Localize for HoldingPeriod {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (HoldingPeriod::ShortTerm, Language::Es) => "corto plazo",
+            (HoldingPeriod::ShortTerm, Language::En) => "short term",
+            (HoldingPeriod::LongTerm, Language::Es) => "largo plazo",
+            (HoldingPeriod::LongTerm, Language::En) => "long term",
+        }
+        .into()
+    }
+}
+
+impl Localize for UnrealizedPnlReport {
+    fn localize(&self, language: Language) -> String {
+        let locale = Locale::from(language);
+        let money = |amount, currency| Money::new(amount, currency).format(locale);
+
+        // montos en monedas distintas no se suman: el total va por moneda
+        let mut totals: BTreeMap<Currency, Decimal> = BTreeMap::new();
+        for t in &self.tickers {
+            *totals.entry(t.currency).or_default() += t.gain;
+        }
+        let total = if totals.is_empty() {
+            locale.format_decimal(Decimal::ZERO, 2)
+        } else {
+            totals
+                .into_iter()
+                .map(|(currency, gain)| money(gain, currency))
+                .collect::<Vec<_>>()
+                .join(" + ")
+        };
+        let header = match language {
+            Language::Es => format!("Ganancia no realizada al {}: {total}", self.as_of),
+            Language::En => format!("Unrealized gain as of {}: {total}", self.as_of),
+        };
+
+        let tickers = self.tickers.iter().map(|t| {
+            format!(
+                "{} x{}: {} ({}%)",
+                t.ticker,
+                t.units,
+                money(t.gain, t.currency),
+                locale.format_decimal(t.return_pct, 2)
+            )
+        });
+        let lots = self.lots.iter().map(|l| {
+            format!(
+                "  {} {} x{} @ {}: {} ({})",
+                l.lot.ticker,
+                l.lot.basis.acquired,
+                l.lot.units,
+                money(l.lot.basis.cost, l.lot.currency),
+                money(l.gain, l.lot.currency),
+                l.period.localize(language)
+            )
+        });
+
+        core::iter::once(header)
+            .chain(tickers)
+            .chain(lots)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for UnrealizedPnlReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+
+    #[test]
+    fn test_pnl_per_lot_and_ticker() {
+        let d = |year, month, day| Date::new(year, month, day).unwrap();
+        let meta = Stock::new("META", dec!(50));
+
+        let mut stocks = vec![meta.clone().with_basis(dec!(40), d(2023, 3, 1)); 2];
+        stocks.push(meta.clone().with_basis(dec!(60), d(2024, 1, 10)));
+
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: PortfolioTarget::new(meta),
+        };
+
+        let report = portfolio.unrealized_pnl_at(d(2024, 3, 1));
+
+        assert_eq!(report.lots[0].period, HoldingPeriod::ShortTerm);
+        assert_eq!(report.lots[0].return_pct, dec!(25));
+        assert_eq!(report.gain_for(HoldingPeriod::ShortTerm), dec!(10));
+        assert_eq!(report.gain_for(HoldingPeriod::LongTerm), dec!(0));
+
+        assert_eq!(report.tickers.len(), 1);
+        assert_eq!(report.tickers[0].units, 3);
+        assert_eq!(report.total_gain(), dec!(10));
+        assert_eq!(report.tickers[0].return_pct.round_dp(2), dec!(7.14));
+
+        let later = portfolio.unrealized_pnl_at(d(2024, 3, 2));
+        assert_eq!(later.gain_for(HoldingPeriod::LongTerm), dec!(20));
+
+        let text = report.localize(Language::Es);
+        assert!(text.starts_with("Ganancia no realizada al 2024-03-01: US$10,00\n"));
+        assert!(text.contains("META x3: US$10,00 (7,14%)"));
+        assert!(text.contains("@ US$60,00: -US$10,00 (corto plazo)"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Lo que se pierde por redondear.
+//!
+//! El rebalanceo conservador trunca las unidades objetivo, asi que despues de cada rebalanceo
+//! queda caja sin invertir y un drift residual que ninguna orden entera puede cerrar. Medirlo
+//! (en cada rebalanceo y acumulado) sirve para decidir si vale la pena pasar a fracciones.
+
+use crate::Portfolio;
+use crate::date::Date;
+use crate::i18n::{Language, Localize, language};
+use crate::money::{Currency, Locale, Money};
+use crate::pipeline::{AggregateHoldings, ComputeTargets, Plan, RebalanceStage, RoundDown};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Perdida por redondeo de un rebalanceo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundingLoss {
+    /// Valor que quedaria invertido con fracciones y que con unidades enteras queda en caja.
+    pub unspent_cash: Decimal,
+
+    /// Moneda en que cotizan los objetivos (el rebalanceo sin tipos de cambio asume una sola).
+    pub currency: Currency,
+
+    /// Mayor distancia (en puntos porcentuales) entre el peso que queda y el objetivo, y su
+    /// ticker; `None` si el redondeo no deja a ninguno corto.
+    pub residual_drift: Option<(String, Decimal)>,
+}
+
+impl Portfolio {
+    /// Cuanto deja de invertir (y cuan lejos del objetivo queda) `rebalance_portfolio` por usar
+    /// unidades enteras.
+    pub fn rounding_loss(&self) -> RoundingLoss {
+        let mut plan = Plan::default();
+        AggregateHoldings.apply(self, &mut plan);
+        ComputeTargets.apply(self, &mut plan);
+        let ideal = plan.targets.clone();
+        RoundDown.apply(self, &mut plan);
+
+        let mut loss = RoundingLoss {
+            unspent_cash: Decimal::ZERO,
+            currency: self
+                .allocation()
+                .targets()
+                .first()
+                .map_or(Currency::Usd, |(_, stock)| stock.currency),
+            residual_drift: None,
+        };
+        for (ticker, units) in &ideal {
+            let price = self
+                .priced(ticker)
+                .map_or(Decimal::ZERO, |s| s.current_price());
+            let rounded = plan.targets.get(ticker).copied().unwrap_or_default();
+            let short = (units - rounded) * price;
+            if short <= Decimal::ZERO {
+                continue;
+            }
+
+            loss.unspent_cash += short;
+            let drift = short / plan.total * Decimal::ONE_HUNDRED;
+            if loss
+                .residual_drift
+                .as_ref()
+                .is_none_or(|(_, max)| drift > *max)
+            {
+                loss.residual_drift = Some((ticker.to_string(), drift));
+            }
+        }
+        loss
+    }
+}
+
+/// Perdidas por redondeo de varios rebalanceos, p. ej. los de un backtest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoundingLedger {
+    pub entries: Vec<(Date, RoundingLoss)>,
+}
+
+impl RoundingLedger {
+    pub fn record(&mut self, date: Date, loss: RoundingLoss) {
+        self.entries.push((date, loss));
+    }
+
+    /// Caja sin invertir sumada sobre todos los rebalanceos.
+    pub fn total_unspent(&self) -> Decimal {
+        self.entries.iter().map(|(_, l)| l.unspent_cash).sum()
+    }
+
+    /// Promedio del drift residual maximo de cada rebalanceo, en puntos porcentuales.
+    pub fn average_residual_drift(&self) -> Decimal {
+        if self.entries.is_empty() {
+            return Decimal::ZERO;
+        }
+        let total: Decimal = self
+            .entries
+            .iter()
+            .filter_map(|(_, l)| l.residual_drift.as_ref().map(|(_, d)| *d))
+            .sum();
+        total / Decimal::from(self.entries.len())
+    }
+}
+
+impl Localize for RoundingLoss {
+    fn localize(&self, language: Language) -> String {
+        let locale = Locale::from(language);
+        let cash = Money::new(self.unspent_cash, self.currency).format(locale);
+        let drift = match &self.residual_drift {
+            Some((ticker, drift)) => format!("{ticker} {}", locale.format_decimal(*drift, 2)),
+            None => "-".into(),
+        };
+        match language {
+            Language::Es => format!("Redondeo: {cash} sin invertir, drift residual {drift}"),
+            Language::En => format!("Rounding: {cash} left uninvested, residual drift {drift}"),
+        }
+    }
+}
+
+impl Localize for RoundingLedger {
+    fn localize(&self, language: Language) -> String {
+        let locale = Locale::from(language);
+        let count = self.entries.len();
+        let total = match self.entries.first() {
+            Some((_, loss)) => Money::new(self.total_unspent(), loss.currency).format(locale),
+            None => locale.format_decimal(Decimal::ZERO, 2),
+        };
+        let drift = locale.format_decimal(self.average_residual_drift(), 2);
+        match language {
+            Language::Es => format!(
+                "Redondeo en {count} rebalanceos: {total} sin invertir en total, drift residual \
+                 promedio {drift} pp"
+            ),
+            Language::En => format!(
+                "Rounding over {count} rebalances: {total} left uninvested in total, average \
+                 residual drift {drift} pp"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for RoundingLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl fmt::Display for RoundingLedger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use alloc::vec;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_truncation_leaves_cash_and_drift() {
+        let portfolio = Portfolio {
+            cash: dec!(1000),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: PortfolioTarget::try_from_vec(vec![
+                (dec!(50), Stock::new("BRK", dec!(300))),
+                (dec!(50), Stock::new("KO", dec!(60))),
+            ])
+            .unwrap(),
+        };
+
+        // 500 en BRK son 1,67 unidades: se compra 1 y quedan 200 afuera; en KO quedan 20
+        let loss = portfolio.rounding_loss();
+        assert_eq!(loss.unspent_cash.round_dp(6), dec!(220));
+        let (ticker, drift) = loss.residual_drift.clone().unwrap();
+        assert_eq!((ticker.as_str(), drift.round_dp(2)), ("BRK", dec!(20)));
+
+        let mut ledger = RoundingLedger::default();
+        ledger.record(Date::new(2024, 1, 2).unwrap(), loss.clone());
+        ledger.record(Date::new(2024, 2, 1).unwrap(), loss);
+        assert_eq!(ledger.total_unspent().round_dp(6), dec!(440));
+        assert_eq!(ledger.average_residual_drift().round_dp(2), dec!(20));
+        assert_eq!(
+            ledger.localize(Language::En),
+            "Rounding over 2 rebalances: $440.00 left uninvested in total, average residual \
+             drift 20.00 pp"
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::date::Date;
+use crate::i18n::{Language, Localize, language};
+use crate::journal::{Journal, TransactionKind};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Cuanto tiempo se mantuvieron las unidades de un ticker, ponderado por unidades.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoldingStats {
+    pub ticker: String,
+
+    /// Unidades vendidas y cuantos dias se tuvieron en promedio; `None` si no se vendio nada.
+    pub sold_units: Decimal,
+    pub sold_days: Option<Decimal>,
+
+    /// Unidades que siguen en cartera y cuantos dias llevan en promedio a la fecha del reporte.
+    pub held_units: Decimal,
+    pub held_days: Option<Decimal>,
+}
+
+impl HoldingStats {
+    /// Dias promedio considerando tanto las unidades vendidas como las que siguen en cartera.
+    pub fn average_days(&self) -> Option<Decimal> {
+        let units = self.sold_units + self.held_units;
+        if units.is_zero() {
+            return None;
+        }
+        let days = self.sold_days.unwrap_or_default() * self.sold_units
+            + self.held_days.unwrap_or_default() * self.held_units;
+        Some(days / units)
+    }
+}
+
+/// Rotacion de un periodo y permanencia de cada ticker, calculadas desde el diario.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnoverReport {
+    pub from: Date,
+    pub to: Date,
+
+    /// Montos comprados y vendidos en el periodo, a precio de ejecucion.
+    pub purchases: Decimal,
+    pub sales: Decimal,
+
+    /// Valor promedio del portafolio en el periodo, contra el que se mide la rotacion.
+    pub average_value: Decimal,
+
+    /// Una entrada por ticker operado, ordenadas por ticker.
+    pub holdings: Vec<HoldingStats>,
+}
+
+impl TurnoverReport {
+    /// Calcula el reporte con las transacciones de `journal` entre `from` y `to` (inclusive).
+    ///
+    /// Las ventas se asignan a las compras mas recientes del ticker, que es como las ejecuta
+    /// `Portfolio::apply`; las compras anteriores a `from` no se ven, asi que conviene pasar el
+    /// diario desde el inicio de la cuenta y acotar con las fechas.
+    pub fn new(journal: &Journal, from: Date, to: Date, average_value: Decimal) -> Self {
+        let (mut purchases, mut sales) = (Decimal::ZERO, Decimal::ZERO);
+        // ticker -> compras abiertas (fecha, unidades) y acumulados de lo vendido
+        let mut open: BTreeMap<&str, Vec<(Date, Decimal)>> = BTreeMap::new();
+        let mut sold: BTreeMap<&str, (Decimal, Decimal)> = BTreeMap::new();
+
+        for transaction in journal.between(from, to) {
+            let Some(ticker) = transaction.ticker.as_deref() else {
+                continue;
+            };
+            let amount = transaction.units * transaction.price;
+            match transaction.kind {
+                TransactionKind::Buy => {
+                    purchases += amount;
+                    open.entry(ticker)
+                        .or_default()
+                        .push((transaction.date, transaction.units));
+                }
+                TransactionKind::Sell => {
+                    sales += amount;
+                    let lots = open.entry(ticker).or_default();
+                    let (units, days) = sold.entry(ticker).or_default();
+                    let mut pending = transaction.units;
+                    while pending > Decimal::ZERO {
+                        let Some((bought, lot)) = lots.last_mut() else {
+                            break;
+                        };
+                        let taken = pending.min(*lot);
+                        *units += taken;
+                        *days += Decimal::from(bought.days_until(transaction.date)) * taken;
+                        *lot -= taken;
+                        pending -= taken;
+                        if lot.is_zero() {
+                            lots.pop();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let tickers: Vec<&str> = open.keys().chain(sold.keys()).copied().collect();
+        let mut holdings: Vec<HoldingStats> = Vec::new();
+        for ticker in tickers {
+            if holdings.iter().any(|h| h.ticker == ticker) {
+                continue;
+            }
+            let (sold_units, sold_days) = sold.get(ticker).copied().unwrap_or_default();
+            let lots = open.get(ticker).map_or(&[][..], Vec::as_slice);
+            let held_units: Decimal = lots.iter().map(|(_, units)| *units).sum();
+            let held_days: Decimal = lots
+                .iter()
+                .map(|(bought, units)| Decimal::from(bought.days_until(to)) * units)
+                .sum();
+            holdings.push(HoldingStats {
+                ticker: ticker.into(),
+                sold_units,
+                sold_days: (!sold_units.is_zero()).then(|| sold_days / sold_units),
+                held_units,
+                held_days: (!held_units.is_zero()).then(|| held_days / held_units),
+            });
+        }
+        holdings.sort_by(|a, b| a.ticker.cmp(&b.ticker));
+
+        Self {
+            from,
+            to,
+            purchases,
+            sales,
+            average_value,
+            holdings,
+        }
+    }
+
+    /// Rotacion del periodo: lo menor entre compras y ventas sobre el valor promedio (la
+    /// definicion usual de los fondos, que no cuenta como rotacion invertir aportes nuevos).
+    pub fn turnover(&self) -> Option<Decimal> {
+        (!self.average_value.is_zero()).then(|| self.purchases.min(self.sales) / self.average_value)
+    }
+
+    /// `turnover` llevada a un año de 365 dias.
+    pub fn annualized_turnover(&self) -> Option<Decimal> {
+        let days = self.from.days_until(self.to).max(1);
+        Some(self.turnover()? * Decimal::from(365) / Decimal::from(days))
+    }
+}
+
+impl Localize for TurnoverReport {
+    fn localize(&self, language: Language) -> String {
+        let turnover = self.annualized_turnover().map_or("-".into(), |t| {
+            format!("{}%", (t * Decimal::ONE_HUNDRED).round_dp(1))
+        });
+        let header = match language {
+            Language::Es => format!(
+                "Rotacion anualizada entre {} y {}: {turnover}",
+                self.from, self.to
+            ),
+            Language::En => format!(
+                "Annualized turnover between {} and {}: {turnover}",
+                self.from, self.to
+            ),
+        };
+
+        let days = |d: Option<Decimal>| d.map_or("-".into(), |d| d.round_dp(0).to_string());
+        let lines = self.holdings.iter().map(|h| match language {
+            Language::Es => format!(
+                "{}: {} dias en promedio (vendidas: {}, en cartera: {})",
+                h.ticker,
+                days(h.average_days()),
+                days(h.sold_days),
+                days(h.held_days)
+            ),
+            Language::En => format!(
+                "{}: {} days on average (sold: {}, held: {})",
+                h.ticker,
+                days(h.average_days()),
+                days(h.sold_days),
+                days(h.held_days)
+            ),
+        });
+
+        core::iter::once(header)
+            .chain(lines)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for TurnoverReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::Transaction;
+    use rust_decimal_macros::dec;
+
+    fn day(month: u32, d: u32) -> Date {
+        Date::new(2024, month, d).unwrap()
+    }
+
+    #[test]
+    fn test_holding_periods_match_sells_to_latest_buys() {
+        let journal: Journal = [
+            Transaction::trade(day(1, 1), TransactionKind::Buy, "META", dec!(10), dec!(10)),
+            Transaction::trade(day(1, 31), TransactionKind::Buy, "META", dec!(10), dec!(10)),
+            // se venden las 10 compradas el 31 de enero (10 dias) y 5 del 1 de enero (40 dias)
+            Transaction::trade(
+                day(2, 10),
+                TransactionKind::Sell,
+                "META",
+                dec!(15),
+                dec!(12),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let report = TurnoverReport::new(&journal, day(1, 1), day(3, 1), dec!(200));
+        let meta = &report.holdings[0];
+        assert_eq!(meta.sold_units, dec!(15));
+        assert_eq!(meta.sold_days, Some(dec!(20)));
+        assert_eq!(meta.held_units, dec!(5));
+        assert_eq!(meta.held_days, Some(dec!(60)));
+        assert_eq!(meta.average_days(), Some(dec!(30)));
+
+        // min(200, 180) / 200 en 60 dias
+        assert_eq!(report.turnover(), Some(dec!(0.9)));
+        assert_eq!(report.annualized_turnover(), Some(dec!(5.475)));
+    }
+}
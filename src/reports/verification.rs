@@ -0,0 +1,266 @@
+use crate::error::ArithmeticOverflow;
+use crate::i18n::{Language, Localize, language};
+use crate::{Portfolio, PortfolioTarget};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Peso objetivo y actual (en %) de un stock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    pub ticker: String,
+    pub target: Decimal,
+    pub actual: Decimal,
+
+    /// Desviacion maxima aceptada para este stock: su banda en el objetivo o, si no tiene, la
+    /// tolerancia global.
+    pub tolerance: Decimal,
+}
+
+impl Drift {
+    /// Cuanto sobra (positivo) o falta (negativo), en puntos porcentuales.
+    pub fn difference(&self) -> Decimal {
+        self.actual - self.target
+    }
+
+    pub fn is_within_tolerance(&self) -> bool {
+        self.difference().abs() <= self.tolerance
+    }
+}
+
+/// Resultado de comparar un portafolio con su objetivo despues de rebalancear.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// Desviacion maxima aceptada, en puntos porcentuales, para los stocks sin banda propia.
+    pub tolerance: Decimal,
+
+    /// Cada stock del objetivo o de los holdings, ordenado por ticker. Un stock que se tiene pero
+    /// no esta en el objetivo aparece con objetivo 0.
+    pub drifts: Vec<Drift>,
+
+    /// Peso objetivo y actual de la caja.
+    pub cash: (Decimal, Decimal),
+}
+
+impl VerificationReport {
+    /// Los stocks que quedaron fuera de la tolerancia.
+    pub fn residual(&self) -> Vec<&Drift> {
+        self.drifts
+            .iter()
+            .filter(|d| !d.is_within_tolerance())
+            .collect()
+    }
+
+    pub fn is_within_tolerance(&self) -> bool {
+        self.residual().is_empty()
+    }
+
+    /// Una linea con el resultado y los desvios fuera de tolerancia, pensada para
+    /// `RebalanceRecord::with_constraints` o cualquier log de texto.
+    pub fn audit_line(&self) -> String {
+        let status = if self.is_within_tolerance() {
+            "ok"
+        } else {
+            "drift"
+        };
+        let residual: Vec<String> = self
+            .residual()
+            .iter()
+            .map(|d| format!("{}={:+}", d.ticker, d.difference().round_dp(2).normalize()))
+            .collect();
+
+        format!(
+            "verify tolerance={} {status} {}",
+            self.tolerance.normalize(),
+            residual.join(",")
+        )
+        .trim_end()
+        .to_string()
+    }
+}
+
+impl Portfolio {
+    /// Si algun stock se alejo de su objetivo mas de lo que permite su banda (o `tolerance`, si
+    /// no tiene banda propia); ver `PortfolioTarget::with_band`.
+    pub fn needs_rebalance(&self, tolerance: Decimal) -> bool {
+        !self.verify_against_target(tolerance).is_within_tolerance()
+    }
+
+    /// Verifica que el peso de cada stock este a no mas de `tolerance` puntos porcentuales de su
+    /// objetivo (o de su banda, si el objetivo le da una), por ejemplo despues de `apply` o
+    /// `reconcile`.
+    pub fn verify_against_target(&self, tolerance: Decimal) -> VerificationReport {
+        verify(&self.weights(), &self.allocation, tolerance)
+    }
+
+    /// Como `verify_against_target`, pero con un error si la valorizacion no cabe en un
+    /// `Decimal`.
+    pub fn checked_verify_against_target(
+        &self,
+        tolerance: Decimal,
+    ) -> Result<VerificationReport, ArithmeticOverflow> {
+        Ok(verify(
+            &self.checked_weights()?,
+            &self.allocation,
+            tolerance,
+        ))
+    }
+}
+
+/// Compara pesos (en %, ver `Portfolio::weights`) con un objetivo; lo que falta para 100 es caja.
+pub(crate) fn verify(
+    weights: &[(&str, Decimal)],
+    target: &PortfolioTarget,
+    tolerance: Decimal,
+) -> VerificationReport {
+    let actual = |ticker: &str| {
+        weights
+            .iter()
+            .find(|(name, _)| *name == ticker)
+            .map_or(Decimal::ZERO, |(_, weight)| *weight)
+    };
+
+    let mut drifts: Vec<Drift> = target
+        .targets()
+        .iter()
+        .map(|(weight, stock)| Drift {
+            ticker: stock.name().to_string(),
+            target: *weight,
+            actual: actual(stock.name()),
+            tolerance: target.band(stock.name()).unwrap_or(tolerance),
+        })
+        .collect();
+    for (ticker, weight) in weights {
+        if !target.contains_key(ticker) {
+            drifts.push(Drift {
+                ticker: ticker.to_string(),
+                target: Decimal::ZERO,
+                actual: *weight,
+                tolerance,
+            });
+        }
+    }
+    drifts.sort_by(|a, b| a.ticker.cmp(&b.ticker));
+
+    let invested: Decimal = weights.iter().map(|(_, w)| *w).sum();
+    let cash_actual = if weights.is_empty() {
+        Decimal::ZERO
+    } else {
+        Decimal::ONE_HUNDRED - invested
+    };
+
+    VerificationReport {
+        tolerance,
+        drifts,
+        cash: (target.cash_weight(), cash_actual),
+    }
+}
+
+impl Localize for VerificationReport {
+    fn localize(&self, language: Language) -> String {
+        let header = match (self.is_within_tolerance(), language) {
+            (true, Language::Es) => format!("Dentro de la tolerancia de {}%", self.tolerance),
+            (true, Language::En) => format!("Within the {}% tolerance", self.tolerance),
+            (false, Language::Es) => format!("Fuera de la tolerancia de {}%", self.tolerance),
+            (false, Language::En) => format!("Outside the {}% tolerance", self.tolerance),
+        };
+
+        let lines = self.drifts.iter().map(|d| {
+            let mark = if !d.is_within_tolerance() { "! " } else { "" };
+            format!(
+                "{mark}{}: {}% / {}%",
+                d.ticker,
+                d.actual.round_dp(2),
+                d.target.round_dp(2)
+            )
+        });
+
+        core::iter::once(header)
+            .chain(lines)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::i18n::{Language, Localize};
+    use crate::{Portfolio, PortfolioTarget, Stock};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_verify_reports_residual_drift() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("META", dec!(10))),
+            (dec!(50), Stock::new("AAPL", dec!(10))),
+        ])
+        .unwrap();
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: [
+                vec![Stock::new("META", dec!(10)); 6],
+                vec![Stock::new("AAPL", dec!(10)); 4],
+            ]
+            .concat(),
+            allocation: target,
+        };
+
+        let strict = portfolio.verify_against_target(dec!(5));
+        assert!(!strict.is_within_tolerance());
+        assert_eq!(strict.residual().len(), 2);
+        assert_eq!(
+            strict.audit_line(),
+            "verify tolerance=5 drift AAPL=-10,META=+10"
+        );
+        assert_eq!(
+            strict.localize(Language::En),
+            "Outside the 5% tolerance\n! AAPL: 40.00% / 50%\n! META: 60.00% / 50%"
+        );
+
+        let loose = portfolio.verify_against_target(dec!(10));
+        assert!(loose.is_within_tolerance());
+        assert_eq!(loose.audit_line(), "verify tolerance=10 ok");
+    }
+
+    #[test]
+    fn test_per_asset_bands() {
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(50), Stock::new("META", dec!(10))),
+            (dec!(50), Stock::new("BOND", dec!(10))),
+        ])
+        .unwrap()
+        .with_band("BOND", dec!(2));
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: [
+                vec![Stock::new("META", dec!(10)); 53],
+                vec![Stock::new("BOND", dec!(10)); 47],
+            ]
+            .concat(),
+            allocation: target,
+        };
+
+        // 3 puntos de drift: dentro de los 5 globales pero fuera de los 2 del bono
+        let report = portfolio.verify_against_target(dec!(5));
+        assert_eq!(report.residual().len(), 1);
+        assert_eq!(report.residual()[0].ticker, "BOND");
+        assert!(portfolio.needs_rebalance(dec!(5)));
+
+        let loose = Portfolio {
+            allocation: portfolio.allocation.clone().with_band("BOND", dec!(3)),
+            ..portfolio
+        };
+        assert!(!loose.needs_rebalance(dec!(5)));
+    }
+}
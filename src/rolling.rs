@@ -0,0 +1,144 @@
+//! Estadisticas moviles sobre una curva de valor (p. ej. `BacktestResult::equity_curve`).
+//!
+//! Cada serie queda fechada con el ultimo dia de su ventana y en `f64`, que es lo que esperan
+//! las librerias de graficos. La ventana se mide en retornos (observaciones consecutivas de la
+//! curva), no en dias calendario: con precios diarios de bolsa, 21 es un mes y 252 un año.
+
+use crate::date::Date;
+use crate::metrics::{mean, std_dev};
+use rust_decimal::prelude::*;
+
+/// Observaciones por año con precios diarios de bolsa.
+pub const TRADING_DAYS: f64 = 252.0;
+
+/// Estadisticas moviles de una curva de valor.
+#[derive(Debug, Clone)]
+pub struct Rolling {
+    dates: Vec<Date>,
+    values: Vec<f64>,
+    returns: Vec<f64>,
+    window: usize,
+    periods_per_year: f64,
+}
+
+impl Rolling {
+    /// Ventanas de `window` retornos sobre `equity_curve`, anualizando como si fueran precios
+    /// diarios de bolsa (ver `with_periods_per_year`).
+    pub fn new(equity_curve: &[(Date, Decimal)], window: usize) -> Self {
+        let dates = equity_curve.iter().map(|(date, _)| *date).collect();
+        let values: Vec<f64> = equity_curve
+            .iter()
+            .map(|(_, value)| value.to_f64().unwrap_or(0.0))
+            .collect();
+        let returns = values
+            .windows(2)
+            .map(|pair| {
+                if pair[0] == 0.0 {
+                    0.0
+                } else {
+                    pair[1] / pair[0] - 1.0
+                }
+            })
+            .collect();
+
+        Self {
+            dates,
+            values,
+            returns,
+            window: window.max(1),
+            periods_per_year: TRADING_DAYS,
+        }
+    }
+
+    /// Cuantas observaciones hay por año (12 para una curva mensual).
+    pub fn with_periods_per_year(mut self, periods_per_year: f64) -> Self {
+        self.periods_per_year = periods_per_year;
+        self
+    }
+
+    /// Volatilidad anualizada de cada ventana.
+    pub fn volatility(&self) -> Vec<(Date, f64)> {
+        self.each_window(|returns, _| Some(std_dev(returns)? * self.periods_per_year.sqrt()))
+    }
+
+    /// Sharpe anualizado de cada ventana contra una tasa libre de riesgo anual; se omiten las
+    /// ventanas sin volatilidad.
+    pub fn sharpe(&self, risk_free: f64) -> Vec<(Date, f64)> {
+        let per_period = risk_free / self.periods_per_year;
+        self.each_window(|returns, _| {
+            let volatility = std_dev(returns)?;
+            (volatility > 0.0).then(|| {
+                (mean(returns).unwrap_or(0.0) - per_period) / volatility
+                    * self.periods_per_year.sqrt()
+            })
+        })
+    }
+
+    /// Maxima caida (desde un maximo a un minimo posterior) dentro de cada ventana, como
+    /// fraccion positiva (`0.2` = cayo un 20%).
+    pub fn drawdown(&self) -> Vec<(Date, f64)> {
+        self.each_window(|_, values| Some(max_drawdown(values)))
+    }
+
+    /// Aplica `statistic` a cada ventana, con sus retornos y los valores de la curva que los
+    /// generan (uno mas que retornos).
+    fn each_window(&self, statistic: impl Fn(&[f64], &[f64]) -> Option<f64>) -> Vec<(Date, f64)> {
+        if self.returns.len() < self.window {
+            return Vec::new();
+        }
+
+        (self.window..=self.returns.len())
+            .filter_map(|end| {
+                let returns = &self.returns[end - self.window..end];
+                let values = &self.values[end - self.window..=end];
+                Some((self.dates[end], statistic(returns, values)?))
+            })
+            .collect()
+    }
+}
+
+/// Maxima caida de una serie de valores, como fraccion positiva.
+pub fn max_drawdown(values: &[f64]) -> f64 {
+    let mut peak = f64::NEG_INFINITY;
+    values.iter().fold(0.0, |worst, &value| {
+        peak = peak.max(value);
+        if peak > 0.0 {
+            worst.max(1.0 - value / peak)
+        } else {
+            worst
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_rolling_windows() {
+        let d = |day| Date::new(2024, 1, day).unwrap();
+        let curve = [
+            (d(1), dec!(100)),
+            (d(2), dec!(110)),
+            (d(3), dec!(99)),
+            (d(4), dec!(108.9)),
+            (d(5), dec!(108.9)),
+        ];
+        let rolling = Rolling::new(&curve, 2).with_periods_per_year(1.0);
+
+        let drawdown = rolling.drawdown();
+        assert_eq!(drawdown.len(), 3);
+        assert_eq!(drawdown[0].0, d(3));
+        assert!((drawdown[0].1 - 0.1).abs() < 1e-9);
+        assert!((drawdown[1].1 - 0.1).abs() < 1e-9);
+        assert_eq!(drawdown[2].1, 0.0);
+
+        // +10%, -10%: media 0 y desviacion 0.1414
+        let volatility = rolling.volatility();
+        assert!((volatility[0].1 - 0.02f64.sqrt()).abs() < 1e-9);
+        assert!(rolling.sharpe(0.0)[0].1.abs() < 1e-9);
+
+        assert!(Rolling::new(&curve, 10).volatility().is_empty());
+    }
+}
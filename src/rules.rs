@@ -0,0 +1,367 @@
+//! Reglas de cumplimiento.
+//!
+//! Un `RuleSet` junta restricciones de inversion (peso maximo por emisor o sector, tickers
+//! prohibidos, minimo de posiciones) que se pueden revisar sobre un objetivo o un portafolio, y
+//! que tambien sirven como etapa del rebalanceo (ver `pipeline`) para que las sugerencias las
+//! respeten. Los emisores y sectores salen del `Universe`; un ticker que no esta ahi es su propio
+//! emisor y no tiene sector.
+
+use crate::i18n::{Language, Localize, language};
+use crate::pipeline::{Plan, RebalanceStage};
+use crate::universe::Universe;
+use crate::{Portfolio, PortfolioTarget};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+
+/// Una restriccion. Los pesos son en %, como en `PortfolioTarget`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    MaxIssuerWeight(Decimal),
+    MaxSectorWeight(Decimal),
+    Banned(String),
+    MinHoldings(usize),
+}
+
+/// Una regla que no se cumple.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    IssuerOverweight {
+        issuer: String,
+        weight: Decimal,
+        max: Decimal,
+    },
+    SectorOverweight {
+        sector: String,
+        weight: Decimal,
+        max: Decimal,
+    },
+    Banned(String),
+    TooFewHoldings {
+        count: usize,
+        min: usize,
+    },
+}
+
+/// Conjunto de reglas junto con la metadata para evaluarlas.
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    universe: Universe,
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn new(universe: Universe) -> Self {
+        Self {
+            universe,
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    fn issuer<'a>(&'a self, ticker: &'a str) -> &'a str {
+        self.universe
+            .get(ticker)
+            .and_then(|info| info.issuer.as_deref())
+            .unwrap_or(ticker)
+    }
+
+    fn sector(&self, ticker: &str) -> Option<&str> {
+        self.universe.get(ticker)?.sector.as_deref()
+    }
+
+    /// Grupo al que suma el peso de `ticker` para una regla de peso maximo.
+    fn group<'a>(&'a self, rule: &Rule, ticker: &'a str) -> Option<&'a str> {
+        match rule {
+            Rule::MaxIssuerWeight(_) => Some(self.issuer(ticker)),
+            Rule::MaxSectorWeight(_) => self.sector(ticker),
+            _ => None,
+        }
+    }
+
+    /// Evalua las reglas sobre pesos en %. Las posiciones con peso cero no cuentan.
+    pub fn evaluate(&self, weights: &[(&str, Decimal)]) -> Vec<Violation> {
+        let weights: Vec<(&str, Decimal)> = weights
+            .iter()
+            .copied()
+            .filter(|(_, w)| !w.is_zero())
+            .collect();
+
+        let mut by_issuer: BTreeMap<&str, Decimal> = BTreeMap::new();
+        let mut by_sector: BTreeMap<&str, Decimal> = BTreeMap::new();
+        for (ticker, weight) in &weights {
+            *by_issuer.entry(self.issuer(ticker)).or_default() += weight;
+            if let Some(sector) = self.sector(ticker) {
+                *by_sector.entry(sector).or_default() += weight;
+            }
+        }
+
+        let mut violations = Vec::new();
+        for rule in &self.rules {
+            match rule {
+                Rule::MaxIssuerWeight(max) => {
+                    for (issuer, weight) in by_issuer.iter().filter(|(_, w)| *w > max) {
+                        violations.push(Violation::IssuerOverweight {
+                            issuer: issuer.to_string(),
+                            weight: *weight,
+                            max: *max,
+                        });
+                    }
+                }
+                Rule::MaxSectorWeight(max) => {
+                    for (sector, weight) in by_sector.iter().filter(|(_, w)| *w > max) {
+                        violations.push(Violation::SectorOverweight {
+                            sector: sector.to_string(),
+                            weight: *weight,
+                            max: *max,
+                        });
+                    }
+                }
+                Rule::Banned(banned) => {
+                    if weights.iter().any(|(ticker, _)| ticker == banned) {
+                        violations.push(Violation::Banned(banned.clone()));
+                    }
+                }
+                Rule::MinHoldings(min) => {
+                    if weights.len() < *min {
+                        violations.push(Violation::TooFewHoldings {
+                            count: weights.len(),
+                            min: *min,
+                        });
+                    }
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Revisa un objetivo antes de usarlo.
+    pub fn check_target(&self, target: &PortfolioTarget) -> Result<(), Vec<Violation>> {
+        let weights: Vec<(&str, Decimal)> = target
+            .targets()
+            .iter()
+            .map(|(weight, stock)| (stock.name(), *weight))
+            .collect();
+
+        as_result(self.evaluate(&weights))
+    }
+
+    /// Revisa el estado actual de un portafolio.
+    pub fn check_portfolio(&self, portfolio: &Portfolio) -> Result<(), Vec<Violation>> {
+        as_result(self.evaluate(&portfolio.weights()))
+    }
+}
+
+fn as_result(violations: Vec<Violation>) -> Result<(), Vec<Violation>> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// Como etapa del rebalanceo: los tickers prohibidos van a cero y los emisores o sectores que
+/// se pasan del maximo se achican proporcionalmente. `MinHoldings` no se puede forzar
+/// vendiendo o comprando, asi que aqui se ignora.
+impl RebalanceStage for RuleSet {
+    fn name(&self) -> &str {
+        "compliance"
+    }
+
+    fn apply<'a>(&self, portfolio: &'a Portfolio, plan: &mut Plan<'a>) {
+        if plan.total.is_zero() {
+            return;
+        }
+
+        for rule in &self.rules {
+            let max = match rule {
+                Rule::Banned(banned) => {
+                    if let Some(units) = plan.targets.get_mut(banned.as_str()) {
+                        *units = Decimal::ZERO;
+                    }
+                    continue;
+                }
+                Rule::MinHoldings(_) => continue,
+                Rule::MaxIssuerWeight(max) | Rule::MaxSectorWeight(max) => max,
+            };
+
+            let weight = |ticker: &str, units: Decimal| {
+                let price = portfolio
+                    .priced(ticker)
+                    .map_or(Decimal::ZERO, |s| s.current_price());
+                units * price / plan.total * Decimal::ONE_HUNDRED
+            };
+            let mut totals: BTreeMap<&str, Decimal> = BTreeMap::new();
+            for (ticker, units) in &plan.targets {
+                if let Some(key) = self.group(rule, ticker) {
+                    *totals.entry(key).or_default() += weight(ticker, *units);
+                }
+            }
+
+            for (ticker, units) in plan.targets.iter_mut() {
+                let Some(total) = self.group(rule, ticker).and_then(|key| totals.get(key)) else {
+                    continue;
+                };
+                if total > max {
+                    *units = *units * max / total;
+                }
+            }
+        }
+    }
+}
+
+impl Localize for Violation {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (
+                Violation::IssuerOverweight {
+                    issuer,
+                    weight,
+                    max,
+                },
+                Language::Es,
+            ) => format!(
+                "El emisor {issuer} pesa {}% (maximo {max}%)",
+                weight.round_dp(2)
+            ),
+            (
+                Violation::IssuerOverweight {
+                    issuer,
+                    weight,
+                    max,
+                },
+                Language::En,
+            ) => format!(
+                "Issuer {issuer} weighs {}% (max {max}%)",
+                weight.round_dp(2)
+            ),
+            (
+                Violation::SectorOverweight {
+                    sector,
+                    weight,
+                    max,
+                },
+                Language::Es,
+            ) => format!(
+                "El sector {sector} pesa {}% (maximo {max}%)",
+                weight.round_dp(2)
+            ),
+            (
+                Violation::SectorOverweight {
+                    sector,
+                    weight,
+                    max,
+                },
+                Language::En,
+            ) => format!(
+                "Sector {sector} weighs {}% (max {max}%)",
+                weight.round_dp(2)
+            ),
+            (Violation::Banned(ticker), Language::Es) => format!("{ticker} esta prohibido"),
+            (Violation::Banned(ticker), Language::En) => format!("{ticker} is banned"),
+            (Violation::TooFewHoldings { count, min }, Language::Es) => {
+                format!("Hay {count} posiciones (minimo {min})")
+            }
+            (Violation::TooFewHoldings { count, min }, Language::En) => {
+                format!("There are {count} holdings (min {min})")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stock;
+    use crate::money::Currency;
+    use crate::pipeline::Pipeline;
+    use crate::universe::{AssetClass, InstrumentInfo};
+    use rust_decimal_macros::dec;
+
+    fn rules() -> RuleSet {
+        let info = |ticker: &str, sector: &str| {
+            InstrumentInfo::new(ticker, ticker, AssetClass::Equity, Currency::Usd)
+                .with_sector(sector)
+        };
+        let universe = Universe::new()
+            .with(info("GOOGL", "Tech").with_issuer("Alphabet"))
+            .with(info("GOOG", "Tech").with_issuer("Alphabet"))
+            .with(info("XOM", "Energy"));
+
+        RuleSet::new(universe)
+            .with(Rule::MaxIssuerWeight(dec!(40)))
+            .with(Rule::MaxSectorWeight(dec!(70)))
+            .with(Rule::Banned("XOM".into()))
+            .with(Rule::MinHoldings(4))
+    }
+
+    fn target() -> PortfolioTarget {
+        PortfolioTarget::try_from_vec(vec![
+            (dec!(25), Stock::new("GOOGL", dec!(10))),
+            (dec!(25), Stock::new("GOOG", dec!(10))),
+            (dec!(50), Stock::new("XOM", dec!(10))),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_target_violations() {
+        let violations = rules().check_target(&target()).unwrap_err();
+
+        assert_eq!(
+            violations,
+            vec![
+                Violation::IssuerOverweight {
+                    issuer: "Alphabet".into(),
+                    weight: dec!(50),
+                    max: dec!(40),
+                },
+                Violation::IssuerOverweight {
+                    issuer: "XOM".into(),
+                    weight: dec!(50),
+                    max: dec!(40),
+                },
+                Violation::Banned("XOM".into()),
+                Violation::TooFewHoldings { count: 3, min: 4 },
+            ]
+        );
+        assert_eq!(violations[2].localize(Language::Es), "XOM esta prohibido");
+    }
+
+    #[test]
+    fn test_rules_as_rebalance_constraint() {
+        let portfolio = Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: target(),
+        };
+
+        let suggestion = Pipeline::conservative()
+            .insert_before("round", rules())
+            .run(&portfolio);
+
+        // Alphabet se achica de 50% a 40%, repartido entre sus dos series; XOM no se compra
+        assert_eq!(suggestion.to_buy["GOOGL"], 2);
+        assert_eq!(suggestion.to_buy["GOOG"], 2);
+        assert!(!suggestion.to_buy.contains_key("XOM"));
+    }
+}
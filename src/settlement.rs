@@ -0,0 +1,200 @@
+//! Liquidacion de operaciones (T+N).
+//!
+//! En la mayoria de los mercados la plata de una venta no esta disponible el mismo dia: en
+//! acciones de EE.UU. o Chile llega dos dias habiles despues (T+2). Si el rebalanceo vende y
+//! compra el mismo dia, las compras solo pueden usar la caja ya liquidada; el resto tiene que
+//! esperar a que se liquiden las ventas. `Portfolio::apply_settled` separa el plan en esas dos
+//! fases.
+
+use crate::Portfolio;
+use crate::costs::CostModel;
+use crate::date::Date;
+use crate::error::EventError;
+use crate::execution::{Execution, Order, Side};
+use alloc::vec::Vec;
+use rust_decimal::prelude::*;
+
+/// Regla de liquidacion: cuantos dias habiles tarda en estar disponible la plata de una venta.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Settlement {
+    days: u32,
+}
+
+impl Settlement {
+    /// Liquidacion inmediata (T+0): la plata de una venta se puede usar al tiro.
+    pub fn immediate() -> Self {
+        Self::default()
+    }
+
+    /// T+`days` dias habiles.
+    pub fn t_plus(days: u32) -> Self {
+        Self { days }
+    }
+
+    pub fn days(&self) -> u32 {
+        self.days
+    }
+
+    /// Dia en que se liquida una venta hecha el dia `trade_date`.
+    pub fn settles_on(&self, trade_date: Date) -> Date {
+        trade_date.add_business_days(self.days)
+    }
+}
+
+/// Resultado de `Portfolio::apply_settled`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettledExecution {
+    /// Lo que se ejecuto hoy: todas las ventas y las compras (o la parte de ellas) que alcanzo
+    /// a pagar la caja liquidada.
+    pub execution: Execution,
+
+    /// Compras (o el resto de ellas) que esperan a que se liquiden las ventas.
+    pub deferred: Vec<Order>,
+
+    /// Plata de las ventas de hoy que aun no se liquida.
+    pub unsettled: Decimal,
+
+    /// Cuando se liquidan las ventas de hoy, y por lo tanto cuando se pueden hacer las compras
+    /// de `deferred`.
+    pub settles_on: Date,
+}
+
+impl Portfolio {
+    /// Como `apply`, pero respetando la liquidacion: primero las ventas y despues solo las
+    /// compras que alcanza a pagar la caja ya liquidada, que es la caja actual menos
+    /// `unsettled` (lo que aun no se liquida de ventas anteriores) y menos lo que entra por las
+    /// ventas de hoy si `settlement` no es inmediata. Una compra que no alcanza se hace en
+    /// parte y el resto queda en `deferred`.
+    pub fn apply_settled(
+        &mut self,
+        orders: &[Order],
+        date: Date,
+        costs: &CostModel,
+        settlement: &Settlement,
+        unsettled: Decimal,
+    ) -> Result<SettledExecution, EventError> {
+        let (sells, buys): (Vec<&Order>, Vec<&Order>) =
+            orders.iter().partition(|order| order.side == Side::Sell);
+
+        let before = self.cash;
+        let mut execution = Execution::default();
+        for order in sells {
+            let part = self.apply(core::slice::from_ref(order), date, costs)?;
+            execution.transactions.extend(part.transactions);
+        }
+        let proceeds = if settlement.days() == 0 {
+            Decimal::ZERO
+        } else {
+            // solo lo que entro por las ventas; las comisiones salen igual de la caja liquidada
+            (self.cash - before).max(Decimal::ZERO)
+        };
+
+        let mut deferred = Vec::new();
+        for order in buys {
+            let available = self.cash - unsettled - proceeds;
+            let units = self.affordable_units(order, available, costs);
+            if units > 0 {
+                let bought = Order {
+                    units,
+                    ..order.clone()
+                };
+                let part = self.apply(&[bought], date, costs)?;
+                execution.transactions.extend(part.transactions);
+            }
+            if units < order.units {
+                deferred.push(Order {
+                    units: order.units - units,
+                    ..order.clone()
+                });
+            }
+        }
+
+        Ok(SettledExecution {
+            execution,
+            deferred,
+            unsettled: proceeds,
+            settles_on: settlement.settles_on(date),
+        })
+    }
+
+    /// Cuantas unidades de la compra `order` (comision incluida) se pagan con `cash`.
+    pub(crate) fn affordable_units(
+        &self,
+        order: &Order,
+        cash: Decimal,
+        costs: &CostModel,
+    ) -> usize {
+        let Some(price) = self.priced(&order.ticker).map(|s| s.current_price()) else {
+            return order.units;
+        };
+        let cost = |units: usize| price * Decimal::from(units) + costs.commission(units, price);
+        if cost(order.units) <= cash {
+            return order.units;
+        }
+        if price.is_zero() || cash <= Decimal::ZERO {
+            return 0;
+        }
+
+        let mut units = (cash / price)
+            .trunc()
+            .to_usize()
+            .unwrap_or(0)
+            .min(order.units);
+        while units > 0 && cost(units) > cash {
+            units -= 1;
+        }
+        units
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_t_plus_two_defers_buys() {
+        let mut portfolio = Portfolio {
+            cash: dec!(30),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("GOOG", dec!(50)); 2],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(10))),
+        };
+        let orders = portfolio.rebalance_portfolio().orders();
+        // viernes: las ventas se liquidan el martes
+        let friday = Date::new(2024, 5, 3).unwrap();
+
+        let result = portfolio
+            .apply_settled(
+                &orders,
+                friday,
+                &CostModel::free(),
+                &Settlement::t_plus(2),
+                Decimal::ZERO,
+            )
+            .unwrap();
+
+        assert_eq!(result.deferred, vec![Order::buy("META", 10)]);
+        assert_eq!(result.unsettled, dec!(100));
+        assert_eq!(result.settles_on, Date::new(2024, 5, 7).unwrap());
+        assert_eq!(portfolio.value_of("META"), dec!(30));
+
+        let mut immediate = Portfolio {
+            cash: dec!(30),
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("GOOG", dec!(50)); 2],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(10))),
+        };
+        let result = immediate
+            .apply_settled(
+                &orders,
+                friday,
+                &CostModel::free(),
+                &Settlement::immediate(),
+                Decimal::ZERO,
+            )
+            .unwrap();
+        assert!(result.deferred.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{Portfolio, RebalanceSuggestion};
+use rust_decimal::Decimal;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Handle compartible entre threads a un mismo `Portfolio`.
+///
+/// Pensado para un servidor donde un thread recibe cotizaciones y las va aplicando mientras
+/// otros calculan valorizaciones o sugerencias. Muchas lecturas pueden ocurrir en paralelo; una
+/// actualizacion de precios espera a que terminen y bloquea mientras se aplica, asi que nunca se
+/// calcula una sugerencia con la mitad de los precios actualizados.
+///
+/// Clonar el handle es barato y todos los clones apuntan al mismo portafolio.
+#[derive(Debug, Clone)]
+pub struct SharedPortfolio {
+    inner: Arc<RwLock<Portfolio>>,
+}
+
+impl SharedPortfolio {
+    pub fn new(portfolio: Portfolio) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(portfolio)),
+        }
+    }
+
+    // Si algun thread hizo panic con el lock tomado, los datos siguen siendo un portafolio valido
+    // (ninguna operacion lo deja a medias), asi que no tiene sentido propagar el envenenamiento.
+    fn read_guard(&self) -> RwLockReadGuard<'_, Portfolio> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_guard(&self) -> RwLockWriteGuard<'_, Portfolio> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Ejecuta `f` con acceso de lectura al portafolio.
+    pub fn read<R>(&self, f: impl FnOnce(&Portfolio) -> R) -> R {
+        f(&self.read_guard())
+    }
+
+    /// Ejecuta `f` con acceso exclusivo al portafolio.
+    pub fn write<R>(&self, f: impl FnOnce(&mut Portfolio) -> R) -> R {
+        f(&mut self.write_guard())
+    }
+
+    /// Aplica una cotizacion; ver `Portfolio::update_price`.
+    pub fn update_price(&self, ticker: &str, price: Decimal) -> bool {
+        self.write_guard().update_price(ticker, price)
+    }
+
+    /// Aplica un lote de cotizaciones de forma atomica: los lectores ven todas o ninguna.
+    pub fn update_prices<'q>(&self, quotes: impl IntoIterator<Item = (&'q str, Decimal)>) {
+        let mut portfolio = self.write_guard();
+        for (ticker, price) in quotes {
+            portfolio.update_price(ticker, price);
+        }
+    }
+
+    /// Valor total del portafolio con los ultimos precios.
+    pub fn total_value(&self) -> Decimal {
+        self.read(Portfolio::total_value)
+    }
+
+    /// Calcula una sugerencia con los precios actuales y se la pasa a `f`. La sugerencia toma
+    /// prestados los nombres del portafolio, por eso no se puede devolver directamente.
+    pub fn with_suggestion<R>(&self, f: impl FnOnce(&RebalanceSuggestion<'_>) -> R) -> R {
+        let portfolio = self.read_guard();
+        f(&portfolio.rebalance_portfolio())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+    use std::thread;
+
+    #[test]
+    fn test_concurrent_updates_and_reads() {
+        let shared = SharedPortfolio::new(Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("META", dec!(10)); 10],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(10))),
+        });
+
+        let writer = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for i in 1..=100 {
+                    shared.update_price("META", Decimal::from(10 + i));
+                }
+            })
+        };
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        // todas las unidades se actualizan juntas, asi que el total siempre es
+                        // un multiplo de 10
+                        assert!((shared.total_value() % dec!(10)).is_zero());
+                        shared.with_suggestion(|s| assert!(s.to_sell.is_empty()));
+                    }
+                })
+            })
+            .collect();
+
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        assert_eq!(shared.total_value(), dec!(1100));
+    }
+
+    #[test]
+    fn test_update_unknown_ticker() {
+        let shared = SharedPortfolio::new(Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(10))),
+        });
+
+        assert!(shared.update_price("META", dec!(11)));
+        assert!(!shared.update_price("AAPL", dec!(11)));
+        assert_eq!(
+            shared.read(|p| p.allocation().targets()[0].1.current_price()),
+            dec!(11)
+        );
+    }
+}
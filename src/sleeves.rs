@@ -0,0 +1,201 @@
+//! Portafolios divididos en sleeves.
+//!
+//! Una cuenta se puede dividir en partes con nombre ("Core", "Satellite") que tienen su propio
+//! objetivo y un peso sobre el total. El rebalanceo es en dos niveles: primero se decide cuanta
+//! caja mover entre sleeves para que cada uno llegue a su peso, y despues cada sleeve se rebalancea
+//! contra su objetivo con ese presupuesto. Las ordenes de todos los sleeves se netean para
+//! mandarlas juntas al broker (ver `netting`).
+
+use crate::Portfolio;
+use crate::error::TargetError;
+use crate::execution::Order;
+use crate::netting::{NettedOrders, net};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use rust_decimal::prelude::*;
+
+/// Una parte de la cuenta.
+#[derive(Debug, Clone)]
+pub struct Sleeve {
+    pub name: String,
+
+    /// % del valor total de la cuenta.
+    pub weight: Decimal,
+    pub portfolio: Portfolio,
+}
+
+impl Sleeve {
+    pub fn new(name: &str, weight: Decimal, portfolio: Portfolio) -> Self {
+        Self {
+            name: name.into(),
+            weight,
+            portfolio,
+        }
+    }
+
+    fn value(&self) -> Decimal {
+        self.portfolio.total_value()
+    }
+}
+
+/// Cuenta dividida en sleeves cuyos pesos suman 100%.
+#[derive(Debug, Clone)]
+pub struct SleevedPortfolio {
+    sleeves: Vec<Sleeve>,
+}
+
+/// Resultado de rebalancear una cuenta con sleeves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SleevePlan {
+    /// Caja que entra (positiva) o sale (negativa) de cada sleeve; suman cero.
+    pub transfers: BTreeMap<String, Decimal>,
+
+    /// Ordenes de cada sleeve para llegar a su objetivo.
+    pub orders: BTreeMap<String, Vec<Order>>,
+}
+
+impl SleevePlan {
+    /// Ordenes de toda la cuenta: lo que un sleeve vende y otro compra del mismo ticker se
+    /// compensa, asi que solo se transa la diferencia.
+    pub fn net_orders(&self) -> Vec<Order> {
+        self.netted().external
+    }
+
+    /// Como `net_orders`, pero anotando tambien que unidades pasan de un sleeve a otro.
+    pub fn netted(&self) -> NettedOrders {
+        net(&self.orders)
+    }
+}
+
+impl SleevedPortfolio {
+    /// Valida que los pesos de los sleeves sean positivos y sumen 100%.
+    pub fn try_new(sleeves: Vec<Sleeve>) -> Result<Self, TargetError> {
+        if let Some(sleeve) = sleeves.iter().find(|s| s.weight <= Decimal::ZERO) {
+            return Err(TargetError::NonPositiveWeight(sleeve.name.clone()));
+        }
+        let total: Decimal = sleeves.iter().map(|s| s.weight).sum();
+        if total != Decimal::ONE_HUNDRED {
+            return Err(TargetError::InvalidTotal(total));
+        }
+
+        Ok(Self { sleeves })
+    }
+
+    pub fn sleeves(&self) -> &[Sleeve] {
+        &self.sleeves
+    }
+
+    pub fn sleeve(&self, name: &str) -> Option<&Sleeve> {
+        self.sleeves.iter().find(|s| s.name == name)
+    }
+
+    pub fn sleeve_mut(&mut self, name: &str) -> Option<&mut Sleeve> {
+        self.sleeves.iter_mut().find(|s| s.name == name)
+    }
+
+    pub fn total_value(&self) -> Decimal {
+        self.sleeves.iter().map(Sleeve::value).sum()
+    }
+
+    /// % que representa cada sleeve del total hoy.
+    pub fn current_weights(&self) -> BTreeMap<String, Decimal> {
+        let total = self.total_value();
+        self.sleeves
+            .iter()
+            .map(|s| {
+                let weight = if total.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    s.value() / total * Decimal::ONE_HUNDRED
+                };
+                (s.name.clone(), weight)
+            })
+            .collect()
+    }
+
+    /// Rebalancea en dos niveles: mueve caja entre sleeves hasta que cada uno vale su peso del
+    /// total, y rebalancea cada sleeve (con la estrategia conservadora) como si esa caja ya
+    /// hubiera llegado. Un sleeve que queda con caja negativa vende para cubrirla.
+    pub fn rebalance(&self) -> SleevePlan {
+        let total = self.total_value();
+        let mut plan = SleevePlan {
+            transfers: BTreeMap::new(),
+            orders: BTreeMap::new(),
+        };
+
+        for sleeve in &self.sleeves {
+            let transfer = total * sleeve.weight / Decimal::ONE_HUNDRED - sleeve.value();
+
+            let mut budgeted = sleeve.portfolio.clone();
+            budgeted.cash += transfer;
+            let orders = budgeted.rebalance_portfolio().orders();
+
+            plan.transfers.insert(sleeve.name.clone(), transfer);
+            plan.orders.insert(sleeve.name.clone(), orders);
+        }
+
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn sleeve(name: &str, weight: Decimal, target: Stock, stocks: Vec<Stock>) -> Sleeve {
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: PortfolioTarget::new(target),
+        };
+        Sleeve::new(name, weight, portfolio)
+    }
+
+    #[test]
+    fn test_two_level_rebalance() {
+        let voo = Stock::new("VOO", dec!(10));
+        let arkk = Stock::new("ARKK", dec!(10));
+        let account = SleevedPortfolio::try_new(vec![
+            sleeve("Core", dec!(80), voo.clone(), vec![voo.clone(); 6]),
+            sleeve("Satellite", dec!(20), arkk.clone(), vec![arkk.clone(); 4]),
+        ])
+        .unwrap();
+
+        assert_eq!(account.current_weights()["Core"], dec!(60));
+
+        let plan = account.rebalance();
+        assert_eq!(plan.transfers["Core"], dec!(20));
+        assert_eq!(plan.transfers["Satellite"], dec!(-20));
+        assert_eq!(plan.orders["Core"], vec![Order::buy("VOO", 2)]);
+        assert_eq!(plan.orders["Satellite"], vec![Order::sell("ARKK", 2)]);
+        assert_eq!(
+            plan.net_orders(),
+            vec![Order::sell("ARKK", 2), Order::buy("VOO", 2)]
+        );
+    }
+
+    #[test]
+    fn test_orders_net_across_sleeves() {
+        let voo = Stock::new("VOO", dec!(10));
+        let mut a = sleeve("A", dec!(50), voo.clone(), vec![]);
+        a.portfolio.cash = dec!(30);
+        let b = sleeve(
+            "B",
+            dec!(50),
+            Stock::new("BND", dec!(10)),
+            vec![voo.clone(); 3],
+        );
+        let account = SleevedPortfolio::try_new(vec![a, b]).unwrap();
+
+        // A compra 3 VOO y B vende sus 3: la cuenta no transa VOO
+        let plan = account.rebalance();
+        assert_eq!(plan.net_orders(), vec![Order::buy("BND", 3)]);
+        assert_eq!(plan.netted().transfers[0].from, "B");
+
+        assert!(SleevedPortfolio::try_new(vec![sleeve("A", dec!(50), voo, vec![])]).is_err());
+    }
+}
@@ -0,0 +1,768 @@
+//! Snapshots binarios de un portafolio.
+//!
+//! JSON es comodo pero pesado para portafolios grandes; este formato es compacto y rapido de
+//! leer. Esta escrito a mano (sin serde ni bincode) porque asi controlo exactamente los bytes y
+//! puedo garantizar compatibilidad hacia adelante:
+//!
+//! ```text
+//! "FTPF" | version mayor (u8) | version menor (u8) | secciones...
+//! seccion = tag (u8) | largo (u32 LE) | contenido
+//! ```
+//!
+//! Una version menor nueva solo puede agregar secciones; un lector antiguo las salta usando el
+//! largo. Un cambio incompatible sube la version mayor y los lectores antiguos lo rechazan con un
+//! error claro en vez de leer basura.
+//!
+//! Desde la version 1.5, lo que una unidad tiene ademas de ticker y precio (moneda, lote minimo,
+//! tipo de instrumento, costo de compra, bloqueo, puntaje ESG, puntas y metadata) va en una
+//! seccion aparte, solo para los grupos de unidades que lo tienen. Un lector 1.4 la salta y
+//! recupera el portafolio como antes, con los precios de valorizacion de siempre.
+
+use crate::bond::{Bond, CouponFrequency};
+use crate::date::Date;
+use crate::i18n::{Language, Localize, language};
+use crate::lots::CostBasis;
+use crate::metadata::Metadata;
+use crate::money::Currency;
+use crate::quote::BidAsk;
+use crate::{Allocation, InstrumentKind, Portfolio, PortfolioTarget, Stock};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"FTPF";
+pub const FORMAT_MAJOR: u8 = 1;
+pub const FORMAT_MINOR: u8 = 5;
+
+const TAG_HOLDINGS: u8 = 1;
+const TAG_TARGET: u8 = 2;
+// desde la version 1.1
+const TAG_CASH: u8 = 3;
+// desde la version 1.2
+const TAG_TARGET_CASH: u8 = 4;
+// desde la version 1.3
+const TAG_FOREIGN_CASH: u8 = 5;
+// desde la version 1.4
+const TAG_TARGET_BANDS: u8 = 6;
+// desde la version 1.5
+const TAG_DETAILS: u8 = 7;
+
+/// Maximo de unidades en un snapshot. Al leerlo cada unidad se vuelve un `Stock`, asi que un
+/// conteo manipulado no puede pedir gigabytes de memoria.
+pub const MAX_UNITS: usize = 1_000_000;
+
+/// Errores al leer un snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// Los bytes no empiezan con la firma del formato.
+    BadMagic,
+
+    /// El snapshot fue escrito por una version incompatible (mas nueva) del formato.
+    UnsupportedVersion { found: u8, supported: u8 },
+
+    /// Se acabaron los bytes en medio de un campo.
+    Truncated,
+
+    /// Los bytes se leyeron bien pero no forman un portafolio valido.
+    InvalidData(String),
+
+    /// El portafolio no cabe en el formato (un texto de mas de 65535 bytes o mas de
+    /// `MAX_UNITS` unidades).
+    TooLarge(String),
+}
+
+impl Localize for SnapshotError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            SnapshotError::BadMagic => match language {
+                Language::Es => "Los datos no son un snapshot de portafolio.".into(),
+                Language::En => "Data is not a portfolio snapshot.".into(),
+            },
+            SnapshotError::UnsupportedVersion { found, supported } => match language {
+                Language::Es => format!(
+                    "Snapshot en formato v{found}, esta version solo entiende hasta v{supported}."
+                ),
+                Language::En => {
+                    format!("Snapshot format v{found}, this version only reads up to v{supported}.")
+                }
+            },
+            SnapshotError::Truncated => match language {
+                Language::Es => "El snapshot esta incompleto.".into(),
+                Language::En => "The snapshot is truncated.".into(),
+            },
+            SnapshotError::InvalidData(reason) => match language {
+                Language::Es => format!("Snapshot invalido: {reason}"),
+                Language::En => format!("Invalid snapshot: {reason}"),
+            },
+            SnapshotError::TooLarge(reason) => match language {
+                Language::Es => format!("El portafolio no cabe en un snapshot: {reason}"),
+                Language::En => format!("The portfolio does not fit in a snapshot: {reason}"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) -> Result<(), SnapshotError> {
+        let len = u16::try_from(value.len())
+            .map_err(|_| SnapshotError::TooLarge(format!("texto de {} bytes", value.len())))?;
+        self.u16(len);
+        self.bytes.extend_from_slice(value.as_bytes());
+        Ok(())
+    }
+
+    fn decimal(&mut self, value: Decimal) {
+        self.bytes.extend_from_slice(&value.serialize());
+    }
+
+    fn date(&mut self, value: Date) {
+        self.bytes
+            .extend_from_slice(&value.days_since_epoch().to_le_bytes());
+    }
+
+    /// Un byte 0/1 y, si hay valor, el valor.
+    fn option<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        self.u8(value.is_some() as u8);
+        if let Some(value) = value {
+            write(self, value);
+        }
+    }
+
+    fn section(&mut self, tag: u8, content: Writer) {
+        self.bytes.push(tag);
+        self.u32(content.bytes.len() as u32);
+        self.bytes.extend(content.bytes);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < n {
+            return Err(SnapshotError::Truncated);
+        }
+
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn str(&mut self) -> Result<String, SnapshotError> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| SnapshotError::InvalidData("ticker no es UTF-8".into()))
+    }
+
+    fn decimal(&mut self) -> Result<Decimal, SnapshotError> {
+        Ok(Decimal::deserialize(self.take(16)?.try_into().unwrap()))
+    }
+
+    fn date(&mut self) -> Result<Date, SnapshotError> {
+        let days = i64::from_le_bytes(self.take(8)?.try_into().unwrap());
+        // fuera de este rango el año no cabe en un `Date` (y el calculo se desborda)
+        let first = Date::new(i32::MIN, 1, 1).unwrap().days_since_epoch();
+        let last = Date::new(i32::MAX, 12, 31).unwrap().days_since_epoch();
+        if !(first..=last).contains(&days) {
+            return Err(SnapshotError::InvalidData(format!(
+                "fecha fuera de rango: {days}"
+            )));
+        }
+        Ok(Date::from_days_since_epoch(days))
+    }
+
+    fn option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, SnapshotError>,
+    ) -> Result<Option<T>, SnapshotError> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => read(self).map(Some),
+            other => Err(SnapshotError::InvalidData(format!(
+                "marca de opcional invalida: {other}"
+            ))),
+        }
+    }
+}
+
+/// Lo que tiene `stock` ademas de ticker y precio, o nada si es un stock como los de
+/// `Stock::new`; asi los portafolios simples no crecen.
+fn details(stock: &Stock) -> Result<Vec<u8>, SnapshotError> {
+    let plain = Stock::new(&stock.name, stock.current_price);
+    if stock.currency == plain.currency
+        && stock.increment == plain.increment
+        && stock.kind == plain.kind
+        && stock.basis.is_none()
+        && stock.esg.is_none()
+        && stock.locked_until.is_none()
+        && stock.bid_ask.is_none()
+        && stock.metadata().is_empty()
+    {
+        return Ok(Vec::new());
+    }
+
+    let mut out = Writer { bytes: Vec::new() };
+    out.str(stock.currency.code())?;
+    out.decimal(stock.increment);
+    match &stock.kind {
+        InstrumentKind::Equity => out.u8(0),
+        InstrumentKind::Bond { terms, valued_at } => {
+            out.u8(1);
+            // la seccion de holdings guarda el precio sucio; aca va la cotizacion limpia
+            out.decimal(stock.quote());
+            out.decimal(terms.face_value);
+            out.decimal(terms.coupon_rate);
+            out.date(terms.maturity);
+            out.u8(terms.frequency.per_year() as u8);
+            out.date(*valued_at);
+        }
+        InstrumentKind::Fund { cut_off } => {
+            out.u8(2);
+            out.u32(*cut_off);
+        }
+    }
+    out.option(stock.basis(), |out, basis| {
+        out.decimal(basis.cost);
+        out.date(basis.acquired);
+        out.option(basis.fx_rate, Writer::decimal);
+    });
+    out.option(stock.esg, Writer::decimal);
+    out.option(stock.locked_until, Writer::date);
+    out.option(stock.bid_ask(), |out, quote| {
+        out.decimal(quote.bid);
+        out.decimal(quote.ask);
+    });
+
+    let metadata = stock.metadata();
+    let tags: Vec<&str> = metadata.tags().collect();
+    out.u32(tags.len() as u32);
+    for tag in tags {
+        out.str(tag)?;
+    }
+    let fields: Vec<(&str, &str)> = metadata.fields().collect();
+    out.u32(fields.len() as u32);
+    for (key, value) in fields {
+        out.str(key)?;
+        out.str(value)?;
+    }
+    Ok(out.bytes)
+}
+
+/// `stock` con los datos escritos por `details`.
+fn read_details(reader: &mut Reader, mut stock: Stock) -> Result<Stock, SnapshotError> {
+    let code = reader.str()?;
+    stock.currency = Currency::from_code(&code)
+        .ok_or_else(|| SnapshotError::InvalidData(format!("moneda desconocida: {code}")))?;
+    stock.increment = reader.decimal()?;
+    stock.kind = match reader.u8()? {
+        0 => InstrumentKind::Equity,
+        1 => {
+            stock.current_price = reader.decimal()?;
+            let face_value = reader.decimal()?;
+            let coupon_rate = reader.decimal()?;
+            let maturity = reader.date()?;
+            let frequency = match reader.u8()? {
+                1 => CouponFrequency::Annual,
+                2 => CouponFrequency::SemiAnnual,
+                4 => CouponFrequency::Quarterly,
+                12 => CouponFrequency::Monthly,
+                other => {
+                    return Err(SnapshotError::InvalidData(format!(
+                        "frecuencia de cupon invalida: {other}"
+                    )));
+                }
+            };
+            InstrumentKind::Bond {
+                terms: Bond {
+                    face_value,
+                    coupon_rate,
+                    maturity,
+                    frequency,
+                },
+                valued_at: reader.date()?,
+            }
+        }
+        2 => InstrumentKind::Fund {
+            cut_off: reader.u32()?,
+        },
+        other => {
+            return Err(SnapshotError::InvalidData(format!(
+                "tipo de instrumento desconocido: {other}"
+            )));
+        }
+    };
+    stock.basis = reader
+        .option(|reader| {
+            Ok(CostBasis {
+                cost: reader.decimal()?,
+                acquired: reader.date()?,
+                fx_rate: reader.option(Reader::decimal)?,
+            })
+        })?
+        .map(Box::new);
+    stock.esg = reader.option(Reader::decimal)?;
+    stock.locked_until = reader.option(Reader::date)?;
+    stock.bid_ask = reader
+        .option(|reader| {
+            Ok(BidAsk {
+                bid: reader.decimal()?,
+                ask: reader.decimal()?,
+            })
+        })?
+        .map(Box::new);
+
+    let mut metadata = Metadata::new();
+    for _ in 0..reader.u32()? {
+        metadata = metadata.with_tag(&reader.str()?);
+    }
+    for _ in 0..reader.u32()? {
+        let key = reader.str()?;
+        metadata = metadata.with_field(&key, &reader.str()?);
+    }
+    Ok(stock.with_metadata(metadata))
+}
+
+/// Grupos (ver `TAG_DETAILS`) con datos ademas de ticker y precio: indice y datos.
+fn write_details(out: &mut Writer, details: &[Vec<u8>]) {
+    let filled: Vec<(usize, &Vec<u8>)> = details
+        .iter()
+        .enumerate()
+        .filter(|(_, details)| !details.is_empty())
+        .collect();
+    out.u32(filled.len() as u32);
+    for (index, details) in filled {
+        out.u32(index as u32);
+        out.bytes.extend_from_slice(details);
+    }
+}
+
+impl Portfolio {
+    /// Serializa el portafolio (holdings con todos sus datos, objetivo y efectivo) al formato
+    /// binario.
+    ///
+    /// Las unidades repetidas de un mismo stock al mismo precio (y con los mismos datos) se
+    /// guardan una sola vez con su cantidad, que es lo que hace al formato compacto.
+    ///
+    /// Falla con `SnapshotError::TooLarge` si el portafolio tiene mas de `MAX_UNITS` unidades o
+    /// algun texto (ticker, tag o metadata) de mas de 65535 bytes.
+    pub fn to_snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        if self.stocks().len() > MAX_UNITS {
+            return Err(SnapshotError::TooLarge(format!(
+                "{} unidades (maximo {MAX_UNITS})",
+                self.stocks().len()
+            )));
+        }
+
+        let mut lots: Vec<(&str, Decimal, Vec<u8>, u32)> = Vec::new();
+        for stock in self.stocks() {
+            let extra = details(stock)?;
+            match lots.iter_mut().find(|(name, price, details, _)| {
+                *name == stock.name() && *price == stock.current_price() && *details == extra
+            }) {
+                Some((_, _, _, units)) => *units += 1,
+                None => lots.push((stock.name(), stock.current_price(), extra, 1)),
+            }
+        }
+
+        let mut holdings = Writer { bytes: Vec::new() };
+        holdings.u32(lots.len() as u32);
+        for (name, price, _, units) in &lots {
+            holdings.str(name)?;
+            holdings.decimal(*price);
+            holdings.u32(*units);
+        }
+
+        let mut target = Writer { bytes: Vec::new() };
+        target.u32(self.allocation().targets().len() as u32);
+        for (weight, stock) in self.allocation().targets() {
+            target.str(stock.name())?;
+            target.decimal(stock.current_price());
+            target.decimal(*weight);
+        }
+
+        let mut out = Writer {
+            bytes: MAGIC.to_vec(),
+        };
+        out.bytes.push(FORMAT_MAJOR);
+        out.bytes.push(FORMAT_MINOR);
+        out.section(TAG_HOLDINGS, holdings);
+        out.section(TAG_TARGET, target);
+
+        let mut cash = Writer { bytes: Vec::new() };
+        cash.decimal(self.cash());
+        out.section(TAG_CASH, cash);
+
+        let mut target_cash = Writer { bytes: Vec::new() };
+        target_cash.decimal(self.allocation().cash_weight());
+        out.section(TAG_TARGET_CASH, target_cash);
+
+        let mut foreign_cash = Writer { bytes: Vec::new() };
+        foreign_cash.u32(self.foreign_cash().len() as u32);
+        for (currency, amount) in self.foreign_cash() {
+            foreign_cash.str(currency.code())?;
+            foreign_cash.decimal(*amount);
+        }
+        out.section(TAG_FOREIGN_CASH, foreign_cash);
+
+        let mut bands = Writer { bytes: Vec::new() };
+        let banded: Vec<(&str, Decimal)> = self
+            .allocation()
+            .targets()
+            .iter()
+            .filter_map(|(_, stock)| Some((stock.name(), self.allocation().band(stock.name())?)))
+            .collect();
+        bands.u32(banded.len() as u32);
+        for (ticker, band) in banded {
+            bands.str(ticker)?;
+            bands.decimal(band);
+        }
+        out.section(TAG_TARGET_BANDS, bands);
+
+        let lots: Vec<Vec<u8>> = lots.into_iter().map(|(_, _, details, _)| details).collect();
+        let targets: Vec<Vec<u8>> = self
+            .allocation()
+            .targets()
+            .iter()
+            .map(|(_, stock)| details(stock))
+            .collect::<Result<_, _>>()?;
+        // un portafolio sin nada extra queda igual que en la 1.4
+        if lots
+            .iter()
+            .chain(&targets)
+            .any(|details| !details.is_empty())
+        {
+            let mut extra = Writer { bytes: Vec::new() };
+            write_details(&mut extra, &lots);
+            write_details(&mut extra, &targets);
+            out.section(TAG_DETAILS, extra);
+        }
+
+        Ok(out.bytes)
+    }
+
+    /// Reconstruye un portafolio desde un snapshot. El objetivo se vuelve a validar, asi que un
+    /// snapshot manipulado no puede producir un objetivo que no sume 100%.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(4).map_err(|_| SnapshotError::BadMagic)? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+
+        let major = reader.u8()?;
+        let _minor = reader.u8()?;
+        if major > FORMAT_MAJOR {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: major,
+                supported: FORMAT_MAJOR,
+            });
+        }
+
+        let mut stocks = Vec::new();
+        let mut targets = None;
+        // los snapshots 1.0 no tenian efectivo
+        let mut cash = Decimal::ZERO;
+        // ni objetivos con efectivo antes de la 1.2
+        let mut target_cash = Decimal::ZERO;
+        let mut foreign_cash = BTreeMap::new();
+        let mut bands = Vec::new();
+        // primera unidad, cantidad y stock de cada grupo de holdings, para aplicarles
+        // `TAG_DETAILS`
+        let mut lots = Vec::new();
+        let mut extra = None;
+
+        while !reader.bytes.is_empty() {
+            let tag = reader.u8()?;
+            let len = reader.u32()? as usize;
+            let mut section = Reader {
+                bytes: reader.take(len)?,
+            };
+
+            match tag {
+                TAG_HOLDINGS => {
+                    for _ in 0..section.u32()? {
+                        let name = section.str()?;
+                        let price = section.decimal()?;
+                        let units = section.u32()? as usize;
+                        if stocks.len() + units > MAX_UNITS {
+                            return Err(SnapshotError::InvalidData(format!(
+                                "mas de {MAX_UNITS} unidades"
+                            )));
+                        }
+                        let stock = Stock::new(&name, price);
+                        lots.push((stocks.len(), units, stock.clone()));
+                        stocks.extend(std::iter::repeat_n(stock, units));
+                    }
+                }
+                TAG_TARGET => {
+                    let mut entries = Vec::new();
+                    for _ in 0..section.u32()? {
+                        let name = section.str()?;
+                        let price = section.decimal()?;
+                        entries.push((section.decimal()?, Stock::new(&name, price)));
+                    }
+                    targets = Some(entries);
+                }
+                TAG_CASH => cash = section.decimal()?,
+                TAG_TARGET_CASH => target_cash = section.decimal()?,
+                TAG_FOREIGN_CASH => {
+                    for _ in 0..section.u32()? {
+                        let code = section.str()?;
+                        let currency = Currency::from_code(&code).ok_or_else(|| {
+                            SnapshotError::InvalidData(format!("moneda desconocida: {code}"))
+                        })?;
+                        foreign_cash.insert(currency, section.decimal()?);
+                    }
+                }
+                TAG_TARGET_BANDS => {
+                    for _ in 0..section.u32()? {
+                        let ticker = section.str()?;
+                        bands.push((ticker, section.decimal()?));
+                    }
+                }
+                TAG_DETAILS => extra = Some(section),
+                // seccion de una version menor mas nueva: se ignora
+                _ => {}
+            }
+        }
+
+        let mut targets =
+            targets.ok_or_else(|| SnapshotError::InvalidData("falta el objetivo".into()))?;
+
+        if let Some(mut section) = extra {
+            let out_of_range =
+                || SnapshotError::InvalidData("grupo de unidades inexistente".into());
+            for _ in 0..section.u32()? {
+                let (start, units, stock) =
+                    lots.get(section.u32()? as usize).ok_or_else(out_of_range)?;
+                let stock = read_details(&mut section, stock.clone())?;
+                stocks[*start..start + units].fill(stock);
+            }
+            for _ in 0..section.u32()? {
+                let (_, target) = targets
+                    .get_mut(section.u32()? as usize)
+                    .ok_or_else(out_of_range)?;
+                *target = read_details(&mut section, target.clone())?;
+            }
+        }
+
+        let mut targets: Vec<Allocation> = targets
+            .into_iter()
+            .map(|(weight, stock)| Allocation::Stock(weight, stock))
+            .collect();
+        if !target_cash.is_zero() {
+            targets.push(Allocation::Cash(target_cash));
+        }
+        let allocation = PortfolioTarget::try_from_allocations(targets)
+            .map_err(|e| SnapshotError::InvalidData(e.to_string()))?;
+        let allocation = bands.iter().fold(allocation, |allocation, (ticker, band)| {
+            allocation.with_band(ticker, *band)
+        });
+
+        Ok(Portfolio {
+            stocks,
+            allocation,
+            cash,
+            foreign_cash,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        let mut stocks = vec![Stock::new("META", dec!(10.5)); 1000];
+        stocks.push(Stock::new("AAPL", dec!(15)));
+
+        Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: BTreeMap::from([(Currency::Eur, dec!(12.5))]),
+            stocks,
+            allocation: PortfolioTarget::try_from_allocations(vec![
+                Allocation::Stock(dec!(40), Stock::new("META", dec!(10.5))),
+                Allocation::Stock(dec!(50), Stock::new("AAPL", dec!(15))),
+                Allocation::Cash(dec!(10)),
+            ])
+            .unwrap()
+            .with_band("AAPL", dec!(2)),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_is_compact() {
+        let original = portfolio();
+        let bytes = original.to_snapshot().unwrap();
+        let restored = Portfolio::from_snapshot(&bytes).unwrap();
+
+        assert!(bytes.len() < 256);
+        assert_eq!(restored.allocation().cash_weight(), dec!(10));
+        assert_eq!(restored.allocation().band("AAPL"), Some(dec!(2)));
+        assert_eq!(restored.foreign_cash()[&Currency::Eur], dec!(12.5));
+        assert_eq!(
+            restored.state_id(Default::default()),
+            original.state_id(Default::default())
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_keeps_unit_details() {
+        let day = |d| Date::new(2024, 3, d).unwrap();
+        let san = Stock::new("SAN", dec!(5))
+            .with_currency(Currency::Clp)
+            .with_basis(dec!(4), day(1))
+            .with_lock_up(day(20))
+            .with_tag("core")
+            .with_field("cuenta", "APV");
+        let bond = Stock::bond(
+            "BONO",
+            Bond::new(dec!(1000), dec!(0.05), Date::new(2030, 1, 15).unwrap()),
+            dec!(98),
+            day(10),
+        );
+        let fund = Stock::fund("FONDO", dec!(12), 18 * 60)
+            .with_fx_basis(dec!(11), day(2), dec!(900))
+            .with_bid_ask(dec!(11.9), dec!(12.1))
+            .with_esg_score(dec!(7));
+
+        let mut stocks = vec![san.clone(); 3];
+        stocks.extend(vec![Stock::new("SAN", dec!(5)); 2]);
+        stocks.extend([bond, fund]);
+        let original = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: BTreeMap::new(),
+            stocks,
+            allocation: PortfolioTarget::try_from_vec(vec![(dec!(100), san)]).unwrap(),
+        };
+        let restored = Portfolio::from_snapshot(&original.to_snapshot().unwrap()).unwrap();
+
+        let describe = |stock: &Stock| {
+            (
+                stock.name().to_string(),
+                stock.current_price(),
+                stock.currency,
+                stock.kind().clone(),
+                stock.basis().copied(),
+                stock.esg_score(),
+                stock.locked_until(),
+                stock.bid_ask(),
+                stock.metadata().clone(),
+            )
+        };
+        let all = |portfolio: &Portfolio| {
+            let mut stocks: Vec<_> = portfolio.stocks().iter().map(describe).collect();
+            stocks.extend(
+                portfolio
+                    .allocation()
+                    .targets()
+                    .iter()
+                    .map(|(_, s)| describe(s)),
+            );
+            stocks
+        };
+        assert_eq!(all(&restored), all(&original));
+    }
+
+    #[test]
+    fn test_unknown_sections_are_skipped() {
+        let mut bytes = portfolio().to_snapshot().unwrap();
+        bytes[5] = FORMAT_MINOR + 1;
+        bytes.extend_from_slice(&[99, 3, 0, 0, 0, 1, 2, 3]);
+
+        assert!(Portfolio::from_snapshot(&bytes).is_ok());
+    }
+
+    #[test]
+    fn test_incompatible_or_broken_snapshots() {
+        let mut bytes = portfolio().to_snapshot().unwrap();
+        bytes[4] = FORMAT_MAJOR + 1;
+        assert_eq!(
+            Portfolio::from_snapshot(&bytes).unwrap_err(),
+            SnapshotError::UnsupportedVersion {
+                found: FORMAT_MAJOR + 1,
+                supported: FORMAT_MAJOR,
+            }
+        );
+
+        let bytes = portfolio().to_snapshot().unwrap();
+        assert_eq!(
+            Portfolio::from_snapshot(&bytes[..bytes.len() - 3]).unwrap_err(),
+            SnapshotError::Truncated
+        );
+        assert_eq!(
+            Portfolio::from_snapshot(b"{}").unwrap_err(),
+            SnapshotError::BadMagic
+        );
+    }
+
+    #[test]
+    fn test_hostile_counts_dates_and_long_texts() {
+        let mut holdings = Writer { bytes: Vec::new() };
+        holdings.u32(1);
+        holdings.str("META").unwrap();
+        holdings.decimal(dec!(1));
+        holdings.u32(u32::MAX);
+        let mut out = Writer {
+            bytes: MAGIC.to_vec(),
+        };
+        out.u8(FORMAT_MAJOR);
+        out.u8(FORMAT_MINOR);
+        out.section(TAG_HOLDINGS, holdings);
+        assert!(matches!(
+            Portfolio::from_snapshot(&out.bytes),
+            Err(SnapshotError::InvalidData(_))
+        ));
+
+        let mut reader = Reader {
+            bytes: &i64::MAX.to_le_bytes(),
+        };
+        assert!(matches!(reader.date(), Err(SnapshotError::InvalidData(_))));
+
+        let mut original = portfolio();
+        original.stocks[0] = original.stocks[0].clone().with_tag(&"x".repeat(70_000));
+        assert!(matches!(
+            original.to_snapshot(),
+            Err(SnapshotError::TooLarge(_))
+        ));
+    }
+}
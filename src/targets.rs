@@ -0,0 +1,376 @@
+//! Edicion y versiones de objetivos.
+//!
+//! Un `PortfolioTarget` siempre suma 100%, asi que cada edicion reparte la diferencia entre el
+//! resto de las entradas (efectivo incluido) en proporcion a sus pesos. Si el resultado no seria
+//! un objetivo valido, la edicion falla y el objetivo queda como estaba.
+//!
+//! Para armar objetivos desde iteradores estan `TargetBuilder` (que implementa `FromIterator`) y
+//! las conversiones `TryFrom`.
+//!
+//! Cuando la politica de inversion cambia en el tiempo, un `TargetHistory` guarda cada version con
+//! la fecha desde la que rige, y `Portfolio::rebalance_portfolio_at` usa la vigente a una fecha.
+
+use crate::Map;
+use crate::date::Date;
+use crate::error::TargetError;
+use crate::{Allocation, Portfolio, PortfolioTarget, RebalanceSuggestion, Stock};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use rust_decimal::prelude::*;
+
+impl PortfolioTarget {
+    fn position(&self, ticker: &str) -> Result<usize, TargetError> {
+        self.targets
+            .iter()
+            .position(|(_, stock)| stock.name() == ticker)
+            .ok_or_else(|| TargetError::NotInTarget(ticker.into()))
+    }
+
+    /// Cambia el peso de `ticker` a `weight` % y escala el resto para que siga sumando 100%.
+    pub fn set_weight(&mut self, ticker: &str, weight: Decimal) -> Result<(), TargetError> {
+        if weight <= Decimal::ZERO {
+            return Err(TargetError::NonPositiveWeight(ticker.into()));
+        }
+        let index = self.position(ticker)?;
+
+        let mut edited = self.clone();
+        let previous = core::mem::replace(&mut edited.targets[index].0, weight);
+        edited.rescale(Some(index), Decimal::ONE_HUNDRED - previous)?;
+
+        *self = edited;
+        Ok(())
+    }
+
+    /// Saca `ticker` del objetivo y reparte su peso entre el resto.
+    pub fn remove(&mut self, ticker: &str) -> Result<Stock, TargetError> {
+        let index = self.position(ticker)?;
+
+        let mut edited = self.clone();
+        let (weight, stock) = edited.targets.remove(index);
+        edited.rescale(None, Decimal::ONE_HUNDRED - weight)?;
+
+        *self = edited;
+        Ok(stock)
+    }
+
+    /// Redondea los pesos a 2 decimales (p. ej. despues de varias ediciones que dejaron
+    /// 33,333...%), dejando el residuo en la entrada mas grande para que sigan sumando 100%.
+    pub fn renormalize(&mut self) {
+        for (weight, _) in self.targets.iter_mut() {
+            *weight = weight.round_dp(2);
+        }
+        self.cash = self.cash.round_dp(2);
+        self.absorb_residual();
+    }
+
+    /// Escala todas las entradas salvo `fixed`, que hoy suman `current`, para que el objetivo
+    /// vuelva a sumar 100%.
+    fn rescale(&mut self, fixed: Option<usize>, current: Decimal) -> Result<(), TargetError> {
+        let fixed_weight = fixed.map_or(Decimal::ZERO, |index| self.targets[index].0);
+        let wanted = Decimal::ONE_HUNDRED - fixed_weight;
+
+        if current.is_zero() {
+            // no hay con que compensar
+            if wanted.is_zero() {
+                return Ok(());
+            }
+            return Err(TargetError::InvalidTotal(fixed_weight));
+        }
+
+        let factor = wanted / current;
+        for (index, (weight, stock)) in self.targets.iter_mut().enumerate() {
+            if Some(index) == fixed {
+                continue;
+            }
+            *weight *= factor;
+            if *weight <= Decimal::ZERO {
+                return Err(TargetError::NonPositiveWeight(stock.name().into()));
+            }
+        }
+        if !self.cash.is_zero() {
+            self.cash *= factor;
+            if self.cash <= Decimal::ZERO {
+                return Err(TargetError::NonPositiveWeight("cash".into()));
+            }
+        }
+
+        self.absorb_residual();
+        Ok(())
+    }
+
+    /// Suma a la entrada mas grande lo que le falta (o sobra) al total para ser 100% exacto.
+    fn absorb_residual(&mut self) {
+        let total: Decimal = self.targets.iter().map(|(w, _)| *w).sum::<Decimal>() + self.cash;
+        let residual = Decimal::ONE_HUNDRED - total;
+        if residual.is_zero() {
+            return;
+        }
+
+        let largest = self.targets.iter_mut().map(|(weight, _)| weight).max();
+        match largest {
+            Some(weight) if *weight >= self.cash => *weight += residual,
+            _ => self.cash += residual,
+        }
+    }
+}
+
+/// Acumula entradas de un objetivo y las valida todas juntas en `build`.
+#[derive(Debug, Clone, Default)]
+pub struct TargetBuilder {
+    allocations: Vec<Allocation>,
+}
+
+impl TargetBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_stock(mut self, weight: Decimal, stock: Stock) -> Self {
+        self.allocations.push(Allocation::Stock(weight, stock));
+        self
+    }
+
+    pub fn with_cash(mut self, weight: Decimal) -> Self {
+        self.allocations.push(Allocation::Cash(weight));
+        self
+    }
+
+    /// Mismas validaciones que `PortfolioTarget::try_from_allocations`.
+    pub fn build(self) -> Result<PortfolioTarget, TargetError> {
+        PortfolioTarget::try_from_allocations(self.allocations)
+    }
+}
+
+impl FromIterator<Allocation> for TargetBuilder {
+    fn from_iter<I: IntoIterator<Item = Allocation>>(iter: I) -> Self {
+        Self {
+            allocations: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl FromIterator<(Decimal, Stock)> for TargetBuilder {
+    fn from_iter<I: IntoIterator<Item = (Decimal, Stock)>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|(weight, stock)| Allocation::Stock(weight, stock))
+            .collect()
+    }
+}
+
+impl TryFrom<Vec<(Decimal, Stock)>> for PortfolioTarget {
+    type Error = TargetError;
+
+    fn try_from(stocks: Vec<(Decimal, Stock)>) -> Result<Self, Self::Error> {
+        Self::try_from_vec(stocks)
+    }
+}
+
+/// Pesos por ticker junto con los precios de donde sacar cada stock (p. ej. lo que devuelve
+/// `PriceSource::latest_prices`). Las entradas quedan ordenadas por ticker.
+impl TryFrom<(Map<String, Decimal>, &Map<String, Decimal>)> for PortfolioTarget {
+    type Error = TargetError;
+
+    fn try_from(
+        (weights, prices): (Map<String, Decimal>, &Map<String, Decimal>),
+    ) -> Result<Self, Self::Error> {
+        let mut weights: Vec<(String, Decimal)> = weights.into_iter().collect();
+        weights.sort();
+
+        weights
+            .into_iter()
+            .map(|(ticker, weight)| {
+                let price = prices
+                    .get(&ticker)
+                    .ok_or_else(|| TargetError::MissingPrice(ticker.clone()))?;
+                Ok((weight, Stock::new(&ticker, *price)))
+            })
+            .collect::<Result<TargetBuilder, TargetError>>()?
+            .build()
+    }
+}
+
+/// Versiones de un objetivo, cada una con la fecha desde la que rige.
+#[derive(Debug, Clone, Default)]
+pub struct TargetHistory {
+    versions: BTreeMap<Date, PortfolioTarget>,
+}
+
+impl TargetHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agrega una version que rige desde `effective`; devuelve la que habia esa misma fecha.
+    pub fn insert(&mut self, effective: Date, target: PortfolioTarget) -> Option<PortfolioTarget> {
+        self.versions.insert(effective, target)
+    }
+
+    /// Version encadenable de `insert`.
+    pub fn with_version(mut self, effective: Date, target: PortfolioTarget) -> Self {
+        self.insert(effective, target);
+        self
+    }
+
+    /// La version vigente el dia `date` (la ultima que empezo a regir ese dia o antes) y su fecha.
+    pub fn effective_at(&self, date: Date) -> Option<(Date, &PortfolioTarget)> {
+        self.versions
+            .range(..=date)
+            .next_back()
+            .map(|(effective, target)| (*effective, target))
+    }
+
+    /// Todas las versiones, de la mas antigua a la mas nueva.
+    pub fn versions(&self) -> impl Iterator<Item = (Date, &PortfolioTarget)> {
+        self.versions.iter().map(|(date, target)| (*date, target))
+    }
+}
+
+impl Portfolio {
+    /// Como `rebalance_portfolio`, pero contra la version del objetivo vigente el dia `date` en vez
+    /// de `allocation()`. Los precios de esa version se toman del portafolio cuando los tiene,
+    /// porque los guardados en el historial pueden estar viejos. `None` si ninguna version rige
+    /// todavia.
+    pub fn rebalance_portfolio_at<'a>(
+        &'a self,
+        history: &'a TargetHistory,
+        date: Date,
+    ) -> Option<RebalanceSuggestion<'a>> {
+        let (_, target) = history.effective_at(date)?;
+
+        let mut versioned = self.clone();
+        versioned.allocation = target.clone();
+        for (_, stock) in versioned.allocation.targets.iter_mut() {
+            if let Some(current) = self.priced(stock.name()) {
+                stock.current_price = current.current_price;
+            }
+        }
+
+        // la sugerencia toma prestados los nombres de la copia; se traducen a los de `self` o
+        // del historial para poder devolverla
+        let plan = versioned.rebalance_portfolio();
+        let held = self.stocks.iter();
+        let targeted = target.targets().iter().map(|(_, stock)| stock);
+        let names: Vec<&'a str> = held.chain(targeted).map(Stock::name).collect();
+        let find = |name: &str| *names.iter().find(|n| **n == name).unwrap();
+
+        Some(RebalanceSuggestion {
+            id: plan.id,
+            to_buy: plan.to_buy.iter().map(|(n, u)| (find(n), *u)).collect(),
+            to_sell: plan.to_sell.iter().map(|(n, u)| (find(n), *u)).collect(),
+            wash_sales: Vec::new(),
+            priorities: plan.priorities,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Allocation;
+    use rust_decimal_macros::dec;
+
+    fn target() -> PortfolioTarget {
+        PortfolioTarget::try_from_allocations(vec![
+            Allocation::Stock(dec!(50), Stock::new("META", dec!(10))),
+            Allocation::Stock(dec!(30), Stock::new("AAPL", dec!(10))),
+            Allocation::Cash(dec!(20)),
+        ])
+        .unwrap()
+    }
+
+    fn total(target: &PortfolioTarget) -> Decimal {
+        target.targets().iter().map(|(w, _)| *w).sum::<Decimal>() + target.cash_weight()
+    }
+
+    #[test]
+    fn test_set_weight_and_remove_keep_100() {
+        let mut target = target();
+
+        target.set_weight("META", dec!(60)).unwrap();
+        assert_eq!(target.targets()[0].0, dec!(60));
+        assert_eq!(target.targets()[1].0, dec!(24));
+        assert_eq!(target.cash_weight(), dec!(16));
+
+        let removed = target.remove("AAPL").unwrap();
+        assert_eq!(removed.name(), "AAPL");
+        assert_eq!(total(&target), dec!(100));
+        assert_eq!(target.targets().len(), 1);
+
+        target.set_weight("META", dec!(100) / dec!(3)).unwrap();
+        target.renormalize();
+        assert_eq!(target.targets()[0].0, dec!(33.33));
+        assert_eq!(target.cash_weight(), dec!(66.67));
+    }
+
+    #[test]
+    fn test_invalid_edits_leave_target_untouched() {
+        let mut target = target();
+
+        assert_eq!(
+            target.set_weight("TSLA", dec!(10)),
+            Err(TargetError::NotInTarget("TSLA".into()))
+        );
+        assert_eq!(
+            target.set_weight("META", dec!(100)),
+            Err(TargetError::NonPositiveWeight("AAPL".into()))
+        );
+
+        let mut single = PortfolioTarget::new(Stock::new("META", dec!(10)));
+        assert!(single.set_weight("META", dec!(50)).is_err());
+        assert!(single.remove("META").is_err());
+
+        assert_eq!(target.targets()[0].0, dec!(50));
+        assert_eq!(total(&target), dec!(100));
+    }
+
+    #[test]
+    fn test_targets_from_iterators() {
+        let target: PortfolioTarget = ["META", "AAPL"]
+            .into_iter()
+            .map(|ticker| (dec!(50), Stock::new(ticker, dec!(10))))
+            .collect::<TargetBuilder>()
+            .build()
+            .unwrap();
+        assert_eq!(target.targets().len(), 2);
+
+        let weights = Map::from([("META".to_string(), dec!(60)), ("AAPL".into(), dec!(40))]);
+        let mut prices = Map::from([("META".to_string(), dec!(500))]);
+        assert_eq!(
+            PortfolioTarget::try_from((weights.clone(), &prices)).unwrap_err(),
+            TargetError::MissingPrice("AAPL".into())
+        );
+
+        prices.insert("AAPL".into(), dec!(200));
+        let target = PortfolioTarget::try_from((weights, &prices)).unwrap();
+        assert_eq!(target.targets()[0].1.name(), "AAPL");
+        assert_eq!(target.targets()[1].1.current_price(), dec!(500));
+
+        let invalid = vec![(dec!(90), Stock::new("META", dec!(1)))];
+        assert!(PortfolioTarget::try_from(invalid).is_err());
+    }
+
+    #[test]
+    fn test_rebalance_uses_version_effective_at_date() {
+        let date = |month| Date::new(2024, month, 1).unwrap();
+        let history = TargetHistory::new()
+            .with_version(date(1), PortfolioTarget::new(Stock::new("META", dec!(1))))
+            .with_version(date(6), PortfolioTarget::new(Stock::new("AAPL", dec!(1))));
+        let portfolio = Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: PortfolioTarget::new(Stock::new("AAPL", dec!(20))),
+        };
+
+        let march = portfolio.rebalance_portfolio_at(&history, date(3)).unwrap();
+        assert_eq!(march.to_buy["META"], 100);
+
+        // el precio de AAPL sale del portafolio, no del historial
+        let july = portfolio.rebalance_portfolio_at(&history, date(7)).unwrap();
+        assert_eq!(july.to_buy["AAPL"], 5);
+
+        let before = Date::new(2023, 12, 31).unwrap();
+        assert!(portfolio.rebalance_portfolio_at(&history, before).is_none());
+    }
+}
@@ -0,0 +1,442 @@
+//! Consideraciones tributarias sobre las sugerencias.
+//!
+//! Cosecha de perdidas: vender lotes con perdidas no realizadas para usarlas tributariamente y
+//! comprar un sustituto parecido (p. ej. otro ETF del mismo indice), para que el portafolio siga
+//! expuesto a lo mismo.
+//!
+//! Una venta lavada (wash sale) es vender con perdida y recomprar el mismo instrumento dentro de
+//! una ventana de dias (30 en EE.UU.); la perdida no se puede deducir. El diario de
+//! transacciones dice que se compro y vendio recientemente, y con eso se marcan las operaciones
+//! sugeridas que caerian en la regla.
+
+use crate::date::Date;
+use crate::i18n::{Language, Localize, language};
+use crate::journal::{Journal, Transaction, TransactionKind};
+use crate::lots::Lot;
+use crate::{Portfolio, RebalanceSuggestion, Stock};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+/// Configuracion de la regla de ventas lavadas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WashSaleRule {
+    /// Dias antes y despues de una venta con perdida en los que una compra la "lava".
+    pub window_days: i64,
+
+    /// Quitar de la sugerencia las compras que lavarian una venta reciente.
+    pub suppress_buys: bool,
+}
+
+impl Default for WashSaleRule {
+    fn default() -> Self {
+        Self {
+            window_days: 30,
+            suppress_buys: false,
+        }
+    }
+}
+
+/// Una operacion sugerida que cae en la regla.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WashSaleConflict {
+    /// Se sugiere vender con perdida algo que se compro dentro de la ventana.
+    SellAfterPurchase { ticker: String, purchased: Date },
+
+    /// Se sugiere recomprar algo que se vendio con perdida dentro de la ventana.
+    BuyAfterLossSale {
+        ticker: String,
+        sold: Date,
+        suppressed: bool,
+    },
+}
+
+/// Costo promedio de las compras de `ticker` hasta `until` (inclusive), o `None` si no hay.
+fn average_cost(journal: &Journal, ticker: &str, until: Date) -> Option<Decimal> {
+    let (units, cost) = journal
+        .for_ticker(ticker)
+        .filter(|t| t.kind == TransactionKind::Buy && t.date <= until)
+        .fold((Decimal::ZERO, Decimal::ZERO), |(units, cost), t| {
+            (units + t.units, cost + t.units * t.price)
+        });
+
+    if units.is_zero() {
+        None
+    } else {
+        Some(cost / units)
+    }
+}
+
+/// Si la venta del diario fue con perdida respecto del costo promedio a esa fecha.
+fn is_loss_sale(journal: &Journal, sale: &Transaction) -> bool {
+    let ticker = sale.ticker.as_deref().unwrap_or_default();
+    average_cost(journal, ticker, sale.date).is_some_and(|cost| sale.price < cost)
+}
+
+impl Portfolio {
+    /// Marca en la sugerencia las operaciones que generarian una venta lavada, considerando las
+    /// transacciones del diario dentro de la ventana hasta `today`:
+    /// - una venta con perdida (precio actual bajo el costo promedio) de algo comprado en la
+    ///   ventana;
+    /// - una compra de algo vendido con perdida en la ventana, que ademas se quita de la
+    ///   sugerencia si `rule.suppress_buys`.
+    pub fn check_wash_sales(
+        &self,
+        suggestion: &mut RebalanceSuggestion<'_>,
+        journal: &Journal,
+        today: Date,
+        rule: &WashSaleRule,
+    ) {
+        let window_start = today.add_days(-rule.window_days);
+        let in_window = |t: &&Transaction| (window_start..=today).contains(&t.date);
+
+        let mut sells: Vec<&str> = suggestion.to_sell.keys().copied().collect();
+        sells.sort_unstable();
+        for ticker in sells {
+            let Some(price) = self.priced(ticker).map(|s| s.current_price()) else {
+                continue;
+            };
+            let at_loss = average_cost(journal, ticker, today).is_some_and(|cost| price < cost);
+
+            let purchase = journal
+                .for_ticker(ticker)
+                .filter(|t| t.kind == TransactionKind::Buy)
+                .filter(in_window)
+                .last();
+
+            if let Some(purchase) = purchase.filter(|_| at_loss) {
+                suggestion
+                    .wash_sales
+                    .push(WashSaleConflict::SellAfterPurchase {
+                        ticker: ticker.into(),
+                        purchased: purchase.date,
+                    });
+            }
+        }
+
+        let mut buys: Vec<&str> = suggestion.to_buy.keys().copied().collect();
+        buys.sort_unstable();
+        for ticker in buys {
+            let sale = journal
+                .for_ticker(ticker)
+                .filter(|t| t.kind == TransactionKind::Sell)
+                .filter(in_window)
+                .filter(|t| is_loss_sale(journal, t))
+                .last();
+
+            if let Some(sale) = sale {
+                if rule.suppress_buys {
+                    suggestion.to_buy.remove(ticker);
+                }
+                suggestion
+                    .wash_sales
+                    .push(WashSaleConflict::BuyAfterLossSale {
+                        ticker: ticker.into(),
+                        sold: sale.date,
+                        suppressed: rule.suppress_buys,
+                    });
+            }
+        }
+    }
+}
+
+/// Sustitutos para la cosecha de perdidas.
+#[derive(Debug, Clone, Default)]
+pub struct Substitutes {
+    /// Ticker original → instrumento que lo reemplaza (con su precio actual).
+    pairs: BTreeMap<String, Stock>,
+
+    /// Maximo % del portafolio que puede quedar en caja por redondeo al cambiar un ticker por su
+    /// sustituto; si se pasa, ese cambio no se sugiere.
+    tolerance: Decimal,
+}
+
+impl Substitutes {
+    pub fn new() -> Self {
+        Self {
+            pairs: BTreeMap::new(),
+            tolerance: dec!(1),
+        }
+    }
+
+    pub fn with(mut self, ticker: &str, substitute: Stock) -> Self {
+        self.pairs.insert(ticker.into(), substitute);
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: Decimal) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn get(&self, ticker: &str) -> Option<&Stock> {
+        self.pairs.get(ticker)
+    }
+}
+
+/// Ventas y compras de una cosecha de perdidas.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HarvestPlan {
+    /// Lotes a vender.
+    pub lots: Vec<Lot>,
+
+    pub to_sell: BTreeMap<String, usize>,
+    pub to_buy: BTreeMap<String, usize>,
+
+    /// Perdida total realizada (positiva).
+    pub harvested: Decimal,
+}
+
+impl Portfolio {
+    /// Propone vender los lotes cuya perdida no realizada es al menos `min_loss` y reemplazarlos
+    /// por su sustituto con lo que se obtiene de la venta.
+    ///
+    /// Solo se consideran tickers con sustituto, y un cambio se descarta si el redondeo a
+    /// unidades enteras del sustituto dejaria en caja mas que `substitutes.tolerance` % del
+    /// portafolio: la idea es no mover la asignacion. `Portfolio::apply` vende las unidades mas
+    /// recientes, asi que para vender exactamente estos lotes hay que ejecutarlos por lote en el
+    /// broker.
+    pub fn harvest_losses(&self, min_loss: Decimal, substitutes: &Substitutes) -> HarvestPlan {
+        let total = self.total_value();
+
+        let mut losing: BTreeMap<String, Vec<Lot>> = BTreeMap::new();
+        for lot in self.lots() {
+            if -lot.unrealized() >= min_loss && lot.unrealized() < Decimal::ZERO {
+                losing.entry(lot.ticker.clone()).or_default().push(lot);
+            }
+        }
+
+        let mut plan = HarvestPlan::default();
+        for (ticker, lots) in losing {
+            let Some(substitute) = substitutes.get(&ticker) else {
+                continue;
+            };
+            let price = substitute.current_price();
+            if price.is_zero() || total.is_zero() {
+                continue;
+            }
+
+            let proceeds: Decimal = lots.iter().map(Lot::market_value).sum();
+            let units = (proceeds / price).trunc().to_usize().unwrap_or(0);
+            let leftover = proceeds - price * Decimal::from(units);
+            if leftover / total * dec!(100) > substitutes.tolerance {
+                continue;
+            }
+
+            plan.to_sell
+                .insert(ticker, lots.iter().map(|lot| lot.units).sum());
+            if units > 0 {
+                *plan.to_buy.entry(substitute.name().into()).or_default() += units;
+            }
+            plan.harvested -= lots.iter().map(Lot::unrealized).sum::<Decimal>();
+            plan.lots.extend(lots);
+        }
+
+        plan
+    }
+}
+
+impl Localize for HarvestPlan {
+    fn localize(&self, language: Language) -> String {
+        let (sell, buy, harvested, nothing) = match language {
+            Language::Es => (
+                "Vender",
+                "Comprar",
+                "Perdida cosechada",
+                "No hay perdidas que cosechar.",
+            ),
+            Language::En => ("Sell", "Buy", "Harvested loss", "No losses to harvest."),
+        };
+
+        if self.to_sell.is_empty() {
+            return nothing.into();
+        }
+
+        self.to_sell
+            .iter()
+            .map(|(ticker, units)| format!("{sell} {units} {ticker}"))
+            .chain(
+                self.to_buy
+                    .iter()
+                    .map(|(ticker, units)| format!("{buy} {units} {ticker}")),
+            )
+            .chain(core::iter::once(format!(
+                "{harvested}: {}",
+                self.harvested.round_dp(2)
+            )))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for HarvestPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl Localize for WashSaleConflict {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            WashSaleConflict::SellAfterPurchase { ticker, purchased } => match language {
+                Language::Es => format!(
+                    "Vender {ticker} con perdida seria una venta lavada (comprado el {purchased})"
+                ),
+                Language::En => format!(
+                    "Selling {ticker} at a loss would be a wash sale (bought on {purchased})"
+                ),
+            },
+            WashSaleConflict::BuyAfterLossSale {
+                ticker,
+                sold,
+                suppressed,
+            } => {
+                let text = match language {
+                    Language::Es => {
+                        format!("Comprar {ticker} lavaria la perdida de la venta del {sold}")
+                    }
+                    Language::En => {
+                        format!("Buying {ticker} would wash the loss from the sale on {sold}")
+                    }
+                };
+                match (suppressed, language) {
+                    (false, _) => text,
+                    (true, Language::Es) => format!("{text}; compra omitida"),
+                    (true, Language::En) => format!("{text}; buy skipped"),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for WashSaleConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn d(month: u32, day: u32) -> Date {
+        Date::new(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_loss_sell_after_recent_purchase_is_flagged() {
+        let journal: Journal = vec![
+            Transaction::trade(d(1, 2), TransactionKind::Buy, "GOOG", dec!(2), dec!(60)),
+            Transaction::trade(d(3, 1), TransactionKind::Buy, "GOOG", dec!(1), dec!(60)),
+        ]
+        .into_iter()
+        .collect();
+
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("GOOG", dec!(50)); 3],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(30))),
+        };
+
+        let mut suggestion = portfolio.rebalance_portfolio();
+        portfolio.check_wash_sales(
+            &mut suggestion,
+            &journal,
+            d(3, 20),
+            &WashSaleRule::default(),
+        );
+
+        assert_eq!(
+            suggestion.wash_sales,
+            vec![WashSaleConflict::SellAfterPurchase {
+                ticker: "GOOG".into(),
+                purchased: d(3, 1),
+            }]
+        );
+
+        // fuera de la ventana ya no hay conflicto
+        let mut suggestion = portfolio.rebalance_portfolio();
+        portfolio.check_wash_sales(&mut suggestion, &journal, d(5, 1), &WashSaleRule::default());
+        assert!(suggestion.wash_sales.is_empty());
+    }
+
+    #[test]
+    fn test_harvest_swaps_losing_lots_for_substitute() {
+        let vti = Stock::new("VTI", dec!(200));
+        let mut stocks = vec![vti.clone().with_basis(dec!(250), d(1, 2)); 5];
+        stocks.extend(vec![vti.clone().with_basis(dec!(180), d(2, 1)); 5]);
+        stocks.push(Stock::new("BND", dec!(70)).with_basis(dec!(75), d(1, 2)));
+
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks,
+            allocation: PortfolioTarget::new(vti),
+        };
+        let substitutes = Substitutes::new().with("VTI", Stock::new("ITOT", dec!(100)));
+
+        let plan = portfolio.harvest_losses(dec!(100), &substitutes);
+
+        // solo el lote de enero pierde (5 x 50); BND pierde 5 pero no llega al minimo
+        assert_eq!(plan.to_sell, BTreeMap::from([("VTI".to_string(), 5)]));
+        assert_eq!(plan.to_buy, BTreeMap::from([("ITOT".to_string(), 10)]));
+        assert_eq!(plan.harvested, dec!(250));
+        assert_eq!(plan.lots.len(), 1);
+    }
+
+    #[test]
+    fn test_harvest_respects_tolerance() {
+        let portfolio = Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("VTI", dec!(200)).with_basis(dec!(250), d(1, 2))],
+            allocation: PortfolioTarget::new(Stock::new("VTI", dec!(200))),
+        };
+
+        // con ITOT a 150 quedan 50 en caja, un 25% del portafolio
+        let substitutes = Substitutes::new().with("VTI", Stock::new("ITOT", dec!(150)));
+        assert_eq!(
+            portfolio
+                .harvest_losses(dec!(1), &substitutes)
+                .localize(Language::En),
+            "No losses to harvest."
+        );
+    }
+
+    #[test]
+    fn test_repurchase_after_loss_sale_can_be_suppressed() {
+        let journal: Journal = vec![
+            Transaction::trade(d(1, 2), TransactionKind::Buy, "META", dec!(5), dec!(40)),
+            Transaction::trade(d(3, 1), TransactionKind::Sell, "META", dec!(5), dec!(30)),
+        ]
+        .into_iter()
+        .collect();
+
+        let portfolio = Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(30))),
+        };
+        let rule = WashSaleRule {
+            suppress_buys: true,
+            ..Default::default()
+        };
+
+        let mut suggestion = portfolio.rebalance_portfolio();
+        portfolio.check_wash_sales(&mut suggestion, &journal, d(3, 15), &rule);
+
+        assert!(suggestion.to_buy.is_empty());
+        assert_eq!(
+            suggestion.localize(Language::En),
+            "No trades suggested.\n! Buying META would wash the loss from the sale on 2024-03-01; buy skipped"
+        );
+    }
+}
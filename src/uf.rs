@@ -0,0 +1,109 @@
+//! Unidad de Fomento (UF).
+//!
+//! La UF es la unidad de cuenta chilena que se reajusta diariamente por inflacion; muchos fondos
+//! y depositos en Chile cotizan en UF. En la libreria es la moneda `Currency::Clf`: un stock que
+//! cotiza en UF usa `with_currency(Currency::Clf)`, y para valorizarlo junto a acciones en dolares
+//! y caja en pesos basta agregar el valor de la UF del dia a las `FxRates` con `with_uf`.
+//!
+//! El valor de la UF lo entrega un `UfProvider`; `UfSeries` es una serie en memoria (p. ej.
+//! cargada desde la serie publicada por el Banco Central).
+
+use crate::date::Date;
+use crate::fx::{FxError, FxRates};
+use crate::money::Currency;
+use alloc::collections::BTreeMap;
+use rust_decimal::Decimal;
+
+/// Algo que sabe cuantos pesos vale una UF en una fecha.
+pub trait UfProvider {
+    fn uf_on(&self, date: Date) -> Option<Decimal>;
+}
+
+/// Serie de valores diarios de la UF, en pesos.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UfSeries {
+    values: BTreeMap<Date, Decimal>,
+}
+
+impl UfSeries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_value(mut self, date: Date, clp: Decimal) -> Self {
+        self.values.insert(date, clp);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// El valor del dia o, si falta, el ultimo conocido antes de esa fecha; `None` antes del primero.
+impl UfProvider for UfSeries {
+    fn uf_on(&self, date: Date) -> Option<Decimal> {
+        self.values
+            .range(..=date)
+            .next_back()
+            .map(|(_, value)| *value)
+    }
+}
+
+impl FxRates {
+    /// Agrega la UF del dia `date` a los tipos de cambio. Si la base no es el peso, se pasa a la
+    /// base con el tipo de cambio del peso, que entonces tiene que estar.
+    pub fn with_uf(self, provider: &dyn UfProvider, date: Date) -> Result<Self, FxError> {
+        let uf = provider
+            .uf_on(date)
+            .ok_or(FxError::MissingRate(Currency::Clf))?;
+        let clp = self.rate(Currency::Clp, self.base())?;
+        Ok(self.with_rate(Currency::Clf, uf * clp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Portfolio, PortfolioTarget, Stock};
+    use alloc::vec;
+    use rust_decimal_macros::dec;
+
+    fn d(day: u32) -> Date {
+        Date::new(2024, 6, day).unwrap()
+    }
+
+    #[test]
+    fn test_uf_fund_alongside_usd_stock() {
+        let series = UfSeries::new()
+            .with_value(d(1), dec!(37_500))
+            .with_value(d(3), dec!(37_510));
+        assert_eq!(series.uf_on(d(2)), Some(dec!(37_500)));
+        assert_eq!(series.uf_on(Date::new(2024, 5, 31).unwrap()), None);
+
+        let rates = FxRates::new(Currency::Clp)
+            .with_rate(Currency::Usd, dec!(900))
+            .with_uf(&series, d(3))
+            .unwrap();
+        assert_eq!(rates.rate(Currency::Clf, Currency::Clp), Ok(dec!(37_510)));
+
+        // un fondo de 1 UF por cuota y una accion de 50 USD, 50/50, con 1.000.000 CLP en caja
+        let fund = Stock::new("FONDO-UF", dec!(1)).with_currency(Currency::Clf);
+        let spy = Stock::new("SPY", dec!(50)).with_currency(Currency::Usd);
+        let portfolio = Portfolio {
+            cash: dec!(1_000_000),
+            foreign_cash: Default::default(),
+            stocks: vec![],
+            allocation: PortfolioTarget::try_from_vec(vec![(dec!(50), fund), (dec!(50), spy)])
+                .unwrap(),
+        };
+        let suggestion = portfolio.rebalance_with_fx(&rates, dec!(0.01)).unwrap();
+
+        // 500.000 CLP: 13 cuotas de 37.510 y 11 SPY de 45.000
+        assert_eq!(suggestion.trades.to_buy["FONDO-UF"], 13);
+        assert_eq!(suggestion.trades.to_buy["SPY"], 11);
+        // las cuotas en UF se pagan directo en pesos; solo se convierte a dolares
+        assert_eq!(suggestion.conversions.len(), 1);
+        assert_eq!(suggestion.conversions[0].to, Currency::Usd);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::error::TargetError;
+use crate::money::Currency;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Tipo de activo de un instrumento.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetClass {
+    Equity,
+    FixedIncome,
+    Commodity,
+    RealEstate,
+    Crypto,
+    Cash,
+}
+
+/// Metadata de un instrumento invertible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstrumentInfo {
+    pub ticker: String,
+    pub name: String,
+    pub asset_class: AssetClass,
+    pub currency: Currency,
+    pub sector: Option<String>,
+
+    /// Emisor, p. ej. para agrupar las distintas series de acciones de una misma empresa.
+    pub issuer: Option<String>,
+}
+
+impl InstrumentInfo {
+    pub fn new(ticker: &str, name: &str, asset_class: AssetClass, currency: Currency) -> Self {
+        Self {
+            ticker: ticker.into(),
+            name: name.into(),
+            asset_class,
+            currency,
+            sector: None,
+            issuer: None,
+        }
+    }
+
+    pub fn with_sector(mut self, sector: &str) -> Self {
+        self.sector = Some(sector.into());
+        self
+    }
+
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+}
+
+/// Conjunto de instrumentos en los que se permite invertir.
+///
+/// La idea es que un objetivo solo pueda referirse a tickers que conocemos; un "APPL" escrito a
+/// mano deberia fallar al construir el `PortfolioTarget`, no al momento de mandar la orden.
+#[derive(Debug, Clone, Default)]
+pub struct Universe {
+    // BTreeMap para que `tickers()` tenga un orden estable.
+    instruments: BTreeMap<String, InstrumentInfo>,
+}
+
+impl Universe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agrega (o reemplaza) un instrumento al universo.
+    pub fn insert(&mut self, info: InstrumentInfo) {
+        self.instruments.insert(info.ticker.clone(), info);
+    }
+
+    /// Version encadenable de `insert`.
+    pub fn with(mut self, info: InstrumentInfo) -> Self {
+        self.insert(info);
+        self
+    }
+
+    pub fn remove(&mut self, ticker: &str) -> Option<InstrumentInfo> {
+        self.instruments.remove(ticker)
+    }
+
+    pub fn get(&self, ticker: &str) -> Option<&InstrumentInfo> {
+        self.instruments.get(ticker)
+    }
+
+    pub fn contains(&self, ticker: &str) -> bool {
+        self.instruments.contains_key(ticker)
+    }
+
+    pub fn tickers(&self) -> impl Iterator<Item = &str> {
+        self.instruments.keys().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &InstrumentInfo> {
+        self.instruments.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    /// Verifica que el ticker exista; si no, intenta sugerir el ticker mas parecido para que el
+    /// error sea util ("APPL" -> "AAPL").
+    pub fn check(&self, ticker: &str) -> Result<&InstrumentInfo, TargetError> {
+        self.get(ticker).ok_or_else(|| TargetError::UnknownTicker {
+            ticker: ticker.into(),
+            did_you_mean: self.closest(ticker),
+        })
+    }
+
+    /// Ticker del universo mas cercano por distancia de edicion, si es que hay alguno razonable
+    /// (a lo mas 2 ediciones).
+    fn closest(&self, ticker: &str) -> Option<String> {
+        self.tickers()
+            .map(|candidate| (edit_distance(ticker, candidate), candidate))
+            .filter(|(distance, _)| *distance <= 2)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate.to_string())
+    }
+}
+
+/// Distancia de Damerau-Levenshtein restringida; cuenta una transposicion ("PA" -> "AP") como una
+/// sola edicion, que es el error de tipeo mas comun en tickers.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal_macros::dec;
+
+    fn universe() -> Universe {
+        Universe::new()
+            .with(InstrumentInfo::new(
+                "AAPL",
+                "Apple Inc.",
+                AssetClass::Equity,
+                Currency::Usd,
+            ))
+            .with(InstrumentInfo::new(
+                "META",
+                "Meta Platforms",
+                AssetClass::Equity,
+                Currency::Usd,
+            ))
+    }
+
+    #[test]
+    fn test_target_with_known_tickers_is_valid() {
+        let target = PortfolioTarget::try_from_vec_in(
+            vec![
+                (dec!(40), Stock::new("META", dec!(10))),
+                (dec!(60), Stock::new("AAPL", dec!(15))),
+            ],
+            &universe(),
+        );
+
+        assert!(target.is_ok());
+    }
+
+    #[test]
+    fn test_typo_is_caught_with_suggestion() {
+        let target = PortfolioTarget::try_from_vec_in(
+            vec![
+                (dec!(40), Stock::new("META", dec!(10))),
+                (dec!(60), Stock::new("APPL", dec!(15))),
+            ],
+            &universe(),
+        );
+
+        assert_eq!(
+            target.unwrap_err(),
+            TargetError::UnknownTicker {
+                ticker: "APPL".into(),
+                did_you_mean: Some("AAPL".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unrelated_ticker_has_no_suggestion() {
+        assert_eq!(universe().closest("BRK.B"), None);
+    }
+}
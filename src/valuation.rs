@@ -0,0 +1,219 @@
+//! Modo de valorizacion: precios intradia o cierres oficiales.
+//!
+//! Durante el dia los precios del portafolio son las ultimas cotizaciones; para un reporte
+//! oficial (cartola, comite, impuestos) se quieren los cierres del dia, que son los que despues
+//! se pueden reproducir. `Portfolio::report` calcula cualquier reporte con el modo elegido y lo
+//! devuelve envuelto en `Valued`, que deja anotado con que precios se hizo.
+
+use crate::Portfolio;
+use crate::date::Date;
+use crate::i18n::{Language, Localize, language};
+use crate::prices::PriceHistory;
+use std::fmt;
+
+/// Con que precios se valoriza.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValuationMode {
+    /// Los precios que tiene el portafolio en este momento.
+    Intraday,
+
+    /// El cierre de cada ticker en esa fecha segun una `PriceHistory`.
+    EndOfDay(Date),
+}
+
+impl ValuationMode {
+    /// Nombre estable, para registros de auditoria.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValuationMode::Intraday => "intraday",
+            ValuationMode::EndOfDay(_) => "end_of_day",
+        }
+    }
+}
+
+impl Localize for ValuationMode {
+    fn localize(&self, language: Language) -> String {
+        match (self, language) {
+            (ValuationMode::Intraday, Language::Es) => "Valorizado a precios intradia.".into(),
+            (ValuationMode::Intraday, Language::En) => "Valued at intraday prices.".into(),
+            (ValuationMode::EndOfDay(date), Language::Es) => {
+                format!("Valorizado a cierres oficiales del {date}.")
+            }
+            (ValuationMode::EndOfDay(date), Language::En) => {
+                format!("Valued at official closes of {date}.")
+            }
+        }
+    }
+}
+
+/// Un reporte junto con el modo de valorizacion con que se calculo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Valued<R> {
+    pub mode: ValuationMode,
+    pub report: R,
+}
+
+/// El reporte con una linea al final que dice como se valorizo.
+impl<R: Localize> Localize for Valued<R> {
+    fn localize(&self, language: Language) -> String {
+        format!(
+            "{}\n{}",
+            self.report.localize(language).trim_end(),
+            self.mode.localize(language)
+        )
+    }
+}
+
+impl<R: Localize> fmt::Display for Valued<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+/// Errores al valorizar a cierre.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValuationError {
+    /// Falta el cierre de un ticker en la fecha pedida. No se completa con el cierre anterior:
+    /// un reporte oficial no deberia depender de un precio de otro dia sin que nadie lo decida.
+    MissingClose { ticker: String, date: Date },
+}
+
+impl Localize for ValuationError {
+    fn localize(&self, language: Language) -> String {
+        match self {
+            ValuationError::MissingClose { ticker, date } => match language {
+                Language::Es => format!("No hay cierre de {ticker} el {date}."),
+                Language::En => format!("No close for {ticker} on {date}."),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ValuationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(language()))
+    }
+}
+
+impl std::error::Error for ValuationError {}
+
+impl Portfolio {
+    /// Copia del portafolio con los precios del modo: tal cual en `Intraday`, o con el cierre
+    /// de cada ticker (holdings y objetivo) en `EndOfDay`.
+    pub fn valued(
+        &self,
+        mode: ValuationMode,
+        history: &PriceHistory,
+    ) -> Result<Portfolio, ValuationError> {
+        let mut portfolio = self.clone();
+        let ValuationMode::EndOfDay(date) = mode else {
+            return Ok(portfolio);
+        };
+
+        let mut tickers: Vec<String> = self
+            .stocks
+            .iter()
+            .chain(self.allocation.targets().iter().map(|(_, stock)| stock))
+            .map(|stock| stock.name().to_string())
+            .collect();
+        tickers.sort_unstable();
+        tickers.dedup();
+
+        for ticker in tickers {
+            let bar = history
+                .bar(&ticker, date)
+                .ok_or_else(|| ValuationError::MissingClose {
+                    ticker: ticker.clone(),
+                    date,
+                })?;
+            portfolio.update_price(&ticker, bar.close);
+        }
+        Ok(portfolio)
+    }
+
+    /// Calcula un reporte sobre el portafolio valorizado segun `mode`, p. ej.
+    /// `portfolio.report(mode, &history, |p| p.verify_against_target(dec!(1)))`.
+    pub fn report<R>(
+        &self,
+        mode: ValuationMode,
+        history: &PriceHistory,
+        report: impl FnOnce(&Portfolio) -> R,
+    ) -> Result<Valued<R>, ValuationError> {
+        let portfolio = self.valued(mode, history)?;
+        Ok(Valued {
+            mode,
+            report: report(&portfolio),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prices::Bar;
+    use crate::{PortfolioTarget, Stock};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn portfolio() -> Portfolio {
+        Portfolio {
+            cash: Decimal::ZERO,
+            foreign_cash: Default::default(),
+            stocks: vec![Stock::new("META", dec!(12)); 10],
+            allocation: PortfolioTarget::new(Stock::new("META", dec!(12))),
+        }
+    }
+
+    fn day() -> Date {
+        Date::new(2024, 1, 2).unwrap()
+    }
+
+    #[test]
+    fn test_end_of_day_uses_closes_and_records_the_mode() {
+        let history = PriceHistory::new().with_bar(
+            "META",
+            day(),
+            Bar::new(dec!(9), dec!(13), dec!(9), dec!(10)),
+        );
+        let portfolio = portfolio();
+
+        let live = portfolio
+            .report(ValuationMode::Intraday, &history, |p| p.total_value())
+            .unwrap();
+        let close = portfolio
+            .report(ValuationMode::EndOfDay(day()), &history, |p| {
+                p.total_value()
+            })
+            .unwrap();
+
+        assert_eq!(live.report, dec!(120));
+        assert_eq!(close.report, dec!(100));
+        assert_eq!(close.mode.name(), "end_of_day");
+
+        let verification = portfolio
+            .report(ValuationMode::EndOfDay(day()), &history, |p| {
+                p.verify_against_target(dec!(1))
+            })
+            .unwrap();
+        assert!(
+            verification
+                .localize(Language::Es)
+                .ends_with("Valorizado a cierres oficiales del 2024-01-02.")
+        );
+    }
+
+    #[test]
+    fn test_missing_close_is_an_error() {
+        let error = portfolio()
+            .valued(ValuationMode::EndOfDay(day()), &PriceHistory::new())
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            ValuationError::MissingClose {
+                ticker: "META".into(),
+                date: day()
+            }
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Vistas filtradas de un portafolio.
+//!
+//! `Portfolio::view` se queda con las unidades que cumplen un predicado (una etiqueta, un sector
+//! del `Universe`, una cuenta guardada en la metadata) sin copiar el portafolio: la vista solo
+//! guarda referencias. Sirve para valorizar una parte y compararla con un objetivo propio, p. ej.
+//! solo la cuenta de jubilacion contra su objetivo 60/40.
+
+use crate::reports::VerificationReport;
+use crate::reports::verification::verify;
+use crate::{Portfolio, PortfolioTarget, Stock};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use rust_decimal::prelude::*;
+
+/// Unidades de un portafolio que cumplen un predicado; la caja no entra.
+#[derive(Debug, Clone)]
+pub struct PortfolioView<'a> {
+    portfolio: &'a Portfolio,
+    stocks: Vec<&'a Stock>,
+}
+
+impl Portfolio {
+    /// Vista de solo lectura con las unidades para las que `predicate` es verdadero.
+    pub fn view(&self, predicate: impl Fn(&Stock) -> bool) -> PortfolioView<'_> {
+        PortfolioView {
+            portfolio: self,
+            stocks: self.stocks().iter().filter(|s| predicate(s)).collect(),
+        }
+    }
+}
+
+impl<'a> PortfolioView<'a> {
+    pub fn stocks(&self) -> &[&'a Stock] {
+        &self.stocks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stocks.is_empty()
+    }
+
+    /// El portafolio completo del que sale la vista.
+    pub fn portfolio(&self) -> &'a Portfolio {
+        self.portfolio
+    }
+
+    /// Valor de las unidades de la vista, sin caja.
+    pub fn total_value(&self) -> Decimal {
+        self.stocks.iter().map(|s| s.current_price()).sum()
+    }
+
+    /// % del valor total del portafolio (caja incluida) que hay en la vista.
+    pub fn share_of_portfolio(&self) -> Decimal {
+        let total = self.portfolio.total_value();
+        if total.is_zero() {
+            return Decimal::ZERO;
+        }
+        self.total_value() / total * Decimal::ONE_HUNDRED
+    }
+
+    /// Unidades de cada ticker, ordenadas por ticker.
+    pub fn iter_holdings(&self) -> impl Iterator<Item = (&'a str, usize)> + use<'a> {
+        let mut units: BTreeMap<&'a str, usize> = BTreeMap::new();
+        for stock in &self.stocks {
+            *units.entry(stock.name()).or_default() += 1;
+        }
+        units.into_iter()
+    }
+
+    /// Peso (en %) de cada ticker dentro de la vista, ordenado de mayor a menor; suman 100.
+    /// Vacio si la vista no vale nada.
+    pub fn weights(&self) -> Vec<(&'a str, Decimal)> {
+        let total = self.total_value();
+        if total.is_zero() {
+            return Vec::new();
+        }
+
+        let mut values: BTreeMap<&'a str, Decimal> = BTreeMap::new();
+        for stock in &self.stocks {
+            *values.entry(stock.name()).or_default() += stock.current_price();
+        }
+        let mut weights: Vec<(&'a str, Decimal)> = values
+            .into_iter()
+            .map(|(ticker, value)| (ticker, value / total * Decimal::ONE_HUNDRED))
+            .collect();
+        weights.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        weights
+    }
+
+    /// Drift de la vista contra un objetivo propio (ver `Portfolio::verify_against_target`).
+    /// Como la vista no tiene caja, el objetivo deberia sumar 100% en stocks.
+    pub fn verify_against(
+        &self,
+        target: &PortfolioTarget,
+        tolerance: Decimal,
+    ) -> VerificationReport {
+        verify(&self.weights(), target, tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_view_by_account() {
+        let retirement = |s: Stock| s.with_field("account", "retirement");
+        let portfolio = Portfolio {
+            cash: dec!(100),
+            foreign_cash: Default::default(),
+            stocks: [
+                vec![retirement(Stock::new("SPY", dec!(10))); 7],
+                vec![retirement(Stock::new("BND", dec!(10))); 3],
+                vec![Stock::new("SPY", dec!(10)); 20],
+            ]
+            .concat(),
+            allocation: PortfolioTarget::new(Stock::new("SPY", dec!(10))),
+        };
+
+        let view = portfolio.view(|s| s.metadata().field("account") == Some("retirement"));
+        assert_eq!(view.total_value(), dec!(100));
+        assert_eq!(view.share_of_portfolio(), dec!(25));
+        assert_eq!(view.weights(), vec![("SPY", dec!(70)), ("BND", dec!(30))]);
+
+        let target = PortfolioTarget::try_from_vec(vec![
+            (dec!(60), Stock::new("SPY", dec!(10))),
+            (dec!(40), Stock::new("BND", dec!(10))),
+        ])
+        .unwrap();
+        let report = view.verify_against(&target, dec!(5));
+        assert!(!report.is_within_tolerance());
+        assert_eq!(report.residual().len(), 2);
+        assert_eq!(report.cash, (dec!(0), dec!(0)));
+    }
+}
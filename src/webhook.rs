@@ -0,0 +1,460 @@
+//! Notificaciones por webhook.
+//!
+//! Para que una alerta de drift o una sugerencia nueva llegue a Slack, Discord o a una
+//! automatizacion basta un POST a la URL que den. `Webhook` manda el cuerpo, reintenta con
+//! espera exponencial si el destino falla (errores de red, 429 y 5xx; un 4xx no se reintenta
+//! porque el request esta mal) y, si tiene secreto, firma el cuerpo con HMAC-SHA256 en la
+//! cabecera `X-Signature-256: sha256=<hex>` para que el receptor verifique que viene de aca.
+//!
+//! SHA-256 y HMAC estan escritos a mano, igual que el CRC de `export`, para no traer otra
+//! dependencia por dos funciones.
+
+use crate::dry_run::{RunMode, run_mode};
+use crate::i18n::{Language, Localize, language};
+use crate::publish::{EventPublisher, RebalanceEvent, json_string};
+use crate::reports::VerificationReport;
+use std::fmt::Write as _;
+use std::io;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// Cabecera con la firma del cuerpo.
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// Forma del cuerpo que se manda.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PayloadFormat {
+    /// El JSON del evento tal cual, para automatizaciones.
+    #[default]
+    Json,
+
+    /// Un texto legible dentro de `{"<campo>": "..."}`: `text` para Slack, `content` para
+    /// Discord.
+    Chat(String),
+}
+
+impl PayloadFormat {
+    pub fn slack() -> Self {
+        PayloadFormat::Chat("text".into())
+    }
+
+    pub fn discord() -> Self {
+        PayloadFormat::Chat("content".into())
+    }
+}
+
+/// Como se hace el POST. Separado de `Webhook` para poder probar los reintentos sin red.
+pub trait Transport {
+    /// Manda el POST y devuelve el codigo HTTP; `Err` si ni siquiera hubo respuesta.
+    fn post(&self, url: &str, headers: &[(&str, String)], body: &str) -> Result<u16, String>;
+}
+
+/// POST por HTTP(S) con `ureq`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpTransport;
+
+impl Transport for HttpTransport {
+    fn post(&self, url: &str, headers: &[(&str, String)], body: &str) -> Result<u16, String> {
+        let request = headers
+            .iter()
+            .fold(ureq::post(url), |request, (name, value)| {
+                request.set(name, value)
+            });
+        match request.send_string(body) {
+            Ok(response) => Ok(response.status()),
+            Err(ureq::Error::Status(status, _)) => Ok(status),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+}
+
+/// Un destino de webhook.
+#[derive(Debug, Clone)]
+pub struct Webhook<T: Transport = HttpTransport> {
+    url: String,
+    secret: Option<Vec<u8>>,
+    format: PayloadFormat,
+    max_attempts: u32,
+
+    /// Espera antes del primer reintento; se duplica en cada uno.
+    backoff: Duration,
+    transport: T,
+
+    /// Modo propio; sin el se usa el global (ver `dry_run`).
+    run_mode: Option<RunMode>,
+
+    /// Cuerpos que se habrian mandado en modo dry-run; los clones comparten la lista.
+    dry_run: Arc<Mutex<Vec<String>>>,
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Self {
+        Self::with_transport(url, HttpTransport)
+    }
+}
+
+impl<T: Transport> Webhook<T> {
+    pub fn with_transport(url: &str, transport: T) -> Self {
+        Self {
+            url: url.into(),
+            secret: None,
+            format: PayloadFormat::default(),
+            max_attempts: 3,
+            backoff: Duration::from_millis(500),
+            transport,
+            run_mode: None,
+            dry_run: Arc::default(),
+        }
+    }
+
+    /// Firma cada cuerpo con HMAC-SHA256 usando `secret`.
+    pub fn with_secret(mut self, secret: &[u8]) -> Self {
+        self.secret = Some(secret.to_vec());
+        self
+    }
+
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Intentos en total (al menos 1) y espera antes del primer reintento.
+    pub fn with_retries(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.backoff = backoff;
+        self
+    }
+
+    /// Usa `mode` en vez del modo global.
+    pub fn with_run_mode(mut self, mode: RunMode) -> Self {
+        self.run_mode = Some(mode);
+        self
+    }
+
+    /// Lo que se habria mandado en modo dry-run, en orden.
+    pub fn dry_run_bodies(&self) -> Vec<String> {
+        self.dry_run
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Manda `body` tal cual (se asume JSON). Devuelve el codigo de la respuesta exitosa.
+    ///
+    /// En modo dry-run no se conecta a nada: anota el cuerpo (ver `dry_run_bodies`) y devuelve
+    /// 0.
+    pub fn send(&self, body: &str) -> io::Result<u16> {
+        if self.run_mode.unwrap_or_else(run_mode).is_dry_run() {
+            self.dry_run
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(body.into());
+            return Ok(0);
+        }
+
+        let mut headers = vec![("Content-Type", "application/json".to_string())];
+        if let Some(secret) = &self.secret {
+            headers.push((SIGNATURE_HEADER, signature(secret, body.as_bytes())));
+        }
+
+        let mut wait = self.backoff;
+        let mut last_error = String::new();
+        for attempt in 1..=self.max_attempts {
+            match self.transport.post(&self.url, &headers, body) {
+                Ok(status) if (200..300).contains(&status) => return Ok(status),
+                Ok(status) if status != 429 && status < 500 => {
+                    return Err(io::Error::other(format!("el webhook respondio {status}")));
+                }
+                Ok(status) => last_error = format!("el webhook respondio {status}"),
+                Err(error) => last_error = error,
+            }
+            if attempt < self.max_attempts {
+                thread::sleep(wait);
+                wait *= 2;
+            }
+        }
+
+        Err(io::Error::other(format!(
+            "{last_error} ({} intentos)",
+            self.max_attempts
+        )))
+    }
+
+    /// Avisa que el portafolio se salio de la tolerancia. No manda nada (y devuelve `None`) si
+    /// el reporte esta dentro de la tolerancia.
+    pub fn send_drift_alert(&self, report: &VerificationReport) -> io::Result<Option<u16>> {
+        if report.is_within_tolerance() {
+            return Ok(None);
+        }
+
+        let body = match &self.format {
+            PayloadFormat::Json => {
+                let residual: Vec<String> = report
+                    .residual()
+                    .iter()
+                    .map(|d| {
+                        format!(
+                            r#"{{"ticker":{},"target":"{}","actual":"{}"}}"#,
+                            json_string(&d.ticker),
+                            d.target.normalize(),
+                            d.actual.round_dp(4).normalize()
+                        )
+                    })
+                    .collect();
+                format!(
+                    r#"{{"event":"drift_alert","tolerance":"{}","residual":[{}]}}"#,
+                    report.tolerance.normalize(),
+                    residual.join(",")
+                )
+            }
+            PayloadFormat::Chat(field) => chat(field, &report.localize(language())),
+        };
+        self.send(&body).map(Some)
+    }
+}
+
+/// Publica los eventos de rebalanceo en el webhook.
+impl<T: Transport> EventPublisher for Webhook<T> {
+    fn publish(&mut self, event: &RebalanceEvent) -> io::Result<()> {
+        self.send(&self.body(event)).map(|_| ())
+    }
+
+    fn dry_run(&mut self, event: &RebalanceEvent) -> io::Result<()> {
+        let body = self.body(event);
+        self.dry_run
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(body);
+        Ok(())
+    }
+}
+
+impl<T: Transport> Webhook<T> {
+    fn body(&self, event: &RebalanceEvent) -> String {
+        match &self.format {
+            PayloadFormat::Json => event.to_json(),
+            PayloadFormat::Chat(field) => {
+                let (RebalanceEvent::SuggestionGenerated { orders, .. }
+                | RebalanceEvent::SuggestionApplied { orders, .. }) = event;
+                let orders = match (language(), orders.len()) {
+                    (Language::Es, 1) => "1 orden".to_string(),
+                    (Language::Es, n) => format!("{n} ordenes"),
+                    (Language::En, 1) => "1 order".to_string(),
+                    (Language::En, n) => format!("{n} orders"),
+                };
+                chat(
+                    field,
+                    &format!("{} {}: {orders}", event.name(), event.key()),
+                )
+            }
+        }
+    }
+}
+
+fn chat(field: &str, text: &str) -> String {
+    format!("{{{}:{}}}", json_string(field), json_string(text))
+}
+
+/// Valor de la cabecera de firma: `sha256=` y el HMAC en hexadecimal.
+pub fn signature(secret: &[u8], body: &[u8]) -> String {
+    let mut out = String::from("sha256=");
+    for byte in hmac_sha256(secret, body) {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+/// HMAC (RFC 2104) con SHA-256.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        block[..32].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend_from_slice(&sha256(&inner));
+    sha256(&outer)
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 (FIPS 180-4).
+pub fn sha256(message: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    // relleno: un bit 1, ceros hasta dejar 8 bytes libres en el bloque y el largo en bits
+    let mut data = message.to_vec();
+    data.push(0x80);
+    while data.len() % 64 != 56 {
+        data.push(0);
+    }
+    data.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for chunk in data.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (value, add) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (bytes, value) in out.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Lo que recibio el transporte: la firma (si vino) y el cuerpo.
+    struct Request {
+        signature: Option<String>,
+        body: String,
+    }
+
+    /// Responde con los codigos de `responses` en orden y anota cada request.
+    struct Scripted {
+        responses: RefCell<Vec<Result<u16, String>>>,
+        requests: RefCell<Vec<Request>>,
+    }
+
+    impl Scripted {
+        fn new(responses: Vec<Result<u16, String>>) -> Self {
+            Self {
+                responses: RefCell::new(responses),
+                requests: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl Transport for &Scripted {
+        fn post(&self, _: &str, headers: &[(&str, String)], body: &str) -> Result<u16, String> {
+            let signature = headers
+                .iter()
+                .find(|(name, _)| *name == SIGNATURE_HEADER)
+                .map(|(_, value)| value.clone());
+            self.requests.borrow_mut().push(Request {
+                signature,
+                body: body.into(),
+            });
+            self.responses.borrow_mut().remove(0)
+        }
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_sha256_and_hmac_vectors() {
+        assert_eq!(
+            hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // RFC 4231, caso 2
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_retries_server_errors_and_signs() {
+        let transport = Scripted::new(vec![Err("timeout".into()), Ok(503), Ok(200)]);
+        let webhook = Webhook::with_transport("https://example.com/hook", &transport)
+            .with_secret(b"s3cret")
+            .with_retries(3, Duration::ZERO);
+
+        assert_eq!(webhook.send("{}").unwrap(), 200);
+        let requests = transport.requests.borrow();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].signature, Some(signature(b"s3cret", b"{}")));
+    }
+
+    #[test]
+    fn test_dry_run_does_not_post() {
+        let transport = Scripted::new(vec![]);
+        let mut webhook = Webhook::with_transport("https://example.com/hook", &transport)
+            .with_run_mode(RunMode::DryRun);
+
+        assert_eq!(webhook.send("{}").unwrap(), 0);
+        let event = RebalanceEvent::SuggestionGenerated {
+            at: crate::date::Timestamp::from_secs(0),
+            id: Default::default(),
+            orders: vec![],
+        };
+        crate::publish::publish_in(RunMode::DryRun, &mut webhook, &event).unwrap();
+
+        assert!(transport.requests.borrow().is_empty());
+        let bodies = webhook.dry_run_bodies();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[1], event.to_json());
+    }
+
+    #[test]
+    fn test_client_errors_are_not_retried() {
+        let transport = Scripted::new(vec![Ok(400), Ok(200)]);
+        let webhook = Webhook::with_transport("https://example.com/hook", &transport)
+            .with_format(PayloadFormat::slack())
+            .with_retries(3, Duration::ZERO);
+
+        assert!(webhook.send(&chat("text", "hola\n")).is_err());
+        assert_eq!(transport.requests.borrow().len(), 1);
+        assert_eq!(transport.requests.borrow()[0].body, r#"{"text":"hola\n"}"#);
+    }
+}